@@ -0,0 +1,13 @@
+#![no_main]
+
+use bus_mapping_fuzz::{run, Case, Error};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let case = Case::from_bytes(data);
+    match run(&case) {
+        // A transaction rejected by geth can't diverge.
+        Ok(()) | Err(Error::Trace(_)) => {}
+        Err(err) => panic!("{}\n{:?}", err, case),
+    }
+});
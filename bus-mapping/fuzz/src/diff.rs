@@ -0,0 +1,429 @@
+//! Comparison of the execution of a transaction by geth with the state
+//! transitions of bus-mapping.
+
+use crate::{Case, Error};
+use bus_mapping::{
+    circuit_input_builder::{CircuitInputBuilder, ExecStep},
+    mock::BlockData,
+    operation::{OperationContainer, Target, RW},
+};
+use eth_types::{
+    evm_types::{Memory, Stack},
+    geth_types::GethData,
+    GethExecStep, GethExecTrace, Word,
+};
+use mock::{eth, TestContext, MOCK_ACCOUNTS};
+use std::collections::{HashMap, HashSet};
+
+/// Gas price of the transactions of the cases.
+const GAS_PRICE: u64 = 2_000_000_000;
+/// Base fee of the block of the cases.
+const BASE_FEE: u64 = 1_000_000_000;
+
+/// Traces the transaction of `case` with geth and builds its circuit inputs
+/// with bus-mapping.
+fn build(case: &Case) -> Result<(GethData, CircuitInputBuilder), Error> {
+    let ctx = TestContext::<2, 1>::new(
+        |accs| {
+            accs[0]
+                .address(MOCK_ACCOUNTS[0])
+                .balance(eth(10))
+                .code(case.code.clone())
+                .storage(case.storage.clone());
+            accs[1].address(MOCK_ACCOUNTS[1]).balance(eth(10));
+        },
+        |txs, accs| {
+            txs[0]
+                .from(accs[1].address)
+                .to(accs[0].address)
+                .value(case.value)
+                .gas_price(Word::from(GAS_PRICE))
+                .input(case.calldata.clone());
+        },
+        |block| {
+            block.base_fee_per_gas(Word::from(BASE_FEE));
+        },
+    )
+    .map_err(Error::Trace)?;
+    let geth_data: GethData = ctx.into();
+
+    let block_data = BlockData::new_from_geth_data(geth_data.clone());
+    let mut builder = block_data.new_circuit_input_builder();
+    builder
+        .handle_block(&block_data.eth_block, &block_data.geth_traces)
+        .map_err(Error::BusMapping)?;
+    Ok((geth_data, builder))
+}
+
+/// Executes the transaction of `case` with geth and with bus-mapping, and
+/// compares the two executions with [`compare`].
+pub fn run(case: &Case) -> Result<(), Error> {
+    let (geth_data, builder) = build(case)?;
+    compare(&geth_data, &builder)
+}
+
+/// Compares the execution by geth of the single transaction of `geth_data`
+/// with its execution by bus-mapping in `builder`:
+/// - the stack, memory and storage operations of each step of the root call
+///   against the stack, memory and storage of geth before and after the step,
+/// - the intrinsic gas and the gas used after the refund,
+/// - the nonces, balances and storage of the StateDB after the transaction
+///   against the ones which follow from the trace of geth.
+pub fn compare(geth_data: &GethData, builder: &CircuitInputBuilder) -> Result<(), Error> {
+    let tx = &builder.block.txs()[0];
+    let trace = &geth_data.geth_traces[0];
+    compare_steps(&builder.block.container, tx.steps(), &trace.struct_logs)?;
+    compare_gas(builder, trace)?;
+    compare_state(geth_data, builder)
+}
+
+/// Returns the value at the address `address` of `stack`.
+fn stack_value(stack: &Stack, address: usize) -> Option<Word> {
+    // The stack grows from the address 1023 down.
+    1023usize
+        .checked_sub(address)
+        .and_then(|nth| stack.0.get(nth))
+        .copied()
+}
+
+/// Returns the byte at the address `address` of `memory`, which is zero past
+/// its end.
+fn memory_value(memory: &Memory, address: usize) -> u8 {
+    memory.0.get(address).copied().unwrap_or_default()
+}
+
+fn compare_steps(
+    container: &OperationContainer,
+    steps: &[ExecStep],
+    geth_steps: &[GethExecStep],
+) -> Result<(), Error> {
+    // The first step is the BeginTx step, the last one the EndTx step.
+    for (index, (step, geth_step)) in steps[1..].iter().zip(geth_steps).enumerate() {
+        // Only the successful steps of the root call are compared, as the
+        // steps of the calls are skipped by bus-mapping.
+        if geth_step.depth != 1 || geth_step.error.is_some() {
+            continue;
+        }
+        let divergence = |reason: String| Error::Step {
+            index,
+            pc: geth_step.pc.0,
+            op: geth_step.op,
+            reason,
+        };
+
+        let mut stack_writes = HashMap::new();
+        let mut memory_writes = HashMap::new();
+        let mut storage_writes = HashMap::new();
+        for op_ref in step.bus_mapping_instance.iter() {
+            match op_ref.target() {
+                Target::Stack => {
+                    let op = container
+                        .stack
+                        .get(op_ref.as_usize())
+                        .map_err(|err| divergence(err.to_string()))?;
+                    let address = op.op().address().0;
+                    let value = *op.op().value();
+                    if op.rw() == RW::WRITE {
+                        stack_writes.insert(address, value);
+                        continue;
+                    }
+                    let geth_value = stack_value(&geth_step.stack, address);
+                    if geth_value != Some(value) {
+                        return Err(divergence(format!(
+                            "stack read of {:?} at {}, geth has {:?}",
+                            value, address, geth_value
+                        )));
+                    }
+                }
+                Target::Memory => {
+                    let op = container
+                        .memory
+                        .get(op_ref.as_usize())
+                        .map_err(|err| divergence(err.to_string()))?;
+                    let address = op.op().address().0;
+                    let value = op.op().value();
+                    if op.rw() == RW::WRITE {
+                        memory_writes.insert(address, value);
+                        continue;
+                    }
+                    let geth_value = memory_value(&geth_step.memory, address);
+                    if geth_value != value {
+                        return Err(divergence(format!(
+                            "memory read of {:#04x} at {}, geth has {:#04x}",
+                            value, address, geth_value
+                        )));
+                    }
+                }
+                Target::Storage => {
+                    let op = container.storage[op_ref.as_usize()].op();
+                    if container.storage[op_ref.as_usize()].rw() == RW::WRITE {
+                        storage_writes.insert(op.key, op.value);
+                        continue;
+                    }
+                    // Geth captures the storage slots read or written by the
+                    // step.
+                    if let Some(geth_value) = geth_step.storage.get(&op.key) {
+                        if *geth_value != op.value {
+                            return Err(divergence(format!(
+                                "storage read of {:?} at {:?}, geth has {:?}",
+                                op.value, op.key, geth_value
+                            )));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Geth captures the value written by a step in its own storage.
+        for (key, value) in storage_writes.iter() {
+            let geth_value = geth_step.storage.get(key);
+            if geth_value != Some(value) {
+                return Err(divergence(format!(
+                    "storage write of {:?} at {:?}, geth has {:?}",
+                    value, key, geth_value
+                )));
+            }
+        }
+
+        // The writes are compared with the next step of the call, which has
+        // the stack and the memory after the step.
+        let next = match geth_steps.get(index + 1) {
+            Some(next) if next.depth == geth_step.depth => next,
+            _ => continue,
+        };
+        for (address, value) in stack_writes.iter() {
+            let geth_value = stack_value(&next.stack, *address);
+            if geth_value != Some(*value) {
+                return Err(divergence(format!(
+                    "stack write of {:?} at {}, geth has {:?}",
+                    value, address, geth_value
+                )));
+            }
+        }
+        for (nth, value) in next.stack.0.iter().enumerate() {
+            let address = 1023 - nth;
+            if stack_value(&geth_step.stack, address) != Some(*value)
+                && !stack_writes.contains_key(&address)
+            {
+                return Err(divergence(format!(
+                    "missing stack write of {:?} at {}",
+                    value, address
+                )));
+            }
+        }
+        for (address, value) in memory_writes.iter() {
+            let geth_value = memory_value(&next.memory, *address);
+            if geth_value != *value {
+                return Err(divergence(format!(
+                    "memory write of {:#04x} at {}, geth has {:#04x}",
+                    value, address, geth_value
+                )));
+            }
+        }
+        for (address, value) in next.memory.0.iter().enumerate() {
+            if memory_value(&geth_step.memory, address) != *value
+                && !memory_writes.contains_key(&address)
+            {
+                return Err(divergence(format!(
+                    "missing memory write of {:#04x} at {}",
+                    value, address
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn compare_gas(builder: &CircuitInputBuilder, trace: &GethExecTrace) -> Result<(), Error> {
+    let tx = &builder.block.txs()[0];
+    let steps = tx.steps();
+
+    // The BeginTx step charges the intrinsic gas.
+    let begin_tx = &steps[0];
+    if let Some(first) = trace.struct_logs.first() {
+        let gas_left = begin_tx.gas_left.0 - begin_tx.gas_cost.0;
+        if gas_left != first.gas.0 {
+            return Err(Error::Gas {
+                what: "intrinsic gas",
+                bus_mapping: begin_tx.gas_cost.0,
+                geth: tx.gas - first.gas.0,
+            });
+        }
+    }
+
+    // The EndTx step reads the refund, which is capped by a fraction of the
+    // gas used.
+    let end_tx = steps.last().expect("EndTx step");
+    let refund = end_tx
+        .bus_mapping_instance
+        .iter()
+        .find(|op_ref| op_ref.target() == Target::TxRefund)
+        .map(|op_ref| {
+            builder.block.container.tx_refund[op_ref.as_usize()]
+                .op()
+                .value
+        })
+        .unwrap_or_default();
+    let gas_used = tx.gas - end_tx.gas_left.0;
+    let max_refund_quotient = builder
+        .block
+        .chain_config
+        .hardfork
+        .max_refund_quotient_of_gas_used();
+    let gas_used = gas_used - refund.min(gas_used / max_refund_quotient);
+    if gas_used != trace.gas.0 {
+        return Err(Error::Gas {
+            what: "gas used",
+            bus_mapping: gas_used,
+            geth: trace.gas.0,
+        });
+    }
+    Ok(())
+}
+
+fn compare_state(geth_data: &GethData, builder: &CircuitInputBuilder) -> Result<(), Error> {
+    let tx = &builder.block.txs()[0];
+    let trace = &geth_data.geth_traces[0];
+    let pre = |address| {
+        geth_data
+            .accounts
+            .iter()
+            .find(|account| account.address == address)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let divergence = |address, reason: String| Error::State { address, reason };
+
+    // The fees follow from the gas used by geth, and the value is only
+    // transferred by a successful transaction.
+    let gas_used = Word::from(trace.gas.0);
+    let value = if trace.failed { Word::zero() } else { tx.value };
+    let mut balances = HashMap::new();
+    *balances
+        .entry(tx.from)
+        .or_insert_with(|| pre(tx.from).balance) -= value + tx.gas_price * gas_used;
+    *balances.entry(tx.to).or_insert_with(|| pre(tx.to).balance) += value;
+    let coinbase = builder.block.coinbase;
+    *balances
+        .entry(coinbase)
+        .or_insert_with(|| pre(coinbase).balance) +=
+        (tx.gas_price - builder.block.base_fee) * gas_used;
+
+    for (address, balance) in balances.iter() {
+        let (_, account) = builder.sdb.get_account(address);
+        if account.balance != *balance {
+            return Err(divergence(
+                *address,
+                format!("balance {:?}, geth has {:?}", account.balance, balance),
+            ));
+        }
+    }
+
+    let nonce = pre(tx.from).nonce + 1;
+    let (_, sender) = builder.sdb.get_account(&tx.from);
+    if sender.nonce != nonce {
+        return Err(divergence(
+            tx.from,
+            format!("nonce {:?}, geth has {:?}", sender.nonce, nonce),
+        ));
+    }
+
+    // The storage of the contract after the transaction is the last one
+    // captured by geth for each of the slots it accesses.
+    let callee = pre(tx.to);
+    let keys: HashSet<_> = callee
+        .storage
+        .keys()
+        .chain(
+            builder
+                .block
+                .container
+                .storage
+                .iter()
+                .map(|op| &op.op().key),
+        )
+        .collect();
+    for key in keys {
+        let captured = trace
+            .struct_logs
+            .iter()
+            .filter(|step| step.depth == 1)
+            .filter_map(|step| step.storage.get(key))
+            .last()
+            .filter(|_| !trace.failed);
+        let geth_value = captured
+            .or_else(|| callee.storage.get(key))
+            .copied()
+            .unwrap_or_default();
+        let (_, value) = builder.sdb.get_storage(&tx.to, key);
+        if *value != geth_value {
+            return Err(divergence(
+                tx.to,
+                format!(
+                    "storage {:?} at {:?}, geth has {:?}",
+                    value, key, geth_value
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build, compare, run};
+    use crate::{Case, Error};
+    use bus_mapping::operation::RW;
+    use eth_types::{bytecode, Word};
+    use rand::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn diff_random_cases() {
+        let mut rng = XorShiftRng::seed_from_u64(0xcafe);
+        for _ in 0..16 {
+            let mut data = vec![0u8; 512];
+            rng.fill_bytes(&mut data);
+            let case = Case::from_bytes(&data);
+            match run(&case) {
+                Ok(()) | Err(Error::Trace(_)) => {}
+                Err(err) => panic!("{}\n{:?}", err, case),
+            }
+        }
+    }
+
+    #[test]
+    fn diff_detects_divergence() {
+        let case = Case {
+            code: bytecode! {
+                PUSH1(0x01)
+                PUSH1(0x02)
+                ADD
+                PUSH1(0x00)
+                MSTORE
+                STOP
+            },
+            storage: Vec::new(),
+            calldata: Vec::new(),
+            value: Word::zero(),
+        };
+        let (geth_data, mut builder) = build(&case).unwrap();
+        compare(&geth_data, &builder).unwrap();
+
+        // Tamper with the result of the ADD written by bus-mapping.
+        let op = builder
+            .block
+            .container
+            .stack
+            .iter_mut()
+            .filter(|op| op.rw() == RW::WRITE)
+            .nth(2)
+            .unwrap();
+        op.op_mut().value = Word::from(4);
+        assert!(matches!(
+            compare(&geth_data, &builder),
+            Err(Error::Step { .. })
+        ));
+    }
+}
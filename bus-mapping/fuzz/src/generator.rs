@@ -0,0 +1,287 @@
+//! Generation of the transactions of the differential fuzzing from the bytes
+//! of the fuzzer.
+
+use eth_types::{evm_types::OpcodeId, Bytecode, Word};
+
+/// Maximum number of instructions of the generated bytecode.
+const MAX_INSTRUCTIONS: usize = 256;
+/// Stack depth above which the generated bytecode pops the results, to stay
+/// away from stack overflows.
+const MAX_STACK_DEPTH: usize = 512;
+/// Bound of the memory offsets of the generated bytecode, to keep the memory
+/// expansion cheap.
+const MEMORY_BOUND: u64 = 0x400;
+/// Number of storage slots of the contract, which the storage keys of the
+/// generated bytecode mostly hit.
+const STORAGE_SLOTS: u64 = 4;
+
+/// Kind of the first stack input of an opcode, which the generator bounds so
+/// that the transaction is valid-ish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    /// Any word
+    Word,
+    /// Memory offset
+    Memory,
+    /// Storage key
+    Storage,
+}
+
+/// Opcodes of the generated bytecode besides the `PUSH`, `DUP`, `SWAP` and
+/// jumps, with the number of their stack inputs and outputs, and the kind of
+/// their first input.  Only opcodes handled by bus-mapping are generated, the
+/// others would diverge by design.
+const OPCODES: &[(OpcodeId, usize, usize, Operand)] = &[
+    (OpcodeId::ADD, 2, 1, Operand::Word),
+    (OpcodeId::MUL, 2, 1, Operand::Word),
+    (OpcodeId::SUB, 2, 1, Operand::Word),
+    (OpcodeId::DIV, 2, 1, Operand::Word),
+    (OpcodeId::SDIV, 2, 1, Operand::Word),
+    (OpcodeId::MOD, 2, 1, Operand::Word),
+    (OpcodeId::SMOD, 2, 1, Operand::Word),
+    (OpcodeId::ADDMOD, 3, 1, Operand::Word),
+    (OpcodeId::MULMOD, 3, 1, Operand::Word),
+    (OpcodeId::EXP, 2, 1, Operand::Word),
+    (OpcodeId::SIGNEXTEND, 2, 1, Operand::Word),
+    (OpcodeId::LT, 2, 1, Operand::Word),
+    (OpcodeId::GT, 2, 1, Operand::Word),
+    (OpcodeId::SLT, 2, 1, Operand::Word),
+    (OpcodeId::SGT, 2, 1, Operand::Word),
+    (OpcodeId::EQ, 2, 1, Operand::Word),
+    (OpcodeId::ISZERO, 1, 1, Operand::Word),
+    (OpcodeId::AND, 2, 1, Operand::Word),
+    (OpcodeId::OR, 2, 1, Operand::Word),
+    (OpcodeId::XOR, 2, 1, Operand::Word),
+    (OpcodeId::NOT, 1, 1, Operand::Word),
+    (OpcodeId::BYTE, 2, 1, Operand::Word),
+    (OpcodeId::SHL, 2, 1, Operand::Word),
+    (OpcodeId::SHR, 2, 1, Operand::Word),
+    (OpcodeId::SAR, 2, 1, Operand::Word),
+    (OpcodeId::CALLER, 0, 1, Operand::Word),
+    (OpcodeId::CALLVALUE, 0, 1, Operand::Word),
+    (OpcodeId::CALLDATASIZE, 0, 1, Operand::Word),
+    (OpcodeId::CALLDATALOAD, 1, 1, Operand::Memory),
+    (OpcodeId::COINBASE, 0, 1, Operand::Word),
+    (OpcodeId::TIMESTAMP, 0, 1, Operand::Word),
+    (OpcodeId::NUMBER, 0, 1, Operand::Word),
+    (OpcodeId::CHAINID, 0, 1, Operand::Word),
+    (OpcodeId::SELFBALANCE, 0, 1, Operand::Word),
+    (OpcodeId::BASEFEE, 0, 1, Operand::Word),
+    (OpcodeId::POP, 1, 0, Operand::Word),
+    (OpcodeId::MLOAD, 1, 1, Operand::Memory),
+    (OpcodeId::MSTORE, 2, 0, Operand::Memory),
+    (OpcodeId::MSTORE8, 2, 0, Operand::Memory),
+    (OpcodeId::SLOAD, 1, 1, Operand::Storage),
+    (OpcodeId::PC, 0, 1, Operand::Word),
+    (OpcodeId::MSIZE, 0, 1, Operand::Word),
+    (OpcodeId::GAS, 0, 1, Operand::Word),
+];
+
+const DUPS: [OpcodeId; 16] = [
+    OpcodeId::DUP1,
+    OpcodeId::DUP2,
+    OpcodeId::DUP3,
+    OpcodeId::DUP4,
+    OpcodeId::DUP5,
+    OpcodeId::DUP6,
+    OpcodeId::DUP7,
+    OpcodeId::DUP8,
+    OpcodeId::DUP9,
+    OpcodeId::DUP10,
+    OpcodeId::DUP11,
+    OpcodeId::DUP12,
+    OpcodeId::DUP13,
+    OpcodeId::DUP14,
+    OpcodeId::DUP15,
+    OpcodeId::DUP16,
+];
+
+const SWAPS: [OpcodeId; 16] = [
+    OpcodeId::SWAP1,
+    OpcodeId::SWAP2,
+    OpcodeId::SWAP3,
+    OpcodeId::SWAP4,
+    OpcodeId::SWAP5,
+    OpcodeId::SWAP6,
+    OpcodeId::SWAP7,
+    OpcodeId::SWAP8,
+    OpcodeId::SWAP9,
+    OpcodeId::SWAP10,
+    OpcodeId::SWAP11,
+    OpcodeId::SWAP12,
+    OpcodeId::SWAP13,
+    OpcodeId::SWAP14,
+    OpcodeId::SWAP15,
+    OpcodeId::SWAP16,
+];
+
+/// Number of the kinds of instructions: the [`OPCODES`], a `PUSH`, a `DUP`, a
+/// `SWAP` and a conditional jump over the next instruction.
+const INSTRUCTION_KINDS: usize = OPCODES.len() + 4;
+
+/// Reader of the bytes of the fuzzer, which reads zeros once they are
+/// exhausted.
+struct Input<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Input<'a> {
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn byte(&mut self) -> u8 {
+        match self.data.split_first() {
+            Some((byte, rest)) => {
+                self.data = rest;
+                *byte
+            }
+            None => 0,
+        }
+    }
+
+    fn bytes(&mut self, n: usize) -> Vec<u8> {
+        (0..n).map(|_| self.byte()).collect()
+    }
+
+    fn word(&mut self, n: usize) -> Word {
+        Word::from_big_endian(&self.bytes(n))
+    }
+}
+
+/// Transaction to the contract of a [`Case`], which is executed by geth and
+/// by bus-mapping.
+#[derive(Debug, Clone)]
+pub struct Case {
+    /// Code of the contract called by the transaction
+    pub code: Bytecode,
+    /// Storage of the contract before the transaction
+    pub storage: Vec<(Word, Word)>,
+    /// Call data of the transaction
+    pub calldata: Vec<u8>,
+    /// Value of the transaction
+    pub value: Word,
+}
+
+impl Case {
+    /// Generates the case of the fuzzer input `data`.  Every input gives a
+    /// transaction: its bytes are read as the call data, the value and the
+    /// storage of the contract, and then as the instructions of the code, whose
+    /// stack inputs are pushed so that the stack doesn't underflow, and whose
+    /// memory offsets and storage keys are bounded.
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let mut input = Input { data };
+
+        let calldata_len = (input.byte() % 64) as usize;
+        let calldata = input.bytes(calldata_len);
+        let value = Word::from(input.byte());
+        let storage = (0..STORAGE_SLOTS)
+            .map(|key| (Word::from(key), input.word(2)))
+            .collect();
+
+        let mut code = Bytecode::default();
+        let mut depth = 0;
+        let mut jumps = 0;
+        // Label and stack depth of the conditional jump over the current
+        // instruction.
+        let mut jump = None;
+        for _ in 0..MAX_INSTRUCTIONS {
+            if input.is_empty() {
+                break;
+            }
+            let kind = input.byte() as usize % INSTRUCTION_KINDS;
+            match kind.checked_sub(OPCODES.len()) {
+                None => {
+                    let (op, inputs, outputs, operand) = OPCODES[kind];
+                    // Reuse the stack of the previous instructions for the
+                    // unbounded inputs when the input asks for it, unless the
+                    // instruction may be jumped over.
+                    let reuse = operand == Operand::Word
+                        && jump.is_none()
+                        && depth >= inputs
+                        && input.byte() & 1 == 1;
+                    if !reuse {
+                        for nth in (0..inputs).rev() {
+                            let kind = if nth == 0 { operand } else { Operand::Word };
+                            push_operand(&mut code, &mut input, kind);
+                            depth += 1;
+                        }
+                    }
+                    code.write_op(op);
+                    depth = depth - inputs + outputs;
+                }
+                Some(0) => {
+                    push_operand(&mut code, &mut input, Operand::Word);
+                    depth += 1;
+                }
+                Some(n @ (1 | 2)) => {
+                    // DUP<nth> needs nth stack items and SWAP<nth> nth + 1.
+                    let nth = (input.byte() % 16) as usize;
+                    let needed = if n == 1 { nth + 1 } else { nth + 2 };
+                    while depth < needed {
+                        push_operand(&mut code, &mut input, Operand::Word);
+                        depth += 1;
+                    }
+                    if n == 1 {
+                        code.write_op(DUPS[nth]);
+                        depth += 1;
+                    } else {
+                        code.write_op(SWAPS[nth]);
+                    }
+                }
+                _ => {
+                    if jump.is_none() {
+                        let label = format!("skip{}", jumps);
+                        jumps += 1;
+                        code.push(1, Word::from(input.byte() & 1));
+                        code.jumpi(&label);
+                        jump = Some((label, depth));
+                        continue;
+                    }
+                }
+            }
+            // Both paths reach the label of the jump with the same stack
+            // depth: the instruction jumped over, which doesn't consume the
+            // stack of the previous ones, has its results popped.
+            if let Some((label, depth_before)) = jump.take() {
+                while depth > depth_before {
+                    code.write_op(OpcodeId::POP);
+                    depth -= 1;
+                }
+                code.label(&label);
+            }
+            while depth > MAX_STACK_DEPTH {
+                code.write_op(OpcodeId::POP);
+                depth -= 1;
+            }
+        }
+        if let Some((label, _)) = jump.take() {
+            code.label(&label);
+        }
+        code.write_op(OpcodeId::STOP);
+
+        Self {
+            code,
+            storage,
+            calldata,
+            value,
+        }
+    }
+}
+
+/// Pushes a stack input of the kind `operand` read from `input`.
+fn push_operand(code: &mut Bytecode, input: &mut Input, operand: Operand) {
+    match operand {
+        Operand::Word => {
+            let n = (input.byte() % 32) as usize + 1;
+            code.push(n, input.word(n));
+        }
+        Operand::Memory => {
+            code.push(2, input.word(2) % MEMORY_BOUND);
+        }
+        Operand::Storage => {
+            // Mostly hit the storage of the contract, sometimes an empty slot.
+            code.push(1, Word::from(input.byte()) % (STORAGE_SLOTS + 2));
+        }
+    }
+}
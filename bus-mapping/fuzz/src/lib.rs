@@ -0,0 +1,86 @@
+//! Differential fuzzing of bus-mapping against geth.
+//!
+//! The fuzz target `bus_mapping_diff` turns the bytes of the fuzzer into a
+//! transaction to a contract with random, valid-ish bytecode (see [`Case`]),
+//! executes it with geth through geth-utils and with the state transitions of
+//! bus-mapping, and compares the stack, memory and storage of each step, the
+//! gas and the StateDB after the transaction (see [`compare`]).  Any mismatch
+//! is a divergence of an opcode handler of bus-mapping from geth.
+//!
+//! ```sh
+//! cargo +nightly fuzz run bus_mapping_diff
+//! ```
+
+#![deny(missing_docs)]
+
+mod diff;
+mod generator;
+
+pub use diff::{compare, run};
+pub use generator::Case;
+
+use eth_types::{evm_types::OpcodeId, Address};
+use std::fmt;
+
+/// Error of the differential execution of a [`Case`].
+#[derive(Debug)]
+pub enum Error {
+    /// Geth failed to trace the transaction, which isn't a divergence.
+    Trace(eth_types::Error),
+    /// Bus-mapping failed to handle the trace of geth.
+    BusMapping(bus_mapping::Error),
+    /// The operations of a step diverge from the execution of geth.
+    Step {
+        /// Index of the step in the trace of geth
+        index: usize,
+        /// Program counter of the step
+        pc: usize,
+        /// Opcode of the step
+        op: OpcodeId,
+        /// Description of the divergence
+        reason: String,
+    },
+    /// The gas charged by bus-mapping diverges from the gas used by geth.
+    Gas {
+        /// Gas which diverges
+        what: &'static str,
+        /// Gas according to bus-mapping
+        bus_mapping: u64,
+        /// Gas according to geth
+        geth: u64,
+    },
+    /// An account of the StateDB after the transaction diverges from geth.
+    State {
+        /// Address of the account
+        address: Address,
+        /// Description of the divergence
+        reason: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Trace(err) => write!(f, "tracing failed: {}", err),
+            Self::BusMapping(err) => write!(f, "bus-mapping failed: {}", err),
+            Self::Step {
+                index,
+                pc,
+                op,
+                reason,
+            } => write!(f, "step {} ({:?} at pc {}): {}", index, op, pc, reason),
+            Self::Gas {
+                what,
+                bus_mapping,
+                geth,
+            } => write!(
+                f,
+                "{} {} by bus-mapping, {} by geth",
+                what, bus_mapping, geth
+            ),
+            Self::State { address, reason } => write!(f, "account {:?}: {}", address, reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
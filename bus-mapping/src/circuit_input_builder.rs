@@ -5,21 +5,31 @@ use crate::exec_trace::OperationRef;
 use crate::geth_errors::*;
 use crate::operation::container::OperationContainer;
 use crate::operation::{
-    AccountField, CallContextField, MemoryOp, Op, OpEnum, Operation, RWCounter, StackOp, Target, RW,
+    AccountField, AccountOp, CallContextField, MemoryOp, Op, OpEnum, Operation, RWCounter, StackOp,
+    Target, RW,
 };
 use crate::state_db::{self, CodeDB, StateDB};
 use crate::Error;
 use core::fmt::Debug;
-use eth_types::evm_types::{Gas, GasCost, MemoryAddress, OpcodeId, ProgramCounter, StackAddress};
-use eth_types::{self, Address, GethExecStep, GethExecTrace, Hash, ToAddress, ToBigEndian, Word};
+use eth_types::evm_types::{
+    Gas, GasCost, Hardfork, MemoryAddress, OpcodeId, ProgramCounter, StackAddress,
+};
+use eth_types::{
+    self, geth_types::Withdrawal, AccessList, Address, ChainConfig, ForkBlocks, GethExecStep,
+    GethExecTrace, Hash, ToAddress, ToBigEndian, Word,
+};
 use ethers_core::utils::{get_contract_address, get_create2_address};
+use serde::{Deserialize, Serialize};
 use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
 
-use crate::rpc::GethClient;
+use crate::rpc::{GethClient, RpcClient};
 use ethers_providers::JsonRpcClient;
 
+mod observer;
+pub use observer::TraceObserver;
+
 /// Out of Gas errors by opcode
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum OogError {
     /// Out of Gas for opcodes which have non-zero constant gas cost
     Constant,
@@ -29,8 +39,8 @@ pub enum OogError {
     /// Out of Gas for CREATE, RETURN, REVERT, which have dynamic memory
     /// expansion gas cost
     DynamicMemoryExpansion,
-    /// Out of Gas for CALLDATACOPY, CODECOPY, RETURNDATACOPY, which copy a
-    /// specified chunk of memory
+    /// Out of Gas for CALLDATACOPY, CODECOPY, RETURNDATACOPY, MCOPY, which copy
+    /// a specified chunk of memory
     MemoryCopy,
     /// Out of Gas for BALANCE, EXTCODESIZE, EXTCODEHASH, which possibly touch
     /// an extra account
@@ -65,7 +75,7 @@ pub enum OogError {
 }
 
 /// EVM Execution Error
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum ExecError {
     /// Invalid Opcode
     InvalidOpcode,
@@ -102,7 +112,7 @@ pub enum ExecError {
 }
 
 /// An execution step of the EVM.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ExecStep {
     /// The opcode ID
     pub op: OpcodeId,
@@ -173,6 +183,46 @@ impl Default for ExecStep {
     }
 }
 
+/// Type of the source or the destination of a [`CopyEvent`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CopyDataType {
+    /// Memory of a call
+    Memory,
+    /// Bytecode of a contract
+    Bytecode,
+    /// Call data of a transaction
+    TxCalldata,
+    /// Data of a log of a transaction
+    TxLog,
+}
+
+/// A copy of bytes from a source to a destination done by an [`ExecStep`],
+/// whose read-write operations are the ones of the step from
+/// `rw_counter_start` on.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CopyEvent {
+    /// Type of the source
+    pub src_type: CopyDataType,
+    /// Call id for memory, code hash for bytecode or tx id for tx call data
+    pub src_id: Word,
+    /// Address of the first byte to read
+    pub src_addr: u64,
+    /// The bytes at or after this address are read as 0
+    pub src_addr_end: u64,
+    /// Type of the destination
+    pub dst_type: CopyDataType,
+    /// Call id for memory, code hash for bytecode or tx id for a tx log
+    pub dst_id: Word,
+    /// Address of the first byte to write
+    pub dst_addr: u64,
+    /// Id of the log in the tx, only used when the destination is a tx log
+    pub log_id: u64,
+    /// Counter of the first read-write operation of the copy
+    pub rw_counter_start: RWCounter,
+    /// Bytes copied, with whether each one is code when read from bytecode
+    pub bytes: Vec<(u8, bool)>,
+}
+
 /// Context of a [`Block`] which can mutate in a [`Transaction`].
 #[derive(Debug)]
 pub struct BlockContext {
@@ -201,11 +251,53 @@ impl BlockContext {
     }
 }
 
+/// Capacities of the circuits proving a block.  The circuits are padded up to
+/// them, so that their proving keys only depend on these parameters and not on
+/// the block, which is rejected if it doesn't fit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitsParams {
+    /// Maximum number of transactions of the tx circuit
+    pub max_txs: usize,
+    /// Maximum number of call data bytes of the transactions of the tx circuit
+    pub max_calldata: usize,
+    /// Maximum number of rw operations of the state circuit
+    pub max_rws: usize,
+    /// Maximum number of bytes of the bytecodes of the bytecode circuit
+    pub max_bytecode: usize,
+    /// Number of rows of the copy circuit
+    pub max_copy_rows: usize,
+    /// Maximum number of rows of the keccak table, one per hashed input and the
+    /// all-zero row
+    pub max_keccak_rows: usize,
+    /// Maximum number of exponentiations of the exp circuit
+    pub max_exp_steps: usize,
+    /// Maximum number of rows of the MPT table, one per MPT update
+    pub max_mpt_rows: usize,
+}
+
+impl Default for CircuitsParams {
+    /// Capacities which fit the small blocks of the tests.
+    fn default() -> Self {
+        Self {
+            max_txs: 1,
+            max_calldata: 256,
+            max_rws: 1000,
+            max_bytecode: 512,
+            max_copy_rows: 1000,
+            max_keccak_rows: 1000,
+            max_exp_steps: 100,
+            max_mpt_rows: 1000,
+        }
+    }
+}
+
 /// Circuit Input related to a block.
 #[derive(Debug)]
 pub struct Block {
-    /// chain id
-    pub chain_id: Word,
+    /// chain id, hardfork and EIP flags of the chain, whose gas rules are used
+    /// to process the block, the hardfork being the one activated at the
+    /// block
+    pub chain_config: ChainConfig,
     /// history hashes contains most recent 256 block hashes in history, where
     /// the lastest one is at history_hashes[history_hashes.len() - 1].
     pub history_hashes: Vec<Word>,
@@ -221,8 +313,17 @@ pub struct Block {
     pub difficulty: Word,
     /// base fee
     pub base_fee: Word,
+    /// withdrawals root of the block header, zero before Shanghai
+    pub withdrawals_root: Word,
+    /// validator withdrawals credited after the transactions, empty before
+    /// Shanghai
+    pub withdrawals: Vec<Withdrawal>,
     /// Container of operations done in this block.
     pub container: OperationContainer,
+    /// Capacities of the circuits the block is proven with
+    pub circuits_params: CircuitsParams,
+    /// Copies of bytes done by the steps of the block, in step order
+    pub copy_events: Vec<CopyEvent>,
     txs: Vec<Transaction>,
     code: HashMap<Hash, Vec<u8>>,
 }
@@ -230,9 +331,10 @@ pub struct Block {
 impl Block {
     /// Create a new block.
     pub fn new<TX>(
-        chain_id: Word,
+        chain_config: ChainConfig,
         history_hashes: Vec<Word>,
         eth_block: &eth_types::Block<TX>,
+        circuits_params: CircuitsParams,
     ) -> Result<Self, Error> {
         if eth_block.base_fee_per_gas.is_none() {
             // FIXME: resolve this once we have proper EIP-1559 support
@@ -241,20 +343,25 @@ impl Block {
             );
         }
 
+        let number = eth_block
+            .number
+            .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?
+            .low_u64();
+
         Ok(Self {
-            chain_id,
+            chain_config: chain_config.at_block(number),
             history_hashes,
             coinbase: eth_block.author,
             gas_limit: eth_block.gas_limit.low_u64(),
-            number: eth_block
-                .number
-                .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?
-                .low_u64()
-                .into(),
+            number: number.into(),
             timestamp: eth_block.timestamp,
             difficulty: eth_block.difficulty,
             base_fee: eth_block.base_fee_per_gas.unwrap_or_default(),
+            withdrawals_root: Word::zero(),
+            withdrawals: Vec::new(),
             container: OperationContainer::new(),
+            circuits_params,
+            copy_events: Vec::new(),
             txs: Vec::new(),
             code: HashMap::new(),
         })
@@ -272,7 +379,7 @@ impl Block {
 }
 
 /// Type of a *CALL*/CREATE* Function.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CallKind {
     /// CALL
     Call,
@@ -317,7 +424,7 @@ impl TryFrom<OpcodeId> for CallKind {
 }
 
 /// Circuit Input related to an Ethereum Call
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Call {
     /// Unique call identifier within the Block.
     pub call_id: usize,
@@ -430,7 +537,10 @@ impl TransactionContext {
                     // Emerge from call
                     } else if geth_step.depth - 1 == geth_next_step.depth {
                         let is_success = !geth_next_step.stack.last()?.is_zero();
-                        call_is_success_map.insert(call_indices.pop().unwrap(), is_success);
+                        let call_index = call_indices.pop().ok_or(Error::InvalidGethExecTrace(
+                            "return from a call which wasn't entered",
+                        ))?;
+                        call_is_success_map.insert(call_index, is_success);
                     }
                 }
             }
@@ -451,7 +561,7 @@ impl TransactionContext {
             calls: Vec::new(),
             reversion_groups: Vec::new(),
         };
-        tx_ctx.push_call_ctx(0);
+        tx_ctx.push_call_ctx(0)?;
 
         Ok(tx_ctx)
     }
@@ -488,19 +598,33 @@ impl TransactionContext {
         ))
     }
 
+    // Returns whether the call at `call_idx` in the transaction succeeds.
+    fn call_is_success(&self, call_idx: usize) -> Result<bool, Error> {
+        self.call_is_success
+            .get(call_idx)
+            .copied()
+            .ok_or(Error::InvalidGethExecTrace(
+                "more calls than the ones entered in the trace",
+            ))
+    }
+
     /// Push a new call context and its index into the call stack.
-    fn push_call_ctx(&mut self, call_idx: usize) {
-        if !self.call_is_success[call_idx] {
+    fn push_call_ctx(&mut self, call_idx: usize) -> Result<(), Error> {
+        if !self.call_is_success(call_idx)? {
             self.reversion_groups.push(ReversionGroup {
                 calls: vec![(call_idx, 0)],
                 op_refs: Vec::new(),
             })
         } else if let Some(reversion_group) = self.reversion_groups.last_mut() {
-            let caller_swc = self.calls.last().expect("calls should not be empty").swc;
+            let caller_swc = self
+                .calls
+                .last()
+                .ok_or(Error::BrokenInvariant("reverted call without caller"))?
+                .swc;
             let caller_swc_offset = reversion_group
                 .calls
                 .last()
-                .expect("calls should not be empty")
+                .ok_or(Error::BrokenInvariant("reversion group without calls"))?
                 .1;
             reversion_group
                 .calls
@@ -511,17 +635,21 @@ impl TransactionContext {
             index: call_idx,
             swc: 0,
         });
+        Ok(())
     }
 
     /// Pop the last entry in the call stack.
-    fn pop_call_ctx(&mut self) {
-        let call = self.calls.pop().expect("calls should not be empty");
+    fn pop_call_ctx(&mut self) -> Result<(), Error> {
+        let call = self.calls.pop().ok_or(Error::InvalidGethExecTrace(
+            "return from a call with an empty call stack",
+        ))?;
         // Accumulate state_write_counter if call is success
-        if self.call_is_success[call.index] {
+        if self.call_is_success(call.index)? {
             if let Some(caller) = self.calls.last_mut() {
                 caller.swc += call.swc;
             }
         }
+        Ok(())
     }
 }
 
@@ -542,6 +670,21 @@ pub struct Transaction {
     pub value: Word,
     /// Input / Call Data
     pub input: Vec<u8>, // call_data
+    /// Type of the envelope, which is 0 for a legacy transaction, 1 for
+    /// EIP-2930 and 2 for EIP-1559
+    pub tx_type: u64,
+    /// Access list of a typed transaction
+    pub access_list: AccessList,
+    /// Max priority fee per gas of an EIP-1559 transaction
+    pub max_priority_fee_per_gas: Word,
+    /// Max fee per gas of an EIP-1559 transaction
+    pub max_fee_per_gas: Word,
+    /// Signature recovery value
+    pub v: u64,
+    /// Signature r
+    pub r: Word,
+    /// Signature s
+    pub s: Word,
     calls: Vec<Call>,
     steps: Vec<ExecStep>,
 }
@@ -609,6 +752,13 @@ impl Transaction {
             to: eth_tx.to.unwrap_or_default(),
             value: eth_tx.value,
             input: eth_tx.input.to_vec(),
+            tx_type: eth_tx.transaction_type.unwrap_or_default().as_u64(),
+            access_list: eth_tx.access_list.clone().unwrap_or_default(),
+            max_priority_fee_per_gas: eth_tx.max_priority_fee_per_gas.unwrap_or_default(),
+            max_fee_per_gas: eth_tx.max_fee_per_gas.unwrap_or_default(),
+            v: eth_tx.v.as_u64(),
+            r: eth_tx.r,
+            s: eth_tx.s,
             calls: vec![call],
             steps: Vec::new(),
         })
@@ -656,6 +806,8 @@ pub struct CircuitInputStateRef<'a> {
     pub tx_ctx: &'a mut TransactionContext,
     /// Step
     pub step: &'a mut ExecStep,
+    /// Observer of the generated operations
+    pub observer: Option<&'a mut (dyn TraceObserver + 'static)>,
 }
 
 impl<'a> CircuitInputStateRef<'a> {
@@ -663,12 +815,24 @@ impl<'a> CircuitInputStateRef<'a> {
     /// [`RWCounter`] and then adds a reference to the stored operation
     /// ([`OperationRef`]) inside the bus-mapping instance of the current
     /// [`ExecStep`].  Then increase the block_ctx [`RWCounter`] by one.
-    pub fn push_op<T: Op>(&mut self, rw: RW, op: T) {
+    /// Returns an error if the operations of the container fail to be spilled
+    /// to disk.
+    pub fn push_op<T: Op>(&mut self, rw: RW, op: T) -> Result<(), Error> {
+        self.observe_op(rw, &op);
         let op_ref =
             self.block
                 .container
-                .insert(Operation::new(self.block_ctx.rwc.inc_pre(), rw, op));
+                .insert(Operation::new(self.block_ctx.rwc.inc_pre(), rw, op))?;
         self.step.bus_mapping_instance.push(op_ref);
+        Ok(())
+    }
+
+    // Notifies the observer, if any, of the operation about to be pushed with
+    // the next [`RWCounter`].
+    fn observe_op<T: Op>(&mut self, rw: RW, op: &T) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.on_operation(self.block_ctx.rwc, rw, &op.clone().into_enum());
+        }
     }
 
     /// Push an [`Operation`] with reversible to be true into the
@@ -680,11 +844,12 @@ impl<'a> CircuitInputStateRef<'a> {
     /// `push_op` when the operation is `RW::WRITE` and it can be reverted (for
     /// example, a write `StorageOp`).
     pub fn push_op_reversible<T: Op>(&mut self, rw: RW, op: T) -> Result<(), Error> {
+        self.observe_op(rw, &op);
         let op_ref = self.block.container.insert(Operation::new_reversible(
             self.block_ctx.rwc.inc_pre(),
             rw,
             op,
-        ));
+        ))?;
         self.step.bus_mapping_instance.push(op_ref);
 
         // Increase state_write_counter
@@ -695,7 +860,9 @@ impl<'a> CircuitInputStateRef<'a> {
             self.tx_ctx
                 .reversion_groups
                 .last_mut()
-                .expect("reversion_groups should not be empty for non-persistent call")
+                .ok_or(Error::BrokenInvariant(
+                    "no reversion group for a non-persistent call",
+                ))?
                 .op_refs
                 .push((self.tx.steps.len(), op_ref));
         }
@@ -715,8 +882,7 @@ impl<'a> CircuitInputStateRef<'a> {
         value: u8,
     ) -> Result<(), Error> {
         let call_id = self.call()?.call_id;
-        self.push_op(rw, MemoryOp::new(call_id, address, value));
-        Ok(())
+        self.push_op(rw, MemoryOp::new(call_id, address, value))
     }
 
     /// Push a [`StackOp`] into the [`OperationContainer`] with the next
@@ -731,8 +897,13 @@ impl<'a> CircuitInputStateRef<'a> {
         value: Word,
     ) -> Result<(), Error> {
         let call_id = self.call()?.call_id;
-        self.push_op(rw, StackOp::new(call_id, address, value));
-        Ok(())
+        self.push_op(rw, StackOp::new(call_id, address, value))
+    }
+
+    /// Push a [`CopyEvent`] of the current [`ExecStep`] into the [`Block`],
+    /// whose read-write operations must be pushed by the step.
+    pub fn push_copy(&mut self, event: CopyEvent) {
+        self.block.copy_events.push(event);
     }
 
     /// Reference to the current Call
@@ -761,16 +932,17 @@ impl<'a> CircuitInputStateRef<'a> {
 
     /// Push a new [`Call`] into the [`Transaction`], and add its index and
     /// [`CallContext`] in the `call_stack` of the [`TransactionContext`]
-    pub fn push_call(&mut self, call: Call) {
+    pub fn push_call(&mut self, call: Call) -> Result<(), Error> {
         let call_id = call.call_id;
 
         let call_idx = self.tx.calls.len();
-        self.tx_ctx.push_call_ctx(call_idx);
+        self.tx_ctx.push_call_ctx(call_idx)?;
         self.tx.push_call(call);
 
         self.block_ctx
             .call_map
             .insert(call_id, (self.block.txs.len(), call_idx));
+        Ok(())
     }
 
     /// Return the contract address of a CREATE step.  This is calculated by
@@ -803,11 +975,7 @@ impl<'a> CircuitInputStateRef<'a> {
 
     /// Parse [`Call`] from a *CALL*/CREATE* step.
     pub fn parse_call(&mut self, step: &GethExecStep) -> Result<Call, Error> {
-        let is_success = *self
-            .tx_ctx
-            .call_is_success
-            .get(self.tx.calls().len())
-            .unwrap();
+        let is_success = self.tx_ctx.call_is_success(self.tx.calls().len())?;
         let kind = CallKind::try_from(step.op)?;
         let caller = self.call()?;
 
@@ -950,8 +1118,8 @@ impl<'a> CircuitInputStateRef<'a> {
     }
 
     /// Apply reverted op to state and push to container.
-    fn apply_reverted_op(&mut self, op: OpEnum) -> OperationRef {
-        match op {
+    fn apply_reverted_op(&mut self, op: OpEnum) -> Result<OperationRef, Error> {
+        let op_ref = match op {
             OpEnum::Storage(op) => {
                 let (_, account) = self.sdb.get_storage_mut(&op.address, &op.key);
                 *account = op.value;
@@ -959,28 +1127,28 @@ impl<'a> CircuitInputStateRef<'a> {
                     self.block_ctx.rwc.inc_pre(),
                     RW::WRITE,
                     op,
-                ))
+                ))?
             }
             OpEnum::TxAccessListAccount(op) => {
                 if !op.value {
-                    self.sdb.remove_account_from_access_list(&op.address);
+                    self.sdb.remove_account_from_access_list(&op.address)?;
                 }
                 self.block.container.insert(Operation::new(
                     self.block_ctx.rwc.inc_pre(),
                     RW::WRITE,
                     op,
-                ))
+                ))?
             }
             OpEnum::TxAccessListAccountStorage(op) => {
                 if !op.value {
                     self.sdb
-                        .remove_account_storage_from_access_list(&(op.address, op.key));
+                        .remove_account_storage_from_access_list(&(op.address, op.key))?;
                 }
                 self.block.container.insert(Operation::new(
                     self.block_ctx.rwc.inc_pre(),
                     RW::WRITE,
                     op,
-                ))
+                ))?
             }
             OpEnum::Account(op) => {
                 let (_, account) = self.sdb.get_account_mut(&op.address);
@@ -995,26 +1163,39 @@ impl<'a> CircuitInputStateRef<'a> {
                     self.block_ctx.rwc.inc_pre(),
                     RW::WRITE,
                     op,
+                ))?
+            }
+            OpEnum::TxRefund(_) | OpEnum::AccountDestructed(_) => {
+                return Err(Error::BrokenInvariant(
+                    "reverting a tx refund or a destructed account isn't supported",
                 ))
             }
-            OpEnum::TxRefund(_) => unimplemented!(),
-            OpEnum::AccountDestructed(_) => unimplemented!(),
-            _ => unreachable!(),
-        }
+            _ => {
+                return Err(Error::BrokenInvariant(
+                    "reverting an irreversible operation",
+                ))
+            }
+        };
+        Ok(op_ref)
     }
 
     /// Handle a reversion group
-    fn handle_reversion(&mut self) {
+    fn handle_reversion(&mut self) -> Result<(), Error> {
         let reversion_group = self
             .tx_ctx
             .reversion_groups
             .pop()
-            .expect("reversion_groups should not be empty for non-persistent call");
+            .ok_or(Error::BrokenInvariant(
+                "no reversion group for a non-persistent call",
+            ))?;
 
         // Apply reversions
         for (step_index, op_ref) in reversion_group.op_refs.into_iter().rev() {
             if let Some(op) = self.get_rev_op_by_ref(&op_ref) {
-                let rev_op_ref = self.apply_reverted_op(op);
+                if let Some(observer) = self.observer.as_mut() {
+                    observer.on_operation(self.block_ctx.rwc, RW::WRITE, &op);
+                }
+                let rev_op_ref = self.apply_reverted_op(op)?;
                 self.tx.steps[step_index]
                     .bus_mapping_instance
                     .push(rev_op_ref);
@@ -1026,6 +1207,7 @@ impl<'a> CircuitInputStateRef<'a> {
         for (call_idx, swc_offset) in reversion_group.calls {
             self.tx.calls[call_idx].rw_counter_end_of_reversion = rwc - swc_offset;
         }
+        Ok(())
     }
 
     /// Handle a return step caused by any opcode that causes a return to the
@@ -1033,10 +1215,10 @@ impl<'a> CircuitInputStateRef<'a> {
     pub fn handle_return(&mut self) -> Result<(), Error> {
         // Handle reversion if this call doens't end successfully
         if !self.call()?.is_success {
-            self.handle_reversion();
+            self.handle_reversion()?;
         }
 
-        self.tx_ctx.pop_call_ctx();
+        self.tx_ctx.pop_call_ctx()?;
 
         Ok(())
     }
@@ -1046,12 +1228,16 @@ impl<'a> CircuitInputStateRef<'a> {
         step: &GethExecStep,
         next_step: Option<&GethExecStep>,
     ) -> Result<Option<ExecError>, Error> {
-        if let Some(error) = &step.error {
-            return Ok(Some(get_step_reported_error(&step.op, error)));
+        // The opcodes of a later hardfork are invalid, whatever error geth
+        // reports for them.
+        if matches!(step.op, OpcodeId::INVALID(_))
+            || !self.block.chain_config.hardfork.is_opcode_defined(step.op)
+        {
+            return Ok(Some(ExecError::InvalidOpcode));
         }
 
-        if matches!(step.op, OpcodeId::INVALID(_)) {
-            return Ok(Some(ExecError::InvalidOpcode));
+        if let Some(error) = &step.error {
+            return get_step_reported_error(&step.op, error).map(Some);
         }
 
         // When last step has opcodes that halt, there's no error.
@@ -1112,7 +1298,9 @@ impl<'a> CircuitInputStateRef<'a> {
                         && step.memory.0.get(offset.low_u64() as usize) == Some(&0xef)
                     {
                         return Ok(Some(ExecError::InvalidCreationCode));
-                    } else if Word::from(200u64) * length > Word::from(step.gas.0) {
+                    } else if Word::from(GasCost::CODE_DEPOSIT_BYTE_COST.as_u64()) * length
+                        > Word::from(step.gas.0)
+                    {
                         return Ok(Some(ExecError::CodeStoreOutOfGas));
                     } else {
                         return Err(Error::UnexpectedExecStepError(
@@ -1233,6 +1421,8 @@ pub struct CircuitInputBuilder {
     pub block: Block,
     /// Block Context
     pub block_ctx: BlockContext,
+    // Observer of the transactions, steps and operations handled
+    observer: Option<Box<dyn TraceObserver>>,
 }
 
 impl<'a> CircuitInputBuilder {
@@ -1244,9 +1434,21 @@ impl<'a> CircuitInputBuilder {
             code_db,
             block,
             block_ctx: BlockContext::new(),
+            observer: None,
         }
     }
 
+    /// Sets the [`TraceObserver`] notified of the transactions, the steps
+    /// and the operations handled by the builder from now on.
+    pub fn set_observer(&mut self, observer: Box<dyn TraceObserver>) {
+        self.observer = Some(observer);
+    }
+
+    /// Removes the [`TraceObserver`] of the builder, and returns it.
+    pub fn take_observer(&mut self) -> Option<Box<dyn TraceObserver>> {
+        self.observer.take()
+    }
+
     /// Obtain a mutable reference to the state that the `CircuitInputBuilder`
     /// maintains, contextualized to a particular transaction and a
     /// particular execution step in that transaction.
@@ -1264,6 +1466,7 @@ impl<'a> CircuitInputBuilder {
             tx,
             tx_ctx,
             step,
+            observer: self.observer.as_deref_mut(),
         }
     }
 
@@ -1294,7 +1497,7 @@ impl<'a> CircuitInputBuilder {
     /// generate the RwCounterEndOfReversion operation in
     /// `gen_associated_ops` we don't know yet which value it will take,
     /// so we put a placeholder; so we do it here after the values are known.
-    pub fn set_value_ops_call_context_rwc_eor(&mut self) {
+    pub fn set_value_ops_call_context_rwc_eor(&mut self) -> Result<(), Error> {
         for oper in self.block.container.call_context.iter_mut() {
             let op = oper.op_mut();
             if matches!(op.field, CallContextField::RwCounterEndOfReversion) {
@@ -1302,16 +1505,18 @@ impl<'a> CircuitInputBuilder {
                     .block_ctx
                     .call_map
                     .get(&op.call_id)
-                    .expect("call_id not found in call_map");
+                    .ok_or(Error::BrokenInvariant("call_id not found in call_map"))?;
                 op.value = self.block.txs[*tx_idx].calls[*call_idx]
                     .rw_counter_end_of_reversion
                     .into();
             }
         }
+        Ok(())
     }
 
     /// Handle a block by handling each transaction to generate all the
     /// associated operations.
+    #[tracing::instrument(skip_all, fields(n_txs = eth_block.transactions.len()))]
     pub fn handle_block(
         &mut self,
         eth_block: &EthBlock,
@@ -1319,9 +1524,57 @@ impl<'a> CircuitInputBuilder {
     ) -> Result<(), Error> {
         for (tx_index, tx) in eth_block.transactions.iter().enumerate() {
             let geth_trace = &geth_traces[tx_index];
-            self.handle_tx(tx, geth_trace, tx_index + 1 == eth_block.transactions.len())?;
+            if let Some(observer) = self.observer.as_mut() {
+                observer.on_tx_start(tx_index, tx)?;
+            }
+            self.handle_tx(
+                tx_index,
+                tx,
+                geth_trace,
+                tx_index + 1 == eth_block.transactions.len(),
+            )?;
+            if let Some(observer) = self.observer.as_mut() {
+                observer.on_tx_end(tx_index, self.block.txs.last().expect("tx just handled"))?;
+            }
+        }
+        self.handle_withdrawals()?;
+        self.set_value_ops_call_context_rwc_eor()?;
+        Ok(())
+    }
+
+    /// Credit the validator withdrawals of the block to their recipients,
+    /// after all the transactions.  Each withdrawal generates an
+    /// [`AccountOp`] writing the balance of its recipient, which belongs to
+    /// the block instead of to any transaction step.
+    fn handle_withdrawals(&mut self) -> Result<(), Error> {
+        if self.block.withdrawals.is_empty() {
+            return Ok(());
+        }
+        if !self.block.chain_config.is_active(Hardfork::Shanghai) {
+            return Err(Error::WithdrawalsBeforeShanghai);
+        }
+
+        for withdrawal in self.block.withdrawals.clone() {
+            let (_, account) = self.sdb.get_account_mut(&withdrawal.address);
+            let value_prev = account.balance;
+            let value = value_prev + withdrawal.amount_wei();
+            account.balance = value;
+
+            let op = AccountOp {
+                address: withdrawal.address,
+                field: AccountField::Balance,
+                value,
+                value_prev,
+            };
+            if let Some(observer) = self.observer.as_mut() {
+                observer.on_operation(self.block_ctx.rwc, RW::WRITE, &OpEnum::Account(op.clone()));
+            }
+            self.block.container.insert(Operation::new(
+                self.block_ctx.rwc.inc_pre(),
+                RW::WRITE,
+                op,
+            ))?;
         }
-        self.set_value_ops_call_context_rwc_eor();
         Ok(())
     }
 
@@ -1329,8 +1582,10 @@ impl<'a> CircuitInputBuilder {
     /// all the associated operations.  Each operation is registered in
     /// `self.block.container`, and each step stores the [`OperationRef`] to
     /// each of the generated operations.
+    #[tracing::instrument(skip(self, eth_tx, geth_trace), fields(tx_hash = ?eth_tx.hash))]
     fn handle_tx(
         &mut self,
+        tx_index: usize,
         eth_tx: &eth_types::Transaction,
         geth_trace: &GethExecTrace,
         is_last_tx: bool,
@@ -1348,6 +1603,7 @@ impl<'a> CircuitInputBuilder {
             ..Default::default()
         };
         gen_begin_tx_ops(&mut self.state_ref(&mut tx, &mut tx_ctx, &mut step))?;
+        self.observe_step(tx_index, None, &step)?;
         tx.steps.push(step);
 
         for (index, geth_step) in geth_trace.struct_logs.iter().enumerate() {
@@ -1360,8 +1616,10 @@ impl<'a> CircuitInputBuilder {
                 &geth_step.op,
                 &mut state_ref,
                 &geth_trace.struct_logs[index..],
-            )?;
+            )
+            .map_err(|err| err.in_step(tx_index, geth_step))?;
 
+            self.observe_step(tx_index, Some(geth_step), &step)?;
             tx.steps.push(step);
         }
 
@@ -1372,7 +1630,7 @@ impl<'a> CircuitInputBuilder {
         let step_prev = tx
             .steps
             .last()
-            .expect("steps should have at least one BeginTx step");
+            .ok_or(Error::BrokenInvariant("tx without BeginTx step"))?;
         let mut step = ExecStep {
             gas_left: Gas(step_prev.gas_left.0 - step_prev.gas_cost.0),
             rwc: self.block_ctx.rwc,
@@ -1385,6 +1643,7 @@ impl<'a> CircuitInputBuilder {
             ..Default::default()
         };
         gen_end_tx_ops(&mut self.state_ref(&mut tx, &mut tx_ctx, &mut step))?;
+        self.observe_step(tx_index, None, &step)?;
         tx.steps.push(step);
 
         self.block.txs.push(tx);
@@ -1392,9 +1651,32 @@ impl<'a> CircuitInputBuilder {
 
         Ok(())
     }
+
+    // Notifies the observer, if any, of the step of the transaction at
+    // `tx_index` generated from `geth_step`.
+    fn observe_step(
+        &mut self,
+        tx_index: usize,
+        geth_step: Option<&GethExecStep>,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        match self.observer.as_mut() {
+            Some(observer) => observer.on_step(tx_index, geth_step, step),
+            None => Ok(()),
+        }
+    }
+}
+
+fn get_step_reported_error(op: &OpcodeId, error: &str) -> Result<ExecError, Error> {
+    parse_step_reported_error(op, error).ok_or_else(|| Error::UnknownGethExecStepError {
+        op: *op,
+        error: error.to_string(),
+    })
 }
 
-fn get_step_reported_error(op: &OpcodeId, error: &str) -> ExecError {
+/// Returns the [`ExecError`] of the error `error` reported by geth for a step
+/// of `op`, or `None` if the error isn't handled.
+pub fn parse_step_reported_error(op: &OpcodeId, error: &str) -> Option<ExecError> {
     if error == GETH_ERR_OUT_OF_GAS || error == GETH_ERR_GAS_UINT_OVERFLOW {
         // NOTE: We report a GasUintOverflow error as an OutOfGas error
         let oog_err = match op {
@@ -1404,9 +1686,10 @@ fn get_step_reported_error(op: &OpcodeId, error: &str) -> ExecError {
             OpcodeId::CREATE | OpcodeId::RETURN | OpcodeId::REVERT => {
                 OogError::DynamicMemoryExpansion
             }
-            OpcodeId::CALLDATACOPY | OpcodeId::CODECOPY | OpcodeId::RETURNDATACOPY => {
-                OogError::MemoryCopy
-            }
+            OpcodeId::CALLDATACOPY
+            | OpcodeId::CODECOPY
+            | OpcodeId::RETURNDATACOPY
+            | OpcodeId::MCOPY => OogError::MemoryCopy,
             OpcodeId::BALANCE | OpcodeId::EXTCODESIZE | OpcodeId::EXTCODEHASH => {
                 OogError::AccountAccess
             }
@@ -1426,13 +1709,13 @@ fn get_step_reported_error(op: &OpcodeId, error: &str) -> ExecError {
             OpcodeId::SELFDESTRUCT => OogError::SelfDestruct,
             _ => OogError::Constant,
         };
-        ExecError::OutOfGas(oog_err)
+        Some(ExecError::OutOfGas(oog_err))
     } else if error.starts_with(GETH_ERR_STACK_OVERFLOW) {
-        ExecError::StackOverflow
+        Some(ExecError::StackOverflow)
     } else if error.starts_with(GETH_ERR_STACK_UNDERFLOW) {
-        ExecError::StackUnderflow
+        Some(ExecError::StackUnderflow)
     } else {
-        panic!("Unknown GethExecStep.error: {}", error);
+        None
     }
 }
 /// Retrieve the init_code from memory for {CREATE, CREATE2}
@@ -1542,7 +1825,7 @@ impl From<Vec<Access>> for AccessSet {
 }
 
 /// Source of the code in the EVM execution.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum CodeSource {
     /// Code comes from a deployed contract at `Address`.
     Address(Address),
@@ -1708,24 +1991,33 @@ type EthBlock = eth_types::Block<eth_types::Transaction>;
 /// the necessary information and using the CircuitInputBuilder.
 pub struct BuilderClient<P: JsonRpcClient> {
     cli: GethClient<P>,
-    chain_id: Word,
+    chain_config: ChainConfig,
     history_hashes: Vec<Word>,
+    circuits_params: CircuitsParams,
 }
 
 impl<P: JsonRpcClient> BuilderClient<P> {
-    /// Create a new BuilderClient
-    pub async fn new(client: GethClient<P>) -> Result<Self, Error> {
-        let chain_id = client.get_chain_id().await?;
+    /// Create a new BuilderClient, whose blocks are proven with circuits of
+    /// capacities `circuits_params`.  Fails with [`Error::UnknownChain`] if the
+    /// fork blocks of the chain of `client` aren't known.
+    pub async fn new(
+        client: GethClient<P>,
+        circuits_params: CircuitsParams,
+    ) -> Result<Self, Error> {
+        let chain_id: Word = client.get_chain_id().await?.into();
+        let fork_blocks = ForkBlocks::of_chain(chain_id).ok_or(Error::UnknownChain(chain_id))?;
 
         Ok(Self {
             cli: client,
-            chain_id: chain_id.into(),
+            chain_config: ChainConfig::new(chain_id).with_fork_blocks(fork_blocks),
             // TODO: Get history hashes
             history_hashes: Vec::new(),
+            circuits_params,
         })
     }
 
     /// Step 1. Query geth for Block, Txs and TxExecTraces
+    #[tracing::instrument(skip(self))]
     pub async fn get_block(
         &self,
         block_num: u64,
@@ -1759,6 +2051,7 @@ impl<P: JsonRpcClient> BuilderClient<P> {
 
     /// Step 3. Query geth for all accounts, storage keys, and codes from
     /// Accesses
+    #[tracing::instrument(skip(self, access_set))]
     pub async fn get_state(
         &self,
         block_num: u64,
@@ -1777,17 +2070,12 @@ impl<P: JsonRpcClient> BuilderClient<P> {
             let proof = self
                 .cli
                 .get_proof(address, keys, (block_num - 1).into())
-                .await
-                .unwrap();
+                .await?;
             proofs.push(proof);
         }
         let mut codes: HashMap<Address, Vec<u8>> = HashMap::new();
         for address in access_set.code {
-            let code = self
-                .cli
-                .get_code(address, (block_num - 1).into())
-                .await
-                .unwrap();
+            let code = self.cli.get_code(address, (block_num - 1).into()).await?;
             codes.insert(address, code);
         }
         Ok((proofs, codes))
@@ -1801,7 +2089,7 @@ impl<P: JsonRpcClient> BuilderClient<P> {
     ) -> (StateDB, CodeDB) {
         let mut sdb = StateDB::new();
         for proof in proofs {
-            let mut storage = HashMap::new();
+            let mut storage = BTreeMap::new();
             for storage_proof in proof.storage_proof {
                 storage.insert(storage_proof.key, storage_proof.value);
             }
@@ -1832,7 +2120,12 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         eth_block: &EthBlock,
         geth_traces: &[eth_types::GethExecTrace],
     ) -> Result<CircuitInputBuilder, Error> {
-        let block = Block::new(self.chain_id, self.history_hashes.clone(), eth_block)?;
+        let block = Block::new(
+            self.chain_config,
+            self.history_hashes.clone(),
+            eth_block,
+            self.circuits_params,
+        )?;
         let mut builder = CircuitInputBuilder::new(sdb, code_db, block);
         builder.handle_block(eth_block, geth_traces)?;
         Ok(builder)
@@ -1847,6 +2140,104 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         let builder = self.gen_inputs_from_state(state_db, code_db, &eth_block, &geth_traces)?;
         Ok(builder)
     }
+
+    /// Perform all the steps to generate the circuit inputs, with the state
+    /// of the block loaded from `backend` instead of the proofs of step 3.
+    pub async fn gen_inputs_with_backend<B: state_db::StateBackend + ?Sized>(
+        &self,
+        block_num: u64,
+        backend: &B,
+    ) -> Result<CircuitInputBuilder, Error> {
+        let (eth_block, geth_traces) = self.get_block(block_num).await?;
+        let access_set = self.get_state_accesses(&eth_block, &geth_traces)?;
+        let (state_db, code_db) = state_db::load_state(backend, block_num, &access_set).await?;
+        let builder = self.gen_inputs_from_state(state_db, code_db, &eth_block, &geth_traces)?;
+        Ok(builder)
+    }
+
+    /// Perform all the steps to generate the circuit inputs, with the state
+    /// of the block loaded from `disk_db` if it holds all of it, and stored
+    /// in it after step 4 otherwise.
+    #[cfg(feature = "disk-db")]
+    pub async fn gen_inputs_with_disk_db(
+        &self,
+        block_num: u64,
+        disk_db: &state_db::DiskDB,
+    ) -> Result<CircuitInputBuilder, Error> {
+        let (eth_block, geth_traces) = self.get_block(block_num).await?;
+        let access_set = self.get_state_accesses(&eth_block, &geth_traces)?;
+        let (state_db, code_db) = match disk_db.load(block_num, &access_set)? {
+            Some(dbs) => dbs,
+            None => {
+                let (proofs, codes) = self.get_state(block_num, access_set).await?;
+                let (state_db, code_db) = self.build_state_code_db(proofs, codes);
+                disk_db.insert(block_num, &state_db, &code_db)?;
+                (state_db, code_db)
+            }
+        };
+        let builder = self.gen_inputs_from_state(state_db, code_db, &eth_block, &geth_traces)?;
+        Ok(builder)
+    }
+}
+
+impl BuilderClient<RpcClient> {
+    /// Step 1, with the transactions traced in batches of
+    /// `debug_traceTransaction` instead of a single `debug_traceBlockByNumber`.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_block_batched(
+        &self,
+        block_num: u64,
+    ) -> Result<(EthBlock, Vec<eth_types::GethExecTrace>), Error> {
+        let eth_block = self.cli.get_block_by_number(block_num.into()).await?;
+        let hashes: Vec<_> = eth_block.transactions.iter().map(|tx| tx.hash).collect();
+        let geth_traces = self.cli.trace_transactions(&hashes).await?;
+        Ok((eth_block, geth_traces))
+    }
+
+    /// Step 3, with the proofs and the codes queried in batches.
+    #[tracing::instrument(skip(self, access_set))]
+    pub async fn get_state_batched(
+        &self,
+        block_num: u64,
+        access_set: AccessSet,
+    ) -> Result<
+        (
+            Vec<eth_types::EIP1186ProofResponse>,
+            HashMap<Address, Vec<u8>>,
+        ),
+        Error,
+    > {
+        let accounts = access_set
+            .state
+            .into_iter()
+            .map(|(address, key_set)| {
+                let mut keys: Vec<Word> = key_set.into_iter().collect();
+                keys.sort();
+                (address, keys)
+            })
+            .collect();
+        let proofs = self
+            .cli
+            .get_proofs(accounts, (block_num - 1).into())
+            .await?;
+        let addresses: Vec<Address> = access_set.code.into_iter().collect();
+        let codes = self
+            .cli
+            .get_codes(&addresses, (block_num - 1).into())
+            .await?;
+        Ok((proofs, addresses.into_iter().zip(codes).collect()))
+    }
+
+    /// Perform all the steps to generate the circuit inputs, batching the
+    /// JSON-RPC requests of steps 1 and 3.
+    pub async fn gen_inputs_batched(&self, block_num: u64) -> Result<CircuitInputBuilder, Error> {
+        let (eth_block, geth_traces) = self.get_block_batched(block_num).await?;
+        let access_set = self.get_state_accesses(&eth_block, &geth_traces)?;
+        let (proofs, codes) = self.get_state_batched(block_num, access_set).await?;
+        let (state_db, code_db) = self.build_state_code_db(proofs, codes);
+        let builder = self.gen_inputs_from_state(state_db, code_db, &eth_block, &geth_traces)?;
+        Ok(builder)
+    }
 }
 
 #[cfg(test)]
@@ -2044,7 +2435,7 @@ mod tracer_tests {
                 nonce: Word::zero(),
                 balance: Word::from(555u64), /* same value as in
                                               * `mock::new_tracer_account` */
-                storage: HashMap::new(),
+                storage: BTreeMap::new(),
                 code_hash: Hash::zero(),
             },
         );
@@ -2140,7 +2531,10 @@ mod tracer_tests {
         let mut builder = CircuitInputBuilderTx::new(&block, step);
         // Set up call context at CREATE2
         builder.tx_ctx.call_is_success.push(false);
-        builder.state_ref().push_call(mock_internal_create());
+        builder
+            .state_ref()
+            .push_call(mock_internal_create())
+            .unwrap();
         // Set up account and contract that exist during the second CREATE2
         builder.builder.sdb.set_account(
             &ADDR_B,
@@ -2148,7 +2542,7 @@ mod tracer_tests {
                 nonce: Word::zero(),
                 balance: Word::from(555u64), /* same value as in
                                               * `mock::new_tracer_account` */
-                storage: HashMap::new(),
+                storage: BTreeMap::new(),
                 code_hash: Hash::zero(),
             },
         );
@@ -2157,7 +2551,7 @@ mod tracer_tests {
             Account {
                 nonce: Word::zero(),
                 balance: Word::zero(),
-                storage: HashMap::new(),
+                storage: BTreeMap::new(),
                 code_hash: Hash::zero(),
             },
         );
@@ -2175,7 +2569,8 @@ mod tracer_tests {
         step.op == OpcodeId::RETURN
             && step.error.is_none()
             && result(next_step).is_zero()
-            && Word::from(200) * length > Word::from(step.gas.0)
+            && Word::from(GasCost::CODE_DEPOSIT_BYTE_COST.as_u64()) * length
+                > Word::from(step.gas.0)
     }
 
     #[test]
@@ -2245,7 +2640,10 @@ mod tracer_tests {
         let mut builder = CircuitInputBuilderTx::new(&block, step);
         // Set up call context at CREATE
         builder.tx_ctx.call_is_success.push(false);
-        builder.state_ref().push_call(mock_internal_create());
+        builder
+            .state_ref()
+            .push_call(mock_internal_create())
+            .unwrap();
         assert_eq!(
             builder.state_ref().get_step_err(step, next_step).unwrap(),
             Some(ExecError::CodeStoreOutOfGas)
@@ -2329,7 +2727,10 @@ mod tracer_tests {
         let mut builder = CircuitInputBuilderTx::new(&block, step);
         // Set up call context at RETURN
         builder.tx_ctx.call_is_success.push(false);
-        builder.state_ref().push_call(mock_internal_create());
+        builder
+            .state_ref()
+            .push_call(mock_internal_create())
+            .unwrap();
         assert_eq!(
             builder.state_ref().get_step_err(step, next_step).unwrap(),
             Some(ExecError::InvalidCreationCode)
@@ -2414,7 +2815,10 @@ mod tracer_tests {
         let mut builder = CircuitInputBuilderTx::new(&block, step);
         // Set up call context at RETURN
         builder.tx_ctx.call_is_success.push(false);
-        builder.state_ref().push_call(mock_internal_create());
+        builder
+            .state_ref()
+            .push_call(mock_internal_create())
+            .unwrap();
         assert_eq!(
             builder.state_ref().get_step_err(step, next_step).unwrap(),
             Some(ExecError::MaxCodeSizeExceeded)
@@ -2484,7 +2888,10 @@ mod tracer_tests {
         let mut builder = CircuitInputBuilderTx::new(&block, step);
         // Set up call context at STOP
         builder.tx_ctx.call_is_success.push(false);
-        builder.state_ref().push_call(mock_internal_create());
+        builder
+            .state_ref()
+            .push_call(mock_internal_create())
+            .unwrap();
         assert_eq!(
             builder.state_ref().get_step_err(step, next_step).unwrap(),
             None
@@ -2765,6 +3172,32 @@ mod tracer_tests {
         );
     }
 
+    #[test]
+    fn tracer_err_invalid_opcode_before_hardfork() {
+        // BASEFEE is only defined from London on
+        let code = bytecode! {
+            BASEFEE
+            STOP
+        };
+        let block = mock::new_single_tx_trace_code(&code).unwrap();
+
+        let index = block.geth_traces[0].struct_logs.len() - 2; // BASEFEE
+        let step = &block.geth_traces[0].struct_logs[index];
+        let next_step = block.geth_traces[0].struct_logs.get(index + 1);
+        assert_eq!(step.op, OpcodeId::BASEFEE);
+
+        let mut builder = CircuitInputBuilderTx::new(&block, step);
+        assert_eq!(
+            builder.state_ref().get_step_err(step, next_step).unwrap(),
+            None
+        );
+        builder.builder.block.chain_config.hardfork = Hardfork::Berlin;
+        assert_eq!(
+            builder.state_ref().get_step_err(step, next_step).unwrap(),
+            Some(ExecError::InvalidOpcode)
+        );
+    }
+
     #[test]
     fn tracer_err_write_protection() {
         // code_a calls code_b via static call, which tries to SSTORE and fails.
@@ -2795,26 +3228,29 @@ mod tracer_tests {
 
         let mut builder = CircuitInputBuilderTx::new(&block, step);
         builder.tx_ctx.call_is_success.push(false);
-        builder.state_ref().push_call(Call {
-            call_id: 0,
-            caller_id: 0,
-            kind: CallKind::StaticCall,
-            is_static: true,
-            is_root: false,
-            is_persistent: false,
-            is_success: false,
-            rw_counter_end_of_reversion: 0,
-            caller_address: *ADDR_A,
-            address: *ADDR_B,
-            code_source: CodeSource::Address(*ADDR_B),
-            code_hash: Hash::zero(),
-            depth: 2,
-            value: 0.into(),
-            call_data_offset: 0,
-            call_data_length: 0,
-            return_data_offset: 0,
-            return_data_length: 0,
-        });
+        builder
+            .state_ref()
+            .push_call(Call {
+                call_id: 0,
+                caller_id: 0,
+                kind: CallKind::StaticCall,
+                is_static: true,
+                is_root: false,
+                is_persistent: false,
+                is_success: false,
+                rw_counter_end_of_reversion: 0,
+                caller_address: *ADDR_A,
+                address: *ADDR_B,
+                code_source: CodeSource::Address(*ADDR_B),
+                code_hash: Hash::zero(),
+                depth: 2,
+                value: 0.into(),
+                call_data_offset: 0,
+                call_data_length: 0,
+                return_data_offset: 0,
+                return_data_length: 0,
+            })
+            .unwrap();
 
         assert_eq!(
             builder.state_ref().get_step_err(step, next_step).unwrap(),
@@ -2958,7 +3394,10 @@ mod tracer_tests {
         let mut builder = CircuitInputBuilderTx::new(&block, step_create2);
         // Set up call context at CREATE2
         builder.tx_ctx.call_is_success.push(false);
-        builder.state_ref().push_call(mock_internal_create());
+        builder
+            .state_ref()
+            .push_call(mock_internal_create())
+            .unwrap();
         let addr = builder.state_ref().create2_address(step_create2).unwrap();
 
         assert_eq!(addr.to_word(), addr_expect);
@@ -3042,13 +3481,16 @@ mod tracer_tests {
         let mut builder = CircuitInputBuilderTx::new(&block, step_create);
         // Set up call context at CREATE
         builder.tx_ctx.call_is_success.push(false);
-        builder.state_ref().push_call(mock_internal_create());
+        builder
+            .state_ref()
+            .push_call(mock_internal_create())
+            .unwrap();
         builder.builder.sdb.set_account(
             &ADDR_B,
             Account {
                 nonce: Word::from(1),
                 balance: Word::zero(),
-                storage: HashMap::new(),
+                storage: BTreeMap::new(),
                 code_hash: Hash::zero(),
             },
         );
@@ -3161,4 +3603,117 @@ mod tracer_tests {
             }
         )
     }
+
+    #[test]
+    fn block_withdrawals() {
+        let code = bytecode! {
+            STOP
+        };
+        let block = crate::mock::BlockData::new_from_geth_data(
+            mock::new_single_tx_trace_code(&code).unwrap(),
+        );
+        let withdrawals = vec![
+            Withdrawal {
+                index: 7,
+                validator_index: 1,
+                address: *ADDR_B,
+                amount: 2,
+            },
+            Withdrawal {
+                index: 8,
+                validator_index: 2,
+                address: *ADDR_B,
+                amount: 3,
+            },
+        ];
+
+        // Withdrawals are rejected before Shanghai
+        let mut builder = block.new_circuit_input_builder();
+        builder.block.withdrawals = withdrawals.clone();
+        assert!(matches!(
+            builder.handle_block(&block.eth_block, &block.geth_traces),
+            Err(Error::WithdrawalsBeforeShanghai)
+        ));
+
+        let mut builder = block.new_circuit_input_builder();
+        builder.block.chain_config.hardfork = Hardfork::Shanghai;
+        builder.block.withdrawals = withdrawals;
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        // The balance credits are the last operations of the block
+        let gwei = Word::from(1_000_000_000u64);
+        let account_ops = &builder.block.container.account;
+        let credits: Vec<_> = account_ops[account_ops.len() - 2..]
+            .iter()
+            .map(|op| (op.rwc().0, op.rw(), op.op().clone()))
+            .collect();
+        let rwc = builder.block_ctx.rwc.0;
+        assert_eq!(
+            credits,
+            vec![
+                (
+                    rwc - 2,
+                    RW::WRITE,
+                    AccountOp {
+                        address: *ADDR_B,
+                        field: AccountField::Balance,
+                        value: gwei * 2,
+                        value_prev: Word::zero(),
+                    }
+                ),
+                (
+                    rwc - 1,
+                    RW::WRITE,
+                    AccountOp {
+                        address: *ADDR_B,
+                        field: AccountField::Balance,
+                        value: gwei * 5,
+                        value_prev: gwei * 2,
+                    }
+                ),
+            ]
+        );
+        assert_eq!(builder.sdb.get_account(&ADDR_B).1.balance, gwei * 5);
+    }
+
+    #[test]
+    fn tracer_err_in_step() {
+        let code = bytecode! {
+            PUSH1(0x01)
+            SLOAD
+            STOP
+        };
+        let mut block = crate::mock::BlockData::new_from_geth_data(
+            mock::new_single_tx_trace_code(&code).unwrap(),
+        );
+        // Drop the key of the storage slot read by the SLOAD from the trace.
+        let sload = block.geth_traces[0]
+            .struct_logs
+            .iter_mut()
+            .find(|step| step.op == OpcodeId::SLOAD)
+            .unwrap();
+        sload.stack = Stack::new();
+
+        let mut builder = block.new_circuit_input_builder();
+        let err = builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::ExecStep {
+                tx_index: 0,
+                op: OpcodeId::SLOAD,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn tracer_err_access_list_invariant() {
+        let mut sdb = StateDB::new();
+        let err = sdb.remove_account_from_access_list(&ADDR_A).unwrap_err();
+        assert!(matches!(err, Error::BrokenInvariant(_)));
+    }
 }
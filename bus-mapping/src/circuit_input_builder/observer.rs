@@ -0,0 +1,182 @@
+//! Hooks into the building of the circuit inputs of a block.
+//!
+//! A [`TraceObserver`] set on a
+//! [`CircuitInputBuilder`](crate::circuit_input_builder::CircuitInputBuilder)
+//! is notified of every transaction, execution step and bus-mapping operation
+//! the builder handles, in the order it handles them.  It can report the
+//! progress of the building, gather metrics, dump the parts of a trace it is
+//! interested in, or abort the building by returning an error from the hooks
+//! of the transactions and the steps.
+
+use super::{ExecStep, Transaction};
+use crate::{
+    operation::{OpEnum, RWCounter, RW},
+    Error,
+};
+use eth_types::GethExecStep;
+
+/// Observer of the transactions, the execution steps and the operations
+/// handled by a
+/// [`CircuitInputBuilder`](crate::circuit_input_builder::CircuitInputBuilder).
+///
+/// All the hooks do nothing by default.  An error returned by a hook aborts
+/// the building of the block with that error, which is usually
+/// [`Error::Aborted`].
+pub trait TraceObserver: Send {
+    /// Called before the transaction at `tx_index` in the block is handled.
+    fn on_tx_start(
+        &mut self,
+        _tx_index: usize,
+        _eth_tx: &eth_types::Transaction,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called after the operations of `step` of the transaction at
+    /// `tx_index` are generated.  `geth_step` is the step of the trace it is
+    /// generated from, which is `None` for the `BeginTx` and `EndTx` steps.
+    fn on_step(
+        &mut self,
+        _tx_index: usize,
+        _geth_step: Option<&GethExecStep>,
+        _step: &ExecStep,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called for every operation `op` generated, with its [`RWCounter`] and
+    /// whether it is a read or a write, including the ones of the reverted
+    /// writes and of the withdrawals of the block.
+    fn on_operation(&mut self, _rwc: RWCounter, _rw: RW, _op: &OpEnum) {}
+
+    /// Called after the transaction at `tx_index` is handled, with all its
+    /// steps.
+    fn on_tx_end(&mut self, _tx_index: usize, _tx: &Transaction) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TraceObserver;
+    use crate::{
+        circuit_input_builder::{ExecStep, Transaction},
+        mock::BlockData,
+        operation::{OpEnum, RWCounter, RW},
+        Error,
+    };
+    use eth_types::{bytecode, geth_types::GethData, GethExecStep};
+    use mock::{helpers::account_0_code_account_1_no_code, TestContext};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Default)]
+    struct Counts {
+        txs: usize,
+        steps: usize,
+        geth_steps: usize,
+        ops: usize,
+        last_rwc: usize,
+    }
+
+    struct CountingObserver {
+        counts: Arc<Mutex<Counts>>,
+        max_steps: usize,
+    }
+
+    impl TraceObserver for CountingObserver {
+        fn on_tx_start(
+            &mut self,
+            _tx_index: usize,
+            _eth_tx: &eth_types::Transaction,
+        ) -> Result<(), Error> {
+            self.counts.lock().unwrap().txs += 1;
+            Ok(())
+        }
+
+        fn on_step(
+            &mut self,
+            _tx_index: usize,
+            geth_step: Option<&GethExecStep>,
+            _step: &ExecStep,
+        ) -> Result<(), Error> {
+            let mut counts = self.counts.lock().unwrap();
+            counts.steps += 1;
+            counts.geth_steps += geth_step.is_some() as usize;
+            if counts.steps > self.max_steps {
+                return Err(Error::Aborted(format!(
+                    "more than {} steps",
+                    self.max_steps
+                )));
+            }
+            Ok(())
+        }
+
+        fn on_operation(&mut self, rwc: RWCounter, _rw: RW, _op: &OpEnum) {
+            let mut counts = self.counts.lock().unwrap();
+            counts.ops += 1;
+            counts.last_rwc = rwc.0;
+        }
+
+        fn on_tx_end(&mut self, _tx_index: usize, tx: &Transaction) -> Result<(), Error> {
+            assert_eq!(tx.steps().len(), self.counts.lock().unwrap().steps);
+            Ok(())
+        }
+    }
+
+    fn block_data() -> BlockData {
+        let code = bytecode! {
+            PUSH1(0x20)
+            PUSH1(0x40)
+            MSTORE
+            PUSH1(0x01)
+            SLOAD
+            STOP
+        };
+        let ctx = TestContext::<2, 1>::new(
+            |accs| account_0_code_account_1_no_code(accs, code),
+            |txs, accs| {
+                txs[0].from(accs[1].address).to(accs[0].address);
+            },
+            |_block| {},
+        )
+        .unwrap();
+        BlockData::new_from_geth_data(GethData::from(ctx))
+    }
+
+    #[test]
+    fn trace_observer_counts() {
+        let block = block_data();
+        let counts = Arc::new(Mutex::new(Counts::default()));
+        let mut builder = block.new_circuit_input_builder();
+        builder.set_observer(Box::new(CountingObserver {
+            counts: counts.clone(),
+            max_steps: usize::MAX,
+        }));
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let counts = counts.lock().unwrap();
+        let tx = &builder.block.txs()[0];
+        assert_eq!(counts.txs, 1);
+        assert_eq!(counts.steps, tx.steps().len());
+        assert_eq!(counts.geth_steps, block.geth_traces[0].struct_logs.len());
+        assert_eq!(counts.ops, builder.block_ctx.rwc.0 - 1);
+        assert_eq!(counts.last_rwc, builder.block_ctx.rwc.0 - 1);
+    }
+
+    #[test]
+    fn trace_observer_aborts() {
+        let block = block_data();
+        let mut builder = block.new_circuit_input_builder();
+        builder.set_observer(Box::new(CountingObserver {
+            counts: Default::default(),
+            max_steps: 3,
+        }));
+        let err = builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap_err();
+        assert!(matches!(err, Error::Aborted(_)));
+        assert!(builder.block.txs().is_empty());
+    }
+}
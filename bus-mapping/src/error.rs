@@ -1,50 +1,99 @@
 //! Error module for the bus-mapping crate
 
-use core::fmt::{Display, Formatter, Result as FmtResult};
-use eth_types::{Address, GethExecStep, Word};
+use eth_types::{
+    evm_types::{OpcodeId, ProgramCounter},
+    Address, GethExecStep, Word,
+};
 use ethers_providers::ProviderError;
-use std::error::Error as StdError;
+use thiserror::Error as ThisError;
 
 /// Error type for any BusMapping related failure.
-#[derive(Debug)]
+#[derive(Debug, ThisError)]
 pub enum Error {
     /// Serde de/serialization error.
-    SerdeError(serde_json::error::Error),
+    #[error("serde error: {0}")]
+    SerdeError(#[from] serde_json::error::Error),
     /// JSON-RPC related error.
-    JSONRpcError(ProviderError),
+    #[error("JSON-RPC error: {0}")]
+    JSONRpcError(#[from] ProviderError),
     /// OpcodeId is not a call type.
+    #[error("opcode is not a call type")]
     OpcodeIdNotCallType,
     /// Account not found in the StateDB
+    #[error("account {0:?} not found in the StateDB")]
     AccountNotFound(Address),
     /// Storage key not found in the StateDB
+    #[error("storage key {1} of account {0:?} not found in the StateDB")]
     StorageKeyNotFound(Address, Word),
     /// Unable to figure out error at a [`GethExecStep`]
+    #[error("unexpected error of {:?} at pc {}: {0}", .1.op, .1.pc.0)]
     UnexpectedExecStepError(&'static str, GethExecStep),
     /// Invalid [`eth_types::GethExecTrace`] due to an invalid/unexpected value
     /// in it.
+    #[error("invalid geth trace: {0}")]
     InvalidGethExecTrace(&'static str),
     /// Invalid [`GethExecStep`] due to an invalid/unexpected value in it.
+    #[error("invalid geth step of {:?} at pc {}: {0}", .1.op, .1.pc.0)]
     InvalidGethExecStep(&'static str, GethExecStep),
+    /// Error reported by geth for a step which isn't handled.
+    #[error("unknown error {error:?} reported by geth for {op:?}")]
+    UnknownGethExecStepError {
+        /// Opcode of the step
+        op: OpcodeId,
+        /// Error reported by geth
+        error: String,
+    },
+    /// Error while generating the operations of a step of a transaction.
+    #[error("tx {tx_index}, step of {op:?} at pc {}: {source}", .pc.0)]
+    ExecStep {
+        /// Index of the transaction in the block
+        tx_index: usize,
+        /// Program counter of the step
+        pc: ProgramCounter,
+        /// Opcode of the step
+        op: OpcodeId,
+        /// Error of the step
+        source: Box<Error>,
+    },
+    /// An invariant of the state of the builder doesn't hold, which is a bug
+    /// of the builder or a trace it doesn't support.
+    #[error("broken invariant: {0}")]
+    BrokenInvariant(&'static str),
     /// Eth type related error.
-    EthTypeError(eth_types::Error),
+    #[error("eth type error: {0}")]
+    EthTypeError(#[from] eth_types::Error),
+    /// The fork blocks of the chain with this id aren't known.
+    #[error("unknown fork blocks of chain {0}")]
+    UnknownChain(Word),
+    /// The block has withdrawals, but its hardfork is before Shanghai.
+    #[error("withdrawals in a block before Shanghai")]
+    WithdrawalsBeforeShanghai,
+    /// The building of the circuit inputs was aborted by a
+    /// [`TraceObserver`](crate::circuit_input_builder::TraceObserver).
+    #[error("aborted: {0}")]
+    Aborted(String),
+    /// Error spilling the operations of the
+    /// [`OperationContainer`](crate::operation::OperationContainer) to disk.
+    #[error("io error: {0}")]
+    IoError(#[from] std::io::Error),
+    /// Error of the store of the state on disk.
+    #[cfg(feature = "disk-db")]
+    #[error("disk db error: {0}")]
+    DiskDBError(#[from] sled::Error),
+    /// A value read from the store of the state on disk isn't valid.
+    #[error("corrupted {0} in the disk db")]
+    DiskDBCorrupted(&'static str),
 }
 
-impl From<eth_types::Error> for Error {
-    fn from(err: eth_types::Error) -> Self {
-        Error::EthTypeError(err)
+impl Error {
+    /// Returns the error `self` of the step `geth_step` of the transaction at
+    /// `tx_index` in the block, with the location of the step.
+    pub fn in_step(self, tx_index: usize, geth_step: &GethExecStep) -> Self {
+        Error::ExecStep {
+            tx_index,
+            pc: geth_step.pc,
+            op: geth_step.op,
+            source: Box::new(self),
+        }
     }
 }
-
-impl From<ProviderError> for Error {
-    fn from(err: ProviderError) -> Self {
-        Error::JSONRpcError(err)
-    }
-}
-
-impl Display for Error {
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{:?}", self)
-    }
-}
-
-impl StdError for Error {}
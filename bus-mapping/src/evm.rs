@@ -3,4 +3,4 @@
 pub(crate) mod opcodes;
 
 pub use eth_types::evm_types::opcode_ids::OpcodeId;
-pub use opcodes::Opcode;
+pub use opcodes::{is_opcode_supported, Opcode};
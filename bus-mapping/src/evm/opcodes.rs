@@ -4,13 +4,13 @@ use crate::{
     evm::OpcodeId,
     operation::{
         AccountField, AccountOp, CallContextField, CallContextOp, TxAccessListAccountOp,
-        TxRefundOp, RW,
+        TxAccessListAccountStorageOp, TxRefundOp, RW,
     },
     Error,
 };
 use core::fmt::Debug;
 use eth_types::{
-    evm_types::{GasCost, MAX_REFUND_QUOTIENT_OF_GAS_USED},
+    evm_types::{gas::call_data_gas_cost, GasCost},
     GethExecStep, ToWord,
 };
 use log::warn;
@@ -18,7 +18,9 @@ use log::warn;
 mod calldatasize;
 mod caller;
 mod callvalue;
+mod create;
 mod dup;
+mod mcopy;
 mod mload;
 mod mstore;
 mod selfbalance;
@@ -30,7 +32,9 @@ mod swap;
 use calldatasize::Calldatasize;
 use caller::Caller;
 use callvalue::Callvalue;
+use create::Create;
 use dup::Dup;
+use mcopy::Mcopy;
 use mload::Mload;
 use mstore::Mstore;
 use selfbalance::Selfbalance;
@@ -65,7 +69,20 @@ type FnGenAssociatedOps =
     fn(state: &mut CircuitInputStateRef, next_steps: &[GethExecStep]) -> Result<(), Error>;
 
 fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
-    match opcode_id {
+    implemented_gen_associated_ops(opcode_id).unwrap_or_else(|| {
+        warn!("Using dummy gen_associated_ops for opcode {:?}", opcode_id);
+        dummy_gen_associated_ops
+    })
+}
+
+/// Returns whether the associated operations of [`OpcodeId`] are generated,
+/// instead of being skipped by a dummy implementation.
+pub fn is_opcode_supported(opcode_id: &OpcodeId) -> bool {
+    implemented_gen_associated_ops(opcode_id).is_some()
+}
+
+fn implemented_gen_associated_ops(opcode_id: &OpcodeId) -> Option<FnGenAssociatedOps> {
+    Some(match opcode_id {
         OpcodeId::STOP => Stop::gen_associated_ops,
         OpcodeId::ADD => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::MUL => StackOnlyOpcode::<2, 1>::gen_associated_ops,
@@ -115,9 +132,9 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::NUMBER => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         // OpcodeId::DIFFICULTY => {},
         // OpcodeId::GASLIMIT => {},
-        // OpcodeId::CHAINID => {},
+        OpcodeId::CHAINID => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::SELFBALANCE => Selfbalance::gen_associated_ops,
-        // OpcodeId::BASEFEE => {},
+        OpcodeId::BASEFEE => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::POP => StackOnlyOpcode::<1, 0>::gen_associated_ops,
         OpcodeId::MLOAD => Mload::gen_associated_ops,
         OpcodeId::MSTORE => Mstore::<false>::gen_associated_ops,
@@ -130,6 +147,8 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::MSIZE => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::GAS => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::JUMPDEST => dummy_gen_associated_ops,
+        OpcodeId::MCOPY => Mcopy::gen_associated_ops,
+        OpcodeId::PUSH0 => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::PUSH1 => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::PUSH2 => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::PUSH3 => StackOnlyOpcode::<0, 1>::gen_associated_ops,
@@ -199,24 +218,19 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         // OpcodeId::LOG2 => {},
         // OpcodeId::LOG3 => {},
         // OpcodeId::LOG4 => {},
-        // OpcodeId::CREATE => {},
+        OpcodeId::CREATE => Create::<false>::gen_associated_ops,
         // OpcodeId::CALL => {},
         // OpcodeId::CALLCODE => {},
         // TODO: Handle RETURN by its own gen_associated_ops.
         OpcodeId::RETURN => Stop::gen_associated_ops,
         // OpcodeId::DELEGATECALL => {},
-        // OpcodeId::CREATE2 => {},
+        OpcodeId::CREATE2 => Create::<true>::gen_associated_ops,
         // OpcodeId::STATICCALL => {},
         // TODO: Handle REVERT by its own gen_associated_ops.
         OpcodeId::REVERT => Stop::gen_associated_ops,
         // OpcodeId::SELFDESTRUCT => {},
-        // _ => panic!("Opcode {:?} gen_associated_ops not implemented",
-        // self),
-        _ => {
-            warn!("Using dummy gen_associated_ops for opcode {:?}", opcode_id);
-            dummy_gen_associated_ops
-        }
-    }
+        _ => return None,
+    })
 }
 
 /// Generate the associated operations according to the particular
@@ -251,7 +265,7 @@ pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<(), Error> {
                 field,
                 value,
             },
-        );
+        )?;
     }
 
     let caller_address = call.caller_address;
@@ -264,7 +278,7 @@ pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<(), Error> {
             value: (nonce_prev + 1).into(),
             value_prev: (nonce_prev).into(),
         },
-    );
+    )?;
 
     for address in [call.caller_address, call.address] {
         state.sdb.add_account_to_access_list(address);
@@ -276,19 +290,45 @@ pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<(), Error> {
                 value: true,
                 value_prev: false,
             },
-        );
+        )?;
     }
 
-    let call_data_gas_cost = state
-        .tx
-        .input
-        .iter()
-        .fold(0, |acc, byte| acc + if *byte == 0 { 4 } else { 16 });
-    let intrinsic_gas_cost = if state.tx.is_create() {
-        GasCost::CREATION_TX.as_u64()
-    } else {
-        GasCost::TX.as_u64()
-    } + call_data_gas_cost;
+    // Pre-warm the accounts and storage slots of the access list (EIP-2930)
+    for item in state.tx.access_list.0.clone() {
+        let is_warm_prev = !state.sdb.add_account_to_access_list(item.address);
+        state.push_op(
+            RW::WRITE,
+            TxAccessListAccountOp {
+                tx_id: state.tx_ctx.id(),
+                address: item.address,
+                value: true,
+                value_prev: is_warm_prev,
+            },
+        )?;
+        for key in item.storage_keys {
+            let key = key.to_word();
+            let is_warm_prev = !state
+                .sdb
+                .add_account_storage_to_access_list((item.address, key));
+            state.push_op(
+                RW::WRITE,
+                TxAccessListAccountStorageOp {
+                    tx_id: state.tx_ctx.id(),
+                    address: item.address,
+                    key,
+                    value: true,
+                    value_prev: is_warm_prev,
+                },
+            )?;
+        }
+    }
+
+    let intrinsic_gas_cost = state.block.chain_config.hardfork.tx_intrinsic_gas_cost(
+        state.tx.is_create(),
+        state.tx.input.len() as u64,
+        call_data_gas_cost(&state.tx.input),
+        &state.tx.access_list,
+    );
     state.step.gas_cost = GasCost(intrinsic_gas_cost);
 
     let (found, caller_account) = state.sdb.get_account_mut(&call.caller_address);
@@ -340,7 +380,7 @@ pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<(), Error> {
                     value: code_hash.to_word(),
                     value_prev: code_hash.to_word(),
                 },
-            );
+            )?;
         }
     }
 
@@ -372,7 +412,7 @@ pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<(), Error> {
                 field,
                 value,
             },
-        );
+        )?;
     }
 
     Ok(())
@@ -388,7 +428,7 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<(), Error> {
             field: CallContextField::TxId,
             value: state.tx_ctx.id().into(),
         },
-    );
+    )?;
 
     let refund = state.sdb.refund();
     state.push_op(
@@ -398,10 +438,13 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<(), Error> {
             value: refund,
             value_prev: refund,
         },
-    );
+    )?;
 
-    let effective_refund =
-        refund.min((state.tx.gas - state.step.gas_left.0) / MAX_REFUND_QUOTIENT_OF_GAS_USED as u64);
+    let effective_refund = state
+        .block
+        .chain_config
+        .hardfork
+        .effective_refund(refund, state.tx.gas - state.step.gas_left.0);
     let (found, caller_account) = state.sdb.get_account_mut(&call.caller_address);
     if !found {
         return Err(Error::AccountNotFound(call.caller_address));
@@ -417,7 +460,7 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<(), Error> {
             value: caller_balance,
             value_prev: caller_balance_prev,
         },
-    );
+    )?;
 
     let effective_tip = state.tx.gas_price - state.block.base_fee;
     let (found, coinbase_account) = state.sdb.get_account_mut(&state.block.coinbase);
@@ -435,7 +478,7 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<(), Error> {
             value: coinbase_balance,
             value_prev: coinbase_balance_prev,
         },
-    );
+    )?;
 
     if !state.tx_ctx.is_last_tx() {
         state.push_op(
@@ -445,8 +488,89 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<(), Error> {
                 field: CallContextField::TxId,
                 value: (state.tx_ctx.id() + 1).into(),
             },
-        );
+        )?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mock::BlockData,
+        operation::{TxAccessListAccountOp, TxAccessListAccountStorageOp, RW},
+    };
+    use eth_types::{
+        bytecode, evm_types::OpcodeId, geth_types::GethData, AccessList, AccessListItem, Word, H256,
+    };
+    use mock::{helpers::account_0_code_account_1_no_code, TestContext};
+
+    #[test]
+    fn begin_tx_access_list() {
+        let code = bytecode! {
+            PUSH1(0x01)
+            SLOAD
+            STOP
+        };
+        let ctx = TestContext::<2, 1>::new(
+            |accs| account_0_code_account_1_no_code(accs, code),
+            |txs, accs| {
+                txs[0]
+                    .from(accs[1].address)
+                    .to(accs[0].address)
+                    .access_list(AccessList(vec![AccessListItem {
+                        address: accs[0].address,
+                        storage_keys: vec![H256::from_low_u64_be(1)],
+                    }]));
+            },
+            |_block| {},
+        )
+        .unwrap();
+        let callee = ctx.accounts[0].address;
+        let block = BlockData::new_from_geth_data(GethData::from(ctx));
+        let mut builder = block.new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let begin_tx = &builder.block.txs()[0].steps()[0];
+        assert_eq!(begin_tx.gas_cost.as_u64(), 21000 + 2400 + 1900);
+
+        // The callee is warm already, its storage slot is pre-warmed
+        let container = &builder.block.container;
+        assert_eq!(
+            container
+                .tx_access_list_account
+                .iter()
+                .map(|operation| (operation.rw(), operation.op().clone()))
+                .last(),
+            Some((
+                RW::WRITE,
+                TxAccessListAccountOp {
+                    tx_id: 1,
+                    address: callee,
+                    value: true,
+                    value_prev: true,
+                }
+            ))
+        );
+        assert_eq!(
+            container.tx_access_list_account_storage[0].op(),
+            &TxAccessListAccountStorageOp {
+                tx_id: 1,
+                address: callee,
+                key: Word::one(),
+                value: true,
+                value_prev: false,
+            }
+        );
+
+        // The slot is read warm by geth
+        let sload = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::SLOAD)
+            .unwrap();
+        assert_eq!(sload.gas_cost.as_u64(), 100);
+    }
+}
@@ -25,7 +25,7 @@ impl Opcode for Calldatasize {
                 field: CallContextField::CallDataLength,
                 value,
             },
-        );
+        )?;
         state.push_stack_op(RW::WRITE, step.stack.last_filled().map(|a| a - 1), value)?;
         Ok(())
     }
@@ -79,8 +79,12 @@ mod calldatasize_tests {
         );
         assert_eq!(
             {
-                let operation =
-                    &builder.block.container.stack[step.bus_mapping_instance[1].as_usize()];
+                let operation = builder
+                    .block
+                    .container
+                    .stack
+                    .get(step.bus_mapping_instance[1].as_usize())
+                    .unwrap();
                 (operation.rw(), operation.op())
             },
             (
@@ -0,0 +1,442 @@
+use super::Opcode;
+use crate::circuit_input_builder::{CircuitInputStateRef, CopyDataType, CopyEvent};
+use crate::evm::OpcodeId;
+use crate::operation::{
+    AccountField, AccountOp, CallContextField, CallContextOp, MemoryOp, TxAccessListAccountOp, RW,
+};
+use crate::Error;
+use core::convert::TryInto;
+use eth_types::evm_types::{gas::memory_expansion_gas_cost, GasCost, MemoryAddress};
+use eth_types::{GethExecStep, ToWord, Word};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::CREATE`](crate::evm::OpcodeId::CREATE) and
+/// [`OpcodeId::CREATE2`](crate::evm::OpcodeId::CREATE2) `OpcodeId`s.
+///
+/// The step increases the nonce of the caller, warms the address of the
+/// created contract, and enters its init code, which is copied from memory to
+/// the bytecode of its hash as a [`CopyEvent`].  The nonce of the contract and
+/// the transfer of the value belong to the created call, and are reverted with
+/// it.  The address, or 0 if the creation fails, is pushed to the stack right
+/// away, since the caller resumes after it from the state saved in its call
+/// context.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Create<const IS_CREATE2: bool>;
+
+impl<const IS_CREATE2: bool> Opcode for Create<IS_CREATE2> {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+
+        // A creation which doesn't execute its init code, because it's empty
+        // or the creation fails before, doesn't enter a call in the trace.
+        if steps.get(1).map(|next_step| next_step.depth) != Some(step.depth + 1) {
+            return Err(Error::UnexpectedExecStepError(
+                "creation without init code execution isn't supported",
+                step.clone(),
+            ));
+        }
+
+        // The id of the created call is the rw counter of the step, before
+        // any operation is pushed.
+        let callee = state.parse_call(step)?;
+        let caller = state.call()?.clone();
+        let tx_id = state.tx_ctx.id();
+
+        // Stack reads of value, offset, length, and salt for CREATE2, and the
+        // write of the address of the created contract
+        let n_pop = if IS_CREATE2 { 4 } else { 3 };
+        for idx in 0..n_pop {
+            state.push_stack_op(
+                RW::READ,
+                step.stack.nth_last_filled(idx),
+                step.stack.nth_last(idx)?,
+            )?;
+        }
+        let address = if callee.is_success {
+            callee.address.to_word()
+        } else {
+            Word::zero()
+        };
+        state.push_stack_op(RW::WRITE, step.stack.nth_last_filled(n_pop - 1), address)?;
+
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::Depth, caller.depth.into()),
+            (CallContextField::CalleeAddress, caller.address.to_word()),
+            (
+                CallContextField::RwCounterEndOfReversion,
+                caller.rw_counter_end_of_reversion.into(),
+            ),
+            (
+                CallContextField::IsPersistent,
+                (caller.is_persistent as usize).into(),
+            ),
+        ] {
+            state.push_op(
+                RW::READ,
+                CallContextOp {
+                    call_id: caller.call_id,
+                    field,
+                    value,
+                },
+            )?;
+        }
+
+        // The nonce of the caller is increased and the address of the
+        // contract is warmed before entering the init code, so they are only
+        // reverted with the caller.
+        let nonce_prev = state.sdb.increase_nonce(&caller.address);
+        state.push_op_reversible(
+            RW::WRITE,
+            AccountOp {
+                address: caller.address,
+                field: AccountField::Nonce,
+                value: (nonce_prev + 1).into(),
+                value_prev: nonce_prev.into(),
+            },
+        )?;
+        let is_warm_prev = !state.sdb.add_account_to_access_list(callee.address);
+        state.push_op_reversible(
+            RW::WRITE,
+            TxAccessListAccountOp {
+                tx_id,
+                address: callee.address,
+                value: true,
+                value_prev: is_warm_prev,
+            },
+        )?;
+        let caller_swc = state.step.swc + 2;
+
+        // Memory reads of the init code, which is copied to the bytecode of
+        // its hash.  Accesses to memory that hasn't been initialized are
+        // valid, and return 0.
+        let offset: MemoryAddress = step.stack.nth_last(1)?.try_into()?;
+        let length = step.stack.nth_last(2)?.as_usize();
+        let rw_counter_start = state.block_ctx.rwc;
+        let init_code = (0..length)
+            .map(|idx| {
+                step.memory
+                    .0
+                    .get(offset.0 + idx)
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        for (idx, byte) in init_code.iter().enumerate() {
+            state.push_op(
+                RW::READ,
+                MemoryOp::new(caller.call_id, offset.map(|a| a + idx), *byte),
+            )?;
+        }
+        state.push_copy(CopyEvent {
+            src_type: CopyDataType::Memory,
+            src_id: caller.call_id.into(),
+            src_addr: offset.0 as u64,
+            src_addr_end: (offset.0 + length) as u64,
+            dst_type: CopyDataType::Bytecode,
+            dst_id: callee.code_hash.to_word(),
+            dst_addr: 0,
+            log_id: 0,
+            rw_counter_start,
+            bytes: code_bytes(&init_code),
+        });
+
+        state.push_call(callee.clone())?;
+        for (field, value) in [
+            (
+                CallContextField::RwCounterEndOfReversion,
+                callee.rw_counter_end_of_reversion.into(),
+            ),
+            (
+                CallContextField::IsPersistent,
+                (callee.is_persistent as usize).into(),
+            ),
+        ] {
+            state.push_op(
+                RW::READ,
+                CallContextOp {
+                    call_id: callee.call_id,
+                    field,
+                    value,
+                },
+            )?;
+        }
+
+        // The nonce of the contract starts at 1 (EIP-161), and the value is
+        // transferred to it.
+        let callee_nonce_prev = state.sdb.increase_nonce(&callee.address);
+        state.push_op_reversible(
+            RW::WRITE,
+            AccountOp {
+                address: callee.address,
+                field: AccountField::Nonce,
+                value: (callee_nonce_prev + 1).into(),
+                value_prev: callee_nonce_prev.into(),
+            },
+        )?;
+        for (address, is_sender) in [(caller.address, true), (callee.address, false)] {
+            let (_, account) = state.sdb.get_account_mut(&address);
+            let balance_prev = account.balance;
+            account.balance = if is_sender {
+                balance_prev - callee.value
+            } else {
+                balance_prev + callee.value
+            };
+            let balance = account.balance;
+            state.push_op_reversible(
+                RW::WRITE,
+                AccountOp {
+                    address,
+                    field: AccountField::Balance,
+                    value: balance,
+                    value_prev: balance_prev,
+                },
+            )?;
+        }
+
+        for (field, value) in [
+            (CallContextField::CallerId, caller.call_id.into()),
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::Depth, callee.depth.into()),
+            (
+                CallContextField::CallerAddress,
+                callee.caller_address.to_word(),
+            ),
+            (CallContextField::CalleeAddress, callee.address.to_word()),
+            (CallContextField::CallDataOffset, 0.into()),
+            (CallContextField::CallDataLength, 0.into()),
+            (CallContextField::Value, callee.value),
+            (
+                CallContextField::IsSuccess,
+                (callee.is_success as usize).into(),
+            ),
+            (CallContextField::IsStatic, 0.into()),
+            (CallContextField::LastCalleeId, 0.into()),
+            (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+            (CallContextField::LastCalleeReturnDataLength, 0.into()),
+        ] {
+            state.push_op(
+                RW::READ,
+                CallContextOp {
+                    call_id: callee.call_id,
+                    field,
+                    value,
+                },
+            )?;
+        }
+
+        // The gas cost follows the hardfork of the block, which charges every
+        // word of the init code since Shanghai (EIP-3860).
+        let curr_memory_word_size = step.memory.0.len() as u64 / 32;
+        let memory_word_size =
+            curr_memory_word_size.max((offset.0 as u64 + length as u64 + 31) / 32);
+        state.step.gas_cost = GasCost(
+            state
+                .block
+                .chain_config
+                .hardfork
+                .create_gas_cost(IS_CREATE2, length as u64)
+                .as_u64()
+                + memory_expansion_gas_cost(curr_memory_word_size, memory_word_size),
+        );
+
+        // Save the state the caller resumes from, which gives all but one
+        // 64th of the gas left to the created call (EIP-150).
+        let gas_left = step.gas.0 - state.step.gas_cost.0;
+        for (field, value) in [
+            (CallContextField::ProgramCounter, (step.pc.0 + 1).into()),
+            (
+                CallContextField::StackPointer,
+                step.stack.nth_last_filled(n_pop - 1).0.into(),
+            ),
+            (CallContextField::GasLeft, (gas_left / 64).into()),
+            (CallContextField::MemorySize, memory_word_size.into()),
+            (CallContextField::StateWriteCounter, caller_swc.into()),
+        ] {
+            state.push_op(
+                RW::WRITE,
+                CallContextOp {
+                    call_id: caller.call_id,
+                    field,
+                    value,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// Returns the bytes of `code`, with whether each one is an opcode rather than
+// the data of a push.
+fn code_bytes(code: &[u8]) -> Vec<(u8, bool)> {
+    let mut push_data_left = 0;
+    code.iter()
+        .map(|byte| {
+            let mut is_code = true;
+            if push_data_left > 0 {
+                is_code = false;
+                push_data_left -= 1;
+            } else if (OpcodeId::PUSH1.as_u8()..=OpcodeId::PUSH32.as_u8()).contains(byte) {
+                push_data_left = (*byte - (OpcodeId::PUSH1.as_u8() - 1)) as usize;
+            }
+            (*byte, is_code)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod create_tests {
+    use super::*;
+    use crate::operation::StackOp;
+    use eth_types::evm_types::{Hardfork, OpcodeId, StackAddress};
+    use eth_types::{bytecode, ToBigEndian};
+    use ethers_core::utils::{get_contract_address, get_create2_address, keccak256};
+    use pretty_assertions::assert_eq;
+
+    fn test_ok(is_create2: bool, hardfork: Hardfork) {
+        // The init code is a single STOP, at the end of the zeroed word
+        let mut code = bytecode! {
+            PUSH1(0x00)
+            PUSH1(0x00)
+            MSTORE
+        };
+        if is_create2 {
+            code.push(1, Word::from(0x1234));
+        }
+        code.push(1, Word::one()); // length
+        code.push(1, Word::from(31)); // offset
+        code.push(1, Word::zero()); // value
+        code.write_op(if is_create2 {
+            OpcodeId::CREATE2
+        } else {
+            OpcodeId::CREATE
+        });
+        code.write_op(OpcodeId::STOP);
+
+        let block = crate::mock::BlockData::new_from_geth_data(
+            mock::new_single_tx_trace_code(&code).unwrap(),
+        );
+        let mut builder = block.new_circuit_input_builder();
+        builder.block.chain_config.hardfork = hardfork;
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| matches!(step.op, OpcodeId::CREATE | OpcodeId::CREATE2))
+            .unwrap();
+        // The memory isn't expanded by the word of init code
+        assert_eq!(step.gas_cost, hardfork.create_gas_cost(is_create2, 1));
+        let (caller, callee) = (&tx.calls()[0], &tx.calls()[1]);
+        let address = if is_create2 {
+            get_create2_address(
+                caller.address,
+                Word::from(0x1234).to_be_bytes().to_vec(),
+                vec![0x00],
+            )
+        } else {
+            get_contract_address(caller.address, Word::zero())
+        };
+        assert_eq!(callee.address, address);
+        assert_eq!(callee.call_id, step.rwc.0);
+        assert!(callee.is_success);
+
+        // The address is pushed in place of the last argument popped
+        let n_pop = if is_create2 { 4 } else { 3 };
+        let push = builder
+            .block
+            .container
+            .stack
+            .get(step.bus_mapping_instance[n_pop].as_usize())
+            .unwrap();
+        assert_eq!(
+            (push.rw(), push.op()),
+            (
+                RW::WRITE,
+                &StackOp::new(
+                    caller.call_id,
+                    StackAddress::from(1024 - 1),
+                    address.to_word()
+                )
+            )
+        );
+
+        // The caller nonce, then the nonce of the contract and the transfer
+        let nonces = [n_pop + 6, n_pop + 11].map(|idx| {
+            builder
+                .block
+                .container
+                .account
+                .get(step.bus_mapping_instance[idx].as_usize())
+                .unwrap()
+                .op()
+                .clone()
+        });
+        assert_eq!(
+            nonces,
+            [
+                AccountOp {
+                    address: caller.address,
+                    field: AccountField::Nonce,
+                    value: Word::one(),
+                    value_prev: Word::zero(),
+                },
+                AccountOp {
+                    address,
+                    field: AccountField::Nonce,
+                    value: Word::one(),
+                    value_prev: Word::zero(),
+                },
+            ]
+        );
+
+        // The init code is copied to the bytecode of its hash
+        assert_eq!(builder.block.copy_events.len(), 1);
+        let copy_event = &builder.block.copy_events[0];
+        assert_eq!(copy_event.dst_type, CopyDataType::Bytecode);
+        assert_eq!(copy_event.dst_id, Word::from_big_endian(&keccak256([0x00])));
+        assert_eq!(copy_event.bytes, vec![(0x00, true)]);
+        assert_eq!(
+            step.bus_mapping_instance.len(),
+            n_pop + 1 + 7 + 1 + 5 + 13 + 5
+        );
+    }
+
+    #[test]
+    fn create_opcode_impl() {
+        test_ok(false, Hardfork::London);
+    }
+
+    #[test]
+    fn create2_opcode_impl() {
+        test_ok(true, Hardfork::London);
+    }
+
+    #[test]
+    fn create_opcode_charges_init_code_since_shanghai() {
+        test_ok(false, Hardfork::Shanghai);
+        test_ok(true, Hardfork::Shanghai);
+    }
+
+    #[test]
+    fn code_bytes_of_push_data() {
+        assert_eq!(
+            code_bytes(&[0x60, 0x00, 0x61, 0x01, 0x02, 0x00]),
+            vec![
+                (0x60, true),
+                (0x00, false),
+                (0x61, true),
+                (0x01, false),
+                (0x02, false),
+                (0x00, true),
+            ]
+        );
+    }
+}
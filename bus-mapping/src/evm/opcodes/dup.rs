@@ -74,8 +74,12 @@ mod dup_tests {
 
             assert_eq!(
                 [0, 1]
-                    .map(|idx| &builder.block.container.stack
-                        [step.bus_mapping_instance[idx].as_usize()])
+                    .map(|idx| builder
+                        .block
+                        .container
+                        .stack
+                        .get(step.bus_mapping_instance[idx].as_usize())
+                        .unwrap())
                     .map(|operation| (operation.rw(), operation.op())),
                 [
                     (
@@ -0,0 +1,216 @@
+use super::Opcode;
+use crate::circuit_input_builder::{CircuitInputStateRef, CopyDataType, CopyEvent};
+use crate::{operation::RW, Error};
+use core::convert::{TryFrom, TryInto};
+use eth_types::evm_types::MemoryAddress;
+use eth_types::{GethExecStep, Word};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::MCOPY`](crate::evm::OpcodeId::MCOPY)
+/// `OpcodeId`.  This is responsible of generating all of the associated
+/// [`crate::operation::StackOp`]s and [`crate::operation::MemoryOp`]s and place
+/// them inside the trace's [`crate::operation::OperationContainer`].
+///
+/// The source and destination regions are allowed to overlap (EIP-5656), so
+/// all the source bytes are read before any destination byte is written.  This
+/// way every read observes the memory as it was before the copy started.  The
+/// copy is pushed as a [`CopyEvent`] for the copy circuit.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Mcopy;
+
+impl Opcode for Mcopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+
+        // Stack reads of dst_offset, src_offset and length
+        let dst_offset = step.stack.nth_last(0)?;
+        state.push_stack_op(RW::READ, step.stack.nth_last_filled(0), dst_offset)?;
+        let src_offset = step.stack.nth_last(1)?;
+        state.push_stack_op(RW::READ, step.stack.nth_last_filled(1), src_offset)?;
+        let length = step.stack.nth_last(2)?;
+        state.push_stack_op(RW::READ, step.stack.nth_last_filled(2), length)?;
+
+        // A zero length copy doesn't touch the memory at all, and the offsets
+        // can be any value.
+        if length.is_zero() {
+            return Ok(());
+        }
+
+        // A copy this long runs out of gas expanding the memory, which geth
+        // reports as an error of the step, so it isn't handled here.
+        let length = usize::try_from(length).map_err(|_| {
+            Error::InvalidGethExecStep("MCOPY length out of range without error", step.clone())
+        })?;
+        let src_addr: MemoryAddress = src_offset.try_into()?;
+        let dst_addr: MemoryAddress = dst_offset.try_into()?;
+        let call_id = Word::from(state.call()?.call_id as u64);
+        let rw_counter_start = state.block_ctx.rwc;
+
+        // Read all the source bytes first.  Accesses to memory that hasn't been
+        // initialized are valid, and return 0.
+        let bytes = (0..length)
+            .map(|idx| {
+                step.memory
+                    .0
+                    .get(src_addr.0 + idx)
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        for (idx, byte) in bytes.iter().enumerate() {
+            state.push_memory_op(RW::READ, src_addr.map(|a| a + idx), *byte)?;
+        }
+
+        // Then write them to the destination.
+        for (idx, byte) in bytes.iter().enumerate() {
+            state.push_memory_op(RW::WRITE, dst_addr.map(|a| a + idx), *byte)?;
+        }
+
+        state.push_copy(CopyEvent {
+            src_type: CopyDataType::Memory,
+            src_id: call_id,
+            src_addr: src_addr.0 as u64,
+            src_addr_end: (src_addr.0 + length) as u64,
+            dst_type: CopyDataType::Memory,
+            dst_id: call_id,
+            dst_addr: dst_addr.0 as u64,
+            log_id: 0,
+            rw_counter_start,
+            bytes: bytes.into_iter().map(|byte| (byte, false)).collect(),
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod mcopy_tests {
+    use super::*;
+    use crate::operation::{MemoryOp, StackOp};
+    use eth_types::bytecode;
+    use eth_types::evm_types::{OpcodeId, StackAddress};
+    use eth_types::{word, GethExecStep, Word};
+    use itertools::Itertools;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn mcopy_opcode_impl_overlapping() {
+        // The tracer used in tests doesn't know about MCOPY yet, so we trace
+        // the stack setup and then patch the last step into a MCOPY.
+        let code = bytecode! {
+            PUSH32(word!("0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20"))
+            PUSH1(0)
+            MSTORE
+            PUSH1(4) // length
+            PUSH1(0) // src_offset
+            PUSH1(2) // dst_offset
+            STOP
+        };
+
+        let mut block = crate::mock::BlockData::new_from_geth_data(
+            mock::new_single_tx_trace_code(&code).unwrap(),
+        );
+        let struct_logs = &mut block.geth_traces[0].struct_logs;
+        let mcopy_step = struct_logs.last_mut().unwrap();
+        mcopy_step.op = OpcodeId::MCOPY;
+        let mut stop_step: GethExecStep = mcopy_step.clone();
+        stop_step.op = OpcodeId::STOP;
+        stop_step.pc.inc();
+        for _ in 0..3 {
+            stop_step.stack.0.pop().unwrap();
+        }
+        struct_logs.push(stop_step);
+
+        let mut builder = block.new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::MCOPY)
+            .unwrap();
+
+        assert_eq!(
+            (0..3)
+                .map(|idx| builder
+                    .block
+                    .container
+                    .stack
+                    .get(step.bus_mapping_instance[idx].as_usize())
+                    .unwrap())
+                .map(|operation| (operation.rw(), operation.op().clone()))
+                .collect_vec(),
+            vec![
+                (
+                    RW::READ,
+                    StackOp::new(1, StackAddress::from(1021), Word::from(2))
+                ),
+                (
+                    RW::READ,
+                    StackOp::new(1, StackAddress::from(1022), Word::from(0))
+                ),
+                (
+                    RW::READ,
+                    StackOp::new(1, StackAddress::from(1023), Word::from(4))
+                ),
+            ]
+        );
+
+        // Reads observe the memory before the copy, even though the
+        // destination overlaps the source.
+        assert_eq!(
+            (3..11)
+                .map(|idx| builder
+                    .block
+                    .container
+                    .memory
+                    .get(step.bus_mapping_instance[idx].as_usize())
+                    .unwrap())
+                .map(|operation| (operation.rw(), operation.op().clone()))
+                .collect_vec(),
+            [
+                (0..4)
+                    .map(|idx| (
+                        RW::READ,
+                        MemoryOp::new(1, MemoryAddress(idx), idx as u8 + 1)
+                    ))
+                    .collect_vec(),
+                (0..4)
+                    .map(|idx| (
+                        RW::WRITE,
+                        MemoryOp::new(1, MemoryAddress(idx + 2), idx as u8 + 1)
+                    ))
+                    .collect_vec(),
+            ]
+            .concat()
+        );
+
+        // The copy is pushed for the copy circuit, from the first read.
+        assert_eq!(
+            builder.block.copy_events,
+            vec![CopyEvent {
+                src_type: CopyDataType::Memory,
+                src_id: Word::from(1),
+                src_addr: 0,
+                src_addr_end: 4,
+                dst_type: CopyDataType::Memory,
+                dst_id: Word::from(1),
+                dst_addr: 2,
+                log_id: 0,
+                rw_counter_start: builder
+                    .block
+                    .container
+                    .memory
+                    .get(step.bus_mapping_instance[3].as_usize())
+                    .unwrap()
+                    .rwc(),
+                bytes: (1..=4).map(|byte| (byte, false)).collect(),
+            }]
+        );
+    }
+}
@@ -94,7 +94,12 @@ mod mload_tests {
 
         assert_eq!(
             [0, 1]
-                .map(|idx| &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|idx| builder
+                    .block
+                    .container
+                    .stack
+                    .get(step.bus_mapping_instance[idx].as_usize())
+                    .unwrap())
                 .map(|operation| (operation.rw(), operation.op())),
             [
                 (
@@ -110,8 +115,12 @@ mod mload_tests {
 
         assert_eq!(
             (2..34)
-                .map(|idx| &builder.block.container.memory
-                    [step.bus_mapping_instance[idx].as_usize()])
+                .map(|idx| builder
+                    .block
+                    .container
+                    .memory
+                    .get(step.bus_mapping_instance[idx].as_usize())
+                    .unwrap())
                 .map(|operation| (operation.rw(), operation.op().clone()))
                 .collect_vec(),
             Word::from(0x80)
@@ -91,7 +91,12 @@ mod mstore_tests {
 
         assert_eq!(
             [0, 1]
-                .map(|idx| &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|idx| builder
+                    .block
+                    .container
+                    .stack
+                    .get(step.bus_mapping_instance[idx].as_usize())
+                    .unwrap())
                 .map(|operation| (operation.rw(), operation.op())),
             [
                 (
@@ -107,8 +112,12 @@ mod mstore_tests {
 
         assert_eq!(
             (2..34)
-                .map(|idx| &builder.block.container.memory
-                    [step.bus_mapping_instance[idx].as_usize()])
+                .map(|idx| builder
+                    .block
+                    .container
+                    .memory
+                    .get(step.bus_mapping_instance[idx].as_usize())
+                    .unwrap())
                 .map(|operation| (operation.rw(), operation.op().clone()))
                 .collect_vec(),
             Word::from(0x1234)
@@ -151,7 +160,12 @@ mod mstore_tests {
 
         assert_eq!(
             [0, 1]
-                .map(|idx| &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|idx| builder
+                    .block
+                    .container
+                    .stack
+                    .get(step.bus_mapping_instance[idx].as_usize())
+                    .unwrap())
                 .map(|operation| (operation.rw(), operation.op())),
             [
                 (
@@ -165,7 +179,12 @@ mod mstore_tests {
             ]
         );
 
-        let memory_op = &builder.block.container.memory[step.bus_mapping_instance[2].as_usize()];
+        let memory_op = builder
+            .block
+            .container
+            .memory
+            .get(step.bus_mapping_instance[2].as_usize())
+            .unwrap();
         assert_eq!(
             (memory_op.rw(), memory_op.op()),
             (RW::WRITE, &MemoryOp::new(1, MemoryAddress(0x100), 0x34))
@@ -24,7 +24,7 @@ impl Opcode for Selfbalance {
                 field: CallContextField::CalleeAddress,
                 value: callee_address.to_word(),
             },
-        );
+        )?;
 
         // Account read for the balance of the callee_address
         state.push_op(
@@ -35,7 +35,7 @@ impl Opcode for Selfbalance {
                 value: self_balance,
                 value_prev: self_balance,
             },
-        );
+        )?;
 
         // Stack write of self_balance
         state.push_stack_op(
@@ -115,8 +115,12 @@ mod selfbalance_tests {
         );
         assert_eq!(
             {
-                let operation =
-                    &builder.block.container.stack[step.bus_mapping_instance[2].as_usize()];
+                let operation = builder
+                    .block
+                    .container
+                    .stack
+                    .get(step.bus_mapping_instance[2].as_usize())
+                    .unwrap();
                 (operation.rw(), operation.op())
             },
             (
@@ -38,7 +38,7 @@ impl Opcode for Sload {
                 state.tx_ctx.id(),
                 storage_value_read, // TODO: committed_value
             ),
-        );
+        )?;
 
         // First stack write
         state.push_stack_op(RW::WRITE, stack_position, storage_value_read)?;
@@ -88,7 +88,12 @@ mod sload_tests {
 
         assert_eq!(
             [0, 2]
-                .map(|idx| &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|idx| builder
+                    .block
+                    .container
+                    .stack
+                    .get(step.bus_mapping_instance[idx].as_usize())
+                    .unwrap())
                 .map(|operation| (operation.rw(), operation.op())),
             [
                 (
@@ -76,7 +76,12 @@ mod stackonlyop_tests {
         assert_eq!(
             (0..N_POP)
                 .map(|idx| {
-                    &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()]
+                    builder
+                        .block
+                        .container
+                        .stack
+                        .get(step.bus_mapping_instance[idx].as_usize())
+                        .unwrap()
                 })
                 .map(|operation| (operation.rw(), operation.op().clone()))
                 .collect_vec(),
@@ -85,8 +90,12 @@ mod stackonlyop_tests {
         assert_eq!(
             (0..N_PUSH)
                 .map(|idx| {
-                    &builder.block.container.stack
-                        [step.bus_mapping_instance[N_POP + idx].as_usize()]
+                    builder
+                        .block
+                        .container
+                        .stack
+                        .get(step.bus_mapping_instance[N_POP + idx].as_usize())
+                        .unwrap()
                 })
                 .map(|operation| (operation.rw(), operation.op().clone()))
                 .collect_vec(),
@@ -160,4 +169,35 @@ mod stackonlyop_tests {
             vec![StackOp::new(1, StackAddress(1023), Word::from(0x79bdf))],
         );
     }
+
+    #[test]
+    fn basefee_opcode_impl() {
+        // The mock block is a London block with a zero base fee
+        stack_only_opcode_impl::<0, 1>(
+            OpcodeId::BASEFEE,
+            bytecode! {
+                BASEFEE
+                STOP
+            },
+            vec![],
+            vec![StackOp::new(1, StackAddress(1023), Word::zero())],
+        );
+    }
+
+    #[test]
+    fn chainid_opcode_impl() {
+        stack_only_opcode_impl::<0, 1>(
+            OpcodeId::CHAINID,
+            bytecode! {
+                CHAINID
+                STOP
+            },
+            vec![],
+            vec![StackOp::new(
+                1,
+                StackAddress(1023),
+                Word::from(mock::MOCK_CHAIN_ID),
+            )],
+        );
+    }
 }
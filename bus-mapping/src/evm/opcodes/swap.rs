@@ -81,8 +81,12 @@ mod swap_tests {
 
             assert_eq!(
                 [0, 1, 2, 3]
-                    .map(|idx| &builder.block.container.stack
-                        [step.bus_mapping_instance[idx].as_usize()])
+                    .map(|idx| builder
+                        .block
+                        .container
+                        .stack
+                        .get(step.bus_mapping_instance[idx].as_usize())
+                        .unwrap())
                     .map(|operation| (operation.rw(), operation.op())),
                 [
                     (RW::READ, &StackOp::new(1, b_pos, b_val)),
@@ -1,9 +1,10 @@
 //! This module contains the logic for parsing and interacting with EVM
 //! execution traces.
 use crate::operation::Target;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 /// The target and index of an `Operation` in the context of an
 /// `ExecutionTrace`.
 pub struct OperationRef(pub Target, pub usize);
@@ -22,6 +23,8 @@ impl fmt::Debug for OperationRef {
                 Target::Account => "Account",
                 Target::AccountDestructed => "AccountDestructed",
                 Target::CallContext => "CallContext",
+                Target::TxLog => "TxLog",
+                Target::TxReceipt => "TxReceipt",
             },
             self.1
         ))
@@ -42,6 +45,8 @@ impl From<(Target, usize)> for OperationRef {
             Target::Account => Self(Target::Account, op_ref_data.1),
             Target::AccountDestructed => Self(Target::AccountDestructed, op_ref_data.1),
             Target::CallContext => Self(Target::CallContext, op_ref_data.1),
+            Target::TxLog => Self(Target::TxLog, op_ref_data.1),
+            Target::TxReceipt => Self(Target::TxReceipt, op_ref_data.1),
         }
     }
 }
@@ -51,10 +51,10 @@
 //! use bus_mapping::Error;
 //! use bus_mapping::state_db::{self, StateDB, CodeDB};
 //! use eth_types::{
-//!     self, Address, Word, Hash, U64, GethExecTrace, GethExecStep
+//!     self, Address, ChainConfig, Word, Hash, U64, GethExecTrace, GethExecStep
 //! };
 //! use eth_types::evm_types::Gas;
-//! use bus_mapping::circuit_input_builder::{Block, CircuitInputBuilder};
+//! use bus_mapping::circuit_input_builder::{Block, CircuitInputBuilder, CircuitsParams};
 //! use pairing::arithmetic::FieldExt;
 //!
 //! let input_trace = r#"
@@ -116,7 +116,13 @@
 //! let mut builder = CircuitInputBuilder::new(
 //!     sdb,
 //!     CodeDB::new(),
-//!     Block::new(0.into(), Vec::new(), &eth_block).unwrap(),
+//!     Block::new(
+//!         ChainConfig::new(0.into()),
+//!         Vec::new(),
+//!         &eth_block,
+//!         CircuitsParams::default(),
+//!     )
+//!     .unwrap(),
 //! );
 //!
 //! let geth_steps: Vec<GethExecStep> = serde_json::from_str(input_trace).unwrap();
@@ -129,7 +135,7 @@
 //! builder.handle_block(&eth_block, &[geth_trace]).unwrap();
 //!
 //! // Get an ordered vector with all of the Stack operations of this trace.
-//! let stack_ops = builder.block.container.sorted_stack();
+//! let stack_ops = builder.block.container.sorted_stack().unwrap();
 //!
 //! // You can also iterate over the steps of the trace and witness the EVM Proof.
 //! builder.block.txs()[0].steps().iter();
@@ -1,10 +1,10 @@
 //! Mock types and functions to generate mock data useful for tests
 
 use crate::{
-    circuit_input_builder::{Block, CircuitInputBuilder},
+    circuit_input_builder::{Block, CircuitInputBuilder, CircuitsParams},
     state_db::{self, CodeDB, StateDB},
 };
-use eth_types::{geth_types::GethData, Word};
+use eth_types::{geth_types::GethData, ChainConfig, Word};
 
 /// BlockData is a type that contains all the information from a block required
 /// to build the circuit inputs.
@@ -14,8 +14,8 @@ pub struct BlockData {
     pub sdb: StateDB,
     /// CodeDB
     pub code_db: CodeDB,
-    /// chain id
-    pub chain_id: Word,
+    /// chain id, hardfork and EIP flags of the chain
+    pub chain_config: ChainConfig,
     /// history hashes contains most recent 256 block hashes in history, where
     /// the lastest one is at history_hashes[history_hashes.len() - 1].
     pub history_hashes: Vec<Word>,
@@ -23,6 +23,8 @@ pub struct BlockData {
     pub eth_block: eth_types::Block<eth_types::Transaction>,
     /// Execution Trace from geth
     pub geth_traces: Vec<eth_types::GethExecTrace>,
+    /// Capacities of the circuits the block is proven with
+    pub circuits_params: CircuitsParams,
 }
 
 impl BlockData {
@@ -32,12 +34,28 @@ impl BlockData {
         CircuitInputBuilder::new(
             self.sdb.clone(),
             self.code_db.clone(),
-            Block::new(self.chain_id, self.history_hashes.clone(), &self.eth_block).unwrap(),
+            Block::new(
+                self.chain_config,
+                self.history_hashes.clone(),
+                &self.eth_block,
+                self.circuits_params,
+            )
+            .unwrap(),
         )
     }
 
-    /// Create a new block from the given Geth data.
+    /// Create a new block from the given Geth data, proven with circuits of
+    /// the default capacities.
     pub fn new_from_geth_data(geth_data: GethData) -> Self {
+        Self::new_from_geth_data_with_params(geth_data, CircuitsParams::default())
+    }
+
+    /// Create a new block from the given Geth data, proven with circuits of
+    /// capacities `circuits_params`.
+    pub fn new_from_geth_data_with_params(
+        geth_data: GethData,
+        circuits_params: CircuitsParams,
+    ) -> Self {
         let mut sdb = StateDB::new();
         let mut code_db = CodeDB::new();
 
@@ -56,7 +74,7 @@ impl BlockData {
                 state_db::Account {
                     nonce: account.nonce,
                     balance: account.balance,
-                    storage: account.storage,
+                    storage: account.storage.into_iter().collect(),
                     code_hash,
                 },
             );
@@ -65,10 +83,11 @@ impl BlockData {
         Self {
             sdb,
             code_db,
-            chain_id: geth_data.chain_id,
+            chain_config: ChainConfig::new(geth_data.chain_id),
             history_hashes: geth_data.history_hashes,
             eth_block: geth_data.eth_block,
             geth_traces: geth_data.geth_traces,
+            circuits_params,
         }
     }
 }
@@ -1,9 +1,16 @@
 use super::{
+    store::{OperationGroup, OperationStore, SortedOperations},
     AccountDestructedOp, AccountOp, CallContextOp, MemoryOp, Op, OpEnum, Operation, StackOp,
-    StorageOp, Target, TxAccessListAccountOp, TxAccessListAccountStorageOp, TxRefundOp,
+    StorageOp, Target, TxAccessListAccountOp, TxAccessListAccountStorageOp, TxLogOp, TxReceiptOp,
+    TxRefundOp,
 };
 use crate::exec_trace::OperationRef;
-use itertools::Itertools;
+use eth_types::{
+    evm_types::{MemoryAddress, StackAddress},
+    Address, Word,
+};
+use serde::{Deserialize, Serialize};
+use std::{io, path::PathBuf};
 
 /// The `OperationContainer` is meant to store all of the [`Operation`]s that an
 /// [`ExecStep`](crate::circuit_input_builder::ExecStep) performs during its
@@ -18,15 +25,24 @@ use itertools::Itertools;
 /// `Stack`, `Memory` or `Storage` operations ordered according to the criterias
 /// they have specified.
 /// That serves as a way to get an input with which is easy to work with in
-/// order to construct the State proof.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// order to construct the State proof.  These operations are kept grouped by
+/// key as they are inserted, so they are never sorted after the fact, and the
+/// accesses to a key are read with [`OperationContainer::memory_accesses`],
+/// [`OperationContainer::stack_accesses`] and
+/// [`OperationContainer::storage_accesses`].
+///
+/// The `Memory` and `Stack` operations, which are most of the operations of a
+/// large block, can be spilled to disk with [`OperationContainer::spill_to`]
+/// and streamed back sorted to build the State proof, see
+/// [`OperationStore`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OperationContainer {
     /// Operations of MemoryOp
-    pub memory: Vec<Operation<MemoryOp>>,
+    pub memory: OperationStore<MemoryOp>,
     /// Operations of StackOp
-    pub stack: Vec<Operation<StackOp>>,
+    pub stack: OperationStore<StackOp>,
     /// Operations of StorageOp
-    pub storage: Vec<Operation<StorageOp>>,
+    pub storage: OperationStore<StorageOp>,
     /// Operations of TxAccessListAccountOp
     pub tx_access_list_account: Vec<Operation<TxAccessListAccountOp>>,
     /// Operations of TxAccessListAccountStorageOp
@@ -39,6 +55,10 @@ pub struct OperationContainer {
     pub account_destructed: Vec<Operation<AccountDestructedOp>>,
     /// Operations of CallContextOp
     pub call_context: Vec<Operation<CallContextOp>>,
+    /// Operations of TxLogOp
+    pub tx_log: Vec<Operation<TxLogOp>>,
+    /// Operations of TxReceiptOp
+    pub tx_receipt: Vec<Operation<TxReceiptOp>>,
 }
 
 impl Default for OperationContainer {
@@ -52,42 +72,57 @@ impl OperationContainer {
     /// Generates a new instance of an `OperationContainer`.
     pub fn new() -> Self {
         Self {
-            memory: Vec::new(),
-            stack: Vec::new(),
-            storage: Vec::new(),
+            memory: OperationStore::default(),
+            stack: OperationStore::default(),
+            storage: OperationStore::default(),
             tx_access_list_account: Vec::new(),
             tx_access_list_account_storage: Vec::new(),
             tx_refund: Vec::new(),
             account: Vec::new(),
             account_destructed: Vec::new(),
             call_context: Vec::new(),
+            tx_log: Vec::new(),
+            tx_receipt: Vec::new(),
         }
     }
 
+    /// Makes the container spill its `Memory` and `Stack` operations to
+    /// `dir` by chunks of `max_in_memory` operations.  The spilled operations
+    /// can't be accessed by their [`OperationRef`] any more, they are
+    /// returned by [`OperationContainer::sorted_memory_stream`] and
+    /// [`OperationContainer::sorted_stack_stream`], or read back by index with
+    /// [`OperationStore::get_spilled`].
+    pub fn spill_to(&mut self, dir: impl Into<PathBuf>, max_in_memory: usize) -> io::Result<()> {
+        let dir = dir.into();
+        self.memory.spill_to(&dir, "memory", max_in_memory)?;
+        self.stack.spill_to(&dir, "stack", max_in_memory)
+    }
+
     /// Inserts an [`Operation`] into the  container returning a lightweight
     /// reference to it in the form of an [`OperationRef`] which points to the
     /// location of the inserted operation inside the corresponding container
-    /// vector.
-    pub fn insert<T: Op>(&mut self, op: Operation<T>) -> OperationRef {
+    /// vector.  Returns an error if spilling the operations of the container
+    /// to disk fails.
+    pub fn insert<T: Op>(&mut self, op: Operation<T>) -> io::Result<OperationRef> {
         let rwc = op.rwc();
         let rw = op.rw();
         let reversible = op.reversible();
-        match op.op.into_enum() {
+        let op_ref = match op.op.into_enum() {
             OpEnum::Memory(op) => {
-                self.memory.push(Operation::new(rwc, rw, op));
-                OperationRef::from((Target::Memory, self.memory.len() - 1))
+                let index = self.memory.push(Operation::new(rwc, rw, op))?;
+                OperationRef::from((Target::Memory, index))
             }
             OpEnum::Stack(op) => {
-                self.stack.push(Operation::new(rwc, rw, op));
-                OperationRef::from((Target::Stack, self.stack.len() - 1))
+                let index = self.stack.push(Operation::new(rwc, rw, op))?;
+                OperationRef::from((Target::Stack, index))
             }
             OpEnum::Storage(op) => {
-                self.storage.push(if reversible {
+                let index = self.storage.push(if reversible {
                     Operation::new_reversible(rwc, rw, op)
                 } else {
                     Operation::new(rwc, rw, op)
-                });
-                OperationRef::from((Target::Storage, self.storage.len() - 1))
+                })?;
+                OperationRef::from((Target::Storage, index))
             }
             OpEnum::TxAccessListAccount(op) => {
                 self.tx_access_list_account.push(if reversible {
@@ -139,25 +174,86 @@ impl OperationContainer {
                 self.call_context.push(Operation::new(rwc, rw, op));
                 OperationRef::from((Target::CallContext, self.call_context.len() - 1))
             }
-        }
+            OpEnum::TxLog(op) => {
+                self.tx_log.push(Operation::new(rwc, rw, op));
+                OperationRef::from((Target::TxLog, self.tx_log.len() - 1))
+            }
+            OpEnum::TxReceipt(op) => {
+                self.tx_receipt.push(Operation::new(rwc, rw, op));
+                OperationRef::from((Target::TxReceipt, self.tx_receipt.len() - 1))
+            }
+        };
+        Ok(op_ref)
     }
 
     /// Returns a sorted vector of all of the [`MemoryOp`]s contained inside of
-    /// the container.
-    pub fn sorted_memory(&self) -> Vec<Operation<MemoryOp>> {
-        self.memory.iter().sorted().cloned().collect()
+    /// the container, or an error if the spilled ones can't be read back.
+    pub fn sorted_memory(&self) -> io::Result<Vec<Operation<MemoryOp>>> {
+        self.sorted_memory_stream()?.collect()
     }
 
     /// Returns a sorted vector of all of the [`StackOp`]s contained inside of
-    /// the container.
-    pub fn sorted_stack(&self) -> Vec<Operation<StackOp>> {
-        self.stack.iter().sorted().cloned().collect()
+    /// the container, or an error if the spilled ones can't be read back.
+    pub fn sorted_stack(&self) -> io::Result<Vec<Operation<StackOp>>> {
+        self.sorted_stack_stream()?.collect()
+    }
+
+    /// Returns an iterator over all of the [`MemoryOp`]s contained inside of
+    /// the container in sorted order, which reads the spilled ones as it goes.
+    pub fn sorted_memory_stream(&self) -> io::Result<SortedOperations<MemoryOp>> {
+        self.memory.sorted()
+    }
+
+    /// Returns an iterator over all of the [`StackOp`]s contained inside of
+    /// the container in sorted order, which reads the spilled ones as it goes.
+    pub fn sorted_stack_stream(&self) -> io::Result<SortedOperations<StackOp>> {
+        self.stack.sorted()
     }
 
     /// Returns a sorted vector of all of the [`StorageOp`]s contained inside of
     /// the container.
     pub fn sorted_storage(&self) -> Vec<Operation<StorageOp>> {
-        self.storage.iter().sorted().cloned().collect()
+        self.storage.iter_sorted().cloned().collect()
+    }
+
+    /// Returns the [`MemoryOp`]s of the byte at `address` in the memory of the
+    /// call `call_id` in `rwc` order, unless there are none or they were
+    /// spilled to disk.
+    pub fn memory_accesses(
+        &self,
+        call_id: usize,
+        address: MemoryAddress,
+    ) -> Option<OperationGroup<'_, MemoryOp>> {
+        self.memory.group(&MemoryOp::new(call_id, address, 0))
+    }
+
+    /// Returns the [`StackOp`]s of the slot at `address` in the stack of the
+    /// call `call_id` in `rwc` order, unless there are none or they were
+    /// spilled to disk.
+    pub fn stack_accesses(
+        &self,
+        call_id: usize,
+        address: StackAddress,
+    ) -> Option<OperationGroup<'_, StackOp>> {
+        self.stack
+            .group(&StackOp::new(call_id, address, Word::zero()))
+    }
+
+    /// Returns the [`StorageOp`]s of the slot `key` in the storage of the
+    /// account `address` in `rwc` order, unless there are none.
+    pub fn storage_accesses(
+        &self,
+        address: Address,
+        key: Word,
+    ) -> Option<OperationGroup<'_, StorageOp>> {
+        self.storage.group(&StorageOp::new(
+            address,
+            key,
+            Word::zero(),
+            Word::zero(),
+            0,
+            Word::zero(),
+        ))
     }
 }
 
@@ -195,15 +291,37 @@ mod container_test {
                 Word::default(),
             ),
         );
-        let stack_ref = operation_container.insert(stack_operation.clone());
-        let memory_ref = operation_container.insert(memory_operation.clone());
-        let storage_ref = operation_container.insert(storage_operation.clone());
+        let stack_ref = operation_container.insert(stack_operation.clone()).unwrap();
+        let memory_ref = operation_container
+            .insert(memory_operation.clone())
+            .unwrap();
+        let storage_ref = operation_container
+            .insert(storage_operation.clone())
+            .unwrap();
 
-        assert_eq!(operation_container.sorted_stack()[0], stack_operation);
-        assert_eq!(operation_container.sorted_memory()[0], memory_operation);
+        assert_eq!(
+            operation_container.sorted_stack().unwrap()[0],
+            stack_operation
+        );
+        assert_eq!(
+            operation_container.sorted_memory().unwrap()[0],
+            memory_operation
+        );
         assert_eq!(operation_container.sorted_storage()[0], storage_operation);
         assert_eq!(stack_ref, OperationRef::from((Target::Stack, 0)));
         assert_eq!(memory_ref, OperationRef::from((Target::Memory, 0)));
         assert_eq!(storage_ref, OperationRef::from((Target::Storage, 0)));
+
+        let storage_accesses = operation_container
+            .storage_accesses(Address::zero(), Word::default())
+            .unwrap();
+        assert_eq!(storage_accesses.len(), 1);
+        assert_eq!(storage_accesses.first(), Some(&storage_operation));
+        assert!(operation_container
+            .stack_accesses(1, StackAddress(1023))
+            .is_some());
+        assert!(operation_container
+            .memory_accesses(2, MemoryAddress::from(1))
+            .is_none());
     }
 }
@@ -0,0 +1,543 @@
+use super::{Op, Operation};
+use serde::{de::DeserializeOwned, ser, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    ops::Range,
+    path::PathBuf,
+    vec,
+};
+
+/// Where an [`OperationStore`] spills its operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Spill {
+    // Directory of the chunk files
+    dir: PathBuf,
+    // Prefix of the names of the chunk files, which tells apart the stores
+    // spilling to the same directory
+    prefix: String,
+    // Number of operations kept in memory before they are spilled
+    max_in_memory: usize,
+    // Chunk files, each one holding sorted operations with their indices
+    chunks: Vec<PathBuf>,
+    // Number of operations in the chunk files
+    n_spilled: usize,
+}
+
+/// Append-only store of the [`Operation`]s of a kind, which keeps them in
+/// memory like a `Vec`, unless it's told to spill them to disk with
+/// [`OperationStore::spill_to`].
+///
+/// Once spilling, every `max_in_memory` operations are sorted and written to a
+/// chunk file, so that the memory used by the store is bounded.  The spilled
+/// operations can't be accessed by index any more, [`OperationStore::get`]
+/// returns an error for them, they are streamed back in order by
+/// [`OperationStore::sorted`], which merges the chunks, or read back by index
+/// with [`OperationStore::get_spilled`].  The chunk files belong to the
+/// caller, who removes the directory once done.
+///
+/// The operations in memory are grouped by key as they are pushed, the key
+/// being what their `Ord` compares, like the call and the address of a
+/// [`StackOp`](super::StackOp).  The indices of the operations of a key are a
+/// run of a single index of the store, sorted by `rwc`, so they are read in
+/// sorted order, or by group with [`OperationStore::group`], without sorting
+/// them, and the in-memory ones never have to be sorted again.
+#[derive(Debug, Clone)]
+pub struct OperationStore<T: Op> {
+    ops: Vec<Operation<T>>,
+    // Indices in `ops` of the operations, in runs of the operations of a key
+    indices: Vec<usize>,
+    // Run in `indices` of the operations of each key
+    groups: BTreeMap<T, Run>,
+    spill: Option<Spill>,
+}
+
+/// Run of the indices of the operations of a key in the index of an
+/// [`OperationStore`], in `rwc` order, followed by room for the next ones.
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    start: usize,
+    len: usize,
+    capacity: usize,
+}
+
+impl Run {
+    // Returns the range of the indices of the run in the index.
+    fn range(&self) -> Range<usize> {
+        self.start..self.start + self.len
+    }
+}
+
+impl<T: Op> Default for OperationStore<T> {
+    fn default() -> Self {
+        Self {
+            ops: Vec::new(),
+            indices: Vec::new(),
+            groups: BTreeMap::new(),
+            spill: None,
+        }
+    }
+}
+
+// The groups are left out, they only index the operations.
+impl<T: Op> PartialEq for OperationStore<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ops == other.ops && self.spill == other.spill
+    }
+}
+
+impl<T: Op> Eq for OperationStore<T> {}
+
+impl<T: Op> From<Vec<Operation<T>>> for OperationStore<T> {
+    fn from(ops: Vec<Operation<T>>) -> Self {
+        let mut store = Self {
+            ops,
+            ..Self::default()
+        };
+        for index in 0..store.ops.len() {
+            store.group_op(index);
+        }
+        store
+    }
+}
+
+impl<T: Op + Serialize + DeserializeOwned> OperationStore<T> {
+    /// Makes the store spill its operations to chunk files named after
+    /// `prefix` in `dir` once it holds `max_in_memory` operations in memory.
+    /// The operations already in memory are spilled if there are too many.
+    pub fn spill_to(
+        &mut self,
+        dir: impl Into<PathBuf>,
+        prefix: &str,
+        max_in_memory: usize,
+    ) -> io::Result<()> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        self.spill = Some(Spill {
+            dir,
+            prefix: prefix.to_string(),
+            max_in_memory: max_in_memory.max(1),
+            chunks: Vec::new(),
+            n_spilled: 0,
+        });
+        self.spill_if_full()
+    }
+
+    /// Appends `op` to the store, spilling the operations in memory if the
+    /// store is full, and returns its index.
+    pub fn push(&mut self, op: Operation<T>) -> io::Result<usize> {
+        self.ops.push(op);
+        self.group_op(self.ops.len() - 1);
+        let index = self.len() - 1;
+        self.spill_if_full()?;
+        Ok(index)
+    }
+
+    /// Returns an iterator over all the operations of the store, spilled or
+    /// not, in sorted order.
+    pub fn sorted(&self) -> io::Result<SortedOperations<T>> {
+        let in_memory = self.iter_sorted().cloned().collect::<Vec<_>>();
+        let mut sources = vec![Source::Memory(in_memory.into_iter())];
+        for path in self.spill.iter().flat_map(|spill| spill.chunks.iter()) {
+            let reader = BufReader::new(File::open(path)?);
+            sources.push(Source::Chunk(Box::new(
+                serde_json::Deserializer::from_reader(reader).into_iter(),
+            )));
+        }
+
+        let mut heap = BinaryHeap::new();
+        for (index, source) in sources.iter_mut().enumerate() {
+            if let Some(op) = source.next().transpose()? {
+                heap.push(Reverse((op, index)));
+            }
+        }
+        Ok(SortedOperations { sources, heap })
+    }
+
+    /// Returns the spilled operations of the store whose index satisfies
+    /// `keep`, read back from their chunk files.
+    pub fn get_spilled(
+        &self,
+        mut keep: impl FnMut(usize) -> bool,
+    ) -> io::Result<HashMap<usize, Operation<T>>> {
+        let mut ops = HashMap::new();
+        for path in self.spill.iter().flat_map(|spill| spill.chunks.iter()) {
+            let reader = BufReader::new(File::open(path)?);
+            for entry in serde_json::Deserializer::from_reader(reader).into_iter::<ChunkEntry<T>>()
+            {
+                let (index, op) = entry?;
+                if keep(index) {
+                    ops.insert(index, op);
+                }
+            }
+        }
+        Ok(ops)
+    }
+
+    fn spill_if_full(&mut self) -> io::Result<()> {
+        let spill = match &mut self.spill {
+            Some(spill) if self.ops.len() >= spill.max_in_memory => spill,
+            _ => return Ok(()),
+        };
+        let path = spill
+            .dir
+            .join(format!("{}-{}.jsonl", spill.prefix, spill.chunks.len()));
+        let mut writer = BufWriter::new(File::create(&path)?);
+        for run in self.groups.values() {
+            for index in &self.indices[run.range()] {
+                serde_json::to_writer(&mut writer, &(spill.n_spilled + index, &self.ops[*index]))?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        writer.flush()?;
+        spill.chunks.push(path);
+        spill.n_spilled += self.ops.len();
+        self.ops.clear();
+        self.indices.clear();
+        self.groups.clear();
+        Ok(())
+    }
+}
+
+impl<T: Op> OperationStore<T> {
+    /// Returns the number of operations of the store, spilled or not.
+    pub fn len(&self) -> usize {
+        self.n_spilled() + self.ops.len()
+    }
+
+    /// Returns true if the store has no operations.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if some operations of the store were spilled to disk.
+    pub fn is_spilled(&self) -> bool {
+        self.n_spilled() > 0
+    }
+
+    /// Returns the operation of index `index`, or an error if it was spilled
+    /// to disk, see [`OperationStore::get_spilled`], or if there is no such
+    /// operation.
+    pub fn get(&self, index: usize) -> io::Result<&Operation<T>> {
+        let index = self.memory_index(index)?;
+        Ok(&self.ops[index])
+    }
+
+    /// Returns the operation of index `index` mutably, or an error if it was
+    /// spilled to disk or if there is no such operation.  Its key mustn't be
+    /// changed, as the operations are grouped by it.
+    pub fn get_mut(&mut self, index: usize) -> io::Result<&mut Operation<T>> {
+        let index = self.memory_index(index)?;
+        Ok(&mut self.ops[index])
+    }
+
+    /// Returns an iterator over the operations of the store which are in
+    /// memory, in the order they were pushed.
+    pub fn iter(&self) -> std::slice::Iter<'_, Operation<T>> {
+        self.ops.iter()
+    }
+
+    /// Returns a mutable iterator over the operations of the store which are
+    /// in memory, in the order they were pushed.  Their keys mustn't be
+    /// changed, as they are grouped by them.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Operation<T>> {
+        self.ops.iter_mut()
+    }
+
+    /// Returns an iterator over the operations of the store which are in
+    /// memory, in sorted order.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = &Operation<T>> {
+        self.groups().flat_map(|group| group.iter())
+    }
+
+    /// Returns the groups of the operations of the store which are in memory
+    /// with the same key, in the order of their keys.
+    pub fn groups(&self) -> impl Iterator<Item = OperationGroup<'_, T>> {
+        self.groups.values().map(move |run| OperationGroup {
+            ops: &self.ops,
+            indices: &self.indices[run.range()],
+        })
+    }
+
+    /// Returns the group of the operations of the store which are in memory
+    /// with the key of `op`, if there are any.
+    pub fn group(&self, op: &T) -> Option<OperationGroup<'_, T>> {
+        self.groups.get(op).map(|run| OperationGroup {
+            ops: &self.ops,
+            indices: &self.indices[run.range()],
+        })
+    }
+
+    // Adds the operation at `index` in memory to the run of its key, after the
+    // operations of the run with a lower or equal `rwc`, which are all of them
+    // unless the operations aren't pushed in `rwc` order.  A full run is moved
+    // to the end of the index with twice its capacity, so the index holds at
+    // most 4 indices per operation, the runs and the room left by the moved
+    // ones, without allocating per key.
+    fn group_op(&mut self, index: usize) {
+        let ops = &self.ops;
+        let op = &ops[index];
+        let indices = &mut self.indices;
+        let run = self.groups.entry(op.op().clone()).or_insert(Run {
+            start: indices.len(),
+            len: 0,
+            capacity: 0,
+        });
+        if run.len == run.capacity {
+            let start = indices.len();
+            indices.extend_from_within(run.range());
+            run.start = start;
+            run.capacity = (2 * run.capacity).max(1);
+            indices.resize(start + run.capacity, 0);
+        }
+
+        let run_indices = &mut indices[run.start..run.start + run.len + 1];
+        let position =
+            run_indices[..run.len].partition_point(|other| ops[*other].rwc() <= op.rwc());
+        run_indices.copy_within(position..run.len, position + 1);
+        run_indices[position] = index;
+        run.len += 1;
+    }
+
+    fn n_spilled(&self) -> usize {
+        self.spill.as_ref().map_or(0, |spill| spill.n_spilled)
+    }
+
+    // Returns the index in memory of the operation of index `index`.
+    fn memory_index(&self, index: usize) -> io::Result<usize> {
+        if index >= self.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no operation {} in a store of {}", index, self.len()),
+            ));
+        }
+        index.checked_sub(self.n_spilled()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("operation {} was spilled to disk", index),
+            )
+        })
+    }
+}
+
+impl<'a, T: Op> IntoIterator for &'a OperationStore<T> {
+    type Item = &'a Operation<T>;
+    type IntoIter = std::slice::Iter<'a, Operation<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// A store is serialized like the `Vec` of its operations, which only works
+// while none were spilled.
+impl<T: Op + Serialize> Serialize for OperationStore<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.is_spilled() {
+            return Err(<S::Error as ser::Error>::custom(
+                "cannot serialize operations spilled to disk",
+            ));
+        }
+        self.ops.serialize(serializer)
+    }
+}
+
+impl<'de, T: Op + Deserialize<'de>> Deserialize<'de> for OperationStore<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// Operations of an [`OperationStore`] with the same key, like the accesses
+/// to a storage slot, in `rwc` order.
+#[derive(Debug, Clone)]
+pub struct OperationGroup<'a, T: Op> {
+    ops: &'a [Operation<T>],
+    indices: &'a [usize],
+}
+
+impl<'a, T: Op> OperationGroup<'a, T> {
+    /// Returns the number of operations of the group.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns true if the group has no operations, which never happens for
+    /// the groups of a store.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Returns the `n`th operation of the group.
+    pub fn get(&self, n: usize) -> Option<&'a Operation<T>> {
+        self.indices.get(n).map(|index| &self.ops[*index])
+    }
+
+    /// Returns the first operation of the group.
+    pub fn first(&self) -> Option<&'a Operation<T>> {
+        self.get(0)
+    }
+
+    /// Returns the last operation of the group.
+    pub fn last(&self) -> Option<&'a Operation<T>> {
+        self.len().checked_sub(1).and_then(|n| self.get(n))
+    }
+
+    /// Returns an iterator over the operations of the group, in `rwc` order.
+    pub fn iter(&self) -> impl Iterator<Item = &'a Operation<T>> + 'a {
+        let ops = self.ops;
+        self.indices.iter().map(move |index| &ops[*index])
+    }
+}
+
+// Operation of a chunk file, with its index in the store.
+type ChunkEntry<T> = (usize, Operation<T>);
+
+type ChunkIter<T> =
+    serde_json::StreamDeserializer<'static, serde_json::de::IoRead<BufReader<File>>, ChunkEntry<T>>;
+
+enum Source<T: Op> {
+    Memory(vec::IntoIter<Operation<T>>),
+    Chunk(Box<ChunkIter<T>>),
+}
+
+impl<T: Op + DeserializeOwned> Iterator for Source<T> {
+    type Item = io::Result<Operation<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Memory(ops) => ops.next().map(Ok),
+            Self::Chunk(ops) => ops
+                .next()
+                .map(|entry| entry.map(|(_, op)| op).map_err(io::Error::from)),
+        }
+    }
+}
+
+/// Iterator over the sorted operations of an [`OperationStore`], which reads
+/// the spilled ones from their chunk files as it goes.
+pub struct SortedOperations<T: Op> {
+    sources: Vec<Source<T>>,
+    heap: BinaryHeap<Reverse<(Operation<T>, usize)>>,
+}
+
+impl<T: Op + DeserializeOwned> Iterator for SortedOperations<T> {
+    type Item = io::Result<Operation<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((op, index)) = self.heap.pop()?;
+        match self.sources[index].next().transpose() {
+            Ok(Some(next)) => self.heap.push(Reverse((next, index))),
+            Ok(None) => {}
+            Err(err) => return Some(Err(err)),
+        }
+        Some(Ok(op))
+    }
+}
+
+#[cfg(test)]
+mod store_test {
+    use super::OperationStore;
+    use crate::operation::{Operation, RWCounter, StackOp, RW};
+    use eth_types::{evm_types::StackAddress, Word};
+    use std::{env, fs, process};
+
+    fn stack_op(rwc: usize, address: usize) -> Operation<StackOp> {
+        Operation::new(
+            RWCounter(rwc),
+            RW::WRITE,
+            StackOp::new(1, StackAddress(address), Word::from(rwc)),
+        )
+    }
+
+    #[test]
+    fn spilled_operations_are_streamed_sorted() {
+        let dir = env::temp_dir().join(format!("bus-mapping-store-{}", process::id()));
+        let ops = (0..10)
+            .map(|rwc| stack_op(rwc, 1023 - rwc % 4))
+            .collect::<Vec<_>>();
+
+        let mut store = OperationStore::default();
+        store.push(ops[0].clone()).unwrap();
+        store.spill_to(&dir, "stack", 3).unwrap();
+        for (index, op) in ops.iter().enumerate().skip(1) {
+            assert_eq!(store.push(op.clone()).unwrap(), index);
+        }
+        assert_eq!(store.len(), ops.len());
+        assert!(store.is_spilled());
+        // The last operation is still in memory, the first one was spilled.
+        assert_eq!(store.get(9).unwrap(), &ops[9]);
+        assert!(store.get(0).is_err());
+        assert!(store.get(10).is_err());
+        assert!(serde_json::to_string(&store).is_err());
+
+        let mut sorted = ops.clone();
+        sorted.sort();
+        let streamed = store
+            .sorted()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(streamed, sorted);
+
+        let spilled = store.get_spilled(|index| index % 2 == 0).unwrap();
+        assert_eq!(spilled.len(), 5);
+        assert_eq!(spilled[&0], ops[0]);
+        assert_eq!(spilled[&6], ops[6]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn operations_are_grouped_by_key() {
+        // The operation of rwc 3 is pushed after the one of rwc 4.
+        let ops = [
+            (0, 1023),
+            (1, 1022),
+            (2, 1023),
+            (4, 1023),
+            (3, 1023),
+            (5, 1021),
+        ]
+        .into_iter()
+        .map(|(rwc, address)| stack_op(rwc, address))
+        .collect::<Vec<_>>();
+        let mut store = OperationStore::default();
+        for op in ops.iter() {
+            store.push(op.clone()).unwrap();
+        }
+
+        let mut sorted = ops.clone();
+        sorted.sort();
+        assert_eq!(store.iter_sorted().cloned().collect::<Vec<_>>(), sorted);
+        assert_eq!(store.groups().count(), 3);
+        assert_eq!(
+            store.groups().map(|group| group.len()).sum::<usize>(),
+            ops.len()
+        );
+
+        let group = store.group(ops[0].op()).unwrap();
+        assert_eq!(
+            group.iter().map(|op| op.rwc().0).collect::<Vec<_>>(),
+            vec![0, 2, 3, 4]
+        );
+        assert_eq!(group.last(), Some(&stack_op(4, 1023)));
+        assert!(store.group(stack_op(0, 1000).op()).is_none());
+        assert_eq!(OperationStore::from(ops.clone()), store);
+
+        // The runs of the keys which outgrow their room are moved without
+        // growing the index past 4 indices per operation.
+        for rwc in 6..40 {
+            store.push(stack_op(rwc, 1023 - rwc % 2)).unwrap();
+        }
+        assert!(store.indices.len() <= 4 * store.len());
+        let group = store.group(ops[0].op()).unwrap();
+        assert_eq!(group.len(), 4 + 17);
+        assert!(group
+            .iter()
+            .zip(group.iter().skip(1))
+            .all(|(op, next)| op.rwc() < next.rwc()));
+        assert_eq!(store.iter_sorted().count(), store.len());
+    }
+}
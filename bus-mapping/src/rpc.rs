@@ -1,14 +1,16 @@
 //! Module which contains all the RPC calls that are needed at any point to
 //! query a Geth node in order to get a Block, Tx or Trace info.
 
+pub mod client;
+
 use crate::Error;
+pub use client::{RpcClient, RpcClientConfig, RpcClientError};
 use eth_types::{
-    Address, Block, Bytes, EIP1186ProofResponse, GethExecTrace, Hash, ResultGethExecTraces,
-    Transaction, Word, U64,
+    Address, Block, Bytes, EIP1186ProofResponse, GethExecTrace, GethLoggerConfig, Hash,
+    ResultGethExecTraces, Transaction, Word, U64,
 };
 pub use ethers_core::types::BlockNumber;
 use ethers_providers::JsonRpcClient;
-use serde::Serialize;
 
 /// Serialize a type.
 ///
@@ -19,34 +21,6 @@ pub fn serialize<T: serde::Serialize>(t: &T) -> serde_json::Value {
     serde_json::to_value(t).expect("Types never fail to serialize.")
 }
 
-#[derive(Serialize)]
-#[doc(hidden)]
-pub(crate) struct GethLoggerConfig {
-    /// enable memory capture
-    #[serde(rename = "EnableMemory")]
-    enable_memory: bool,
-    /// disable stack capture
-    #[serde(rename = "DisableStack")]
-    disable_stack: bool,
-    /// disable storage capture
-    #[serde(rename = "DisableStorage")]
-    disable_storage: bool,
-    /// enable return data capture
-    #[serde(rename = "EnableReturnData")]
-    enable_return_data: bool,
-}
-
-impl Default for GethLoggerConfig {
-    fn default() -> Self {
-        Self {
-            enable_memory: true,
-            disable_stack: false,
-            disable_storage: false,
-            enable_return_data: true,
-        }
-    }
-}
-
 /// Placeholder structure designed to contain the methods that the BusMapping
 /// needs in order to enable Geth queries.
 pub struct GethClient<P: JsonRpcClient>(pub P);
@@ -105,8 +79,20 @@ impl<P: JsonRpcClient> GethClient<P> {
     /// [`Vec<GethExecTrace>`] with each GethTrace corresponding to 1
     /// transaction of the block.
     pub async fn trace_block_by_hash(&self, hash: Hash) -> Result<Vec<GethExecTrace>, Error> {
+        self.trace_block_by_hash_with_config(hash, &GethLoggerConfig::default())
+            .await
+    }
+
+    /// Calls `debug_traceBlockByHash` via JSON-RPC with the capture options
+    /// `config`, returning a [`Vec<GethExecTrace>`] with each GethTrace
+    /// corresponding to 1 transaction of the block.
+    pub async fn trace_block_by_hash_with_config(
+        &self,
+        hash: Hash,
+        config: &GethLoggerConfig,
+    ) -> Result<Vec<GethExecTrace>, Error> {
         let hash = serialize(&hash);
-        let cfg = serialize(&GethLoggerConfig::default());
+        let cfg = serialize(config);
         let resp: ResultGethExecTraces = self
             .0
             .request("debug_traceBlockByHash", [hash, cfg])
@@ -121,9 +107,21 @@ impl<P: JsonRpcClient> GethClient<P> {
     pub async fn trace_block_by_number(
         &self,
         block_num: BlockNumber,
+    ) -> Result<Vec<GethExecTrace>, Error> {
+        self.trace_block_by_number_with_config(block_num, &GethLoggerConfig::default())
+            .await
+    }
+
+    /// Calls `debug_traceBlockByNumber` via JSON-RPC with the capture options
+    /// `config`, returning a [`Vec<GethExecTrace>`] with each GethTrace
+    /// corresponding to 1 transaction of the block.
+    pub async fn trace_block_by_number_with_config(
+        &self,
+        block_num: BlockNumber,
+        config: &GethLoggerConfig,
     ) -> Result<Vec<GethExecTrace>, Error> {
         let num = serialize(&block_num);
-        let cfg = serialize(&GethLoggerConfig::default());
+        let cfg = serialize(config);
         let resp: ResultGethExecTraces = self
             .0
             .request("debug_traceBlockByNumber", [num, cfg])
@@ -185,4 +183,74 @@ impl<P: JsonRpcClient> GethClient<P> {
     }
 }
 
+impl GethClient<RpcClient> {
+    /// Calls `eth_getProof` via JSON-RPC in batches for each account and its
+    /// storage keys of `accounts`, returning the [`EIP1186ProofResponse`]s in
+    /// the order of `accounts`.
+    pub async fn get_proofs(
+        &self,
+        accounts: Vec<(Address, Vec<Word>)>,
+        block_num: BlockNumber,
+    ) -> Result<Vec<EIP1186ProofResponse>, Error> {
+        let num = serialize(&block_num);
+        let params = accounts
+            .iter()
+            .map(|(account, keys)| [serialize(account), serialize(keys), num.clone()])
+            .collect();
+        self.0
+            .request_batch("eth_getProof", params)
+            .await
+            .map_err(|e| Error::JSONRpcError(e.into()))
+    }
+
+    /// Calls `eth_getCode` via JSON-RPC in batches for each of
+    /// `contract_addresses`, returning the codes in the order of the
+    /// addresses.
+    pub async fn get_codes(
+        &self,
+        contract_addresses: &[Address],
+        block_num: BlockNumber,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let num = serialize(&block_num);
+        let params = contract_addresses
+            .iter()
+            .map(|address| [serialize(address), num.clone()])
+            .collect();
+        let resp: Vec<Bytes> = self
+            .0
+            .request_batch("eth_getCode", params)
+            .await
+            .map_err(|e| Error::JSONRpcError(e.into()))?;
+        Ok(resp.into_iter().map(|code| code.to_vec()).collect())
+    }
+
+    /// Calls `debug_traceTransaction` via JSON-RPC in batches for each of
+    /// `hashes`, returning the [`GethExecTrace`]s in the order of the hashes.
+    pub async fn trace_transactions(&self, hashes: &[Hash]) -> Result<Vec<GethExecTrace>, Error> {
+        self.trace_transactions_with_config(hashes, &GethLoggerConfig::default())
+            .await
+    }
+
+    /// Calls `debug_traceTransaction` via JSON-RPC in batches for each of
+    /// `hashes` with the capture options `config`, returning the
+    /// [`GethExecTrace`]s in the order of the hashes.  Unlike
+    /// `debug_traceBlockByNumber`, no single request has to trace the whole
+    /// block, which public endpoints often time out on.
+    pub async fn trace_transactions_with_config(
+        &self,
+        hashes: &[Hash],
+        config: &GethLoggerConfig,
+    ) -> Result<Vec<GethExecTrace>, Error> {
+        let cfg = serialize(config);
+        let params = hashes
+            .iter()
+            .map(|hash| [serialize(hash), cfg.clone()])
+            .collect();
+        self.0
+            .request_batch("debug_traceTransaction", params)
+            .await
+            .map_err(|e| Error::JSONRpcError(e.into()))
+    }
+}
+
 // Integration tests found in `integration-tests/tests/rpc.rs`.
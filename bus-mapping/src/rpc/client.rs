@@ -0,0 +1,388 @@
+//! JSON-RPC client over HTTP suited to public endpoints, which batches the
+//! requests, retries the failed ones with an exponential backoff and limits
+//! the number of requests in flight.
+
+use async_trait::async_trait;
+use ethers_providers::{JsonRpcClient, ProviderError};
+use futures::future::try_join_all;
+use reqwest::{StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use tokio::sync::Semaphore;
+
+/// Error codes with which nodes answer requests over their rate limit.
+const RATE_LIMITED_CODES: [i64; 2] = [-32005, 429];
+
+/// Configuration of an [`RpcClient`].
+#[derive(Debug, Clone)]
+pub struct RpcClientConfig {
+    /// Maximum number of requests sent in a single batch
+    pub max_batch_size: usize,
+    /// Maximum number of HTTP requests in flight, single requests or batches
+    pub max_concurrent_requests: usize,
+    /// Number of retries of an HTTP request before giving up
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled at every retry
+    pub initial_backoff: Duration,
+    /// Maximum delay between two retries
+    pub max_backoff: Duration,
+}
+
+impl Default for RpcClientConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 100,
+            max_concurrent_requests: 4,
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RpcClientConfig {
+    /// Returns the delay before the retry `retry`, the first one being 0.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .checked_mul(2u32.saturating_pow(retry))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+/// Error of an [`RpcClient`].
+#[derive(Debug)]
+pub enum RpcClientError {
+    /// The HTTP request failed, after all the retries if it may succeed later.
+    Http(reqwest::Error),
+    /// The response isn't the JSON expected.
+    Json(serde_json::Error),
+    /// The node answered the request with an error.
+    Rpc {
+        /// Error code of the JSON-RPC error
+        code: i64,
+        /// Error message of the JSON-RPC error
+        message: String,
+    },
+    /// The response to the request of id `0` is missing from a batch.
+    MissingResponse(u64),
+}
+
+impl fmt::Display for RpcClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http(err) => write!(f, "HTTP request failed: {}", err),
+            Self::Json(err) => write!(f, "invalid JSON response: {}", err),
+            Self::Rpc { code, message } => write!(f, "JSON-RPC error {}: {}", code, message),
+            Self::MissingResponse(id) => write!(f, "missing response to request {}", id),
+        }
+    }
+}
+
+impl std::error::Error for RpcClientError {}
+
+impl From<reqwest::Error> for RpcClientError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Http(err)
+    }
+}
+
+impl From<serde_json::Error> for RpcClientError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<RpcClientError> for ProviderError {
+    fn from(err: RpcClientError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(err))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Request<'a, T> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    // Null in the error answering an invalid request.
+    id: Option<u64>,
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<JsonRpcError>,
+}
+
+impl Response {
+    fn into_result<R: DeserializeOwned>(self) -> Result<R, RpcClientError> {
+        match self.error {
+            Some(JsonRpcError { code, message }) => Err(RpcClientError::Rpc { code, message }),
+            None => Ok(serde_json::from_value(self.result.unwrap_or(Value::Null))?),
+        }
+    }
+}
+
+/// Returns whether the node rejected the request, or one of the requests of
+/// the batch, because of its rate limit.
+fn is_rate_limited(response: &Value) -> bool {
+    let code_of = |response: &Value| response.pointer("/error/code").and_then(Value::as_i64);
+    match response {
+        Value::Array(responses) => responses
+            .iter()
+            .any(|response| code_of(response).map_or(false, |c| RATE_LIMITED_CODES.contains(&c))),
+        response => code_of(response).map_or(false, |c| RATE_LIMITED_CODES.contains(&c)),
+    }
+}
+
+/// Returns whether the HTTP request failed in a way that may succeed later.
+fn is_transient(err: &reqwest::Error) -> bool {
+    err.is_timeout()
+        || err.is_connect()
+        || err.is_request()
+        || err.status().map_or(false, |status| {
+            status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+        })
+}
+
+/// Returns the results of the `n` requests of a batch whose first id is
+/// `first_id` in the order of the requests, the responses of a batch coming
+/// in any order.
+fn batch_results<R: DeserializeOwned>(
+    first_id: u64,
+    n: usize,
+    responses: Vec<Response>,
+) -> Result<Vec<R>, RpcClientError> {
+    let mut responses: HashMap<u64, Response> = responses
+        .into_iter()
+        .filter_map(|response| Some((response.id?, response)))
+        .collect();
+    (first_id..first_id + n as u64)
+        .map(|id| {
+            responses
+                .remove(&id)
+                .ok_or(RpcClientError::MissingResponse(id))?
+                .into_result()
+        })
+        .collect()
+}
+
+/// JSON-RPC client over HTTP, which retries the requests failing because of a
+/// timeout, a server error or the rate limit of the node with an exponential
+/// backoff, limits the number of requests in flight, and sends batches of
+/// requests with [`RpcClient::request_batch`].
+#[derive(Debug)]
+pub struct RpcClient {
+    http: reqwest::Client,
+    url: Url,
+    config: RpcClientConfig,
+    next_id: AtomicU64,
+    permits: Semaphore,
+}
+
+impl RpcClient {
+    /// Creates a client of the node at `url` with the default configuration.
+    pub fn new(url: Url) -> Self {
+        Self::new_with_config(url, RpcClientConfig::default())
+    }
+
+    /// Creates a client of the node at `url` with the configuration `config`.
+    pub fn new_with_config(url: Url, config: RpcClientConfig) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url,
+            permits: Semaphore::new(config.max_concurrent_requests.max(1)),
+            config,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Returns the configuration of the client.
+    pub fn config(&self) -> &RpcClientConfig {
+        &self.config
+    }
+
+    /// Posts `body` to the node once a request slot is free, retrying while
+    /// the failure is transient, and returns the JSON response.
+    async fn post<B: Serialize + Sync>(&self, body: &B) -> Result<Value, RpcClientError> {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("the semaphore is never closed");
+        let mut retry = 0;
+        loop {
+            let response = async {
+                self.http
+                    .post(self.url.clone())
+                    .json(body)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<Value>()
+                    .await
+            }
+            .await;
+            let retriable = match &response {
+                Ok(response) => is_rate_limited(response),
+                Err(err) => is_transient(err),
+            };
+            if !retriable || retry >= self.config.max_retries {
+                return Ok(response?);
+            }
+            let backoff = self.config.backoff(retry);
+            log::warn!(
+                "JSON-RPC request to {} failed, retrying in {:?}",
+                self.url,
+                backoff
+            );
+            tokio::time::sleep(backoff).await;
+            retry += 1;
+        }
+    }
+
+    /// Calls `method` once for each of `params`, in batches of at most
+    /// `max_batch_size` requests sent concurrently, and returns the results
+    /// in the order of `params`.
+    pub async fn request_batch<T, R>(
+        &self,
+        method: &str,
+        params: Vec<T>,
+    ) -> Result<Vec<R>, RpcClientError>
+    where
+        T: Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let batches = params
+            .chunks(self.config.max_batch_size.max(1))
+            .map(|chunk| self.send_batch(method, chunk));
+        let results = try_join_all(batches).await?;
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    async fn send_batch<T, R>(&self, method: &str, params: &[T]) -> Result<Vec<R>, RpcClientError>
+    where
+        T: Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let first_id = self
+            .next_id
+            .fetch_add(params.len() as u64, Ordering::Relaxed);
+        let requests: Vec<_> = params
+            .iter()
+            .zip(first_id..)
+            .map(|(params, id)| Request {
+                jsonrpc: "2.0",
+                id,
+                method,
+                params,
+            })
+            .collect();
+        let response = self.post(&requests).await?;
+        // A node answers an invalid batch with a single error.
+        if response.is_object() {
+            let response: Response = serde_json::from_value(response)?;
+            return Err(response
+                .into_result::<Value>()
+                .err()
+                .unwrap_or(RpcClientError::MissingResponse(first_id)));
+        }
+        batch_results(first_id, params.len(), serde_json::from_value(response)?)
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for RpcClient {
+    type Error = RpcClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = Request {
+            jsonrpc: "2.0",
+            id,
+            method,
+            params,
+        };
+        let response: Response = serde_json::from_value(self.post(&request).await?)?;
+        response.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let config = RpcClientConfig {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(3),
+            ..RpcClientConfig::default()
+        };
+        let backoffs: Vec<_> = (0..5).map(|retry| config.backoff(retry)).collect();
+        assert_eq!(
+            backoffs,
+            [500, 1000, 2000, 3000, 3000].map(Duration::from_millis)
+        );
+        assert_eq!(config.backoff(u32::MAX), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn batch_results_in_request_order() {
+        let responses: Vec<Response> = serde_json::from_value(json!([
+            { "jsonrpc": "2.0", "id": 12, "result": "0x3" },
+            { "jsonrpc": "2.0", "id": 10, "result": "0x1" },
+            { "jsonrpc": "2.0", "id": 11, "result": "0x2" },
+        ]))
+        .unwrap();
+        let results: Vec<String> = batch_results(10, 3, responses).unwrap();
+        assert_eq!(results, ["0x1", "0x2", "0x3"]);
+
+        let responses: Vec<Response> = serde_json::from_value(json!([
+            { "jsonrpc": "2.0", "id": 10, "result": "0x1" },
+            {
+                "jsonrpc": "2.0",
+                "id": 11,
+                "error": { "code": -32000, "message": "missing trie node" }
+            },
+        ]))
+        .unwrap();
+        assert!(matches!(
+            batch_results::<String>(10, 2, responses),
+            Err(RpcClientError::Rpc { code: -32000, .. })
+        ));
+    }
+
+    #[test]
+    fn rate_limited_responses() {
+        let limited = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": -32005, "message": "limit exceeded" }
+        });
+        let ok = json!({ "jsonrpc": "2.0", "id": 2, "result": "0x1" });
+        assert!(is_rate_limited(&limited));
+        assert!(!is_rate_limited(&ok));
+        assert!(is_rate_limited(&json!([ok.clone(), limited])));
+        assert!(!is_rate_limited(&json!([ok])));
+    }
+}
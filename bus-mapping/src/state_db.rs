@@ -1,10 +1,28 @@
 //! Implementation of an in-memory key-value database to represent the
 //! Ethereum State Trie.
-
-use eth_types::{Address, Hash, Word, H256, U256};
-use ethers_core::utils::keccak256;
+//!
+//! The accounts and their storage are held in ordered maps, so that iterating
+//! over them, and the witnesses and the snapshots built from them, don't
+//! depend on the seed of a hasher.
+//!
+//! The state before a block is loaded from a [`StateBackend`].  With the
+//! `disk-db` feature, the accounts and the code fetched for a block can be
+//! kept on disk across runs in a `DiskDB`.
+
+use crate::Error;
+use eth_types::{keccak256, Address, Hash, Word, H256, U256};
 use lazy_static::lazy_static;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+mod backend;
+#[cfg(feature = "disk-db")]
+mod disk;
+
+#[cfg(feature = "disk-db")]
+pub use backend::CachedDiskBackend;
+pub use backend::{load_state, MemoryBackend, StateBackend};
+#[cfg(feature = "disk-db")]
+pub use disk::DiskDB;
 
 lazy_static! {
     static ref ACCOUNT_ZERO: Account = Account::zero();
@@ -43,8 +61,8 @@ pub struct Account {
     pub nonce: Word,
     /// Balance
     pub balance: Word,
-    /// Storage key-value map
-    pub storage: HashMap<Word, Word>,
+    /// Storage key-value map, ordered by key
+    pub storage: BTreeMap<Word, Word>,
     /// Code hash
     pub code_hash: Hash,
 }
@@ -55,7 +73,7 @@ impl Account {
         Self {
             nonce: Word::zero(),
             balance: Word::zero(),
-            storage: HashMap::new(),
+            storage: BTreeMap::new(),
             code_hash: *CODE_HASH_ZERO,
         }
     }
@@ -72,7 +90,7 @@ impl Account {
 /// In-memory key-value database that represents the Ethereum State Trie.
 #[derive(Debug, Clone)]
 pub struct StateDB {
-    state: HashMap<Address, Account>,
+    state: BTreeMap<Address, Account>,
     // Fields with transaction lifespan, will be clear in `clear_access_list_and_refund`.
     access_list_account: HashSet<Address>,
     access_list_account_storage: HashSet<(Address, U256)>,
@@ -89,13 +107,19 @@ impl StateDB {
     /// Create an empty Self
     pub fn new() -> Self {
         Self {
-            state: HashMap::new(),
+            state: BTreeMap::new(),
             access_list_account: HashSet::new(),
             access_list_account_storage: HashSet::new(),
             refund: 0,
         }
     }
 
+    /// Returns an iterator over the addresses and the [`Account`]s of the
+    /// StateDB, in the order of the addresses.
+    pub fn iter(&self) -> impl Iterator<Item = (&Address, &Account)> {
+        self.state.iter()
+    }
+
     /// Set an [`Account`] at `addr` in the StateDB.
     pub fn set_account(&mut self, addr: &Address, acc: Account) {
         self.state.insert(*addr, acc);
@@ -165,9 +189,15 @@ impl StateDB {
         self.access_list_account.insert(addr)
     }
 
-    /// Remove `addr` from account access list.
-    pub fn remove_account_from_access_list(&mut self, addr: &Address) {
-        debug_assert!(self.access_list_account.remove(addr));
+    /// Remove `addr` from account access list.  Returns an error if it isn't
+    /// in the access list.
+    pub fn remove_account_from_access_list(&mut self, addr: &Address) -> Result<(), Error> {
+        if !self.access_list_account.remove(addr) {
+            return Err(Error::BrokenInvariant(
+                "removed account isn't in the access list",
+            ));
+        }
+        Ok(())
     }
 
     /// Add `(addr, key)` into account storage access list. Returns `true` if
@@ -176,9 +206,18 @@ impl StateDB {
         self.access_list_account_storage.insert((addr, key))
     }
 
-    /// Remove `(addr, key)` from account storage access list.
-    pub fn remove_account_storage_from_access_list(&mut self, pair: &(Address, Word)) {
-        debug_assert!(self.access_list_account_storage.remove(pair));
+    /// Remove `(addr, key)` from account storage access list.  Returns an
+    /// error if it isn't in the access list.
+    pub fn remove_account_storage_from_access_list(
+        &mut self,
+        pair: &(Address, Word),
+    ) -> Result<(), Error> {
+        if !self.access_list_account_storage.remove(pair) {
+            return Err(Error::BrokenInvariant(
+                "removed storage slot isn't in the access list",
+            ));
+        }
+        Ok(())
     }
 
     /// Retrieve refund.
@@ -254,4 +293,32 @@ mod statedb_tests {
         assert!(found);
         assert_eq!(value, &Word::from(102));
     }
+
+    #[test]
+    fn statedb_iter_ordered() {
+        let addrs = [
+            address!("0x00000000000000000000000000000000000000ff"),
+            address!("0x0000000000000000000000000000000000000001"),
+            address!("0x0000000000000000000000000000000000000010"),
+        ];
+        let mut statedb = StateDB::new();
+        for addr in addrs.iter() {
+            for key in [7u64, 3, 5] {
+                *statedb.get_storage_mut(addr, &Word::from(key)).1 = Word::one();
+            }
+        }
+
+        let mut sorted_addrs = addrs;
+        sorted_addrs.sort();
+        assert_eq!(
+            statedb.iter().map(|(addr, _)| *addr).collect::<Vec<_>>(),
+            sorted_addrs
+        );
+        for (_, acc) in statedb.iter() {
+            assert_eq!(
+                acc.storage.keys().copied().collect::<Vec<_>>(),
+                [3u64, 5, 7].map(Word::from)
+            );
+        }
+    }
 }
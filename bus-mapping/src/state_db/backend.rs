@@ -0,0 +1,306 @@
+//! Sources of the state before a block which the circuit inputs of the block
+//! are built from.
+//!
+//! A [`StateBackend`] answers the queries of the accounts, the storage slots
+//! and the code before a block, whether they are held in memory by the tests,
+//! fetched from a node by a prover following a chain, or replayed from a
+//! store on disk.  [`load_state`] loads the [`StateDB`] and the [`CodeDB`] of
+//! the accesses of a block from any of them, so that the
+//! [`CircuitInputBuilder`](crate::circuit_input_builder::CircuitInputBuilder)
+//! of the block is built the same way for all of them.
+
+use super::{Account, CodeDB, StateDB};
+use crate::{circuit_input_builder::AccessSet, rpc::GethClient, Error};
+use async_trait::async_trait;
+use eth_types::{Address, EIP1186ProofResponse, StorageProof, Word};
+use ethers_providers::JsonRpcClient;
+
+/// Source of the state before the blocks of a chain.
+#[async_trait]
+pub trait StateBackend: Send + Sync {
+    /// Returns the [`Account`] at `address` before the block `block_num`,
+    /// without its storage, which is a zero [`Account`] if it doesn't exist.
+    async fn get_account(&self, block_num: u64, address: Address) -> Result<Account, Error>;
+
+    /// Returns the value of the storage slot `key` of `address` before the
+    /// block `block_num`.
+    async fn get_storage(&self, block_num: u64, address: Address, key: Word)
+        -> Result<Word, Error>;
+
+    /// Returns the code of `address` before the block `block_num`.
+    async fn get_code(&self, block_num: u64, address: Address) -> Result<Vec<u8>, Error>;
+
+    /// Returns the account at `address` and its storage slots `keys` before
+    /// the block `block_num`, with their Merkle proofs if the backend has
+    /// them.
+    async fn get_proof(
+        &self,
+        block_num: u64,
+        address: Address,
+        keys: Vec<Word>,
+    ) -> Result<EIP1186ProofResponse, Error>;
+}
+
+/// Backend of the state of a single block held in memory, e.g. the one of a
+/// test, which is the state before any block number.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    /// Accounts and their storage
+    pub sdb: StateDB,
+    /// Code of the accounts
+    pub code_db: CodeDB,
+}
+
+impl MemoryBackend {
+    /// Returns the backend of the state of `sdb` and `code_db`.
+    pub fn new(sdb: StateDB, code_db: CodeDB) -> Self {
+        Self { sdb, code_db }
+    }
+}
+
+#[async_trait]
+impl StateBackend for MemoryBackend {
+    async fn get_account(&self, _block_num: u64, address: Address) -> Result<Account, Error> {
+        let (_, account) = self.sdb.get_account(&address);
+        Ok(Account {
+            storage: Default::default(),
+            ..account.clone()
+        })
+    }
+
+    async fn get_storage(
+        &self,
+        _block_num: u64,
+        address: Address,
+        key: Word,
+    ) -> Result<Word, Error> {
+        Ok(*self.sdb.get_storage(&address, &key).1)
+    }
+
+    async fn get_code(&self, _block_num: u64, address: Address) -> Result<Vec<u8>, Error> {
+        let (_, account) = self.sdb.get_account(&address);
+        Ok(self
+            .code_db
+            .0
+            .get(&account.code_hash)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    /// Returns the account and its storage slots without Merkle proofs, as
+    /// the state in memory isn't a trie.
+    async fn get_proof(
+        &self,
+        _block_num: u64,
+        address: Address,
+        keys: Vec<Word>,
+    ) -> Result<EIP1186ProofResponse, Error> {
+        let (_, account) = self.sdb.get_account(&address);
+        Ok(EIP1186ProofResponse {
+            address,
+            balance: account.balance,
+            code_hash: account.code_hash,
+            nonce: account.nonce,
+            storage_proof: keys
+                .into_iter()
+                .map(|key| StorageProof {
+                    key,
+                    value: *self.sdb.get_storage(&address, &key).1,
+                    proof: Vec::new(),
+                })
+                .collect(),
+            ..Default::default()
+        })
+    }
+}
+
+/// The state fetched from a node, which has to keep the state of the blocks
+/// it is queried for.
+#[async_trait]
+impl<P: JsonRpcClient> StateBackend for GethClient<P> {
+    async fn get_account(&self, block_num: u64, address: Address) -> Result<Account, Error> {
+        let proof = StateBackend::get_proof(self, block_num, address, Vec::new()).await?;
+        Ok(Account {
+            nonce: proof.nonce,
+            balance: proof.balance,
+            storage: Default::default(),
+            code_hash: proof.code_hash,
+        })
+    }
+
+    async fn get_storage(
+        &self,
+        block_num: u64,
+        address: Address,
+        key: Word,
+    ) -> Result<Word, Error> {
+        let proof = StateBackend::get_proof(self, block_num, address, vec![key]).await?;
+        proof
+            .storage_proof
+            .first()
+            .map(|storage_proof| storage_proof.value)
+            .ok_or(Error::StorageKeyNotFound(address, key))
+    }
+
+    async fn get_code(&self, block_num: u64, address: Address) -> Result<Vec<u8>, Error> {
+        GethClient::get_code(self, address, (block_num - 1).into()).await
+    }
+
+    async fn get_proof(
+        &self,
+        block_num: u64,
+        address: Address,
+        keys: Vec<Word>,
+    ) -> Result<EIP1186ProofResponse, Error> {
+        GethClient::get_proof(self, address, keys, (block_num - 1).into()).await
+    }
+}
+
+/// Returns the [`StateDB`] and the [`CodeDB`] of the accounts, the storage
+/// slots and the code of `access_set` before the block `block_num`, loaded
+/// from `backend`.
+pub async fn load_state<B: StateBackend + ?Sized>(
+    backend: &B,
+    block_num: u64,
+    access_set: &AccessSet,
+) -> Result<(StateDB, CodeDB), Error> {
+    let mut sdb = StateDB::new();
+    for (address, keys) in access_set.state.iter() {
+        let mut account = backend.get_account(block_num, *address).await?;
+        for key in keys {
+            let value = backend.get_storage(block_num, *address, *key).await?;
+            account.storage.insert(*key, value);
+        }
+        sdb.set_account(address, account);
+    }
+    let mut code_db = CodeDB::new();
+    for address in access_set.code.iter() {
+        code_db.insert(backend.get_code(block_num, *address).await?);
+    }
+    Ok((sdb, code_db))
+}
+
+#[cfg(feature = "disk-db")]
+pub use cached::CachedDiskBackend;
+
+#[cfg(feature = "disk-db")]
+mod cached {
+    use super::StateBackend;
+    use crate::{
+        state_db::{Account, DiskDB},
+        Error,
+    };
+    use async_trait::async_trait;
+    use eth_types::{Address, EIP1186ProofResponse, Word};
+
+    /// Backend caching the accounts, the storage slots and the code of another
+    /// backend in a [`DiskDB`], so that they are only queried once.  The
+    /// proofs aren't cached.
+    #[derive(Debug, Clone)]
+    pub struct CachedDiskBackend<B> {
+        backend: B,
+        disk_db: DiskDB,
+    }
+
+    impl<B: StateBackend> CachedDiskBackend<B> {
+        /// Returns the backend caching `backend` in `disk_db`.
+        pub fn new(backend: B, disk_db: DiskDB) -> Self {
+            Self { backend, disk_db }
+        }
+    }
+
+    #[async_trait]
+    impl<B: StateBackend> StateBackend for CachedDiskBackend<B> {
+        async fn get_account(&self, block_num: u64, address: Address) -> Result<Account, Error> {
+            if let Some(account) = self.disk_db.get_account(block_num, &address)? {
+                return Ok(account);
+            }
+            let account = self.backend.get_account(block_num, address).await?;
+            self.disk_db.insert_account(block_num, &address, &account)?;
+            Ok(account)
+        }
+
+        async fn get_storage(
+            &self,
+            block_num: u64,
+            address: Address,
+            key: Word,
+        ) -> Result<Word, Error> {
+            if let Some(value) = self.disk_db.get_storage(block_num, &address, &key)? {
+                return Ok(value);
+            }
+            let value = self.backend.get_storage(block_num, address, key).await?;
+            self.disk_db
+                .insert_storage(block_num, &address, &key, &value)?;
+            Ok(value)
+        }
+
+        async fn get_code(&self, block_num: u64, address: Address) -> Result<Vec<u8>, Error> {
+            let account = self.get_account(block_num, address).await?;
+            if let Some(code) = self.disk_db.get_code(&account.code_hash)? {
+                return Ok(code);
+            }
+            let code = self.backend.get_code(block_num, address).await?;
+            self.disk_db.insert_code(&code)?;
+            Ok(code)
+        }
+
+        async fn get_proof(
+            &self,
+            block_num: u64,
+            address: Address,
+            keys: Vec<Word>,
+        ) -> Result<EIP1186ProofResponse, Error> {
+            self.backend.get_proof(block_num, address, keys).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_state, MemoryBackend, StateBackend};
+    use crate::{
+        circuit_input_builder::AccessSet,
+        state_db::{CodeDB, StateDB},
+    };
+    use eth_types::{address, Word};
+    use futures::executor::block_on;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn memory_backend_load_state() {
+        let addr_a = address!("0x00000000000000000000000000000000000000aa");
+        let addr_b = address!("0x00000000000000000000000000000000000000bb");
+        let mut sdb = StateDB::new();
+        let mut code_db = CodeDB::new();
+        {
+            let (_, account) = sdb.get_account_mut(&addr_a);
+            account.balance = Word::from(1000);
+            account.code_hash = code_db.insert(vec![0x60, 0x00]);
+        }
+        *sdb.get_storage_mut(&addr_a, &Word::from(1)).1 = Word::from(42);
+        *sdb.get_storage_mut(&addr_a, &Word::from(2)).1 = Word::from(43);
+        let backend = MemoryBackend::new(sdb, code_db.clone());
+
+        let access_set = AccessSet {
+            state: HashMap::from([
+                (addr_a, HashSet::from([Word::from(1)])),
+                (addr_b, HashSet::new()),
+            ]),
+            code: HashSet::from([addr_a]),
+        };
+        let (sdb, code_db_loaded) = block_on(load_state(&backend, 1, &access_set)).unwrap();
+        let (found, account) = sdb.get_account(&addr_a);
+        assert!(found);
+        assert_eq!(account.balance, Word::from(1000));
+        assert_eq!(
+            account.storage.iter().collect::<Vec<_>>(),
+            vec![(&Word::from(1), &Word::from(42))]
+        );
+        assert!(sdb.get_account(&addr_b).1.is_empty());
+        assert_eq!(code_db_loaded.0, code_db.0);
+
+        let proof = block_on(backend.get_proof(1, addr_a, vec![Word::from(2)])).unwrap();
+        assert_eq!(proof.storage_proof[0].value, Word::from(43));
+    }
+}
@@ -0,0 +1,247 @@
+//! Persistent store of the accounts, the storage and the code fetched for the
+//! blocks of a chain, so that a prover following many blocks keeps them across
+//! runs instead of fetching them again.
+//!
+//! The accounts and the storage slots are keyed by the number of the block
+//! whose state before it they are of, as the state changes with every block,
+//! and only the ones accessed by a block are loaded into the [`StateDB`] of
+//! the block.  The code is keyed by its hash.
+
+use super::{Account, CodeDB, StateDB};
+use crate::{circuit_input_builder::AccessSet, Error};
+use eth_types::{keccak256, Address, Hash, Word, H256};
+use std::{collections::BTreeMap, path::Path};
+
+// Length of an encoded account: nonce, balance and code hash.
+const ACCOUNT_LEN: usize = 32 * 3;
+
+/// Store on disk of the state before the blocks of a chain and of the code
+/// of its contracts.
+#[derive(Debug, Clone)]
+pub struct DiskDB {
+    accounts: sled::Tree,
+    storage: sled::Tree,
+    code: sled::Tree,
+}
+
+// Returns the key of the state of `address` before the block `block_num`,
+// followed by the storage slot `key` if any.
+fn state_key(block_num: u64, address: &Address, key: Option<&Word>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(8 + 20 + 32);
+    bytes.extend_from_slice(&block_num.to_be_bytes());
+    bytes.extend_from_slice(address.as_bytes());
+    if let Some(key) = key {
+        let mut slot = [0u8; 32];
+        key.to_big_endian(&mut slot);
+        bytes.extend_from_slice(&slot);
+    }
+    bytes
+}
+
+fn encode_account(account: &Account) -> Vec<u8> {
+    let mut bytes = vec![0u8; ACCOUNT_LEN];
+    account.nonce.to_big_endian(&mut bytes[..32]);
+    account.balance.to_big_endian(&mut bytes[32..64]);
+    bytes[64..].copy_from_slice(account.code_hash.as_bytes());
+    bytes
+}
+
+fn decode_account(bytes: &[u8]) -> Result<Account, Error> {
+    if bytes.len() != ACCOUNT_LEN {
+        return Err(Error::DiskDBCorrupted("account"));
+    }
+    Ok(Account {
+        nonce: Word::from_big_endian(&bytes[..32]),
+        balance: Word::from_big_endian(&bytes[32..64]),
+        storage: BTreeMap::new(),
+        code_hash: H256::from_slice(&bytes[64..]),
+    })
+}
+
+impl DiskDB {
+    /// Opens the store in the directory `path`, which is created if it
+    /// doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let db = sled::open(path)?;
+        Ok(Self {
+            accounts: db.open_tree("accounts")?,
+            storage: db.open_tree("storage")?,
+            code: db.open_tree("code")?,
+        })
+    }
+
+    /// Returns the [`Account`] at `address` before the block `block_num`,
+    /// without its storage, if it is stored.
+    pub fn get_account(&self, block_num: u64, address: &Address) -> Result<Option<Account>, Error> {
+        self.accounts
+            .get(state_key(block_num, address, None))?
+            .map(|bytes| decode_account(&bytes))
+            .transpose()
+    }
+
+    /// Returns the value of the storage slot `key` of `address` before the
+    /// block `block_num`, if it is stored.
+    pub fn get_storage(
+        &self,
+        block_num: u64,
+        address: &Address,
+        key: &Word,
+    ) -> Result<Option<Word>, Error> {
+        match self.storage.get(state_key(block_num, address, Some(key)))? {
+            Some(bytes) if bytes.len() == 32 => Ok(Some(Word::from_big_endian(&bytes))),
+            Some(_) => Err(Error::DiskDBCorrupted("storage slot")),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the code of hash `code_hash`, if it is stored.
+    pub fn get_code(&self, code_hash: &Hash) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self
+            .code
+            .get(code_hash.as_bytes())?
+            .map(|code| code.to_vec()))
+    }
+
+    /// Stores `account` at `address`, without its storage, as the one before
+    /// the block `block_num`.
+    pub fn insert_account(
+        &self,
+        block_num: u64,
+        address: &Address,
+        account: &Account,
+    ) -> Result<(), Error> {
+        self.accounts
+            .insert(state_key(block_num, address, None), encode_account(account))?;
+        Ok(())
+    }
+
+    /// Stores `value` as the one of the storage slot `key` of `address`
+    /// before the block `block_num`.
+    pub fn insert_storage(
+        &self,
+        block_num: u64,
+        address: &Address,
+        key: &Word,
+        value: &Word,
+    ) -> Result<(), Error> {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        self.storage
+            .insert(state_key(block_num, address, Some(key)), &bytes[..])?;
+        Ok(())
+    }
+
+    /// Stores `code` by its hash, which is returned.
+    pub fn insert_code(&self, code: &[u8]) -> Result<Hash, Error> {
+        let code_hash = H256(keccak256(code));
+        self.code.insert(code_hash.as_bytes(), code)?;
+        Ok(code_hash)
+    }
+
+    /// Stores the accounts of `sdb`, with their storage, as the state before
+    /// the block `block_num`, and the code of `code_db`.
+    pub fn insert(&self, block_num: u64, sdb: &StateDB, code_db: &CodeDB) -> Result<(), Error> {
+        for (address, account) in sdb.iter() {
+            self.insert_account(block_num, address, account)?;
+            for (key, value) in account.storage.iter() {
+                self.insert_storage(block_num, address, key, value)?;
+            }
+        }
+        for code in code_db.0.values() {
+            self.insert_code(code)?;
+        }
+        self.accounts.flush()?;
+        self.storage.flush()?;
+        self.code.flush()?;
+        Ok(())
+    }
+
+    /// Returns the [`StateDB`] and the [`CodeDB`] of the accounts, storage
+    /// slots and code in `access_set` before the block `block_num`, or `None`
+    /// if any of them isn't stored.
+    pub fn load(
+        &self,
+        block_num: u64,
+        access_set: &AccessSet,
+    ) -> Result<Option<(StateDB, CodeDB)>, Error> {
+        let mut sdb = StateDB::new();
+        for (address, keys) in access_set.state.iter() {
+            let mut account = match self.get_account(block_num, address)? {
+                Some(account) => account,
+                None => return Ok(None),
+            };
+            for key in keys {
+                match self.get_storage(block_num, address, key)? {
+                    Some(value) => account.storage.insert(*key, value),
+                    None => return Ok(None),
+                };
+            }
+            sdb.set_account(address, account);
+        }
+
+        let mut code_db = CodeDB::new();
+        for address in access_set.code.iter() {
+            let (found, account) = sdb.get_account(address);
+            let code = if found {
+                self.get_code(&account.code_hash)?
+            } else {
+                None
+            };
+            match code {
+                Some(code) => code_db.insert(code),
+                None => return Ok(None),
+            };
+        }
+        Ok(Some((sdb, code_db)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiskDB;
+    use crate::{
+        circuit_input_builder::AccessSet,
+        state_db::{CodeDB, StateDB},
+    };
+    use eth_types::{address, Word};
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn disk_db_roundtrip() {
+        let path = std::env::temp_dir().join(format!("disk-db-{}", std::process::id()));
+        let addr = address!("0x00000000000000000000000000000000000000aa");
+        let mut sdb = StateDB::new();
+        let mut code_db = CodeDB::new();
+        {
+            let (_, account) = sdb.get_account_mut(&addr);
+            account.nonce = Word::from(3);
+            account.balance = Word::from(1000);
+            account.code_hash = code_db.insert(vec![0x60, 0x00]);
+        }
+        *sdb.get_storage_mut(&addr, &Word::from(1)).1 = Word::from(42);
+
+        let access_set = AccessSet {
+            state: HashMap::from([(addr, HashSet::from([Word::from(1)]))]),
+            code: HashSet::from([addr]),
+        };
+        let disk_db = DiskDB::open(&path).unwrap();
+        assert!(disk_db.load(7, &access_set).unwrap().is_none());
+        disk_db.insert(7, &sdb, &code_db).unwrap();
+        drop(disk_db);
+
+        let disk_db = DiskDB::open(&path).unwrap();
+        let (sdb_loaded, code_db_loaded) = disk_db.load(7, &access_set).unwrap().unwrap();
+        assert_eq!(sdb_loaded.get_account(&addr).1, sdb.get_account(&addr).1);
+        assert_eq!(code_db_loaded.0, code_db.0);
+        assert!(disk_db.load(8, &access_set).unwrap().is_none());
+
+        let mut access_set = access_set;
+        access_set
+            .state
+            .get_mut(&addr)
+            .unwrap()
+            .insert(Word::from(2));
+        assert!(disk_db.load(7, &access_set).unwrap().is_none());
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
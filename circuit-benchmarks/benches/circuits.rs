@@ -0,0 +1,251 @@
+//! Criterion benchmarks of the key generation, the witness generation, the
+//! proving and the verification of the EVM, state, keccak, tx and bytecode
+//! circuits at several degrees.
+//!
+//! The degrees of every circuit are its default ones, or the comma-separated
+//! ones of the `DEGREES` env var, e.g.
+//!
+//! ```text
+//! DEGREES=16,18 cargo bench -p circuit-benchmarks --features benches -- evm_circuit
+//! ```
+//!
+//! Once the benchmarks ran, the results of the latest run are written to
+//! `<criterion dir>/circuits.json`, or to the file of the `BENCH_RESULTS` env
+//! var, see `circuit_benchmarks::report`.  The MPT is not benchmarked, as only
+//! its table is in this repository.
+
+use bus_mapping::mock::BlockData;
+use circuit_benchmarks::{
+    keccak_permutation::keccak_round_circuit,
+    report::{collect_results, write_results},
+};
+use criterion::{BenchmarkId, Criterion, SamplingMode};
+use eth_types::{bytecode, ChainConfig};
+use halo2_proofs::{
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, SingleVerifier},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use pairing::bn256::{Bn256, Fr, G1Affine};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use std::{env::var, iter, path::PathBuf, slice};
+use zkevm_circuits::{
+    bytecode_circuit::BytecodeCircuit,
+    evm_circuit::{
+        table::FixedTableTag,
+        test::TestCircuit,
+        witness::{block_convert, Block},
+        EvmCircuit,
+    },
+    state_circuit::{StateCircuit, DEFAULT_MAX_DEGREE},
+    tx_circuit::{TxCircuit, POW_RAND_SIZE},
+};
+
+const EVM_CIRCUIT: &str = "evm_circuit";
+const STATE_CIRCUIT: &str = "state_circuit";
+const KECCAK_CIRCUIT: &str = "keccak_circuit";
+const TX_CIRCUIT: &str = "tx_circuit";
+const BYTECODE_CIRCUIT: &str = "bytecode_circuit";
+const CIRCUITS: [&str; 5] = [
+    EVM_CIRCUIT,
+    STATE_CIRCUIT,
+    KECCAK_CIRCUIT,
+    TX_CIRCUIT,
+    BYTECODE_CIRCUIT,
+];
+
+// Capacity of the benchmarked state circuit, whose rows fit in the smallest
+// degree 12.
+const STATE_ROWS_MAX: usize = (1 << 12) - 64;
+const MEMORY_ADDRESS_MAX: usize = 2000;
+const STACK_ADDRESS_MAX: usize = 1300;
+
+type BenchStateCircuit = StateCircuit<
+    Fr,
+    true,
+    STATE_ROWS_MAX,
+    MEMORY_ADDRESS_MAX,
+    STACK_ADDRESS_MAX,
+    STATE_ROWS_MAX,
+    DEFAULT_MAX_DEGREE,
+>;
+
+// Capacity of the benchmarked tx circuit, which is filled with padding
+// transactions.
+const MAX_TXS: usize = 1;
+const MAX_CALLDATA: usize = 128;
+
+// Returns the degrees of the `DEGREES` env var, or `default` if it isn't set.
+fn degrees(default: &[u32]) -> Vec<u32> {
+    match var("DEGREES") {
+        Ok(degrees) => degrees
+            .split(',')
+            .map(|k| {
+                k.trim()
+                    .parse()
+                    .expect("Cannot parse DEGREES env var as u32s")
+            })
+            .collect(),
+        Err(_) => default.to_vec(),
+    }
+}
+
+// Returns the values of the `n` instance columns of the powers of
+// `randomness` of a circuit of degree `k`.
+fn power_of_randomness(randomness: Fr, n: usize, k: u32) -> Vec<Vec<Fr>> {
+    iter::successors(Some(randomness), |power| Some(*power * randomness))
+        .take(n)
+        .map(|power| vec![power; (1 << k) - 64])
+        .collect()
+}
+
+// Returns the witness of the block of a transaction calling a contract which
+// does some arithmetic, and accesses the memory and the storage.
+fn block() -> Block<Fr> {
+    let code = bytecode! {
+        PUSH32(0x1234_5678)
+        PUSH1(0x20)
+        MSTORE
+        PUSH1(0x20)
+        MLOAD
+        PUSH1(0x03)
+        MUL
+        PUSH1(0x01)
+        SSTORE
+        PUSH1(0x01)
+        SLOAD
+        PUSH1(0x20)
+        PUSH1(0x00)
+        SHA3
+        STOP
+    };
+    let block_data = BlockData::new_from_geth_data(mock::new_single_tx_trace_code(&code).unwrap());
+    let mut builder = block_data.new_circuit_input_builder();
+    builder
+        .handle_block(&block_data.eth_block, &block_data.geth_traces)
+        .unwrap();
+    block_convert(&builder.block, &builder.code_db)
+}
+
+// Benchmarks the stages of the circuit `name` at the `degrees`, `witness`
+// returning the circuit of degree `k` and the values of its instance columns.
+fn bench_circuit<C: Circuit<Fr>>(
+    c: &mut Criterion,
+    name: &str,
+    degrees: &[u32],
+    witness: impl Fn(u32) -> (C, Vec<Vec<Fr>>),
+) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10).sampling_mode(SamplingMode::Flat);
+    for &k in degrees {
+        let params = Params::<G1Affine>::unsafe_setup::<Bn256>(k);
+
+        group.bench_with_input(BenchmarkId::new("witness", k), &k, |b, &k| {
+            b.iter(|| witness(k));
+        });
+        let (circuit, instances) = witness(k);
+        let instances = instances.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        group.bench_with_input(BenchmarkId::new("keygen", k), &k, |b, _| {
+            b.iter(|| {
+                let vk = keygen_vk(&params, &circuit).unwrap();
+                keygen_pk(&params, vk, &circuit).unwrap()
+            })
+        });
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk, &circuit).unwrap();
+
+        let prove = || {
+            let rng = XorShiftRng::seed_from_u64(k as u64);
+            let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+            create_proof(
+                &params,
+                &pk,
+                slice::from_ref(&circuit),
+                &[&instances[..]],
+                rng,
+                &mut transcript,
+            )
+            .unwrap();
+            transcript.finalize()
+        };
+        group.bench_with_input(BenchmarkId::new("prove", k), &k, |b, _| b.iter(&prove));
+        let proof = prove();
+
+        let n_instance_rows = instances.iter().map(|column| column.len()).max();
+        let verifier_params = params
+            .verifier(n_instance_rows.unwrap_or_default())
+            .unwrap();
+        group.bench_with_input(BenchmarkId::new("verify", k), &k, |b, _| {
+            b.iter(|| {
+                let strategy = SingleVerifier::new(&verifier_params);
+                let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+                verify_proof(
+                    &verifier_params,
+                    pk.get_vk(),
+                    strategy,
+                    &[&instances[..]],
+                    &mut transcript,
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn main() {
+    let mut c = Criterion::default().configure_from_args();
+
+    bench_circuit(&mut c, EVM_CIRCUIT, &degrees(&[18, 19]), |k| {
+        let block = block();
+        let mut instance = power_of_randomness(block.randomness, 31, k);
+        instance.extend(EvmCircuit::chunk_states_instance(&block, (1 << k) - 64));
+        let circuit = TestCircuit::new(block, FixedTableTag::iterator().collect());
+        (circuit, instance)
+    });
+    bench_circuit(&mut c, STATE_CIRCUIT, &degrees(&[12, 14]), |_| {
+        let block = block();
+        let circuit = BenchStateCircuit::new(block.randomness, &block.rws).unwrap();
+        let instance = circuit.instance();
+        (circuit, instance)
+    });
+    bench_circuit(&mut c, KECCAK_CIRCUIT, &degrees(&[17, 18]), |_| {
+        keccak_round_circuit()
+    });
+    bench_circuit(&mut c, TX_CIRCUIT, &degrees(&[14, 16]), |k| {
+        let randomness = Fr::from(0x100);
+        let circuit = TxCircuit::new(
+            &ChainConfig::new(1.into()),
+            randomness,
+            Vec::new(),
+            MAX_TXS,
+            MAX_CALLDATA,
+        );
+        (circuit, power_of_randomness(randomness, POW_RAND_SIZE, k))
+    });
+    bench_circuit(&mut c, BYTECODE_CIRCUIT, &degrees(&[10, 12, 14]), |k| {
+        let block = block();
+        let randomness = block.randomness;
+        let circuit = BytecodeCircuit {
+            block,
+            size: 1 << k,
+        };
+        (circuit, power_of_randomness(randomness, 1, k))
+    });
+
+    c.final_summary();
+
+    let criterion_dir = match (var("CRITERION_HOME"), var("CARGO_TARGET_DIR")) {
+        (Ok(dir), _) => PathBuf::from(dir),
+        (_, Ok(dir)) => PathBuf::from(dir).join("criterion"),
+        _ => PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../target/criterion")),
+    };
+    let path = var("BENCH_RESULTS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| criterion_dir.join("circuits.json"));
+    let results = collect_results(&criterion_dir, &CIRCUITS).unwrap();
+    write_results(&path, &results).unwrap();
+    println!("wrote {} results to {}", results.len(), path.display());
+}
@@ -1,11 +1,23 @@
 //! Evm circuit benchmarks
 
-use eth_types::Field;
+use eth_types::{evm_types::Hardfork, Field};
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner},
     plonk::{Circuit, ConstraintSystem, Error, Expression},
 };
-use zkevm_circuits::evm_circuit::{witness::Block, EvmCircuit};
+use zkevm_circuits::{
+    block_table::BlockTable,
+    bytecode_table::BytecodeTable,
+    copy_table::CopyTable,
+    evm_circuit::{
+        witness::{Block, CHUNK_STATE_SIZE},
+        EvmCircuit,
+    },
+    exp_table::ExpTable,
+    keccak_table::KeccakTable,
+    rw_table::RwTable,
+    tx_table::TxTable,
+};
 
 #[derive(Debug, Default)]
 pub struct TestCircuit<F> {
@@ -21,21 +33,33 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let tx_table = [(); 4].map(|_| meta.advice_column());
-        let rw_table = [(); 11].map(|_| meta.advice_column());
-        let bytecode_table = [(); 4].map(|_| meta.advice_column());
-        let block_table = [(); 3].map(|_| meta.advice_column());
+        let tx_table = TxTable::construct(meta);
+        let rw_table = RwTable::construct(meta);
+        let bytecode_table = BytecodeTable::construct(meta);
+        let block_table = BlockTable::construct(meta);
+        let keccak_table = KeccakTable::construct(meta);
+        let copy_table = CopyTable::construct(meta);
+        let exp_table = ExpTable::construct(meta);
         // Use constant expression to mock constant instance column for a more
         // reasonable benchmark.
         let power_of_randomness = [(); 31].map(|_| Expression::Constant(F::one()));
+        // The benchmarked block isn't split into chunks, whose states are
+        // mocked by constants too.
+        let chunk_states =
+            [(); 2].map(|_| [(); CHUNK_STATE_SIZE].map(|_| Expression::Constant(F::zero())));
 
         EvmCircuit::configure(
             meta,
             power_of_randomness,
+            chunk_states,
+            Hardfork::default(),
             tx_table,
             rw_table,
             bytecode_table,
             block_table,
+            keccak_table,
+            copy_table,
+            exp_table,
         )
     }
 
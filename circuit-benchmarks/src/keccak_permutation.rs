@@ -5,12 +5,19 @@ use halo2_proofs::{
     circuit::{AssignedCell, Layouter, SimpleFloorPlanner},
     plonk::{Circuit, ConstraintSystem, Error},
 };
+use itertools::Itertools;
 use keccak256::{
-    common::NEXT_INPUTS_LANES, keccak_arith::KeccakFArith, permutation::circuit::KeccakFConfig,
+    arith_helpers::{convert_b2_to_b13, convert_b2_to_b9, state_bigint_to_field, StateBigInt},
+    common::{State, NEXT_INPUTS_LANES, ROUND_CONSTANTS},
+    gate_helpers::biguint_to_f,
+    keccak_arith::KeccakFArith,
+    permutation::circuit::KeccakFConfig,
 };
+use pairing::bn256::Fr;
 
+/// Circuit of the keccak permutation of a state, without absorbing.
 #[derive(Default, Clone)]
-struct KeccakRoundTestCircuit<F> {
+pub struct KeccakRoundTestCircuit<F> {
     in_state: [F; 25],
     out_state: [F; 25],
     next_mixing: Option<[F; NEXT_INPUTS_LANES]>,
@@ -70,6 +77,40 @@ impl<F: Field> Circuit<F> for KeccakRoundTestCircuit<F> {
     }
 }
 
+/// Returns the circuit of the permutation of the state with a single 1 bit,
+/// and the values of its instance columns, the round constants in base 9
+/// and in base 13.
+pub fn keccak_round_circuit() -> (KeccakRoundTestCircuit<Fr>, Vec<Vec<Fr>>) {
+    let mut in_state: State = [[0; 5]; 5];
+    in_state[0][0] = 1;
+
+    let mut in_state_fp = [Fr::zero(); 25];
+    let mut in_state_biguint = StateBigInt::default();
+    for (x, y) in (0..5).cartesian_product(0..5) {
+        in_state_fp[5 * x + y] = biguint_to_f(&convert_b2_to_b13(in_state[x][y]));
+        in_state_biguint[(x, y)] = convert_b2_to_b13(in_state[x][y]);
+    }
+    let mut out_state = in_state_biguint;
+    KeccakFArith::permute_and_absorb(&mut out_state, None);
+
+    let constants_b9 = ROUND_CONSTANTS
+        .iter()
+        .map(|num| biguint_to_f(&convert_b2_to_b9(*num)))
+        .collect();
+    let constants_b13 = ROUND_CONSTANTS
+        .iter()
+        .map(|num| biguint_to_f(&convert_b2_to_b13(*num)))
+        .collect();
+
+    let circuit = KeccakRoundTestCircuit {
+        in_state: in_state_fp,
+        out_state: state_bigint_to_field(out_state),
+        next_mixing: None,
+        is_mixing: false,
+    };
+    (circuit, vec![constants_b9, constants_b13])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -11,6 +11,8 @@ pub mod state_circuit;
 #[cfg(feature = "benches")]
 pub mod bench_params;
 
-#[cfg(test)]
 #[cfg(feature = "benches")]
 pub mod keccak_permutation;
+
+#[cfg(feature = "benches")]
+pub mod report;
@@ -0,0 +1,133 @@
+//! Summary of the criterion benchmarks of the circuits in a single JSON file,
+//! for tracking performance regressions across commits.
+//!
+//! Criterion writes the estimates of every benchmark `<group>/<function>/<k>`
+//! to `<criterion dir>/<group>/<function>/<k>/new/estimates.json`.  The
+//! circuits are the groups and the benchmarked stages the functions, so the
+//! results are gathered by walking these directories.
+
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+
+/// Result of the benchmark of a stage of a circuit at a degree.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchResult {
+    /// Benchmarked circuit
+    pub circuit: String,
+    /// Benchmarked stage: `keygen`, `witness`, `prove` or `verify`
+    pub stage: String,
+    /// Degree of the circuit
+    pub k: u32,
+    /// Mean time, in nanoseconds
+    pub mean_ns: f64,
+    /// Median time, in nanoseconds
+    pub median_ns: f64,
+    /// Standard deviation of the time, in nanoseconds
+    pub std_dev_ns: f64,
+}
+
+#[derive(Deserialize)]
+struct Estimate {
+    point_estimate: f64,
+}
+
+// Estimates written by criterion, of which only the point estimates are kept.
+#[derive(Deserialize)]
+struct Estimates {
+    mean: Estimate,
+    median: Estimate,
+    std_dev: Estimate,
+}
+
+// Returns the names of the subdirectories of `dir` in order, or none if it
+// doesn't exist.
+fn subdirectories(dir: &Path) -> io::Result<Vec<String>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            names.extend(entry.file_name().to_str().map(str::to_string));
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Returns the results of the latest run of the benchmarks of `circuits`
+/// found in the criterion directory `dir`, by circuit, stage and degree.
+/// Circuits that weren't benchmarked have no results.
+pub fn collect_results(dir: &Path, circuits: &[&str]) -> io::Result<Vec<BenchResult>> {
+    let mut results = Vec::new();
+    for circuit in circuits {
+        let circuit_dir = dir.join(circuit);
+        for stage in subdirectories(&circuit_dir)? {
+            for k in subdirectories(&circuit_dir.join(&stage))? {
+                let path = circuit_dir.join(&stage).join(&k).join("new/estimates.json");
+                let k = match k.parse() {
+                    Ok(k) if path.is_file() => k,
+                    _ => continue,
+                };
+                let estimates: Estimates = serde_json::from_slice(&fs::read(path)?)?;
+                results.push(BenchResult {
+                    circuit: circuit.to_string(),
+                    stage: stage.clone(),
+                    k,
+                    mean_ns: estimates.mean.point_estimate,
+                    median_ns: estimates.median.point_estimate,
+                    std_dev_ns: estimates.std_dev.point_estimate,
+                });
+            }
+        }
+    }
+    results.sort_by(|a, b| (&a.circuit, &a.stage, a.k).cmp(&(&b.circuit, &b.stage, b.k)));
+    Ok(results)
+}
+
+/// Writes `results` to the JSON file at `path`.
+pub fn write_results(path: &Path, results: &[BenchResult]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_vec_pretty(results)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect_results, write_results, BenchResult};
+    use std::{env, fs, process};
+
+    #[test]
+    fn collect_criterion_results() {
+        let dir = env::temp_dir().join(format!("circuit-benchmarks-{}", process::id()));
+        for (stage, k, mean) in [("prove", 12, 3.0), ("prove", 10, 2.0), ("verify", 10, 1.0)] {
+            let bench_dir = dir.join(format!("tx_circuit/{}/{}/new", stage, k));
+            fs::create_dir_all(&bench_dir).unwrap();
+            let estimates = format!(
+                r#"{{"mean":{{"point_estimate":{}}},"median":{{"point_estimate":{}}},
+                    "std_dev":{{"point_estimate":0.5}},"slope":null}}"#,
+                mean, mean
+            );
+            fs::write(bench_dir.join("estimates.json"), estimates).unwrap();
+        }
+        fs::create_dir_all(dir.join("tx_circuit/report")).unwrap();
+
+        let results = collect_results(&dir, &["evm_circuit", "tx_circuit"]).unwrap();
+        let ids = results
+            .iter()
+            .map(|result| (result.stage.as_str(), result.k, result.mean_ns))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            [("prove", 10, 2.0), ("prove", 12, 3.0), ("verify", 10, 1.0)]
+        );
+
+        let path = dir.join("circuits.json");
+        write_results(&path, &results).unwrap();
+        let written: Vec<BenchResult> = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(written, results);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
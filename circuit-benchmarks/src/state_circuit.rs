@@ -12,10 +12,11 @@ mod tests {
     use pairing::bn256::{Bn256, Fr, G1Affine};
     use rand::SeedableRng;
     use rand_xorshift::XorShiftRng;
-    use zkevm_circuits::state_circuit::StateCircuit;
+    use zkevm_circuits::state_circuit::{StateCircuit, DEFAULT_MAX_DEGREE};
 
     const RW_COUNTER_MAX: usize = 1 << DEGREE;
-    const ROWS_MAX: usize = 1 << DEGREE;
+    // All the ROWS_MAX rows are enabled, leave some rows for the blinding factors.
+    const ROWS_MAX: usize = (1 << DEGREE) - 64;
 
     #[cfg_attr(not(feature = "benches"), ignore)]
     #[test]
@@ -27,6 +28,7 @@ mod tests {
             MEMORY_ADDRESS_MAX,
             STACK_ADDRESS_MAX,
             ROWS_MAX,
+            DEFAULT_MAX_DEGREE,
         >::default();
 
         // Initialize the polynomial commitment parameters
@@ -40,12 +42,15 @@ mod tests {
         let start1 = start_timer!(|| setup_message);
         let general_params: Params<G1Affine> =
             Params::<G1Affine>::unsafe_setup::<Bn256>(DEGREE.try_into().unwrap());
-        let verifier_params: ParamsVerifier<Bn256> = general_params.verifier(DEGREE * 2).unwrap();
+        let verifier_params: ParamsVerifier<Bn256> = general_params.verifier(ROWS_MAX + 1).unwrap();
         end_timer!(start1);
 
         // Initialize the proving key
         let vk = keygen_vk(&general_params, &empty_circuit).expect("keygen_vk should not fail");
         let pk = keygen_pk(&general_params, vk, &empty_circuit).expect("keygen_pk should not fail");
+        let instance = empty_circuit.instance();
+        let instance = instance.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
         // Create a proof
         let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
 
@@ -56,7 +61,7 @@ mod tests {
             &general_params,
             &pk,
             &[empty_circuit],
-            &[&[]],
+            &[&instance[..]],
             rng,
             &mut transcript,
         )
@@ -73,7 +78,7 @@ mod tests {
             &verifier_params,
             pk.get_vk(),
             strategy,
-            &[&[]],
+            &[&instance[..]],
             &mut verifier_transcript,
         )
         .expect("failed to verify bench circuit");
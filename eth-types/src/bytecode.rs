@@ -1,8 +1,12 @@
 //! EVM byte code generator
 
+use crate::error::AsmParsingError;
 use crate::evm_types::OpcodeId;
-use crate::Word;
+use crate::{Bytes, Word};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// EVM Bytecode
 #[derive(Debug, Default, Clone)]
@@ -10,26 +14,68 @@ pub struct Bytecode {
     code: Vec<u8>,
     num_opcodes: usize,
     markers: HashMap<String, usize>,
+    /// Byte offsets of the JUMPDESTs of the labels
+    labels: HashMap<String, usize>,
+    /// Byte offsets and sizes of the pushes of labels which aren't defined yet
+    label_refs: Vec<(usize, usize, String)>,
+}
+
+/// Number of loops written by [`Bytecode::counted_loop`], to name their labels.
+static NUM_LOOPS: AtomicUsize = AtomicUsize::new(0);
+
+impl From<Bytecode> for Bytes {
+    fn from(code: Bytecode) -> Self {
+        code.code.into()
+    }
 }
 
 impl Bytecode {
     /// Get a reference to the generated code
     pub fn code(&self) -> &[u8] {
+        self.check_labels();
         &self.code
     }
 
     /// Get the generated code
     pub fn to_vec(&self) -> Vec<u8> {
+        self.check_labels();
         self.code.clone()
     }
 
+    fn check_labels(&self) {
+        debug_assert!(
+            self.label_refs.is_empty(),
+            "undefined labels: {:?}",
+            self.label_refs
+                .iter()
+                .map(|(_, _, label)| label)
+                .collect::<Vec<_>>()
+        );
+    }
+
     /// Append
     pub fn append(&mut self, other: &Bytecode) {
+        let offset = self.code.len();
         self.code.extend_from_slice(&other.code);
         for (key, val) in other.markers.iter() {
             self.insert_marker(key, self.num_opcodes + val);
         }
         self.num_opcodes += other.num_opcodes;
+        for (label, pos) in other.labels.iter() {
+            debug_assert!(
+                !self.labels.contains_key(label),
+                "label already used: {}",
+                label
+            );
+            self.labels.insert(label.clone(), offset + pos);
+        }
+        self.label_refs.extend(
+            other
+                .label_refs
+                .iter()
+                .map(|(pos, n, label)| (offset + pos, *n, label.clone())),
+        );
+        self.resolve_labels();
     }
 
     /// Write op
@@ -92,6 +138,102 @@ impl Bytecode {
             .unwrap_or_else(|| panic!("marker '{}' not found", marker))
     }
 
+    /// Define the label `label` at the current position, which is marked as a
+    /// jump destination with a JUMPDEST.
+    pub fn label(&mut self, label: &str) -> &mut Self {
+        debug_assert!(
+            !self.labels.contains_key(label),
+            "label already used: {}",
+            label
+        );
+        self.labels.insert(label.to_string(), self.code.len());
+        self.write_op(OpcodeId::JUMPDEST);
+        self.resolve_labels();
+        self
+    }
+
+    /// Push the position of the label `label` with PUSH`n`, even if the label
+    /// is only defined later on.
+    pub fn push_label(&mut self, n: usize, label: &str) -> &mut Self {
+        self.push(n, Word::zero());
+        self.label_refs
+            .push((self.code.len() - n, n, label.to_string()));
+        self.resolve_labels();
+        self
+    }
+
+    /// Write op with the position of the label `label` as argument: a push
+    /// of the position with a PUSH op, or a JUMP or a JUMPI to the position.
+    pub fn op_label(&mut self, op: OpcodeId, label: &str) -> &mut Self {
+        if op.is_push() {
+            let n = op.as_u8() - OpcodeId::PUSH1.as_u8() + 1;
+            self.push_label(n as usize, label)
+        } else {
+            debug_assert!(
+                op == OpcodeId::JUMP || op == OpcodeId::JUMPI,
+                "{:?} doesn't take a label",
+                op
+            );
+            self.push_label(2, label).write_op(op)
+        }
+    }
+
+    /// Jump to the label `label`.
+    pub fn jump(&mut self, label: &str) -> &mut Self {
+        self.op_label(OpcodeId::JUMP, label)
+    }
+
+    /// Jump to the label `label` if the top of the stack, which is popped
+    /// along with it, isn't zero.
+    pub fn jumpi(&mut self, label: &str) -> &mut Self {
+        self.op_label(OpcodeId::JUMPI, label)
+    }
+
+    /// Write the positions of the labels which are defined in the pushes
+    /// which refer to them.
+    fn resolve_labels(&mut self) {
+        let labels = &self.labels;
+        let code = &mut self.code;
+        self.label_refs
+            .retain(|(pos, n, label)| match labels.get(label) {
+                Some(target) => {
+                    let mut bytes = [0u8; 32];
+                    Word::from(*target).to_big_endian(&mut bytes);
+                    debug_assert!(
+                        bytes[..32 - n].iter().all(|byte| *byte == 0),
+                        "position {} of label {} too big for PUSH{}",
+                        target,
+                        label,
+                        n
+                    );
+                    code[*pos..*pos + n].copy_from_slice(&bytes[32 - n..]);
+                    false
+                }
+                None => true,
+            });
+    }
+
+    /// Repeat `body` `times` times in a loop, with a counter on the top of
+    /// the stack which `body` has to leave there.  The counter goes from
+    /// `times` down to 1 and is popped at the end of the loop.
+    pub fn counted_loop(&mut self, times: Word, body: &Bytecode) -> &mut Self {
+        let id = NUM_LOOPS.fetch_add(1, Ordering::Relaxed);
+        let start = format!("__loop{}_start", id);
+        let end = format!("__loop{}_end", id);
+        self.push(32, times)
+            .label(&start)
+            .write_op(OpcodeId::DUP1)
+            .write_op(OpcodeId::ISZERO)
+            .jumpi(&end);
+        self.append(body);
+        self.push(1, Word::one())
+            .write_op(OpcodeId::SWAP1)
+            .write_op(OpcodeId::SUB)
+            .jump(&start)
+            .label(&end)
+            .write_op(OpcodeId::POP)
+    }
+
     /// Setup state
     pub fn setup_state(&mut self) -> &mut Self {
         self.append(&crate::bytecode! {
@@ -128,7 +270,186 @@ impl Bytecode {
     }
 }
 
+/// Instruction of disassembled EVM code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// Byte offset of the instruction in the code
+    pub pc: usize,
+    /// Opcode, which is [`OpcodeId::INVALID`] for the bytes which aren't
+    /// opcodes
+    pub op: OpcodeId,
+    /// Bytes pushed by a PUSH, which are fewer than its size when the code
+    /// ends before them
+    pub push_data: Vec<u8>,
+}
+
+impl Instruction {
+    /// Returns the value pushed by a PUSH.
+    pub fn push_value(&self) -> Option<Word> {
+        if self.op.is_push() {
+            Some(Word::from_big_endian(&self.push_data))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    /// Formats the instruction in the assembly parsed by
+    /// [`Bytecode::from_str`]: the mnemonic, the value of a PUSH in hex, and
+    /// `INVALID(0x..)` for the bytes which aren't opcodes.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.op {
+            OpcodeId::INVALID(0xfe) => write!(f, "INVALID"),
+            OpcodeId::INVALID(byte) => write!(f, "INVALID({:#04x})", byte),
+            op if op.is_push() => {
+                write!(f, "{:?} 0x", op)?;
+                if self.push_data.is_empty() {
+                    write!(f, "0")?;
+                }
+                for byte in self.push_data.iter() {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+            op => write!(f, "{:?}", op),
+        }
+    }
+}
+
+/// Disassembles the EVM code `code` into its instructions.
+pub fn disasm(code: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let op = OpcodeId::from(code[pc]);
+        let size = if op.is_push() {
+            (op.as_u8() - OpcodeId::PUSH1.as_u8() + 1) as usize
+        } else {
+            0
+        };
+        let push_data = code[(pc + 1).min(code.len())..(pc + 1 + size).min(code.len())].to_vec();
+        instructions.push(Instruction { pc, op, push_data });
+        pc += 1 + size;
+    }
+    instructions
+}
+
+impl Bytecode {
+    /// Disassemble the code into its instructions.
+    pub fn disasm(&self) -> Vec<Instruction> {
+        disasm(self.code())
+    }
+}
+
+/// Parses the value of a PUSH`n` in decimal or in hex with a `0x` prefix.
+fn parse_push_value(push: &str, value: &str, n: usize) -> Result<Word, AsmParsingError> {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) if !hex.is_empty() && hex.len() <= 64 => Word::from_str_radix(hex, 16).ok(),
+        Some(_) => None,
+        None => Word::from_dec_str(value).ok(),
+    };
+    parsed
+        .filter(|value| value.bits() <= 8 * n)
+        .ok_or_else(|| AsmParsingError::InvalidPushValue(format!("{} {}", push, value)))
+}
+
+impl FromStr for Bytecode {
+    type Err = AsmParsingError;
+
+    /// Assembles the EVM assembly `asm`, made of the mnemonics of the opcodes
+    /// separated by whitespace, with comments from `//` to the end of the
+    /// line:
+    /// - `PUSH`n is followed by its value, in decimal or in hex with a `0x`
+    ///   prefix,
+    /// - `@label` defines a label, which is a jump destination defined with a
+    ///   JUMPDEST, and `JUMP @label`, `JUMPI @label` and `PUSH2 @label` refer
+    ///   to it before or after its definition, as in
+    ///   [`bytecode!`](crate::bytecode!),
+    /// - `INVALID(0x..)` writes a byte which isn't an opcode.
+    ///
+    /// The assembly of the [`Instruction`]s of [`Bytecode::disasm`] gives the
+    /// same code back.
+    fn from_str(asm: &str) -> Result<Self, Self::Err> {
+        let mut code = Bytecode::default();
+        let mut tokens = asm
+            .lines()
+            .flat_map(|line| {
+                line.split("//")
+                    .next()
+                    .unwrap_or_default()
+                    .split_whitespace()
+            })
+            .peekable();
+        while let Some(token) = tokens.next() {
+            if let Some(label) = token.strip_prefix('@') {
+                if code.labels.contains_key(label) {
+                    return Err(AsmParsingError::DuplicateLabel(label.to_string()));
+                }
+                code.label(label);
+                continue;
+            }
+            if let Some(byte) = token
+                .strip_prefix("INVALID(0x")
+                .and_then(|byte| byte.strip_suffix(')'))
+            {
+                let byte = u8::from_str_radix(byte, 16)
+                    .map_err(|_| AsmParsingError::UnknownOpcode(token.to_string()))?;
+                code.write_op_internal(byte);
+                continue;
+            }
+
+            let op = OpcodeId::from_str(&token.to_uppercase())
+                .map_err(|_| AsmParsingError::UnknownOpcode(token.to_string()))?;
+            let label = tokens
+                .next_if(|next| next.starts_with('@'))
+                .map(|next| &next[1..]);
+            match label {
+                Some(label) if op.is_push() || op == OpcodeId::JUMP || op == OpcodeId::JUMPI => {
+                    code.op_label(op, label);
+                }
+                Some(label) => {
+                    return Err(AsmParsingError::UnknownOpcode(format!(
+                        "{} @{}",
+                        token, label
+                    )));
+                }
+                None if op.is_push() => {
+                    let n = (op.as_u8() - OpcodeId::PUSH1.as_u8() + 1) as usize;
+                    let value = tokens
+                        .next()
+                        .ok_or_else(|| AsmParsingError::InvalidPushValue(token.to_string()))?;
+                    code.push(n, parse_push_value(token, value, n)?);
+                }
+                None => {
+                    code.write_op(op);
+                }
+            }
+        }
+        if !code.label_refs.is_empty() {
+            let mut labels: Vec<_> = code
+                .label_refs
+                .iter()
+                .map(|(_, _, label)| label.clone())
+                .collect();
+            labels.sort();
+            labels.dedup();
+            return Err(AsmParsingError::UndefinedLabels(labels));
+        }
+        Ok(code)
+    }
+}
+
 /// EVM code macro
+///
+/// Besides op codes, the code can contain:
+/// - `#[marker]` markers of the position of the next op code,
+/// - `@label` labels, which are jump destinations defined with a JUMPDEST, and
+///   `JUMP(@label)`, `JUMPI(@label)` and `PUSH2(@label)` which refer to them
+///   before or after their definition,
+/// - `.function(args)` calls of the methods of [`Bytecode`], like
+///   `.counted_loop(times, &body)`,
+/// - `{snippet}` inclusions of other [`Bytecode`]s.
 #[macro_export]
 macro_rules! bytecode {
     ($($args:tt)*) => {{
@@ -143,6 +464,16 @@ macro_rules! bytecode {
 macro_rules! bytecode_internal {
     // Nothing left to do
     ($code:ident, ) => {};
+    // Label, defined with a JUMPDEST
+    ($code:ident, @$label:ident $($rest:tt)*) => {{
+        $code.label(stringify!($label));
+        $crate::bytecode_internal!($code, $($rest)*);
+    }};
+    // PUSHX, JUMP and JUMPI op codes with a label
+    ($code:ident, $x:ident (@$label:ident) $($rest:tt)*) => {{
+        $code.op_label($crate::evm_types::OpcodeId::$x, stringify!($label));
+        $crate::bytecode_internal!($code, $($rest)*);
+    }};
     // PUSHX op codes
     ($code:ident, $x:ident ($v:expr) $($rest:tt)*) => {{
         debug_assert!($crate::evm_types::OpcodeId::$x.is_push(), "invalid push");
@@ -168,4 +499,157 @@ macro_rules! bytecode_internal {
         $code.$function($($args.into(),)*);
         $crate::bytecode_internal!($code, $($rest)*);
     }};
+    // Included snippet
+    ($code:ident, {$snippet:expr} $($rest:tt)*) => {{
+        $code.append(&$snippet);
+        $crate::bytecode_internal!($code, $($rest)*);
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::disasm;
+    use crate::error::AsmParsingError;
+    use crate::evm_types::OpcodeId;
+    use crate::{Bytecode, Word};
+
+    #[test]
+    fn bytecode_labels() {
+        let code = bytecode! {
+            PUSH1(0)
+            JUMPI(@end)
+            JUMP(@end)
+            PUSH2(@start)
+            @start
+            STOP
+            @end
+            STOP
+        };
+        assert_eq!(
+            code.to_vec(),
+            vec![
+                0x60, 0x00, 0x61, 0x00, 0x0f, 0x57, 0x61, 0x00, 0x0f, 0x56, 0x61, 0x00, 0x0d, 0x5b,
+                0x00, 0x5b, 0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn bytecode_snippets_and_loops() {
+        let body = bytecode! {
+            @inner
+            PUSH1(1)
+            POP
+        };
+        let code = bytecode! {
+            #[start]
+            JUMP(@inner)
+            {body}
+            .counted_loop(3, &Bytecode::default())
+            STOP
+        };
+        let bytes = code.to_vec();
+        assert_eq!(code.get_pos("start"), 0);
+        assert_eq!(bytes[..4], [0x61, 0x00, 0x04, 0x56]);
+        assert_eq!(bytes[4], 0x5b);
+        assert_eq!(bytes.last(), Some(&0x00));
+    }
+
+    #[test]
+    fn bytecode_disasm() {
+        let code = bytecode! {
+            PUSH1(0x80)
+            PUSH1(0x40)
+            MSTORE
+            @start
+            JUMP(@start)
+        };
+        let instructions = code.disasm();
+        assert_eq!(
+            instructions.iter().map(|ins| ins.pc).collect::<Vec<_>>(),
+            vec![0, 2, 4, 5, 6, 9]
+        );
+        assert_eq!(instructions[1].push_value(), Some(Word::from(0x40)));
+        assert_eq!(instructions[2].push_value(), None);
+        assert_eq!(
+            instructions
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "PUSH1 0x80",
+                "PUSH1 0x40",
+                "MSTORE",
+                "JUMPDEST",
+                "PUSH2 0x0005",
+                "JUMP"
+            ]
+        );
+
+        // Bytes which aren't opcodes and a PUSH cut by the end of the code
+        assert_eq!(
+            disasm(&[0x0c, 0xfe, 0x61, 0x01])
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["INVALID(0x0c)", "INVALID", "PUSH2 0x01"]
+        );
+        for byte in 0..=255u8 {
+            assert_eq!(OpcodeId::from(byte).as_u8(), byte);
+        }
+    }
+
+    #[test]
+    fn bytecode_asm() {
+        let code: Bytecode = "
+            PUSH1 0x80 PUSH1 64 MSTORE // free memory pointer
+            @start
+            push2 @end
+            JUMPI @start
+            @end
+            INVALID(0x0c)
+        "
+        .parse()
+        .unwrap();
+        let mut expected = bytecode! {
+            PUSH1(0x80)
+            PUSH1(64)
+            MSTORE
+            @start
+            PUSH2(@end)
+            JUMPI(@start)
+            @end
+        };
+        expected.write(0x0c);
+        assert_eq!(code.to_vec(), expected.to_vec());
+
+        let asm = code
+            .disasm()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(asm.parse::<Bytecode>().unwrap().to_vec(), code.to_vec());
+
+        assert_eq!(
+            "PUSH1 0x100".parse::<Bytecode>().unwrap_err(),
+            AsmParsingError::InvalidPushValue("PUSH1 0x100".to_string())
+        );
+        assert_eq!(
+            "PUSH1".parse::<Bytecode>().unwrap_err(),
+            AsmParsingError::InvalidPushValue("PUSH1".to_string())
+        );
+        assert_eq!(
+            "ADD FOO".parse::<Bytecode>().unwrap_err(),
+            AsmParsingError::UnknownOpcode("FOO".to_string())
+        );
+        assert_eq!(
+            "JUMP @nowhere".parse::<Bytecode>().unwrap_err(),
+            AsmParsingError::UndefinedLabels(vec!["nowhere".to_string()])
+        );
+        assert_eq!(
+            "@a @a".parse::<Bytecode>().unwrap_err(),
+            AsmParsingError::DuplicateLabel("a".to_string())
+        );
+    }
 }
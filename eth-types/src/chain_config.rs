@@ -0,0 +1,247 @@
+//! Rules of the chain a block is processed under, shared by bus-mapping, the
+//! geth tracer and the circuits so that they all agree on them.
+
+use crate::{evm_types::Hardfork, ChainConfigOverrides, GethLoggerConfig, Word};
+use serde::{Deserialize, Serialize};
+
+/// Rule of a chain on whether the signatures of its legacy transactions sign
+/// its chain id with EIP-155.  Typed transactions always sign the chain id, so
+/// they are only allowed if signing it is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Eip155Rule {
+    /// Only signatures without EIP-155, whose `v` is 27 or 28
+    Unprotected,
+    /// Only signatures with EIP-155, whose `v` is `35 + 2 * chain_id` or `36 +
+    /// 2 * chain_id`
+    Required,
+    /// Signatures either with or without EIP-155
+    Optional,
+}
+
+impl Default for Eip155Rule {
+    fn default() -> Self {
+        Self::Required
+    }
+}
+
+impl Eip155Rule {
+    /// Returns the allowed values of whether a transaction is signed with
+    /// EIP-155, repeated to have two of them.
+    pub fn allowed(&self) -> [bool; 2] {
+        match self {
+            Self::Unprotected => [false, false],
+            Self::Required => [true, true],
+            Self::Optional => [false, true],
+        }
+    }
+}
+
+/// Numbers of the first blocks of the hardforks after Berlin, `None` for the
+/// ones which aren't scheduled.  Berlin is the earliest hardfork supported, so
+/// the blocks before London are processed under it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForkBlocks {
+    /// First block of London
+    pub london: Option<u64>,
+    /// First block of Shanghai
+    pub shanghai: Option<u64>,
+}
+
+impl Default for ForkBlocks {
+    /// The default hardfork and the ones before it activated from the
+    /// genesis.
+    fn default() -> Self {
+        Self::from_genesis(Hardfork::default())
+    }
+}
+
+impl ForkBlocks {
+    /// Fork blocks of the Ethereum mainnet
+    pub const MAINNET: Self = Self {
+        london: Some(12_965_000),
+        shanghai: Some(17_034_870),
+    };
+
+    /// Returns the fork blocks activating `hardfork` and the ones before it
+    /// from the genesis.
+    pub fn from_genesis(hardfork: Hardfork) -> Self {
+        Self {
+            london: (hardfork >= Hardfork::London).then(|| 0),
+            shanghai: (hardfork >= Hardfork::Shanghai).then(|| 0),
+        }
+    }
+
+    /// Fork blocks of the Goerli testnet
+    pub const GOERLI: Self = Self {
+        london: Some(5_062_605),
+        shanghai: Some(8_656_123),
+    };
+
+    /// Fork blocks of the Sepolia testnet
+    pub const SEPOLIA: Self = Self {
+        london: Some(0),
+        shanghai: Some(2_990_908),
+    };
+
+    /// Chain id of the geth dev chain, which activates the default hardfork
+    /// from the genesis.
+    pub const DEV_CHAIN_ID: u64 = 1337;
+
+    /// Returns the fork blocks of the chain `chain_id`, `None` if the chain
+    /// isn't known.
+    pub fn of_chain(chain_id: Word) -> Option<Self> {
+        if chain_id > Word::from(u64::MAX) {
+            return None;
+        }
+        match chain_id.as_u64() {
+            1 => Some(Self::MAINNET),
+            5 => Some(Self::GOERLI),
+            11_155_111 => Some(Self::SEPOLIA),
+            Self::DEV_CHAIN_ID => Some(Self::default()),
+            _ => None,
+        }
+    }
+
+    /// Returns the latest hardfork activated at the block `number`.
+    pub fn hardfork_at(&self, number: u64) -> Hardfork {
+        let is_active = |fork_block: Option<u64>| fork_block.map_or(false, |block| block <= number);
+        if is_active(self.shanghai) {
+            Hardfork::Shanghai
+        } else if is_active(self.london) {
+            Hardfork::London
+        } else {
+            Hardfork::Berlin
+        }
+    }
+}
+
+/// Chain id, activated hardfork and EIP flags of a chain.  The hardfork is the
+/// latest one activated at the block processed, all the previous ones being
+/// activated too, which is derived from the fork blocks by
+/// [`ChainConfig::at_block`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainConfig {
+    /// Chain id
+    pub chain_id: Word,
+    /// Latest activated hardfork, whose gas rules are used
+    pub hardfork: Hardfork,
+    /// First blocks of the hardforks of the chain
+    pub fork_blocks: ForkBlocks,
+    /// Rule on signing the chain id in the transactions
+    pub eip155_rule: Eip155Rule,
+}
+
+impl ChainConfig {
+    /// Returns the config of the chain `chain_id` with the default fork blocks
+    /// and EIP-155 rule.
+    pub fn new(chain_id: Word) -> Self {
+        Self {
+            chain_id,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the config with `hardfork` and the ones before it activated
+    /// from the genesis.
+    pub fn with_hardfork(self, hardfork: Hardfork) -> Self {
+        Self {
+            hardfork,
+            fork_blocks: ForkBlocks::from_genesis(hardfork),
+            ..self
+        }
+    }
+
+    /// Returns the config with `fork_blocks` as the first blocks of the
+    /// hardforks, and the hardfork activated at the genesis.
+    pub fn with_fork_blocks(self, fork_blocks: ForkBlocks) -> Self {
+        Self {
+            fork_blocks,
+            ..self
+        }
+        .at_block(0)
+    }
+
+    /// Returns the config processing the block `number`, whose hardfork is
+    /// the latest one activated at it.
+    pub fn at_block(self, number: u64) -> Self {
+        Self {
+            hardfork: self.fork_blocks.hardfork_at(number),
+            ..self
+        }
+    }
+
+    /// Returns whether `hardfork` is activated.
+    pub fn is_active(&self, hardfork: Hardfork) -> bool {
+        self.hardfork >= hardfork
+    }
+
+    /// Returns the fork blocks of geth activating the hardfork from the
+    /// genesis.  The geth tracer doesn't know Shanghai, which is traced as
+    /// London: the init code charge of creation transactions is left to
+    /// bus-mapping.
+    pub fn geth_overrides(&self) -> ChainConfigOverrides {
+        match self.hardfork {
+            Hardfork::Berlin => ChainConfigOverrides::berlin(),
+            Hardfork::London | Hardfork::Shanghai => ChainConfigOverrides::london(),
+        }
+    }
+
+    /// Returns `logger_config` tracing under the hardfork of the chain.
+    pub fn logger_config(&self, logger_config: GethLoggerConfig) -> GethLoggerConfig {
+        GethLoggerConfig {
+            overrides: Some(self.geth_overrides()),
+            ..logger_config
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_config_hardforks() {
+        let config = ChainConfig::new(Word::from(1));
+        assert_eq!(config.hardfork, Hardfork::London);
+        assert!(config.is_active(Hardfork::Berlin) && config.is_active(Hardfork::London));
+        assert!(!config.is_active(Hardfork::Shanghai));
+        assert_eq!(config.geth_overrides(), ChainConfigOverrides::london());
+
+        let berlin = config.with_hardfork(Hardfork::Berlin);
+        assert!(!berlin.is_active(Hardfork::London));
+        assert_eq!(berlin.geth_overrides(), ChainConfigOverrides::berlin());
+        assert_eq!(
+            berlin.logger_config(GethLoggerConfig::minimal()).overrides,
+            Some(ChainConfigOverrides::berlin())
+        );
+        assert_eq!(
+            config.with_hardfork(Hardfork::Shanghai).geth_overrides(),
+            ChainConfigOverrides::london()
+        );
+        assert_eq!(berlin.at_block(0xcafe).hardfork, Hardfork::Berlin);
+    }
+
+    #[test]
+    fn chain_config_hardfork_at_block() {
+        let config = ChainConfig::new(Word::one())
+            .with_fork_blocks(ForkBlocks::of_chain(Word::one()).unwrap());
+        assert_eq!(config.hardfork, Hardfork::Berlin);
+        assert_eq!(config.at_block(12_964_999).hardfork, Hardfork::Berlin);
+        assert_eq!(config.at_block(12_965_000).hardfork, Hardfork::London);
+        assert_eq!(config.at_block(17_034_870).hardfork, Hardfork::Shanghai);
+
+        let config = ChainConfig::new(Word::from(1338)).at_block(0xcafe);
+        assert_eq!(config.hardfork, Hardfork::default());
+        assert_eq!(
+            ForkBlocks::of_chain(Word::from(ForkBlocks::DEV_CHAIN_ID)),
+            Some(ForkBlocks::default())
+        );
+        assert_eq!(
+            ForkBlocks::of_chain(Word::from(11_155_111))
+                .unwrap()
+                .hardfork_at(2_990_907),
+            Hardfork::London
+        );
+        assert_eq!(ForkBlocks::of_chain(Word::from(1338)), None);
+    }
+}
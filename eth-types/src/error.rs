@@ -47,3 +47,30 @@ pub enum EthAddressParsingError {
     /// Hex decoding error
     Hex(hex::FromHexError),
 }
+
+/// Error type for a failure while parsing EVM assembly into a
+/// [`Bytecode`](crate::Bytecode).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmParsingError {
+    /// Mnemonic which isn't an opcode
+    UnknownOpcode(String),
+    /// PUSH without a value, or whose value isn't a number fitting in it
+    InvalidPushValue(String),
+    /// Label defined more than once
+    DuplicateLabel(String),
+    /// Labels referred to but never defined
+    UndefinedLabels(Vec<String>),
+}
+
+impl Display for AsmParsingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::UnknownOpcode(op) => write!(f, "unknown opcode {}", op),
+            Self::InvalidPushValue(push) => write!(f, "invalid push value in {}", push),
+            Self::DuplicateLabel(label) => write!(f, "label {} defined twice", label),
+            Self::UndefinedLabels(labels) => write!(f, "undefined labels {:?}", labels),
+        }
+    }
+}
+
+impl StdError for AsmParsingError {}
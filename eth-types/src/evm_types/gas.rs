@@ -0,0 +1,513 @@
+//! Gas costs of the EVM, shared by the witness generation of bus-mapping and
+//! by the EVM circuit, so that the gas charged by the witness and the one
+//! constrained by the circuit can't drift apart.
+//!
+//! [`GasCost`] holds the constant costs, which `OpcodeId::constant_gas_cost`
+//! and the fixed table of the EVM circuit are built from, and the functions
+//! of this module the dynamic ones: memory expansion, copies, storage and
+//! account accesses.  The costs and refunds which changed with a hardfork,
+//! like the refunds of `SSTORE` or the cost of the init code of a creation,
+//! are methods of [`Hardfork`].
+
+use super::OpcodeId;
+use crate::{AccessList, Word};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Defines the gas consumption.
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GasCost(pub u64);
+
+impl fmt::Debug for GasCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("{}", self.0))
+    }
+}
+
+impl GasCost {
+    /// Constant cost for free step
+    pub const ZERO: Self = Self(0);
+    /// Constant cost for jumpdest step, only takes one gas
+    pub const ONE: Self = Self(1);
+    /// Constant cost for quick step
+    pub const QUICK: Self = Self(2);
+    /// Constant cost for fastest step
+    pub const FASTEST: Self = Self(3);
+    /// Constant cost for fast step
+    pub const FAST: Self = Self(5);
+    /// Constant cost for mid step
+    pub const MID: Self = Self(8);
+    /// Constant cost for slow step
+    pub const SLOW: Self = Self(10);
+    /// Constant cost for ext step
+    pub const EXT: Self = Self(20);
+    /// Constant cost for SHA3
+    pub const SHA3: Self = Self(30);
+    /// Constant cost for SELFDESTRUCT
+    pub const SELFDESTRUCT: Self = Self(5000);
+    /// Constant cost for CREATE
+    pub const CREATE: Self = Self(32000);
+    /// Constant cost for every byte of the exponent of EXP
+    pub const EXP_BYTE_TIMES: Self = Self(50);
+    /// Constant cost for every word hashed by SHA3, or by CREATE2 for the
+    /// address
+    pub const SHA3_WORD: Self = Self(6);
+    /// Constant cost for every additional word when expanding memory
+    pub const MEMORY: Self = Self(3);
+    /// Constant cost for copying every word
+    pub const COPY: Self = Self(3);
+    /// Constant cost for every byte of the code stored by a creation
+    pub const CODE_DEPOSIT_BYTE_COST: Self = Self(200);
+    /// Constant cost for a cold SLOAD
+    pub const COLD_SLOAD_COST: Self = Self(2100);
+    /// Constant cost for a cold account access
+    pub const COLD_ACCOUNT_ACCESS_COST: Self = Self(2600);
+    /// Constant cost for a warm storage read
+    pub const WARM_STORAGE_READ_COST: Self = Self(100);
+    /// Constant cost for a basic storage operation
+    pub const SLOAD_GAS: Self = Self(100);
+    /// Constant cost for a storage set
+    pub const SSTORE_SET_GAS: Self = Self(20000);
+    /// Constant cost for a storage reset
+    pub const SSTORE_RESET_GAS: Self = Self(2900);
+    /// Constant cost for a non-creation transaction
+    pub const TX: Self = Self(21000);
+    /// Constant cost for creation transaction
+    pub const CREATION_TX: Self = Self(53000);
+    /// Constant cost for every zero byte of the call data of a transaction
+    pub const TX_DATA_ZERO: Self = Self(4);
+    /// Constant cost for every non-zero byte of the call data of a
+    /// transaction
+    pub const TX_DATA_NON_ZERO: Self = Self(16);
+    /// Constant cost for every address of the access list of a transaction
+    pub const TX_ACCESS_LIST_ADDRESS: Self = Self(2400);
+    /// Constant cost for every storage key of the access list of a transaction
+    pub const TX_ACCESS_LIST_STORAGE_KEY: Self = Self(1900);
+    /// Denominator of quadratic part of memory expansion gas cost
+    pub const MEMORY_EXPANSION_QUAD_DENOMINATOR: Self = Self(512);
+    /// Coefficient of linear part of memory expansion gas cost
+    pub const MEMORY_EXPANSION_LINEAR_COEFF: Self = Self(3);
+}
+
+impl GasCost {
+    /// Returns the `GasCost` as a `u64`.
+    #[inline]
+    pub const fn as_u64(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns the `GasCost` as a `usize`.
+    #[inline]
+    pub const fn as_usize(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl From<u8> for GasCost {
+    fn from(cost: u8) -> Self {
+        GasCost(cost as u64)
+    }
+}
+
+impl From<u64> for GasCost {
+    fn from(cost: u64) -> Self {
+        GasCost(cost)
+    }
+}
+
+/// Hardforks with different gas rules, which are provided by the methods of
+/// `Hardfork` instead of the constants in `GasCost`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Hardfork {
+    /// Berlin
+    Berlin,
+    /// London, which reduces the gas refunds (EIP-3529)
+    London,
+    /// Shanghai, which charges the init code of creations (EIP-3860) and
+    /// introduces PUSH0 (EIP-3855)
+    Shanghai,
+}
+
+impl Default for Hardfork {
+    /// London, which is the hardfork the geth tracer is configured with.
+    fn default() -> Self {
+        Self::London
+    }
+}
+
+impl Hardfork {
+    /// Returns whether `opcode` is defined in the hardfork, the opcodes
+    /// introduced by a later hardfork being invalid, like BASEFEE before
+    /// London (EIP-3198) and PUSH0 before Shanghai (EIP-3855).
+    pub fn is_opcode_defined(&self, opcode: OpcodeId) -> bool {
+        match opcode {
+            OpcodeId::BASEFEE => *self >= Self::London,
+            OpcodeId::PUSH0 => *self >= Self::Shanghai,
+            _ => true,
+        }
+    }
+
+    /// Returns the gas refunded for clearing a storage slot.
+    pub const fn sstore_clears_schedule(&self) -> GasCost {
+        match self {
+            Self::Berlin => GasCost(15000),
+            Self::London | Self::Shanghai => GasCost(4800),
+        }
+    }
+
+    /// Returns the quotient of gas used, which caps the refund of a
+    /// transaction.
+    pub const fn max_refund_quotient_of_gas_used(&self) -> u64 {
+        match self {
+            Self::Berlin => 2,
+            Self::London | Self::Shanghai => 5,
+        }
+    }
+
+    /// Returns the gas charged for every word of the init code of a creation
+    /// transaction.
+    pub const fn init_code_word_cost(&self) -> GasCost {
+        match self {
+            Self::Berlin | Self::London => GasCost::ZERO,
+            Self::Shanghai => GasCost(2),
+        }
+    }
+
+    /// Returns the refund counter after an `SSTORE` of `value` to a slot
+    /// holding `value_prev`, whose value at the start of the transaction was
+    /// `committed_value`, with the refund counter at `refund` (EIP-2200,
+    /// EIP-3529).
+    pub fn sstore_refund(
+        &self,
+        mut refund: u64,
+        value: Word,
+        value_prev: Word,
+        committed_value: Word,
+    ) -> u64 {
+        let clears_schedule = self.sstore_clears_schedule().as_u64();
+        if value_prev == value {
+            return refund;
+        }
+        if committed_value == value_prev {
+            if !committed_value.is_zero() && value.is_zero() {
+                refund += clears_schedule;
+            }
+            return refund;
+        }
+        if !committed_value.is_zero() {
+            if value_prev.is_zero() {
+                refund -= clears_schedule;
+            }
+            if value.is_zero() {
+                refund += clears_schedule;
+            }
+        }
+        if committed_value == value {
+            refund += if committed_value.is_zero() {
+                GasCost::SSTORE_SET_GAS.as_u64() - GasCost::SLOAD_GAS.as_u64()
+            } else {
+                GasCost::SSTORE_RESET_GAS.as_u64() - GasCost::SLOAD_GAS.as_u64()
+            };
+        }
+        refund
+    }
+
+    /// Returns the gas refunded at the end of a transaction which used
+    /// `gas_used` gas, with the refund counter at `refund`, which is capped
+    /// by a quotient of the gas used.
+    pub const fn effective_refund(&self, refund: u64, gas_used: u64) -> u64 {
+        let max_refund = gas_used / self.max_refund_quotient_of_gas_used();
+        if refund < max_refund {
+            refund
+        } else {
+            max_refund
+        }
+    }
+
+    /// Returns the gas cost of a CREATE, or of a CREATE2 which hashes its init
+    /// code for the address, of `init_code_length` bytes of init code, besides
+    /// the expansion of the memory (EIP-1014, EIP-3860).
+    pub const fn create_gas_cost(&self, is_create2: bool, init_code_length: u64) -> GasCost {
+        let words = (init_code_length + 31) / 32;
+        let hash_cost = if is_create2 {
+            GasCost::SHA3_WORD.as_u64()
+        } else {
+            0
+        };
+        GasCost(
+            GasCost::CREATE.as_u64() + words * (self.init_code_word_cost().as_u64() + hash_cost),
+        )
+    }
+
+    /// Returns the intrinsic gas cost of a transaction, whose call data gas
+    /// cost is `call_data_gas_cost`, including the gas of its `access_list`
+    /// (EIP-2930).
+    pub fn tx_intrinsic_gas_cost(
+        &self,
+        is_create: bool,
+        call_data_length: u64,
+        call_data_gas_cost: u64,
+        access_list: &AccessList,
+    ) -> u64 {
+        let access_list_gas_cost = access_list
+            .0
+            .iter()
+            .map(|item| {
+                GasCost::TX_ACCESS_LIST_ADDRESS.as_u64()
+                    + item.storage_keys.len() as u64 * GasCost::TX_ACCESS_LIST_STORAGE_KEY.as_u64()
+            })
+            .sum::<u64>();
+        let base_gas_cost = if is_create {
+            GasCost::CREATION_TX.as_u64()
+                + (call_data_length + 31) / 32 * self.init_code_word_cost().as_u64()
+        } else {
+            GasCost::TX.as_u64()
+        };
+        base_gas_cost + call_data_gas_cost + access_list_gas_cost
+    }
+}
+
+/// Returns the gas cost of the call data `input` of a transaction.
+pub fn call_data_gas_cost(input: &[u8]) -> u64 {
+    input
+        .iter()
+        .map(|byte| {
+            if *byte == 0 {
+                GasCost::TX_DATA_ZERO.as_u64()
+            } else {
+                GasCost::TX_DATA_NON_ZERO.as_u64()
+            }
+        })
+        .sum()
+}
+
+/// Returns the gas cost of a memory of `memory_word_size` words, which is
+/// linear in its size with a quadratic part for large memories.
+pub const fn memory_gas_cost(memory_word_size: u64) -> u64 {
+    memory_word_size * GasCost::MEMORY_EXPANSION_LINEAR_COEFF.as_u64()
+        + memory_word_size * memory_word_size / GasCost::MEMORY_EXPANSION_QUAD_DENOMINATOR.as_u64()
+}
+
+/// Returns the gas cost of the expansion of the memory from
+/// `curr_memory_word_size` to `next_memory_word_size` words, which is free if
+/// the memory doesn't grow.
+pub const fn memory_expansion_gas_cost(
+    curr_memory_word_size: u64,
+    next_memory_word_size: u64,
+) -> u64 {
+    if next_memory_word_size <= curr_memory_word_size {
+        0
+    } else {
+        memory_gas_cost(next_memory_word_size) - memory_gas_cost(curr_memory_word_size)
+    }
+}
+
+/// Returns the gas cost of copying `num_copy_bytes` bytes to the memory,
+/// which expands from `curr_memory_word_size` to `next_memory_word_size`
+/// words, like CALLDATACOPY or MCOPY do.
+pub const fn memory_copier_gas_cost(
+    curr_memory_word_size: u64,
+    next_memory_word_size: u64,
+    num_copy_bytes: u64,
+) -> u64 {
+    (num_copy_bytes + 31) / 32 * GasCost::COPY.as_u64()
+        + memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size)
+}
+
+/// Returns the gas cost of an SLOAD of a warm or cold slot (EIP-2929).
+pub const fn sload_gas_cost(is_warm: bool) -> GasCost {
+    if is_warm {
+        GasCost::WARM_STORAGE_READ_COST
+    } else {
+        GasCost::COLD_SLOAD_COST
+    }
+}
+
+/// Returns the gas cost of an access to a warm or cold account, like a
+/// BALANCE or an EXTCODESIZE (EIP-2929).
+pub const fn account_access_gas_cost(is_warm: bool) -> GasCost {
+    if is_warm {
+        GasCost::WARM_STORAGE_READ_COST
+    } else {
+        GasCost::COLD_ACCOUNT_ACCESS_COST
+    }
+}
+
+/// Returns the gas cost of an `SSTORE` of `value` to a warm or cold slot
+/// holding `value_prev`, whose value at the start of the transaction was
+/// `committed_value` (EIP-2200, EIP-2929).
+pub fn sstore_gas_cost(
+    value: Word,
+    value_prev: Word,
+    committed_value: Word,
+    is_warm: bool,
+) -> GasCost {
+    let warm_case_gas = if value_prev == value || committed_value != value_prev {
+        GasCost::SLOAD_GAS
+    } else if committed_value.is_zero() {
+        GasCost::SSTORE_SET_GAS
+    } else {
+        GasCost::SSTORE_RESET_GAS
+    };
+    if is_warm {
+        warm_case_gas
+    } else {
+        GasCost(warm_case_gas.as_u64() + GasCost::COLD_SLOAD_COST.as_u64())
+    }
+}
+
+#[cfg(test)]
+mod hardfork_tests {
+    use super::*;
+    use crate::{AccessListItem, Address, H256};
+
+    // Returns the gas used and the refund of the `SSTORE`s of `values` to a
+    // warm slot holding `committed_value`, with the 2 pushes of each of them.
+    fn sstores(hardfork: Hardfork, committed_value: u64, values: &[u64]) -> (u64, u64) {
+        let mut value_prev = Word::from(committed_value);
+        let (mut gas_used, mut refund) = (0, 0);
+        for value in values.iter().map(|value| Word::from(*value)) {
+            gas_used += 2 * GasCost::FASTEST.as_u64()
+                + sstore_gas_cost(value, value_prev, committed_value.into(), true).as_u64();
+            refund = hardfork.sstore_refund(refund, value, value_prev, committed_value.into());
+            value_prev = value;
+        }
+        (gas_used, refund)
+    }
+
+    #[test]
+    fn sstore_gas_and_refund_of_eip_3529() {
+        // The test cases of EIP-3529, with the slot warm: (original value,
+        // values stored, gas used, refund).
+        for (committed_value, values, gas_used, refund) in [
+            (0, vec![0, 0], 212, 0),
+            (0, vec![0, 1], 20112, 0),
+            (0, vec![1, 0], 20112, 19900),
+            (0, vec![1, 2], 20112, 0),
+            (0, vec![1, 1], 20112, 0),
+            (1, vec![0, 0], 3012, 4800),
+            (1, vec![0, 1], 3012, 2800),
+            (1, vec![0, 2], 3012, 0),
+            (1, vec![2, 0], 3012, 4800),
+            (1, vec![2, 3], 3012, 0),
+            (1, vec![2, 1], 3012, 2800),
+            (1, vec![2, 2], 3012, 0),
+            (1, vec![1, 0], 3012, 4800),
+            (1, vec![1, 2], 3012, 0),
+            (1, vec![1, 1], 212, 0),
+            (0, vec![1, 0, 1], 40118, 19900),
+            (1, vec![0, 1, 0], 5918, 7600),
+        ] {
+            assert_eq!(
+                sstores(Hardfork::London, committed_value, &values),
+                (gas_used, refund),
+                "original value {} and values {:?}",
+                committed_value,
+                values
+            );
+        }
+        // The refund of clearing a slot before EIP-3529 (EIP-2200)
+        assert_eq!(sstores(Hardfork::Berlin, 1, &[0]).1, 15000);
+        // The cold access of EIP-2929
+        assert_eq!(
+            sstore_gas_cost(Word::one(), Word::zero(), Word::zero(), false),
+            GasCost(22100)
+        );
+    }
+
+    #[test]
+    fn effective_refund_is_capped() {
+        assert_eq!(Hardfork::London.effective_refund(4800, 30000), 4800);
+        assert_eq!(Hardfork::London.effective_refund(19900, 40118), 8023);
+        assert_eq!(Hardfork::Berlin.effective_refund(19900, 30000), 15000);
+    }
+
+    #[test]
+    fn opcodes_defined_by_hardfork() {
+        assert!(!Hardfork::Berlin.is_opcode_defined(OpcodeId::BASEFEE));
+        assert!(Hardfork::London.is_opcode_defined(OpcodeId::BASEFEE));
+        assert!(!Hardfork::London.is_opcode_defined(OpcodeId::PUSH0));
+        assert!(Hardfork::Shanghai.is_opcode_defined(OpcodeId::PUSH0));
+        assert!(Hardfork::Berlin.is_opcode_defined(OpcodeId::ADD));
+    }
+
+    #[test]
+    fn create_gas_cost() {
+        // 33 bytes of init code are 2 words.
+        assert_eq!(Hardfork::London.create_gas_cost(false, 33), GasCost(32000));
+        assert_eq!(
+            Hardfork::London.create_gas_cost(true, 33),
+            GasCost(32000 + 2 * 6)
+        );
+        assert_eq!(
+            Hardfork::Shanghai.create_gas_cost(true, 33),
+            GasCost(32000 + 2 * (6 + 2))
+        );
+    }
+
+    #[test]
+    fn tx_intrinsic_gas_cost() {
+        let empty = AccessList::default();
+        for hardfork in [Hardfork::Berlin, Hardfork::London, Hardfork::Shanghai] {
+            assert_eq!(
+                hardfork.tx_intrinsic_gas_cost(false, 33, 528, &empty),
+                21528
+            );
+        }
+        assert_eq!(
+            Hardfork::London.tx_intrinsic_gas_cost(true, 33, 528, &empty),
+            53528
+        );
+        assert_eq!(
+            Hardfork::Shanghai.tx_intrinsic_gas_cost(true, 33, 528, &empty),
+            53528 + 2 * 2
+        );
+
+        let access_list = AccessList(vec![
+            AccessListItem {
+                address: Address::zero(),
+                storage_keys: vec![H256::zero(), H256::repeat_byte(1)],
+            },
+            AccessListItem {
+                address: Address::repeat_byte(1),
+                storage_keys: vec![],
+            },
+        ]);
+        assert_eq!(
+            Hardfork::London.tx_intrinsic_gas_cost(false, 0, 0, &access_list),
+            21000 + 2 * 2400 + 2 * 1900
+        );
+    }
+}
+
+#[cfg(test)]
+mod gas_tests {
+    use super::*;
+
+    #[test]
+    fn memory_gas_cost_of_yellow_paper() {
+        // C_mem(a) = G_memory * a + floor(a^2 / 512)
+        assert_eq!(memory_gas_cost(0), 0);
+        assert_eq!(memory_gas_cost(1), 3);
+        assert_eq!(memory_gas_cost(32), 98);
+        assert_eq!(memory_gas_cost(1024), 5120);
+        assert_eq!(memory_expansion_gas_cost(1, 32), 95);
+        assert_eq!(memory_expansion_gas_cost(32, 1), 0);
+        // 33 bytes are copied as 2 words.
+        assert_eq!(memory_copier_gas_cost(0, 2, 33), 6 + 6);
+    }
+
+    #[test]
+    fn access_gas_costs_of_eip_2929() {
+        assert_eq!(sload_gas_cost(true), GasCost(100));
+        assert_eq!(sload_gas_cost(false), GasCost(2100));
+        assert_eq!(account_access_gas_cost(true), GasCost(100));
+        assert_eq!(account_access_gas_cost(false), GasCost(2600));
+    }
+
+    #[test]
+    fn call_data_gas_cost_of_eip_2028() {
+        assert_eq!(call_data_gas_cost(&[]), 0);
+        assert_eq!(call_data_gas_cost(&[0, 1, 0, 0xff]), 4 + 16 + 4 + 16);
+    }
+}
@@ -4,10 +4,11 @@ use crate::{DebugByte, ToBigEndian, Word};
 use core::convert::TryFrom;
 use core::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Sub, SubAssign};
 use core::str::FromStr;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Represents a `MemoryAddress` of the EVM.
-#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct MemoryAddress(pub usize);
 
 impl fmt::Debug for MemoryAddress {
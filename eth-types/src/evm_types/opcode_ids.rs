@@ -7,7 +7,7 @@ use serde::{de, Deserialize, Serialize};
 use std::str::FromStr;
 
 /// Opcode enum. One-to-one corresponding to an `u8` value.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum OpcodeId {
     /// `STOP`
     STOP,
@@ -92,6 +92,10 @@ pub enum OpcodeId {
     MSIZE,
     /// `JUMPDEST`
     JUMPDEST,
+    /// `MCOPY`
+    MCOPY,
+    /// `PUSH0`
+    PUSH0,
 
     // PUSHn
     /// `PUSH1`
@@ -374,6 +378,8 @@ impl OpcodeId {
             OpcodeId::PC => 0x58u8,
             OpcodeId::MSIZE => 0x59u8,
             OpcodeId::JUMPDEST => 0x5bu8,
+            OpcodeId::MCOPY => 0x5eu8,
+            OpcodeId::PUSH0 => 0x5fu8,
             OpcodeId::PUSH1 => 0x60u8,
             OpcodeId::PUSH2 => 0x61u8,
             OpcodeId::PUSH3 => 0x62u8,
@@ -552,6 +558,8 @@ impl OpcodeId {
             OpcodeId::MSIZE => GasCost::QUICK,
             OpcodeId::GAS => GasCost::QUICK,
             OpcodeId::JUMPDEST => GasCost::ONE,
+            OpcodeId::MCOPY => GasCost::FASTEST,
+            OpcodeId::PUSH0 => GasCost::QUICK,
             OpcodeId::PUSH1 => GasCost::FASTEST,
             OpcodeId::PUSH2 => GasCost::FASTEST,
             OpcodeId::PUSH3 => GasCost::FASTEST,
@@ -635,6 +643,158 @@ impl OpcodeId {
     }
 }
 
+impl From<u8> for OpcodeId {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00u8 => OpcodeId::STOP,
+            0x01u8 => OpcodeId::ADD,
+            0x02u8 => OpcodeId::MUL,
+            0x03u8 => OpcodeId::SUB,
+            0x04u8 => OpcodeId::DIV,
+            0x05u8 => OpcodeId::SDIV,
+            0x06u8 => OpcodeId::MOD,
+            0x07u8 => OpcodeId::SMOD,
+            0x08u8 => OpcodeId::ADDMOD,
+            0x09u8 => OpcodeId::MULMOD,
+            0x0au8 => OpcodeId::EXP,
+            0x0bu8 => OpcodeId::SIGNEXTEND,
+            0x10u8 => OpcodeId::LT,
+            0x11u8 => OpcodeId::GT,
+            0x12u8 => OpcodeId::SLT,
+            0x13u8 => OpcodeId::SGT,
+            0x14u8 => OpcodeId::EQ,
+            0x15u8 => OpcodeId::ISZERO,
+            0x16u8 => OpcodeId::AND,
+            0x17u8 => OpcodeId::OR,
+            0x18u8 => OpcodeId::XOR,
+            0x19u8 => OpcodeId::NOT,
+            0x1au8 => OpcodeId::BYTE,
+            0x1bu8 => OpcodeId::SHL,
+            0x1cu8 => OpcodeId::SHR,
+            0x1du8 => OpcodeId::SAR,
+            0x20u8 => OpcodeId::SHA3,
+            0x30u8 => OpcodeId::ADDRESS,
+            0x31u8 => OpcodeId::BALANCE,
+            0x32u8 => OpcodeId::ORIGIN,
+            0x33u8 => OpcodeId::CALLER,
+            0x34u8 => OpcodeId::CALLVALUE,
+            0x35u8 => OpcodeId::CALLDATALOAD,
+            0x36u8 => OpcodeId::CALLDATASIZE,
+            0x37u8 => OpcodeId::CALLDATACOPY,
+            0x38u8 => OpcodeId::CODESIZE,
+            0x39u8 => OpcodeId::CODECOPY,
+            0x3au8 => OpcodeId::GASPRICE,
+            0x3bu8 => OpcodeId::EXTCODESIZE,
+            0x3cu8 => OpcodeId::EXTCODECOPY,
+            0x3du8 => OpcodeId::RETURNDATASIZE,
+            0x3eu8 => OpcodeId::RETURNDATACOPY,
+            0x3fu8 => OpcodeId::EXTCODEHASH,
+            0x40u8 => OpcodeId::BLOCKHASH,
+            0x41u8 => OpcodeId::COINBASE,
+            0x42u8 => OpcodeId::TIMESTAMP,
+            0x43u8 => OpcodeId::NUMBER,
+            0x44u8 => OpcodeId::DIFFICULTY,
+            0x45u8 => OpcodeId::GASLIMIT,
+            0x46u8 => OpcodeId::CHAINID,
+            0x47u8 => OpcodeId::SELFBALANCE,
+            0x48u8 => OpcodeId::BASEFEE,
+            0x50u8 => OpcodeId::POP,
+            0x51u8 => OpcodeId::MLOAD,
+            0x52u8 => OpcodeId::MSTORE,
+            0x53u8 => OpcodeId::MSTORE8,
+            0x54u8 => OpcodeId::SLOAD,
+            0x55u8 => OpcodeId::SSTORE,
+            0x56u8 => OpcodeId::JUMP,
+            0x57u8 => OpcodeId::JUMPI,
+            0x58u8 => OpcodeId::PC,
+            0x59u8 => OpcodeId::MSIZE,
+            0x5au8 => OpcodeId::GAS,
+            0x5bu8 => OpcodeId::JUMPDEST,
+            0x5eu8 => OpcodeId::MCOPY,
+            0x5fu8 => OpcodeId::PUSH0,
+            0x60u8 => OpcodeId::PUSH1,
+            0x61u8 => OpcodeId::PUSH2,
+            0x62u8 => OpcodeId::PUSH3,
+            0x63u8 => OpcodeId::PUSH4,
+            0x64u8 => OpcodeId::PUSH5,
+            0x65u8 => OpcodeId::PUSH6,
+            0x66u8 => OpcodeId::PUSH7,
+            0x67u8 => OpcodeId::PUSH8,
+            0x68u8 => OpcodeId::PUSH9,
+            0x69u8 => OpcodeId::PUSH10,
+            0x6au8 => OpcodeId::PUSH11,
+            0x6bu8 => OpcodeId::PUSH12,
+            0x6cu8 => OpcodeId::PUSH13,
+            0x6du8 => OpcodeId::PUSH14,
+            0x6eu8 => OpcodeId::PUSH15,
+            0x6fu8 => OpcodeId::PUSH16,
+            0x70u8 => OpcodeId::PUSH17,
+            0x71u8 => OpcodeId::PUSH18,
+            0x72u8 => OpcodeId::PUSH19,
+            0x73u8 => OpcodeId::PUSH20,
+            0x74u8 => OpcodeId::PUSH21,
+            0x75u8 => OpcodeId::PUSH22,
+            0x76u8 => OpcodeId::PUSH23,
+            0x77u8 => OpcodeId::PUSH24,
+            0x78u8 => OpcodeId::PUSH25,
+            0x79u8 => OpcodeId::PUSH26,
+            0x7au8 => OpcodeId::PUSH27,
+            0x7bu8 => OpcodeId::PUSH28,
+            0x7cu8 => OpcodeId::PUSH29,
+            0x7du8 => OpcodeId::PUSH30,
+            0x7eu8 => OpcodeId::PUSH31,
+            0x7fu8 => OpcodeId::PUSH32,
+            0x80u8 => OpcodeId::DUP1,
+            0x81u8 => OpcodeId::DUP2,
+            0x82u8 => OpcodeId::DUP3,
+            0x83u8 => OpcodeId::DUP4,
+            0x84u8 => OpcodeId::DUP5,
+            0x85u8 => OpcodeId::DUP6,
+            0x86u8 => OpcodeId::DUP7,
+            0x87u8 => OpcodeId::DUP8,
+            0x88u8 => OpcodeId::DUP9,
+            0x89u8 => OpcodeId::DUP10,
+            0x8au8 => OpcodeId::DUP11,
+            0x8bu8 => OpcodeId::DUP12,
+            0x8cu8 => OpcodeId::DUP13,
+            0x8du8 => OpcodeId::DUP14,
+            0x8eu8 => OpcodeId::DUP15,
+            0x8fu8 => OpcodeId::DUP16,
+            0x90u8 => OpcodeId::SWAP1,
+            0x91u8 => OpcodeId::SWAP2,
+            0x92u8 => OpcodeId::SWAP3,
+            0x93u8 => OpcodeId::SWAP4,
+            0x94u8 => OpcodeId::SWAP5,
+            0x95u8 => OpcodeId::SWAP6,
+            0x96u8 => OpcodeId::SWAP7,
+            0x97u8 => OpcodeId::SWAP8,
+            0x98u8 => OpcodeId::SWAP9,
+            0x99u8 => OpcodeId::SWAP10,
+            0x9au8 => OpcodeId::SWAP11,
+            0x9bu8 => OpcodeId::SWAP12,
+            0x9cu8 => OpcodeId::SWAP13,
+            0x9du8 => OpcodeId::SWAP14,
+            0x9eu8 => OpcodeId::SWAP15,
+            0x9fu8 => OpcodeId::SWAP16,
+            0xa0u8 => OpcodeId::LOG0,
+            0xa1u8 => OpcodeId::LOG1,
+            0xa2u8 => OpcodeId::LOG2,
+            0xa3u8 => OpcodeId::LOG3,
+            0xa4u8 => OpcodeId::LOG4,
+            0xf0u8 => OpcodeId::CREATE,
+            0xf1u8 => OpcodeId::CALL,
+            0xf2u8 => OpcodeId::CALLCODE,
+            0xf3u8 => OpcodeId::RETURN,
+            0xf4u8 => OpcodeId::DELEGATECALL,
+            0xf5u8 => OpcodeId::CREATE2,
+            0xfau8 => OpcodeId::STATICCALL,
+            0xfdu8 => OpcodeId::REVERT,
+            0xffu8 => OpcodeId::SELFDESTRUCT,
+            b => OpcodeId::INVALID(b),
+        }
+    }
+}
+
 impl FromStr for OpcodeId {
     type Err = Error;
 
@@ -680,6 +840,8 @@ impl FromStr for OpcodeId {
             "PC" => OpcodeId::PC,
             "MSIZE" => OpcodeId::MSIZE,
             "JUMPDEST" => OpcodeId::JUMPDEST,
+            "MCOPY" => OpcodeId::MCOPY,
+            "PUSH0" => OpcodeId::PUSH0,
             "PUSH1" => OpcodeId::PUSH1,
             "PUSH2" => OpcodeId::PUSH2,
             "PUSH3" => OpcodeId::PUSH3,
@@ -802,6 +964,22 @@ impl FromStr for OpcodeId {
     }
 }
 
+impl Serialize for OpcodeId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // Serialize as the names reported by geth, which `from_str` parses.
+        match self {
+            OpcodeId::INVALID(0xfe) => serializer.serialize_str("INVALID"),
+            OpcodeId::INVALID(b) => {
+                serializer.serialize_str(&format!("opcode 0x{:02x} not defined", b))
+            }
+            op => serializer.serialize_str(&format!("{:?}", op)),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for OpcodeId {
     fn deserialize<D>(deserializer: D) -> Result<OpcodeId, D::Error>
     where
@@ -1,7 +1,7 @@
 //! Types needed for generating Ethereum traces
 
 use crate::{AccessList, Address, Block, Bytes, Error, GethExecTrace, Word, U64};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Definition of all of the data related to an account.
@@ -20,10 +20,12 @@ pub struct Account {
 }
 
 /// Definition of all of the constants related to an Ethereum block and
-/// chain to be used as setup for the external tracer.
-#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+/// chain to be used as setup for the external tracer.  It deserializes from
+/// the block of the geth JSON-RPC API too.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BlockConstants {
     /// coinbase
+    #[serde(alias = "miner")]
     pub coinbase: Address,
     /// time
     pub timestamp: Word,
@@ -32,8 +34,10 @@ pub struct BlockConstants {
     /// difficulty
     pub difficulty: Word,
     /// gas limit
+    #[serde(alias = "gasLimit")]
     pub gas_limit: Word,
     /// base fee
+    #[serde(alias = "baseFeePerGas")]
     pub base_fee: Word,
 }
 
@@ -73,9 +77,36 @@ impl BlockConstants {
     }
 }
 
-/// Definition of all of the constants related to an Ethereum transaction.
-#[derive(Debug, Default, Clone, Serialize)]
+/// A validator withdrawal of a post-Shanghai block (EIP-4895), which credits
+/// `amount` Gwei to `address` after the txs of the block.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Withdrawal {
+    /// Index of the withdrawal, monotonically increasing over the chain
+    pub index: u64,
+    /// Index of the validator the withdrawal is from
+    pub validator_index: u64,
+    /// Recipient address
+    pub address: Address,
+    /// Amount, in Gwei
+    pub amount: u64,
+}
+
+impl Withdrawal {
+    /// Returns the amount of the withdrawal in Wei.
+    pub fn amount_wei(&self) -> Word {
+        Word::from(self.amount) * Word::from(1_000_000_000u64)
+    }
+}
+
+/// Definition of all of the constants related to an Ethereum transaction.  It
+/// deserializes from the transaction of the geth JSON-RPC API too.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Transaction {
+    /// Type of the envelope, which is 0 for a legacy transaction, 1 for
+    /// EIP-2930 and 2 for EIP-1559
+    #[serde(alias = "type")]
+    pub transaction_type: U64,
     /// From Address
     pub from: Address,
     /// To Address
@@ -83,18 +114,24 @@ pub struct Transaction {
     /// Nonce
     pub nonce: Word,
     /// Gas Limit
+    #[serde(alias = "gas")]
     pub gas_limit: Word,
     /// Value
     pub value: Word,
-    /// Gas Price
+    /// Gas Price, which is the effective gas price of an EIP-1559 transaction
+    #[serde(alias = "gasPrice")]
     pub gas_price: Word,
-    /// Gas fee cap
+    /// Gas fee cap, the max fee per gas of an EIP-1559 transaction
+    #[serde(alias = "maxFeePerGas")]
     pub gas_fee_cap: Word,
-    /// Gas tip cap
+    /// Gas tip cap, the max priority fee per gas of an EIP-1559 transaction
+    #[serde(alias = "maxPriorityFeePerGas")]
     pub gas_tip_cap: Word,
     /// Call data
+    #[serde(alias = "input", alias = "data")]
     pub call_data: Bytes,
     /// Access list
+    #[serde(alias = "accessList")]
     pub access_list: Option<AccessList>,
 }
 
@@ -102,14 +139,15 @@ impl Transaction {
     /// Create Self from a web3 transaction
     pub fn from_eth_tx(tx: &crate::Transaction) -> Self {
         Self {
+            transaction_type: tx.transaction_type.unwrap_or_default(),
             from: tx.from,
             to: tx.to,
             nonce: tx.nonce,
             gas_limit: tx.gas,
             value: tx.value,
             gas_price: tx.gas_price.unwrap_or_default(),
-            gas_fee_cap: tx.max_priority_fee_per_gas.unwrap_or_default(),
-            gas_tip_cap: tx.max_fee_per_gas.unwrap_or_default(),
+            gas_fee_cap: tx.max_fee_per_gas.unwrap_or_default(),
+            gas_tip_cap: tx.max_priority_fee_per_gas.unwrap_or_default(),
             call_data: tx.input.clone(),
             access_list: tx.access_list.clone(),
         }
@@ -131,3 +169,121 @@ pub struct GethData {
     /// Accounts
     pub accounts: Vec<Account>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockConstants, Transaction};
+    use crate::{address, AccessList, AccessListItem, Word, H256, U64};
+
+    #[test]
+    fn deserialize_eip1559_tx() {
+        let tx_json = r#"{
+            "hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "blockHash": null,
+            "blockNumber": null,
+            "transactionIndex": null,
+            "v": "0x1",
+            "r": "0x1",
+            "s": "0x1",
+            "type": "0x2",
+            "from": "0x00000000000000000000000000000000000000fe",
+            "to": "0x00000000000000000000000000000000000000ff",
+            "nonce": "0x1",
+            "gas": "0x5208",
+            "value": "0x0",
+            "gasPrice": "0x3b9aca0a",
+            "maxFeePerGas": "0x77359400",
+            "maxPriorityFeePerGas": "0xa",
+            "input": "0x",
+            "accessList": []
+        }"#;
+        let tx: Transaction = serde_json::from_str(tx_json).unwrap();
+        assert_eq!(tx.transaction_type, U64::from(2));
+        assert_eq!(
+            tx.to,
+            Some(address!("0x00000000000000000000000000000000000000ff"))
+        );
+        assert_eq!(tx.gas_limit, Word::from(21000));
+        assert_eq!(tx.gas_price, Word::from(1_000_000_010));
+        assert_eq!(tx.gas_fee_cap, Word::from(2_000_000_000));
+        assert_eq!(tx.gas_tip_cap, Word::from(10));
+        assert_eq!(
+            tx.access_list.map(|access_list| access_list.0),
+            Some(vec![])
+        );
+
+        let eth_tx: crate::Transaction = serde_json::from_str(tx_json).unwrap();
+        let tx = Transaction::from_eth_tx(&eth_tx);
+        assert_eq!(tx.transaction_type, U64::from(2));
+        assert_eq!(tx.gas_fee_cap, Word::from(2_000_000_000));
+        assert_eq!(tx.gas_tip_cap, Word::from(10));
+    }
+
+    #[test]
+    fn deserialize_access_list() {
+        let tx_json = r#"{
+            "type": "0x1",
+            "from": "0x00000000000000000000000000000000000000fe",
+            "to": "0x00000000000000000000000000000000000000ff",
+            "gas": "0x7530",
+            "accessList": [
+                {
+                    "address": "0x00000000000000000000000000000000000000ff",
+                    "storageKeys": [
+                        "0x0000000000000000000000000000000000000000000000000000000000000001"
+                    ]
+                }
+            ]
+        }"#;
+        let tx: Transaction = serde_json::from_str(tx_json).unwrap();
+        let access_list = tx.access_list.clone().unwrap();
+        assert_eq!(
+            access_list,
+            AccessList(vec![AccessListItem {
+                address: address!("0x00000000000000000000000000000000000000ff"),
+                storage_keys: vec![H256::from_low_u64_be(1)],
+            }])
+        );
+
+        // Serialized back as geth's JSON
+        let json = serde_json::to_value(&access_list).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "address": "0x00000000000000000000000000000000000000ff",
+                "storageKeys": [
+                    "0x0000000000000000000000000000000000000000000000000000000000000001"
+                ]
+            }])
+        );
+        assert_eq!(
+            serde_json::from_value::<AccessList>(json).unwrap(),
+            access_list
+        );
+    }
+
+    #[test]
+    fn deserialize_block_constants() {
+        let block_json = r#"{
+            "miner": "0x00000000000000000000000000000000c014ba5e",
+            "timestamp": "0x6160ee17",
+            "number": "0x1e240",
+            "difficulty": "0x200000",
+            "gasLimit": "0xe4e1c0",
+            "baseFeePerGas": "0x7",
+            "gasUsed": "0x0"
+        }"#;
+        let block: BlockConstants = serde_json::from_str(block_json).unwrap();
+        assert_eq!(
+            block,
+            BlockConstants::new(
+                address!("0x00000000000000000000000000000000c014ba5e"),
+                Word::from(1633742359),
+                U64::from(123456),
+                Word::from(0x200000),
+                Word::from(15_000_000),
+                Word::from(7),
+            )
+        );
+    }
+}
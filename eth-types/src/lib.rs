@@ -18,23 +18,30 @@ pub mod macros;
 pub mod error;
 #[macro_use]
 pub mod bytecode;
+pub mod chain_config;
 pub mod evm_types;
 pub mod geth_types;
+pub mod word;
 
 pub use bytecode::Bytecode;
+pub use chain_config::{ChainConfig, Eip155Rule, ForkBlocks};
 pub use error::Error;
 use pairing::group::ff::PrimeField;
+pub use word::{Sign, WordExt};
 
 use crate::evm_types::{memory::Memory, stack::Stack, storage::Storage};
 use crate::evm_types::{Gas, GasCost, OpcodeId, ProgramCounter};
 use ethers_core::types;
 pub use ethers_core::types::{
-    transaction::{eip2930::AccessList, response::Transaction},
+    transaction::{
+        eip2930::{AccessList, AccessListItem},
+        response::Transaction,
+    },
     Address, Block, Bytes, H160, H256, U256, U64,
 };
 use pairing::arithmetic::FieldExt;
 use pairing::bn256::Fr;
-use serde::{de, Deserialize};
+use serde::{de, Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
@@ -47,7 +54,16 @@ pub trait Field: FieldExt + PrimeField<Repr = [u8; 32]> {}
 // rest of the workspace.
 impl Field for Fr {}
 
-/// Trait used to define types that can be converted to a 256 bit scalar value.
+/// Returns the keccak256 hash of `input`.  The hashes computed outside of the
+/// circuits, such as the code hashes of the state and the outputs witnessed
+/// in the keccak table, all go through this function so that they agree.
+pub fn keccak256<T: AsRef<[u8]>>(input: T) -> [u8; 32] {
+    ethers_core::utils::keccak256(input)
+}
+
+/// Trait used to define types that can be converted to a 256 bit scalar value,
+/// the field element of the same integer, which is the little-endian
+/// conversion [`WordFieldExt::to_field_le`] for a [`Word`].
 pub trait ToScalar<F> {
     /// Convert the type to a scalar value.
     fn to_scalar(&self) -> Option<F>;
@@ -77,6 +93,28 @@ pub trait ToLittleEndian {
     fn to_le_bytes(&self) -> [u8; 32];
 }
 
+/// Trait used to convert a [`Word`] from and to field elements, with the
+/// order of the bytes or of the halves in the name of each conversion.  The
+/// canonical representation of a field element is in little endian.
+pub trait WordFieldExt: Sized {
+    /// Returns the word whose little-endian bytes are the canonical
+    /// representation of `value`.
+    fn from_field_le<F: Field>(value: &F) -> Self;
+
+    /// Returns the field element whose canonical representation is the
+    /// little-endian bytes of the word, or `None` if the word isn't below the
+    /// modulus.
+    fn to_field_le<F: Field>(&self) -> Option<F>;
+
+    /// Returns the high and the low 128-bit halves of the word as field
+    /// elements, in this order.
+    fn to_field_hi_lo<F: Field>(&self) -> (F, F);
+
+    /// Returns the word of its high and low 128-bit halves, or `None` if
+    /// either of them doesn't fit in 128 bits.
+    fn from_field_hi_lo<F: Field>(hi: &F, lo: &F) -> Option<Self>;
+}
+
 // We use our own declaration of another U256 in order to implement a custom
 // deserializer that can parse U256 when returned by structLogs fields in geth
 // debug_trace* methods, which don't contain the `0x` prefix.
@@ -143,6 +181,32 @@ impl ToLittleEndian for U256 {
     }
 }
 
+impl WordFieldExt for U256 {
+    fn from_field_le<F: Field>(value: &F) -> Self {
+        U256::from_little_endian(&value.to_repr())
+    }
+
+    fn to_field_le<F: Field>(&self) -> Option<F> {
+        F::from_repr(self.to_le_bytes()).into()
+    }
+
+    fn to_field_hi_lo<F: Field>(&self) -> (F, F) {
+        let [lo, hi] = self.to_le_limbs::<2>();
+        (F::from_u128(hi.as_u128()), F::from_u128(lo.as_u128()))
+    }
+
+    fn from_field_hi_lo<F: Field>(hi: &F, lo: &F) -> Option<Self> {
+        let [hi, lo] = [hi, lo].map(|half| {
+            let repr = half.to_repr();
+            repr[16..]
+                .iter()
+                .all(|byte| *byte == 0)
+                .then(|| U256::from_little_endian(&repr[..16]))
+        });
+        Some((hi? << 128) + lo?)
+    }
+}
+
 impl<F: Field> ToScalar<F> for U256 {
     fn to_scalar(&self) -> Option<F> {
         let mut bytes = [0u8; 32];
@@ -214,6 +278,131 @@ pub struct EIP1186ProofResponse {
     pub storage_proof: Vec<StorageProof>,
 }
 
+/// Capture options of the struct logger of the geth RPC debug_trace* methods.
+/// Corresponds to `Config` in `go-ethereum/eth/tracers/logger/logger.go`.
+///
+/// The default captures everything bus-mapping needs to build the circuit
+/// inputs: the stack, the memory, the storage and the return data of every
+/// step.  The struct logs of a trace with a capture disabled have null or
+/// missing fields, which are deserialized as empty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct GethLoggerConfig {
+    /// enable memory capture
+    #[serde(rename = "EnableMemory")]
+    pub enable_memory: bool,
+    /// disable stack capture
+    #[serde(rename = "DisableStack")]
+    pub disable_stack: bool,
+    /// disable storage capture
+    #[serde(rename = "DisableStorage")]
+    pub disable_storage: bool,
+    /// enable return data capture
+    #[serde(rename = "EnableReturnData")]
+    pub enable_return_data: bool,
+    /// maximum number of struct logs, or 0 for no limit
+    #[serde(rename = "Limit")]
+    pub limit: usize,
+    /// fork blocks overriding the ones of the chain, to trace under the rules
+    /// of a given hardfork
+    #[serde(rename = "overrides", skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<ChainConfigOverrides>,
+}
+
+impl Default for GethLoggerConfig {
+    fn default() -> Self {
+        Self {
+            enable_memory: true,
+            disable_stack: false,
+            disable_storage: false,
+            enable_return_data: true,
+            limit: 0,
+            overrides: None,
+        }
+    }
+}
+
+impl GethLoggerConfig {
+    /// Returns the config which only captures the opcodes and the gas of the
+    /// steps, to inspect the control flow of large transactions.
+    pub fn minimal() -> Self {
+        Self {
+            enable_memory: false,
+            disable_stack: true,
+            disable_storage: true,
+            enable_return_data: false,
+            limit: 0,
+            overrides: None,
+        }
+    }
+}
+
+/// Fork blocks of the chain config of geth.  Corresponds to the fork fields of
+/// `ChainConfig` in `go-ethereum/params/config.go`, a fork being disabled when
+/// its block is `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainConfigOverrides {
+    /// Homestead block
+    pub homestead_block: Option<u64>,
+    /// EIP-150 block
+    pub eip150_block: Option<u64>,
+    /// EIP-155 block
+    pub eip155_block: Option<u64>,
+    /// EIP-158 block
+    pub eip158_block: Option<u64>,
+    /// Byzantium block
+    pub byzantium_block: Option<u64>,
+    /// Constantinople block
+    pub constantinople_block: Option<u64>,
+    /// Petersburg block
+    pub petersburg_block: Option<u64>,
+    /// Istanbul block
+    pub istanbul_block: Option<u64>,
+    /// Muir Glacier block
+    pub muir_glacier_block: Option<u64>,
+    /// Berlin block
+    pub berlin_block: Option<u64>,
+    /// London block
+    pub london_block: Option<u64>,
+}
+
+impl ChainConfigOverrides {
+    /// Returns the overrides enabling the forks up to Berlin from the genesis.
+    pub fn berlin() -> Self {
+        Self {
+            homestead_block: Some(0),
+            eip150_block: Some(0),
+            eip155_block: Some(0),
+            eip158_block: Some(0),
+            byzantium_block: Some(0),
+            constantinople_block: Some(0),
+            petersburg_block: Some(0),
+            istanbul_block: Some(0),
+            muir_glacier_block: Some(0),
+            berlin_block: Some(0),
+            london_block: None,
+        }
+    }
+
+    /// Returns the overrides enabling the forks up to London from the genesis.
+    pub fn london() -> Self {
+        Self {
+            london_block: Some(0),
+            ..Self::berlin()
+        }
+    }
+}
+
+// Deserializes a field of a struct log which is null when its capture is
+// disabled as its default value.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
 #[derive(Deserialize)]
 #[doc(hidden)]
 struct GethExecStepInternal {
@@ -224,14 +413,32 @@ struct GethExecStepInternal {
     gas_cost: GasCost,
     depth: u16,
     error: Option<String>,
-    // stack is in hex 0x prefixed
+    // stack is in hex 0x prefixed, missing or null without stack capture
+    #[serde(default, deserialize_with = "null_as_default")]
     stack: Vec<DebugU256>,
-    // memory is in chunks of 32 bytes, in hex
-    #[serde(default)]
+    // memory is in chunks of 32 bytes, in hex, missing or null without memory
+    // capture
+    #[serde(default, deserialize_with = "null_as_default")]
     memory: Vec<DebugU256>,
-    // storage is hex -> hex
-    #[serde(default)]
+    // storage is hex -> hex, missing or null without storage capture
+    #[serde(default, deserialize_with = "null_as_default")]
     storage: HashMap<DebugU256, DebugU256>,
+    #[serde(default)]
+    refund: u64,
+    // return data is in hex 0x prefixed, missing without return data capture
+    #[serde(rename = "returnData", default, deserialize_with = "null_as_default")]
+    return_data: Bytes,
+    // The steps of the trimmed struct logger of geth-utils only have the top
+    // of the stack and the slice of the memory read by the step, along with
+    // the sizes of the whole stack and memory.
+    #[serde(rename = "stackSize")]
+    stack_size: Option<usize>,
+    #[serde(rename = "memSize")]
+    mem_size: Option<usize>,
+    #[serde(rename = "memSliceOffset", default)]
+    mem_slice_offset: usize,
+    #[serde(rename = "memSlice", default)]
+    mem_slice: Bytes,
 }
 
 /// The execution step type returned by geth RPC debug_trace* methods.
@@ -251,6 +458,10 @@ pub struct GethExecStep {
     pub memory: Memory,
     // storage is hex -> hex
     pub storage: Storage,
+    // refund counter before the step
+    pub refund: Gas,
+    // return data of the last call, only captured when the step reads it
+    pub return_data: Bytes,
 }
 
 // Wrapper over u8 that provides formats the byte in hex for [`fmt::Debug`].
@@ -284,6 +495,8 @@ impl fmt::Debug for GethExecStep {
             .field("stack", &self.stack)
             .field("memory", &self.memory)
             .field("storage", &self.storage)
+            .field("refund", &format_args!("{}", self.refund.0))
+            .field("return_data", &self.return_data)
             .finish()
     }
 }
@@ -294,6 +507,32 @@ impl<'de> Deserialize<'de> for GethExecStep {
         D: serde::Deserializer<'de>,
     {
         let s = GethExecStepInternal::deserialize(deserializer)?;
+        let mut stack = s.stack.iter().map(|dw| dw.to_word()).collect::<Vec<Word>>();
+        // The items below the top of a trimmed stack are unknown, so they are
+        // zeros.
+        if let Some(stack_size) = s.stack_size {
+            stack.splice(
+                0..0,
+                vec![Word::zero(); stack_size.saturating_sub(stack.len())],
+            );
+        }
+        let memory = match s.mem_size {
+            // The memory out of the slice of a trimmed memory is unknown, so it
+            // is zeros.
+            Some(mem_size) => {
+                let mut memory = vec![0u8; mem_size];
+                let start = s.mem_slice_offset.min(mem_size);
+                let end = (start + s.mem_slice.len()).min(mem_size);
+                memory[start..end].copy_from_slice(&s.mem_slice[..end - start]);
+                Memory::from(memory)
+            }
+            None => Memory::from(
+                s.memory
+                    .iter()
+                    .map(|dw| dw.to_word())
+                    .collect::<Vec<Word>>(),
+            ),
+        };
         Ok(Self {
             pc: s.pc,
             op: s.op,
@@ -301,19 +540,16 @@ impl<'de> Deserialize<'de> for GethExecStep {
             gas_cost: s.gas_cost,
             depth: s.depth,
             error: s.error,
-            stack: Stack(s.stack.iter().map(|dw| dw.to_word()).collect::<Vec<Word>>()),
-            memory: Memory::from(
-                s.memory
-                    .iter()
-                    .map(|dw| dw.to_word())
-                    .collect::<Vec<Word>>(),
-            ),
+            stack: Stack(stack),
+            memory,
             storage: Storage(
                 s.storage
                     .iter()
                     .map(|(k, v)| (k.to_word(), v.to_word()))
                     .collect(),
             ),
+            refund: Gas(s.refund),
+            return_data: s.return_data,
         })
     }
 }
@@ -340,7 +576,7 @@ pub struct GethExecTraceInternal {
     pub gas: Gas,
     pub failed: bool,
     // return_value is a hex encoded byte array
-    #[serde(rename = "structLogs")]
+    #[serde(rename = "structLogs", default, deserialize_with = "null_as_default")]
     pub struct_logs: Vec<GethExecStep>,
 }
 
@@ -522,6 +758,8 @@ mod tests {
                         stack: Stack::new(),
                         storage: Storage(word_map!()),
                         memory: Memory::new(),
+                        refund: Gas(0),
+                        return_data: Bytes::default(),
                     },
                     GethExecStep {
                         pc: ProgramCounter(163),
@@ -533,6 +771,8 @@ mod tests {
                         stack: Stack(vec![word!("0x1003e2d2"), word!("0x2a"), word!("0x0")]),
                         storage: Storage(word_map!("0x0" => "0x6f")),
                         memory: Memory::from(vec![word!("0x0"), word!("0x0"), word!("0x080")]),
+                        refund: Gas(0),
+                        return_data: Bytes::default(),
                     },
                     GethExecStep {
                         pc: ProgramCounter(189),
@@ -567,6 +807,8 @@ mod tests {
                                 "00000000000000000000000000000000000000000000003635c9adc5dea00000"
                             ),
                         ]),
+                        refund: Gas(0),
+                        return_data: Bytes::default(),
                     }
                 ],
             }
@@ -574,6 +816,117 @@ mod tests {
     }
 }
 
+#[cfg(test)]
+mod geth_logger_config_test {
+    use super::*;
+    use crate::evm_types::opcode_ids::OpcodeId;
+
+    #[test]
+    fn serialize_geth_logger_config() {
+        assert_eq!(
+            serde_json::to_value(GethLoggerConfig::default()).unwrap(),
+            serde_json::json!({
+                "EnableMemory": true,
+                "DisableStack": false,
+                "DisableStorage": false,
+                "EnableReturnData": true,
+                "Limit": 0,
+            })
+        );
+
+        let config = GethLoggerConfig {
+            limit: 10,
+            overrides: Some(ChainConfigOverrides::berlin()),
+            ..GethLoggerConfig::minimal()
+        };
+        let config = serde_json::to_value(config).unwrap();
+        assert_eq!(config["Limit"], 10);
+        assert_eq!(config["overrides"]["eip150Block"], 0);
+        assert_eq!(config["overrides"]["berlinBlock"], 0);
+        assert_eq!(config["overrides"]["londonBlock"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn deserialize_geth_exec_trace_without_capture() {
+        let trace_json = r#"
+  {
+    "gas": 21003,
+    "failed": false,
+    "returnValue": "",
+    "structLogs": [
+      {
+        "pc": 0,
+        "op": "PUSH1",
+        "gas": 79000,
+        "gasCost": 3,
+        "depth": 1,
+        "stack": null,
+        "memory": null,
+        "storage": null
+      },
+      {
+        "pc": 2,
+        "op": "STOP",
+        "gas": 78997,
+        "gasCost": 0,
+        "depth": 1
+      }
+    ]
+  }
+        "#;
+        let trace: GethExecTrace =
+            serde_json::from_str(trace_json).expect("json-deserialize GethExecTrace");
+        assert_eq!(trace.struct_logs.len(), 2);
+        for (step, op) in trace
+            .struct_logs
+            .iter()
+            .zip([OpcodeId::PUSH1, OpcodeId::STOP])
+        {
+            assert_eq!(step.op, op);
+            assert_eq!(step.stack, Stack::new());
+            assert_eq!(step.memory, Memory::new());
+            assert_eq!(step.storage, Storage(word_map!()));
+        }
+
+        let trace: GethExecTrace = serde_json::from_str(
+            r#"{ "gas": 21000, "failed": false, "returnValue": "", "structLogs": null }"#,
+        )
+        .expect("json-deserialize GethExecTrace");
+        assert!(trace.struct_logs.is_empty());
+    }
+
+    #[test]
+    fn deserialize_trimmed_geth_exec_step() {
+        let step_json = r#"
+      {
+        "pc": 7,
+        "op": "MLOAD",
+        "gas": 78990,
+        "gasCost": 3,
+        "depth": 1,
+        "refund": 4800,
+        "stackSize": 3,
+        "stack": ["0x2a", "0x20"],
+        "memSize": 96,
+        "memSliceOffset": 32,
+        "memSlice": "0x00000000000000000000000000000000000000000000000000000000000000ff"
+      }
+        "#;
+        let step: GethExecStep =
+            serde_json::from_str(step_json).expect("json-deserialize GethExecStep");
+        assert_eq!(step.op, OpcodeId::MLOAD);
+        assert_eq!(step.refund, Gas(4800));
+        assert_eq!(
+            step.stack,
+            Stack(vec![word!("0x0"), word!("0x2a"), word!("0x20")])
+        );
+        assert_eq!(
+            step.memory,
+            Memory::from(vec![word!("0x0"), word!("0xff"), word!("0x0")])
+        );
+    }
+}
+
 #[cfg(test)]
 mod eth_types_test {
     use super::*;
@@ -652,6 +1005,26 @@ mod eth_types_test {
         Ok(())
     }
 
+    #[test]
+    fn word_field_conversions() {
+        let word = (Word::from(3) << 128) + Word::from(5);
+        let field: Fr = word.to_field_le().unwrap();
+        assert_eq!(
+            field,
+            Fr::from(3) * Fr::from_u128(1 << 127) * Fr::from(2) + Fr::from(5)
+        );
+        assert_eq!(Word::from_field_le(&field), word);
+        assert_eq!(field.to_repr(), word.to_le_bytes());
+        assert_eq!(Word::MAX.to_field_le::<Fr>(), None);
+
+        let (hi, lo) = word.to_field_hi_lo::<Fr>();
+        assert_eq!((hi, lo), (Fr::from(3), Fr::from(5)));
+        assert_eq!(Word::from_field_hi_lo(&hi, &lo), Some(word));
+        let (hi, lo) = Word::MAX.to_field_hi_lo::<Fr>();
+        assert_eq!(Word::from_field_hi_lo(&hi, &lo), Some(Word::MAX));
+        assert_eq!(Word::from_field_hi_lo(&field, &lo), None);
+    }
+
     #[test]
     fn word_from_str() -> Result<(), Error> {
         let word_str = "000000000000000000000000000000000000000000000000000c849c24f39248";
@@ -0,0 +1,242 @@
+//! Arithmetic of EVM words beyond the unsigned operations of [`Word`].
+//!
+//! The EVM interprets the same 256 bits as an unsigned integer or as a two's
+//! complement signed integer depending on the opcode, and defines shifts and
+//! exponentiations for any operand, wrapping modulo 2^256.  [`WordExt`]
+//! implements these semantics once, for the opcode handlers and the witness
+//! generation of the circuits.
+
+use crate::Word;
+use std::cmp::Ordering;
+
+/// Sign of a word interpreted as a two's complement signed integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sign {
+    /// The most significant bit is set.
+    Negative,
+    /// The word is zero.
+    Zero,
+    /// The word is non-zero and its most significant bit is unset.
+    Positive,
+}
+
+/// Extension of [`Word`] with the EVM semantics of its signed, shift and
+/// exponentiation operations.
+pub trait WordExt: Sized {
+    /// Returns whether the word is negative as a signed integer.
+    fn is_neg(&self) -> bool;
+
+    /// Returns the sign of the word as a signed integer.
+    fn sign(&self) -> Sign;
+
+    /// Returns the two's complement negation of the word, which wraps for the
+    /// minimum signed integer.
+    fn wrapping_neg(&self) -> Self;
+
+    /// Returns the absolute value of the word as a signed integer, which
+    /// wraps for the minimum signed integer.
+    fn abs(&self) -> Self;
+
+    /// Compares the words as signed integers, like SLT and SGT.
+    fn signed_cmp(&self, other: &Self) -> Ordering;
+
+    /// Returns the word whose bytes above the byte `index` are copies of its
+    /// sign bit, like SIGNEXTEND.
+    fn sign_extend(&self, index: Self) -> Self;
+
+    /// Returns the `N` limbs of `256 / N` bits of the word, least significant
+    /// first.
+    fn to_le_limbs<const N: usize>(&self) -> [Self; N];
+
+    /// Returns the word to the power of `exponent` modulo 2^256, like EXP.
+    fn wrapping_exp(&self, exponent: Self) -> Self;
+
+    /// Returns the word shifted left by `shift` bits, or `None` if all the bits
+    /// are shifted out.
+    fn checked_shl(&self, shift: Self) -> Option<Self>;
+
+    /// Returns the word shifted right by `shift` bits, or `None` if all the
+    /// bits are shifted out.
+    fn checked_shr(&self, shift: Self) -> Option<Self>;
+
+    /// Returns the word shifted left by `shift` bits, like SHL.
+    fn evm_shl(&self, shift: Self) -> Self;
+
+    /// Returns the word shifted right by `shift` bits, like SHR.
+    fn evm_shr(&self, shift: Self) -> Self;
+
+    /// Returns the word shifted right by `shift` bits and filled with its sign
+    /// bit, like SAR.
+    fn evm_sar(&self, shift: Self) -> Self;
+}
+
+// Returns the shift as a number of bits, if it is less than 256.
+fn shift_bits(shift: Word) -> Option<usize> {
+    (shift < Word::from(256)).then(|| shift.as_usize())
+}
+
+impl WordExt for Word {
+    fn is_neg(&self) -> bool {
+        self.bit(255)
+    }
+
+    fn sign(&self) -> Sign {
+        if self.is_zero() {
+            Sign::Zero
+        } else if self.is_neg() {
+            Sign::Negative
+        } else {
+            Sign::Positive
+        }
+    }
+
+    fn wrapping_neg(&self) -> Self {
+        self.overflowing_neg().0
+    }
+
+    fn abs(&self) -> Self {
+        if self.is_neg() {
+            self.wrapping_neg()
+        } else {
+            *self
+        }
+    }
+
+    fn signed_cmp(&self, other: &Self) -> Ordering {
+        match (self.is_neg(), other.is_neg()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            // Two's complement preserves the order of words of the same sign.
+            _ => self.cmp(other),
+        }
+    }
+
+    fn sign_extend(&self, index: Self) -> Self {
+        if index >= Word::from(31) {
+            return *self;
+        }
+        let sign_bit = index.as_usize() * 8 + 7;
+        let mask = (Word::one() << sign_bit) - Word::one();
+        if self.bit(sign_bit) {
+            *self | !mask
+        } else {
+            *self & mask
+        }
+    }
+
+    fn to_le_limbs<const N: usize>(&self) -> [Self; N] {
+        assert!(N > 0 && 256 % N == 0, "{} limbs don't split a word", N);
+        let limb_bits = 256 / N;
+        let mask = if limb_bits == 256 {
+            Word::MAX
+        } else {
+            (Word::one() << limb_bits) - Word::one()
+        };
+        let mut limbs = [Word::zero(); N];
+        for (idx, limb) in limbs.iter_mut().enumerate() {
+            *limb = (*self >> (idx * limb_bits)) & mask;
+        }
+        limbs
+    }
+
+    fn wrapping_exp(&self, exponent: Self) -> Self {
+        self.overflowing_pow(exponent).0
+    }
+
+    fn checked_shl(&self, shift: Self) -> Option<Self> {
+        shift_bits(shift).map(|shift| *self << shift)
+    }
+
+    fn checked_shr(&self, shift: Self) -> Option<Self> {
+        shift_bits(shift).map(|shift| *self >> shift)
+    }
+
+    fn evm_shl(&self, shift: Self) -> Self {
+        self.checked_shl(shift).unwrap_or_default()
+    }
+
+    fn evm_shr(&self, shift: Self) -> Self {
+        self.checked_shr(shift).unwrap_or_default()
+    }
+
+    fn evm_sar(&self, shift: Self) -> Self {
+        match (self.is_neg(), shift_bits(shift)) {
+            (false, _) => self.evm_shr(shift),
+            (true, Some(shift)) => !(!*self >> shift),
+            (true, None) => Word::MAX,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Sign, WordExt};
+    use crate::Word;
+    use std::cmp::Ordering;
+
+    fn minus(value: u64) -> Word {
+        Word::from(value).wrapping_neg()
+    }
+
+    #[test]
+    fn word_signed() {
+        let min = Word::one() << 255;
+        assert_eq!(minus(1), Word::MAX);
+        assert_eq!(Word::zero().sign(), Sign::Zero);
+        assert_eq!(Word::from(7).sign(), Sign::Positive);
+        assert_eq!(minus(7).sign(), Sign::Negative);
+        assert_eq!(min.sign(), Sign::Negative);
+
+        assert_eq!(minus(7).abs(), Word::from(7));
+        assert_eq!(Word::from(7).abs(), Word::from(7));
+        assert_eq!(min.abs(), min);
+
+        assert_eq!(minus(2).signed_cmp(&minus(1)), Ordering::Less);
+        assert_eq!(minus(1).signed_cmp(&Word::one()), Ordering::Less);
+        assert_eq!(Word::one().signed_cmp(&min), Ordering::Greater);
+        assert_eq!(min.signed_cmp(&min), Ordering::Equal);
+    }
+
+    #[test]
+    fn word_sign_extend() {
+        let value = Word::from(0xf00201);
+        assert_eq!(value.sign_extend(Word::from(2)), minus(0x0ffdff));
+        assert_eq!(value.sign_extend(Word::from(1)), Word::from(0x0201));
+        assert_eq!(Word::from(0xff01).sign_extend(Word::zero()), Word::one());
+        assert_eq!(value.sign_extend(Word::from(31)), value);
+        assert_eq!(value.sign_extend(Word::MAX), value);
+    }
+
+    #[test]
+    fn word_limbs() {
+        let value = Word([1, 2, 3, 4]);
+        assert_eq!(value.to_le_limbs::<1>(), [value]);
+        assert_eq!(
+            value.to_le_limbs::<2>(),
+            [Word([1, 2, 0, 0]), Word([3, 4, 0, 0])]
+        );
+        assert_eq!(value.to_le_limbs::<4>(), [1u64, 2, 3, 4].map(Word::from));
+        assert_eq!(Word::MAX.to_le_limbs::<32>(), [Word::from(0xff); 32]);
+    }
+
+    #[test]
+    fn word_exp_shift() {
+        assert_eq!(Word::from(3).wrapping_exp(Word::from(5)), Word::from(243));
+        assert_eq!(Word::from(2).wrapping_exp(Word::from(256)), Word::zero());
+        assert_eq!(Word::zero().wrapping_exp(Word::zero()), Word::one());
+
+        assert_eq!(
+            Word::one().checked_shl(Word::from(255)),
+            Some(Word::one() << 255)
+        );
+        assert_eq!(Word::one().checked_shl(Word::from(256)), None);
+        assert_eq!(Word::MAX.checked_shr(Word::MAX), None);
+        assert_eq!(Word::MAX.evm_shl(Word::from(300)), Word::zero());
+        assert_eq!(Word::MAX.evm_shr(Word::from(248)), Word::from(0xff));
+
+        assert_eq!(minus(16).evm_sar(Word::from(2)), minus(4));
+        assert_eq!(minus(16).evm_sar(Word::from(256)), Word::MAX);
+        assert_eq!(Word::from(16).evm_sar(Word::from(2)), Word::from(4));
+        assert_eq!(Word::from(16).evm_sar(Word::MAX), Word::zero());
+    }
+}
@@ -2,7 +2,7 @@
 
 use eth_types::{
     geth_types::{Account, BlockConstants, Transaction},
-    Address, Error, GethExecTrace, Word,
+    Address, ChainConfig, Error, GethExecTrace, GethLoggerConfig, Word,
 };
 use serde::Serialize;
 use std::collections::HashMap;
@@ -21,6 +21,25 @@ pub struct TraceConfig {
     pub accounts: HashMap<Address, Account>,
     /// transaction
     pub transactions: Vec<Transaction>,
+    /// trim the stack and the memory of the struct logs to the parts that
+    /// bus-mapping reads, to shrink the traces of large transactions
+    pub trim_struct_logs: bool,
+    /// capture options and hardfork of the struct logger, which captures the
+    /// memory on London by default
+    pub logger_config: Option<GethLoggerConfig>,
+}
+
+impl TraceConfig {
+    /// Returns the config tracing under the chain id and the hardfork of
+    /// `chain_config`, keeping the capture options of its logger config.
+    pub fn with_chain_config(self, chain_config: &ChainConfig) -> Self {
+        let logger_config = self.logger_config.unwrap_or_default();
+        Self {
+            chain_id: chain_config.chain_id,
+            logger_config: Some(chain_config.logger_config(logger_config)),
+            ..self
+        }
+    }
 }
 
 /// Creates a trace for the specified config
@@ -35,3 +54,67 @@ pub fn trace(config: &TraceConfig) -> Result<Vec<GethExecTrace>, Error> {
     let trace = serde_json::from_str(&trace_string).map_err(Error::SerdeError)?;
     Ok(trace)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{trace, TraceConfig};
+    use eth_types::{
+        address, bytecode,
+        evm_types::OpcodeId,
+        geth_types::{Account, Transaction},
+        Word,
+    };
+
+    #[test]
+    fn trace_without_node() {
+        let code = bytecode! {
+            PUSH1(0x01)
+            PUSH1(0x02)
+            ADD
+            STOP
+        };
+        let sender = Account {
+            address: address!("0x00000000000000000000000000000000000000fe"),
+            balance: Word::from(10u64).pow(18.into()),
+            ..Default::default()
+        };
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000000ff"),
+            code: code.to_vec().into(),
+            ..Default::default()
+        };
+        let config = TraceConfig {
+            chain_id: Word::one(),
+            accounts: [sender.clone(), contract.clone()]
+                .into_iter()
+                .map(|account| (account.address, account))
+                .collect(),
+            transactions: vec![Transaction {
+                from: sender.address,
+                to: Some(contract.address),
+                gas_limit: Word::from(100_000),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let traces = trace(&config).unwrap();
+        assert_eq!(traces.len(), 1);
+        assert!(!traces[0].failed);
+        assert_eq!(traces[0].gas.0, 21_000 + 3 + 3 + 3);
+        let ops: Vec<_> = traces[0].struct_logs.iter().map(|step| step.op).collect();
+        assert_eq!(
+            ops,
+            [
+                OpcodeId::PUSH1,
+                OpcodeId::PUSH1,
+                OpcodeId::ADD,
+                OpcodeId::STOP
+            ]
+        );
+        assert_eq!(
+            traces[0].struct_logs[3].stack.last().unwrap(),
+            Word::from(3)
+        );
+    }
+}
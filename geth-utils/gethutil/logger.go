@@ -0,0 +1,180 @@
+package gethutil
+
+import (
+	"fmt"
+	"math/big"
+	"time"
+
+	"github.com/ethereum/go-ethereum/common"
+	"github.com/ethereum/go-ethereum/common/hexutil"
+	"github.com/ethereum/go-ethereum/core/vm"
+)
+
+// Number of items at the top of the stack logged by TrimmedStructLogger.  It
+// covers the items read by any opcode (SWAP16 reads 17) and the items written
+// by the previous opcode, which bus-mapping reads from the stack of the next
+// step.
+const trimmedStackWindow = 17
+
+// TrimmedStructLog is a step logged by TrimmedStructLogger.  Unlike the logs
+// of logger.StructLogger, it only holds the top of the stack and the slice of
+// the memory read by the opcode, along with the sizes of the whole stack and
+// memory.
+type TrimmedStructLog struct {
+	Pc             uint64             `json:"pc"`
+	Op             string             `json:"op"`
+	Gas            uint64             `json:"gas"`
+	GasCost        uint64             `json:"gasCost"`
+	Depth          int                `json:"depth"`
+	Error          string             `json:"error,omitempty"`
+	Refund         uint64             `json:"refund,omitempty"`
+	StackSize      int                `json:"stackSize"`
+	Stack          []string           `json:"stack"`
+	MemSize        int                `json:"memSize"`
+	MemSliceOffset uint64             `json:"memSliceOffset,omitempty"`
+	MemSlice       hexutil.Bytes      `json:"memSlice,omitempty"`
+	ReturnData     hexutil.Bytes      `json:"returnData,omitempty"`
+	Storage        *map[string]string `json:"storage,omitempty"`
+}
+
+// TrimmedStructLogger is a vm.EVMLogger which logs the steps of a transaction
+// with only the parts of the stack, the memory and the return data that
+// bus-mapping reads, which shrinks the traces of large transactions by an
+// order of magnitude since the memory isn't copied at each step anymore.
+type TrimmedStructLogger struct {
+	env     *vm.EVM
+	storage map[common.Address]map[common.Hash]common.Hash
+	logs    []TrimmedStructLog
+}
+
+// NewTrimmedStructLogger returns a new TrimmedStructLogger.
+func NewTrimmedStructLogger() *TrimmedStructLogger {
+	return &TrimmedStructLogger{
+		storage: make(map[common.Address]map[common.Hash]common.Hash),
+	}
+}
+
+// StructLogs returns the logged steps.
+func (l *TrimmedStructLogger) StructLogs() []TrimmedStructLog {
+	return l.logs
+}
+
+// CaptureStart implements vm.EVMLogger.
+func (l *TrimmedStructLogger) CaptureStart(env *vm.EVM, from common.Address, to common.Address, create bool, input []byte, gas uint64, value *big.Int) {
+	l.env = env
+}
+
+// CaptureState implements vm.EVMLogger.
+func (l *TrimmedStructLogger) CaptureState(pc uint64, op vm.OpCode, gas, cost uint64, scope *vm.ScopeContext, rData []byte, depth int, err error) {
+	stack := scope.Stack.Data()
+	memory := scope.Memory.Data()
+
+	log := TrimmedStructLog{
+		Pc:        pc,
+		Op:        op.String(),
+		Gas:       gas,
+		GasCost:   cost,
+		Depth:     depth,
+		Refund:    l.env.StateDB.GetRefund(),
+		StackSize: len(stack),
+		MemSize:   len(memory),
+	}
+	if err != nil {
+		log.Error = err.Error()
+	}
+
+	window := stack
+	if len(window) > trimmedStackWindow {
+		window = window[len(window)-trimmedStackWindow:]
+	}
+	log.Stack = make([]string, len(window))
+	for i, value := range window {
+		log.Stack[i] = value.Hex()
+	}
+
+	if offset, size, ok := memoryRead(op, scope.Stack); ok {
+		end := uint64(len(memory))
+		if offset < end {
+			if size < end-offset {
+				end = offset + size
+			}
+			log.MemSliceOffset = offset
+			log.MemSlice = common.CopyBytes(memory[offset:end])
+		}
+	}
+
+	if op == vm.RETURNDATACOPY {
+		log.ReturnData = common.CopyBytes(rData)
+	}
+
+	// Like logger.StructLogger, log the storage slots known to the contract
+	// on SLOAD and SSTORE.
+	if op == vm.SLOAD || op == vm.SSTORE {
+		address := scope.Contract.Address()
+		if l.storage[address] == nil {
+			l.storage[address] = make(map[common.Hash]common.Hash)
+		}
+		if op == vm.SLOAD && len(stack) >= 1 {
+			key := common.Hash(stack[len(stack)-1].Bytes32())
+			l.storage[address][key] = l.env.StateDB.GetState(address, key)
+		}
+		if op == vm.SSTORE && len(stack) >= 2 {
+			key := common.Hash(stack[len(stack)-1].Bytes32())
+			l.storage[address][key] = common.Hash(stack[len(stack)-2].Bytes32())
+		}
+		storage := make(map[string]string)
+		for key, value := range l.storage[address] {
+			storage[fmt.Sprintf("%x", key)] = fmt.Sprintf("%x", value)
+		}
+		log.Storage = &storage
+	}
+
+	l.logs = append(l.logs, log)
+}
+
+// CaptureEnter implements vm.EVMLogger.
+func (l *TrimmedStructLogger) CaptureEnter(typ vm.OpCode, from common.Address, to common.Address, input []byte, gas uint64, value *big.Int) {
+}
+
+// CaptureExit implements vm.EVMLogger.
+func (l *TrimmedStructLogger) CaptureExit(output []byte, gasUsed uint64, err error) {}
+
+// CaptureFault implements vm.EVMLogger.
+func (l *TrimmedStructLogger) CaptureFault(pc uint64, op vm.OpCode, gas, cost uint64, scope *vm.ScopeContext, depth int, err error) {
+}
+
+// CaptureEnd implements vm.EVMLogger.
+func (l *TrimmedStructLogger) CaptureEnd(output []byte, gasUsed uint64, t time.Duration, err error) {
+}
+
+// memoryRead returns the offset and the size of the memory read by op, given
+// the stack before its execution.
+func memoryRead(op vm.OpCode, stack *vm.Stack) (uint64, uint64, bool) {
+	operand := func(n int) (uint64, bool) {
+		if stack.Len() <= n {
+			return 0, false
+		}
+		value := stack.Back(n)
+		return value.Uint64(), value.IsUint64()
+	}
+	sized := func(offsetIdx, sizeIdx int) (uint64, uint64, bool) {
+		offset, offsetOk := operand(offsetIdx)
+		size, sizeOk := operand(sizeIdx)
+		return offset, size, offsetOk && sizeOk
+	}
+
+	switch {
+	case op == vm.MLOAD:
+		offset, ok := operand(0)
+		return offset, 32, ok
+	case op == vm.SHA3, op == vm.RETURN, op == vm.REVERT, op >= vm.LOG0 && op <= vm.LOG4:
+		return sized(0, 1)
+	case op == vm.CREATE, op == vm.CREATE2:
+		return sized(1, 2)
+	case op == vm.CALL, op == vm.CALLCODE:
+		return sized(3, 4)
+	case op == vm.DELEGATECALL, op == vm.STATICCALL:
+		return sized(2, 3)
+	}
+	return 0, 0, false
+}
@@ -19,11 +19,13 @@ import (
 // ExecutionResult groups all structured logs emitted by the EVM
 // while replaying a transaction in debug mode as well as transaction
 // execution status, the amount of gas used and the return value
+// The struct logs are TrimmedStructLogs when the trace is configured with
+// TrimStructLogs.
 type ExecutionResult struct {
-	Gas         uint64         `json:"gas"`
-	Failed      bool           `json:"failed"`
-	ReturnValue string         `json:"returnValue"`
-	StructLogs  []StructLogRes `json:"structLogs"`
+	Gas         uint64      `json:"gas"`
+	Failed      bool        `json:"failed"`
+	ReturnValue string      `json:"returnValue"`
+	StructLogs  interface{} `json:"structLogs"`
 }
 
 // StructLogRes stores a structured log emitted by the EVM while replaying a
@@ -96,6 +98,7 @@ type Account struct {
 }
 
 type Transaction struct {
+	Type       hexutil.Uint64  `json:"transaction_type"`
 	From       common.Address  `json:"from"`
 	To         *common.Address `json:"to"`
 	Nonce      hexutil.Uint64  `json:"nonce"`
@@ -119,6 +122,13 @@ type TraceConfig struct {
 	Block         Block                      `json:"block_constants"`
 	Accounts      map[common.Address]Account `json:"accounts"`
 	Transactions  []Transaction              `json:"transactions"`
+	// TrimStructLogs selects the TrimmedStructLogger instead of the struct
+	// logger of geth.
+	TrimStructLogs bool `json:"trim_struct_logs"`
+	// LoggerConfig holds the capture options of the struct logger of geth,
+	// which are ignored by the TrimmedStructLogger, and the chain config
+	// overrides, to trace under the rules of a given hardfork.
+	LoggerConfig *logger.Config `json:"logger_config"`
 }
 
 func Trace(config TraceConfig) ([]*ExecutionResult, error) {
@@ -140,11 +150,21 @@ func Trace(config TraceConfig) ([]*ExecutionResult, error) {
 		LondonBlock:         big.NewInt(0),
 	}
 
+	loggerConfig := logger.Config{EnableMemory: true}
+	if config.LoggerConfig != nil {
+		loggerConfig = *config.LoggerConfig
+	}
+	if loggerConfig.Overrides != nil {
+		chainConfig = *loggerConfig.Overrides
+		chainConfig.ChainID = toBigInt(config.ChainID)
+	}
+
 	var blockGasLimit uint64
 	messages := make([]types.Message, len(config.Transactions))
 	for i, tx := range config.Transactions {
-		// If gas price is specified directly, the tx is treated as legacy type.
-		if tx.GasPrice != nil {
+		// If gas price is specified directly for a tx which is not of the
+		// EIP-1559 type, the tx is treated as legacy type.
+		if tx.GasPrice != nil && tx.Type != types.DynamicFeeTxType {
 			tx.GasFeeCap = tx.GasPrice
 			tx.GasTipCap = tx.GasPrice
 		}
@@ -207,7 +227,17 @@ func Trace(config TraceConfig) ([]*ExecutionResult, error) {
 	// Run the transactions with tracing enabled.
 	executionResults := make([]*ExecutionResult, len(config.Transactions))
 	for i, message := range messages {
-		tracer := logger.NewStructLogger(&logger.Config{EnableMemory: true})
+		var tracer vm.EVMLogger
+		var structLogs func() interface{}
+		if config.TrimStructLogs {
+			trimmedTracer := NewTrimmedStructLogger()
+			tracer = trimmedTracer
+			structLogs = func() interface{} { return trimmedTracer.StructLogs() }
+		} else {
+			structLogger := logger.NewStructLogger(&loggerConfig)
+			tracer = structLogger
+			structLogs = func() interface{} { return FormatLogs(structLogger.StructLogs()) }
+		}
 		evm := vm.NewEVM(blockCtx, core.NewEVMTxContext(message), stateDB, &chainConfig, vm.Config{Debug: true, Tracer: tracer, NoBaseFee: true})
 
 		result, err := core.ApplyMessage(evm, message, new(core.GasPool).AddGas(message.Gas()))
@@ -220,7 +250,7 @@ func Trace(config TraceConfig) ([]*ExecutionResult, error) {
 			Gas:         result.UsedGas,
 			Failed:      result.Failed(),
 			ReturnValue: fmt.Sprintf("%x", result.ReturnData),
-			StructLogs:  FormatLogs(tracer.StructLogs()),
+			StructLogs:  structLogs(),
 		}
 	}
 
@@ -95,6 +95,81 @@ mod test {
         }
     }
 
+    #[test]
+    fn trimmed_struct_logs() {
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x00 MLOAD STOP
+        let config = |trim_struct_logs: bool| {
+            format!(
+                r#"{{
+                    "accounts": {{
+                        "0x00000000000000000000000000000000000000ff": {{
+                            "code": "0x602a60005260005100"
+                        }}
+                    }},
+                    "transactions": [
+                        {{
+                            "from": "0x00000000000000000000000000000000000000fe",
+                            "to": "0x00000000000000000000000000000000000000ff",
+                            "gas_limit": "0xc350"
+                        }}
+                    ],
+                    "trim_struct_logs": {}
+                }}"#,
+                trim_struct_logs
+            )
+        };
+        assert!(!trace(&config(false)).unwrap().contains("memSize"));
+        let trimmed_trace = trace(&config(true)).unwrap();
+        assert!(trimmed_trace.contains(r#""memSize": 32"#));
+        assert!(trimmed_trace.contains(
+            r#""memSlice": "0x000000000000000000000000000000000000000000000000000000000000002a""#
+        ));
+    }
+
+    #[test]
+    fn logger_config() {
+        // BASEFEE STOP
+        let config = |logger_config: &str| {
+            format!(
+                r#"{{
+                    "accounts": {{
+                        "0x00000000000000000000000000000000000000ff": {{
+                            "code": "0x4800"
+                        }}
+                    }},
+                    "transactions": [
+                        {{
+                            "from": "0x00000000000000000000000000000000000000fe",
+                            "to": "0x00000000000000000000000000000000000000ff",
+                            "gas_limit": "0xc350"
+                        }}
+                    ],
+                    "logger_config": {}
+                }}"#,
+                logger_config
+            )
+        };
+
+        let trace_london = trace(&config("null")).unwrap();
+        assert!(trace_london.contains(r#""failed": false"#));
+        assert!(trace_london.contains(r#""stack""#));
+
+        let trace_no_stack = trace(&config(r#"{ "DisableStack": true, "Limit": 1 }"#)).unwrap();
+        assert!(!trace_no_stack.contains(r#""stack""#));
+        assert_eq!(trace_no_stack.matches(r#""pc""#).count(), 1);
+
+        // BASEFEE is an invalid opcode before London
+        let trace_berlin = trace(&config(
+            r#"{ "EnableMemory": true, "overrides": {
+                "homesteadBlock": 0, "eip150Block": 0, "eip155Block": 0, "eip158Block": 0,
+                "byzantiumBlock": 0, "constantinopleBlock": 0, "petersburgBlock": 0,
+                "istanbulBlock": 0, "muirGlacierBlock": 0, "berlinBlock": 0, "londonBlock": null
+            } }"#,
+        ))
+        .unwrap();
+        assert!(trace_berlin.contains(r#""failed": true"#));
+    }
+
     #[test]
     fn invalid_tx() {
         for config in [
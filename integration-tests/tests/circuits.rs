@@ -1,6 +1,6 @@
 #![cfg(feature = "circuits")]
 
-use bus_mapping::circuit_input_builder::BuilderClient;
+use bus_mapping::circuit_input_builder::{BuilderClient, CircuitsParams};
 use bus_mapping::operation::OperationContainer;
 use halo2_proofs::dev::MockProver;
 use integration_tests::{get_client, log_init, GenDataOutput};
@@ -10,14 +10,16 @@ use zkevm_circuits::evm_circuit::witness::RwMap;
 use zkevm_circuits::evm_circuit::{
     test::run_test_circuit_complete_fixed_table, witness::block_convert,
 };
-use zkevm_circuits::state_circuit::StateCircuit;
+use zkevm_circuits::state_circuit::{StateCircuit, DEFAULT_MAX_DEGREE};
 lazy_static! {
     pub static ref GEN_DATA: GenDataOutput = GenDataOutput::load();
 }
 
 async fn test_evm_circuit_block(block_num: u64) {
     let cli = get_client();
-    let cli = BuilderClient::new(cli).await.unwrap();
+    let cli = BuilderClient::new(cli, CircuitsParams::default())
+        .await
+        .unwrap();
     let builder = cli.gen_inputs(block_num).await.unwrap();
 
     let block = block_convert(&builder.block, &builder.code_db);
@@ -50,13 +52,15 @@ async fn test_state_circuit_block(block_num: u64) {
     use pairing::bn256::Fr;
 
     let cli = get_client();
-    let cli = BuilderClient::new(cli).await.unwrap();
+    let cli = BuilderClient::new(cli, CircuitsParams::default())
+        .await
+        .unwrap();
     let builder = cli.gen_inputs(block_num).await.unwrap();
 
     // Generate state proof
-    let stack_ops = builder.block.container.sorted_stack();
+    let stack_ops = builder.block.container.sorted_stack().unwrap();
     trace!("stack_ops: {:#?}", stack_ops);
-    let memory_ops = builder.block.container.sorted_memory();
+    let memory_ops = builder.block.container.sorted_memory().unwrap();
     trace!("memory_ops: {:#?}", memory_ops);
     let storage_ops = builder.block.container.sorted_storage();
     trace!("storage_ops: {:#?}", storage_ops);
@@ -66,14 +70,16 @@ async fn test_state_circuit_block(block_num: u64) {
     const STACK_ADDRESS_MAX: usize = 1024;
 
     const RW_COUNTER_MAX: usize = 1 << DEGREE;
-    const ROWS_MAX: usize = 1 << DEGREE;
+    // All the ROWS_MAX rows are enabled, leave some rows for the blinding factors.
+    const ROWS_MAX: usize = (1 << DEGREE) - 64;
 
-    let rw_map = RwMap::from(&OperationContainer {
-        memory: memory_ops,
-        stack: stack_ops,
+    let rw_map = RwMap::try_from(&OperationContainer {
+        memory: memory_ops.into(),
+        stack: stack_ops.into(),
         storage: storage_ops,
         ..Default::default()
-    });
+    })
+    .unwrap();
     let circuit = StateCircuit::<
         Fr,
         true,
@@ -81,10 +87,12 @@ async fn test_state_circuit_block(block_num: u64) {
         MEMORY_ADDRESS_MAX,
         STACK_ADDRESS_MAX,
         ROWS_MAX,
-    >::new(Fr::rand(), &rw_map);
+        DEFAULT_MAX_DEGREE,
+    >::new(Fr::rand(), &rw_map)
+    .unwrap();
 
     use pairing::bn256::Fr as Fp;
-    let prover = MockProver::<Fp>::run(DEGREE as u32, &circuit, vec![]).unwrap();
+    let prover = MockProver::<Fp>::run(DEGREE as u32, &circuit, circuit.instance()).unwrap();
     prover.verify().expect("state_circuit verification failed");
 }
 
@@ -0,0 +1,197 @@
+#![cfg(feature = "live_network")]
+
+//! Tests of the circuits with real blocks of a live network.
+//!
+//! Environment variables:
+//! - LIVE_RPC_URL - a geth http rpc of the network that supports the debug
+//!   namespace and `eth_getProof`
+//! - LIVE_BLOCKS - comma separated list of block numbers or inclusive ranges of
+//!   block numbers (`start-end`) to test
+//!
+//! For each block, the circuit inputs are built and the evm and state circuits
+//! are checked with the `MockProver`.  The opcodes of the block which aren't
+//! supported by bus-mapping or the evm circuit, and the errors of its steps,
+//! are reported.  The test only fails for the blocks which fail without using
+//! any unsupported opcode.
+
+use bus_mapping::circuit_input_builder::{BuilderClient, CircuitsParams};
+use bus_mapping::evm::{is_opcode_supported, OpcodeId};
+use bus_mapping::rpc::GethClient;
+use eth_types::GethExecTrace;
+use ethers::providers::Http;
+use halo2_proofs::dev::MockProver;
+use integration_tests::log_init;
+use log::{error, info};
+use pairing::bn256::Fr;
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use url::Url;
+use zkevm_circuits::evm_circuit::{
+    test::run_test_circuit_complete_fixed_table, witness::block_convert, ExecutionState,
+};
+use zkevm_circuits::state_circuit::{StateCircuit, DEFAULT_MAX_DEGREE};
+
+const DEGREE: usize = 16;
+
+// Returns the block numbers of the LIVE_BLOCKS env var.
+fn live_blocks() -> Vec<u64> {
+    let blocks = env::var("LIVE_BLOCKS").expect("LIVE_BLOCKS env var");
+    blocks
+        .split(',')
+        .map(str::trim)
+        .filter(|range| !range.is_empty())
+        .flat_map(|range| {
+            let parse = |n: &str| {
+                n.trim()
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Cannot parse LIVE_BLOCKS entry {:?}", range))
+            };
+            match range.split_once('-') {
+                Some((start, end)) => parse(start)..=parse(end),
+                None => parse(range)..=parse(range),
+            }
+        })
+        .collect()
+}
+
+/// Opcodes and errors of the steps of a block which the circuits don't
+/// support.
+#[derive(Debug, Default)]
+struct Unsupported {
+    /// Opcodes skipped by bus-mapping, with their number of steps
+    bus_mapping: HashMap<OpcodeId, usize>,
+    /// Opcodes without an execution state in the evm circuit, with their number
+    /// of steps
+    evm_circuit: HashMap<OpcodeId, usize>,
+    /// Errors of the steps, which the evm circuit doesn't handle yet
+    errors: BTreeSet<String>,
+}
+
+impl Unsupported {
+    fn new(geth_traces: &[GethExecTrace]) -> Self {
+        let mut unsupported = Self::default();
+        for step in geth_traces
+            .iter()
+            .flat_map(|trace| trace.struct_logs.iter())
+        {
+            if !is_opcode_supported(&step.op) {
+                *unsupported.bus_mapping.entry(step.op).or_default() += 1;
+            }
+            if ExecutionState::from_opcode(step.op).is_none() {
+                *unsupported.evm_circuit.entry(step.op).or_default() += 1;
+            }
+            if let Some(error) = &step.error {
+                unsupported.errors.insert(error.clone());
+            }
+        }
+        unsupported
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bus_mapping.is_empty() && self.evm_circuit.is_empty() && self.errors.is_empty()
+    }
+}
+
+// Builds the circuit inputs of the block `block_num` and checks the evm and
+// state circuits, returning the unsupported features of the block along with
+// the failures.
+async fn test_live_block(
+    cli: &BuilderClient<GethClient<Http>>,
+    block_num: u64,
+) -> (Unsupported, Vec<String>) {
+    let (eth_block, geth_traces) = match cli.get_block(block_num).await {
+        Ok(block) => block,
+        Err(err) => {
+            return (
+                Unsupported::default(),
+                vec![format!("get_block: {:?}", err)],
+            )
+        }
+    };
+    let unsupported = Unsupported::new(&geth_traces);
+
+    let builder = match cli.get_state_accesses(&eth_block, &geth_traces) {
+        Ok(access_set) => {
+            let (proofs, codes) = cli.get_state(block_num, access_set).await.unwrap();
+            let (state_db, code_db) = cli.build_state_code_db(proofs, codes);
+            cli.gen_inputs_from_state(state_db, code_db, &eth_block, &geth_traces)
+        }
+        Err(err) => Err(err),
+    };
+    let builder = match builder {
+        Ok(builder) => builder,
+        Err(err) => return (unsupported, vec![format!("circuit inputs: {:?}", err)]),
+    };
+
+    let mut failures = Vec::new();
+    let block = block_convert(&builder.block, &builder.code_db);
+    if let Err(errs) = run_test_circuit_complete_fixed_table(block.clone()) {
+        failures.push(format!("evm_circuit: {:?}", errs));
+    }
+
+    const MEMORY_ADDRESS_MAX: usize = 2000;
+    const STACK_ADDRESS_MAX: usize = 1024;
+    const RW_COUNTER_MAX: usize = 1 << DEGREE;
+    // All the ROWS_MAX rows are enabled, leave some rows for the blinding factors.
+    const ROWS_MAX: usize = (1 << DEGREE) - 64;
+    match StateCircuit::<
+        Fr,
+        true,
+        RW_COUNTER_MAX,
+        MEMORY_ADDRESS_MAX,
+        STACK_ADDRESS_MAX,
+        ROWS_MAX,
+        DEFAULT_MAX_DEGREE,
+    >::new(block.randomness, &block.rws)
+    {
+        Ok(circuit) => {
+            let prover =
+                MockProver::<Fr>::run(DEGREE as u32, &circuit, circuit.instance()).unwrap();
+            if let Err(errs) = prover.verify() {
+                failures.push(format!("state_circuit: {:?}", errs));
+            }
+        }
+        Err(err) => failures.push(format!("state_circuit: {:?}", err)),
+    }
+
+    (unsupported, failures)
+}
+
+#[tokio::test]
+async fn test_live_blocks() {
+    log_init();
+    let rpc_url = env::var("LIVE_RPC_URL").expect("LIVE_RPC_URL env var");
+    let transport = Http::new(Url::parse(&rpc_url).expect("invalid LIVE_RPC_URL"));
+    let cli = BuilderClient::new(GethClient::new(transport), CircuitsParams::default())
+        .await
+        .unwrap();
+
+    let mut unexpected_failures = Vec::new();
+    for block_num in live_blocks() {
+        let (unsupported, failures) = test_live_block(&cli, block_num).await;
+        if !unsupported.is_empty() {
+            info!(
+                "block {}: unsupported opcodes in bus-mapping {:?}, in the evm circuit {:?}, \
+                 step errors {:?}",
+                block_num, unsupported.bus_mapping, unsupported.evm_circuit, unsupported.errors
+            );
+        }
+        if failures.is_empty() {
+            info!("block {}: ok", block_num);
+        } else if unsupported.is_empty() {
+            error!("block {}: failed with {:?}", block_num, failures);
+            unexpected_failures.push(block_num);
+        } else {
+            info!(
+                "block {}: failed as expected with {:?}",
+                block_num, failures
+            );
+        }
+    }
+
+    assert!(
+        unexpected_failures.is_empty(),
+        "blocks without unsupported opcodes failed: {:?}",
+        unexpected_failures
+    );
+}
@@ -8,6 +8,7 @@ use halo2_proofs::{
     plonk::{ConstraintSystem, Error, TableColumn},
 };
 use itertools::Itertools;
+use rayon::prelude::*;
 use std::convert::TryInto;
 use std::marker::PhantomData;
 
@@ -15,6 +16,52 @@ const MAX_CHUNKS: usize = 64;
 const NUM_OF_BINARY_CHUNKS: usize = 16;
 const NUM_OF_B9_CHUNKS: usize = 5;
 
+// Returns the `index`th combination of `n_chunks` chunks of `base`, in the
+// order of `multi_cartesian_product`, which are the big-endian digits of
+// `index` in `base`.
+fn chunks_of(mut index: usize, base: u8, n_chunks: usize) -> Vec<u8> {
+    let mut chunks = vec![0; n_chunks];
+    for chunk in chunks.iter_mut().rev() {
+        *chunk = (index % base as usize) as u8;
+        index /= base as usize;
+    }
+    chunks
+}
+
+// Returns the rows of a table over all the combinations of `n_chunks` chunks
+// of `base`, computed by `row` in parallel, since the large tables take most
+// of the time of the loading of the keccak circuit.
+fn conversion_rows<F: Field, const N: usize>(
+    base: u8,
+    n_chunks: usize,
+    row: impl Fn(&[u8]) -> [F; N] + Sync,
+) -> Vec<[F; N]> {
+    (0..(base as usize).pow(n_chunks as u32))
+        .into_par_iter()
+        .map(|index| row(&chunks_of(index, base, n_chunks)))
+        .collect()
+}
+
+// Assigns `rows` to the named `columns` of the table `name`.
+fn assign_rows<F: Field, const N: usize>(
+    layouter: &mut impl Layouter<F>,
+    name: &str,
+    columns: [(&str, TableColumn); N],
+    rows: &[[F; N]],
+) -> Result<(), Error> {
+    layouter.assign_table(
+        || name,
+        |mut table| {
+            for (offset, row) in rows.iter().enumerate() {
+                for ((annotation, column), value) in columns.iter().zip(row.iter()) {
+                    table.assign_cell(|| *annotation, *column, offset, || Ok(*value))?;
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
 #[derive(Debug, Clone)]
 pub struct RangeCheckConfig<F, const K: u64> {
     pub range: TableColumn,
@@ -52,48 +99,27 @@ pub struct Base13toBase9TableConfig<F> {
 
 impl<F: Field> Base13toBase9TableConfig<F> {
     pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        layouter.assign_table(
-            || "13 -> 9",
-            |mut table| {
-                // Iterate over all possible 13-ary values of size 4
-                for (i, b13_chunks) in (0..BASE_NUM_OF_CHUNKS)
-                    .map(|_| 0..B13)
-                    .multi_cartesian_product()
-                    .enumerate()
-                {
-                    table.assign_cell(
-                        || "base 13",
-                        self.base13,
-                        i,
-                        || Ok(f_from_radix_be::<F>(&b13_chunks, B13)),
-                    )?;
-
-                    table.assign_cell(
-                        || "base 9",
-                        self.base9,
-                        i,
-                        || {
-                            let converted_chunks: Vec<u8> = b13_chunks
-                                .iter()
-                                .map(|&x| convert_b13_coef(x))
-                                .collect_vec();
-                            Ok(f_from_radix_be::<F>(&converted_chunks, B9))
-                        },
-                    )?;
-                    table.assign_cell(
-                        || "overflow_detector",
-                        self.overflow_detector,
-                        i,
-                        || {
-                            Ok(F::from(
-                                get_overflow_detector(b13_chunks.clone().try_into().unwrap())
-                                    .into(),
-                            ))
-                        },
-                    )?;
-                }
-                Ok(())
-            },
+        // All possible 13-ary values of size 4
+        let rows = conversion_rows(B13, BASE_NUM_OF_CHUNKS as usize, |b13_chunks| {
+            let converted_chunks: Vec<u8> = b13_chunks
+                .iter()
+                .map(|&x| convert_b13_coef(x))
+                .collect_vec();
+            [
+                f_from_radix_be::<F>(b13_chunks, B13),
+                f_from_radix_be::<F>(&converted_chunks, B9),
+                F::from(get_overflow_detector(b13_chunks.try_into().unwrap()).into()),
+            ]
+        });
+        assign_rows(
+            layouter,
+            "13 -> 9",
+            [
+                ("base 13", self.base13),
+                ("base 9", self.base9),
+                ("overflow_detector", self.overflow_detector),
+            ],
+            &rows,
         )
     }
 
@@ -246,37 +272,23 @@ pub struct FromBinaryTableConfig<F> {
 
 impl<F: Field> FromBinaryTableConfig<F> {
     pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        layouter.assign_table(
-            || "2 -> (9 and 13)",
-            |mut table| {
-                // Iterate over all possible binary values of size 16
-                for (i, b2_chunks) in (0..NUM_OF_BINARY_CHUNKS)
-                    .map(|_| 0..B2)
-                    .multi_cartesian_product()
-                    .enumerate()
-                {
-                    table.assign_cell(
-                        || "base 2",
-                        self.base2,
-                        i,
-                        || Ok(f_from_radix_be::<F>(&b2_chunks, B2)),
-                    )?;
-
-                    table.assign_cell(
-                        || "base 9",
-                        self.base9,
-                        i,
-                        || Ok(f_from_radix_be::<F>(&b2_chunks, B9)),
-                    )?;
-                    table.assign_cell(
-                        || "base 13",
-                        self.base13,
-                        i,
-                        || Ok(f_from_radix_be::<F>(&b2_chunks, B13)),
-                    )?;
-                }
-                Ok(())
-            },
+        // All possible binary values of size 16
+        let rows = conversion_rows(B2, NUM_OF_BINARY_CHUNKS, |b2_chunks| {
+            [
+                f_from_radix_be::<F>(b2_chunks, B2),
+                f_from_radix_be::<F>(b2_chunks, B9),
+                f_from_radix_be::<F>(b2_chunks, B13),
+            ]
+        });
+        assign_rows(
+            layouter,
+            "2 -> (9 and 13)",
+            [
+                ("base 2", self.base2),
+                ("base 9", self.base9),
+                ("base 13", self.base13),
+            ],
+            &rows,
         )
     }
 
@@ -312,38 +324,25 @@ pub struct FromBase9TableConfig<F> {
 
 impl<F: Field> FromBase9TableConfig<F> {
     pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        layouter.assign_table(
-            || "9 -> (2 and 13)",
-            |mut table| {
-                // Iterate over all possible base 9 values of size 5
-                for (i, b9_chunks) in (0..NUM_OF_B9_CHUNKS)
-                    .map(|_| 0..B9)
-                    .multi_cartesian_product()
-                    .enumerate()
-                {
-                    table.assign_cell(
-                        || "base 9",
-                        self.base9,
-                        i,
-                        || Ok(f_from_radix_be::<F>(&b9_chunks, B9)),
-                    )?;
-                    let converted_chunks: Vec<u8> =
-                        b9_chunks.iter().map(|&x| convert_b9_coef(x)).collect_vec();
-                    table.assign_cell(
-                        || "base 13",
-                        self.base13,
-                        i,
-                        || Ok(f_from_radix_be::<F>(&converted_chunks, B13)),
-                    )?;
-                    table.assign_cell(
-                        || "base 2",
-                        self.base2,
-                        i,
-                        || Ok(f_from_radix_be::<F>(&converted_chunks, B2)),
-                    )?;
-                }
-                Ok(())
-            },
+        // All possible base 9 values of size 5
+        let rows = conversion_rows(B9, NUM_OF_B9_CHUNKS, |b9_chunks| {
+            let converted_chunks: Vec<u8> =
+                b9_chunks.iter().map(|&x| convert_b9_coef(x)).collect_vec();
+            [
+                f_from_radix_be::<F>(b9_chunks, B9),
+                f_from_radix_be::<F>(&converted_chunks, B13),
+                f_from_radix_be::<F>(&converted_chunks, B2),
+            ]
+        });
+        assign_rows(
+            layouter,
+            "9 -> (2 and 13)",
+            [
+                ("base 9", self.base9),
+                ("base 13", self.base13),
+                ("base 2", self.base2),
+            ],
+            &rows,
         )
     }
 
@@ -368,3 +367,17 @@ impl<F: Field> FromBase9TableConfig<F> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::chunks_of;
+    use crate::arith_helpers::B9;
+    use itertools::Itertools;
+
+    #[test]
+    fn chunks_of_follow_cartesian_product() {
+        for (index, chunks) in (0..3).map(|_| 0..B9).multi_cartesian_product().enumerate() {
+            assert_eq!(chunks_of(index, B9, 3), chunks);
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use crate::common::*;
 use itertools::Itertools;
 
+/// Model of the keccak sponge the circuit implements.  It isn't meant to hash
+/// on the host, which is done by [`eth_types::keccak256`], and is tested to
+/// agree with it.
 pub struct Keccak {
     state: State,
     sponge: Sponge,
@@ -232,3 +235,12 @@ fn test_long_input() {
     ];
     assert_eq!(keccak256(&input), output);
 }
+
+#[test]
+fn test_matches_eth_types() {
+    // Lengths around the rate of 136 bytes, where the padding changes
+    for len in [0, 1, 135, 136, 137, 271, 272, 300] {
+        let input: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+        assert_eq!(keccak256(&input), eth_types::keccak256(&input));
+    }
+}
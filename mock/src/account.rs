@@ -0,0 +1,65 @@
+//! Mock Account definition and builder related methods.
+
+use eth_types::{geth_types::Account, Address, Bytes, Word};
+use std::collections::HashMap;
+
+/// Mock structure which represents an Account and can be used for tests.
+/// It contains all the builder-pattern methods required to be able to specify
+/// any of its details.
+#[derive(Debug, Clone, Default)]
+pub struct MockAccount {
+    /// Address
+    pub address: Address,
+    /// nonce
+    pub nonce: Word,
+    /// Balance
+    pub balance: Word,
+    /// EVM Code
+    pub code: Bytes,
+    /// Storage
+    pub storage: HashMap<Word, Word>,
+}
+
+impl From<MockAccount> for Account {
+    fn from(mock: MockAccount) -> Self {
+        Account {
+            address: mock.address,
+            nonce: mock.nonce,
+            balance: mock.balance,
+            code: mock.code,
+            storage: mock.storage,
+        }
+    }
+}
+
+impl MockAccount {
+    /// Set address field for the MockAccount.
+    pub fn address(&mut self, address: Address) -> &mut Self {
+        self.address = address;
+        self
+    }
+
+    /// Set nonce field for the MockAccount.
+    pub fn nonce(&mut self, nonce: Word) -> &mut Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Set balance field for the MockAccount.
+    pub fn balance(&mut self, balance: Word) -> &mut Self {
+        self.balance = balance;
+        self
+    }
+
+    /// Set code field for the MockAccount.
+    pub fn code<T: Into<Bytes>>(&mut self, code: T) -> &mut Self {
+        self.code = code.into();
+        self
+    }
+
+    /// Add storage slots to the storage of the MockAccount.
+    pub fn storage<I: IntoIterator<Item = (Word, Word)>>(&mut self, storage: I) -> &mut Self {
+        self.storage.extend(storage);
+        self
+    }
+}
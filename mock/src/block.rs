@@ -0,0 +1,117 @@
+//! Mock Block definition and builder related methods.
+
+use super::new_block;
+use eth_types::{Address, Block, Hash, Word, U64};
+
+/// Mock structure which represents a Block and can be used for tests.  Its
+/// defaults are the ones of [`new_block`].  It contains all the
+/// builder-pattern methods required to be able to specify any of its details.
+#[derive(Debug, Clone)]
+pub struct MockBlock {
+    /// Hash
+    pub hash: Hash,
+    /// Hash of the parent block
+    pub parent_hash: Hash,
+    /// Coinbase
+    pub author: Address,
+    /// Number
+    pub number: U64,
+    /// Gas limit
+    pub gas_limit: Word,
+    /// Base fee
+    pub base_fee_per_gas: Word,
+    /// Timestamp
+    pub timestamp: Word,
+    /// Difficulty
+    pub difficulty: Word,
+}
+
+impl Default for MockBlock {
+    fn default() -> Self {
+        let block = new_block();
+        MockBlock {
+            hash: block.hash.unwrap_or_default(),
+            parent_hash: block.parent_hash,
+            author: block.author,
+            number: block.number.unwrap_or_default(),
+            gas_limit: block.gas_limit,
+            base_fee_per_gas: block.base_fee_per_gas.unwrap_or_default(),
+            timestamp: block.timestamp,
+            difficulty: block.difficulty,
+        }
+    }
+}
+
+impl MockBlock {
+    /// Set hash field for the MockBlock.
+    pub fn hash(&mut self, hash: Hash) -> &mut Self {
+        self.hash = hash;
+        self
+    }
+
+    /// Set parent_hash field for the MockBlock.
+    pub fn parent_hash(&mut self, parent_hash: Hash) -> &mut Self {
+        self.parent_hash = parent_hash;
+        self
+    }
+
+    /// Set author field for the MockBlock.
+    pub fn author(&mut self, author: Address) -> &mut Self {
+        self.author = author;
+        self
+    }
+
+    /// Set number field for the MockBlock.
+    pub fn number(&mut self, number: u64) -> &mut Self {
+        self.number = U64::from(number);
+        self
+    }
+
+    /// Set gas_limit field for the MockBlock.
+    pub fn gas_limit(&mut self, gas_limit: Word) -> &mut Self {
+        self.gas_limit = gas_limit;
+        self
+    }
+
+    /// Set base_fee_per_gas field for the MockBlock.
+    pub fn base_fee_per_gas(&mut self, base_fee_per_gas: Word) -> &mut Self {
+        self.base_fee_per_gas = base_fee_per_gas;
+        self
+    }
+
+    /// Set timestamp field for the MockBlock.
+    pub fn timestamp(&mut self, timestamp: Word) -> &mut Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Set difficulty field for the MockBlock.
+    pub fn difficulty(&mut self, difficulty: Word) -> &mut Self {
+        self.difficulty = difficulty;
+        self
+    }
+
+    /// Returns the block with the transactions `transactions`, whose block
+    /// hash, block number and index are set accordingly.
+    pub fn build(
+        &self,
+        transactions: Vec<eth_types::Transaction>,
+    ) -> Block<eth_types::Transaction> {
+        let mut block = new_block();
+        block.hash = Some(self.hash);
+        block.parent_hash = self.parent_hash;
+        block.author = self.author;
+        block.number = Some(self.number);
+        block.gas_limit = self.gas_limit;
+        block.base_fee_per_gas = Some(self.base_fee_per_gas);
+        block.timestamp = self.timestamp;
+        block.difficulty = self.difficulty;
+        block.transactions = transactions;
+        for (idx, tx) in block.transactions.iter_mut().enumerate() {
+            tx.block_hash = block.hash;
+            tx.block_number = block.number;
+            tx.transaction_index = Some(idx.into());
+        }
+        block
+    }
+}
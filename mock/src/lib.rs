@@ -5,18 +5,42 @@ use eth_types::{
     bytecode::Bytecode,
     evm_types::Gas,
     geth_types::{Account, BlockConstants, GethData, Transaction},
-    Address, Block, Bytes, Error, Hash, Word, U64,
+    Address, Block, Bytes, ChainConfig, Error, Hash, Word, U64,
 };
 use external_tracer::{trace, TraceConfig};
 use lazy_static::lazy_static;
 
+mod account;
+mod block;
+pub mod precompile;
+mod random;
+mod test_ctx;
+mod transaction;
+
+pub use account::MockAccount;
+pub use block::MockBlock;
+pub use random::RandomConfig;
+pub use test_ctx::{helpers, TestContext};
+pub use transaction::MockTransaction;
+
 /// Mock chain ID
-const MOCK_CHAIN_ID: u64 = 1338;
+pub const MOCK_CHAIN_ID: u64 = 1338;
 
 lazy_static! {
     /// Mock coinbase value
     static ref MOCK_COINBASE: Address =
         address!("0x00000000000000000000000000000000c014ba5e");
+    /// Mock addresses of accounts, used by the [`helpers`] of [`TestContext`]
+    pub static ref MOCK_ACCOUNTS: [Address; 3] = [
+        address!("0x000000000000000000000000000000000cafe111"),
+        address!("0x000000000000000000000000000000000cafe222"),
+        address!("0x000000000000000000000000000000000cafe333"),
+    ];
+}
+
+/// Returns `x` ether in wei.
+pub fn eth(x: u64) -> Word {
+    Word::from(x) * Word::from(10u64).pow(18.into())
 }
 
 /// Create a new block with txs.
@@ -29,11 +53,19 @@ pub fn new(
     for (idx, tx) in eth_block.transactions.iter_mut().enumerate() {
         tx.transaction_index = Some(idx.into())
     }
+    // TODO: Add mocking history_hashes when nedded.
+    trace_block(Vec::new(), eth_block, accounts)
+}
 
+/// Trace the transactions of `eth_block` with the external tracer, given the
+/// `history_hashes` and the `accounts` before the block.
+fn trace_block(
+    history_hashes: Vec<Word>,
+    eth_block: Block<eth_types::Transaction>,
+    accounts: Vec<Account>,
+) -> Result<GethData, Error> {
     let trace_config = TraceConfig {
-        chain_id: MOCK_CHAIN_ID.into(),
-        // TODO: Add mocking history_hashes when nedded.
-        history_hashes: Vec::new(),
+        history_hashes,
         block_constants: BlockConstants::try_from(&eth_block)?,
         accounts: accounts
             .iter()
@@ -44,7 +76,9 @@ pub fn new(
             .iter()
             .map(Transaction::from_eth_tx)
             .collect(),
-    };
+        ..Default::default()
+    }
+    .with_chain_config(&ChainConfig::new(MOCK_CHAIN_ID.into()));
     let geth_traces = trace(&trace_config)?;
 
     Ok(GethData {
@@ -0,0 +1,363 @@
+//! Fixtures of calls to the precompiled contracts, with valid and malformed
+//! inputs and with the gas around their cost, for the regression tests of the
+//! precompile handlers of bus-mapping and of the gadgets of the EVM circuit.
+//!
+//! Every [`PrecompileCall`] is a [`TestContext`] whose transaction calls a
+//! contract which stores the input in memory, calls the precompile with it and
+//! stops, leaving the success of the call on the stack.
+
+use crate::TestContext;
+use eth_types::{evm_types::OpcodeId, Address, Bytecode, Error, Word};
+
+/// Offset of the memory where the output of the precompile is written.
+const RETURN_OFFSET: u64 = 0;
+/// Number of bytes of the output of the precompile written to the memory.
+const RETURN_LENGTH: u64 = 0x40;
+
+/// Precompiled contracts of the EVM up to London, whose address is their
+/// discriminant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Precompile {
+    /// Recovery of the signer of an ECDSA signature
+    EcRecover = 1,
+    /// SHA2-256 hash
+    Sha256,
+    /// RIPEMD-160 hash
+    Ripemd160,
+    /// Copy of the input
+    Identity,
+    /// Modular exponentiation (EIP-198)
+    Modexp,
+    /// Point addition on alt_bn128 (EIP-196)
+    Bn256Add,
+    /// Scalar multiplication on alt_bn128 (EIP-196)
+    Bn256ScalarMul,
+    /// Pairing check on alt_bn128 (EIP-197)
+    Bn256Pairing,
+    /// Compression function F of BLAKE2 (EIP-152)
+    Blake2F,
+}
+
+impl Precompile {
+    /// All the precompiles, by address.
+    pub const ALL: [Self; 9] = [
+        Self::EcRecover,
+        Self::Sha256,
+        Self::Ripemd160,
+        Self::Identity,
+        Self::Modexp,
+        Self::Bn256Add,
+        Self::Bn256ScalarMul,
+        Self::Bn256Pairing,
+        Self::Blake2F,
+    ];
+
+    /// Returns the address of the precompile.
+    pub fn address(&self) -> Address {
+        Address::from_low_u64_be(*self as u64)
+    }
+}
+
+/// Call to a precompile with an input and some gas, and its expected success.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrecompileCall {
+    /// Name of the fixture
+    pub name: String,
+    /// Called precompile
+    pub precompile: Precompile,
+    /// Input of the call
+    pub input: Vec<u8>,
+    /// Gas given to the precompile by the call
+    pub gas: u64,
+    /// Whether the call succeeds
+    pub success: bool,
+}
+
+impl PrecompileCall {
+    fn new(name: &str, precompile: Precompile, input: Vec<u8>, gas: u64, success: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            precompile,
+            input,
+            gas,
+            success,
+        }
+    }
+
+    /// Returns the code which stores the input in memory from the offset 0,
+    /// calls the precompile with it and stops.
+    pub fn code(&self) -> Bytecode {
+        let mut code = Bytecode::default();
+        for (idx, chunk) in self.input.chunks(32).enumerate() {
+            let mut word = [0u8; 32];
+            word[..chunk.len()].copy_from_slice(chunk);
+            code.push(32, Word::from_big_endian(&word))
+                .push(2, Word::from(idx * 32))
+                .write_op(OpcodeId::MSTORE);
+        }
+        code.push(1, Word::from(RETURN_LENGTH))
+            .push(1, Word::from(RETURN_OFFSET))
+            .push(2, Word::from(self.input.len()))
+            .push(1, Word::zero())
+            .push(1, Word::zero())
+            .push(1, Word::from(self.precompile as u64))
+            .push(4, Word::from(self.gas))
+            .write_op(OpcodeId::CALL)
+            .write_op(OpcodeId::STOP);
+        code
+    }
+
+    /// Returns the context of a transaction to a contract with the
+    /// [`code`](Self::code) of the call.
+    pub fn ctx(&self) -> Result<TestContext<2, 1>, Error> {
+        TestContext::simple_ctx_with_bytecode(self.code())
+    }
+}
+
+/// Returns the calls to every precompile with valid inputs and the exact gas
+/// of their cost.
+pub fn valid_calls() -> Vec<PrecompileCall> {
+    vec![
+        PrecompileCall::new(
+            "ecRecover",
+            Precompile::EcRecover,
+            hex(concat!(
+                "18c547e4f7b0f325ad1e56f57e26c745b09a3e503d86e00e5255ff7f715d3d1c",
+                "000000000000000000000000000000000000000000000000000000000000001c",
+                "73b1693892219d736caba55bdb67216e485557ea6b6af75f37096c9aa6a5a75f",
+                "eeb940b1d03b21e36b0e47e79769f095fe2ab855bd91e3a38756b7d75a9c4549",
+            )),
+            3000,
+            true,
+        ),
+        PrecompileCall::new("sha256", Precompile::Sha256, b"abc".to_vec(), 60 + 12, true),
+        PrecompileCall::new(
+            "ripemd160",
+            Precompile::Ripemd160,
+            b"abc".to_vec(),
+            600 + 120,
+            true,
+        ),
+        PrecompileCall::new(
+            "identity",
+            Precompile::Identity,
+            (0..40).collect(),
+            15 + 3 * 2,
+            true,
+        ),
+        // 3^5 mod 7, the minimum cost of EIP-2565
+        PrecompileCall::new(
+            "modexp",
+            Precompile::Modexp,
+            [modexp_header(1, 1, 1), vec![3, 5, 7]].concat(),
+            200,
+            true,
+        ),
+        // G + G
+        PrecompileCall::new(
+            "ecAdd",
+            Precompile::Bn256Add,
+            words(&[1, 2, 1, 2]),
+            150,
+            true,
+        ),
+        // 2 * G
+        PrecompileCall::new(
+            "ecMul",
+            Precompile::Bn256ScalarMul,
+            words(&[1, 2, 2]),
+            6000,
+            true,
+        ),
+        PrecompileCall::new(
+            "ecPairing with no pair",
+            Precompile::Bn256Pairing,
+            Vec::new(),
+            45000,
+            true,
+        ),
+        // The points at infinity
+        PrecompileCall::new(
+            "ecPairing with a pair",
+            Precompile::Bn256Pairing,
+            vec![0; 192],
+            45000 + 34000,
+            true,
+        ),
+        PrecompileCall::new(
+            "blake2f",
+            Precompile::Blake2F,
+            blake2f_input(12, 1),
+            12,
+            true,
+        ),
+    ]
+}
+
+/// Returns the calls to the precompiles with malformed inputs, which either
+/// fail or are padded or rejected without failing.
+pub fn malformed_calls() -> Vec<PrecompileCall> {
+    vec![
+        // An invalid `v` returns no address without failing.
+        PrecompileCall::new(
+            "ecRecover with an invalid v",
+            Precompile::EcRecover,
+            words(&[1, 29, 1, 1]),
+            3000,
+            true,
+        ),
+        // A short input is padded with zeros.
+        PrecompileCall::new(
+            "ecRecover with a short input",
+            Precompile::EcRecover,
+            vec![1; 10],
+            3000,
+            true,
+        ),
+        // The base, the exponent and the modulus are padded with zeros.
+        PrecompileCall::new(
+            "modexp with missing values",
+            Precompile::Modexp,
+            modexp_header(1, 1, 1),
+            200,
+            true,
+        ),
+        PrecompileCall::new(
+            "ecAdd with a point off the curve",
+            Precompile::Bn256Add,
+            words(&[1, 3, 1, 2]),
+            150,
+            false,
+        ),
+        PrecompileCall::new(
+            "ecMul with a point off the curve",
+            Precompile::Bn256ScalarMul,
+            words(&[1, 3, 2]),
+            6000,
+            false,
+        ),
+        PrecompileCall::new(
+            "ecPairing with a truncated pair",
+            Precompile::Bn256Pairing,
+            vec![0; 191],
+            45000,
+            false,
+        ),
+        PrecompileCall::new(
+            "blake2f with a truncated input",
+            Precompile::Blake2F,
+            blake2f_input(12, 1)[..212].to_vec(),
+            12,
+            false,
+        ),
+        PrecompileCall::new(
+            "blake2f with an invalid final flag",
+            Precompile::Blake2F,
+            blake2f_input(12, 2),
+            12,
+            false,
+        ),
+    ]
+}
+
+/// Returns the [`valid_calls`] with one gas less than their cost, which run
+/// out of gas.
+pub fn out_of_gas_calls() -> Vec<PrecompileCall> {
+    valid_calls()
+        .into_iter()
+        .map(|call| PrecompileCall {
+            name: format!("{} out of gas", call.name),
+            gas: call.gas - 1,
+            success: false,
+            ..call
+        })
+        .collect()
+}
+
+/// Returns all the fixtures: the valid, the malformed and the out of gas
+/// calls.
+pub fn all_calls() -> Vec<PrecompileCall> {
+    [valid_calls(), malformed_calls(), out_of_gas_calls()].concat()
+}
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&s[idx..idx + 2], 16).unwrap())
+        .collect()
+}
+
+// Returns the concatenation of the 32 bytes big-endian encodings of `values`.
+fn words(values: &[u64]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| {
+            let mut bytes = [0u8; 32];
+            Word::from(*value).to_big_endian(&mut bytes);
+            bytes
+        })
+        .collect()
+}
+
+// Returns the lengths of the base, the exponent and the modulus which start
+// the input of modexp.
+fn modexp_header(base_len: u64, exp_len: u64, mod_len: u64) -> Vec<u8> {
+    words(&[base_len, exp_len, mod_len])
+}
+
+// Returns the 213 bytes input of blake2f of `rounds` rounds compressing the
+// block "abc", with the final block flag `f`.
+fn blake2f_input(rounds: u32, f: u8) -> Vec<u8> {
+    let mut input = rounds.to_be_bytes().to_vec();
+    // State vector h
+    input.extend([0u8; 64]);
+    // Message block m
+    let mut m = [0u8; 128];
+    m[..3].copy_from_slice(b"abc");
+    input.extend(m);
+    // Offset counter t
+    input.extend(3u128.to_le_bytes());
+    input.push(f);
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{all_calls, out_of_gas_calls, valid_calls, Precompile};
+    use eth_types::{evm_types::OpcodeId, Word};
+
+    #[test]
+    fn precompile_calls_cover_all_precompiles() {
+        for precompile in Precompile::ALL {
+            assert!(valid_calls()
+                .iter()
+                .any(|call| call.precompile == precompile));
+        }
+        assert_eq!(out_of_gas_calls().len(), valid_calls().len());
+        assert_eq!(
+            Precompile::Blake2F.address(),
+            eth_types::address!("0x0000000000000000000000000000000000000009")
+        );
+    }
+
+    #[test]
+    fn precompile_calls_succeed_as_expected() {
+        for call in all_calls() {
+            let ctx = call.ctx().unwrap();
+            let trace = &ctx.geth_traces[0];
+            assert!(!trace.failed, "{}", call.name);
+            let steps = &trace.struct_logs;
+            let idx = steps
+                .iter()
+                .position(|step| step.op == OpcodeId::CALL)
+                .unwrap();
+            // The success of the call is on the stack of the next step.
+            assert_eq!(
+                steps[idx + 1].stack.last().unwrap(),
+                Word::from(call.success as u64),
+                "{}",
+                call.name
+            );
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Generation of random [`TestContext`]s from a seed, so that a failure found
+//! with random inputs is reproduced from the seed alone.
+//!
+//! The accounts at the even indices are contracts, with random bytecode and
+//! storage, and the ones at the odd indices are externally owned accounts,
+//! which send the transactions to random accounts with random values and
+//! call data.  The bytecode pushes the stack inputs of its instructions so
+//! that the stack never underflows, and bounds their memory offsets and
+//! storage keys, so that most transactions run to their end.
+
+use crate::{eth, MockAccount, MockTransaction, TestContext};
+use eth_types::{evm_types::OpcodeId, Address, Bytecode, Error, Word};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+/// Bound of the memory offsets of the generated bytecode, to keep the memory
+/// expansion cheap.
+const MEMORY_BOUND: u64 = 0x400;
+/// Stack depth above which the generated bytecode pops the results.
+const MAX_STACK_DEPTH: usize = 16;
+
+/// Kind of the first stack input of an opcode, which the generator bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    /// Any word
+    Word,
+    /// Memory offset
+    Memory,
+    /// Storage key
+    Storage,
+}
+
+/// Opcodes of the generated bytecode besides the `PUSH`, with the number of
+/// their stack inputs and outputs, and the kind of their first input.
+const OPCODES: &[(OpcodeId, usize, usize, Operand)] = &[
+    (OpcodeId::ADD, 2, 1, Operand::Word),
+    (OpcodeId::MUL, 2, 1, Operand::Word),
+    (OpcodeId::SUB, 2, 1, Operand::Word),
+    (OpcodeId::DIV, 2, 1, Operand::Word),
+    (OpcodeId::MOD, 2, 1, Operand::Word),
+    (OpcodeId::LT, 2, 1, Operand::Word),
+    (OpcodeId::GT, 2, 1, Operand::Word),
+    (OpcodeId::EQ, 2, 1, Operand::Word),
+    (OpcodeId::ISZERO, 1, 1, Operand::Word),
+    (OpcodeId::AND, 2, 1, Operand::Word),
+    (OpcodeId::OR, 2, 1, Operand::Word),
+    (OpcodeId::XOR, 2, 1, Operand::Word),
+    (OpcodeId::NOT, 1, 1, Operand::Word),
+    (OpcodeId::BYTE, 2, 1, Operand::Word),
+    (OpcodeId::SHL, 2, 1, Operand::Word),
+    (OpcodeId::SHR, 2, 1, Operand::Word),
+    (OpcodeId::CALLER, 0, 1, Operand::Word),
+    (OpcodeId::CALLVALUE, 0, 1, Operand::Word),
+    (OpcodeId::CALLDATASIZE, 0, 1, Operand::Word),
+    (OpcodeId::CALLDATALOAD, 1, 1, Operand::Memory),
+    (OpcodeId::SELFBALANCE, 0, 1, Operand::Word),
+    (OpcodeId::POP, 1, 0, Operand::Word),
+    (OpcodeId::MLOAD, 1, 1, Operand::Memory),
+    (OpcodeId::MSTORE, 2, 0, Operand::Memory),
+    (OpcodeId::MSTORE8, 2, 0, Operand::Memory),
+    (OpcodeId::SLOAD, 1, 1, Operand::Storage),
+    (OpcodeId::SSTORE, 2, 0, Operand::Storage),
+    (OpcodeId::PC, 0, 1, Operand::Word),
+    (OpcodeId::MSIZE, 0, 1, Operand::Word),
+    (OpcodeId::GAS, 0, 1, Operand::Word),
+    (OpcodeId::DUP1, 1, 2, Operand::Word),
+    (OpcodeId::SWAP1, 2, 2, Operand::Word),
+];
+
+/// Complexity of the accounts and of the transactions of a random
+/// [`TestContext`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RandomConfig {
+    /// Maximum number of instructions of the bytecode of a contract
+    pub max_instructions: usize,
+    /// Maximum number of storage slots of a contract, which are the keys the
+    /// bytecode mostly accesses
+    pub max_storage_slots: u64,
+    /// Maximum length of the call data of a transaction
+    pub max_calldata_len: usize,
+}
+
+impl Default for RandomConfig {
+    fn default() -> Self {
+        Self {
+            max_instructions: 64,
+            max_storage_slots: 4,
+            max_calldata_len: 64,
+        }
+    }
+}
+
+impl<const NACC: usize, const NTX: usize> TestContext<NACC, NTX> {
+    /// Create a new TestContext whose accounts and transactions are generated
+    /// from `seed` with the complexity `config`, and trace its transactions.
+    /// The same seed and config always give the same context.
+    pub fn random(seed: u64, config: &RandomConfig) -> Result<Self, Error> {
+        assert!(NACC >= 2, "a random context has a contract and a sender");
+        let mut rng = XorShiftRng::seed_from_u64(seed);
+
+        let mut accounts = [(); NACC].map(|_| MockAccount::default());
+        for (idx, account) in accounts.iter_mut().enumerate() {
+            account
+                .address(Address::from(rng.gen::<[u8; 20]>()))
+                .balance(eth(rng.gen_range(1..=100)));
+            if idx % 2 == 0 {
+                let storage_slots = rng.gen_range(0..=config.max_storage_slots);
+                account
+                    .code(random_bytecode(&mut rng, config).to_vec())
+                    .storage(
+                        (0..storage_slots).map(|key| (Word::from(key), random_word(&mut rng, 32))),
+                    );
+            } else {
+                account.nonce(Word::from(rng.gen_range(0..4u64)));
+            }
+        }
+
+        let mut transactions = [(); NTX].map(|_| MockTransaction::default());
+        for tx in transactions.iter_mut() {
+            let from = &accounts[rng.gen_range(0..NACC / 2) * 2 + 1];
+            let to = &accounts[rng.gen_range(0..NACC)];
+            let calldata_len = rng.gen_range(0..=config.max_calldata_len);
+            // The senders keep enough balance for all their transactions.
+            let value = from.balance / (NTX + 1) * rng.gen_range(0..=100u64) / 100;
+            tx.from(from.address)
+                .to(to.address)
+                .value(value)
+                .input((0..calldata_len).map(|_| rng.gen()).collect::<Vec<u8>>());
+        }
+
+        TestContext::new(
+            |accs| *accs = accounts,
+            |txs, _accs| *txs = transactions,
+            |_block| {},
+        )
+    }
+}
+
+/// Returns a word of `n_bytes` random bytes.
+fn random_word(rng: &mut XorShiftRng, n_bytes: usize) -> Word {
+    Word::from_big_endian(&(0..n_bytes).map(|_| rng.gen()).collect::<Vec<u8>>())
+}
+
+/// Returns random bytecode of at most `config.max_instructions` instructions
+/// besides the `PUSH` of their stack inputs, ending with a `STOP`.
+fn random_bytecode(rng: &mut XorShiftRng, config: &RandomConfig) -> Bytecode {
+    let mut code = Bytecode::default();
+    let mut depth = 0;
+    for _ in 0..rng.gen_range(0..=config.max_instructions) {
+        let (op, inputs, outputs, operand) = OPCODES[rng.gen_range(0..OPCODES.len())];
+        for nth in (0..inputs).rev() {
+            match if nth == 0 { operand } else { Operand::Word } {
+                Operand::Word => {
+                    let n_bytes = rng.gen_range(1..=32);
+                    let value = random_word(rng, n_bytes);
+                    code.push(n_bytes, value);
+                }
+                Operand::Memory => {
+                    code.push(2, Word::from(rng.gen_range(0..MEMORY_BOUND)));
+                }
+                Operand::Storage => {
+                    // Mostly hit the storage of the contract, sometimes an
+                    // empty slot.
+                    let key = rng.gen_range(0..config.max_storage_slots + 2);
+                    code.push(1, Word::from(key));
+                }
+            }
+        }
+        code.write_op(op);
+        depth += outputs;
+        while depth > MAX_STACK_DEPTH {
+            code.write_op(OpcodeId::POP);
+            depth -= 1;
+        }
+    }
+    code.write_op(OpcodeId::STOP);
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RandomConfig;
+    use crate::TestContext;
+
+    #[test]
+    fn random_ctx_is_reproducible() {
+        let config = RandomConfig::default();
+        let ctx = TestContext::<4, 3>::random(0xcafe, &config).unwrap();
+        let same_ctx = TestContext::<4, 3>::random(0xcafe, &config).unwrap();
+        let other_ctx = TestContext::<4, 3>::random(0xbeef, &config).unwrap();
+
+        assert_eq!(ctx.eth_block.transactions, same_ctx.eth_block.transactions);
+        for (account, same_account) in ctx.accounts.iter().zip(same_ctx.accounts.iter()) {
+            assert_eq!(account.address, same_account.address);
+            assert_eq!(account.code, same_account.code);
+            assert_eq!(account.storage, same_account.storage);
+        }
+        assert_eq!(
+            ctx.geth_traces
+                .iter()
+                .map(|trace| trace.gas)
+                .collect::<Vec<_>>(),
+            same_ctx
+                .geth_traces
+                .iter()
+                .map(|trace| trace.gas)
+                .collect::<Vec<_>>()
+        );
+        assert_ne!(ctx.eth_block.transactions, other_ctx.eth_block.transactions);
+
+        // The contracts are at the even indices and the senders at the odd
+        // ones.
+        assert!(!ctx.accounts[0].code.is_empty() && ctx.accounts[1].code.is_empty());
+        assert!(ctx
+            .eth_block
+            .transactions
+            .iter()
+            .all(|tx| tx.from == ctx.accounts[1].address || tx.from == ctx.accounts[3].address));
+    }
+}
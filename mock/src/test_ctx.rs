@@ -0,0 +1,222 @@
+//! Mock types and functions to generate test environments for ZKEVM tests
+
+use super::{trace_block, MockAccount, MockBlock, MockTransaction};
+use eth_types::{
+    geth_types::{Account, GethData},
+    Block, Bytecode, Error, GethExecTrace, Word,
+};
+use std::collections::HashMap;
+
+/// TestContext is a type that contains all the information from a block
+/// required to build the circuit inputs.
+///
+/// It is built from closures which fill the accounts, the transactions and the
+/// block with the fluent builders of [`MockAccount`], [`MockTransaction`] and
+/// [`MockBlock`], and holds the traces of the transactions generated with the
+/// external tracer.  It converts into a [`GethData`], from which bus-mapping
+/// builds the StateDB and the CodeDB with `BlockData::new_from_geth_data`.
+///
+/// ```ignore
+/// let ctx = TestContext::<2, 1>::new(
+///     |accs| {
+///         accs[0].address(address!("0x...")).code(code);
+///         accs[1].address(address!("0x...")).balance(eth(10));
+///     },
+///     |txs, accs| {
+///         txs[0].from(accs[1].address).to(accs[0].address);
+///     },
+///     |block| {
+///         block.number(0xcafe);
+///     },
+/// )?;
+/// let block_data = bus_mapping::mock::BlockData::new_from_geth_data(ctx.into());
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestContext<const NACC: usize, const NTX: usize> {
+    /// chain id
+    pub chain_id: Word,
+    /// Account list
+    pub accounts: [Account; NACC],
+    /// history hashes contains most recent 256 block hashes in history, where
+    /// the lastest one is at history_hashes[history_hashes.len() - 1].
+    pub history_hashes: Vec<Word>,
+    /// Block from geth
+    pub eth_block: Block<eth_types::Transaction>,
+    /// Execution Trace from geth
+    pub geth_traces: Vec<GethExecTrace>,
+}
+
+impl<const NACC: usize, const NTX: usize> From<TestContext<NACC, NTX>> for GethData {
+    fn from(ctx: TestContext<NACC, NTX>) -> GethData {
+        GethData {
+            chain_id: ctx.chain_id,
+            history_hashes: ctx.history_hashes,
+            eth_block: ctx.eth_block,
+            geth_traces: ctx.geth_traces,
+            accounts: ctx.accounts.into(),
+        }
+    }
+}
+
+impl<const NACC: usize, const NTX: usize> TestContext<NACC, NTX> {
+    /// Create a new TestContext whose `NACC` accounts are set by `acc_fns`,
+    /// whose `NTX` transactions are set by `func_tx` given the accounts, and
+    /// whose block is set by `func_block`, and trace its transactions.
+    ///
+    /// The transactions without an explicit nonce get the next nonce of their
+    /// sender, starting from the nonce of its account.
+    pub fn new<FAcc, FTx, FBlock>(
+        acc_fns: FAcc,
+        func_tx: FTx,
+        func_block: FBlock,
+    ) -> Result<Self, Error>
+    where
+        FAcc: FnOnce(&mut [MockAccount; NACC]),
+        FTx: FnOnce(&mut [MockTransaction; NTX], [MockAccount; NACC]),
+        FBlock: FnOnce(&mut MockBlock),
+    {
+        let mut accounts = [(); NACC].map(|_| MockAccount::default());
+        acc_fns(&mut accounts);
+
+        let mut transactions = [(); NTX].map(|_| MockTransaction::default());
+        func_tx(&mut transactions, accounts.clone());
+
+        let mut block = MockBlock::default();
+        func_block(&mut block);
+
+        let mut nonces: HashMap<_, _> = accounts
+            .iter()
+            .map(|account| (account.address, account.nonce))
+            .collect();
+        let transactions: Vec<eth_types::Transaction> = transactions
+            .map(|tx| {
+                let nonce = nonces.entry(tx.from).or_default();
+                let tx_nonce = tx.nonce.unwrap_or(*nonce);
+                *nonce = tx_nonce + 1;
+                let mut eth_tx = eth_types::Transaction::from(tx);
+                eth_tx.nonce = tx_nonce;
+                eth_tx
+            })
+            .into();
+
+        let accounts = accounts.map(Account::from);
+        let geth_data = trace_block(Vec::new(), block.build(transactions), accounts.to_vec())?;
+
+        Ok(Self {
+            chain_id: geth_data.chain_id,
+            accounts,
+            history_hashes: geth_data.history_hashes,
+            eth_block: geth_data.eth_block,
+            geth_traces: geth_data.geth_traces,
+        })
+    }
+}
+
+impl TestContext<2, 1> {
+    /// Create a new TestContext with a single transaction from the account
+    /// `accs[1]`, which has a balance of 10 ether, to the account `accs[0]`,
+    /// which has the code `code`.
+    pub fn simple_ctx_with_bytecode(code: Bytecode) -> Result<Self, Error> {
+        TestContext::new(
+            |accs| helpers::account_0_code_account_1_no_code(accs, code),
+            |txs, accs| helpers::tx_from_1_to_0(txs, accs),
+            |_block| {},
+        )
+    }
+}
+
+/// Collection of helper functions which contribute to specific routines on the
+/// builder pattern used to construct [`TestContext`]s.
+pub mod helpers {
+    use super::*;
+    use crate::MOCK_ACCOUNTS;
+
+    /// Generate a simple setup which adds balance to two default accounts
+    /// from [`static@MOCK_ACCOUNTS`]:
+    /// - 0x000000000000000000000000000000000cafe111
+    /// - 0x000000000000000000000000000000000cafe222
+    /// And injects the provided bytecode into the first one.
+    pub fn account_0_code_account_1_no_code(accs: &mut [MockAccount; 2], code: Bytecode) {
+        accs[0]
+            .address(MOCK_ACCOUNTS[0])
+            .balance(crate::eth(10))
+            .code(code);
+        accs[1].address(MOCK_ACCOUNTS[1]).balance(crate::eth(10));
+    }
+
+    /// Generate a single transaction from the second account of the list to
+    /// the first one.
+    pub fn tx_from_1_to_0(txs: &mut [MockTransaction; 1], accs: [MockAccount; 2]) {
+        txs[0].from(accs[1].address).to(accs[0].address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TestContext;
+    use crate::{eth, MOCK_ACCOUNTS};
+    use eth_types::{bytecode, geth_types::GethData, Word};
+
+    #[test]
+    fn test_context_nonces() {
+        let ctx = TestContext::<2, 3>::new(
+            |accs| {
+                accs[0].address(MOCK_ACCOUNTS[0]).balance(eth(10));
+                accs[1]
+                    .address(MOCK_ACCOUNTS[1])
+                    .balance(eth(10))
+                    .nonce(Word::from(5));
+            },
+            |txs, accs| {
+                txs[0].from(accs[1].address).to(accs[0].address);
+                txs[1].from(accs[0].address).to(accs[1].address);
+                txs[2]
+                    .from(accs[1].address)
+                    .to(accs[0].address)
+                    .value(eth(1));
+            },
+            |block| {
+                block.number(0xcafe);
+            },
+        )
+        .unwrap();
+
+        let nonces = ctx
+            .eth_block
+            .transactions
+            .iter()
+            .map(|tx| tx.nonce.as_u64())
+            .collect::<Vec<_>>();
+        assert_eq!(nonces, vec![5, 0, 6]);
+        assert!(ctx
+            .eth_block
+            .transactions
+            .iter()
+            .all(|tx| tx.block_number == Some(0xcafe.into())));
+        assert_eq!(ctx.geth_traces.len(), 3);
+        assert!(ctx.geth_traces.iter().all(|trace| !trace.failed));
+    }
+
+    #[test]
+    fn test_context_simple_ctx_with_bytecode() {
+        let code = bytecode! {
+            PUSH1(0x01)
+            PUSH1(0x02)
+            ADD
+            STOP
+        };
+        let geth_data: GethData = TestContext::simple_ctx_with_bytecode(code).unwrap().into();
+
+        assert_eq!(geth_data.accounts.len(), 2);
+        assert_eq!(
+            geth_data.eth_block.transactions[0].to,
+            Some(MOCK_ACCOUNTS[0])
+        );
+        let ops = geth_data.geth_traces[0]
+            .struct_logs
+            .iter()
+            .map(|step| step.op.as_u8())
+            .collect::<Vec<_>>();
+        assert_eq!(ops, vec![0x60, 0x60, 0x01, 0x00]);
+    }
+}
@@ -0,0 +1,141 @@
+//! Mock Transaction definition and builder related methods.
+
+use super::{MOCK_CHAIN_ID, MOCK_COINBASE};
+use eth_types::{AccessList, Address, Bytes, Hash, Word, U64};
+
+/// Mock structure which represents a Transaction and can be used for tests.
+/// It contains all the builder-pattern methods required to be able to specify
+/// any of its details.
+#[derive(Debug, Clone)]
+pub struct MockTransaction {
+    /// Hash
+    pub hash: Hash,
+    /// Nonce, or the next nonce of the sender when `None`
+    pub nonce: Option<Word>,
+    /// Sender
+    pub from: Address,
+    /// Recipient, or `None` for a contract creation
+    pub to: Option<Address>,
+    /// Transferred value
+    pub value: Word,
+    /// Gas price
+    pub gas_price: Word,
+    /// Gas limit
+    pub gas: Word,
+    /// Call data, or init code for a contract creation
+    pub input: Bytes,
+    /// Chain id
+    pub chain_id: Word,
+    /// Access list, which makes the transaction an EIP-2930 one when set
+    pub access_list: Option<AccessList>,
+}
+
+impl Default for MockTransaction {
+    fn default() -> Self {
+        MockTransaction {
+            hash: Hash::zero(),
+            nonce: None,
+            from: *MOCK_COINBASE,
+            to: Some(Address::zero()),
+            value: Word::zero(),
+            gas_price: Word::zero(),
+            gas: Word::from(1_000_000u64),
+            input: Bytes::default(),
+            chain_id: Word::from(MOCK_CHAIN_ID),
+            access_list: None,
+        }
+    }
+}
+
+impl From<MockTransaction> for eth_types::Transaction {
+    fn from(mock: MockTransaction) -> Self {
+        eth_types::Transaction {
+            hash: mock.hash,
+            nonce: mock.nonce.unwrap_or_default(),
+            from: mock.from,
+            to: mock.to,
+            value: mock.value,
+            gas_price: Some(mock.gas_price),
+            gas: mock.gas,
+            input: mock.input,
+            transaction_type: Some(if mock.access_list.is_some() {
+                U64::one()
+            } else {
+                U64::zero()
+            }),
+            max_priority_fee_per_gas: Some(mock.gas_price),
+            max_fee_per_gas: Some(mock.gas_price),
+            chain_id: Some(mock.chain_id),
+            access_list: mock.access_list,
+            ..Default::default()
+        }
+    }
+}
+
+impl MockTransaction {
+    /// Set hash field for the MockTransaction.
+    pub fn hash(&mut self, hash: Hash) -> &mut Self {
+        self.hash = hash;
+        self
+    }
+
+    /// Set nonce field for the MockTransaction.
+    pub fn nonce(&mut self, nonce: Word) -> &mut Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Set from field for the MockTransaction.
+    pub fn from(&mut self, from: Address) -> &mut Self {
+        self.from = from;
+        self
+    }
+
+    /// Set to field for the MockTransaction.
+    pub fn to(&mut self, to: Address) -> &mut Self {
+        self.to = Some(to);
+        self
+    }
+
+    /// Make the MockTransaction a contract creation.
+    pub fn create(&mut self) -> &mut Self {
+        self.to = None;
+        self
+    }
+
+    /// Set value field for the MockTransaction.
+    pub fn value(&mut self, value: Word) -> &mut Self {
+        self.value = value;
+        self
+    }
+
+    /// Set gas_price field for the MockTransaction.
+    pub fn gas_price(&mut self, gas_price: Word) -> &mut Self {
+        self.gas_price = gas_price;
+        self
+    }
+
+    /// Set gas field for the MockTransaction.
+    pub fn gas(&mut self, gas: Word) -> &mut Self {
+        self.gas = gas;
+        self
+    }
+
+    /// Set input field for the MockTransaction.
+    pub fn input<T: Into<Bytes>>(&mut self, input: T) -> &mut Self {
+        self.input = input.into();
+        self
+    }
+
+    /// Set chain_id field for the MockTransaction.
+    pub fn chain_id(&mut self, chain_id: Word) -> &mut Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Set access_list field for the MockTransaction.
+    pub fn access_list(&mut self, access_list: AccessList) -> &mut Self {
+        self.access_list = Some(access_list);
+        self
+    }
+}
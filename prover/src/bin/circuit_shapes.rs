@@ -0,0 +1,92 @@
+//! Reports the shape of every circuit, see `prover::shape`, as a markdown
+//! table on the standard output, and optionally as JSON, with the map of
+//! their columns and gates, see `prover::circuit_map`:
+//!
+//! ```text
+//! cargo run --release -p prover --bin circuit-shapes -- --json shapes.json --map map.json
+//! ```
+//!
+//! The minimum `k` accounts for the fixed tables of the EVM circuit and for
+//! the capacity of the state circuit; the rows of the other circuits depend
+//! on their witness, so theirs is only the one of their constraint system.
+
+use clap::Parser;
+use pairing::bn256::Fr;
+use prover::{
+    circuit_map::CircuitMap,
+    circuits::{ProverStateCircuit, ProverSuperCircuit},
+    shape::{markdown_table, CircuitShape},
+};
+use std::{error::Error, fs, path::PathBuf};
+use zkevm_circuits::{
+    bytecode_circuit::BytecodeCircuit, copy_circuit::CopyCircuit, ecc_circuit::EccCircuit,
+    evm_circuit::test::TestCircuit, exp_circuit::ExpCircuit, fixed_table::FixedTableTag,
+    pi_circuit::PiCircuit, sha256_circuit::Sha256Circuit, sig_circuit::SigCircuit,
+    tx_circuit::TxCircuit,
+};
+
+#[derive(Parser)]
+#[clap(
+    name = "circuit-shapes",
+    about = "Reports the columns, gates, lookups, degree and minimum k of the circuits"
+)]
+struct Cli {
+    /// File the shapes are written to as JSON
+    #[clap(long)]
+    json: Option<PathBuf>,
+    /// File the markdown table is written to, instead of the standard output
+    #[clap(long)]
+    markdown: Option<PathBuf>,
+    /// File the map of the columns and of the gates of the circuits is
+    /// written to as JSON
+    #[clap(long)]
+    map: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    let fixed_table_rows = FixedTableTag::iterator()
+        .map(|tag| tag.build::<Fr>().count())
+        .sum::<usize>();
+    let state_rows = (1 << ProverStateCircuit::min_k()) - 64;
+    let shapes = vec![
+        CircuitShape::of::<TestCircuit<Fr>>("evm_circuit", fixed_table_rows),
+        CircuitShape::of::<ProverStateCircuit>("state_circuit", state_rows),
+        CircuitShape::of::<TxCircuit<Fr>>("tx_circuit", 0),
+        CircuitShape::of::<BytecodeCircuit<Fr>>("bytecode_circuit", 0),
+        CircuitShape::of::<CopyCircuit<Fr>>("copy_circuit", 0),
+        CircuitShape::of::<ExpCircuit<Fr>>("exp_circuit", 0),
+        CircuitShape::of::<PiCircuit<Fr>>("pi_circuit", 0),
+        CircuitShape::of::<Sha256Circuit<Fr>>("sha256_circuit", 0),
+        CircuitShape::of::<SigCircuit<Fr>>("sig_circuit", 0),
+        CircuitShape::of::<EccCircuit<Fr>>("ecc_circuit", 0),
+        CircuitShape::of::<ProverSuperCircuit>("super_circuit", fixed_table_rows.max(state_rows)),
+    ];
+
+    if let Some(path) = cli.json {
+        fs::write(&path, serde_json::to_vec_pretty(&shapes)?)?;
+    }
+    if let Some(path) = cli.map {
+        let maps = vec![
+            CircuitMap::of::<TestCircuit<Fr>>("evm_circuit"),
+            CircuitMap::of::<ProverStateCircuit>("state_circuit"),
+            CircuitMap::of::<TxCircuit<Fr>>("tx_circuit"),
+            CircuitMap::of::<BytecodeCircuit<Fr>>("bytecode_circuit"),
+            CircuitMap::of::<CopyCircuit<Fr>>("copy_circuit"),
+            CircuitMap::of::<ExpCircuit<Fr>>("exp_circuit"),
+            CircuitMap::of::<PiCircuit<Fr>>("pi_circuit"),
+            CircuitMap::of::<Sha256Circuit<Fr>>("sha256_circuit"),
+            CircuitMap::of::<SigCircuit<Fr>>("sig_circuit"),
+            CircuitMap::of::<EccCircuit<Fr>>("ecc_circuit"),
+            CircuitMap::of::<ProverSuperCircuit>("super_circuit"),
+        ];
+        fs::write(&path, serde_json::to_vec_pretty(&maps)?)?;
+    }
+    let table = markdown_table(&shapes);
+    match cli.markdown {
+        Some(path) => fs::write(path, table)?,
+        None => print!("{}", table),
+    }
+    Ok(())
+}
@@ -1,28 +1,29 @@
-use bus_mapping::circuit_input_builder::BuilderClient;
-use bus_mapping::rpc::GethClient;
+use bus_mapping::circuit_input_builder::{BuilderClient, CircuitsParams};
+use bus_mapping::rpc::{GethClient, RpcClient};
 use env_logger::Env;
-use ethers_providers::Http;
 use halo2_proofs::{
     plonk::*,
     poly::commitment::Params,
     transcript::{Blake2bWrite, Challenge255},
 };
 use pairing::bn256::{Fr, G1Affine};
+use prover::circuits::{evm_circuit_key_id, state_circuit_key_id, ProverStateCircuit};
+use prover::keys::KeyCache;
+use prover::proof::ProofFile;
 use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
 use std::env::var;
 use std::fs::File;
 use std::io::BufReader;
-use std::str::FromStr;
+use std::path::PathBuf;
 use zkevm_circuits::evm_circuit::{
     table::FixedTableTag, test::TestCircuit, witness::block_convert,
 };
-use zkevm_circuits::state_circuit::StateCircuit;
 
 #[derive(serde::Serialize)]
 pub struct Proofs {
-    state_proof: eth_types::Bytes,
-    evm_proof: eth_types::Bytes,
+    state_proof: ProofFile,
+    evm_proof: ProofFile,
 }
 
 /// This command generates and prints the proofs to stdout.
@@ -30,6 +31,12 @@ pub struct Proofs {
 /// - BLOCK_NUM - the block number to generate the proof for
 /// - RPC_URL - a geth http rpc that supports the debug namespace
 /// - PARAMS_PATH - a path to a file generated with the gen_params tool
+/// - DEGREE - the degree of the params
+/// Optional environment variables:
+/// - KEYS_PATH - a directory where the verifying keys are cached between runs,
+///   required to check the proofs with the verify_cmd tool
+/// - PROOFS_PATH - a directory where each proof is also written to a file named
+///   after its circuit, to be checked with the verify_cmd tool
 // TODO: move the proof generation into a module once we implement a rpc daemon for generating
 // proofs.
 #[tokio::main]
@@ -48,6 +55,12 @@ async fn main() {
         .expect("PARAMS_PATH env var")
         .parse()
         .expect("Cannot parse PARAMS_PATH env var");
+    let degree: u32 = var("DEGREE")
+        .expect("DEGREE env var")
+        .parse()
+        .expect("Cannot parse DEGREE env var");
+    let key_cache = var("KEYS_PATH").ok().map(KeyCache::new);
+    let proofs_path = var("PROOFS_PATH").ok().map(PathBuf::from);
 
     // load polynomial commitment parameters
     let params_fs = File::open(&params_path).expect("couldn't open params");
@@ -55,12 +68,12 @@ async fn main() {
         Params::read::<_>(&mut BufReader::new(params_fs)).expect("Failed to read params");
 
     // request & build the inputs for the circuits
-    let geth_client = GethClient::new(Http::from_str(&rpc_url).expect("GethClient from RPC_URL"));
-    let builder = BuilderClient::new(geth_client)
+    let geth_client = GethClient::new(RpcClient::new(rpc_url.parse().expect("URL from RPC_URL")));
+    let builder = BuilderClient::new(geth_client, CircuitsParams::default())
         .await
         .expect("BuilderClient from GethClient");
     let builder = builder
-        .gen_inputs(block_num)
+        .gen_inputs_batched(block_num)
         .await
         .expect("gen_inputs for BLOCK_NUM");
 
@@ -72,12 +85,16 @@ async fn main() {
         // generate evm_circuit proof
         let circuit = TestCircuit::<Fr>::new(block.clone(), FixedTableTag::iterator().collect());
 
-        // TODO: can this be pre-generated to a file?
-        // related
-        // https://github.com/zcash/halo2/issues/443
-        // https://github.com/zcash/halo2/issues/449
-        let vk = keygen_vk(&params, &circuit).expect("keygen_vk for params, evm_circuit");
-        let pk = keygen_pk(&params, vk, &circuit).expect("keygen_pk for params, vk, evm_circuit");
+        let id = evm_circuit_key_id(degree);
+        let pk = match &key_cache {
+            Some(key_cache) => key_cache
+                .load_or_keygen(&id, &params, &circuit)
+                .expect("cached keys for params, evm_circuit"),
+            None => {
+                let vk = keygen_vk(&params, &circuit).expect("keygen_vk for params, evm_circuit");
+                keygen_pk(&params, vk, &circuit).expect("keygen_pk for params, vk, evm_circuit")
+            }
+        };
 
         // Create randomness
         let rng = XorShiftRng::from_seed([
@@ -88,32 +105,24 @@ async fn main() {
         // create a proof
         let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
         create_proof(&params, &pk, &[circuit], &[], rng, &mut transcript).expect("evm proof");
-        evm_proof = transcript.finalize();
+        evm_proof = ProofFile::new(&id, &[], transcript.finalize());
     }
 
     {
         // generate state_circuit proof
-        //
-        // TODO: this should be configurable
-        const MEMORY_ADDRESS_MAX: usize = 2000;
-        const STACK_ADDRESS_MAX: usize = 1300;
-        const MEMORY_ROWS_MAX: usize = 16384;
-        const STACK_ROWS_MAX: usize = 16384;
-        const STORAGE_ROWS_MAX: usize = 16384;
-        const GLOBAL_COUNTER_MAX: usize = MEMORY_ROWS_MAX + STACK_ROWS_MAX + STORAGE_ROWS_MAX;
-
-        let circuit = StateCircuit::<
-            Fr,
-            true,
-            GLOBAL_COUNTER_MAX,
-            MEMORY_ADDRESS_MAX,
-            STACK_ADDRESS_MAX,
-            GLOBAL_COUNTER_MAX,
-        >::new(block.randomness, &block.rws);
+        let circuit = ProverStateCircuit::new(block.randomness, &block.rws)
+            .expect("too many rw operations for the state circuit");
 
-        // TODO: same quest like in the first scope
-        let vk = keygen_vk(&params, &circuit).expect("keygen_vk for params, state_circuit");
-        let pk = keygen_pk(&params, vk, &circuit).expect("keygen_pk for params, vk, state_circuit");
+        let id = state_circuit_key_id(degree);
+        let pk = match &key_cache {
+            Some(key_cache) => key_cache
+                .load_or_keygen(&id, &params, &circuit)
+                .expect("cached keys for params, state_circuit"),
+            None => {
+                let vk = keygen_vk(&params, &circuit).expect("keygen_vk for params, state_circuit");
+                keygen_pk(&params, vk, &circuit).expect("keygen_pk for params, vk, state_circuit")
+            }
+        };
 
         // Create randomness
         let rng = XorShiftRng::from_seed([
@@ -122,16 +131,34 @@ async fn main() {
         ]);
 
         // create a proof
+        let instance = circuit.instance();
+        let columns = instance.iter().map(Vec::as_slice).collect::<Vec<_>>();
         let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-        create_proof(&params, &pk, &[circuit], &[], rng, &mut transcript).expect("state proof");
-        state_proof = transcript.finalize();
+        create_proof(
+            &params,
+            &pk,
+            &[circuit],
+            &[&columns[..]],
+            rng,
+            &mut transcript,
+        )
+        .expect("state proof");
+        state_proof = ProofFile::new(&id, &instance, transcript.finalize());
+    }
+
+    if let Some(proofs_path) = proofs_path {
+        for proof in [&evm_proof, &state_proof] {
+            proof
+                .write(proofs_path.join(format!("{}.json", proof.circuit_id)))
+                .expect("write proof file");
+        }
     }
 
     serde_json::to_writer(
         std::io::stdout(),
         &Proofs {
-            evm_proof: evm_proof.into(),
-            state_proof: state_proof.into(),
+            evm_proof,
+            state_proof,
         },
     )
     .expect("serialize and write");
@@ -0,0 +1,52 @@
+use env_logger::Env;
+use halo2_proofs::poly::commitment::Params;
+use pairing::bn256::{Fr, G1Affine};
+use prover::circuits::{ProverStateCircuit, EVM_CIRCUIT_ID, STATE_CIRCUIT_ID};
+use prover::keys::KeyCache;
+use prover::proof::ProofFile;
+use std::env::var;
+use std::fs::File;
+use std::io::BufReader;
+use std::process::exit;
+use zkevm_circuits::evm_circuit::test::TestCircuit;
+
+/// This command verifies a proof file written by the prover_cmd tool.
+/// Required environment variables:
+/// - PROOF_PATH - a path to the proof file to verify
+/// - PARAMS_PATH - a path to the file of the params the proof was created with
+/// - KEYS_PATH - the directory where the verifying key of the proof is cached
+fn main() {
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+
+    let proof_path: String = var("PROOF_PATH")
+        .expect("PROOF_PATH env var")
+        .parse()
+        .expect("Cannot parse PROOF_PATH env var");
+    let params_path: String = var("PARAMS_PATH")
+        .expect("PARAMS_PATH env var")
+        .parse()
+        .expect("Cannot parse PARAMS_PATH env var");
+    let keys_path: String = var("KEYS_PATH")
+        .expect("KEYS_PATH env var")
+        .parse()
+        .expect("Cannot parse KEYS_PATH env var");
+
+    let proof = ProofFile::read(&proof_path).expect("read proof file");
+    let params_fs = File::open(&params_path).expect("couldn't open params");
+    let params: Params<G1Affine> =
+        Params::read::<_>(&mut BufReader::new(params_fs)).expect("Failed to read params");
+    let key_cache = KeyCache::new(keys_path);
+
+    let result = match proof.circuit_id.as_str() {
+        EVM_CIRCUIT_ID => proof.verify::<TestCircuit<Fr>>(&params, &key_cache),
+        STATE_CIRCUIT_ID => proof.verify::<ProverStateCircuit>(&params, &key_cache),
+        circuit_id => panic!("unknown circuit {}", circuit_id),
+    };
+    match result {
+        Ok(()) => println!("proof of {} is valid", proof.circuit_id),
+        Err(err) => {
+            eprintln!("proof of {} is invalid: {}", proof.circuit_id, err);
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,532 @@
+//! Command line interface of the prover, so that operating it doesn't require
+//! writing Rust:
+//!
+//! - `setup` stores the SRS parameters, read from a file, downloaded from a
+//!   ceremony or generated, and the verifying keys of the circuits in a key
+//!   cache directory;
+//! - `witness` queries a geth node for a block and saves its witness block,
+//!   which may be proven on another machine;
+//! - `prove` proves a saved witness block with the keys of the key cache,
+//!   possibly split into chunks proven one by one;
+//! - `verify` verifies proof files and bundles;
+//! - `aggregate` verifies the proof files of a block and gathers them in a
+//!   bundle, checking that the proofs of chunks stitch, until a circuit
+//!   aggregates them into a single proof.
+//!
+//! The spans of the fetching of a block, of the bus-mapping of its
+//! transactions, of the witness assignment of every circuit and of the phases
+//! of the proving are logged on close with their duration, filtered by the
+//! `RUST_LOG` env var.  With the `metrics` feature, `--metrics-addr` serves
+//! their durations to Prometheus during the run.
+
+use bus_mapping::circuit_input_builder::{BuilderClient, CircuitsParams};
+use bus_mapping::rpc::{GethClient, RpcClient};
+use clap::{ArgEnum, Args, Parser, Subcommand};
+use halo2_proofs::{plonk::Circuit, poly::commitment::Params};
+use pairing::bn256::{Fr, G1Affine};
+use prover::circuits::{
+    evm_circuit_instances, evm_circuit_key_id, prover_circuits_params, prover_super_circuit,
+    state_circuit_key_id, super_circuit_key_id, ProverStateCircuit, ProverSuperCircuit,
+    EVM_CIRCUIT_ID, STATE_CIRCUIT_ID, SUPER_CIRCUIT_ID,
+};
+use prover::keys::{KeyCache, KeyId};
+use prover::proof::{ProofBundle, ProofFile, ProofFileError};
+use prover::srs::{SrsCache, SrsManifest};
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::BufReader,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::exit,
+};
+use tracing_subscriber::{
+    fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter,
+};
+use zkevm_circuits::evm_circuit::{
+    table::FixedTableTag,
+    test::TestCircuit,
+    witness::{block_convert, Block},
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// Circuit proving a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+enum CircuitKind {
+    /// EVM circuit with all the fixed tables
+    Evm,
+    /// State circuit
+    State,
+    /// Super circuit, which proves the block on its own
+    Super,
+}
+
+#[derive(Parser)]
+#[clap(
+    name = "zkevm-prover",
+    version,
+    about = "Proves the blocks of an EVM chain"
+)]
+struct Cli {
+    /// Address the Prometheus metrics of the durations of the spans are
+    /// served on during the run, like `0.0.0.0:9184`, with the `metrics`
+    /// feature
+    #[clap(long, global = true)]
+    metrics_addr: Option<SocketAddr>,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Stores the SRS parameters and the verifying keys of the circuits in the
+    /// key cache
+    Setup {
+        #[clap(flatten)]
+        keys: KeyArgs,
+        #[clap(flatten)]
+        circuits: CircuitArgs,
+        #[clap(flatten)]
+        srs: SrsArgs,
+        /// JSON file of the capacities of the circuits of the super circuit,
+        /// the ones of the prover otherwise
+        #[clap(long)]
+        circuits_params: Option<PathBuf>,
+    },
+    /// Queries a geth node for a block and saves its witness block
+    Witness {
+        /// URL of a geth node with the debug namespace
+        #[clap(long)]
+        rpc_url: String,
+        /// Number of the block
+        #[clap(long)]
+        block: u64,
+        /// JSON file of the capacities of the circuits proving the block, the
+        /// ones of the prover otherwise
+        #[clap(long)]
+        circuits_params: Option<PathBuf>,
+        /// File the witness block is saved to
+        #[clap(long)]
+        out: PathBuf,
+    },
+    /// Proves a saved witness block with the keys of the key cache, which are
+    /// generated if they are missing
+    Prove {
+        #[clap(flatten)]
+        keys: KeyArgs,
+        #[clap(flatten)]
+        circuits: CircuitArgs,
+        /// File of the witness block
+        #[clap(long)]
+        witness: PathBuf,
+        /// Directory where the proof file of every circuit is written
+        #[clap(long)]
+        out: PathBuf,
+        #[clap(flatten)]
+        chunks: ChunkArgs,
+    },
+    /// Verifies proof files and bundles
+    Verify {
+        /// Directory of the key cache holding the verifying keys
+        #[clap(long)]
+        keys: PathBuf,
+        /// Proof files or bundles
+        #[clap(required = true)]
+        proofs: Vec<PathBuf>,
+    },
+    /// Verifies the proof files of a block and gathers them in a bundle
+    Aggregate {
+        /// Directory of the key cache holding the verifying keys
+        #[clap(long)]
+        keys: PathBuf,
+        /// File the bundle is written to
+        #[clap(long)]
+        out: PathBuf,
+        /// Proof files
+        #[clap(required = true)]
+        proofs: Vec<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct KeyArgs {
+    /// Directory of the key cache
+    #[clap(long)]
+    keys: PathBuf,
+    /// Degree of the circuits
+    #[clap(long)]
+    k: u32,
+}
+
+/// SRS parameters of degree `k` from a trusted setup, which are generated
+/// with an unsafe setup if none is given.
+#[derive(Args)]
+struct SrsArgs {
+    /// File of SRS parameters
+    #[clap(long, conflicts_with = "srs-manifest")]
+    srs: Option<PathBuf>,
+    /// JSON manifest of the ceremony the SRS parameters are downloaded from
+    #[clap(long)]
+    srs_manifest: Option<PathBuf>,
+    /// Directory where the downloaded SRS parameters are cached, the key
+    /// cache otherwise
+    #[clap(long)]
+    srs_dir: Option<PathBuf>,
+}
+
+/// Capacity of the chunks a block is split into, which is proven whole if
+/// none is given.
+#[derive(Args)]
+struct ChunkArgs {
+    /// Maximum number of steps of a chunk
+    #[clap(long)]
+    max_steps: Option<usize>,
+    /// Maximum number of rw operations of the steps of a chunk, the capacity
+    /// of the block otherwise
+    #[clap(long, requires = "max-steps")]
+    max_rws: Option<usize>,
+}
+
+#[derive(Args)]
+struct CircuitArgs {
+    /// Circuits to set up or to prove
+    #[clap(long = "circuit", arg_enum, default_values = &["evm", "state"])]
+    circuits: Vec<CircuitKind>,
+}
+
+fn read_circuits_params(path: Option<&Path>) -> Result<CircuitsParams> {
+    match path {
+        Some(path) => Ok(serde_json::from_slice(&fs::read(path)?)?),
+        None => Ok(prover_circuits_params()),
+    }
+}
+
+fn load_params(key_cache: &KeyCache, k: u32) -> Result<Params<G1Affine>> {
+    key_cache.load_params(k)?.ok_or_else(|| {
+        format!(
+            "no SRS parameters of degree {} in the key cache, run setup first",
+            k
+        )
+        .into()
+    })
+}
+
+// Returns the proof of `circuit` with the keys `id`, generated and stored in
+// `key_cache` if they are missing.
+fn prove_circuit<C: Circuit<Fr>>(
+    key_cache: &KeyCache,
+    params: &Params<G1Affine>,
+    id: KeyId,
+    circuit: C,
+    instances: Vec<Vec<Fr>>,
+) -> Result<ProofFile> {
+    let pk = key_cache.load_or_keygen(&id, params, &circuit)?;
+    log::info!("proving {} of degree {}", id.circuit_id, id.k);
+    Ok(ProofFile::create(&id, params, &pk, circuit, &instances)?)
+}
+
+// Returns the proof of the circuit `kind` of `block`, or the one of a block
+// without witnesses to generate the keys.
+fn prove_block(
+    key_cache: &KeyCache,
+    params: &Params<G1Affine>,
+    k: u32,
+    kind: CircuitKind,
+    block: &Block<Fr>,
+) -> Result<ProofFile> {
+    let fixed_table_tags = FixedTableTag::iterator().collect();
+    let mut proof = match kind {
+        CircuitKind::Evm => prove_circuit(
+            key_cache,
+            params,
+            evm_circuit_key_id(k),
+            TestCircuit::new(block.clone(), fixed_table_tags),
+            evm_circuit_instances(block, k),
+        ),
+        CircuitKind::State => {
+            let circuit = ProverStateCircuit::new(block.randomness, &block.rws)?
+                .with_chunk(block.chunk.as_ref());
+            let instances = circuit.instance();
+            prove_circuit(
+                key_cache,
+                params,
+                state_circuit_key_id(k),
+                circuit,
+                instances,
+            )
+        }
+        CircuitKind::Super => {
+            let circuit = prover_super_circuit(block.clone(), k);
+            let instances = circuit.instance()?;
+            prove_circuit(
+                key_cache,
+                params,
+                super_circuit_key_id(k, &block.circuits_params),
+                circuit,
+                instances,
+            )
+        }
+    }?;
+    proof.chunk = block.chunk.clone();
+    Ok(proof)
+}
+
+async fn setup(
+    keys: KeyArgs,
+    circuits: CircuitArgs,
+    srs: SrsArgs,
+    circuits_params: Option<PathBuf>,
+) -> Result<()> {
+    let key_cache = KeyCache::new(&keys.keys);
+    let params = match (srs.srs, srs.srs_manifest) {
+        (Some(path), _) => {
+            let mut reader = BufReader::new(File::open(path)?);
+            Some(Params::<G1Affine>::read(&mut reader)?)
+        }
+        (None, Some(path)) => {
+            let srs_cache = SrsCache::new(srs.srs_dir.unwrap_or(keys.keys));
+            let manifest = SrsManifest::read(path)?;
+            Some(srs_cache.load_or_download(&manifest, keys.k).await?)
+        }
+        (None, None) => None,
+    };
+    let params = match params {
+        Some(params) => {
+            key_cache.store_params(keys.k, &params)?;
+            params
+        }
+        None => key_cache.load_or_setup_params(keys.k)?,
+    };
+    let circuits_params = read_circuits_params(circuits_params.as_deref())?;
+    for kind in circuits.circuits {
+        let (id, vk_exists) = match kind {
+            CircuitKind::Evm => {
+                let id = evm_circuit_key_id(keys.k);
+                let vk = key_cache.load_vk::<TestCircuit<Fr>>(&id, &params)?;
+                (id, vk.is_some())
+            }
+            CircuitKind::State => {
+                let id = state_circuit_key_id(keys.k);
+                let vk = key_cache.load_vk::<ProverStateCircuit>(&id, &params)?;
+                (id, vk.is_some())
+            }
+            CircuitKind::Super => {
+                let id = super_circuit_key_id(keys.k, &circuits_params);
+                let vk = key_cache.load_vk::<ProverSuperCircuit>(&id, &params)?;
+                (id, vk.is_some())
+            }
+        };
+        if vk_exists {
+            log::info!("verifying key of {} is cached", id.circuit_id);
+            continue;
+        }
+        let block = Block {
+            circuits_params,
+            ..Default::default()
+        };
+        let fixed_table_tags = FixedTableTag::iterator().collect();
+        match kind {
+            CircuitKind::Evm => {
+                let circuit = TestCircuit::<Fr>::new(block, fixed_table_tags);
+                key_cache.load_or_keygen(&id, &params, &circuit)?;
+            }
+            CircuitKind::State => {
+                key_cache.load_or_keygen(&id, &params, &ProverStateCircuit::default())?;
+            }
+            CircuitKind::Super => {
+                let circuit = prover_super_circuit(block, keys.k);
+                key_cache.load_or_keygen(&id, &params, &circuit)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn witness(
+    rpc_url: String,
+    block: u64,
+    circuits_params: Option<PathBuf>,
+    out: PathBuf,
+) -> Result<()> {
+    let circuits_params = read_circuits_params(circuits_params.as_deref())?;
+    let geth_client = GethClient::new(RpcClient::new(rpc_url.parse()?));
+    let builder = BuilderClient::new(geth_client, circuits_params).await?;
+    let builder = builder.gen_inputs_batched(block).await?;
+    block_convert(&builder.block, &builder.code_db).save(&out)?;
+    log::info!("saved the witness of block {} to {}", block, out.display());
+    Ok(())
+}
+
+fn prove(
+    keys: KeyArgs,
+    circuits: CircuitArgs,
+    witness: PathBuf,
+    out: PathBuf,
+    chunks: ChunkArgs,
+) -> Result<()> {
+    let key_cache = KeyCache::new(keys.keys);
+    let params = load_params(&key_cache, keys.k)?;
+    let block = Block::<Fr>::load(witness)?;
+    let blocks = match chunks.max_steps {
+        Some(max_steps) => {
+            let max_rws = chunks.max_rws.unwrap_or(block.circuits_params.max_rws);
+            let blocks = block.chunks(max_steps, max_rws)?;
+            log::info!("split the block into {} chunks", blocks.len());
+            blocks
+        }
+        None => vec![block],
+    };
+    fs::create_dir_all(&out)?;
+    for block in blocks.iter() {
+        for kind in circuits.circuits.iter() {
+            let proof = prove_block(&key_cache, &params, keys.k, *kind, block)?;
+            let path = match &proof.chunk {
+                Some(chunk) => out.join(format!("{}-chunk-{}.json", proof.circuit_id, chunk.index)),
+                None => out.join(format!("{}.json", proof.circuit_id)),
+            };
+            proof.write(&path)?;
+            log::info!("wrote {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+// Returns the proofs of a proof file or of a bundle.
+fn read_proofs(path: &Path) -> Result<Vec<ProofFile>> {
+    match ProofFile::read(path) {
+        Ok(proof) => Ok(vec![proof]),
+        Err(ProofFileError::Json(_)) => Ok(ProofBundle::read(path)?.proofs),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn verify_proof(key_cache: &KeyCache, proof: &ProofFile) -> Result<()> {
+    let params = load_params(key_cache, proof.k)?;
+    match proof.circuit_id.as_str() {
+        EVM_CIRCUIT_ID => proof.verify::<TestCircuit<Fr>>(&params, key_cache)?,
+        STATE_CIRCUIT_ID => proof.verify::<ProverStateCircuit>(&params, key_cache)?,
+        SUPER_CIRCUIT_ID => proof.verify::<ProverSuperCircuit>(&params, key_cache)?,
+        circuit_id => return Err(format!("unknown circuit {}", circuit_id).into()),
+    }
+    Ok(())
+}
+
+fn verify(keys: PathBuf, paths: Vec<PathBuf>) -> Result<()> {
+    let key_cache = KeyCache::new(keys);
+    let mut n_invalid = 0;
+    for path in paths {
+        let proofs = read_proofs(&path)?;
+        if let Err(err) = ProofBundle::new(proofs.clone()).check_chunks() {
+            n_invalid += 1;
+            println!("{}: {}", path.display(), err);
+        }
+        for proof in proofs {
+            match verify_proof(&key_cache, &proof) {
+                Ok(()) => println!("{}: proof of {} is valid", path.display(), proof.circuit_id),
+                Err(err) => {
+                    n_invalid += 1;
+                    println!(
+                        "{}: proof of {} is invalid: {}",
+                        path.display(),
+                        proof.circuit_id,
+                        err
+                    );
+                }
+            }
+        }
+    }
+    if n_invalid > 0 {
+        return Err(format!("{} invalid proofs", n_invalid).into());
+    }
+    Ok(())
+}
+
+fn aggregate(keys: PathBuf, out: PathBuf, paths: Vec<PathBuf>) -> Result<()> {
+    let key_cache = KeyCache::new(keys);
+    let mut proofs = Vec::new();
+    for path in paths {
+        let proof = ProofFile::read(&path)?;
+        verify_proof(&key_cache, &proof)
+            .map_err(|err| format!("{}: proof of {}: {}", path.display(), proof.circuit_id, err))?;
+        proofs.push(proof);
+    }
+    let bundle = ProofBundle::new(proofs);
+    bundle.check_chunks()?;
+    bundle.write(&out)?;
+    log::info!("wrote {}", out.display());
+    Ok(())
+}
+
+// Installs the subscriber logging the events and the closed spans, which
+// serves the metrics of the durations of the spans on `metrics_addr` if any.
+async fn init_tracing(metrics_addr: Option<SocketAddr>) -> Result<()> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let fmt = tracing_subscriber::fmt::layer().with_span_events(FmtSpan::CLOSE);
+    #[cfg(feature = "metrics")]
+    {
+        use prover::metrics::{serve, SpanMetrics};
+
+        let registry = prometheus::Registry::new();
+        let metrics = match metrics_addr {
+            Some(_) => Some(SpanMetrics::new(&registry)?),
+            None => None,
+        };
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt)
+            .with(metrics)
+            .try_init()?;
+        if let Some(addr) = metrics_addr {
+            serve(addr, registry).await?;
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    {
+        if metrics_addr.is_some() {
+            return Err("the prover is built without the metrics feature".into());
+        }
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt)
+            .try_init()?;
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = init_tracing(cli.metrics_addr).await {
+        eprintln!("error: {}", err);
+        exit(1);
+    }
+
+    let result = match cli.command {
+        Command::Setup {
+            keys,
+            circuits,
+            srs,
+            circuits_params,
+        } => setup(keys, circuits, srs, circuits_params).await,
+        Command::Witness {
+            rpc_url,
+            block,
+            circuits_params,
+            out,
+        } => witness(rpc_url, block, circuits_params, out).await,
+        Command::Prove {
+            keys,
+            circuits,
+            witness,
+            out,
+            chunks,
+        } => prove(keys, circuits, witness, out, chunks),
+        Command::Verify { keys, proofs } => verify(keys, proofs),
+        Command::Aggregate { keys, out, proofs } => aggregate(keys, out, proofs),
+    };
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        exit(1);
+    }
+}
@@ -0,0 +1,144 @@
+//! Map of the circuits: every column of their `ConstraintSystem` with the
+//! gadget or chip owning it, see `zkevm_circuits::annotation`, and every gate
+//! with the names and the degrees of its constraints, so that tools outside
+//! of the circuits can analyse them.
+//!
+//! The maps are reported as JSON by the `circuit-shapes` binary, with
+//! `--map`.
+
+use halo2_proofs::plonk::{Circuit, ConstraintSystem};
+use pairing::bn256::Fr;
+use serde::{Deserialize, Serialize};
+use zkevm_circuits::annotation::{Annotate, ColumnAnnotations, ColumnKind};
+
+/// Column of a circuit, with its owner and its name if it is named.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnEntry {
+    /// Kind of the column
+    pub kind: ColumnKind,
+    /// Index of the column among the ones of its kind
+    pub index: usize,
+    /// Path of the gadget or chip owning the column
+    pub owner: Option<String>,
+    /// Name of the column in its owner
+    pub name: Option<String>,
+}
+
+/// Constraint of a gate.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstraintEntry {
+    /// Name of the constraint, which is empty if it isn't named
+    pub name: String,
+    /// Degree of the constraint
+    pub degree: usize,
+}
+
+/// Gate of a circuit.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GateEntry {
+    /// Name of the gate
+    pub name: String,
+    /// Constraints of the gate
+    pub constraints: Vec<ConstraintEntry>,
+}
+
+/// Map of the columns and of the gates of a circuit.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitMap {
+    /// Name of the circuit
+    pub circuit: String,
+    /// Advice, fixed and instance columns, by kind and index
+    pub columns: Vec<ColumnEntry>,
+    /// Gates, in the order they are created
+    pub gates: Vec<GateEntry>,
+}
+
+impl CircuitMap {
+    /// Returns the map of the circuit `C`, which is named `circuit`, the
+    /// owner of all its columns.
+    pub fn of<C: Circuit<Fr>>(circuit: &str) -> Self
+    where
+        C::Config: Annotate,
+    {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let config = C::configure(&mut cs);
+        let mut annotations = ColumnAnnotations::default();
+        config.annotate(circuit, &mut annotations);
+
+        let columns = [
+            (ColumnKind::Advice, cs.num_advice_columns()),
+            (ColumnKind::Fixed, cs.num_fixed_columns()),
+            (ColumnKind::Instance, cs.num_instance_columns()),
+        ]
+        .into_iter()
+        .flat_map(|(kind, n_columns)| {
+            let annotations = &annotations;
+            (0..n_columns).map(move |index| {
+                let annotation = annotations.get(kind, index);
+                ColumnEntry {
+                    kind,
+                    index,
+                    owner: annotation.map(|annotation| annotation.owner.clone()),
+                    name: annotation.map(|annotation| annotation.name.clone()),
+                }
+            })
+        })
+        .collect();
+        let gates = cs
+            .gates()
+            .iter()
+            .map(|gate| GateEntry {
+                name: gate.name().to_string(),
+                constraints: gate
+                    .polynomials()
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, poly)| ConstraintEntry {
+                        name: gate.constraint_name(idx).to_string(),
+                        degree: poly.degree(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            circuit: circuit.to_string(),
+            columns,
+            gates,
+        }
+    }
+
+    /// Returns the columns which aren't named, like the instance columns of
+    /// the powers of the randomness.
+    pub fn unnamed_columns(&self) -> impl Iterator<Item = &ColumnEntry> {
+        self.columns.iter().filter(|column| column.name.is_none())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CircuitMap;
+    use pairing::bn256::Fr;
+    use zkevm_circuits::{annotation::ColumnKind, bytecode_circuit::BytecodeCircuit};
+
+    #[test]
+    fn bytecode_circuit_map() {
+        let map = CircuitMap::of::<BytecodeCircuit<Fr>>("bytecode_circuit");
+        // Only the instance column of the randomness isn't named.
+        let unnamed = map.unnamed_columns().collect::<Vec<_>>();
+        assert_eq!(unnamed.len(), 1);
+        assert_eq!(unnamed[0].kind, ColumnKind::Instance);
+
+        assert!(map
+            .columns
+            .iter()
+            .filter_map(|column| column.owner.as_ref())
+            .all(|owner| owner.starts_with("bytecode_circuit")));
+        assert!(!map.gates.is_empty());
+        assert!(map
+            .gates
+            .iter()
+            .flat_map(|gate| gate.constraints.iter())
+            .all(|constraint| constraint.degree > 0));
+    }
+}
@@ -0,0 +1,181 @@
+//! Circuits proven by the prover, with the ids of their keys.
+
+use crate::keys::KeyId;
+use bus_mapping::circuit_input_builder::CircuitsParams;
+use eth_types::ToScalar;
+use pairing::bn256::Fr;
+use std::iter;
+use zkevm_circuits::{
+    evm_circuit::{
+        table::FixedTableTag,
+        witness::{Block, Chunk},
+        EvmCircuit,
+    },
+    pi_circuit::PublicData,
+    state_circuit::{StateCircuit, DEFAULT_MAX_DEGREE},
+    super_circuit::SuperCircuit,
+};
+
+/// Id of the EVM circuit.
+pub const EVM_CIRCUIT_ID: &str = "evm_circuit";
+/// Id of the state circuit.
+pub const STATE_CIRCUIT_ID: &str = "state_circuit";
+/// Id of the super circuit.
+pub const SUPER_CIRCUIT_ID: &str = "super_circuit";
+
+// TODO: this should be configurable
+/// Maximum memory address of the state circuit.
+pub const MEMORY_ADDRESS_MAX: usize = 2000;
+/// Maximum stack address of the state circuit.
+pub const STACK_ADDRESS_MAX: usize = 1300;
+const MEMORY_ROWS_MAX: usize = 16384;
+const STACK_ROWS_MAX: usize = 16384;
+const STORAGE_ROWS_MAX: usize = 16384;
+/// Maximum rw counter and number of rows of the state circuit.
+pub const GLOBAL_COUNTER_MAX: usize = MEMORY_ROWS_MAX + STACK_ROWS_MAX + STORAGE_ROWS_MAX;
+/// Maximum number of withdrawals committed by the PI circuit of the super
+/// circuit.
+pub const MAX_WITHDRAWALS: usize = 16;
+
+/// State circuit proven by the prover.
+pub type ProverStateCircuit = StateCircuit<
+    Fr,
+    true,
+    GLOBAL_COUNTER_MAX,
+    MEMORY_ADDRESS_MAX,
+    STACK_ADDRESS_MAX,
+    GLOBAL_COUNTER_MAX,
+    DEFAULT_MAX_DEGREE,
+>;
+
+/// Super circuit proven by the prover, whose state circuit has the capacity of
+/// `ProverStateCircuit`.
+pub type ProverSuperCircuit = SuperCircuit<Fr, MEMORY_ADDRESS_MAX, GLOBAL_COUNTER_MAX>;
+
+/// Returns the `ProverSuperCircuit` of degree `k` proving `block` with all the
+/// fixed tables.  The block hash, the parent hash and the state roots
+/// committed by its PI circuit aren't part of the witness of the block yet, so
+/// they are zero, and so are the withdrawals.
+pub fn prover_super_circuit(block: Block<Fr>, k: u32) -> ProverSuperCircuit {
+    ProverSuperCircuit {
+        block,
+        fixed_table_tags: FixedTableTag::iterator().collect(),
+        size: 1 << k,
+        header: PublicData::default(),
+        max_withdrawals: MAX_WITHDRAWALS,
+    }
+}
+
+/// Returns the capacities of the circuits of the blocks proven by the prover,
+/// whose number of rw operations is the one of `ProverSuperCircuit`.
+pub fn prover_circuits_params() -> CircuitsParams {
+    CircuitsParams {
+        max_rws: GLOBAL_COUNTER_MAX,
+        ..Default::default()
+    }
+}
+
+/// Returns the values of the `n` instance columns of the powers of
+/// `randomness` of a circuit of degree `k`, the `i`th column holding
+/// `randomness^(i + 1)` on all the rows but the 64 ones of the blinding
+/// factors.
+pub fn power_of_randomness_instances(randomness: Fr, n: usize, k: u32) -> Vec<Vec<Fr>> {
+    iter::successors(Some(randomness), |power| Some(*power * randomness))
+        .take(n)
+        .map(|power| vec![power; (1 << k) - 64])
+        .collect()
+}
+
+/// Number of instance columns of the powers of the randomness of the EVM
+/// circuit, which are followed by the ones of its chunk states.
+const EVM_CIRCUIT_POW_RAND_SIZE: usize = 31;
+
+/// Returns the values of the instance columns of the EVM circuit of degree `k`
+/// proving `block`: the powers of its randomness, then the begin and end
+/// states of its chunk.
+pub fn evm_circuit_instances(block: &Block<Fr>, k: u32) -> Vec<Vec<Fr>> {
+    let mut instances =
+        power_of_randomness_instances(block.randomness, EVM_CIRCUIT_POW_RAND_SIZE, k);
+    instances.extend(EvmCircuit::chunk_states_instance(block, (1 << k) - 64));
+    instances
+}
+
+/// Returns true if the begin and end states of `chunk` are the ones of the
+/// values `instances` of the instance columns of a proof of the EVM circuit,
+/// on all their rows.
+pub fn evm_circuit_proves_chunk(chunk: &Chunk, instances: &[Vec<Fr>]) -> bool {
+    let randomness = match instances.first().and_then(|column| column.first()) {
+        Some(randomness) => *randomness,
+        None => return false,
+    };
+    let values = chunk.instance(randomness);
+    let columns = &instances[EVM_CIRCUIT_POW_RAND_SIZE.min(instances.len())..];
+    columns.len() == values.len()
+        && columns
+            .iter()
+            .zip(values)
+            .all(|(column, value)| !column.is_empty() && column.iter().all(|cell| *cell == value))
+}
+
+/// Number of instance columns of the powers of the randomness of the state
+/// circuit, which are followed by the one of the rw counter its chunk begins
+/// at.
+const STATE_CIRCUIT_POW_RAND_SIZE: usize = 31;
+
+/// Returns true if the rw counter `chunk` begins at and the fingerprint of the
+/// rw operations carried to it are the ones of the values `instances` of the
+/// instance columns of a proof of the state circuit, see
+/// `StateCircuit::instance`.
+pub fn state_circuit_proves_chunk(chunk: &Chunk, instances: &[Vec<Fr>]) -> bool {
+    let rw_counter_begin = Fr::from(chunk.begin.rw_counter as u64);
+    let carried_rws = match chunk.begin.carried_rws.to_scalar() {
+        Some(carried_rws) => carried_rws,
+        None => return false,
+    };
+    match (instances.get(STATE_CIRCUIT_POW_RAND_SIZE), instances.last()) {
+        (Some(rw_counters_begin), Some(fingerprint)) => {
+            !rw_counters_begin.is_empty()
+                && rw_counters_begin
+                    .iter()
+                    .all(|cell| *cell == rw_counter_begin)
+                && fingerprint[..] == [carried_rws]
+        }
+        _ => false,
+    }
+}
+
+/// Returns the id of the keys of the EVM circuit of degree `k`, with all the
+/// fixed tables.
+pub fn evm_circuit_key_id(k: u32) -> KeyId {
+    let fixed_table_tags = FixedTableTag::iterator()
+        .map(|tag| tag as u64)
+        .collect::<Vec<_>>();
+    KeyId::new(EVM_CIRCUIT_ID, &fixed_table_tags, k)
+}
+
+/// Returns the id of the keys of `ProverStateCircuit` of degree `k`.
+pub fn state_circuit_key_id(k: u32) -> KeyId {
+    let params = (
+        GLOBAL_COUNTER_MAX,
+        MEMORY_ADDRESS_MAX,
+        STACK_ADDRESS_MAX,
+        DEFAULT_MAX_DEGREE,
+    );
+    KeyId::new(STATE_CIRCUIT_ID, &params, k)
+}
+
+/// Returns the id of the keys of `ProverSuperCircuit` of degree `k` proving
+/// blocks of capacities `circuits_params`.
+pub fn super_circuit_key_id(k: u32, circuits_params: &CircuitsParams) -> KeyId {
+    let fixed_table_tags = FixedTableTag::iterator()
+        .map(|tag| tag as u64)
+        .collect::<Vec<_>>();
+    let params = (
+        circuits_params,
+        MEMORY_ADDRESS_MAX,
+        GLOBAL_COUNTER_MAX,
+        MAX_WITHDRAWALS,
+        fixed_table_tags,
+    );
+    KeyId::new(SUPER_CIRCUIT_ID, &params, k)
+}
@@ -0,0 +1,248 @@
+//! Keccak transcript of the proofs verified by the EVM, the root proofs.
+//!
+//! The transcript absorbs a point as the 32-byte big endian words of its
+//! coordinates, `(0, 0)` for the identity, and a scalar as a 32-byte big endian
+//! word, which are also their encodings in the proof.  A challenge is the
+//! keccak256 hash of the bytes absorbed since the previous challenge, preceded
+//! by the hash of the previous challenge, as a big endian integer reduced
+//! modulo the scalar field.  The EVM replays it with the `KECCAK256` opcode on
+//! a buffer of its memory, with the points and scalars of the proof copied
+//! from the call data as they are.
+
+use eth_types::keccak256;
+use halo2_proofs::transcript::{
+    Challenge255, EncodedChallenge, Transcript, TranscriptRead, TranscriptWrite,
+};
+use pairing::{
+    arithmetic::CurveAffine,
+    bn256::{Fq, Fr, G1Affine},
+    group::{
+        ff::{Field, PrimeField},
+        prime::PrimeCurveAffine,
+    },
+};
+use std::io::{self, Read, Write};
+
+/// Number of bytes of a scalar or of a coordinate in the transcript.
+pub const N_WORD_BYTES: usize = 32;
+/// Number of bytes of a point in the transcript.
+pub const N_POINT_BYTES: usize = 2 * N_WORD_BYTES;
+
+// Returns the big endian bytes of a canonical little endian representation.
+fn to_be_bytes(mut repr: [u8; N_WORD_BYTES]) -> [u8; N_WORD_BYTES] {
+    repr.reverse();
+    repr
+}
+
+fn scalar_bytes(scalar: &Fr) -> [u8; N_WORD_BYTES] {
+    to_be_bytes(scalar.to_repr())
+}
+
+fn point_bytes(point: &G1Affine) -> [u8; N_POINT_BYTES] {
+    let mut bytes = [0; N_POINT_BYTES];
+    if !bool::from(point.is_identity()) {
+        let coordinates = point.coordinates().unwrap();
+        bytes[..N_WORD_BYTES].copy_from_slice(&to_be_bytes(coordinates.x().to_repr()));
+        bytes[N_WORD_BYTES..].copy_from_slice(&to_be_bytes(coordinates.y().to_repr()));
+    }
+    bytes
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_scalar_bytes(bytes: [u8; N_WORD_BYTES]) -> io::Result<Fr> {
+    Option::from(Fr::from_repr(to_be_bytes(bytes)))
+        .ok_or_else(|| invalid_data("scalar isn't canonical"))
+}
+
+fn read_point_bytes(bytes: [u8; N_POINT_BYTES]) -> io::Result<G1Affine> {
+    if bytes == [0; N_POINT_BYTES] {
+        return Ok(G1Affine::identity());
+    }
+    let mut coordinates = [Fq::zero(); 2];
+    for (coordinate, bytes) in coordinates.iter_mut().zip(bytes.chunks(N_WORD_BYTES)) {
+        let mut repr = [0; N_WORD_BYTES];
+        repr.copy_from_slice(bytes);
+        *coordinate = Option::from(Fq::from_repr(to_be_bytes(repr)))
+            .ok_or_else(|| invalid_data("coordinate isn't canonical"))?;
+    }
+    let [x, y] = coordinates;
+    Option::from(G1Affine::from_xy(x, y)).ok_or_else(|| invalid_data("point isn't on the curve"))
+}
+
+// Bytes absorbed by the transcript since the previous challenge, preceded by
+// its hash.
+#[derive(Clone, Debug, Default)]
+struct KeccakState(Vec<u8>);
+
+impl KeccakState {
+    fn squeeze_challenge(&mut self) -> Challenge255<G1Affine> {
+        let hash = keccak256(&self.0);
+        self.0 = hash.to_vec();
+        // The challenge is the hash as a big endian integer, reduced modulo
+        // the scalar field as a 512-bit little endian integer.
+        let mut input = [0; 2 * N_WORD_BYTES];
+        input[..N_WORD_BYTES].copy_from_slice(&to_be_bytes(hash));
+        Challenge255::new(&input)
+    }
+}
+
+/// Keccak transcript writing a proof to `W`.
+#[derive(Debug)]
+pub struct KeccakWrite<W: Write> {
+    state: KeccakState,
+    writer: W,
+}
+
+impl<W: Write> KeccakWrite<W> {
+    /// Initializes the transcript of a proof written to `writer`.
+    pub fn init(writer: W) -> Self {
+        Self {
+            state: KeccakState::default(),
+            writer,
+        }
+    }
+
+    /// Returns the writer of the proof.
+    pub fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Transcript<G1Affine, Challenge255<G1Affine>> for KeccakWrite<W> {
+    fn squeeze_challenge(&mut self) -> Challenge255<G1Affine> {
+        self.state.squeeze_challenge()
+    }
+
+    fn common_point(&mut self, point: G1Affine) -> io::Result<()> {
+        self.state.0.extend_from_slice(&point_bytes(&point));
+        Ok(())
+    }
+
+    fn common_scalar(&mut self, scalar: Fr) -> io::Result<()> {
+        self.state.0.extend_from_slice(&scalar_bytes(&scalar));
+        Ok(())
+    }
+}
+
+impl<W: Write> TranscriptWrite<G1Affine, Challenge255<G1Affine>> for KeccakWrite<W> {
+    fn write_point(&mut self, point: G1Affine) -> io::Result<()> {
+        self.common_point(point)?;
+        self.writer.write_all(&point_bytes(&point))
+    }
+
+    fn write_scalar(&mut self, scalar: Fr) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        self.writer.write_all(&scalar_bytes(&scalar))
+    }
+}
+
+/// Keccak transcript reading a proof from `R`.
+#[derive(Debug)]
+pub struct KeccakRead<R: Read> {
+    state: KeccakState,
+    reader: R,
+}
+
+impl<R: Read> KeccakRead<R> {
+    /// Initializes the transcript of a proof read from `reader`.
+    pub fn init(reader: R) -> Self {
+        Self {
+            state: KeccakState::default(),
+            reader,
+        }
+    }
+}
+
+impl<R: Read> Transcript<G1Affine, Challenge255<G1Affine>> for KeccakRead<R> {
+    fn squeeze_challenge(&mut self) -> Challenge255<G1Affine> {
+        self.state.squeeze_challenge()
+    }
+
+    fn common_point(&mut self, point: G1Affine) -> io::Result<()> {
+        self.state.0.extend_from_slice(&point_bytes(&point));
+        Ok(())
+    }
+
+    fn common_scalar(&mut self, scalar: Fr) -> io::Result<()> {
+        self.state.0.extend_from_slice(&scalar_bytes(&scalar));
+        Ok(())
+    }
+}
+
+impl<R: Read> TranscriptRead<G1Affine, Challenge255<G1Affine>> for KeccakRead<R> {
+    fn read_point(&mut self) -> io::Result<G1Affine> {
+        let mut bytes = [0; N_POINT_BYTES];
+        self.reader.read_exact(&mut bytes)?;
+        let point = read_point_bytes(bytes)?;
+        self.common_point(point)?;
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<Fr> {
+        let mut bytes = [0; N_WORD_BYTES];
+        self.reader.read_exact(&mut bytes)?;
+        let scalar = read_scalar_bytes(bytes)?;
+        self.common_scalar(scalar)?;
+        Ok(scalar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::arithmetic::FieldExt;
+    use pairing::group::Curve;
+
+    #[test]
+    fn keccak_transcript_roundtrip() {
+        let points = [
+            G1Affine::generator(),
+            G1Affine::identity(),
+            (G1Affine::generator() * Fr::from(3)).to_affine(),
+        ];
+        let scalars = [Fr::zero(), Fr::one(), -Fr::one()];
+
+        let mut write = KeccakWrite::init(vec![]);
+        let mut write_challenges = vec![];
+        for (point, scalar) in points.iter().zip(scalars) {
+            write.write_point(*point).unwrap();
+            write.write_scalar(scalar).unwrap();
+            write_challenges.push(write.squeeze_challenge().get_scalar());
+        }
+        let proof = write.finalize();
+        assert_eq!(proof.len(), 3 * (N_POINT_BYTES + N_WORD_BYTES));
+
+        let mut read = KeccakRead::init(&proof[..]);
+        for ((point, scalar), challenge) in points.iter().zip(scalars).zip(write_challenges) {
+            assert_eq!(read.read_point().unwrap(), *point);
+            assert_eq!(read.read_scalar().unwrap(), scalar);
+            assert_eq!(read.squeeze_challenge().get_scalar(), challenge);
+        }
+    }
+
+    #[test]
+    fn keccak_transcript_challenge() {
+        // The first challenge is the keccak256 hash of the absorbed bytes
+        let mut transcript = KeccakWrite::init(vec![]);
+        transcript.common_scalar(Fr::one()).unwrap();
+        let hash = keccak256(&scalar_bytes(&Fr::one()));
+        let mut wide = [0; 2 * N_WORD_BYTES];
+        wide[..N_WORD_BYTES].copy_from_slice(&to_be_bytes(hash));
+        assert_eq!(
+            transcript.squeeze_challenge().get_scalar(),
+            Fr::from_bytes_wide(&wide)
+        );
+    }
+
+    #[test]
+    fn keccak_transcript_rejects_non_canonical() {
+        let mut proof = [0xff; N_WORD_BYTES].to_vec();
+        assert!(KeccakRead::init(&proof[..]).read_scalar().is_err());
+        proof = scalar_bytes(&Fr::one()).to_vec();
+        proof.extend_from_slice(&[0; N_WORD_BYTES]);
+        assert!(KeccakRead::init(&proof[..]).read_point().is_err());
+    }
+}
@@ -0,0 +1,401 @@
+//! Cache of the SRS parameters and the keys of the circuits on disk.
+//!
+//! Generating the keys of a circuit takes hours at the degrees of a mainnet
+//! block, so the prover stores them in a directory, keyed by the circuit, the
+//! hash of its parameters and its degree `k`, and only generates them when
+//! they are missing.
+//!
+//! Every file starts with a header holding the kind of its content, `k` and
+//! the hash of the parameters of the circuit, and ends with the keccak hash
+//! of its content.  They are all checked on load, so that a truncated file or
+//! the key of other parameters is never used: it is generated again and
+//! overwritten instead.
+//!
+//! Our version of halo2 can't serialize proving keys, so only the verifying
+//! key is stored, and the proving key is derived from it with `keygen_pk`,
+//! which skips the commitments to the fixed columns done by `keygen_vk`.
+
+use eth_types::{keccak256, H256};
+use halo2_proofs::{
+    plonk::{keygen_pk, keygen_vk, Circuit, ProvingKey, VerifyingKey},
+    poly::commitment::Params,
+};
+use pairing::bn256::{Bn256, Fr, G1Affine};
+use serde::Serialize;
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+// Magic bytes at the start of every file of the cache.
+const MAGIC: [u8; 8] = *b"zkevmkey";
+// Version of the format of the files, to be bumped when it changes.
+const VERSION: u8 = 1;
+// Length of the header: magic, version, kind, k, parameters hash and length
+// of the content.
+const HEADER_LEN: usize = 8 + 1 + 1 + 4 + 32 + 8;
+// Length of the keccak hash of the content at the end of a file.
+const CHECKSUM_LEN: usize = 32;
+
+/// Kind of the content of a file of the cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyKind {
+    /// SRS parameters
+    Params = 1,
+    /// Verifying key of a circuit
+    VerifyingKey,
+}
+
+impl KeyKind {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Params => "srs",
+            Self::VerifyingKey => "vk",
+        }
+    }
+}
+
+/// Identifies the keys of a circuit: they only depend on the circuit, the
+/// parameters it is configured with and its degree `k`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyId {
+    /// Name of the circuit, like `evm_circuit`
+    pub circuit_id: String,
+    /// Keccak hash of the JSON serialization of the parameters of the circuit
+    pub params_hash: H256,
+    /// Degree of the circuit
+    pub k: u32,
+}
+
+impl KeyId {
+    /// Returns the id of the keys of the circuit `circuit_id` configured with
+    /// `params`, of degree `k`.
+    pub fn new<P: Serialize>(circuit_id: &str, params: &P, k: u32) -> Self {
+        let params = serde_json::to_vec(params).expect("serialize circuit parameters");
+        Self {
+            circuit_id: circuit_id.to_string(),
+            params_hash: H256(keccak256(params)),
+            k,
+        }
+    }
+
+    // Id of the SRS parameters of degree `k`, which are shared by all the
+    // circuits.
+    fn params(k: u32) -> Self {
+        Self {
+            circuit_id: "params".to_string(),
+            params_hash: H256::zero(),
+            k,
+        }
+    }
+
+    fn file_name(&self, kind: KeyKind) -> String {
+        format!(
+            "{}-{}-k{}.{}",
+            self.circuit_id,
+            hex::encode(self.params_hash),
+            self.k,
+            kind.extension()
+        )
+    }
+}
+
+/// Error when loading or storing a file of the cache.
+#[derive(Debug)]
+pub enum KeyCacheError {
+    /// Reading or writing the file failed.
+    Io(io::Error),
+    /// The file isn't the one expected, or its content doesn't match its
+    /// checksum.
+    Corrupted {
+        /// Path of the file
+        path: PathBuf,
+        /// Check which failed
+        reason: &'static str,
+    },
+    /// The generation of a key failed.
+    Keygen(halo2_proofs::plonk::Error),
+}
+
+impl fmt::Display for KeyCacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "key cache io error: {}", err),
+            Self::Corrupted { path, reason } => {
+                write!(f, "corrupted key cache file {}: {}", path.display(), reason)
+            }
+            Self::Keygen(err) => write!(f, "key generation error: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for KeyCacheError {}
+
+impl From<io::Error> for KeyCacheError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<halo2_proofs::plonk::Error> for KeyCacheError {
+    fn from(err: halo2_proofs::plonk::Error) -> Self {
+        Self::Keygen(err)
+    }
+}
+
+/// Directory where the SRS parameters and the keys of the circuits are cached.
+#[derive(Clone, Debug)]
+pub struct KeyCache {
+    dir: PathBuf,
+}
+
+impl KeyCache {
+    /// Returns the cache stored in `dir`, which is created on the first store.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Path of the file of `id` with the content `kind`.
+    pub fn path(&self, id: &KeyId, kind: KeyKind) -> PathBuf {
+        self.dir.join(id.file_name(kind))
+    }
+
+    /// Loads the SRS parameters of degree `k`, or returns `None` if they are
+    /// not cached.
+    pub fn load_params(&self, k: u32) -> Result<Option<Params<G1Affine>>, KeyCacheError> {
+        let path = self.path(&KeyId::params(k), KeyKind::Params);
+        match self.load(&KeyId::params(k), KeyKind::Params)? {
+            Some(content) => verifier::read_params(&content)
+                .map(Some)
+                .map_err(|_| corrupted(&path, "invalid params")),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores the SRS parameters `params` of degree `k`.
+    pub fn store_params(&self, k: u32, params: &Params<G1Affine>) -> Result<(), KeyCacheError> {
+        let mut content = Vec::new();
+        params.write(&mut content)?;
+        self.store(&KeyId::params(k), KeyKind::Params, &content)
+    }
+
+    /// Loads the SRS parameters of degree `k`, or generates them with an
+    /// unsafe setup and stores them if they are not cached or corrupted.
+    /// Production provers should load the parameters of a trusted setup
+    /// instead.
+    pub fn load_or_setup_params(&self, k: u32) -> Result<Params<G1Affine>, KeyCacheError> {
+        if let Some(params) = load_or_warn(self.load_params(k))? {
+            return Ok(params);
+        }
+        log::info!("generating params of degree {}", k);
+        let params = Params::<G1Affine>::unsafe_setup::<Bn256>(k);
+        self.store_params(k, &params)?;
+        Ok(params)
+    }
+
+    /// Loads the verifying key `id` of the circuit `C`, or returns `None` if
+    /// it is not cached.
+    pub fn load_vk<C: Circuit<Fr>>(
+        &self,
+        id: &KeyId,
+        params: &Params<G1Affine>,
+    ) -> Result<Option<VerifyingKey<G1Affine>>, KeyCacheError> {
+        let path = self.path(id, KeyKind::VerifyingKey);
+        match self.load(id, KeyKind::VerifyingKey)? {
+            Some(content) => verifier::read_vk::<C>(&content, params)
+                .map(Some)
+                .map_err(|_| corrupted(&path, "invalid verifying key")),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores the verifying key `id`.
+    pub fn store_vk(&self, id: &KeyId, vk: &VerifyingKey<G1Affine>) -> Result<(), KeyCacheError> {
+        let mut content = Vec::new();
+        vk.write(&mut content)?;
+        self.store(id, KeyKind::VerifyingKey, &content)
+    }
+
+    /// Returns the proving key `id` of `circuit`, derived from its cached
+    /// verifying key, or from a verifying key generated and stored if it is
+    /// not cached or corrupted.
+    pub fn load_or_keygen<C: Circuit<Fr>>(
+        &self,
+        id: &KeyId,
+        params: &Params<G1Affine>,
+        circuit: &C,
+    ) -> Result<ProvingKey<G1Affine>, KeyCacheError> {
+        let vk = match load_or_warn(self.load_vk::<C>(id, params))? {
+            Some(vk) => vk,
+            None => {
+                log::info!(
+                    "generating verifying key of {} of degree {}",
+                    id.circuit_id,
+                    id.k
+                );
+                let vk = tracing::info_span!("keygen_vk", circuit = %id.circuit_id, k = id.k)
+                    .in_scope(|| keygen_vk(params, circuit))?;
+                self.store_vk(id, &vk)?;
+                vk
+            }
+        };
+        let pk = tracing::info_span!("keygen_pk", circuit = %id.circuit_id, k = id.k)
+            .in_scope(|| keygen_pk(params, vk, circuit))?;
+        Ok(pk)
+    }
+
+    // Returns the content of the file of `id` with the content `kind`, after
+    // checking its header and its checksum, or `None` if it doesn't exist.
+    fn load(&self, id: &KeyId, kind: KeyKind) -> Result<Option<Vec<u8>>, KeyCacheError> {
+        let path = self.path(id, kind);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(corrupted(&path, "truncated header"));
+        }
+        let (header, rest) = bytes.split_at(HEADER_LEN);
+        if header != self.header(id, kind, rest.len() - CHECKSUM_LEN).as_slice() {
+            return Err(corrupted(&path, "unexpected header"));
+        }
+        let (content, checksum) = rest.split_at(rest.len() - CHECKSUM_LEN);
+        if keccak256(content) != checksum {
+            return Err(corrupted(&path, "checksum mismatch"));
+        }
+        Ok(Some(content.to_vec()))
+    }
+
+    // Writes `content` to the file of `id` with the content `kind`, through a
+    // temporary file so that an interrupted write doesn't leave a truncated
+    // file behind.
+    fn store(&self, id: &KeyId, kind: KeyKind, content: &[u8]) -> Result<(), KeyCacheError> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(id, kind);
+        let tmp_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(&self.header(id, kind, content.len()))?;
+        file.write_all(content)?;
+        file.write_all(&keccak256(content))?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &path)?;
+        log::info!("stored {}", path.display());
+        Ok(())
+    }
+
+    fn header(&self, id: &KeyId, kind: KeyKind, content_len: usize) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend(MAGIC);
+        header.extend([VERSION, kind as u8]);
+        header.extend(id.k.to_le_bytes());
+        header.extend(id.params_hash.as_bytes());
+        header.extend((content_len as u64).to_le_bytes());
+        header
+    }
+}
+
+fn corrupted(path: &Path, reason: &'static str) -> KeyCacheError {
+    KeyCacheError::Corrupted {
+        path: path.to_path_buf(),
+        reason,
+    }
+}
+
+// Turns a corrupted file into a cache miss, so that it is generated again.
+fn load_or_warn<T>(loaded: Result<Option<T>, KeyCacheError>) -> Result<Option<T>, KeyCacheError> {
+    match loaded {
+        Err(err @ KeyCacheError::Corrupted { .. }) => {
+            log::warn!("{}, generating it again", err);
+            Ok(None)
+        }
+        loaded => loaded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyCache, KeyCacheError, KeyId, KeyKind, HEADER_LEN};
+    use std::{fs, path::PathBuf};
+
+    const K: u32 = 4;
+
+    // Returns an empty directory for the cache of the test `name`.
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("prover-keys-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn params_bytes(cache: &KeyCache, k: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        cache
+            .load_params(k)
+            .unwrap()
+            .unwrap()
+            .write(&mut bytes)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn key_id_depends_on_params() {
+        let id = KeyId::new("state_circuit", &(2000, 1300), K);
+        assert_eq!(id, KeyId::new("state_circuit", &(2000, 1300), K));
+        assert_ne!(id, KeyId::new("state_circuit", &(2000, 1301), K));
+        assert_ne!(
+            id.file_name(KeyKind::VerifyingKey),
+            KeyId::new("state_circuit", &(2000, 1300), K + 1).file_name(KeyKind::VerifyingKey)
+        );
+    }
+
+    #[test]
+    fn params_roundtrip() {
+        let dir = cache_dir("roundtrip");
+        let cache = KeyCache::new(&dir);
+        assert!(cache.load_params(K).unwrap().is_none());
+
+        let mut bytes = Vec::new();
+        cache
+            .load_or_setup_params(K)
+            .unwrap()
+            .write(&mut bytes)
+            .unwrap();
+        assert_eq!(params_bytes(&cache, K), bytes);
+        assert!(cache.load_params(K + 1).unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn params_corrupted() {
+        let dir = cache_dir("corrupted");
+        let cache = KeyCache::new(&dir);
+        cache.load_or_setup_params(K).unwrap();
+        let path = cache.path(&KeyId::params(K), KeyKind::Params);
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[HEADER_LEN] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            cache.load_params(K),
+            Err(KeyCacheError::Corrupted {
+                reason: "checksum mismatch",
+                ..
+            })
+        ));
+
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(
+            cache.load_params(K),
+            Err(KeyCacheError::Corrupted { .. })
+        ));
+
+        // The corrupted params are generated again
+        cache.load_or_setup_params(K).unwrap();
+        assert!(cache.load_params(K).unwrap().is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
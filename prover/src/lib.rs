@@ -0,0 +1,19 @@
+//! # prover
+//!
+//! Utilities shared by the binaries generating the proofs of the circuits.
+
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+pub mod circuit_map;
+pub mod circuits;
+pub mod keccak_transcript;
+pub mod keys;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod poseidon;
+pub mod proof;
+pub mod root_circuit;
+pub mod root_verifier;
+pub mod shape;
+pub mod srs;
@@ -0,0 +1,171 @@
+//! Prometheus metrics of the time spent in the spans of the witness
+//! generation and of the proving, so that operators see where a proving run
+//! spends its time.
+//!
+//! `SpanMetrics` is a `tracing` layer observing the duration of every closed
+//! span in the `zkevm_span_duration_seconds` histogram, labelled by the name
+//! of the span: the fetching of a block and of its state (`get_block`,
+//! `get_state`), the bus-mapping of a transaction (`handle_tx`), the witness
+//! assignment of a circuit (`evm_circuit_assign`, `state_circuit_assign`...)
+//! and the phases of the proving (`keygen_vk`, `keygen_pk`, `create_proof`,
+//! `verify_proof`).  `serve` exposes the metrics over HTTP in the text format
+//! of Prometheus, to be scraped during a run.
+
+use prometheus::{Encoder, HistogramOpts, HistogramVec, Registry, TextEncoder};
+use std::{io, net::SocketAddr, time::Instant};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+use tracing::{span, Subscriber};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+// Upper bounds of the buckets of the durations in seconds, from a millisecond
+// for a transaction to an hour for the proof of a block.
+const DURATION_BUCKETS: [f64; 10] = [
+    0.001, 0.01, 0.1, 1.0, 10.0, 60.0, 300.0, 900.0, 1800.0, 3600.0,
+];
+
+/// Layer of a `tracing` subscriber observing the durations of the spans.
+#[derive(Clone, Debug)]
+pub struct SpanMetrics {
+    durations: HistogramVec,
+}
+
+// Instant a span was created at, stored in the extensions of the span.
+struct SpanStart(Instant);
+
+impl SpanMetrics {
+    /// Returns the layer, with its histogram registered in `registry`.
+    pub fn new(registry: &Registry) -> prometheus::Result<Self> {
+        let durations = HistogramVec::new(
+            HistogramOpts::new(
+                "zkevm_span_duration_seconds",
+                "Duration of the spans of the witness generation and of the proving",
+            )
+            .buckets(DURATION_BUCKETS.to_vec()),
+            &["span"],
+        )?;
+        registry.register(Box::new(durations.clone()))?;
+        Ok(Self { durations })
+    }
+}
+
+impl<S> Layer<S> for SpanMetrics
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanStart(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id) {
+            if let Some(SpanStart(start)) = span.extensions().get::<SpanStart>() {
+                self.durations
+                    .with_label_values(&[span.name()])
+                    .observe(start.elapsed().as_secs_f64());
+            }
+        }
+    }
+}
+
+/// Returns the metrics of `registry` in the text format of Prometheus.
+pub fn encode(registry: &Registry) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    TextEncoder::new()
+        .encode(&registry.gather(), &mut bytes)
+        .expect("encode metrics");
+    bytes
+}
+
+/// Serves the metrics of `registry` on `addr`, answering every HTTP request
+/// with them, as long as the runtime runs.  Returns the address the metrics
+/// are served on, which has the port picked by the OS if the one of `addr`
+/// is 0.
+pub async fn serve(addr: SocketAddr, registry: Registry) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr).await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        loop {
+            let mut stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    log::warn!("metrics connection failed: {}", err);
+                    continue;
+                }
+            };
+            let body = encode(&registry);
+            tokio::spawn(async move {
+                // Every path is answered with the metrics, so the request is
+                // read and ignored.
+                let mut request = [0u8; 1024];
+                let response = async {
+                    stream.read(&mut request).await?;
+                    let header = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+                         Connection: close\r\n\r\n",
+                        prometheus::TEXT_FORMAT,
+                        body.len()
+                    );
+                    stream.write_all(header.as_bytes()).await?;
+                    stream.write_all(&body).await?;
+                    stream.shutdown().await
+                };
+                if let Err(err) = response.await {
+                    log::warn!("metrics response failed: {}", err);
+                }
+            });
+        }
+    });
+    log::info!("serving metrics on http://{}/metrics", addr);
+    Ok(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, serve, SpanMetrics};
+    use prometheus::Registry;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn registry_with_spans() -> Registry {
+        let registry = Registry::new();
+        let subscriber = tracing_subscriber::registry().with(SpanMetrics::new(&registry).unwrap());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("create_proof").in_scope(|| {
+                tracing::info_span!("keygen_pk").in_scope(|| {});
+            });
+            let _span = tracing::info_span!("create_proof").entered();
+        });
+        registry
+    }
+
+    #[test]
+    fn span_metrics_count_closed_spans() {
+        let metrics = String::from_utf8(encode(&registry_with_spans())).unwrap();
+        assert!(metrics.contains(r#"zkevm_span_duration_seconds_count{span="create_proof"} 2"#));
+        assert!(metrics.contains(r#"zkevm_span_duration_seconds_count{span="keygen_pk"} 1"#));
+    }
+
+    #[tokio::test]
+    async fn serve_metrics() {
+        let addr = serve("127.0.0.1:0".parse().unwrap(), registry_with_spans())
+            .await
+            .unwrap();
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(r#"zkevm_span_duration_seconds_count{span="keygen_pk"} 1"#));
+    }
+}
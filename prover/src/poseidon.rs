@@ -0,0 +1,171 @@
+//! Poseidon sponge over `Fr`, computed natively and with the base gate of
+//! halo2ecc, so that a challenge squeezed by a circuit is the one of its
+//! native witness generation.
+//!
+//! The permutation has a width of 3, a rate of 2, the S-box `x^5`, 8 full
+//! rounds and 57 partial rounds, the parameters of the 128-bit security level
+//! for the scalar field of BN254.  The round constants are the keccak256 hashes
+//! of the domain of the sponge and of their indices, reduced to `Fr`, and the
+//! MDS matrix is the Cauchy matrix `1 / (i + T + j)`.  The capacity element is
+//! initialized to the number of absorbed values.
+
+use eth_types::keccak256;
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use halo2ecc::gates::{
+    base_gate::{AssignedValue, BaseGateOps, RegionAux},
+    five::base_gate::FiveColumnBaseGate,
+};
+use pairing::{bn256::Fr, group::ff::Field};
+
+/// Width of the state of the permutation.
+pub const T: usize = 3;
+/// Number of values absorbed per permutation.
+pub const RATE: usize = T - 1;
+const FULL_ROUNDS: usize = 8;
+const PARTIAL_ROUNDS: usize = 57;
+const DOMAIN: &[u8] = b"zkevm root circuit poseidon";
+
+// Round constants and MDS matrix of the permutation.
+struct Spec {
+    round_constants: Vec<[Fr; T]>,
+    mds: [[Fr; T]; T],
+}
+
+impl Spec {
+    fn new() -> Self {
+        let constant = |idx: usize| {
+            let mut bytes = [0u8; 64];
+            for (half, chunk) in bytes.chunks_mut(32).enumerate() {
+                let preimage = [DOMAIN, &(2 * idx + half).to_be_bytes()].concat();
+                chunk.copy_from_slice(&keccak256(preimage));
+            }
+            Fr::from_bytes_wide(&bytes)
+        };
+        let round_constants = (0..FULL_ROUNDS + PARTIAL_ROUNDS)
+            .map(|round| [0, 1, 2].map(|idx| constant(round * T + idx)))
+            .collect();
+        let mds = [0, 1, 2]
+            .map(|row| [0, 1, 2].map(|col| Fr::from((row + T + col) as u64).invert().unwrap()));
+        Self {
+            round_constants,
+            mds,
+        }
+    }
+
+    fn is_full_round(round: usize) -> bool {
+        round < FULL_ROUNDS / 2 || round >= FULL_ROUNDS / 2 + PARTIAL_ROUNDS
+    }
+}
+
+fn pow5(value: Fr) -> Fr {
+    value.square().square() * value
+}
+
+/// Returns the Poseidon hash of `inputs`.
+pub fn hash(inputs: &[Fr]) -> Fr {
+    let spec = Spec::new();
+    let mut state = [Fr::zero(); T];
+    state[0] = Fr::from(inputs.len() as u64);
+    for chunk in inputs.chunks(RATE) {
+        for (element, input) in state[1..].iter_mut().zip(chunk) {
+            *element += input;
+        }
+        for (round, constants) in spec.round_constants.iter().enumerate() {
+            for (element, constant) in state.iter_mut().zip(constants) {
+                *element += constant;
+            }
+            let n_sboxes = if Spec::is_full_round(round) { T } else { 1 };
+            for element in state[..n_sboxes].iter_mut() {
+                *element = pow5(*element);
+            }
+            state = spec.mds.map(|row| {
+                row.iter()
+                    .zip(state)
+                    .fold(Fr::zero(), |acc, (coeff, element)| acc + *coeff * element)
+            });
+        }
+    }
+    state[1]
+}
+
+fn assign_pow5(
+    base_gate: &FiveColumnBaseGate<Fr>,
+    r: &mut RegionAux<'_, '_, Fr>,
+    value: &AssignedValue<Fr>,
+) -> Result<AssignedValue<Fr>, Error> {
+    let square = base_gate.mul(r, value, value)?;
+    let fourth = base_gate.mul(r, &square, &square)?;
+    base_gate.mul(r, &fourth, value)
+}
+
+/// Returns the cell of the Poseidon hash of the values of `inputs`, whose
+/// value is `hash` of them, computed with `base_gate`.
+pub fn assign_hash(
+    base_gate: &FiveColumnBaseGate<Fr>,
+    r: &mut RegionAux<'_, '_, Fr>,
+    inputs: &[AssignedValue<Fr>],
+) -> Result<AssignedValue<Fr>, Error> {
+    let spec = Spec::new();
+    let zero = base_gate.assign_constant(r, Fr::zero())?;
+    let mut state = [
+        base_gate.assign_constant(r, Fr::from(inputs.len() as u64))?,
+        zero.clone(),
+        zero,
+    ];
+    for chunk in inputs.chunks(RATE) {
+        for (element, input) in state[1..].iter_mut().zip(chunk) {
+            *element = base_gate.sum_with_constant(
+                r,
+                vec![(&*element, Fr::one()), (input, Fr::one())],
+                None,
+            )?;
+        }
+        for (round, constants) in spec.round_constants.iter().enumerate() {
+            for (element, constant) in state.iter_mut().zip(constants) {
+                *element = base_gate.sum_with_constant(
+                    r,
+                    vec![(&*element, Fr::one())],
+                    Some(*constant),
+                )?;
+            }
+            let n_sboxes = if Spec::is_full_round(round) { T } else { 1 };
+            for element in state[..n_sboxes].iter_mut() {
+                *element = assign_pow5(base_gate, r, element)?;
+            }
+            let mut next = vec![];
+            for row in spec.mds.iter() {
+                next.push(
+                    base_gate.sum_with_constant(
+                        r,
+                        state
+                            .iter()
+                            .zip(row)
+                            .map(|(element, coeff)| (element, *coeff))
+                            .collect(),
+                        None,
+                    )?,
+                );
+            }
+            state = [next[0].clone(), next[1].clone(), next[2].clone()];
+        }
+    }
+    let [_, output, _] = state;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poseidon_hash_of_inputs() {
+        let inputs = [1, 2, 3].map(Fr::from);
+        assert_eq!(hash(&inputs), hash(&inputs));
+        assert_ne!(hash(&inputs), hash(&inputs[..2]));
+        assert_ne!(
+            hash(&inputs[..2]),
+            hash(&[inputs[0], inputs[1], Fr::zero()])
+        );
+        assert_ne!(hash(&inputs), hash(&[inputs[1], inputs[0], inputs[2]]));
+    }
+}
@@ -0,0 +1,531 @@
+//! Canonical serialization of proofs with their public inputs.
+//!
+//! A proof file is the JSON of a `ProofFile`: the version of the format, the
+//! id of the keys of the circuit of the proof (see `keys`), the values of its
+//! instance columns as 32-byte big-endian hex strings, and the proof created
+//! with a Blake2b transcript as a hex string.  It holds everything needed to
+//! check the proof besides the SRS parameters and the verifying key, which
+//! are found in a `KeyCache` by the id of the keys, so proofs can be handed
+//! between services and audited offline.
+//!
+//! The proof of a chunk of a block also holds the `Chunk` it proves, and the
+//! proofs of the chunks of a block are stitched by checking that the chunks
+//! of every circuit follow each other.  The begin and end states of the chunk
+//! of a proof of the EVM circuit are public inputs, so they are only trusted
+//! once checked against the instance of the proof, and the chunks of the
+//! other circuits have to be the ones of the EVM circuit.  The fingerprint of
+//! the rw operations carried to a chunk is a public input of the proofs of the
+//! EVM circuit of the chunk and of the previous one, and of the proof of the
+//! state circuit of the chunk, which proves that they are the rw operations
+//! before the chunk in its rw table.
+
+use crate::circuits::{
+    evm_circuit_proves_chunk, state_circuit_proves_chunk, EVM_CIRCUIT_ID, STATE_CIRCUIT_ID,
+};
+use crate::keys::{KeyCache, KeyCacheError, KeyId};
+use eth_types::{Bytes, H256};
+use halo2_proofs::{
+    plonk::{create_proof, Circuit, ProvingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bWrite, Challenge255},
+};
+use pairing::bn256::{Fr, G1Affine};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, fs, io, iter, path::Path};
+use verifier::{fr_from_be_bytes, fr_to_be_bytes};
+use zkevm_circuits::evm_circuit::witness::{check_chunks, Chunk, ChunkError};
+
+/// Version of the format of the proof files, to be bumped when it changes.
+pub const PROOF_FORMAT_VERSION: u32 = 1;
+
+/// Error when reading or verifying a proof file.
+#[derive(Debug)]
+pub enum ProofFileError {
+    /// Reading or writing the file failed.
+    Io(io::Error),
+    /// The file isn't the JSON of a proof file.
+    Json(serde_json::Error),
+    /// The file has another version of the format.
+    Version(u32),
+    /// A value of the instance isn't a canonical scalar.
+    InvalidInstance(H256),
+    /// The verifying key of the proof isn't in the key cache.
+    MissingKey(KeyId),
+    /// Loading the verifying key of the proof failed.
+    KeyCache(KeyCacheError),
+    /// The proof doesn't verify.
+    Verification(halo2_proofs::plonk::Error),
+    /// The creation of the proof failed.
+    Proving(halo2_proofs::plonk::Error),
+    /// The chunks of the proofs of a circuit don't stitch.
+    Chunks(String, ChunkError),
+}
+
+impl fmt::Display for ProofFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "proof file io error: {}", err),
+            Self::Json(err) => write!(f, "invalid proof file: {}", err),
+            Self::Version(version) => write!(
+                f,
+                "proof file version {} isn't the supported version {}",
+                version, PROOF_FORMAT_VERSION
+            ),
+            Self::InvalidInstance(value) => {
+                write!(f, "instance value {:?} isn't a scalar", value)
+            }
+            Self::MissingKey(id) => write!(
+                f,
+                "no verifying key of {} of degree {} in the key cache",
+                id.circuit_id, id.k
+            ),
+            Self::KeyCache(err) => write!(f, "{}", err),
+            Self::Verification(err) => write!(f, "proof verification failed: {:?}", err),
+            Self::Proving(err) => write!(f, "proof creation failed: {:?}", err),
+            Self::Chunks(circuit_id, err) => {
+                write!(f, "chunks of the proofs of {}: {}", circuit_id, err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProofFileError {}
+
+impl From<io::Error> for ProofFileError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ProofFileError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<KeyCacheError> for ProofFileError {
+    fn from(err: KeyCacheError) -> Self {
+        Self::KeyCache(err)
+    }
+}
+
+/// A proof with its public inputs and the id of the keys of its circuit.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofFile {
+    /// `PROOF_FORMAT_VERSION`
+    pub version: u32,
+    /// Name of the circuit of the proof
+    pub circuit_id: String,
+    /// Hash of the parameters of the circuit, see `KeyId`
+    pub params_hash: H256,
+    /// Degree of the circuit
+    pub k: u32,
+    /// Values of the instance columns, as big-endian scalars
+    pub instances: Vec<Vec<H256>>,
+    /// Proof created with a Blake2b transcript
+    pub proof: Bytes,
+    /// Chunk of its block the proof is of, if the block is proven in chunks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk: Option<Chunk>,
+}
+
+impl ProofFile {
+    /// Returns the proof file of `proof` of the circuit of the keys `id`, with
+    /// the values `instances` of its instance columns.
+    pub fn new(id: &KeyId, instances: &[Vec<Fr>], proof: Vec<u8>) -> Self {
+        let instances = instances
+            .iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|value| H256(fr_to_be_bytes(value)))
+                    .collect()
+            })
+            .collect();
+        Self {
+            version: PROOF_FORMAT_VERSION,
+            circuit_id: id.circuit_id.clone(),
+            params_hash: id.params_hash,
+            k: id.k,
+            instances,
+            proof: proof.into(),
+            chunk: None,
+        }
+    }
+
+    /// Creates the proof file of `circuit` with the proving key `pk` of the
+    /// keys `id`, the values `instances` of its instance columns and a
+    /// Blake2b transcript.
+    #[tracing::instrument(
+        name = "create_proof",
+        skip_all,
+        fields(circuit = %id.circuit_id, k = id.k)
+    )]
+    pub fn create<C: Circuit<Fr>>(
+        id: &KeyId,
+        params: &Params<G1Affine>,
+        pk: &ProvingKey<G1Affine>,
+        circuit: C,
+        instances: &[Vec<Fr>],
+    ) -> Result<Self, ProofFileError> {
+        let columns = instances.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof(
+            params,
+            pk,
+            &[circuit],
+            &[&columns[..]],
+            OsRng,
+            &mut transcript,
+        )
+        .map_err(ProofFileError::Proving)?;
+        Ok(Self::new(id, instances, transcript.finalize()))
+    }
+
+    /// Returns the id of the keys of the circuit of the proof.
+    pub fn key_id(&self) -> KeyId {
+        KeyId {
+            circuit_id: self.circuit_id.clone(),
+            params_hash: self.params_hash,
+            k: self.k,
+        }
+    }
+
+    /// Returns the values of the instance columns.
+    pub fn instances(&self) -> Result<Vec<Vec<Fr>>, ProofFileError> {
+        self.instances
+            .iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|value| {
+                        fr_from_be_bytes(value.to_fixed_bytes())
+                            .ok_or(ProofFileError::InvalidInstance(*value))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Reads the proof file at `path`, which has to be of the current version.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, ProofFileError> {
+        let proof_file: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if proof_file.version != PROOF_FORMAT_VERSION {
+            return Err(ProofFileError::Version(proof_file.version));
+        }
+        Ok(proof_file)
+    }
+
+    /// Writes the proof file to `path`.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), ProofFileError> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Verifies the proof of the circuit `C`, with the SRS parameters `params`
+    /// and the verifying key of the proof in `key_cache`.
+    #[tracing::instrument(
+        name = "verify_proof",
+        skip_all,
+        fields(circuit = %self.circuit_id, k = self.k)
+    )]
+    pub fn verify<C: Circuit<Fr>>(
+        &self,
+        params: &Params<G1Affine>,
+        key_cache: &KeyCache,
+    ) -> Result<(), ProofFileError> {
+        let id = self.key_id();
+        let vk = key_cache
+            .load_vk::<C>(&id, params)?
+            .ok_or(ProofFileError::MissingKey(id))?;
+        verifier::verify(params, &vk, &self.instances()?, &self.proof)
+            .map_err(ProofFileError::Verification)
+    }
+}
+
+/// Proofs of the circuits of a block gathered in a single file, which are
+/// verified one by one as long as no circuit aggregates them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofBundle {
+    /// `PROOF_FORMAT_VERSION`
+    pub version: u32,
+    /// Proofs of the circuits
+    pub proofs: Vec<ProofFile>,
+}
+
+impl ProofBundle {
+    /// Returns the bundle of `proofs`.
+    pub fn new(proofs: Vec<ProofFile>) -> Self {
+        Self {
+            version: PROOF_FORMAT_VERSION,
+            proofs,
+        }
+    }
+
+    /// Reads the bundle at `path`, which has to be of the current version.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, ProofFileError> {
+        let bundle: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if let Some(version) = iter::once(bundle.version)
+            .chain(bundle.proofs.iter().map(|proof| proof.version))
+            .find(|version| *version != PROOF_FORMAT_VERSION)
+        {
+            return Err(ProofFileError::Version(version));
+        }
+        Ok(bundle)
+    }
+
+    /// Writes the bundle to `path`.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), ProofFileError> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Checks that the proofs of chunks of every circuit are of all the chunks
+    /// of the block in order, every chunk beginning where the previous one
+    /// ends and with the rw operations it hands over.  The states of the chunks
+    /// of the EVM circuit are the ones of the instances of their proofs, the
+    /// rw counters they begin at and the fingerprints of the rw operations
+    /// carried to them are the ones of the instances of the proofs of the
+    /// state circuit, and the chunks of the other circuits, whose proofs don't
+    /// have them as public inputs, are the ones of the EVM circuit if the
+    /// bundle has its proofs.
+    pub fn check_chunks(&self) -> Result<(), ProofFileError> {
+        let mut chunks = BTreeMap::<_, Vec<_>>::new();
+        for proof in self.proofs.iter() {
+            if let Some(chunk) = &proof.chunk {
+                let proves_chunk = match proof.circuit_id.as_str() {
+                    EVM_CIRCUIT_ID => evm_circuit_proves_chunk(chunk, &proof.instances()?),
+                    STATE_CIRCUIT_ID => state_circuit_proves_chunk(chunk, &proof.instances()?),
+                    _ => true,
+                };
+                if !proves_chunk {
+                    return Err(ProofFileError::Chunks(
+                        proof.circuit_id.clone(),
+                        ChunkError::Unproven { index: chunk.index },
+                    ));
+                }
+                chunks
+                    .entry(proof.circuit_id.as_str())
+                    .or_default()
+                    .push(chunk);
+            }
+        }
+        let evm_chunks = chunks.get(EVM_CIRCUIT_ID);
+        for (circuit_id, circuit_chunks) in chunks.iter() {
+            check_chunks(circuit_chunks.iter().copied())
+                .map_err(|err| ProofFileError::Chunks(circuit_id.to_string(), err))?;
+            if let Some(index) = evm_chunks.and_then(|evm_chunks| {
+                circuit_chunks
+                    .iter()
+                    .zip(evm_chunks.iter())
+                    .position(|(chunk, evm_chunk)| chunk != evm_chunk)
+            }) {
+                return Err(ProofFileError::Chunks(
+                    circuit_id.to_string(),
+                    ChunkError::Unproven { index },
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProofBundle, ProofFile, ProofFileError, PROOF_FORMAT_VERSION};
+    use crate::{
+        circuits::{EVM_CIRCUIT_ID, STATE_CIRCUIT_ID},
+        keys::KeyId,
+    };
+    use eth_types::{Word, H256};
+    use pairing::bn256::Fr;
+    use std::{fs, iter};
+    use zkevm_circuits::evm_circuit::witness::{Chunk, ChunkError};
+
+    fn proof_file() -> ProofFile {
+        ProofFile::new(
+            &KeyId::new("test_circuit", &(1, 2), 4),
+            &[vec![Fr::from(1), -Fr::from(1)], vec![]],
+            vec![0xaa, 0xbb],
+        )
+    }
+
+    #[test]
+    fn proof_file_roundtrip() {
+        let path = std::env::temp_dir().join(format!("proof-{}.json", std::process::id()));
+        proof_file().write(&path).unwrap();
+        let proof_file_read = ProofFile::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(proof_file_read, proof_file());
+        assert_eq!(
+            proof_file_read.instances().unwrap(),
+            vec![vec![Fr::from(1), -Fr::from(1)], vec![]]
+        );
+        assert_eq!(
+            proof_file_read.instances[0][0],
+            H256::from_low_u64_be(1),
+            "instance values are big-endian"
+        );
+    }
+
+    #[test]
+    fn proof_file_invalid() {
+        let mut proof_file = proof_file();
+        proof_file.instances[0][0] = H256::repeat_byte(0xff);
+        assert!(matches!(
+            proof_file.instances(),
+            Err(ProofFileError::InvalidInstance(_))
+        ));
+
+        proof_file.version = PROOF_FORMAT_VERSION + 1;
+        let path = std::env::temp_dir().join(format!("proof-v2-{}.json", std::process::id()));
+        proof_file.write(&path).unwrap();
+        let result = ProofFile::read(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(ProofFileError::Version(_))));
+    }
+
+    #[test]
+    fn proof_bundle_roundtrip() {
+        let path = std::env::temp_dir().join(format!("proofs-{}.json", std::process::id()));
+        let bundle = ProofBundle::new(vec![proof_file(), proof_file()]);
+        bundle.write(&path).unwrap();
+        assert_eq!(ProofBundle::read(&path).unwrap(), bundle);
+
+        let mut bundle = bundle;
+        bundle.proofs[1].version = PROOF_FORMAT_VERSION + 1;
+        bundle.write(&path).unwrap();
+        let result = ProofBundle::read(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(ProofFileError::Version(_))));
+    }
+
+    #[test]
+    fn proof_bundle_chunks() {
+        let chunk_proof = |index: usize, begin: usize, end: usize| {
+            let mut proof = proof_file();
+            let mut chunk = Chunk {
+                index,
+                n_chunks: 2,
+                ..Default::default()
+            };
+            chunk.begin.rw_counter = begin;
+            chunk.end.rw_counter = end;
+            proof.chunk = Some(chunk);
+            proof
+        };
+        let mut bundle = ProofBundle::new(vec![proof_file(), chunk_proof(0, 1, 10)]);
+        assert!(matches!(
+            bundle.check_chunks(),
+            Err(ProofFileError::Chunks(_, ChunkError::Missing { index: 1 }))
+        ));
+        bundle.proofs.push(chunk_proof(1, 10, 20));
+        bundle.check_chunks().unwrap();
+        bundle.proofs[2] = chunk_proof(1, 11, 20);
+        assert!(matches!(
+            bundle.check_chunks(),
+            Err(ProofFileError::Chunks(
+                _,
+                ChunkError::Discontinuity { index: 1 }
+            ))
+        ));
+        bundle.proofs[2] = chunk_proof(1, 10, 20);
+        bundle.proofs[2].chunk.as_mut().unwrap().begin.carried_rws = Word::one();
+        assert!(matches!(
+            bundle.check_chunks(),
+            Err(ProofFileError::Chunks(
+                _,
+                ChunkError::CarriedRws { index: 1 }
+            ))
+        ));
+    }
+
+    #[test]
+    fn proof_bundle_chunks_of_evm_circuit() {
+        let randomness = Fr::from(0x100);
+        let chunk = Chunk {
+            index: 0,
+            n_chunks: 1,
+            ..Default::default()
+        };
+        let instances = iter::repeat(vec![randomness])
+            .take(31)
+            .chain(
+                chunk
+                    .instance(randomness)
+                    .into_iter()
+                    .map(|value| vec![value; 2]),
+            )
+            .collect::<Vec<_>>();
+        let mut evm_proof = ProofFile::new(&KeyId::new(EVM_CIRCUIT_ID, &(), 4), &instances, vec![]);
+        evm_proof.chunk = Some(chunk.clone());
+        let mut bundle = ProofBundle::new(vec![evm_proof]);
+        bundle.check_chunks().unwrap();
+
+        // The chunks of the other circuits are the ones of the EVM circuit.
+        let mut state_proof = proof_file();
+        state_proof.chunk = Some(chunk);
+        state_proof.chunk.as_mut().unwrap().end.gas_left = 1;
+        bundle.proofs.push(state_proof);
+        assert!(matches!(
+            bundle.check_chunks(),
+            Err(ProofFileError::Chunks(_, ChunkError::Unproven { index: 0 }))
+        ));
+
+        // The states of the chunks of the EVM circuit are the ones of the
+        // instances of their proofs, on all their rows.
+        bundle.proofs[0].chunk.as_mut().unwrap().end.gas_left = 1;
+        assert!(matches!(
+            bundle.check_chunks(),
+            Err(ProofFileError::Chunks(circuit_id, ChunkError::Unproven { index: 0 }))
+                if circuit_id == EVM_CIRCUIT_ID
+        ));
+        bundle.proofs[0].chunk.as_mut().unwrap().end.gas_left = 0;
+        bundle.proofs[1].chunk.as_mut().unwrap().end.gas_left = 0;
+        bundle.check_chunks().unwrap();
+        bundle.proofs[0].instances[31][1] = H256::from_low_u64_be(1);
+        assert!(bundle.check_chunks().is_err());
+    }
+
+    #[test]
+    fn proof_bundle_chunks_of_state_circuit() {
+        let mut chunk = Chunk {
+            index: 1,
+            n_chunks: 2,
+            ..Default::default()
+        };
+        chunk.begin.rw_counter = 10;
+        chunk.begin.carried_rws = Word::from(7);
+        let instances = iter::repeat(vec![Fr::from(0x100)])
+            .take(31)
+            .chain(iter::once(vec![Fr::from(10); 2]))
+            .chain(iter::once(vec![Fr::zero(); 2]))
+            .chain(iter::once(vec![Fr::from(7)]))
+            .collect::<Vec<_>>();
+        let mut state_proof =
+            ProofFile::new(&KeyId::new(STATE_CIRCUIT_ID, &(), 4), &instances, vec![]);
+        state_proof.chunk = Some(chunk);
+        let mut bundle = ProofBundle::new(vec![state_proof]);
+        assert!(matches!(
+            bundle.check_chunks(),
+            Err(ProofFileError::Chunks(_, ChunkError::Missing { index: 0 }))
+        ));
+
+        // The rw operations carried to the chunk are the ones of the instance
+        // of its proof.
+        bundle.proofs[0].chunk.as_mut().unwrap().begin.carried_rws = Word::from(8);
+        assert!(matches!(
+            bundle.check_chunks(),
+            Err(ProofFileError::Chunks(circuit_id, ChunkError::Unproven { index: 1 }))
+                if circuit_id == STATE_CIRCUIT_ID
+        ));
+        bundle.proofs[0].chunk.as_mut().unwrap().begin.carried_rws = Word::from(7);
+        bundle.proofs[0].instances[31][1] = H256::from_low_u64_be(11);
+        assert!(matches!(
+            bundle.check_chunks(),
+            Err(ProofFileError::Chunks(_, ChunkError::Unproven { index: 1 }))
+        ));
+    }
+}
@@ -0,0 +1,1090 @@
+//! Root circuit, which verifies proofs of the super circuit in-circuit, so
+//! that a batch of them is checked by a single proof.
+//!
+//! Each proof is created with a Poseidon transcript, which the verifier
+//! gadget of halo2ecc replays with field operations instead of bit operations.
+//! The gadget reads the proof from its transcript, checks its commitments and
+//! evaluations, and reduces it to the points `w_x` and `w_g` of the KZG
+//! multi-open check `e(w_x, [s]_2) = e(w_g, [1]_2)`.  The checks of the proofs
+//! are combined with the powers of a challenge `c` into the KZG accumulator,
+//! the points `lhs = Σ c^i ⋅ w_g_i` and `rhs = Σ c^i ⋅ w_x_i`, whose limbs are
+//! exposed in the instance, so that the verifier of the root proof checks all
+//! the proofs with the single pairing check `e(lhs, [1]_2) = e(rhs, [s]_2)`.
+//! The challenge `c` is squeezed in-circuit from the transcript of the points,
+//! the `poseidon` hash of the limbs of their coordinates, so that it is fixed
+//! after the points.
+//!
+//! The proofs can be of consecutive blocks of a batch, in which case the
+//! headers of the blocks are chained: the parent hash and the previous state
+//! root of each block are the block hash and the state root of the block
+//! before it.  The parent hash and the previous state root of the first block
+//! and the block hash and the state root of the last one are the instance of
+//! the root circuit, so a rollup posts a single proof per batch.  The public
+//! input of each proof, the instance of its PI circuit in the last instance
+//! column of the super circuit, is the digest of the public inputs of its
+//! block and the digest of the block before it, which is zero for the first
+//! block, so the digest of the last block is the tx digest of the instance and
+//! commits the txs of the whole batch.  The public input of each proof has the
+//! halves of the header fields of its block too, which are copied to the
+//! headers of the root circuit, so that the chained headers are the ones of
+//! the proofs.  The instance follows the versioned layout of
+//! `zkevm_circuits::instance`, whose version is constrained to the one the
+//! circuit is built with.
+
+use crate::poseidon;
+use eth_types::{Word, WordFieldExt};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector, VerifyingKey,
+    },
+    poly::{commitment::ParamsVerifier, Rotation},
+    transcript::{Challenge255, PoseidonRead},
+};
+use halo2ecc::{
+    arith::code::{FieldCode, PointCode},
+    circuits::{
+        five::integer_circuit::{FiveColumnIntegerCircuit, COMMON_RANGE_BITS},
+        native_ecc_circuit::NativeEccCircuit,
+    },
+    gates::{
+        base_gate::{BaseGateOps, RegionAux},
+        five::{
+            base_gate::{FiveColumnBaseGate, FiveColumnBaseGateConfig},
+            range_gate::FiveColumnRangeGate,
+        },
+        range_gate::RangeGateConfig,
+    },
+    verify::{halo2::verify::*, plonk::bn_to_field},
+};
+use num_bigint::BigUint;
+use pairing::{
+    arithmetic::CurveAffine,
+    bn256::{Bn256, Fq, Fr, G1Affine, G1},
+    group::{
+        ff::{Field, PrimeField},
+        prime::PrimeCurveAffine,
+        Curve, Group,
+    },
+};
+use std::fmt;
+use zkevm_circuits::{
+    instance::{
+        instance_offset, InstanceField, PublicInstance, ACCUMULATOR_LIMB_BITS,
+        INSTANCE_LAYOUT_VERSION, N_ACCUMULATOR_LIMBS,
+    },
+    pi_circuit::N_PI_INSTANCE,
+};
+
+// Indices of the header fields in the header columns, each one is split in
+// two columns, for its high and low 128-bit halves.
+const BLOCK_HASH: usize = 0;
+const PARENT_HASH: usize = 2;
+const STATE_ROOT: usize = 4;
+const PREV_STATE_ROOT: usize = 6;
+
+// Offsets of the halves of the digest, of the digest of the previous block and
+// of the header fields, in the order of the header columns, in the public input
+// of the PI circuit.
+const DIGEST: usize = 0;
+const PREV_DIGEST: usize = 2;
+const HEADERS: usize = 4;
+
+/// Config of the root circuit.
+#[derive(Clone)]
+pub struct RootCircuitConfig {
+    base_gate_config: FiveColumnBaseGateConfig,
+    range_gate_config: RangeGateConfig,
+    // Enables the rows of the blocks after the first one
+    q_chain: Selector,
+    headers: [Column<Advice>; 8],
+    // The version of the instance layout and zero, the digest of the block
+    // before the first one
+    constants: Column<Fixed>,
+    instance: Column<Instance>,
+}
+
+/// Fields of the header of a block which chain it to the previous block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockHeader {
+    /// Hash of the block
+    pub block_hash: Word,
+    /// Hash of the previous block
+    pub parent_hash: Word,
+    /// State root after the block
+    pub state_root: Word,
+    /// State root before the block
+    pub prev_state_root: Word,
+}
+
+impl BlockHeader {
+    // Returns the high and low 128-bit halves of the fields, in the order of
+    // the header columns.
+    fn halves(&self) -> [Fr; 8] {
+        let mut halves = [Fr::zero(); 8];
+        for (idx, word) in [
+            self.block_hash,
+            self.parent_hash,
+            self.state_root,
+            self.prev_state_root,
+        ]
+        .iter()
+        .enumerate()
+        {
+            halves[2 * idx] = Fr::from_u128((*word >> 128).low_u128());
+            halves[2 * idx + 1] = Fr::from_u128(word.low_u128());
+        }
+        halves
+    }
+}
+
+/// Error when building the root circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RootCircuitError {
+    /// There are no proofs to verify.
+    NoProofs,
+    /// The number of block headers isn't the number of proofs.
+    HeadersMismatch {
+        /// Number of proofs
+        n_snarks: usize,
+        /// Number of block headers
+        n_headers: usize,
+    },
+    /// A block isn't chained to the previous one.
+    BrokenChain {
+        /// Index of the block
+        index: usize,
+    },
+    /// The last instance column of a proof isn't a public input of the PI
+    /// circuit.
+    InvalidPublicInput {
+        /// Index of the proof
+        index: usize,
+    },
+    /// The digest of the previous block of a proof isn't the digest of the
+    /// proof before it, or zero for the first proof.
+    BrokenDigestChain {
+        /// Index of the proof
+        index: usize,
+    },
+    /// The header of a block isn't the one of the public input of its proof.
+    UnboundHeader {
+        /// Index of the block
+        index: usize,
+    },
+    /// A proof doesn't reduce to a KZG multi-open check.
+    InvalidProof {
+        /// Index of the proof
+        index: usize,
+    },
+}
+
+impl fmt::Display for RootCircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoProofs => write!(f, "no proofs to verify"),
+            Self::HeadersMismatch {
+                n_snarks,
+                n_headers,
+            } => write!(
+                f,
+                "number of block headers {} doesn't match the number of proofs {}",
+                n_headers, n_snarks
+            ),
+            Self::BrokenChain { index } => write!(
+                f,
+                "block {} isn't chained to the previous block by its parent hash and state root",
+                index
+            ),
+            Self::InvalidPublicInput { index } => write!(
+                f,
+                "instance of proof {} doesn't end with the public input of the pi circuit",
+                index
+            ),
+            Self::BrokenDigestChain { index } => write!(
+                f,
+                "proof {} isn't chained to the previous proof by its digest",
+                index
+            ),
+            Self::UnboundHeader { index } => write!(
+                f,
+                "header of block {} isn't the one of the public input of its proof",
+                index
+            ),
+            Self::InvalidProof { index } => write!(f, "proof {} is invalid", index),
+        }
+    }
+}
+
+impl std::error::Error for RootCircuitError {}
+
+/// A proof of the super circuit to verify in the root circuit, with its
+/// instance columns.
+#[derive(Clone, Debug, Default)]
+pub struct Snark {
+    /// Proof created with a Poseidon transcript
+    pub proof: Vec<u8>,
+    /// Values of the instance columns of the proof, the last one being the
+    /// public input of its PI circuit
+    pub instances: Vec<Vec<Fr>>,
+}
+
+/// Checks that each block of `headers` is chained to the previous one.
+fn check_chain(headers: &[BlockHeader]) -> Result<(), RootCircuitError> {
+    for (index, pair) in headers.windows(2).enumerate() {
+        if pair[1].parent_hash != pair[0].block_hash
+            || pair[1].prev_state_root != pair[0].state_root
+        {
+            return Err(RootCircuitError::BrokenChain { index: index + 1 });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the digest of the previous block of each proof is the digest of
+/// the proof before it, zero for the first one, and returns the digest of the
+/// last proof.
+fn check_digest_chain(snarks: &[Snark]) -> Result<Word, RootCircuitError> {
+    let mut prev_digest = Word::zero();
+    for (index, snark) in snarks.iter().enumerate() {
+        let invalid = RootCircuitError::InvalidPublicInput { index };
+        let pi = snark
+            .instances
+            .last()
+            .filter(|pi| pi.len() == N_PI_INSTANCE)
+            .ok_or(invalid)?;
+        let [digest, snark_prev_digest] = [DIGEST, PREV_DIGEST]
+            .map(|offset| Word::from_field_hi_lo(&pi[offset], &pi[offset + 1]).ok_or(invalid));
+        if snark_prev_digest? != prev_digest {
+            return Err(RootCircuitError::BrokenDigestChain { index });
+        }
+        prev_digest = digest?;
+    }
+    Ok(prev_digest)
+}
+
+/// Checks that each block of `headers` is the one of the public input of its
+/// proof in `snarks`, which are checked by `check_digest_chain`.
+fn check_headers(snarks: &[Snark], headers: &[BlockHeader]) -> Result<(), RootCircuitError> {
+    for (index, (snark, header)) in snarks.iter().zip(headers).enumerate() {
+        let pi = &snark.instances[snark.instances.len() - 1];
+        if pi[HEADERS..HEADERS + 8] != header.halves() {
+            return Err(RootCircuitError::UnboundHeader { index });
+        }
+    }
+    Ok(())
+}
+
+// Verifies `snark` natively up to its KZG multi-open check, and returns the
+// points `w_x` and `w_g` of the check, or `None` if the proof is invalid.
+fn multi_open_points(
+    params: &ParamsVerifier<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    snark: &Snark,
+) -> Option<(G1Affine, G1Affine)> {
+    let fc = FieldCode::<Fr>::default();
+    let pc = PointCode::<G1Affine>::default();
+    let instances = snark
+        .instances
+        .iter()
+        .map(|instance| &instance[..])
+        .collect::<Vec<_>>();
+    let mut transcript = PoseidonRead::<_, _, Challenge255<_>>::init(&snark.proof[..]);
+
+    let param = VerifierParams::from_transcript(
+        &fc,
+        &pc,
+        &mut (),
+        Fr::zero(),
+        Fr::zero(),
+        Fr::zero(),
+        &[&instances[..]],
+        vk,
+        params,
+        &mut transcript,
+    )
+    .ok()?;
+    let proof = param.batch_multi_open_proofs(&fc, &pc, &mut ()).ok()?;
+    let (w_x, w_g) = (proof.w_x, proof.w_g);
+    (!bool::from(w_x.is_identity() | w_g.is_identity())).then(|| (w_x, w_g))
+}
+
+// Returns the limbs of the coordinates of `points`, `x` and then `y` of each
+// point, least significant limb first, which are the limbs of the reduced
+// points of the ecc gate.
+fn point_limbs(points: &[G1Affine]) -> Vec<Fr> {
+    let mask = (BigUint::from(1u64) << ACCUMULATOR_LIMB_BITS) - 1u64;
+    points
+        .iter()
+        .flat_map(|point| {
+            let coordinates = point.coordinates().unwrap();
+            [*coordinates.x(), *coordinates.y()]
+        })
+        .flat_map(|coordinate| {
+            let value = BigUint::from_bytes_le(coordinate.to_repr().as_ref());
+            (0..N_ACCUMULATOR_LIMBS / 4)
+                .map(|idx| bn_to_field(&((&value >> (idx * ACCUMULATOR_LIMB_BITS)) & &mask)))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+// Returns the limbs of the coordinates of the points of an accumulator.
+fn accumulator_limbs(points: &[G1Affine; 2]) -> [Fr; N_ACCUMULATOR_LIMBS] {
+    let mut limbs = [Fr::zero(); N_ACCUMULATOR_LIMBS];
+    limbs.copy_from_slice(&point_limbs(points));
+    limbs
+}
+
+/// Root circuit that verifies proofs of the super circuit of `vk`, of
+/// consecutive blocks.
+pub struct RootCircuit<'a> {
+    /// Verifier parameters of the proofs
+    pub params: &'a ParamsVerifier<Bn256>,
+    /// Verifying key of the proofs
+    pub vk: &'a VerifyingKey<G1Affine>,
+    /// Proofs to verify
+    pub snarks: Vec<Snark>,
+    /// Headers of the blocks of the proofs, in order
+    pub headers: Vec<BlockHeader>,
+}
+
+impl<'a> RootCircuit<'a> {
+    /// Returns the root circuit of the proofs of the blocks of `headers`.
+    /// Returns an error if there isn't a header per proof, if the blocks or
+    /// the digests of the proofs aren't chained, or if a header isn't the one
+    /// of its proof.
+    pub fn new(
+        params: &'a ParamsVerifier<Bn256>,
+        vk: &'a VerifyingKey<G1Affine>,
+        snarks: Vec<Snark>,
+        headers: Vec<BlockHeader>,
+    ) -> Result<Self, RootCircuitError> {
+        if snarks.is_empty() {
+            return Err(RootCircuitError::NoProofs);
+        }
+        if snarks.len() != headers.len() {
+            return Err(RootCircuitError::HeadersMismatch {
+                n_snarks: snarks.len(),
+                n_headers: headers.len(),
+            });
+        }
+        check_chain(&headers)?;
+        check_digest_chain(&snarks)?;
+        check_headers(&snarks, &headers)?;
+        Ok(Self {
+            params,
+            vk,
+            snarks,
+            headers,
+        })
+    }
+
+    // Returns the points `lhs` and `rhs` of the accumulator of the proofs,
+    // combined with the challenge squeezed from the transcript of their points.
+    fn accumulator(&self) -> Result<[G1Affine; 2], RootCircuitError> {
+        let points = self
+            .snarks
+            .iter()
+            .enumerate()
+            .map(|(index, snark)| {
+                multi_open_points(self.params, self.vk, snark)
+                    .ok_or(RootCircuitError::InvalidProof { index })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let transcript = points
+            .iter()
+            .flat_map(|(w_x, w_g)| [*w_x, *w_g])
+            .collect::<Vec<_>>();
+        let challenge = poseidon::hash(&point_limbs(&transcript));
+
+        // Σ c^i ⋅ p_i, by Horner's rule from the last point
+        let (mut lhs, mut rhs) = (G1::identity(), G1::identity());
+        for (w_x, w_g) in points.iter().rev() {
+            lhs = lhs * challenge + w_g;
+            rhs = rhs * challenge + w_x;
+        }
+        Ok([lhs.to_affine(), rhs.to_affine()])
+    }
+
+    /// Returns the instance of the root circuit, with the parent hash and
+    /// previous state root of the first block, the block hash and state root
+    /// of the last block, the digest of the last proof and the accumulator of
+    /// the proofs.  Returns an error if a proof is invalid.
+    pub fn instance(&self) -> Result<Vec<Fr>, RootCircuitError> {
+        let accumulator = self.accumulator()?;
+        let mut public_instance = PublicInstance {
+            tx_digest: check_digest_chain(&self.snarks)?,
+            accumulator: accumulator_limbs(&accumulator),
+            ..Default::default()
+        };
+        if let (Some(first), Some(last)) = (self.headers.first(), self.headers.last()) {
+            public_instance.parent_hash = first.parent_hash;
+            public_instance.prev_state_root = first.prev_state_root;
+            public_instance.block_hash = last.block_hash;
+            public_instance.state_root = last.state_root;
+        }
+        Ok(public_instance.to_instance())
+    }
+}
+
+impl<'a> Circuit<Fr> for RootCircuit<'a> {
+    type Config = RootCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // The layout of the verifier gadget follows the transcript of the proofs,
+    // which is fixed by `vk`, so the proofs are kept.
+    fn without_witnesses(&self) -> Self {
+        Self {
+            params: self.params,
+            vk: self.vk,
+            snarks: self.snarks.clone(),
+            headers: vec![BlockHeader::default(); self.headers.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let base_gate_config = FiveColumnBaseGate::<Fr>::configure(meta);
+        let range_gate_config = FiveColumnRangeGate::<'_, Fq, Fr, COMMON_RANGE_BITS>::configure(
+            meta,
+            &base_gate_config,
+        );
+        let q_chain = meta.selector();
+        let headers = [(); 8].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        for column in headers {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(constants);
+        meta.enable_equality(instance);
+
+        meta.create_gate("Block chain", |meta| {
+            let q_chain = meta.query_selector(q_chain);
+            [(PARENT_HASH, BLOCK_HASH), (PREV_STATE_ROOT, STATE_ROOT)]
+                .iter()
+                .flat_map(|(cur, prev)| [(*cur, *prev), (cur + 1, prev + 1)])
+                .map(|(cur, prev)| {
+                    q_chain.clone()
+                        * (meta.query_advice(headers[cur], Rotation::cur())
+                            - meta.query_advice(headers[prev], Rotation::prev()))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        RootCircuitConfig {
+            base_gate_config,
+            range_gate_config,
+            q_chain,
+            headers,
+            constants,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let base_gate = FiveColumnBaseGate::new(config.base_gate_config);
+        let range_gate = FiveColumnRangeGate::<'_, Fq, Fr, COMMON_RANGE_BITS>::new(
+            config.range_gate_config,
+            &base_gate,
+        );
+        let integer_gate = FiveColumnIntegerCircuit::new(&range_gate);
+        let ecc_gate = NativeEccCircuit::new(&integer_gate);
+
+        range_gate
+            .init_table(&mut layouter, &integer_gate.helper.integer_modulus)
+            .map_err(|_| Error::Synthesis)?;
+
+        let header_cells = layouter.assign_region(
+            || "block headers",
+            |mut region| {
+                let mut cells: Vec<Vec<AssignedCell<Fr, Fr>>> = vec![];
+                for (offset, header) in self.headers.iter().enumerate() {
+                    if offset > 0 {
+                        config.q_chain.enable(&mut region, offset)?;
+                    }
+                    let mut row = vec![];
+                    for (column, value) in config.headers.iter().zip(header.halves()) {
+                        row.push(region.assign_advice(
+                            || format!("block header {}", offset),
+                            *column,
+                            offset,
+                            || Ok(value),
+                        )?);
+                    }
+                    cells.push(row);
+                }
+                Ok(cells)
+            },
+        )?;
+        if let (Some(first), Some(last)) = (header_cells.first(), header_cells.last()) {
+            for (field, cells, idx) in [
+                (InstanceField::ParentHash, first, PARENT_HASH),
+                (InstanceField::PrevStateRoot, first, PREV_STATE_ROOT),
+                (InstanceField::BlockHash, last, BLOCK_HASH),
+                (InstanceField::StateRoot, last, STATE_ROOT),
+            ] {
+                for half in 0..2 {
+                    layouter.constrain_instance(
+                        cells[idx + half].cell(),
+                        config.instance,
+                        instance_offset(field) + half,
+                    )?;
+                }
+            }
+        }
+
+        let [version, zero] = layouter.assign_region(
+            || "instance constants",
+            |mut region| {
+                let version = region.assign_fixed(
+                    || "instance layout version",
+                    config.constants,
+                    0,
+                    || Ok(Fr::from(INSTANCE_LAYOUT_VERSION)),
+                )?;
+                let zero =
+                    region.assign_fixed(|| "zero", config.constants, 1, || Ok(Fr::zero()))?;
+                Ok([version, zero])
+            },
+        )?;
+        layouter.constrain_instance(
+            version.cell(),
+            config.instance,
+            instance_offset(InstanceField::Version),
+        )?;
+
+        let (pi_cells, accumulator_cells) = layouter.assign_region(
+            || "verify proofs",
+            |mut region| {
+                let mut base_offset = 0usize;
+                let mut aux = RegionAux::new(&mut region, &mut base_offset);
+                let r = &mut aux;
+
+                let (mut pi_cells, mut w_xs, mut w_gs) = (vec![], vec![], vec![]);
+                // Limbs of the points of the proofs, the transcript of the
+                // challenge of the accumulator
+                let mut transcript = vec![];
+                for snark in self.snarks.iter() {
+                    let instances = snark
+                        .instances
+                        .iter()
+                        .map(|instance| &instance[..])
+                        .collect::<Vec<_>>();
+                    let mut transcript =
+                        PoseidonRead::<_, _, Challenge255<_>>::init(&snark.proof[..]);
+
+                    let param = VerifierParams::from_transcript(
+                        &base_gate,
+                        &ecc_gate,
+                        r,
+                        Fr::zero(),
+                        Fr::zero(),
+                        Fr::zero(),
+                        &[&instances[..]],
+                        self.vk,
+                        self.params,
+                        &mut transcript,
+                    )
+                    .map_err(|_| Error::Synthesis)?;
+                    // The instance values of the proof are assigned by the
+                    // gadget, the public input of the PI circuit is the last
+                    // column
+                    let pi = param.instances[0].last().ok_or(Error::Synthesis)?;
+                    pi_cells.push(
+                        pi[..N_PI_INSTANCE]
+                            .iter()
+                            .map(|value| value.cell)
+                            .collect::<Vec<_>>(),
+                    );
+                    let proof = param
+                        .batch_multi_open_proofs(&base_gate, &ecc_gate, r)
+                        .map_err(|_| Error::Synthesis)?;
+                    let w_x = ecc_gate.ecc_reduce(r, &proof.w_x)?;
+                    let w_g = ecc_gate.ecc_reduce(r, &proof.w_g)?;
+                    for coordinate in [&w_x.x, &w_x.y, &w_g.x, &w_g.y] {
+                        transcript.extend(coordinate.limbs_le.iter().cloned());
+                    }
+                    w_xs.push(w_x);
+                    w_gs.push(w_g);
+                }
+
+                let challenge = poseidon::assign_hash(&base_gate, r, &transcript)?;
+                let mut accumulator_cells = vec![];
+                for points in [w_gs, w_xs] {
+                    // Σ c^i ⋅ p_i, by Horner's rule from the last point
+                    let mut points = points.into_iter().rev();
+                    let mut acc = points.next().ok_or(Error::Synthesis)?;
+                    for point in points {
+                        let acc_c = ecc_gate.ecc_mul(r, &acc, challenge.clone())?;
+                        acc = ecc_gate.ecc_add(r, &acc_c, &point)?;
+                    }
+                    let acc = ecc_gate.ecc_reduce(r, &acc)?;
+                    for coordinate in [&acc.x, &acc.y] {
+                        accumulator_cells.extend(coordinate.limbs_le.iter().map(|limb| limb.cell));
+                    }
+                }
+
+                Ok((pi_cells, accumulator_cells))
+            },
+        )?;
+
+        // The digest of the previous block of each proof is the digest of the
+        // proof before it, and the one of the first proof is zero.  The header
+        // of each block is the one of its proof.
+        layouter.assign_region(
+            || "proof public inputs",
+            |mut region| {
+                let mut prev_digest = [zero.cell(), zero.cell()];
+                for (pi, header) in pi_cells.iter().zip(header_cells.iter()) {
+                    for (cell, header_cell) in pi[HEADERS..HEADERS + 8].iter().zip(header) {
+                        region.constrain_equal(*cell, header_cell.cell())?;
+                    }
+                    for (cell, prev_cell) in
+                        pi[PREV_DIGEST..PREV_DIGEST + 2].iter().zip(prev_digest)
+                    {
+                        region.constrain_equal(*cell, prev_cell)?;
+                    }
+                    prev_digest = [pi[DIGEST], pi[DIGEST + 1]];
+                }
+                Ok(())
+            },
+        )?;
+        if let Some(last) = pi_cells.last() {
+            for half in 0..2 {
+                layouter.constrain_instance(
+                    last[DIGEST + half],
+                    config.instance,
+                    instance_offset(InstanceField::TxDigest) + half,
+                )?;
+            }
+        }
+        let offset = instance_offset(InstanceField::Accumulator);
+        for (row, cell) in accumulator_cells.into_iter().enumerate() {
+            layouter.constrain_instance(cell, config.instance, offset + row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        keccak_transcript::{KeccakRead, KeccakWrite},
+        root_verifier::root_verifier_runtime_yul,
+    };
+    use bus_mapping::{circuit_input_builder::CircuitsParams, mock::BlockData};
+    use eth_types::{geth_types::Account, Address};
+    use halo2_proofs::{
+        dev::MockProver,
+        plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, ProvingKey, SingleVerifier},
+        poly::commitment::Params,
+        transcript::PoseidonWrite,
+    };
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+    use zkevm_circuits::{
+        evm_circuit::{
+            table::FixedTableTag,
+            witness::{block_convert, Block},
+        },
+        instance::{check_instance, instance_len},
+        pi_circuit::PublicData,
+        super_circuit::SuperCircuit,
+    };
+
+    const K: u32 = 20;
+    const SUPER_K: u32 = 16;
+    const MEMORY_ADDRESS_MAX: usize = 100;
+    const MAX_RWS: usize = 200;
+    const MAX_WITHDRAWALS: usize = 1;
+
+    const CIRCUITS_PARAMS: CircuitsParams = CircuitsParams {
+        max_txs: 1,
+        max_calldata: 32,
+        max_rws: MAX_RWS,
+        max_bytecode: 512,
+        max_copy_rows: 100,
+        max_keccak_rows: 100,
+        max_exp_steps: 1,
+        max_mpt_rows: 100,
+    };
+
+    type TestSuperCircuit = SuperCircuit<Fr, MEMORY_ADDRESS_MAX, MAX_RWS>;
+
+    // Returns the witness of a block without txs, whose tx circuit proves its
+    // padding txs.
+    fn block() -> Block<Fr> {
+        let block_data = BlockData::new_from_geth_data_with_params(
+            mock::new(vec![], vec![]).unwrap(),
+            CIRCUITS_PARAMS,
+        );
+        let mut builder = block_data.new_circuit_input_builder();
+        builder
+            .handle_block(&block_data.eth_block, &block_data.geth_traces)
+            .unwrap();
+        block_convert(&builder.block, &builder.code_db)
+    }
+
+    // Returns the super circuit of a block without txs, with the header fields
+    // of `header`.
+    fn super_circuit(header: PublicData) -> TestSuperCircuit {
+        TestSuperCircuit {
+            block: block(),
+            fixed_table_tags: vec![
+                FixedTableTag::Range5,
+                FixedTableTag::Range16,
+                FixedTableTag::Range32,
+                FixedTableTag::Range256,
+                FixedTableTag::Range512,
+                FixedTableTag::SignByte,
+                FixedTableTag::ResponsibleOpcode,
+            ],
+            size: 1 << SUPER_K,
+            header,
+            max_withdrawals: MAX_WITHDRAWALS,
+        }
+    }
+
+    // Returns the digest of the public inputs of the block of `circuit`.
+    fn digest(circuit: &TestSuperCircuit) -> Word {
+        let params = circuit.block.circuits_params;
+        circuit
+            .public_data()
+            .digest(
+                circuit.block.randomness,
+                params.max_txs,
+                params.max_calldata,
+                MAX_WITHDRAWALS,
+            )
+            .unwrap()
+    }
+
+    // Returns the super circuits of two chained blocks.
+    fn super_circuits() -> Vec<TestSuperCircuit> {
+        let first = super_circuit(PublicData {
+            block_hash: Word::from(0xb1),
+            parent_hash: Word::from(0xb0),
+            state_root: Word::from(0x51),
+            prev_state_root: Word::from(0x50),
+            ..Default::default()
+        });
+        let second = super_circuit(PublicData {
+            block_hash: Word::from(0xb2),
+            parent_hash: Word::from(0xb1),
+            state_root: Word::from(0x52),
+            prev_state_root: Word::from(0x51),
+            prev_digest: digest(&first),
+            ..Default::default()
+        });
+        vec![first, second]
+    }
+
+    fn headers(circuits: &[TestSuperCircuit]) -> Vec<BlockHeader> {
+        circuits
+            .iter()
+            .map(|circuit| BlockHeader {
+                block_hash: circuit.header.block_hash,
+                parent_hash: circuit.header.parent_hash,
+                state_root: circuit.header.state_root,
+                prev_state_root: circuit.header.prev_state_root,
+            })
+            .collect()
+    }
+
+    // Returns the proofs of `circuits` with `pk`.
+    fn snarks(
+        general_params: &Params<G1Affine>,
+        pk: &ProvingKey<G1Affine>,
+        circuits: &[TestSuperCircuit],
+    ) -> Vec<Snark> {
+        circuits
+            .iter()
+            .map(|circuit| {
+                let instances = circuit.instance().unwrap();
+                let instance_slices = instances
+                    .iter()
+                    .map(|instance| &instance[..])
+                    .collect::<Vec<_>>();
+                let mut transcript = PoseidonWrite::<_, _, Challenge255<_>>::init(vec![]);
+                create_proof(
+                    general_params,
+                    pk,
+                    &[circuit.clone()],
+                    &[&instance_slices[..]],
+                    XorShiftRng::seed_from_u64(0),
+                    &mut transcript,
+                )
+                .unwrap();
+                Snark {
+                    proof: transcript.finalize(),
+                    instances,
+                }
+            })
+            .collect()
+    }
+
+    // Runs `f` with the root circuit of the proofs of `super_circuits()`, and
+    // with these super circuits.
+    fn with_root_circuit(f: impl FnOnce(RootCircuit, &[TestSuperCircuit])) {
+        let circuits = super_circuits();
+        let general_params = Params::<G1Affine>::unsafe_setup::<Bn256>(SUPER_K);
+        let verifier_params: ParamsVerifier<Bn256> =
+            general_params.verifier((1 << SUPER_K) - 64).unwrap();
+        let vk = keygen_vk(&general_params, &circuits[0]).unwrap();
+        let pk = keygen_pk(&general_params, vk, &circuits[0]).unwrap();
+
+        let root_circuit = RootCircuit::new(
+            &verifier_params,
+            pk.get_vk(),
+            snarks(&general_params, &pk, &circuits),
+            headers(&circuits),
+        )
+        .unwrap();
+        f(root_circuit, &circuits)
+    }
+
+    #[test]
+    fn root_circuit_prover() {
+        with_root_circuit(|root_circuit, circuits| {
+            let instance = root_circuit.instance().unwrap();
+            check_instance(&instance).unwrap();
+            let public_instance = PublicInstance::from_instance(&instance).unwrap();
+            assert_eq!(public_instance.tx_digest, digest(&circuits[1]));
+
+            let prover = match MockProver::run(K, &root_circuit, vec![instance.clone()]) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:#?}", e),
+            };
+            assert_eq!(prover.verify(), Ok(()));
+
+            for field in [InstanceField::TxDigest, InstanceField::Accumulator] {
+                let mut other_instance = instance.clone();
+                other_instance[instance_offset(field)] += Fr::one();
+                let prover = MockProver::run(K, &root_circuit, vec![other_instance]).unwrap();
+                assert!(prover.verify().is_err(), "{:?} isn't constrained", field);
+            }
+            let offset = instance_offset(InstanceField::Accumulator);
+            assert!(instance[offset..].iter().any(|limb| *limb != Fr::zero()));
+        });
+    }
+
+    // Compiles the Yul `source` with solc, which has to be installed.
+    fn compile_yul(source: &str) -> Vec<u8> {
+        let mut solc = Command::new("solc")
+            .args(["--strict-assembly", "--bin", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("solc");
+        solc.stdin
+            .take()
+            .unwrap()
+            .write_all(source.as_bytes())
+            .unwrap();
+        let output = solc.wait_with_output().unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let bin = stdout
+            .lines()
+            .skip_while(|line| !line.starts_with("Binary representation"))
+            .nth(1)
+            .expect("binary representation of the contract");
+        hex::decode(bin.trim()).unwrap()
+    }
+
+    // Returns the call data of the verifier contract for `instance` and
+    // `proof`.
+    fn calldata(instance: &[Fr], proof: &[u8]) -> Vec<u8> {
+        instance
+            .iter()
+            .flat_map(|cell| {
+                let mut bytes = cell.to_bytes();
+                bytes.reverse();
+                bytes
+            })
+            .chain(proof.iter().copied())
+            .collect()
+    }
+
+    // Returns whether the contract of `code` accepts `calldata`, run by the
+    // EVM of the external tracer.
+    fn call_verifier(code: &[u8], calldata: Vec<u8>) -> bool {
+        let account = Account {
+            address: Address::zero(),
+            code: code.to_vec().into(),
+            ..Default::default()
+        };
+        let mut tx = mock::new_tx(&mock::new_block());
+        tx.gas = Word::from(10_000_000u64);
+        tx.input = calldata.into();
+        let block = mock::new(vec![account], vec![tx]).unwrap();
+        !block.geth_traces[0].failed
+    }
+
+    // Maximum size of the code of a contract, from EIP-170.
+    const MAX_CODE_SIZE: usize = 0x6000;
+
+    #[test]
+    fn root_verifier_contract() {
+        with_root_circuit(|root_circuit, _| {
+            let instance = root_circuit.instance().unwrap();
+
+            let root_params = Params::<G1Affine>::unsafe_setup::<Bn256>(K);
+            let root_vk = keygen_vk(&root_params, &root_circuit).unwrap();
+            let root_pk = keygen_pk(&root_params, root_vk, &root_circuit).unwrap();
+            let mut transcript = KeccakWrite::init(vec![]);
+            create_proof(
+                &root_params,
+                &root_pk,
+                &[root_circuit],
+                &[&[&instance]],
+                XorShiftRng::seed_from_u64(0),
+                &mut transcript,
+            )
+            .unwrap();
+            let proof = transcript.finalize();
+
+            let root_verifier_params = root_params.verifier(instance_len()).unwrap();
+            verify_proof(
+                &root_verifier_params,
+                root_pk.get_vk(),
+                SingleVerifier::new(&root_verifier_params),
+                &[&[&instance]],
+                &mut KeccakRead::init(&proof[..]),
+            )
+            .unwrap();
+
+            let code = compile_yul(&root_verifier_runtime_yul(
+                &root_verifier_params,
+                root_pk.get_vk(),
+            ));
+            assert!(code.len() <= MAX_CODE_SIZE);
+            assert!(call_verifier(&code, calldata(&instance, &proof)));
+
+            // A proof of another instance is rejected
+            let mut other_instance = instance.clone();
+            other_instance[instance_offset(InstanceField::TxDigest)] += Fr::from(1);
+            assert!(!call_verifier(&code, calldata(&other_instance, &proof)));
+
+            // A tampered proof is rejected
+            for offset in [0, proof.len() / 2, proof.len() - 1] {
+                let mut tampered_proof = proof.clone();
+                tampered_proof[offset] ^= 1;
+                assert!(!call_verifier(&code, calldata(&instance, &tampered_proof)));
+            }
+            assert!(!call_verifier(
+                &code,
+                calldata(&instance, &proof[..proof.len() - 1])
+            ));
+
+            let mut other_version = instance.clone();
+            other_version[instance_offset(InstanceField::Version)] =
+                Fr::from(INSTANCE_LAYOUT_VERSION + 1);
+            assert!(!call_verifier(&code, calldata(&other_version, &proof)));
+
+            // The identity accumulator passes the pairing check, but is
+            // rejected
+            let mut identity_accumulator = instance.clone();
+            let accumulator = instance_offset(InstanceField::Accumulator);
+            identity_accumulator[accumulator..accumulator + N_ACCUMULATOR_LIMBS].fill(Fr::zero());
+            assert!(!call_verifier(
+                &code,
+                calldata(&identity_accumulator, &proof)
+            ));
+
+            // The accumulator with the generator (1, 2) of G1 as rhs fails the
+            // pairing check
+            let mut invalid_accumulator = instance;
+            let rhs = instance_offset(InstanceField::Accumulator) + N_ACCUMULATOR_LIMBS / 2;
+            invalid_accumulator[rhs..rhs + N_ACCUMULATOR_LIMBS / 2].fill(Fr::zero());
+            invalid_accumulator[rhs] = Fr::from(1);
+            invalid_accumulator[rhs + N_ACCUMULATOR_LIMBS / 4] = Fr::from(2);
+            assert!(!call_verifier(
+                &code,
+                calldata(&invalid_accumulator, &proof)
+            ));
+        });
+    }
+
+    #[test]
+    fn root_circuit_broken_chain() {
+        let headers = vec![
+            BlockHeader {
+                block_hash: Word::from(0xb1),
+                state_root: Word::from(0x51),
+                ..Default::default()
+            },
+            BlockHeader {
+                parent_hash: Word::from(0xb1),
+                prev_state_root: Word::from(0x50),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            check_chain(&headers),
+            Err(RootCircuitError::BrokenChain { index: 1 })
+        );
+    }
+
+    // Returns a proof with the public input of `digest`, `prev_digest` and a
+    // default header.
+    fn snark(digest: u64, prev_digest: u64) -> Snark {
+        let mut pi = vec![Fr::zero(); N_PI_INSTANCE];
+        pi[DIGEST + 1] = Fr::from(digest);
+        pi[PREV_DIGEST + 1] = Fr::from(prev_digest);
+        Snark {
+            instances: vec![pi],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn root_circuit_broken_digest_chain() {
+        assert_eq!(
+            check_digest_chain(&[snark(0xd1, 0), snark(0xd2, 0xd1)]),
+            Ok(Word::from(0xd2))
+        );
+        assert_eq!(
+            check_digest_chain(&[snark(0xd1, 0xd0), snark(0xd2, 0xd1)]),
+            Err(RootCircuitError::BrokenDigestChain { index: 0 })
+        );
+        assert_eq!(
+            check_digest_chain(&[snark(0xd1, 0), snark(0xd2, 0xd0)]),
+            Err(RootCircuitError::BrokenDigestChain { index: 1 })
+        );
+        assert_eq!(
+            check_digest_chain(&[snark(0xd1, 0), Snark::default()]),
+            Err(RootCircuitError::InvalidPublicInput { index: 1 })
+        );
+    }
+
+    #[test]
+    fn root_circuit_unbound_header() {
+        let snarks = [snark(0xd1, 0), snark(0xd2, 0xd1)];
+        let headers = [BlockHeader::default(); 2];
+        assert_eq!(check_headers(&snarks, &headers), Ok(()));
+        let other_headers = [
+            BlockHeader::default(),
+            BlockHeader {
+                state_root: Word::from(0x51),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            check_headers(&snarks, &other_headers),
+            Err(RootCircuitError::UnboundHeader { index: 1 })
+        );
+    }
+}
@@ -0,0 +1,1015 @@
+//! Generator of the verifier contract of the root circuit.
+//!
+//! The contract is emitted as a Yul object, to be compiled with `solc
+//! --strict-assembly`.  It takes as call data the cells of the instance of the
+//! root circuit, as 32-byte big-endian words following the layout of
+//! `zkevm_circuits::instance`, followed by the root proof, created with the
+//! `KeccakWrite` transcript, and returns 1 if they are accepted, reverting
+//! otherwise.  It checks that:
+//! - the instance has the layout version the contract is generated for, and all
+//!   its cells are scalars,
+//! - the root proof is valid for the instance and the verifying key of the root
+//!   circuit the contract is generated for,
+//! - the KZG accumulator of the instance, the points `lhs` and `rhs` made of
+//!   the limbs of their `x` and `y` coordinates in this order, passes the
+//!   deferred pairing check `e(lhs, [1]_2) = e(rhs, [s]_2)`, which completes
+//!   the verification of the proofs aggregated by the root proof, and that
+//!   neither `lhs` nor `rhs` is the identity, with which the check passes
+//!   trivially.
+//!
+//! The root proof is verified as `halo2_proofs::plonk::verify_proof` does: the
+//! contract replays the keccak transcript of the proof, checks that the
+//! evaluations of the gates and of the permutation and lookup arguments are
+//! the ones of the quotient, and checks the multi-open KZG opening of the
+//! queries of the proof with a pairing.  The layout of the proof and its
+//! queries are fixed by the constraint system of the verifying key, so the
+//! generated code is straight-line, with every value it computes in its own
+//! word of memory.
+
+use crate::keccak_transcript::{N_POINT_BYTES, N_WORD_BYTES};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::{Any, Column, ConstraintSystem, Expression, VerifyingKey},
+    poly::{commitment::ParamsVerifier, Rotation},
+    transcript::{Challenge255, Transcript},
+};
+use pairing::{
+    arithmetic::CurveAffine,
+    bn256::{Bn256, Fq, Fq2, Fr, G1Affine, G2Affine},
+    group::{
+        ff::{Field, PrimeField},
+        prime::PrimeCurveAffine,
+    },
+};
+use std::io;
+use zkevm_circuits::instance::{
+    instance_len, instance_offset, InstanceField, ACCUMULATOR_LIMB_BITS, INSTANCE_LAYOUT_VERSION,
+    N_ACCUMULATOR_LIMBS,
+};
+
+// Modulus of the scalar field of BN254.
+const SCALAR_MODULUS: &str = "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001";
+// Modulus of the base field of BN254.
+const BASE_MODULUS: &str = "0x30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47";
+
+// Number of limbs of a coordinate of the accumulator.
+const N_COORDINATE_LIMBS: usize = N_ACCUMULATOR_LIMBS / 4;
+
+// The memory below is the scratch space of the calls to the precompiles, the
+// values of the verification of the root proof follow it.
+const SCRATCH_SIZE: usize = 0x180;
+
+// Returns the 32-byte big endian hex literal of a little endian
+// representation.
+fn literal(mut repr: [u8; N_WORD_BYTES]) -> String {
+    repr.reverse();
+    format!("0x{}", hex::encode(repr))
+}
+
+// Returns `value` as a Yul hex literal.
+fn fq_literal(value: &Fq) -> String {
+    literal(value.to_repr())
+}
+
+// Returns `value` as a Yul hex literal.
+fn fr_literal(value: &Fr) -> String {
+    literal(value.to_repr())
+}
+
+// Returns the `mstore`s of `point` at `offset` in the encoding of the pairing
+// precompile, the imaginary part of each coordinate first.
+fn g2_mstores(point: &G2Affine, offset: usize) -> String {
+    let coordinates: [&Fq2; 2] = [&point.x, &point.y];
+    coordinates
+        .iter()
+        .flat_map(|coordinate| [&coordinate.c1, &coordinate.c0])
+        .enumerate()
+        .map(|(idx, value)| {
+            format!(
+                "            mstore({:#x}, {})\n",
+                offset + 0x20 * idx,
+                fq_literal(value)
+            )
+        })
+        .collect()
+}
+
+// Returns the code checking `e(a, a_g2) * e(-b, b_g2) = 1` for the points `a`
+// and `b` of G1, given by the Yul expressions of their coordinates.
+fn pairing_check(a: [String; 2], a_g2: &G2Affine, b: [String; 2], b_g2: &G2Affine) -> String {
+    let [a_x, a_y] = a;
+    let [b_x, b_y] = b;
+    format!(
+        r#"            mstore(0x00, {a_x})
+            mstore(0x20, {a_y})
+{a_g2}            mstore(0xc0, {b_x})
+            mstore(0xe0, mod(sub(q, {b_y}), q))
+{b_g2}            if iszero(staticcall(gas(), 0x08, 0x00, 0x180, 0x00, 0x20)) {{
+                revert(0, 0)
+            }}
+            if iszero(mload(0x00)) {{
+                revert(0, 0)
+            }}
+"#,
+        a_x = a_x,
+        a_y = a_y,
+        a_g2 = g2_mstores(a_g2, 0x40),
+        b_x = b_x,
+        b_y = b_y,
+        b_g2 = g2_mstores(b_g2, 0x100),
+    )
+}
+
+// Scalar of the generated code.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Scalar {
+    Constant(Fr),
+    // Word of memory at the address
+    Memory(usize),
+    // Word of the call data at the offset
+    Calldata(usize),
+}
+
+impl Scalar {
+    fn is_constant(&self, value: u64) -> bool {
+        *self == Self::Constant(Fr::from(value))
+    }
+
+    fn expr(&self) -> String {
+        match self {
+            Self::Constant(value) => fr_literal(value),
+            Self::Memory(ptr) => format!("mload({:#x})", ptr),
+            Self::Calldata(offset) => format!("calldataload({:#x})", offset),
+        }
+    }
+}
+
+// Point of G1 of the generated code.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Point {
+    Constant(G1Affine),
+    // Coordinates in the two words of memory at the address
+    Memory(usize),
+}
+
+impl Point {
+    fn exprs(&self) -> [String; 2] {
+        match self {
+            Self::Constant(point) => {
+                if bool::from(point.is_identity()) {
+                    return ["0".to_string(), "0".to_string()];
+                }
+                let coordinates = point.coordinates().unwrap();
+                [fq_literal(coordinates.x()), fq_literal(coordinates.y())]
+            }
+            Self::Memory(ptr) => [
+                format!("mload({:#x})", ptr),
+                format!("mload({:#x})", ptr + 0x20),
+            ],
+        }
+    }
+}
+
+// Multi-scalar multiplication of the generated code, the sum of its terms.
+#[derive(Clone, Debug, Default)]
+struct Msm(Vec<(Scalar, Point)>);
+
+impl Msm {
+    fn base(point: Point) -> Self {
+        Self(vec![(Scalar::Constant(Fr::one()), point)])
+    }
+}
+
+// Expression of the evaluations of a proof, evaluated by the generated code.
+enum Term {
+    Value(Scalar),
+    Negated(Box<Term>),
+    Sum(Box<Term>, Box<Term>),
+    Product(Box<Term>, Box<Term>),
+    Scaled(Box<Term>, Fr),
+}
+
+// Evaluations of the queries of the columns of a proof, in the order of the
+// queries of the constraint system.
+struct Evals {
+    instance: Vec<Scalar>,
+    advice: Vec<Scalar>,
+    fixed: Vec<Scalar>,
+}
+
+impl Evals {
+    // Returns the evaluation of `column` at `x`.
+    fn column(&self, cs: &ConstraintSystem<Fr>, column: &Column<Any>) -> Scalar {
+        let position = |queries: Vec<(usize, Rotation)>| {
+            queries
+                .iter()
+                .position(|(index, at)| *index == column.index() && *at == Rotation::cur())
+                .expect("permutation column queried at the current row")
+        };
+        match column.column_type() {
+            Any::Advice => {
+                self.advice[position(
+                    cs.advice_queries
+                        .iter()
+                        .map(|(column, at)| (column.index(), *at))
+                        .collect(),
+                )]
+            }
+            Any::Fixed => {
+                self.fixed[position(
+                    cs.fixed_queries
+                        .iter()
+                        .map(|(column, at)| (column.index(), *at))
+                        .collect(),
+                )]
+            }
+            Any::Instance => {
+                self.instance[position(
+                    cs.instance_queries
+                        .iter()
+                        .map(|(column, at)| (column.index(), *at))
+                        .collect(),
+                )]
+            }
+        }
+    }
+}
+
+// Query of a commitment at `x` rotated by `rotation`, which opens to `eval`.
+struct Query {
+    rotation: i32,
+    commitment: Msm,
+    eval: Scalar,
+}
+
+impl Query {
+    fn new(rotation: i32, commitment: Point, eval: Scalar) -> Self {
+        Self {
+            rotation,
+            commitment: Msm::base(commitment),
+            eval,
+        }
+    }
+}
+
+// Transcript recording the scalars absorbed by `VerifyingKey::hash_into`.
+#[derive(Default)]
+struct ScalarRecorder(Vec<Fr>);
+
+impl Transcript<G1Affine, Challenge255<G1Affine>> for ScalarRecorder {
+    fn squeeze_challenge(&mut self) -> Challenge255<G1Affine> {
+        unreachable!("the verifying key is hashed without challenges")
+    }
+
+    fn common_point(&mut self, _: G1Affine) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the verifying key is hashed into scalars",
+        ))
+    }
+
+    fn common_scalar(&mut self, scalar: Fr) -> io::Result<()> {
+        self.0.push(scalar);
+        Ok(())
+    }
+}
+
+// Generator of the code of the verification of a root proof.  The transcript
+// is a buffer of memory at `t`, after the values, whose first word is the hash
+// of the previous challenge once one is squeezed.
+struct Generator {
+    code: String,
+    // Address of the next free word of memory
+    memory: usize,
+    // Number of bytes absorbed in the transcript buffer
+    transcript_len: usize,
+    // Offset of the next value of the proof in the call data
+    calldata_offset: usize,
+}
+
+impl Generator {
+    fn new(calldata_offset: usize) -> Self {
+        Self {
+            code: String::new(),
+            memory: SCRATCH_SIZE,
+            transcript_len: 0,
+            calldata_offset,
+        }
+    }
+
+    fn emit(&mut self, statement: String) {
+        self.code.push_str("            ");
+        self.code.push_str(&statement);
+        self.code.push('\n');
+    }
+
+    fn alloc(&mut self, words: usize) -> usize {
+        let ptr = self.memory;
+        self.memory += 0x20 * words;
+        ptr
+    }
+
+    // Returns the scalar stored in a new word of memory with the value of the
+    // Yul expression `expr`.
+    fn scalar(&mut self, expr: String) -> Scalar {
+        let ptr = self.alloc(1);
+        self.emit(format!("mstore({:#x}, {})", ptr, expr));
+        Scalar::Memory(ptr)
+    }
+
+    fn add(&mut self, a: Scalar, b: Scalar) -> Scalar {
+        match (a, b) {
+            (Scalar::Constant(a), Scalar::Constant(b)) => Scalar::Constant(a + b),
+            _ if a.is_constant(0) => b,
+            _ if b.is_constant(0) => a,
+            _ => self.scalar(format!("addmod({}, {}, r)", a.expr(), b.expr())),
+        }
+    }
+
+    fn neg(&mut self, a: Scalar) -> Scalar {
+        match a {
+            Scalar::Constant(a) => Scalar::Constant(-a),
+            _ => self.scalar(format!("mod(sub(r, {}), r)", a.expr())),
+        }
+    }
+
+    fn sub(&mut self, a: Scalar, b: Scalar) -> Scalar {
+        let b = self.neg(b);
+        self.add(a, b)
+    }
+
+    fn mul(&mut self, a: Scalar, b: Scalar) -> Scalar {
+        match (a, b) {
+            (Scalar::Constant(a), Scalar::Constant(b)) => Scalar::Constant(a * b),
+            _ if a.is_constant(1) => b,
+            _ if b.is_constant(1) => a,
+            _ => self.scalar(format!("mulmod({}, {}, r)", a.expr(), b.expr())),
+        }
+    }
+
+    fn invert(&mut self, a: Scalar) -> Scalar {
+        match a {
+            Scalar::Constant(a) => Scalar::Constant(a.invert().unwrap()),
+            _ => self.scalar(format!("invert({})", a.expr())),
+        }
+    }
+
+    fn term(&mut self, term: &Term) -> Scalar {
+        match term {
+            Term::Value(value) => *value,
+            Term::Negated(a) => {
+                let a = self.term(a);
+                self.neg(a)
+            }
+            Term::Sum(a, b) => {
+                let (a, b) = (self.term(a), self.term(b));
+                self.add(a, b)
+            }
+            Term::Product(a, b) => {
+                let (a, b) = (self.term(a), self.term(b));
+                self.mul(a, b)
+            }
+            Term::Scaled(a, scalar) => {
+                let a = self.term(a);
+                self.mul(a, Scalar::Constant(*scalar))
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression<Fr>, evals: &Evals) -> Scalar {
+        let term = expression.evaluate(
+            &|constant| Term::Value(Scalar::Constant(constant)),
+            &|_| panic!("virtual selectors are removed during optimization"),
+            &|query_index, _, _| Term::Value(evals.fixed[query_index]),
+            &|query_index, _, _| Term::Value(evals.advice[query_index]),
+            &|query_index, _, _| Term::Value(evals.instance[query_index]),
+            &|a| Term::Negated(Box::new(a)),
+            &|a, b| Term::Sum(Box::new(a), Box::new(b)),
+            &|a, b| Term::Product(Box::new(a), Box::new(b)),
+            &|a, scalar| Term::Scaled(Box::new(a), scalar),
+        );
+        self.term(&term)
+    }
+
+    // Adds `scalar * point` to `msm`, merging the terms of the same point.
+    fn msm_push(&mut self, msm: &mut Msm, scalar: Scalar, point: Point) {
+        match msm.0.iter().position(|(_, other)| *other == point) {
+            Some(idx) => msm.0[idx].0 = self.add(msm.0[idx].0, scalar),
+            None => msm.0.push((scalar, point)),
+        }
+    }
+
+    // Adds `scalar * other` to `msm`.
+    fn msm_add_scaled(&mut self, msm: &mut Msm, scalar: Scalar, other: &Msm) {
+        for (other_scalar, point) in other.0.iter() {
+            let scalar = self.mul(scalar, *other_scalar);
+            self.msm_push(msm, scalar, *point);
+        }
+    }
+
+    // Returns the point of `msm`, computed with the precompiles.
+    fn msm(&mut self, msm: &Msm) -> Point {
+        // The memory is zero, the identity, before it's written
+        let ptr = self.alloc(2);
+        for (scalar, point) in msm.0.iter() {
+            let [x, y] = point.exprs();
+            if scalar.is_constant(1) {
+                self.emit(format!("ec_add_acc({:#x}, {}, {})", ptr, x, y));
+            } else if !scalar.is_constant(0) {
+                self.emit(format!(
+                    "ec_mul_acc({:#x}, {}, {}, {})",
+                    ptr,
+                    x,
+                    y,
+                    scalar.expr()
+                ));
+            }
+        }
+        Point::Memory(ptr)
+    }
+
+    // Returns the Yul expression of the address of the next `len` bytes of the
+    // transcript buffer, which are absorbed.
+    fn absorb(&mut self, len: usize) -> String {
+        let ptr = format!("add(t, {:#x})", self.transcript_len);
+        self.transcript_len += len;
+        ptr
+    }
+
+    fn common_scalar(&mut self, scalar: Scalar) {
+        let ptr = self.absorb(N_WORD_BYTES);
+        self.emit(format!("mstore({}, {})", ptr, scalar.expr()));
+    }
+
+    fn common_point(&mut self, point: Point) {
+        let ptr = self.absorb(N_POINT_BYTES);
+        let [x, y] = point.exprs();
+        self.emit(format!("mstore({}, {})", ptr, x));
+        self.emit(format!("mstore(add({}, 0x20), {})", ptr, y));
+    }
+
+    fn read_point(&mut self) -> Point {
+        let transcript_ptr = self.absorb(N_POINT_BYTES);
+        let ptr = self.alloc(2);
+        self.emit(format!(
+            "read_point({:#x}, {}, {:#x})",
+            self.calldata_offset, transcript_ptr, ptr
+        ));
+        self.calldata_offset += N_POINT_BYTES;
+        Point::Memory(ptr)
+    }
+
+    fn read_points(&mut self, n: usize) -> Vec<Point> {
+        (0..n).map(|_| self.read_point()).collect()
+    }
+
+    fn read_scalar(&mut self) -> Scalar {
+        let transcript_ptr = self.absorb(N_WORD_BYTES);
+        let ptr = self.alloc(1);
+        self.emit(format!(
+            "read_scalar({:#x}, {}, {:#x})",
+            self.calldata_offset, transcript_ptr, ptr
+        ));
+        self.calldata_offset += N_WORD_BYTES;
+        Scalar::Memory(ptr)
+    }
+
+    fn read_scalars(&mut self, n: usize) -> Vec<Scalar> {
+        (0..n).map(|_| self.read_scalar()).collect()
+    }
+
+    fn squeeze_challenge(&mut self) -> Scalar {
+        let ptr = self.alloc(1);
+        self.emit(format!(
+            "squeeze(t, {:#x}, {:#x})",
+            self.transcript_len, ptr
+        ));
+        self.transcript_len = N_WORD_BYTES;
+        Scalar::Memory(ptr)
+    }
+
+    // Generates the verification of a proof of the circuit of `vk` for the
+    // instance at the start of the call data, returning the points `left` and
+    // `right` of its pairing check `e(left, [s]_2) = e(right, [1]_2)`.
+    fn verify_proof(
+        &mut self,
+        params: &ParamsVerifier<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+    ) -> [Point; 2] {
+        let cs = &vk.cs;
+        assert_eq!(
+            cs.num_instance_columns, 1,
+            "root circuit has an instance column"
+        );
+        let omega = vk.domain.get_omega();
+        let omega_inv = vk.domain.get_omega_inv();
+        let rotate = |rotation: i32| {
+            if rotation >= 0 {
+                omega.pow_vartime(&[rotation as u64])
+            } else {
+                omega_inv.pow_vartime(&[(-rotation) as u64])
+            }
+        };
+        let one = Scalar::Constant(Fr::one());
+
+        let mut recorder = ScalarRecorder::default();
+        vk.hash_into(&mut recorder)
+            .expect("verifying key hashed into scalars");
+        for scalar in recorder.0 {
+            self.common_scalar(Scalar::Constant(scalar));
+        }
+        let instance_commitment = self.msm(&Msm(params.g_lagrange[..instance_len()]
+            .iter()
+            .enumerate()
+            .map(|(idx, point)| (Scalar::Calldata(0x20 * idx), Point::Constant(*point)))
+            .collect()));
+        self.common_point(instance_commitment);
+
+        let advice_commitments = self.read_points(cs.num_advice_columns);
+        let theta = self.squeeze_challenge();
+        let permuted_commitments = cs
+            .lookups
+            .iter()
+            .map(|_| [self.read_point(), self.read_point()])
+            .collect::<Vec<_>>();
+        let beta = self.squeeze_challenge();
+        let gamma = self.squeeze_challenge();
+        let permutation_columns = cs.permutation.get_columns();
+        let chunk_len = cs.degree() - 2;
+        let product_commitments =
+            self.read_points((permutation_columns.len() + chunk_len - 1) / chunk_len);
+        let lookup_product_commitments = self.read_points(cs.lookups.len());
+        let random_poly_commitment = self.read_point();
+        let y = self.squeeze_challenge();
+        let h_commitments = self.read_points(cs.degree() - 1);
+        let x = self.squeeze_challenge();
+
+        let evals = Evals {
+            instance: self.read_scalars(cs.instance_queries.len()),
+            advice: self.read_scalars(cs.advice_queries.len()),
+            fixed: self.read_scalars(cs.fixed_queries.len()),
+        };
+        let random_eval = self.read_scalar();
+        let permutation_evals = self.read_scalars(vk.permutation.commitments().len());
+        // The evaluations of each permutation product at x, ω x, and at the
+        // last row ω^-(blinding_factors + 1) x but for the last product
+        let product_evals = (0..product_commitments.len())
+            .map(|idx| {
+                let eval = self.read_scalar();
+                let next_eval = self.read_scalar();
+                let last_eval = (idx + 1 < product_commitments.len()).then(|| self.read_scalar());
+                (eval, next_eval, last_eval)
+            })
+            .collect::<Vec<_>>();
+        // The evaluations of each lookup product at x and ω x, of the permuted
+        // input at x and ω^-1 x, and of the permuted table at x
+        let lookup_evals = cs
+            .lookups
+            .iter()
+            .map(|_| [(); 5].map(|_| self.read_scalar()))
+            .collect::<Vec<_>>();
+
+        // The Lagrange polynomials at x: l_i(x) = ω^i (x^n - 1) / (n (x - ω^i))
+        let xn = (0..vk.domain.k()).fold(x, |xn, _| self.mul(xn, xn));
+        let xn_minus_one = self.sub(xn, one);
+        let n_inv = Fr::from(1u64 << vk.domain.k()).invert().unwrap();
+        let common = self.mul(xn_minus_one, Scalar::Constant(n_inv));
+        let lagrange = |generator: &mut Self, rotation: i32| {
+            let omega_i = Scalar::Constant(rotate(rotation));
+            let denominator = generator.sub(x, omega_i);
+            let denominator_inv = generator.invert(denominator);
+            let l = generator.mul(denominator_inv, common);
+            generator.mul(l, omega_i)
+        };
+        let blinding_factors = cs.blinding_factors() as i32;
+        let l_last = lagrange(self, -(blinding_factors + 1));
+        let mut l_blind = Scalar::Constant(Fr::zero());
+        for rotation in -blinding_factors..0 {
+            let l = lagrange(self, rotation);
+            l_blind = self.add(l_blind, l);
+        }
+        let l_0 = lagrange(self, 0);
+        let l_last_or_blind = self.add(l_last, l_blind);
+        let active_rows = self.sub(one, l_last_or_blind);
+
+        // The expressions which vanish on the rows, in the order of the
+        // quotient
+        let mut expressions = vec![];
+        for gate in cs.gates.iter() {
+            for polynomial in gate.polynomials() {
+                expressions.push(self.expression(polynomial, &evals));
+            }
+        }
+
+        // The first product starts at 1, the last one ends at 0 or 1, and each
+        // product starts at the end of the previous one
+        if let (Some((first, _, _)), Some((last, _, _))) =
+            (product_evals.first(), product_evals.last())
+        {
+            let (first, last) = (*first, *last);
+            let one_minus_first = self.sub(one, first);
+            expressions.push(self.mul(l_0, one_minus_first));
+            let last_square = self.mul(last, last);
+            let last_square_minus_last = self.sub(last_square, last);
+            expressions.push(self.mul(l_last, last_square_minus_last));
+        }
+        for (prev, (eval, _, _)) in product_evals.iter().zip(product_evals.iter().skip(1)) {
+            let prev_last = prev.2.expect("last evaluation of the previous product");
+            let diff = self.sub(*eval, prev_last);
+            expressions.push(self.mul(diff, l_0));
+        }
+        let beta_x = self.mul(beta, x);
+        for (chunk_idx, ((columns, permutation_evals), (eval, next_eval, _))) in permutation_columns
+            .chunks(chunk_len)
+            .zip(permutation_evals.chunks(chunk_len))
+            .zip(product_evals.iter())
+            .enumerate()
+        {
+            // z(ω x) Π (p(x) + β s_i(x) + γ) - z(x) Π (p(x) + δ^i β x + γ)
+            let mut left = *next_eval;
+            let mut right = *eval;
+            let mut delta =
+                Scalar::Constant(Fr::DELTA.pow_vartime(&[(chunk_idx * chunk_len) as u64]));
+            for (column, permutation_eval) in columns.iter().zip(permutation_evals) {
+                let column_eval = evals.column(cs, column);
+                let beta_s = self.mul(beta, *permutation_eval);
+                let factor = self.add(column_eval, beta_s);
+                let factor = self.add(factor, gamma);
+                left = self.mul(left, factor);
+                let delta_beta_x = self.mul(delta, beta_x);
+                let factor = self.add(column_eval, delta_beta_x);
+                let factor = self.add(factor, gamma);
+                right = self.mul(right, factor);
+                delta = self.mul(delta, Scalar::Constant(Fr::DELTA));
+            }
+            let diff = self.sub(left, right);
+            expressions.push(self.mul(diff, active_rows));
+        }
+
+        for (lookup, [product, product_next, input, input_inv, table]) in
+            cs.lookups.iter().zip(lookup_evals.iter().copied())
+        {
+            let one_minus_product = self.sub(one, product);
+            expressions.push(self.mul(l_0, one_minus_product));
+            let product_square = self.mul(product, product);
+            let product_square_minus_product = self.sub(product_square, product);
+            expressions.push(self.mul(l_last, product_square_minus_product));
+
+            // z(ω x) (a'(x) + β) (s'(x) + γ) - z(x) (θ^{m-1} a_0(x) + ... +
+            // β) (θ^{m-1} s_0(x) + ... + γ)
+            let input_beta = self.add(input, beta);
+            let table_gamma = self.add(table, gamma);
+            let left = self.mul(product_next, input_beta);
+            let left = self.mul(left, table_gamma);
+            let mut compressed = [Scalar::Constant(Fr::zero()); 2];
+            for (compressed, lookup_expressions) in compressed
+                .iter_mut()
+                .zip([&lookup.input_expressions, &lookup.table_expressions])
+            {
+                for expression in lookup_expressions.iter() {
+                    let eval = self.expression(expression, &evals);
+                    let acc = self.mul(*compressed, theta);
+                    *compressed = self.add(acc, eval);
+                }
+            }
+            let [compressed_input, compressed_table] = compressed;
+            let input_beta = self.add(compressed_input, beta);
+            let table_gamma = self.add(compressed_table, gamma);
+            let right = self.mul(product, input_beta);
+            let right = self.mul(right, table_gamma);
+            let diff = self.sub(left, right);
+            expressions.push(self.mul(diff, active_rows));
+
+            let input_minus_table = self.sub(input, table);
+            expressions.push(self.mul(l_0, input_minus_table));
+            let input_minus_input_inv = self.sub(input, input_inv);
+            let diff = self.mul(input_minus_table, input_minus_input_inv);
+            expressions.push(self.mul(diff, active_rows));
+        }
+
+        // h(x) = Σ y^i expression_i / (x^n - 1), committed as the pieces
+        // h_i(x), with h(x) = Σ x^(n i) h_i(x)
+        let mut h_eval = Scalar::Constant(Fr::zero());
+        for expression in expressions {
+            let acc = self.mul(h_eval, y);
+            h_eval = self.add(acc, expression);
+        }
+        let xn_minus_one_inv = self.invert(xn_minus_one);
+        let h_eval = self.mul(h_eval, xn_minus_one_inv);
+        let mut h_commitment = Msm::default();
+        let mut xn_power = one;
+        for commitment in h_commitments {
+            self.msm_push(&mut h_commitment, xn_power, commitment);
+            xn_power = self.mul(xn_power, xn);
+        }
+
+        let mut queries = vec![];
+        for (idx, (column, at)) in cs.instance_queries.iter().enumerate() {
+            assert_eq!(column.index(), 0);
+            queries.push(Query::new(at.0, instance_commitment, evals.instance[idx]));
+        }
+        for (idx, (column, at)) in cs.advice_queries.iter().enumerate() {
+            queries.push(Query::new(
+                at.0,
+                advice_commitments[column.index()],
+                evals.advice[idx],
+            ));
+        }
+        for (commitment, (eval, next_eval, _)) in
+            product_commitments.iter().zip(product_evals.iter())
+        {
+            queries.push(Query::new(0, *commitment, *eval));
+            queries.push(Query::new(1, *commitment, *next_eval));
+        }
+        for (commitment, (_, _, last_eval)) in product_commitments
+            .iter()
+            .zip(product_evals.iter())
+            .rev()
+            .skip(1)
+        {
+            queries.push(Query::new(
+                -(blinding_factors + 1),
+                *commitment,
+                last_eval.expect("last evaluation of the product"),
+            ));
+        }
+        for (
+            (product_commitment, [input_commitment, table_commitment]),
+            [product, product_next, input, input_inv, table],
+        ) in lookup_product_commitments
+            .iter()
+            .zip(permuted_commitments.iter())
+            .zip(lookup_evals.iter())
+        {
+            queries.push(Query::new(0, *product_commitment, *product));
+            queries.push(Query::new(0, *input_commitment, *input));
+            queries.push(Query::new(0, *table_commitment, *table));
+            queries.push(Query::new(-1, *input_commitment, *input_inv));
+            queries.push(Query::new(1, *product_commitment, *product_next));
+        }
+        for (idx, (column, at)) in cs.fixed_queries.iter().enumerate() {
+            queries.push(Query::new(
+                at.0,
+                Point::Constant(vk.fixed_commitments[column.index()]),
+                evals.fixed[idx],
+            ));
+        }
+        for (commitment, eval) in vk
+            .permutation
+            .commitments()
+            .iter()
+            .zip(permutation_evals.iter())
+        {
+            queries.push(Query::new(0, Point::Constant(*commitment), *eval));
+        }
+        queries.push(Query {
+            rotation: 0,
+            commitment: h_commitment,
+            eval: h_eval,
+        });
+        queries.push(Query::new(0, random_poly_commitment, random_eval));
+
+        // Multi-open of the queries grouped by point, in the order of their
+        // first query
+        let v = self.squeeze_challenge();
+        let mut groups: Vec<(i32, Vec<Query>)> = vec![];
+        for query in queries {
+            match groups
+                .iter_mut()
+                .find(|(rotation, _)| *rotation == query.rotation)
+            {
+                Some((_, group)) => group.push(query),
+                None => groups.push((query.rotation, vec![query])),
+            }
+        }
+        let witnesses = self.read_points(groups.len());
+        let u = self.squeeze_challenge();
+
+        let mut commitment_multi = Msm::default();
+        let mut eval_multi = Scalar::Constant(Fr::zero());
+        let mut witness = Msm::default();
+        let mut witness_with_aux = Msm::default();
+        let mut power_of_u = one;
+        for ((rotation, group), w) in groups.iter().zip(witnesses) {
+            let z = self.mul(x, Scalar::Constant(rotate(*rotation)));
+            let mut commitment_batch = Msm::default();
+            let mut eval_batch = Scalar::Constant(Fr::zero());
+            let mut power_of_v = one;
+            for (idx, query) in group.iter().enumerate() {
+                if idx > 0 {
+                    power_of_v = self.mul(power_of_v, v);
+                }
+                self.msm_add_scaled(&mut commitment_batch, power_of_v, &query.commitment);
+                let eval = self.mul(power_of_v, query.eval);
+                eval_batch = self.add(eval_batch, eval);
+            }
+            self.msm_add_scaled(&mut commitment_multi, power_of_u, &commitment_batch);
+            let eval = self.mul(power_of_u, eval_batch);
+            eval_multi = self.add(eval_multi, eval);
+            let power_of_u_z = self.mul(power_of_u, z);
+            self.msm_push(&mut witness_with_aux, power_of_u_z, w);
+            self.msm_push(&mut witness, power_of_u, w);
+            power_of_u = self.mul(power_of_u, u);
+        }
+
+        // e(Σ u^i w_i, [s]_2) = e(Σ u^i (z_i w_i + Σ v^j c_ij - Σ v^j e_ij [1]_1),
+        // [1]_2)
+        let mut right = witness_with_aux;
+        for (scalar, point) in commitment_multi.0 {
+            self.msm_push(&mut right, scalar, point);
+        }
+        let minus_eval_multi = self.neg(eval_multi);
+        self.msm_push(&mut right, minus_eval_multi, Point::Constant(params.g1));
+        [self.msm(&witness), self.msm(&right)]
+    }
+}
+
+/// Returns the Yul code of the runtime of the verifier contract of the root
+/// circuit with the verifier parameters `params` and the verifying key `vk`.
+pub fn root_verifier_runtime_yul(
+    params: &ParamsVerifier<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+) -> String {
+    let instance_size = 0x20 * instance_len();
+    let mut generator = Generator::new(instance_size);
+    let [left, right] = generator.verify_proof(params, vk);
+    let accumulator = |cell: usize| format!("coordinate({}, q)", cell);
+
+    format!(
+        r#"{{
+            let r := {scalar_modulus}
+            let q := {base_modulus}
+            // Transcript buffer
+            let t := {transcript:#x}
+
+            // Returns the coordinate made of the limbs starting at the instance
+            // cell `cell`, least significant first.
+            function coordinate(cell, q) -> c {{
+                for {{ let i := {n_coordinate_limbs} }} gt(i, 0) {{ i := sub(i, 1) }} {{
+                    let limb := calldataload(mul(add(cell, sub(i, 1)), 0x20))
+                    if or(shr({limb_bits}, limb), shr(sub(256, {limb_bits}), c)) {{
+                        revert(0, 0)
+                    }}
+                    c := or(shl({limb_bits}, c), limb)
+                }}
+                if iszero(lt(c, q)) {{
+                    revert(0, 0)
+                }}
+            }}
+
+            // Copies the point at `cd` in the call data to the transcript at
+            // `tp` and to the memory at `ptr`, checking that it's the identity
+            // (0, 0) or a point of the curve.
+            function read_point(cd, tp, ptr) {{
+                let x := calldataload(cd)
+                let y := calldataload(add(cd, 0x20))
+                if iszero(and(lt(x, {base_modulus}), lt(y, {base_modulus}))) {{
+                    revert(0, 0)
+                }}
+                if or(x, y) {{
+                    // y^2 = x^3 + 3
+                    let y2 := mulmod(y, y, {base_modulus})
+                    let x3 := mulmod(x, mulmod(x, x, {base_modulus}), {base_modulus})
+                    if iszero(eq(y2, addmod(x3, 3, {base_modulus}))) {{
+                        revert(0, 0)
+                    }}
+                }}
+                mstore(tp, x)
+                mstore(add(tp, 0x20), y)
+                mstore(ptr, x)
+                mstore(add(ptr, 0x20), y)
+            }}
+
+            // Copies the scalar at `cd` in the call data to the transcript at
+            // `tp` and to the memory at `ptr`, checking that it's canonical.
+            function read_scalar(cd, tp, ptr) {{
+                let s := calldataload(cd)
+                if iszero(lt(s, {scalar_modulus})) {{
+                    revert(0, 0)
+                }}
+                mstore(tp, s)
+                mstore(ptr, s)
+            }}
+
+            // Stores at `ptr` the challenge of the `len` bytes of the transcript
+            // at `t`, whose hash becomes the first word of the transcript.
+            function squeeze(t, len, ptr) {{
+                let h := keccak256(t, len)
+                mstore(t, h)
+                mstore(ptr, mod(h, {scalar_modulus}))
+            }}
+
+            // Returns the inverse of `a` modulo r, with the modexp precompile.
+            function invert(a) -> inv {{
+                mstore(0x00, 0x20)
+                mstore(0x20, 0x20)
+                mstore(0x40, 0x20)
+                mstore(0x60, a)
+                mstore(0x80, {scalar_modulus_minus_two})
+                mstore(0xa0, {scalar_modulus})
+                if iszero(staticcall(gas(), 0x05, 0x00, 0xc0, 0x00, 0x20)) {{
+                    revert(0, 0)
+                }}
+                inv := mload(0x00)
+            }}
+
+            // Adds (x, y) to the point at `acc` in memory.
+            function ec_add_acc(acc, x, y) {{
+                mstore(0x00, mload(acc))
+                mstore(0x20, mload(add(acc, 0x20)))
+                mstore(0x40, x)
+                mstore(0x60, y)
+                if iszero(staticcall(gas(), 0x06, 0x00, 0x80, acc, 0x40)) {{
+                    revert(0, 0)
+                }}
+            }}
+
+            // Adds s * (x, y) to the point at `acc` in memory.
+            function ec_mul_acc(acc, x, y, s) {{
+                mstore(0x00, x)
+                mstore(0x20, y)
+                mstore(0x40, s)
+                if iszero(staticcall(gas(), 0x07, 0x00, 0x60, 0x00, 0x40)) {{
+                    revert(0, 0)
+                }}
+                mstore(0x40, mload(acc))
+                mstore(0x60, mload(add(acc, 0x20)))
+                if iszero(staticcall(gas(), 0x06, 0x00, 0x80, acc, 0x40)) {{
+                    revert(0, 0)
+                }}
+            }}
+
+            if iszero(eq(calldatasize(), {calldata_size})) {{
+                revert(0, 0)
+            }}
+            for {{ let i := 0 }} lt(i, {instance_size}) {{ i := add(i, 0x20) }} {{
+                if iszero(lt(calldataload(i), r)) {{
+                    revert(0, 0)
+                }}
+            }}
+            if iszero(eq(calldataload({version_offset}), {version})) {{
+                revert(0, 0)
+            }}
+
+            // The root proof
+{proof}
+            // e(left, [s]_2) * e(-right, [1]_2) = 1
+{proof_pairing}
+            // The identity, encoded as (0, 0), passes the pairing check of the
+            // accumulator as both lhs and rhs
+            let lhs_x := {lhs_x}
+            let lhs_y := {lhs_y}
+            let rhs_x := {rhs_x}
+            let rhs_y := {rhs_y}
+            if or(iszero(or(lhs_x, lhs_y)), iszero(or(rhs_x, rhs_y))) {{
+                revert(0, 0)
+            }}
+
+            // e(lhs, [1]_2) * e(-rhs, [s]_2) = 1
+{accumulator_pairing}
+            mstore(0x00, 1)
+            return(0x00, 0x20)
+        }}"#,
+        scalar_modulus = SCALAR_MODULUS,
+        scalar_modulus_minus_two = fr_literal(&-Fr::from(2)),
+        base_modulus = BASE_MODULUS,
+        transcript = generator.memory,
+        n_coordinate_limbs = N_COORDINATE_LIMBS,
+        limb_bits = ACCUMULATOR_LIMB_BITS,
+        calldata_size = generator.calldata_offset,
+        instance_size = instance_size,
+        version_offset = 0x20 * instance_offset(InstanceField::Version),
+        version = INSTANCE_LAYOUT_VERSION,
+        proof = generator.code,
+        proof_pairing = pairing_check(left.exprs(), &params.s_g2, right.exprs(), &params.g2),
+        lhs_x = accumulator(instance_offset(InstanceField::Accumulator)),
+        lhs_y = accumulator(instance_offset(InstanceField::Accumulator) + N_COORDINATE_LIMBS),
+        rhs_x = accumulator(instance_offset(InstanceField::Accumulator) + 2 * N_COORDINATE_LIMBS),
+        rhs_y = accumulator(instance_offset(InstanceField::Accumulator) + 3 * N_COORDINATE_LIMBS),
+        accumulator_pairing = pairing_check(
+            [String::from("lhs_x"), String::from("lhs_y")],
+            &params.g2,
+            [String::from("rhs_x"), String::from("rhs_y")],
+            &params.s_g2,
+        ),
+    )
+}
+
+/// Returns the Yul object of the verifier contract of the root circuit with
+/// the verifier parameters `params` and the verifying key `vk`, whose code
+/// deploys its runtime.
+pub fn root_verifier_yul(params: &ParamsVerifier<Bn256>, vk: &VerifyingKey<G1Affine>) -> String {
+    format!(
+        r#"object "RootVerifier" {{
+    code {{
+        datacopy(0, dataoffset("runtime"), datasize("runtime"))
+        return(0, datasize("runtime"))
+    }}
+    object "runtime" {{
+        code {}
+    }}
+}}
+"#,
+        root_verifier_runtime_yul(params, vk)
+    )
+}
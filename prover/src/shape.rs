@@ -0,0 +1,118 @@
+//! Shape of the circuits: their number of columns of every kind, of gates and
+//! of lookups, their degree and their minimum `k`, which are read from their
+//! `ConstraintSystem` so that the cost of a layout change can be reviewed.
+//!
+//! The shapes are reported as JSON, to be compared across commits, and as a
+//! markdown table, to be pasted in a review.
+
+use halo2_proofs::plonk::{Circuit, ConstraintSystem};
+use pairing::bn256::Fr;
+use serde::{Deserialize, Serialize};
+
+/// Shape of the `ConstraintSystem` of a circuit.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitShape {
+    /// Name of the circuit
+    pub circuit: String,
+    /// Number of advice columns
+    pub advice_columns: usize,
+    /// Number of fixed columns, without the ones the selectors are compressed
+    /// into
+    pub fixed_columns: usize,
+    /// Number of instance columns
+    pub instance_columns: usize,
+    /// Number of selectors
+    pub selectors: usize,
+    /// Number of gates
+    pub gates: usize,
+    /// Number of constraints of all the gates
+    pub constraints: usize,
+    /// Highest degree of the constraints of the gates
+    pub max_gate_degree: usize,
+    /// Number of lookups
+    pub lookups: usize,
+    /// Degree of the circuit, including the lookup and permutation arguments
+    pub degree: usize,
+    /// Smallest `k` whose `2^k` rows fit the rows needed by the circuit and
+    /// its blinding factors
+    pub min_k: u32,
+}
+
+impl CircuitShape {
+    /// Returns the shape of the circuit `C`, which is named `circuit` and
+    /// needs `rows` usable rows, like the ones of its fixed tables.
+    pub fn of<C: Circuit<Fr>>(circuit: &str, rows: usize) -> Self {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        C::configure(&mut cs);
+
+        let polynomials = cs.gates().iter().flat_map(|gate| gate.polynomials());
+        let n_rows = (rows + cs.blinding_factors() + 1).max(cs.minimum_rows());
+        Self {
+            circuit: circuit.to_string(),
+            advice_columns: cs.num_advice_columns(),
+            fixed_columns: cs.num_fixed_columns(),
+            instance_columns: cs.num_instance_columns(),
+            selectors: cs.num_selectors(),
+            gates: cs.gates().len(),
+            constraints: polynomials.clone().count(),
+            max_gate_degree: polynomials
+                .map(|poly| poly.degree())
+                .max()
+                .unwrap_or_default(),
+            lookups: cs.lookups().len(),
+            degree: cs.degree(),
+            min_k: usize::BITS - (n_rows - 1).leading_zeros(),
+        }
+    }
+}
+
+/// Returns the markdown table of `shapes`, with a row per circuit.
+pub fn markdown_table(shapes: &[CircuitShape]) -> String {
+    let mut table = String::from(
+        "| circuit | advice | fixed | instance | selectors | gates | constraints \
+         | max gate degree | lookups | degree | min k |\n\
+         |---|---:|---:|---:|---:|---:|---:|---:|---:|---:|---:|\n",
+    );
+    for shape in shapes {
+        table += &format!(
+            "| {} | {} | {} | {} | {} | {} | {} | {} | {} | {} | {} |\n",
+            shape.circuit,
+            shape.advice_columns,
+            shape.fixed_columns,
+            shape.instance_columns,
+            shape.selectors,
+            shape.gates,
+            shape.constraints,
+            shape.max_gate_degree,
+            shape.lookups,
+            shape.degree,
+            shape.min_k
+        );
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{markdown_table, CircuitShape};
+    use pairing::bn256::Fr;
+    use zkevm_circuits::bytecode_circuit::BytecodeCircuit;
+
+    #[test]
+    fn bytecode_circuit_shape() {
+        let shape = CircuitShape::of::<BytecodeCircuit<Fr>>("bytecode_circuit", 1000);
+        assert_eq!(shape.instance_columns, 1);
+        assert!(shape.advice_columns > 0 && shape.lookups > 0);
+        assert!(shape.max_gate_degree <= shape.degree);
+        // 1000 rows and the blinding factors fit in 2^10 rows.
+        assert_eq!(shape.min_k, 10);
+
+        let table = markdown_table(&[shape.clone()]);
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].starts_with(&format!(
+            "| bytecode_circuit | {} | {} | 1 |",
+            shape.advice_columns, shape.fixed_columns
+        )));
+    }
+}
@@ -0,0 +1,294 @@
+//! Download and cache of the SRS parameters of a trusted setup ceremony.
+//!
+//! A ceremony is described by a `SrsManifest`: the URL of its parameters of
+//! every degree `k`, serialized like halo2 `Params`, and the keccak hash of
+//! each file, which pins the parameters to the ones of the ceremony
+//! whichever server they're downloaded from.  The downloaded files are kept in
+//! a `SrsCache` directory, named after the ceremony and `k`, and their hash is
+//! checked again every time they are loaded, so that a partial download or a
+//! file of another ceremony is never used: it is downloaded again instead.
+
+use eth_types::{keccak256, H256};
+use halo2_proofs::poly::commitment::Params;
+use pairing::bn256::G1Affine;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Where the parameters of a ceremony are downloaded from, and their hashes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SrsManifest {
+    /// Name of the ceremony, like `perpetual-powers-of-tau`, which names the
+    /// cached files
+    pub ceremony: String,
+    /// URL of the parameters of every degree, with `{k}` standing for the
+    /// degree
+    pub url: String,
+    /// Keccak hash of the parameters file of every available degree
+    pub hashes: BTreeMap<u32, H256>,
+}
+
+impl SrsManifest {
+    /// Reads the JSON manifest at `path`.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SrsError> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Returns the URL of the parameters of degree `k`.
+    pub fn url(&self, k: u32) -> String {
+        self.url.replace("{k}", &k.to_string())
+    }
+
+    /// Returns the hash of the parameters of degree `k`.
+    pub fn hash(&self, k: u32) -> Result<H256, SrsError> {
+        self.hashes
+            .get(&k)
+            .copied()
+            .ok_or_else(|| SrsError::UnknownDegree {
+                ceremony: self.ceremony.clone(),
+                k,
+            })
+    }
+}
+
+/// Error when downloading or loading SRS parameters.
+#[derive(Debug)]
+pub enum SrsError {
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// The manifest isn't valid JSON.
+    Manifest(serde_json::Error),
+    /// The download failed.
+    Download(reqwest::Error),
+    /// The ceremony has no parameters of degree `k`.
+    UnknownDegree {
+        /// Name of the ceremony
+        ceremony: String,
+        /// Degree of the parameters
+        k: u32,
+    },
+    /// The parameters don't have the hash of the manifest.
+    HashMismatch {
+        /// Hash of the manifest
+        expected: H256,
+        /// Hash of the parameters
+        actual: H256,
+    },
+    /// The parameters aren't halo2 parameters of the expected degree.
+    InvalidParams(&'static str),
+}
+
+impl fmt::Display for SrsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "srs io error: {}", err),
+            Self::Manifest(err) => write!(f, "invalid srs manifest: {}", err),
+            Self::Download(err) => write!(f, "srs download failed: {}", err),
+            Self::UnknownDegree { ceremony, k } => {
+                write!(f, "no srs of degree {} in the ceremony {}", k, ceremony)
+            }
+            Self::HashMismatch { expected, actual } => write!(
+                f,
+                "srs hash mismatch: expected {:?}, got {:?}",
+                expected, actual
+            ),
+            Self::InvalidParams(reason) => write!(f, "invalid srs params: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SrsError {}
+
+impl From<io::Error> for SrsError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SrsError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Manifest(err)
+    }
+}
+
+impl From<reqwest::Error> for SrsError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Download(err)
+    }
+}
+
+/// Returns the halo2 parameters of degree `k` serialized in `bytes`, after
+/// checking that they have the keccak hash `expected`.
+pub fn verify_params(bytes: &[u8], k: u32, expected: H256) -> Result<Params<G1Affine>, SrsError> {
+    let actual = H256(keccak256(bytes));
+    if actual != expected {
+        return Err(SrsError::HashMismatch { expected, actual });
+    }
+    // The serialization of the parameters starts with their degree.
+    match bytes.get(..4) {
+        Some(degree) if degree == k.to_le_bytes() => {}
+        _ => return Err(SrsError::InvalidParams("unexpected degree")),
+    }
+    Params::read(&mut &bytes[..]).map_err(|_| SrsError::InvalidParams("malformed params"))
+}
+
+/// Directory where the downloaded SRS parameters are cached.
+#[derive(Clone, Debug)]
+pub struct SrsCache {
+    dir: PathBuf,
+}
+
+impl SrsCache {
+    /// Returns the cache stored in `dir`, which is created on the first
+    /// download.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Path of the parameters of degree `k` of the ceremony of `manifest`.
+    pub fn path(&self, manifest: &SrsManifest, k: u32) -> PathBuf {
+        self.dir
+            .join(format!("{}-k{}.params", manifest.ceremony, k))
+    }
+
+    /// Loads the cached parameters of degree `k` of the ceremony of
+    /// `manifest`, or returns `None` if they are not cached or don't match
+    /// the manifest.
+    pub fn load(
+        &self,
+        manifest: &SrsManifest,
+        k: u32,
+    ) -> Result<Option<Params<G1Affine>>, SrsError> {
+        let expected = manifest.hash(k)?;
+        let path = self.path(manifest, k);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        match verify_params(&bytes, k, expected) {
+            Ok(params) => Ok(Some(params)),
+            Err(err @ (SrsError::HashMismatch { .. } | SrsError::InvalidParams(_))) => {
+                log::warn!("{}: {}, downloading it again", path.display(), err);
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Loads the cached parameters of degree `k` of the ceremony of
+    /// `manifest`, or downloads, verifies and caches them.
+    #[tracing::instrument(skip(self, manifest))]
+    pub async fn load_or_download(
+        &self,
+        manifest: &SrsManifest,
+        k: u32,
+    ) -> Result<Params<G1Affine>, SrsError> {
+        if let Some(params) = self.load(manifest, k)? {
+            return Ok(params);
+        }
+        let url = manifest.url(k);
+        log::info!("downloading srs of degree {} from {}", k, url);
+        let bytes = reqwest::get(&url)
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let params = verify_params(&bytes, k, manifest.hash(k)?)?;
+        self.store(&self.path(manifest, k), &bytes)?;
+        Ok(params)
+    }
+
+    // Writes `bytes` to `path` through a temporary file, so that an
+    // interrupted write doesn't leave a truncated file behind.
+    fn store(&self, path: &Path, bytes: &[u8]) -> Result<(), SrsError> {
+        fs::create_dir_all(&self.dir)?;
+        let tmp_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        log::info!("stored {}", path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_params, SrsCache, SrsError, SrsManifest};
+    use eth_types::{keccak256, H256};
+    use halo2_proofs::poly::commitment::Params;
+    use pairing::bn256::{Bn256, G1Affine};
+    use std::{collections::BTreeMap, fs};
+
+    const K: u32 = 4;
+
+    fn params_bytes(k: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        Params::<G1Affine>::unsafe_setup::<Bn256>(k)
+            .write(&mut bytes)
+            .unwrap();
+        bytes
+    }
+
+    fn manifest(hash: H256) -> SrsManifest {
+        SrsManifest {
+            ceremony: "test".to_string(),
+            url: "http://localhost/test-{k}.params".to_string(),
+            hashes: BTreeMap::from([(K, hash)]),
+        }
+    }
+
+    #[test]
+    fn verify_params_checks_hash_and_degree() {
+        let bytes = params_bytes(K);
+        let hash = H256(keccak256(&bytes));
+        assert!(verify_params(&bytes, K, hash).is_ok());
+        assert!(matches!(
+            verify_params(&bytes, K, H256::zero()),
+            Err(SrsError::HashMismatch { .. })
+        ));
+        assert!(matches!(
+            verify_params(&bytes, K + 1, hash),
+            Err(SrsError::InvalidParams(_))
+        ));
+
+        let manifest = manifest(hash);
+        assert_eq!(manifest.url(K), "http://localhost/test-4.params");
+        assert!(matches!(
+            manifest.hash(K + 1),
+            Err(SrsError::UnknownDegree { k, .. }) if k == K + 1
+        ));
+    }
+
+    #[test]
+    fn srs_cache_loads_verified_params() {
+        let dir = std::env::temp_dir().join(format!("prover-srs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let cache = SrsCache::new(&dir);
+        let bytes = params_bytes(K);
+        let manifest = manifest(H256(keccak256(&bytes)));
+        assert!(cache.load(&manifest, K).unwrap().is_none());
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(cache.path(&manifest, K), &bytes).unwrap();
+        let mut loaded = Vec::new();
+        cache
+            .load(&manifest, K)
+            .unwrap()
+            .unwrap()
+            .write(&mut loaded)
+            .unwrap();
+        assert_eq!(loaded, bytes);
+
+        // A file of other parameters is a cache miss.
+        fs::write(cache.path(&manifest, K), params_bytes(K + 1)).unwrap();
+        assert!(cache.load(&manifest, K).unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
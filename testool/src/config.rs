@@ -0,0 +1,145 @@
+//! Configuration of the tests run by the testool.
+
+use regex::Regex;
+use serde::Deserialize;
+use std::{fmt, fs, io, path::Path};
+
+/// Error when reading the configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// The file isn't a TOML configuration.
+    Toml(toml::de::Error),
+    /// A pattern isn't a regex.
+    Pattern(regex::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "config io error: {}", err),
+            Self::Toml(err) => write!(f, "invalid config: {}", err),
+            Self::Pattern(err) => write!(f, "invalid test pattern: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Toml(err)
+    }
+}
+
+impl From<regex::Error> for ConfigError {
+    fn from(err: regex::Error) -> Self {
+        Self::Pattern(err)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TomlConfig {
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<TomlDeny>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TomlDeny {
+    pattern: String,
+    reason: String,
+}
+
+/// Tests to run, as regexes matched against the ids of the tests, which are
+/// `<fixture path>::<name>_d<data>_g<gas>_v<value>`.
+///
+/// ```toml
+/// # Only the tests matching one of these patterns are run, all the tests when
+/// # there is none.
+/// allow = ["stExample/"]
+///
+/// # Known-unsupported tests, which are skipped and reported as such.
+/// [[deny]]
+/// pattern = "stCreate2/"
+/// reason = "CREATE2 isn't supported"
+/// ```
+#[derive(Debug, Default)]
+pub struct Config {
+    allow: Vec<Regex>,
+    deny: Vec<(Regex, String)>,
+}
+
+impl Config {
+    /// Parses the configuration `toml`.
+    pub fn parse(toml: &str) -> Result<Self, ConfigError> {
+        let config: TomlConfig = toml::from_str(toml)?;
+        Ok(Self {
+            allow: config
+                .allow
+                .iter()
+                .map(|pattern| Regex::new(pattern))
+                .collect::<Result<_, _>>()?,
+            deny: config
+                .deny
+                .into_iter()
+                .map(|deny| Ok((Regex::new(&deny.pattern)?, deny.reason)))
+                .collect::<Result<_, regex::Error>>()?,
+        })
+    }
+
+    /// Reads the configuration at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Returns whether the test `id` is run.
+    pub fn is_allowed(&self, id: &str) -> bool {
+        self.allow.is_empty() || self.allow.iter().any(|allow| allow.is_match(id))
+    }
+
+    /// Returns the reason why the test `id` is skipped, if it is denied.
+    pub fn deny_reason(&self, id: &str) -> Option<&str> {
+        self.deny
+            .iter()
+            .find(|(deny, _)| deny.is_match(id))
+            .map(|(_, reason)| reason.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+
+    #[test]
+    fn config_allow_deny() {
+        let config = Config::parse(
+            r#"
+            allow = ["stExample/", "stCreate2/"]
+
+            [[deny]]
+            pattern = "stCreate2/.*_d1_"
+            reason = "unsupported"
+            "#,
+        )
+        .unwrap();
+        assert!(config.is_allowed("stExample/add11.json::add11_d0_g0_v0"));
+        assert!(!config.is_allowed("stRandom/random.json::random_d0_g0_v0"));
+        assert_eq!(config.deny_reason("stCreate2/a.json::a_d0_g0_v0"), None);
+        assert_eq!(
+            config.deny_reason("stCreate2/a.json::a_d1_g0_v0"),
+            Some("unsupported")
+        );
+
+        assert!(Config::default().is_allowed("any"));
+        assert!(Config::parse("allow = [\"(\"]").is_err());
+    }
+}
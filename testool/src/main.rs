@@ -0,0 +1,141 @@
+//! Runner of the GeneralStateTests of the ethereum/tests repository.
+//!
+//! Usage: `testool [--config <path>] [--skip-circuits] <path>...`
+//!
+//! Each path is a fixture filled by retesteth or a directory searched for
+//! them.  The transaction of each test of the London fork is traced from the
+//! pre-state, the circuit inputs are built by bus-mapping, and the state root
+//! and the hash of the logs after the transaction are compared with the
+//! expected ones.  The evm and state circuits are then verified with the
+//! `MockProver`, unless `--skip-circuits` is passed.  The tests to run and the
+//! known-unsupported ones are configured by the TOML file of `--config` (see
+//! `config::Config`), `Config.toml` by default if it exists.
+//!
+//! The command exits with 1 if any test fails.
+
+mod config;
+mod mpt;
+mod runner;
+mod statetest;
+
+use config::Config;
+use env_logger::Env;
+use log::{error, info};
+use runner::{run_state_test, Stage};
+use statetest::load_state_tests;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use walkdir::WalkDir;
+
+const DEFAULT_CONFIG_PATH: &str = "Config.toml";
+
+fn usage() -> ! {
+    eprintln!("Usage: testool [--config <path>] [--skip-circuits] <path>...");
+    exit(2)
+}
+
+// Returns the fixtures at or under `path`, with their path relative to it.
+fn fixtures(path: &Path) -> Vec<(PathBuf, String)> {
+    let mut fixtures: Vec<_> = WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && entry.path().extension().map_or(false, |ext| ext == "json")
+        })
+        .map(|entry| {
+            let name = match entry.path().strip_prefix(path) {
+                Ok(relative) if !relative.as_os_str().is_empty() => relative,
+                _ => entry.path(),
+            };
+            let name = name.display().to_string();
+            (entry.into_path(), name)
+        })
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+fn main() {
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+
+    let mut config_path = None;
+    let mut circuits = true;
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => config_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--skip-circuits" => circuits = false,
+            "-h" | "--help" => usage(),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        usage();
+    }
+
+    let config = match config_path {
+        Some(path) => Config::load(path),
+        None if Path::new(DEFAULT_CONFIG_PATH).exists() => Config::load(DEFAULT_CONFIG_PATH),
+        None => Ok(Config::default()),
+    }
+    .unwrap_or_else(|err| {
+        error!("{}", err);
+        exit(2)
+    });
+
+    let mut passed = 0;
+    let mut skipped = 0;
+    let mut invalid = 0;
+    let mut failed: BTreeMap<Stage, Vec<String>> = BTreeMap::new();
+    for path in paths {
+        for (fixture_path, fixture_name) in fixtures(&path) {
+            let tests = match load_state_tests(&fixture_path) {
+                Ok(tests) => tests,
+                Err(err) => {
+                    error!("{}: {}", fixture_name, err);
+                    invalid += 1;
+                    continue;
+                }
+            };
+            for test in tests {
+                let id = format!("{}::{}", fixture_name, test.id);
+                if !config.is_allowed(&id) {
+                    continue;
+                }
+                if let Some(reason) = config.deny_reason(&id) {
+                    info!("{}: skipped, {}", id, reason);
+                    skipped += 1;
+                    continue;
+                }
+                match run_state_test(&test, circuits) {
+                    Ok(()) => {
+                        info!("{}: passed", id);
+                        passed += 1;
+                    }
+                    Err(failure) => {
+                        error!("{}: {:?} failed, {}", id, failure.stage, failure.reason);
+                        failed.entry(failure.stage).or_default().push(id);
+                    }
+                }
+            }
+        }
+    }
+
+    let n_failed: usize = failed.values().map(Vec::len).sum();
+    println!(
+        "passed: {}, failed: {}, skipped: {}, invalid fixtures: {}",
+        passed, n_failed, skipped, invalid
+    );
+    for (stage, ids) in failed.iter() {
+        println!("failed at {:?}: {}", stage, ids.len());
+        for id in ids {
+            println!("    {}", id);
+        }
+    }
+    if n_failed > 0 || invalid > 0 {
+        exit(1);
+    }
+}
@@ -0,0 +1,192 @@
+//! Roots of Merkle Patricia Tries, to compare the post-state of the tests with
+//! the state root of the fixtures.
+
+use eth_types::{keccak256, Address, Hash, Word, H256};
+use ethers_core::utils::rlp;
+use std::collections::HashMap;
+
+/// Returns the nibbles of `bytes`, most significant first.
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0xf])
+        .collect()
+}
+
+/// Returns the hex prefix encoding of the path `nibbles` of a leaf or an
+/// extension node.
+fn hex_prefix(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 } + nibbles.len() as u8 % 2;
+    let mut encoded = vec![flag << 4];
+    let mut rest = nibbles;
+    if nibbles.len() % 2 == 1 {
+        encoded[0] |= nibbles[0];
+        rest = &nibbles[1..];
+    }
+    encoded.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+    encoded
+}
+
+/// Appends the reference to the node `node` to `stream`: the node itself if
+/// its encoding is shorter than a hash, its hash otherwise.
+fn append_node_ref(stream: &mut rlp::RlpStream, node: &[u8]) {
+    if node.len() < 32 {
+        stream.append_raw(node, 1);
+    } else {
+        stream.append(&keccak256(node).to_vec());
+    }
+}
+
+/// Returns the RLP encoding of the node of the items `items`, sorted by key,
+/// whose keys share their first `depth` nibbles.
+fn encode_node(items: &[(Vec<u8>, &[u8])], depth: usize) -> Vec<u8> {
+    let mut stream = rlp::RlpStream::new();
+    match items {
+        [] => {
+            stream.append_empty_data();
+        }
+        [(key, value)] => {
+            stream.begin_list(2);
+            stream.append(&hex_prefix(&key[depth..], true));
+            stream.append(&value.to_vec());
+        }
+        _ => {
+            let (first, last) = (&items[0].0, &items[items.len() - 1].0);
+            let prefix_len = first[depth..]
+                .iter()
+                .zip(last[depth..].iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            if prefix_len > 0 {
+                stream.begin_list(2);
+                stream.append(&hex_prefix(&first[depth..depth + prefix_len], false));
+                append_node_ref(&mut stream, &encode_node(items, depth + prefix_len));
+            } else {
+                stream.begin_list(17);
+                // A key which ends at the branch is the first one, as the items
+                // are sorted.
+                let (value, items) = if items[0].0.len() == depth {
+                    (Some(items[0].1), &items[1..])
+                } else {
+                    (None, items)
+                };
+                for nibble in 0..16 {
+                    let start = items.partition_point(|(key, _)| key[depth] < nibble);
+                    let end = items.partition_point(|(key, _)| key[depth] <= nibble);
+                    if start == end {
+                        stream.append_empty_data();
+                    } else {
+                        append_node_ref(&mut stream, &encode_node(&items[start..end], depth + 1));
+                    }
+                }
+                match value {
+                    Some(value) => stream.append(&value.to_vec()),
+                    None => stream.append_empty_data(),
+                };
+            }
+        }
+    }
+    stream.out().to_vec()
+}
+
+/// Returns the root of the trie of the key-value pairs `items`.
+pub fn trie_root<K: AsRef<[u8]>, V: AsRef<[u8]>>(items: &[(K, V)]) -> Hash {
+    let mut items: Vec<_> = items
+        .iter()
+        .map(|(key, value)| (nibbles(key.as_ref()), value.as_ref()))
+        .collect();
+    items.sort();
+    H256(keccak256(encode_node(&items, 0)))
+}
+
+/// Returns the root of the secure trie of the key-value pairs `items`, whose
+/// keys are hashed.
+pub fn secure_trie_root<K: AsRef<[u8]>, V: AsRef<[u8]>>(items: &[(K, V)]) -> Hash {
+    let items: Vec<_> = items
+        .iter()
+        .map(|(key, value)| (keccak256(key.as_ref()), value.as_ref()))
+        .collect();
+    trie_root(&items)
+}
+
+/// Account of the state trie.
+#[derive(Debug, Clone, Default)]
+pub struct TrieAccount {
+    /// Nonce
+    pub nonce: Word,
+    /// Balance
+    pub balance: Word,
+    /// Non-zero storage slots
+    pub storage: HashMap<Word, Word>,
+    /// Hash of the code
+    pub code_hash: Hash,
+}
+
+/// Returns the root of the state trie of the accounts `accounts`.
+pub fn state_root(accounts: &HashMap<Address, TrieAccount>) -> Hash {
+    let items: Vec<_> = accounts
+        .iter()
+        .map(|(address, account)| {
+            let storage: Vec<_> = account
+                .storage
+                .iter()
+                .filter(|(_, value)| !value.is_zero())
+                .map(|(key, value)| {
+                    let mut slot = [0u8; 32];
+                    key.to_big_endian(&mut slot);
+                    (slot, rlp::encode(value).to_vec())
+                })
+                .collect();
+            let mut stream = rlp::RlpStream::new_list(4);
+            stream
+                .append(&account.nonce)
+                .append(&account.balance)
+                .append(&secure_trie_root(&storage))
+                .append(&account.code_hash);
+            (address.as_bytes().to_vec(), stream.out().to_vec())
+        })
+        .collect();
+    secure_trie_root(&items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{state_root, trie_root};
+    use eth_types::H256;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn mpt_trie_roots() {
+        let empty: [(&[u8], &[u8]); 0] = [];
+        assert_eq!(
+            trie_root(&empty),
+            H256::from_str("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421")
+                .unwrap()
+        );
+        assert_eq!(state_root(&HashMap::new()), trie_root(&empty));
+
+        let puppy = [
+            ("do", "verb"),
+            ("horse", "stallion"),
+            ("doge", "coin"),
+            ("dog", "puppy"),
+        ];
+        assert_eq!(
+            trie_root(&puppy),
+            H256::from_str("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84")
+                .unwrap()
+        );
+
+        let dogs = [
+            ("doe", "reindeer"),
+            ("dog", "puppy"),
+            ("dogglesworth", "cat"),
+        ];
+        assert_eq!(
+            trie_root(&dogs),
+            H256::from_str("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,332 @@
+//! Execution of a state test through the tracer, bus-mapping and the circuits.
+
+use crate::mpt::{state_root, TrieAccount};
+use crate::statetest::StateTest;
+use bus_mapping::mock::BlockData;
+use eth_types::{
+    evm_types::OpcodeId,
+    geth_types::{BlockConstants, GethData, Transaction},
+    keccak256, Address, ChainConfig, GethExecTrace, ToAddress, Word, H256, U64,
+};
+use ethers_core::utils::{get_contract_address, rlp};
+use external_tracer::{trace, TraceConfig};
+use halo2_proofs::dev::MockProver;
+use pairing::bn256::Fr;
+use std::collections::HashMap;
+use zkevm_circuits::evm_circuit::{
+    test::run_test_circuit_complete_fixed_table, witness::block_convert,
+};
+use zkevm_circuits::state_circuit::{StateCircuit, DEFAULT_MAX_DEGREE};
+
+/// Chain id of the state tests.
+const CHAIN_ID: u64 = 1;
+
+/// Stage of a state test which failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    /// Tracing of the transaction, or its validity
+    Trace,
+    /// Building of the circuit inputs by bus-mapping
+    BusMapping,
+    /// Comparison of the state root after the transaction
+    PostState,
+    /// Comparison of the hash of the logs of the transaction
+    Logs,
+    /// Verification of the evm circuit
+    EvmCircuit,
+    /// Verification of the state circuit
+    StateCircuit,
+}
+
+/// Failure of a state test.
+#[derive(Debug, Clone)]
+pub struct Failure {
+    /// Stage which failed
+    pub stage: Stage,
+    /// Description of the failure
+    pub reason: String,
+}
+
+impl Failure {
+    fn new(stage: Stage, reason: impl Into<String>) -> Self {
+        Self {
+            stage,
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Log of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Log {
+    address: Address,
+    topics: Vec<H256>,
+    data: Vec<u8>,
+}
+
+/// Returns the logs of the transaction of `trace`, which are emitted by the
+/// steps of the calls which don't revert.  `address` is the address of the
+/// contract of the transaction.
+fn trace_logs(address: Address, trace: &GethExecTrace) -> Vec<Log> {
+    // The address of the contract and the logs of each open call frame.  The
+    // address of a contract being created is only known when it returns.
+    let mut frames: Vec<(Option<Address>, Vec<Log>)> = vec![(Some(address), Vec::new())];
+    let steps = &trace.struct_logs;
+    for (idx, step) in steps.iter().enumerate() {
+        let next = steps.get(idx + 1);
+        let op = step.op.as_u8();
+        if step.error.is_none() && (OpcodeId::LOG0.as_u8()..=OpcodeId::LOG4.as_u8()).contains(&op) {
+            let offset = step.stack.nth_last(0).unwrap_or_default();
+            let size = step.stack.nth_last(1).unwrap_or_default().as_usize();
+            let topics = (0..(op - OpcodeId::LOG0.as_u8()) as usize)
+                .map(|nth| {
+                    let mut topic = [0u8; 32];
+                    step.stack
+                        .nth_last(2 + nth)
+                        .unwrap_or_default()
+                        .to_big_endian(&mut topic);
+                    H256(topic)
+                })
+                .collect();
+            // The offset is unbounded when nothing is read.
+            let data = match size {
+                0 => Vec::new(),
+                _ => {
+                    let offset = offset.as_usize();
+                    (offset..offset + size)
+                        .map(|addr| step.memory.0.get(addr).copied().unwrap_or_default())
+                        .collect()
+                }
+            };
+            let frame = frames.last_mut().expect("call frame");
+            frame.1.push(Log {
+                address: frame.0.unwrap_or_default(),
+                topics,
+                data,
+            });
+        }
+
+        let depth = next.map(|next| next.depth);
+        if depth == Some(step.depth + 1) {
+            let current = frames.last().and_then(|frame| frame.0);
+            let callee = match step.op {
+                OpcodeId::CALL | OpcodeId::STATICCALL => {
+                    Some(step.stack.nth_last(1).unwrap_or_default().to_address())
+                }
+                OpcodeId::CALLCODE | OpcodeId::DELEGATECALL => current,
+                _ => None,
+            };
+            frames.push((callee, Vec::new()));
+        } else if depth.map_or(false, |depth| depth < step.depth) {
+            // The step after a call has the result of the call on the top of
+            // its stack: 0 on failure, the address of the contract for a
+            // successful creation.
+            let result = next
+                .and_then(|next| next.stack.last().ok())
+                .unwrap_or_default();
+            let (address, logs) = frames.pop().expect("call frame");
+            if !result.is_zero() {
+                let address = address.unwrap_or_else(|| result.to_address());
+                frames
+                    .last_mut()
+                    .expect("call frame")
+                    .1
+                    .extend(logs.into_iter().map(|log| Log {
+                        address: if log.address.is_zero() {
+                            address
+                        } else {
+                            log.address
+                        },
+                        ..log
+                    }));
+            }
+        }
+    }
+    if trace.failed {
+        Vec::new()
+    } else {
+        frames.swap_remove(0).1
+    }
+}
+
+/// Returns the hash of the RLP encoding of the logs `logs`.
+fn logs_hash(logs: &[Log]) -> H256 {
+    let mut stream = rlp::RlpStream::new_list(logs.len());
+    for log in logs {
+        stream.begin_list(3);
+        stream.append(&log.address);
+        stream.append_list::<H256, H256>(&log.topics);
+        stream.append(&log.data);
+    }
+    H256(keccak256(stream.out()))
+}
+
+/// Runs the state test `test`: traces its transaction, builds the circuit
+/// inputs with bus-mapping, compares the state root and the logs after the
+/// transaction with the expected ones, and verifies the evm and state circuits
+/// with the `MockProver` if `circuits` is set.
+pub fn run_state_test(test: &StateTest, circuits: bool) -> Result<(), Failure> {
+    let mut eth_block = mock::new_block();
+    eth_block.author = test.env.coinbase;
+    eth_block.number = Some(test.env.number);
+    eth_block.gas_limit = test.env.gas_limit;
+    eth_block.timestamp = test.env.timestamp;
+    eth_block.difficulty = test.env.difficulty;
+    eth_block.base_fee_per_gas = Some(test.env.base_fee);
+
+    let mut eth_tx = mock::new_tx(&eth_block);
+    eth_tx.from = test.tx.from;
+    eth_tx.to = test.tx.to;
+    eth_tx.nonce = test.tx.nonce;
+    eth_tx.gas = test.tx.gas_limit;
+    eth_tx.value = test.tx.value;
+    eth_tx.input = test.tx.data.clone();
+    eth_tx.chain_id = Some(Word::from(CHAIN_ID));
+    match (test.tx.max_fee_per_gas, test.tx.max_priority_fee_per_gas) {
+        (Some(max_fee), Some(max_priority_fee)) => {
+            eth_tx.transaction_type = Some(U64::from(2));
+            eth_tx.max_fee_per_gas = Some(max_fee);
+            eth_tx.max_priority_fee_per_gas = Some(max_priority_fee);
+            eth_tx.gas_price = Some(max_fee.min(test.env.base_fee + max_priority_fee));
+        }
+        _ => {
+            eth_tx.transaction_type = Some(U64::zero());
+            eth_tx.gas_price = test.tx.gas_price;
+            eth_tx.max_fee_per_gas = test.tx.gas_price;
+            eth_tx.max_priority_fee_per_gas = test.tx.gas_price;
+        }
+    }
+    eth_block.transactions = vec![eth_tx];
+
+    let trace_config = TraceConfig {
+        history_hashes: Vec::new(),
+        block_constants: BlockConstants::try_from(&eth_block)
+            .map_err(|err| Failure::new(Stage::Trace, format!("{:?}", err)))?,
+        accounts: test
+            .pre
+            .iter()
+            .map(|account| (account.address, account.clone()))
+            .collect(),
+        transactions: eth_block
+            .transactions
+            .iter()
+            .map(Transaction::from_eth_tx)
+            .collect(),
+        ..Default::default()
+    }
+    .with_chain_config(&ChainConfig::new(Word::from(CHAIN_ID)));
+    let geth_traces = match (trace(&trace_config), &test.expect_exception) {
+        (Err(_), Some(_)) => return Ok(()),
+        (Err(err), None) => return Err(Failure::new(Stage::Trace, format!("{:?}", err))),
+        (Ok(_), Some(exception)) => {
+            return Err(Failure::new(
+                Stage::Trace,
+                format!("expected exception {} didn't happen", exception),
+            ))
+        }
+        (Ok(geth_traces), None) => geth_traces,
+    };
+
+    let address = test
+        .tx
+        .to
+        .unwrap_or_else(|| get_contract_address(test.tx.from, test.tx.nonce));
+    let logs = trace_logs(address, &geth_traces[0]);
+
+    let block_data = BlockData::new_from_geth_data(GethData {
+        chain_id: trace_config.chain_id,
+        history_hashes: trace_config.history_hashes,
+        eth_block,
+        geth_traces,
+        accounts: test.pre.clone(),
+    });
+    let mut builder = block_data.new_circuit_input_builder();
+    builder
+        .handle_block(&block_data.eth_block, &block_data.geth_traces)
+        .map_err(|err| Failure::new(Stage::BusMapping, format!("{:?}", err)))?;
+
+    // Touched empty accounts are deleted since Spurious Dragon.
+    let accounts: HashMap<_, _> = builder
+        .sdb
+        .iter()
+        .filter(|(_, account)| !account.is_empty())
+        .map(|(address, account)| {
+            let account = TrieAccount {
+                nonce: account.nonce,
+                balance: account.balance,
+                storage: account.storage.clone().into_iter().collect(),
+                code_hash: account.code_hash,
+            };
+            (*address, account)
+        })
+        .collect();
+    let root = state_root(&accounts);
+    if root != test.post_state_root {
+        return Err(Failure::new(
+            Stage::PostState,
+            format!("state root {:?}, expected {:?}", root, test.post_state_root),
+        ));
+    }
+    let hash = logs_hash(&logs);
+    if hash != test.logs_hash {
+        return Err(Failure::new(
+            Stage::Logs,
+            format!("logs hash {:?}, expected {:?}", hash, test.logs_hash),
+        ));
+    }
+
+    if circuits {
+        let block = block_convert(&builder.block, &builder.code_db);
+        run_test_circuit_complete_fixed_table(block.clone())
+            .map_err(|errs| Failure::new(Stage::EvmCircuit, format!("{:?}", errs)))?;
+
+        const DEGREE: usize = 16;
+        const MEMORY_ADDRESS_MAX: usize = 2000;
+        const STACK_ADDRESS_MAX: usize = 1024;
+        const RW_COUNTER_MAX: usize = 1 << DEGREE;
+        // All the ROWS_MAX rows are enabled, leave some rows for the blinding
+        // factors.
+        const ROWS_MAX: usize = (1 << DEGREE) - 64;
+        let circuit = StateCircuit::<
+            Fr,
+            true,
+            RW_COUNTER_MAX,
+            MEMORY_ADDRESS_MAX,
+            STACK_ADDRESS_MAX,
+            ROWS_MAX,
+            DEFAULT_MAX_DEGREE,
+        >::new(block.randomness, &block.rws)
+        .map_err(|err| Failure::new(Stage::StateCircuit, format!("{:?}", err)))?;
+        let prover = MockProver::<Fr>::run(DEGREE as u32, &circuit, circuit.instance())
+            .map_err(|err| Failure::new(Stage::StateCircuit, format!("{:?}", err)))?;
+        prover
+            .verify()
+            .map_err(|errs| Failure::new(Stage::StateCircuit, format!("{:?}", errs)))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{logs_hash, Log};
+    use eth_types::{Address, H256};
+    use std::str::FromStr;
+
+    #[test]
+    fn runner_logs_hash() {
+        assert_eq!(
+            logs_hash(&[]),
+            H256::from_str("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347")
+                .unwrap()
+        );
+        assert_ne!(
+            logs_hash(&[Log {
+                address: Address::zero(),
+                topics: vec![],
+                data: vec![],
+            }]),
+            logs_hash(&[])
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! Parser of the GeneralStateTests fixtures of the ethereum/tests repository,
+//! in the format filled by retesteth.
+
+use eth_types::{geth_types::Account, Address, Bytes, Hash, Word, H256, U64};
+use ethers_core::{k256::ecdsa::SigningKey, utils::secret_key_to_address};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+/// Fork of the tests run by the testool, which is the one of the tracer.
+pub const FORK: &str = "London";
+
+/// Error when reading a fixture.
+#[derive(Debug)]
+pub enum StateTestError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// The file isn't a JSON fixture of state tests.
+    Json(serde_json::Error),
+    /// The secret key of the transaction isn't valid.
+    SecretKey(String),
+    /// The recipient of the transaction isn't an address.
+    Recipient(String),
+    /// An index of the post-state is out of the values of the transaction.
+    Index(String),
+}
+
+impl fmt::Display for StateTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "fixture io error: {}", err),
+            Self::Json(err) => write!(f, "invalid fixture: {}", err),
+            Self::SecretKey(test) => write!(f, "invalid secret key in {}", test),
+            Self::Recipient(to) => write!(f, "invalid transaction recipient {:?}", to),
+            Self::Index(test) => write!(f, "post-state index out of range in {}", test),
+        }
+    }
+}
+
+impl std::error::Error for StateTestError {}
+
+impl From<io::Error> for StateTestError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for StateTestError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonEnv {
+    current_coinbase: Address,
+    current_difficulty: Word,
+    current_gas_limit: Word,
+    current_number: U64,
+    current_timestamp: Word,
+    #[serde(default)]
+    current_base_fee: Option<Word>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct JsonAccount {
+    balance: Word,
+    code: Bytes,
+    nonce: Word,
+    storage: HashMap<Word, Word>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonTransaction {
+    data: Vec<Bytes>,
+    gas_limit: Vec<Word>,
+    #[serde(default)]
+    gas_price: Option<Word>,
+    #[serde(default)]
+    max_fee_per_gas: Option<Word>,
+    #[serde(default)]
+    max_priority_fee_per_gas: Option<Word>,
+    nonce: Word,
+    secret_key: H256,
+    to: String,
+    value: Vec<Word>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct JsonIndexes {
+    data: usize,
+    gas: usize,
+    value: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonPost {
+    hash: Hash,
+    logs: Hash,
+    indexes: JsonIndexes,
+    #[serde(default)]
+    expect_exception: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct JsonStateTest {
+    env: JsonEnv,
+    pre: HashMap<Address, JsonAccount>,
+    transaction: JsonTransaction,
+    post: HashMap<String, Vec<JsonPost>>,
+}
+
+/// Environment of the block of a test.
+#[derive(Debug, Clone)]
+pub struct Env {
+    /// Coinbase
+    pub coinbase: Address,
+    /// Difficulty
+    pub difficulty: Word,
+    /// Gas limit
+    pub gas_limit: Word,
+    /// Number
+    pub number: U64,
+    /// Timestamp
+    pub timestamp: Word,
+    /// Base fee
+    pub base_fee: Word,
+}
+
+/// Transaction of a test.
+#[derive(Debug, Clone)]
+pub struct Tx {
+    /// Sender, derived from the secret key of the fixture
+    pub from: Address,
+    /// Recipient, or `None` for a contract creation
+    pub to: Option<Address>,
+    /// Nonce
+    pub nonce: Word,
+    /// Gas limit
+    pub gas_limit: Word,
+    /// Value
+    pub value: Word,
+    /// Gas price of a legacy transaction
+    pub gas_price: Option<Word>,
+    /// Max fee per gas of an EIP-1559 transaction
+    pub max_fee_per_gas: Option<Word>,
+    /// Max priority fee per gas of an EIP-1559 transaction
+    pub max_priority_fee_per_gas: Option<Word>,
+    /// Call data
+    pub data: Bytes,
+}
+
+/// A state test with a single transaction, for one of the combinations of the
+/// data, gas limit and value of the transaction of a fixture.
+#[derive(Debug, Clone)]
+pub struct StateTest {
+    /// Id of the test: `<name>_d<data>_g<gas>_v<value>`
+    pub id: String,
+    /// Environment of the block
+    pub env: Env,
+    /// Accounts before the transaction
+    pub pre: Vec<Account>,
+    /// Transaction
+    pub tx: Tx,
+    /// Expected state root after the transaction
+    pub post_state_root: Hash,
+    /// Expected hash of the RLP encoding of the logs of the transaction
+    pub logs_hash: Hash,
+    /// Exception expected when the transaction is invalid
+    pub expect_exception: Option<String>,
+}
+
+/// Returns the state tests of the fork [`FORK`] in the fixture `json`.
+pub fn parse_state_tests(json: &str) -> Result<Vec<StateTest>, StateTestError> {
+    let fixture: HashMap<String, JsonStateTest> = serde_json::from_str(json)?;
+    let mut tests = Vec::new();
+    for (name, test) in fixture {
+        let secret_key = SigningKey::from_bytes(test.transaction.secret_key.as_bytes())
+            .map_err(|_| StateTestError::SecretKey(name.clone()))?;
+        let from = secret_key_to_address(&secret_key);
+        let to = match test.transaction.to.trim() {
+            "" => None,
+            to => {
+                Some(Address::from_str(to).map_err(|_| StateTestError::Recipient(to.to_string()))?)
+            }
+        };
+        let env = Env {
+            coinbase: test.env.current_coinbase,
+            difficulty: test.env.current_difficulty,
+            gas_limit: test.env.current_gas_limit,
+            number: test.env.current_number,
+            timestamp: test.env.current_timestamp,
+            base_fee: test.env.current_base_fee.unwrap_or_default(),
+        };
+        let pre: Vec<_> = test
+            .pre
+            .iter()
+            .map(|(address, account)| Account {
+                address: *address,
+                nonce: account.nonce,
+                balance: account.balance,
+                code: account.code.clone(),
+                storage: account.storage.clone(),
+            })
+            .collect();
+
+        for post in test.post.get(FORK).into_iter().flatten() {
+            let indexes = &post.indexes;
+            let id = format!(
+                "{}_d{}_g{}_v{}",
+                name, indexes.data, indexes.gas, indexes.value
+            );
+            let tx = &test.transaction;
+            let (data, gas_limit, value) = match (
+                tx.data.get(indexes.data),
+                tx.gas_limit.get(indexes.gas),
+                tx.value.get(indexes.value),
+            ) {
+                (Some(data), Some(gas_limit), Some(value)) => (data, gas_limit, value),
+                _ => return Err(StateTestError::Index(id)),
+            };
+            tests.push(StateTest {
+                id,
+                env: env.clone(),
+                pre: pre.clone(),
+                tx: Tx {
+                    from,
+                    to,
+                    nonce: tx.nonce,
+                    gas_limit: *gas_limit,
+                    value: *value,
+                    gas_price: tx.gas_price,
+                    max_fee_per_gas: tx.max_fee_per_gas,
+                    max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+                    data: data.clone(),
+                },
+                post_state_root: post.hash,
+                logs_hash: post.logs,
+                expect_exception: post.expect_exception.clone(),
+            });
+        }
+    }
+    tests.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(tests)
+}
+
+/// Returns the state tests of the fork [`FORK`] in the fixture at `path`.
+pub fn load_state_tests(path: impl AsRef<Path>) -> Result<Vec<StateTest>, StateTestError> {
+    parse_state_tests(&fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_state_tests;
+    use eth_types::{address, Word};
+
+    const FIXTURE: &str = r#"{
+        "add11": {
+            "_info": { "comment": "" },
+            "env": {
+                "currentCoinbase": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+                "currentDifficulty": "0x020000",
+                "currentGasLimit": "0xff112233445566",
+                "currentNumber": "0x01",
+                "currentTimestamp": "0x03e8",
+                "currentBaseFee": "0x0a",
+                "previousHash": "0x5e20a0453cecd065ea59c37ac63e079ee08998b6045136a8ce6635c7912ec0b6"
+            },
+            "pre": {
+                "0x095e7baea6a6c7c4c2dfeb977efac326af552d87": {
+                    "balance": "0x0de0b6b3a7640000",
+                    "code": "0x600160010160005500",
+                    "nonce": "0x00",
+                    "storage": {}
+                }
+            },
+            "transaction": {
+                "data": ["0x", "0x01"],
+                "gasLimit": ["0x061a80"],
+                "gasPrice": "0x0a",
+                "nonce": "0x00",
+                "secretKey": "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+                "to": "0x095e7baea6a6c7c4c2dfeb977efac326af552d87",
+                "value": ["0x01"]
+            },
+            "post": {
+                "Berlin": [],
+                "London": [
+                    {
+                        "hash": "0xe8010ce590f401c9d61fef8ab05bea9bcec24281b795e5868809bc4e515aa530",
+                        "indexes": { "data": 1, "gas": 0, "value": 0 },
+                        "logs": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347"
+                    }
+                ]
+            }
+        }
+    }"#;
+
+    #[test]
+    fn statetest_parse() {
+        let tests = parse_state_tests(FIXTURE).unwrap();
+        assert_eq!(tests.len(), 1);
+        let test = &tests[0];
+        assert_eq!(test.id, "add11_d1_g0_v0");
+        assert_eq!(
+            test.tx.from,
+            address!("0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b")
+        );
+        assert_eq!(
+            test.tx.to,
+            Some(address!("0x095e7baea6a6c7c4c2dfeb977efac326af552d87"))
+        );
+        assert_eq!(test.tx.data.to_vec(), vec![0x01]);
+        assert_eq!(test.tx.gas_limit, Word::from(400_000));
+        assert_eq!(test.env.base_fee, Word::from(10));
+        assert_eq!(
+            test.pre[0].code.to_vec(),
+            vec![0x60, 0x01, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x00]
+        );
+        assert_eq!(test.expect_exception, None);
+    }
+}
@@ -0,0 +1,283 @@
+//! # verifier
+//!
+//! Verification of the proofs of the circuits, depending on halo2 and the
+//! bn256 curve only, so that light clients and browsers can verify them in
+//! wasm32.
+//!
+//! The entry points take bytes: the SRS parameters and the verifying key as
+//! written by halo2, the values of the instance columns as 32-byte big-endian
+//! scalars, and the proof created with a Blake2b transcript, which are the
+//! contents of the key cache and of the proof files of the prover.  They never
+//! touch the filesystem, the network or the randomness of the OS, which
+//! aren't available in a browser.
+//!
+//! The crate isn't `no_std`: halo2 reads the keys through `std::io`, which
+//! the std of `wasm32-unknown-unknown` provides.  Reading a verifying key
+//! configures the constraint system of its circuit, so the verifier is
+//! generic over the circuit type `C` and a wasm module has to link the
+//! circuits it verifies the proofs of.
+
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+use halo2_proofs::{
+    plonk::{verify_proof, Circuit, SingleVerifier, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Challenge255},
+};
+use pairing::{
+    bn256::{Fr, G1Affine},
+    group::ff::PrimeField,
+};
+use std::fmt;
+
+/// Error when verifying a proof.
+#[derive(Debug)]
+pub enum VerifierError {
+    /// The bytes of the SRS parameters are invalid.
+    InvalidParams,
+    /// The bytes of the verifying key are invalid, or of another circuit.
+    InvalidVerifyingKey,
+    /// A value of an instance column isn't a canonical scalar.
+    InvalidInstance {
+        /// Index of the instance column
+        column: usize,
+        /// Row of the value in the column
+        row: usize,
+    },
+    /// The proof doesn't verify.
+    Verification(halo2_proofs::plonk::Error),
+}
+
+impl fmt::Display for VerifierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidParams => write!(f, "invalid params"),
+            Self::InvalidVerifyingKey => write!(f, "invalid verifying key"),
+            Self::InvalidInstance { column, row } => write!(
+                f,
+                "instance value at row {} of column {} isn't a scalar",
+                row, column
+            ),
+            Self::Verification(err) => write!(f, "proof verification failed: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for VerifierError {}
+
+/// Returns the big-endian bytes of the scalar `value`.
+pub fn fr_to_be_bytes(value: &Fr) -> [u8; 32] {
+    let mut bytes = value.to_repr();
+    bytes.reverse();
+    bytes
+}
+
+/// Returns the scalar of the big-endian `bytes`, or `None` if they aren't the
+/// ones of a canonical scalar.
+pub fn fr_from_be_bytes(mut bytes: [u8; 32]) -> Option<Fr> {
+    bytes.reverse();
+    Option::from(Fr::from_repr(bytes))
+}
+
+/// Reads the SRS parameters written by `Params::write`.
+pub fn read_params(mut bytes: &[u8]) -> Result<Params<G1Affine>, VerifierError> {
+    Params::read(&mut bytes).map_err(|_| VerifierError::InvalidParams)
+}
+
+/// Reads the verifying key of the circuit `C` written by
+/// `VerifyingKey::write`, for the SRS parameters `params`.
+pub fn read_vk<C: Circuit<Fr>>(
+    mut bytes: &[u8],
+    params: &Params<G1Affine>,
+) -> Result<VerifyingKey<G1Affine>, VerifierError> {
+    VerifyingKey::read::<_, C>(&mut bytes, params).map_err(|_| VerifierError::InvalidVerifyingKey)
+}
+
+/// Verifies `proof`, created with a Blake2b transcript, with the SRS
+/// parameters `params`, the verifying key `vk` and the values `instances` of
+/// the instance columns.
+pub fn verify(
+    params: &Params<G1Affine>,
+    vk: &VerifyingKey<G1Affine>,
+    instances: &[Vec<Fr>],
+    proof: &[u8],
+) -> Result<(), halo2_proofs::plonk::Error> {
+    let instances = instances.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    let n_instance_rows = instances.iter().map(|column| column.len()).max();
+
+    let verifier_params = params.verifier(n_instance_rows.unwrap_or_default())?;
+    let strategy = SingleVerifier::new(&verifier_params);
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    verify_proof(
+        &verifier_params,
+        vk,
+        strategy,
+        &[&instances[..]],
+        &mut transcript,
+    )
+}
+
+/// Verifies `proof` of the circuit `C` from bytes only: the SRS parameters
+/// `params`, the verifying key `vk` and the big-endian values `instances` of
+/// the instance columns.  This is the single call a wasm binding of the
+/// circuit `C` wraps.
+pub fn verify_bytes<C: Circuit<Fr>>(
+    params: &[u8],
+    vk: &[u8],
+    instances: &[Vec<[u8; 32]>],
+    proof: &[u8],
+) -> Result<(), VerifierError> {
+    let params = read_params(params)?;
+    let vk = read_vk::<C>(vk, &params)?;
+    let instances = instances
+        .iter()
+        .enumerate()
+        .map(|(column, values)| {
+            values
+                .iter()
+                .enumerate()
+                .map(|(row, value)| {
+                    fr_from_be_bytes(*value).ok_or(VerifierError::InvalidInstance { column, row })
+                })
+                .collect()
+        })
+        .collect::<Result<Vec<Vec<_>>, _>>()?;
+    verify(&params, &vk, &instances, proof).map_err(VerifierError::Verification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fr_from_be_bytes, fr_to_be_bytes, verify_bytes, VerifierError};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        plonk::{
+            create_proof, keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error,
+            Instance, Selector,
+        },
+        poly::{commitment::Params, Rotation},
+        transcript::{Blake2bWrite, Challenge255},
+    };
+    use pairing::bn256::{Bn256, Fr, G1Affine};
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    const K: u32 = 4;
+
+    // Circuit proving the knowledge of a square root of its instance.
+    #[derive(Clone, Default)]
+    struct SquareCircuit {
+        root: Fr,
+    }
+
+    #[derive(Clone)]
+    struct SquareConfig {
+        q_square: Selector,
+        value: Column<Advice>,
+        square: Column<Instance>,
+    }
+
+    impl Circuit<Fr> for SquareCircuit {
+        type Config = SquareConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let q_square = meta.selector();
+            let value = meta.advice_column();
+            let square = meta.instance_column();
+            meta.enable_equality(value);
+            meta.enable_equality(square);
+            meta.create_gate("square", |meta| {
+                let q_square = meta.query_selector(q_square);
+                let root = meta.query_advice(value, Rotation::cur());
+                let square = meta.query_advice(value, Rotation::next());
+                vec![q_square * (root.clone() * root - square)]
+            });
+            SquareConfig {
+                q_square,
+                value,
+                square,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let square = layouter.assign_region(
+                || "square",
+                |mut region| {
+                    config.q_square.enable(&mut region, 0)?;
+                    region.assign_advice(|| "root", config.value, 0, || Ok(self.root))?;
+                    region.assign_advice(|| "square", config.value, 1, || Ok(self.root * self.root))
+                },
+            )?;
+            layouter.constrain_instance(square.cell(), config.square, 0)
+        }
+    }
+
+    // Returns the bytes of the params, of the verifying key and of a proof of
+    // the square root `root`.
+    fn prove(root: u64) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let params = Params::<G1Affine>::unsafe_setup::<Bn256>(K);
+        let circuit = SquareCircuit {
+            root: Fr::from(root),
+        };
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk, &circuit).unwrap();
+        let square = Fr::from(root * root);
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof(
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&[square][..]][..]],
+            XorShiftRng::seed_from_u64(root),
+            &mut transcript,
+        )
+        .unwrap();
+
+        let mut params_bytes = Vec::new();
+        params.write(&mut params_bytes).unwrap();
+        let mut vk_bytes = Vec::new();
+        pk.get_vk().write(&mut vk_bytes).unwrap();
+        (params_bytes, vk_bytes, transcript.finalize())
+    }
+
+    #[test]
+    fn verify_bytes_of_proof() {
+        let (params, vk, proof) = prove(7);
+        let instance = |value: u64| vec![vec![fr_to_be_bytes(&Fr::from(value))]];
+        verify_bytes::<SquareCircuit>(&params, &vk, &instance(49), &proof).unwrap();
+
+        assert!(matches!(
+            verify_bytes::<SquareCircuit>(&params, &vk, &instance(50), &proof),
+            Err(VerifierError::Verification(_))
+        ));
+        assert!(matches!(
+            verify_bytes::<SquareCircuit>(&params, &vk, &[vec![[0xff; 32]]], &proof),
+            Err(VerifierError::InvalidInstance { column: 0, row: 0 })
+        ));
+        assert!(matches!(
+            verify_bytes::<SquareCircuit>(&params, &vk[..vk.len() / 2], &instance(49), &proof),
+            Err(VerifierError::InvalidVerifyingKey)
+        ));
+        assert!(matches!(
+            verify_bytes::<SquareCircuit>(&params[..8], &vk, &instance(49), &proof),
+            Err(VerifierError::InvalidParams)
+        ));
+    }
+
+    #[test]
+    fn fr_be_bytes_roundtrip() {
+        let value = -Fr::from(2);
+        assert_eq!(fr_from_be_bytes(fr_to_be_bytes(&value)), Some(value));
+        assert_eq!(fr_to_be_bytes(&Fr::from(1))[31], 1);
+        assert_eq!(fr_from_be_bytes([0xff; 32]), None);
+    }
+}
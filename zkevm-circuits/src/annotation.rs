@@ -0,0 +1,232 @@
+//! Stable names of the columns of the circuits, and of the gadget or chip
+//! owning them, so that tools outside of the circuits can map the columns of
+//! a `ConstraintSystem` back to their meaning, and so that the failures of
+//! the `MockProver`, which only know the index of a column, can be read.
+//!
+//! The config of every circuit implements [`Annotate`], naming its own
+//! columns after its fields and passing down its path to the configs and the
+//! tables it holds, like `super_circuit/state_circuit/lexicographic_ordering`.
+//! A column shared by several configs keeps the name given first, so a table
+//! shared by several circuits is named after the config holding it as long as
+//! configs annotate their tables before their sub-circuits.  Selectors aren't
+//! columns until they are compressed by the key generation, and the instance
+//! columns of the powers of the randomness aren't held by the configs, so
+//! they aren't named.
+
+use crate::{
+    block_table::BlockTable, bytecode_table::BytecodeTable, copy_table::CopyTable,
+    ecc_table::EccTable, evm_circuit::table::LookupTable, exp_table::ExpTable,
+    fixed_table::FixedTable, gadget::range_check::RangeTable, keccak_table::KeccakTable,
+    mpt_table::MptTable, rw_table::RwTable, sha256_table::Sha256Table, sig_table::SigTable,
+    tx_table::TxTable,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::{Any, Column},
+};
+use pairing::bn256::Fr;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Kind of a column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnKind {
+    /// Advice column
+    Advice,
+    /// Fixed column
+    Fixed,
+    /// Instance column
+    Instance,
+}
+
+impl From<&Any> for ColumnKind {
+    fn from(column_type: &Any) -> Self {
+        match column_type {
+            Any::Advice => Self::Advice,
+            Any::Fixed => Self::Fixed,
+            Any::Instance => Self::Instance,
+        }
+    }
+}
+
+/// Name of a column and of the gadget or chip owning it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnAnnotation {
+    /// Path of the owner of the column, like `super_circuit/evm_circuit`
+    pub owner: String,
+    /// Name of the column in its owner, like `rw_counter` or `cell_3`
+    pub name: String,
+}
+
+/// Names of the columns of a circuit, by kind and index.
+#[derive(Clone, Debug, Default)]
+pub struct ColumnAnnotations(BTreeMap<(ColumnKind, usize), ColumnAnnotation>);
+
+impl ColumnAnnotations {
+    /// Names `column` as `name` of `owner`, unless it is already named.
+    pub fn annotate(&mut self, owner: &str, name: &str, column: impl Into<Column<Any>>) {
+        let column = column.into();
+        self.0
+            .entry((column.column_type().into(), column.index()))
+            .or_insert_with(|| ColumnAnnotation {
+                owner: owner.to_string(),
+                name: name.to_string(),
+            });
+    }
+
+    /// Names the `columns` as `name_0`, `name_1`... of `owner`.
+    pub fn annotate_all<C: Into<Column<Any>>>(
+        &mut self,
+        owner: &str,
+        name: &str,
+        columns: impl IntoIterator<Item = C>,
+    ) {
+        for (idx, column) in columns.into_iter().enumerate() {
+            self.annotate(owner, &format!("{}_{}", name, idx), column);
+        }
+    }
+
+    /// Names the columns of `table` after its `LookupTable::annotations`.
+    pub fn annotate_table<F: FieldExt, const W: usize>(
+        &mut self,
+        owner: &str,
+        table: &impl LookupTable<F, W>,
+    ) {
+        for (column, name) in table.columns().into_iter().zip(table.annotations()) {
+            self.annotate(owner, &name, column);
+        }
+    }
+
+    /// Names the columns of `config`, which is `name` in `owner`.
+    pub fn annotate_config(&mut self, owner: &str, name: &str, config: &impl Annotate) {
+        config.annotate(&format!("{}/{}", owner, name), self);
+    }
+
+    /// Returns the name of the column of kind `kind` at `index`, if it is
+    /// named.
+    pub fn get(&self, kind: ColumnKind, index: usize) -> Option<&ColumnAnnotation> {
+        self.0.get(&(kind, index))
+    }
+
+    /// Returns the number of named columns.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether no column is named.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns `text`, like the `Debug` of a failure of the `MockProver`, with
+    /// the name of every named column appended to its `Debug`.
+    pub fn describe(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for ((kind, index), annotation) in self.0.iter() {
+            let column = format!("Column {{ index: {}, column_type: {:?} }}", index, kind);
+            text = text.replace(
+                &column,
+                &format!("{} ({}/{})", column, annotation.owner, annotation.name),
+            );
+        }
+        text
+    }
+}
+
+/// Config of a circuit, a gadget or a table naming its columns.
+pub trait Annotate {
+    /// Names the columns of `self` in `annotations` as owned by `owner`, the
+    /// path of `self` in its circuit.
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations);
+}
+
+impl<A: Annotate, B: Annotate> Annotate for (A, B) {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        self.0.annotate(owner, annotations);
+        self.1.annotate(owner, annotations);
+    }
+}
+
+macro_rules! impl_annotate_table {
+    ($($table:ty, $width:expr);* $(;)?) => {
+        $(
+            impl Annotate for $table {
+                fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+                    annotations.annotate_table::<Fr, $width>(owner, self);
+                }
+            }
+        )*
+    };
+}
+
+impl_annotate_table!(
+    BlockTable, 3;
+    BytecodeTable, 4;
+    CopyTable, 13;
+    EccTable, 7;
+    ExpTable, 3;
+    FixedTable, 4;
+    KeccakTable, 3;
+    MptTable, 7;
+    RwTable, 11;
+    Sha256Table, 3;
+    SigTable, 6;
+    TxTable, 4;
+);
+
+impl<const K: usize> Annotate for RangeTable<K> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_table::<Fr, 1>(owner, self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Annotate, ColumnAnnotation, ColumnAnnotations, ColumnKind};
+    use crate::exp_circuit::ExpCircuit;
+    use halo2_proofs::plonk::{Advice, Any, Circuit, Column, ConstraintSystem};
+    use pairing::bn256::Fr;
+
+    #[test]
+    fn exp_circuit_columns_are_annotated() {
+        let mut meta = ConstraintSystem::<Fr>::default();
+        let config = ExpCircuit::<Fr>::configure(&mut meta);
+        let mut annotations = ColumnAnnotations::default();
+        config.annotate("exp_circuit", &mut annotations);
+
+        assert_eq!(
+            annotations.len(),
+            meta.num_advice_columns() + meta.num_fixed_columns()
+        );
+        // The exp table is constructed first.
+        assert_eq!(
+            annotations.get(ColumnKind::Advice, 0),
+            Some(&ColumnAnnotation {
+                owner: "exp_circuit/exp_table".to_string(),
+                name: "base".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn describe_failure() {
+        let mut meta = ConstraintSystem::<Fr>::default();
+        let columns = [(); 2].map(|_| meta.advice_column());
+        let mut annotations = ColumnAnnotations::default();
+        annotations.annotate("chip", "value", columns[1]);
+        // The first name given to a column is kept.
+        annotations.annotate("other_chip", "other_value", columns[1]);
+
+        let column = |column: Column<Advice>| format!("{:?}", Column::<Any>::from(column));
+        let failure = format!("Cells {} and {}", column(columns[0]), column(columns[1]));
+        assert_eq!(
+            annotations.describe(&failure),
+            format!(
+                "Cells {} and {} (chip/value)",
+                column(columns[0]),
+                column(columns[1])
+            )
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! Reports the opcodes, the precompiles and the error paths exercised by the
+//! traces of blocks, against the ones implemented in bus-mapping and in the
+//! EVM circuit.
+//!
+//! Usage: `opcode_coverage <traces.json>...`
+//!
+//! Each file holds the traces of a block, either as the result of
+//! `debug_traceBlockByNumber` or as a list of traces.  The coverage matrix is
+//! printed as JSON on the standard output, and the features exercised but
+//! missing in bus-mapping or in the EVM circuit, the most exercised first, on
+//! the standard error.
+
+use eth_types::{GethExecTrace, ResultGethExecTraces};
+use std::{fs, process::exit};
+use zkevm_circuits::evm_circuit::coverage::Coverage;
+
+fn usage() -> ! {
+    eprintln!("Usage: opcode_coverage <traces.json>...");
+    exit(2)
+}
+
+fn load_traces(path: &str) -> Result<Vec<GethExecTrace>, String> {
+    let json = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    match serde_json::from_str::<ResultGethExecTraces>(&json) {
+        Ok(traces) => Ok(traces.0.into_iter().map(|trace| trace.result).collect()),
+        Err(_) => serde_json::from_str(&json).map_err(|err| err.to_string()),
+    }
+}
+
+fn main() {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() || paths.iter().any(|path| path == "-h" || path == "--help") {
+        usage();
+    }
+
+    let mut coverage = Coverage::new();
+    for path in paths.iter() {
+        match load_traces(path) {
+            Ok(traces) => coverage.add_traces(&traces),
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                exit(1);
+            }
+        }
+    }
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&coverage).expect("coverage serializes")
+    );
+    for (kind, name, entry) in coverage.missing() {
+        eprintln!(
+            "{} {}: {} steps, bus-mapping: {}, evm circuit: {}",
+            kind, name, entry.count, entry.bus_mapping, entry.evm_circuit
+        );
+    }
+}
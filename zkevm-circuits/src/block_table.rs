@@ -0,0 +1,63 @@
+#![allow(missing_docs)]
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Region,
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::evm_circuit::table::LookupTable;
+
+/// The block table exported by the pi circuit, and looked up by the evm
+/// circuit.  Each row is a `(field_tag, number, value)`, where `number` is
+/// only used by the block hashes.
+#[derive(Clone, Copy, Debug)]
+pub struct BlockTable {
+    pub tag: Column<Advice>,
+    pub index: Column<Advice>,
+    pub value: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F, 3> for BlockTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        [self.tag, self.index, self.value].map(Into::into).to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        ["tag", "index", "value"].map(String::from).to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 3] {
+        [
+            meta.query_advice(self.tag, Rotation::cur()),
+            meta.query_advice(self.index, Rotation::cur()),
+            meta.query_advice(self.value, Rotation::cur()),
+        ]
+    }
+}
+
+impl BlockTable {
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            tag: meta.advice_column(),
+            index: meta.advice_column(),
+            value: meta.advice_column(),
+        }
+    }
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 3],
+    ) -> Result<(), Error> {
+        for (column, value) in [self.tag, self.index, self.value].iter().zip(row) {
+            region.assign_advice(
+                || "assign block row on block table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -2,3 +2,117 @@
 
 pub(crate) mod bytecode_unroller;
 pub(crate) mod param;
+
+use crate::{
+    bytecode_table::BytecodeTable, evm_circuit::witness::Block, keccak_table::KeccakTable,
+    util::power_of_randomness_from_instance,
+};
+use bytecode_unroller::{unroll_bytecodes, Config};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+/// Bytecode circuit proving the bytecodes of a block on its own, with its own
+/// keccak table of their hashes.  The randomness is the first instance
+/// column.
+#[derive(Clone, Debug, Default)]
+pub struct BytecodeCircuit<F> {
+    /// Block with the bytecodes, which fit in `circuits_params.max_bytecode`
+    pub block: Block<F>,
+    /// Number of rows of the circuit, `2^k`
+    pub size: usize,
+}
+
+impl<F: Field> Circuit<F> for BytecodeCircuit<F> {
+    type Config = Config<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            size: self.size,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let bytecode_table = BytecodeTable::construct(meta);
+        let keccak_table = KeccakTable::construct(meta);
+        let [randomness] = power_of_randomness_from_instance::<_, 1>(meta);
+
+        Config::configure(meta, randomness, bytecode_table, keccak_table)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let block = &self.block;
+        let bytecodes = unroll_bytecodes(
+            &block.bytecodes,
+            block.randomness,
+            block.circuits_params.max_bytecode,
+        )
+        .map_err(|_| Error::Synthesis)?;
+
+        config.load(&mut layouter)?;
+        config.load_keccaks(&mut layouter, &bytecodes, block.randomness)?;
+        config.assign(
+            layouter.namespace(|| "bytecode circuit"),
+            self.size,
+            &bytecodes,
+            block.randomness,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BytecodeCircuit;
+    use crate::evm_circuit::witness::block_convert;
+    use bus_mapping::mock::BlockData;
+    use eth_types::bytecode;
+    use halo2_proofs::dev::MockProver;
+    use pairing::bn256::Fr;
+
+    const K: u32 = 10;
+
+    fn circuit_of_block() -> (BytecodeCircuit<Fr>, Vec<Vec<Fr>>) {
+        let code = bytecode! {
+            PUSH32(0x1234)
+            PUSH1(0x01)
+            SSTORE
+            STOP
+        };
+        let block_data =
+            BlockData::new_from_geth_data(mock::new_single_tx_trace_code(&code).unwrap());
+        let mut builder = block_data.new_circuit_input_builder();
+        builder
+            .handle_block(&block_data.eth_block, &block_data.geth_traces)
+            .unwrap();
+        let block = block_convert(&builder.block, &builder.code_db);
+        let randomness = vec![vec![block.randomness; (1 << K) - 64]];
+
+        let circuit = BytecodeCircuit::<Fr> {
+            block,
+            size: 1 << K,
+        };
+        (circuit, randomness)
+    }
+
+    #[test]
+    fn bytecode_circuit_of_block() {
+        let (circuit, randomness) = circuit_of_block();
+        let prover = MockProver::<Fr>::run(K, &circuit, randomness).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[cfg(feature = "real-prover")]
+    #[test]
+    fn bytecode_circuit_of_block_real_prover() {
+        let (circuit, randomness) = circuit_of_block();
+        crate::test_util::run_real_prover(K, &circuit, randomness).unwrap();
+    }
+}
@@ -1,24 +1,28 @@
 use crate::{
-    evm_circuit::util::{
-        and, constraint_builder::BaseConstraintBuilder, not, or, select, RandomLinearCombination,
+    annotation::{Annotate, ColumnAnnotations},
+    bytecode_table::BytecodeTable,
+    evm_circuit::{
+        table::LookupTable,
+        util::{and, constraint_builder::BaseConstraintBuilder, not, or, select},
+        witness::{keccak_table_assignments, Bytecode},
     },
     gadget::{
-        evm_word::encode,
         is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
+        rlc::RunningRlcChip,
     },
+    keccak_table::KeccakTable,
     util::Expr,
 };
 use bus_mapping::evm::OpcodeId;
-use eth_types::Field;
+use eth_types::{keccak256, Field, Word};
 use halo2_proofs::{
     circuit::{Layouter, Region},
-    plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Selector, VirtualCells},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells},
     poly::Rotation,
 };
-use keccak256::plain::Keccak;
-use std::{convert::TryInto, vec};
+use std::{collections::HashSet, fmt, vec};
 
-use super::param::{KECCAK_WIDTH, PUSH_TABLE_WIDTH};
+use super::param::PUSH_TABLE_WIDTH;
 
 /// Public data for the bytecode
 #[derive(Clone, Debug, PartialEq)]
@@ -36,9 +40,31 @@ pub(crate) struct UnrolledBytecode<F: Field> {
     rows: Vec<BytecodeRow<F>>,
 }
 
+impl<F: Field> UnrolledBytecode<F> {
+    /// Returns the `(hash, index, byte, is_code)` rows of the bytecode table
+    /// assigned by the circuit.
+    pub(crate) fn table_rows(&self) -> Vec<[F; 4]> {
+        self.rows
+            .iter()
+            .map(|row| [row.hash, row.index, row.byte, row.is_code])
+            .collect()
+    }
+
+    /// Returns the `(hash_rlc, hash_length, hash)` row looked up in the keccak
+    /// table on the last row of the bytecode, or `None` if it is empty.
+    pub(crate) fn keccak_lookup(&self, randomness: F) -> Option<[F; 3]> {
+        let hash_rlc = self
+            .rows
+            .iter()
+            .fold(F::zero(), |acc, row| acc * randomness + row.byte);
+        self.rows
+            .last()
+            .map(|row| [hash_rlc, F::from(self.bytes.len() as u64), row.hash])
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Config<F> {
-    r: F,
     minimum_rows: usize,
     q_enable: Selector,
     q_first: Column<Fixed>,
@@ -56,18 +82,48 @@ pub struct Config<F> {
     push_rindex_inv: Column<Advice>,
     push_rindex_is_zero: IsZeroConfig<F>,
     push_table: [Column<Fixed>; PUSH_TABLE_WIDTH],
-    keccak_table: [Column<Advice>; KECCAK_WIDTH],
+    keccak_table: KeccakTable,
+}
+
+impl<F> Annotate for Config<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate(owner, "q_first", self.q_first);
+        annotations.annotate(owner, "hash", self.hash);
+        annotations.annotate(owner, "index", self.index);
+        annotations.annotate(owner, "is_code", self.is_code);
+        annotations.annotate(owner, "byte", self.byte);
+        annotations.annotate(owner, "push_rindex", self.push_rindex);
+        annotations.annotate(owner, "hash_rlc", self.hash_rlc);
+        annotations.annotate(owner, "hash_length", self.hash_length);
+        annotations.annotate(owner, "byte_push_size", self.byte_push_size);
+        annotations.annotate(owner, "is_final", self.is_final);
+        annotations.annotate(owner, "padding", self.padding);
+        annotations.annotate(owner, "push_rindex_inv", self.push_rindex_inv);
+        annotations.annotate_config(owner, "push_rindex_is_zero", &self.push_rindex_is_zero);
+        annotations.annotate_all(owner, "push_table", self.push_table);
+        annotations.annotate_config(owner, "keccak_table", &self.keccak_table);
+    }
 }
 
 impl<F: Field> Config<F> {
-    pub(crate) fn configure(meta: &mut ConstraintSystem<F>, r: F) -> Self {
+    /// Configure the bytecode circuit, which assigns the rows of
+    /// `bytecode_table` looked up by the EVM circuit, and looks up the hashes
+    /// in `keccak_table`.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        r: Expression<F>,
+        bytecode_table: BytecodeTable,
+        keccak_table: KeccakTable,
+    ) -> Self {
         let q_enable = meta.complex_selector();
         let q_first = meta.fixed_column();
         let q_last = meta.selector();
-        let hash = meta.advice_column();
-        let index = meta.advice_column();
-        let is_code = meta.advice_column();
-        let byte = meta.advice_column();
+        let BytecodeTable {
+            hash,
+            index,
+            value: byte,
+            is_code,
+        } = bytecode_table;
         let push_rindex = meta.advice_column();
         let hash_rlc = meta.advice_column();
         let hash_length = meta.advice_column();
@@ -76,7 +132,6 @@ impl<F: Field> Config<F> {
         let padding = meta.advice_column();
         let push_rindex_inv = meta.advice_column();
         let push_table = array_init::array_init(|_| meta.fixed_column());
-        let keccak_table = array_init::array_init(|_| meta.advice_column());
 
         // A byte is an opcode when `push_rindex == 0` on the previous row,
         // else it's push data.
@@ -102,6 +157,16 @@ impl<F: Field> Config<F> {
             ])
         };
 
+        // hash_rlc := hash_rlc_prev * r + byte when continuing, and starts at byte
+        RunningRlcChip::configure(
+            meta,
+            |meta| meta.query_selector(q_enable),
+            q_continue,
+            |meta| meta.query_advice(byte, Rotation::cur()),
+            r,
+            hash_rlc,
+        );
+
         meta.create_gate("continue", |meta| {
             let mut cb = BaseConstraintBuilder::default();
             cb.require_equal(
@@ -114,13 +179,6 @@ impl<F: Field> Config<F> {
                 meta.query_advice(is_code, Rotation::cur()),
                 push_rindex_is_zero.clone().is_zero_expression,
             );
-            cb.require_equal(
-                "hash_rlc := hash_rlc_prev * r + byte",
-                meta.query_advice(hash_rlc, Rotation::cur()),
-                meta.query_advice(hash_rlc, Rotation::prev()) * r
-                    + meta.query_advice(byte, Rotation::cur()),
-            );
-
             cb.require_equal(
                 "hash needs to remain the same",
                 meta.query_advice(hash, Rotation::cur()),
@@ -151,16 +209,13 @@ impl<F: Field> Config<F> {
                 "index needs to start at 0",
                 meta.query_advice(index, Rotation::cur()),
             );
+            // Padding rows are all-zero rows of the bytecode table, which
+            // are looked up by the disabled bytecode lookups.
             cb.require_equal(
-                "is_code needs to be 1 (first byte is always an opcode)",
-                1.expr(),
+                "is_code needs to be 1 (first byte is always an opcode), or 0 on padding",
+                not::expr(meta.query_advice(padding, Rotation::cur())),
                 meta.query_advice(is_code, Rotation::cur()),
             );
-            cb.require_equal(
-                "hash_rlc needs to start at byte",
-                meta.query_advice(byte, Rotation::cur()),
-                meta.query_advice(hash_rlc, Rotation::cur()),
-            );
             // Conditions:
             // - Not continuing
             cb.gate(and::expr(vec![
@@ -196,14 +251,34 @@ impl<F: Field> Config<F> {
                 "padding needs to be boolean",
                 meta.query_advice(padding, Rotation::cur()),
             );
-            cb.require_equal(
+            // `is_code` is used as a selector below and looked up by the EVM
+            // circuit to validate jump destinations, so it's range checked on
+            // every row instead of relying on the `start`/`continue` gates.
+            cb.require_boolean(
+                "is_code needs to be boolean",
+                meta.query_advice(is_code, Rotation::cur()),
+            );
+            // Count down the push data bytes left after a PUSH*, the next
+            // opcode is the byte following the one where this reaches 0.
+            cb.require_zero(
                 "push_rindex := is_code ? byte_push_size : push_rindex_prev - 1",
-                meta.query_advice(push_rindex, Rotation::cur()),
-                select::expr(
-                    meta.query_advice(is_code, Rotation::cur()),
-                    meta.query_advice(byte_push_size, Rotation::cur()),
-                    meta.query_advice(push_rindex, Rotation::prev()) - 1.expr(),
-                ),
+                not::expr(meta.query_advice(padding, Rotation::cur()))
+                    * (meta.query_advice(push_rindex, Rotation::cur())
+                        - select::expr(
+                            meta.query_advice(is_code, Rotation::cur()),
+                            meta.query_advice(byte_push_size, Rotation::cur()),
+                            meta.query_advice(push_rindex, Rotation::prev()) - 1.expr(),
+                        )),
+            );
+            cb.require_zero(
+                "hash needs to be 0 on padding rows",
+                meta.query_advice(padding, Rotation::cur())
+                    * meta.query_advice(hash, Rotation::cur()),
+            );
+            cb.require_zero(
+                "byte needs to be 0 on padding rows",
+                meta.query_advice(padding, Rotation::cur())
+                    * meta.query_advice(byte, Rotation::cur()),
             );
             // Conditions: Always
             cb.gate(meta.query_selector(q_enable))
@@ -259,28 +334,32 @@ impl<F: Field> Config<F> {
             constraints
         });
 
-        // keccak lookup
+        // keccak lookup, which proves the hash of every bytecode is the keccak
+        // hash of its bytes (the rows of a bytecode share the same hash, and
+        // padding rows have a 0 hash, so no byte can be added to the table
+        // without having its hash checked)
         meta.lookup_any("keccak", |meta| {
             // Conditions:
             // - On the row with the last byte (`is_final == 1`)
             // - Not padding
             let enable = and::expr(vec![
+                meta.query_selector(q_enable),
                 meta.query_advice(is_final, Rotation::cur()),
                 not::expr(meta.query_advice(padding, Rotation::cur())),
             ]);
-            let lookup_columns = vec![hash_rlc, hash_length, hash];
-            let mut constraints = vec![];
-            for i in 0..KECCAK_WIDTH {
-                constraints.push((
-                    enable.clone() * meta.query_advice(lookup_columns[i], Rotation::cur()),
-                    meta.query_advice(keccak_table[i], Rotation::cur()),
-                ))
-            }
-            constraints
+            vec![hash_rlc, hash_length, hash]
+                .into_iter()
+                .zip(keccak_table.table_exprs(meta))
+                .map(|(column, table)| {
+                    (
+                        enable.clone() * meta.query_advice(column, Rotation::cur()),
+                        table,
+                    )
+                })
+                .collect()
         });
 
         Config {
-            r,
             minimum_rows: meta.minimum_rows(),
             q_enable,
             q_first,
@@ -302,94 +381,107 @@ impl<F: Field> Config<F> {
         }
     }
 
+    /// Returns the maximum number of bytes that can be assigned in a circuit
+    /// of `size` rows, the rows left after the unusable ones.
+    pub(crate) fn max_bytecode_size(&self, size: usize) -> usize {
+        size - self.minimum_rows + 2
+    }
+
+    /// Assign the bytecodes back to back, followed by padding rows up to the
+    /// last usable row.  Returns an error if the bytecodes don't fit in
+    /// `size` rows, see `Config::max_bytecode_size`.
+    #[tracing::instrument(name = "bytecode_circuit_assign", skip_all)]
     pub(crate) fn assign(
         &self,
         mut layouter: impl Layouter<F>,
         size: usize,
         witness: &[UnrolledBytecode<F>],
-    ) {
+        randomness: F,
+    ) -> Result<(), Error> {
         let push_rindex_is_zero_chip = IsZeroChip::construct(self.push_rindex_is_zero.clone());
 
+        // The capacity is checked when building the witness, see
+        // `unroll_bytecodes`.
+        check_capacity(witness, self.max_bytecode_size(size)).map_err(|_| Error::Synthesis)?;
+
         // Subtract the unusable rows from the size
         let last_row_offset = size - self.minimum_rows + 1;
 
-        layouter
-            .assign_region(
-                || "assign bytecode",
-                |mut region| {
-                    let mut offset = 0;
-                    let mut push_rindex_prev = 0;
-
-                    for bytecode in witness.iter() {
-                        // Run over all the bytes
-                        let mut push_rindex = 0;
-                        let mut hash_rlc = F::zero();
-                        let hash_length = F::from(bytecode.bytes.len() as u64);
-                        for row in bytecode.rows.iter() {
-                            // Track which byte is an opcode and which is push
-                            // data
-                            let is_code = push_rindex == 0;
-                            let byte_push_size = get_push_size(row.byte.get_lower_128() as u8);
-                            push_rindex = if is_code {
-                                byte_push_size
-                            } else {
-                                push_rindex - 1
-                            };
-
-                            // Add the byte to the accumulator
-                            hash_rlc = hash_rlc * self.r + row.byte;
-
-                            // Set the data for this row
-                            self.set_row(
-                                &mut region,
-                                &push_rindex_is_zero_chip,
-                                offset,
-                                true,
-                                offset == last_row_offset,
-                                row.hash,
-                                row.index,
-                                row.is_code,
-                                row.byte,
-                                push_rindex,
-                                hash_rlc,
-                                hash_length,
-                                F::from(byte_push_size as u64),
-                                row.index + F::one() == hash_length,
-                                false,
-                                F::from(push_rindex_prev),
-                            )?;
-                            push_rindex_prev = push_rindex;
-                            offset += 1;
-                        }
-                    }
+        layouter.assign_region(
+            || "assign bytecode",
+            |mut region| {
+                let mut offset = 0;
+                let mut push_rindex_prev = 0;
+
+                for bytecode in witness.iter() {
+                    // Run over all the bytes
+                    let mut push_rindex = 0;
+                    let mut hash_rlc = F::zero();
+                    let hash_length = F::from(bytecode.bytes.len() as u64);
+                    for row in bytecode.rows.iter() {
+                        // Track which byte is an opcode and which is push
+                        // data
+                        let is_code = push_rindex == 0;
+                        let byte_push_size = get_push_size(row.byte.get_lower_128() as u8);
+                        push_rindex = if is_code {
+                            byte_push_size
+                        } else {
+                            push_rindex - 1
+                        };
+
+                        // Add the byte to the accumulator
+                        hash_rlc = hash_rlc * randomness + row.byte;
 
-                    // Padding
-                    for idx in offset..size {
+                        // Set the data for this row
                         self.set_row(
                             &mut region,
                             &push_rindex_is_zero_chip,
-                            idx,
-                            idx < size,
-                            idx == last_row_offset,
-                            F::zero(),
-                            F::zero(),
-                            F::one(),
-                            F::zero(),
-                            0,
-                            F::zero(),
-                            F::one(),
-                            F::zero(),
-                            true,
+                            offset,
                             true,
+                            offset == last_row_offset,
+                            row.hash,
+                            row.index,
+                            row.is_code,
+                            row.byte,
+                            push_rindex,
+                            hash_rlc,
+                            hash_length,
+                            F::from(byte_push_size as u64),
+                            row.index + F::one() == hash_length,
+                            false,
                             F::from(push_rindex_prev),
                         )?;
-                        push_rindex_prev = 0;
+                        push_rindex_prev = push_rindex;
+                        offset += 1;
                     }
+                }
 
-                    Ok(())
-                },
-            )
-            .ok();
+                // Padding
+                for idx in offset..size {
+                    self.set_row(
+                        &mut region,
+                        &push_rindex_is_zero_chip,
+                        idx,
+                        idx < size,
+                        idx == last_row_offset,
+                        F::zero(),
+                        F::zero(),
+                        F::zero(),
+                        F::zero(),
+                        0,
+                        F::zero(),
+                        F::one(),
+                        F::zero(),
+                        true,
+                        true,
+                        F::from(push_rindex_prev),
+                    )?;
+                    push_rindex_prev = 0;
+                }
+
+                Ok(())
+            },
+        )
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -457,11 +549,8 @@ impl<F: Field> Config<F> {
         Ok(())
     }
 
-    pub(crate) fn load(
-        &self,
-        layouter: &mut impl Layouter<F>,
-        bytecodes: &[UnrolledBytecode<F>],
-    ) -> Result<(), Error> {
+    /// Load the push table.
+    pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
         // push table: BYTE -> NUM_PUSHED:
         // [0, OpcodeId::PUSH1[ -> 0
         // [OpcodeId::PUSH1, OpcodeId::PUSH32] -> [1..32]
@@ -485,36 +574,126 @@ impl<F: Field> Config<F> {
                 }
                 Ok(())
             },
-        )?;
+        )
+    }
 
-        // keccak table
+    /// Load the keccak table with the hashes of `bytecodes`, when it isn't
+    /// shared with other circuits.
+    pub(crate) fn load_keccaks(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bytecodes: &[UnrolledBytecode<F>],
+        randomness: F,
+    ) -> Result<(), Error> {
         layouter.assign_region(
             || "keccak table",
             |mut region| {
-                for (offset, bytecode) in bytecodes.iter().map(|v| v.bytes.clone()).enumerate() {
-                    let hash: F = keccak(&bytecode[..], self.r);
-                    let rlc: F = linear_combine(bytecode.clone(), self.r);
-                    let size = F::from(bytecode.len() as u64);
-                    for (name, column, value) in &[
-                        ("rlc", self.keccak_table[0], rlc),
-                        ("size", self.keccak_table[1], size),
-                        ("hash", self.keccak_table[2], hash),
-                    ] {
-                        region.assign_advice(
-                            || format!("Keccak table assign {} {}", name, offset),
-                            *column,
-                            offset,
-                            || Ok(*value),
-                        )?;
-                    }
+                self.keccak_table.assign(&mut region, 0, [F::zero(); 3])?;
+                for (idx, bytecode) in bytecodes.iter().enumerate() {
+                    let row = keccak_table_assignments(&bytecode.bytes, randomness);
+                    self.keccak_table.assign(&mut region, idx + 1, row)?;
                 }
                 Ok(())
             },
-        )?;
-        Ok(())
+        )
+    }
+}
+
+/// Error when building the witness of the bytecode circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BytecodeCircuitError {
+    /// The block uses more bytecode bytes than the circuit can hold.
+    TooManyBytes {
+        /// Number of bytes of the bytecodes of the block
+        n_bytes: usize,
+        /// Maximum number of bytes of the circuit
+        max_bytecode_size: usize,
+        /// Code hashes of the bytecodes which don't fit entirely in the
+        /// circuit, in the order they're assigned
+        overflowing: Vec<Word>,
+    },
+}
+
+impl BytecodeCircuitError {
+    /// Returns the number of rows missing to the circuit to hold the
+    /// bytecodes.
+    pub fn overflow(&self) -> usize {
+        match self {
+            Self::TooManyBytes {
+                n_bytes,
+                max_bytecode_size,
+                ..
+            } => n_bytes - max_bytecode_size,
+        }
+    }
+}
+
+impl fmt::Display for BytecodeCircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyBytes {
+                n_bytes,
+                max_bytecode_size,
+                overflowing,
+            } => write!(
+                f,
+                "too many bytecode bytes for the bytecode circuit: {} > max_bytecode_size {}, \
+                 {} rows over, in the bytecodes {:?}",
+                n_bytes,
+                max_bytecode_size,
+                self.overflow(),
+                overflowing
+            ),
+        }
     }
 }
 
+impl std::error::Error for BytecodeCircuitError {}
+
+/// Unrolls the bytecodes of every contract touched in a block, each distinct
+/// bytecode once.  Returns an error if they have more than
+/// `max_bytecode_size` bytes in total.
+pub(crate) fn unroll_bytecodes<F: Field>(
+    bytecodes: &[Bytecode],
+    r: F,
+    max_bytecode_size: usize,
+) -> Result<Vec<UnrolledBytecode<F>>, BytecodeCircuitError> {
+    let mut hashes = HashSet::new();
+    let unrolled = bytecodes
+        .iter()
+        .filter(|bytecode| hashes.insert(bytecode.hash))
+        .map(|bytecode| unroll(bytecode.bytes.clone(), r))
+        .collect::<Vec<_>>();
+
+    check_capacity(&unrolled, max_bytecode_size)?;
+    Ok(unrolled)
+}
+
+// Checks that the `bytecodes` fit in `max_bytecode_size` rows, returning the
+// ones which don't otherwise.
+fn check_capacity<F: Field>(
+    bytecodes: &[UnrolledBytecode<F>],
+    max_bytecode_size: usize,
+) -> Result<(), BytecodeCircuitError> {
+    let mut n_bytes = 0;
+    let mut overflowing = vec![];
+    for bytecode in bytecodes {
+        n_bytes += bytecode.rows.len();
+        if n_bytes > max_bytecode_size {
+            overflowing.push(Word::from_big_endian(&keccak256(&bytecode.bytes)));
+        }
+    }
+
+    if n_bytes > max_bytecode_size {
+        return Err(BytecodeCircuitError::TooManyBytes {
+            n_bytes,
+            max_bytecode_size,
+            overflowing,
+        });
+    }
+    Ok(())
+}
+
 fn unroll<F: Field>(bytes: Vec<u8>, r: F) -> UnrolledBytecode<F> {
     let hash = keccak(&bytes[..], r);
     let mut rows = vec![];
@@ -551,10 +730,10 @@ fn get_push_size(byte: u8) -> u64 {
     }
 }
 
+/// Returns the hash of `msg` encoded like the code hash looked up by the EVM
+/// circuit, the random linear combination of its little-endian bytes.
 fn keccak<F: Field>(msg: &[u8], r: F) -> F {
-    let mut keccak = Keccak::default();
-    keccak.update(msg);
-    RandomLinearCombination::<F, 32>::random_linear_combine(keccak.digest().try_into().unwrap(), r)
+    keccak_table_assignments(msg, r)[2]
 }
 
 fn into_words(message: &[u8]) -> Vec<u64> {
@@ -570,13 +749,10 @@ fn into_words(message: &[u8]) -> Vec<u64> {
     words
 }
 
-fn linear_combine<F: Field>(bytes: Vec<u8>, r: F) -> F {
-    encode(bytes.into_iter(), r)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::evm_circuit::witness;
     use eth_types::{Bytecode, Word};
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner},
@@ -606,7 +782,14 @@ mod tests {
         }
 
         fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            Config::configure(meta, MyCircuit::r())
+            let bytecode_table = BytecodeTable::construct(meta);
+            let keccak_table = KeccakTable::construct(meta);
+            Config::configure(
+                meta,
+                Expression::Constant(MyCircuit::r()),
+                bytecode_table,
+                keccak_table,
+            )
         }
 
         fn synthesize(
@@ -614,9 +797,9 @@ mod tests {
             config: Self::Config,
             mut layouter: impl Layouter<F>,
         ) -> Result<(), Error> {
-            config.load(&mut layouter, &self.bytecodes)?;
-            config.assign(layouter, self.size, &self.bytecodes);
-            Ok(())
+            config.load(&mut layouter)?;
+            config.load_keccaks(&mut layouter, &self.bytecodes, MyCircuit::r())?;
+            config.assign(layouter, self.size, &self.bytecodes, MyCircuit::r())
         }
     }
 
@@ -713,12 +896,20 @@ mod tests {
         verify::<Fr>(k, vec![unroll(vec![7u8; 2usize.pow(k) - 6], r)], true);
     }
 
-    /// Tests a circuit with incomplete bytecode
+    /// Tests a circuit with incomplete bytecode, which doesn't fit in the
+    /// circuit and fails at synthesis
     #[test]
     fn bytecode_incomplete() {
         let k = 9;
         let r = MyCircuit::r();
-        verify::<Fr>(k, vec![unroll(vec![7u8; 2usize.pow(k) + 1], r)], false);
+        let circuit = MyCircuit::<Fr> {
+            bytecodes: vec![unroll(vec![7u8; 2usize.pow(k) + 1], r)],
+            size: 2usize.pow(k),
+        };
+        assert!(matches!(
+            MockProver::<Fr>::run(k, &circuit, vec![]),
+            Err(Error::Synthesis)
+        ));
     }
 
     /// Tests multiple bytecodes in a single circuit
@@ -864,4 +1055,89 @@ mod tests {
             verify::<Fr>(k, vec![invalid], false);
         }
     }
+
+    /// Test push data that is cut off at the end of a bytecode
+    #[test]
+    fn bytecode_truncated_push_data() {
+        let k = 9;
+        let r = MyCircuit::r();
+        let bytecodes = vec![
+            unroll(vec![OpcodeId::PUSH32.as_u8(), 1, 2, 3], r),
+            unroll(
+                vec![OpcodeId::JUMPDEST.as_u8(), OpcodeId::PUSH2.as_u8(), 0, 0],
+                r,
+            ),
+        ];
+        verify::<Fr>(k, bytecodes.clone(), true);
+        // Mark the last byte of the truncated push data as code
+        {
+            let mut invalid = bytecodes.clone();
+            invalid[0].rows[3].is_code = Fr::one();
+            verify::<Fr>(k, invalid, false);
+        }
+        // Mark the first byte of the next bytecode as data
+        {
+            let mut invalid = bytecodes.clone();
+            invalid[1].rows[0].is_code = Fr::zero();
+            verify::<Fr>(k, invalid, false);
+        }
+        // Use a non boolean value for is_code
+        {
+            let mut invalid = bytecodes;
+            invalid[1].rows[0].is_code = Fr::from(2u64);
+            verify::<Fr>(k, invalid, false);
+        }
+    }
+
+    /// Test the hash is encoded like the code hash in the EVM circuit
+    #[test]
+    fn bytecode_hash_encoding() {
+        let r = MyCircuit::r();
+        let bytes = vec![OpcodeId::PUSH1.as_u8(), 0x80, OpcodeId::JUMP.as_u8()];
+        let unrolled = unroll(bytes.clone(), r);
+        let table = witness::Bytecode::new(bytes)
+            .table_assignments(r)
+            .collect::<Vec<_>>();
+        assert_eq!(unrolled.rows.len(), table.len());
+        for (row, table_row) in unrolled.rows.iter().zip(table) {
+            assert_eq!([row.hash, row.index, row.byte, row.is_code], table_row);
+        }
+    }
+
+    /// Test unrolling the bytecodes of a block
+    #[test]
+    fn bytecode_unroll_block_bytecodes() {
+        let k = 9;
+        let r = MyCircuit::r();
+        let bytecodes = vec![
+            witness::Bytecode::new(vec![OpcodeId::PUSH1.as_u8(), 1, OpcodeId::STOP.as_u8()]),
+            witness::Bytecode::new(vec![OpcodeId::ADD.as_u8(); 16]),
+            witness::Bytecode::new(vec![OpcodeId::PUSH1.as_u8(), 1, OpcodeId::STOP.as_u8()]),
+        ];
+        // Each distinct bytecode is unrolled once
+        let unrolled = unroll_bytecodes::<Fr>(&bytecodes, r, 19).unwrap();
+        assert_eq!(
+            unrolled,
+            vec![
+                unroll(bytecodes[0].bytes.clone(), r),
+                unroll(bytecodes[1].bytes.clone(), r),
+            ]
+        );
+        verify::<Fr>(k, unrolled, true);
+        // Too many bytes for the capacity
+        assert_eq!(
+            unroll_bytecodes::<Fr>(&bytecodes, r, 18),
+            Err(BytecodeCircuitError::TooManyBytes {
+                n_bytes: 19,
+                max_bytecode_size: 18,
+                overflowing: vec![bytecodes[1].hash],
+            })
+        );
+        assert_eq!(
+            unroll_bytecodes::<Fr>(&bytecodes, r, 2)
+                .unwrap_err()
+                .overflow(),
+            17
+        );
+    }
 }
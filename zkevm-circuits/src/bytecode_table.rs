@@ -0,0 +1,74 @@
+#![allow(missing_docs)]
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Region,
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::evm_circuit::table::LookupTable;
+
+/// The bytecode table exported by the bytecode circuit, and looked up by the
+/// evm circuit and the copy circuit.  Each row is a `(hash, index, byte,
+/// is_code)` of a byte of a bytecode, where `hash` is the random linear
+/// combination of the code hash.
+#[derive(Clone, Copy, Debug)]
+pub struct BytecodeTable {
+    pub hash: Column<Advice>,
+    pub index: Column<Advice>,
+    pub value: Column<Advice>,
+    pub is_code: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F, 4> for BytecodeTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        [self.hash, self.index, self.value, self.is_code]
+            .map(Into::into)
+            .to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        ["hash", "index", "value", "is_code"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 4] {
+        [
+            meta.query_advice(self.hash, Rotation::cur()),
+            meta.query_advice(self.index, Rotation::cur()),
+            meta.query_advice(self.value, Rotation::cur()),
+            meta.query_advice(self.is_code, Rotation::cur()),
+        ]
+    }
+}
+
+impl BytecodeTable {
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            hash: meta.advice_column(),
+            index: meta.advice_column(),
+            value: meta.advice_column(),
+            is_code: meta.advice_column(),
+        }
+    }
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 4],
+    ) -> Result<(), Error> {
+        for (column, value) in [self.hash, self.index, self.value, self.is_code]
+            .iter()
+            .zip(row)
+        {
+            region.assign_advice(
+                || "assign bytecode row on bytecode table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1068 @@
+//! The copy circuit implementation.
+//!
+//! It proves the copies of bytes done in a block, from memory, bytecode or tx
+//! call data to memory, bytecode or a tx log, and assigns the copy table looked
+//! up by the EVM circuit.  Each byte copied has a read row followed by a write
+//! row, which look up the rw, bytecode or tx table, with the rw counters of the
+//! memory and tx log accesses interleaved.  The copies from memory to memory,
+//! like MCOPY's, read all the bytes before writing the first one instead, so
+//! that the source and the destination can overlap (EIP-5656).  The bytes read
+//! at or after the end of the source are 0, and the random linear combination
+//! of the bytes copied is accumulated so that it can be looked up with the
+//! copy.  The ones of the copies to tx logs are exposed in the [`LogDataTable`]
+//! by log, for the LOG gadgets and the receipts to reference the data logged
+//! without copying it again.
+
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    bytecode_table::BytecodeTable,
+    copy_table::CopyTable,
+    evm_circuit::{
+        param::N_BYTES_MEMORY_ADDRESS,
+        table::{CopyDataType, LookupTable, RwTableTag, TxContextFieldTag, TxLogFieldTag},
+        util::{constraint_builder::BaseConstraintBuilder, select},
+        witness::{Block, CopyEvent, CopyRow},
+    },
+    gadget::binary_number::{BinaryNumberChip, BinaryNumberConfig},
+    rw_table::RwTable,
+    tx_table::TxTable,
+    util::{power_of_randomness_from_instance, Expr},
+};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{
+        Advice, Any, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells,
+    },
+    poly::Rotation,
+};
+
+const MAX_DEGREE: usize = 6;
+
+// Indices of the rows queried by the copy step gate, the read and write rows
+// of the previous step followed by the ones of the step.
+const PREV_READ: usize = 0;
+const PREV_WRITE: usize = 1;
+const READ: usize = 2;
+const WRITE: usize = 3;
+
+// Tags of the rows, which are 0 on padding rows
+const PADDING_TAG: usize = 0;
+const MEMORY_TAG: usize = CopyDataType::Memory as usize;
+const BYTECODE_TAG: usize = CopyDataType::Bytecode as usize;
+const TX_CALLDATA_TAG: usize = CopyDataType::TxCalldata as usize;
+const TX_LOG_TAG: usize = CopyDataType::TxLog as usize;
+const TAGS: [usize; 5] = [
+    PADDING_TAG,
+    MEMORY_TAG,
+    BYTECODE_TAG,
+    TX_CALLDATA_TAG,
+    TX_LOG_TAG,
+];
+
+/// Config of the copy circuit.
+#[derive(Clone, Debug)]
+pub struct CopyCircuitConfig<F> {
+    // Enables the read row of each step
+    q_step: Column<Fixed>,
+    // Enables the first step
+    q_first: Column<Fixed>,
+    // Enables the last step
+    q_last: Column<Fixed>,
+    copy_table: CopyTable,
+    is_last: Column<Advice>,
+    value: Column<Advice>,
+    // Random linear combination of the bytes copied so far
+    value_acc: Column<Advice>,
+    // Whether the byte is read at or after the end of the source
+    is_pad: Column<Advice>,
+    is_code: Column<Advice>,
+    // Whether the copy is from memory to memory, reading all the bytes before
+    // writing the first one
+    is_reads_first: Column<Advice>,
+    // Bits of the tag, one of `TAGS`
+    tag_bits: BinaryNumberConfig<usize, 3>,
+    // Little-endian bytes of `addr - src_addr_end` on padded read rows, and of
+    // `src_addr_end - 1 - addr` on the other read rows
+    addr_diff: [Column<Advice>; N_BYTES_MEMORY_ADDRESS],
+    u8_table: Column<Fixed>,
+    tx_table: TxTable,
+    rw_table: RwTable,
+    bytecode_table: BytecodeTable,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F> Annotate for CopyCircuitConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_config(owner, "copy_table", &self.copy_table);
+        annotations.annotate(owner, "q_step", self.q_step);
+        annotations.annotate(owner, "q_first", self.q_first);
+        annotations.annotate(owner, "q_last", self.q_last);
+        annotations.annotate(owner, "is_last", self.is_last);
+        annotations.annotate(owner, "value", self.value);
+        annotations.annotate(owner, "value_acc", self.value_acc);
+        annotations.annotate(owner, "is_pad", self.is_pad);
+        annotations.annotate(owner, "is_code", self.is_code);
+        annotations.annotate(owner, "is_reads_first", self.is_reads_first);
+        annotations.annotate_config(owner, "tag_bits", &self.tag_bits);
+        annotations.annotate_all(owner, "addr_diff", self.addr_diff);
+        annotations.annotate(owner, "u8_table", self.u8_table);
+        annotations.annotate_config(owner, "tx_table", &self.tx_table);
+        annotations.annotate_config(owner, "rw_table", &self.rw_table);
+        annotations.annotate_config(owner, "bytecode_table", &self.bytecode_table);
+    }
+}
+
+impl<F: Field> CopyCircuitConfig<F> {
+    /// Configure the copy circuit, which assigns the rows of `copy_table`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        randomness: Expression<F>,
+        copy_table: CopyTable,
+        tx_table: TxTable,
+        rw_table: RwTable,
+        bytecode_table: BytecodeTable,
+    ) -> Self {
+        let [q_step, q_first, q_last] = [(); 3].map(|_| meta.fixed_column());
+        let [is_last, value, value_acc, is_pad, is_code, is_reads_first] =
+            [(); 6].map(|_| meta.advice_column());
+        let addr_diff = [(); N_BYTES_MEMORY_ADDRESS].map(|_| meta.advice_column());
+        let u8_table = meta.fixed_column();
+
+        // The tag of the read and write rows of each step is a copy data type,
+        // or 0 on padding rows
+        let tag_bits = BinaryNumberChip::configure(
+            meta,
+            |meta| {
+                meta.query_fixed(q_step, Rotation::cur())
+                    + meta.query_fixed(q_step, Rotation::prev())
+            },
+            copy_table.tag,
+            TAGS,
+        );
+
+        meta.create_gate("Copy step", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+
+            let rotations = [
+                Rotation(-2),
+                Rotation::prev(),
+                Rotation::cur(),
+                Rotation::next(),
+            ];
+            let query = |meta: &mut VirtualCells<F>, column| {
+                rotations.map(|rotation| meta.query_advice(column, rotation))
+            };
+            let is_first = query(meta, copy_table.is_first);
+            let id = query(meta, copy_table.id);
+            let tag = query(meta, copy_table.tag);
+            let addr = query(meta, copy_table.addr);
+            let src_addr_end = query(meta, copy_table.src_addr_end);
+            let log_id = query(meta, copy_table.log_id);
+            let bytes_left = query(meta, copy_table.bytes_left);
+            let rlc_acc = query(meta, copy_table.rlc_acc);
+            let rw_counter = query(meta, copy_table.rw_counter);
+            let rw_inc_left = query(meta, copy_table.rw_inc_left);
+            let is_last = query(meta, is_last);
+            let value = query(meta, value);
+            let value_acc = query(meta, value_acc);
+            let is_pad = query(meta, is_pad);
+            let is_code = query(meta, is_code);
+            let is_reads_first = query(meta, is_reads_first);
+            // The selectors of the tags only query the bits telling them apart,
+            // so that the ones of memory and tx logs are of degree 2 and 1
+            let is_tag = |meta: &mut VirtualCells<F>, tag| {
+                rotations.map(|rotation| tag_bits.value_equals_among(meta, tag, &TAGS, rotation))
+            };
+            let is_memory = is_tag(meta, MEMORY_TAG);
+            let is_bytecode = is_tag(meta, BYTECODE_TAG);
+            let is_tx_calldata = is_tag(meta, TX_CALLDATA_TAG);
+            let is_tx_log = is_tag(meta, TX_LOG_TAG);
+            let is_event = |row: usize| {
+                [&is_memory, &is_bytecode, &is_tx_calldata, &is_tx_log]
+                    .iter()
+                    .fold(0.expr(), |acc, is_tag| acc + is_tag[row].clone())
+            };
+            // Whether the row accesses the rw table
+            let rw_inc = |row: usize| {
+                (is_memory[row].clone() + is_tx_log[row].clone()) * (1.expr() - is_pad[row].clone())
+            };
+
+            cb.require_boolean("is_first is boolean", is_first[READ].clone());
+            cb.require_boolean("is_last is boolean", is_last[WRITE].clone());
+            cb.require_boolean("is_pad is boolean", is_pad[READ].clone());
+            cb.require_boolean("is_code is boolean", is_code[READ].clone());
+            cb.require_zero("only a read row is first", is_first[WRITE].clone());
+            cb.require_zero("only a write row is last", is_last[READ].clone());
+            cb.require_zero(
+                "last is in a copy",
+                is_last[WRITE].clone() * (1.expr() - is_event(WRITE)),
+            );
+            cb.require_zero("a write row isn't padded", is_pad[WRITE].clone());
+            cb.require_zero(
+                "source is memory, bytecode or tx call data",
+                is_tx_log[READ].clone(),
+            );
+            // The init code of a creation is copied from memory to the bytecode
+            // of its hash
+            cb.require_zero(
+                "destination is memory, bytecode or a tx log",
+                is_tx_calldata[WRITE].clone(),
+            );
+            cb.require_equal(
+                "read and write rows are both in a copy or both padding",
+                is_event(WRITE),
+                is_event(READ),
+            );
+            cb.require_equal(
+                "is_reads_first := source and destination are memory",
+                is_reads_first[READ].clone(),
+                is_memory[READ].clone() * is_memory[WRITE].clone(),
+            );
+            cb.require_zero(
+                "a copy reading all the bytes first isn't padded",
+                is_reads_first[READ].clone() * is_pad[READ].clone(),
+            );
+
+            // A copy continues in the next step until its last write row, which
+            // is in a copy
+            let is_continue = (1.expr() - meta.query_fixed(q_first, Rotation::cur()))
+                * (is_event(PREV_WRITE) - is_last[PREV_WRITE].clone());
+            cb.require_zero(
+                "unfinished copy continues",
+                is_continue.clone() * (1.expr() - is_event(READ)),
+            );
+            cb.require_equal(
+                "copy starts after the previous one is finished",
+                is_first[READ].clone(),
+                is_event(READ) - is_continue.clone(),
+            );
+            cb.condition(is_continue, |cb| {
+                for (name, column) in [
+                    ("same source id", &id),
+                    ("same source tag", &tag),
+                    ("same src_addr_end", &src_addr_end),
+                    ("same rlc_acc", &rlc_acc),
+                ] {
+                    cb.require_equal(name, column[READ].clone(), column[PREV_READ].clone());
+                }
+                for (name, column) in [
+                    ("same destination id", &id),
+                    ("same destination tag", &tag),
+                    ("same log_id", &log_id),
+                ] {
+                    cb.require_equal(name, column[WRITE].clone(), column[PREV_WRITE].clone());
+                }
+                cb.require_equal(
+                    "source address increases by 1",
+                    addr[READ].clone(),
+                    addr[PREV_READ].clone() + 1.expr(),
+                );
+                cb.require_equal(
+                    "destination address increases by 1",
+                    addr[WRITE].clone(),
+                    addr[PREV_WRITE].clone() + 1.expr(),
+                );
+                cb.require_equal(
+                    "bytes_left decreases by 1",
+                    bytes_left[READ].clone(),
+                    bytes_left[PREV_READ].clone() - 1.expr(),
+                );
+                cb.require_equal(
+                    "value_acc := value_acc_prev * r + value",
+                    value_acc[READ].clone(),
+                    value_acc[PREV_READ].clone() * randomness.clone() + value[READ].clone(),
+                );
+                // The reads of a copy reading all the bytes first follow each
+                // other instead of the writes
+                cb.require_equal(
+                    "rw_counter increases by the rw accesses of the previous write or read row",
+                    rw_counter[READ].clone(),
+                    rw_counter[PREV_WRITE].clone() + rw_inc(PREV_WRITE)
+                        - is_reads_first[READ].clone()
+                            * (rw_counter[PREV_WRITE].clone() - rw_counter[PREV_READ].clone()),
+                );
+                cb.require_equal(
+                    "rw_inc_left decreases as rw_counter increases from the previous write row",
+                    rw_counter[READ].clone() + rw_inc_left[READ].clone(),
+                    rw_counter[PREV_WRITE].clone() + rw_inc_left[PREV_WRITE].clone(),
+                );
+            });
+            cb.condition(is_first[READ].clone(), |cb| {
+                cb.require_equal(
+                    "value_acc starts at value",
+                    value_acc[READ].clone(),
+                    value[READ].clone(),
+                );
+            });
+
+            // The byte read is the byte written
+            cb.condition(is_event(READ), |cb| {
+                for (name, column) in [
+                    ("same value", &value),
+                    ("same value_acc", &value_acc),
+                    ("same bytes_left", &bytes_left),
+                    ("same rlc_acc", &rlc_acc),
+                ] {
+                    cb.require_equal(name, column[WRITE].clone(), column[READ].clone());
+                }
+                // A copy reading all the bytes first writes each byte after
+                // the reads of all the bytes, whose number is the rw accesses
+                // left minus the bytes left to write
+                cb.require_equal(
+                    "rw_counter increases by the rw accesses of the read row, or of the reads left",
+                    rw_counter[WRITE].clone(),
+                    rw_counter[READ].clone()
+                        + rw_inc(READ)
+                        + is_reads_first[READ].clone()
+                            * (rw_inc_left[READ].clone() - bytes_left[READ].clone() - 1.expr()),
+                );
+                cb.require_equal(
+                    "rw_inc_left decreases as rw_counter increases from the read row",
+                    rw_counter[WRITE].clone() + rw_inc_left[WRITE].clone(),
+                    rw_counter[READ].clone() + rw_inc_left[READ].clone(),
+                );
+                cb.require_zero(
+                    "byte read at or after src_addr_end is 0",
+                    is_pad[READ].clone() * value[READ].clone(),
+                );
+                cb.require_equal(
+                    "is_pad := addr >= src_addr_end",
+                    select::expr(
+                        is_pad[READ].clone(),
+                        addr[READ].clone() - src_addr_end[READ].clone(),
+                        src_addr_end[READ].clone() - 1.expr() - addr[READ].clone(),
+                    ),
+                    addr_diff.iter().rev().fold(0.expr(), |acc, column| {
+                        acc * 256u64.expr() + meta.query_advice(*column, Rotation::cur())
+                    }),
+                );
+            });
+
+            cb.condition(is_last[WRITE].clone(), |cb| {
+                cb.require_equal("last byte", bytes_left[WRITE].clone(), 1.expr());
+                cb.require_equal(
+                    "rlc_acc is the random linear combination of the bytes",
+                    rlc_acc[WRITE].clone(),
+                    value_acc[WRITE].clone(),
+                );
+                cb.require_equal("last rw access", rw_inc_left[WRITE].clone(), rw_inc(WRITE));
+            });
+            cb.require_zero(
+                "copies are finished in the last step",
+                meta.query_fixed(q_last, Rotation::cur())
+                    * is_event(WRITE)
+                    * (1.expr() - is_last[WRITE].clone()),
+            );
+
+            cb.gate(meta.query_fixed(q_step, Rotation::cur()))
+        });
+
+        // Returns the condition of a lookup at a read or write row, which is
+        // enabled when the tag of the row is `tag` and the row isn't padded.
+        let condition = |meta: &mut VirtualCells<F>, tag: CopyDataType, rotation| {
+            meta.query_fixed(q_step, Rotation::cur())
+                * tag_bits.value_equals_among(meta, tag as usize, &TAGS, rotation)
+                * (1.expr() - meta.query_advice(is_pad, rotation))
+        };
+
+        for (name, tag, is_write, rotation) in [
+            ("Memory read", CopyDataType::Memory, false, Rotation::cur()),
+            ("Memory write", CopyDataType::Memory, true, Rotation::next()),
+        ] {
+            meta.lookup_any(name, |meta| {
+                let condition = condition(meta, tag, rotation);
+                vec![
+                    meta.query_advice(copy_table.rw_counter, rotation),
+                    is_write.expr(),
+                    RwTableTag::Memory.expr(),
+                    meta.query_advice(copy_table.id, rotation),
+                    0.expr(),
+                    meta.query_advice(copy_table.addr, rotation),
+                    0.expr(),
+                    meta.query_advice(value, rotation),
+                    0.expr(),
+                    0.expr(),
+                    0.expr(),
+                ]
+                .into_iter()
+                .zip(rw_table.table_exprs(meta))
+                .map(|(input, table)| (condition.clone() * input, table))
+                .collect()
+            });
+        }
+        meta.lookup_any("Tx log write", |meta| {
+            let condition = condition(meta, CopyDataType::TxLog, Rotation::next());
+            vec![
+                meta.query_advice(copy_table.rw_counter, Rotation::next()),
+                1.expr(),
+                RwTableTag::TxLog.expr(),
+                meta.query_advice(copy_table.id, Rotation::next()),
+                meta.query_advice(copy_table.log_id, Rotation::next()),
+                (TxLogFieldTag::Data as u64).expr(),
+                meta.query_advice(copy_table.addr, Rotation::next()),
+                meta.query_advice(value, Rotation::next()),
+                0.expr(),
+                0.expr(),
+                0.expr(),
+            ]
+            .into_iter()
+            .zip(rw_table.table_exprs(meta))
+            .map(|(input, table)| (condition.clone() * input, table))
+            .collect()
+        });
+        for (name, rotation) in [
+            ("Bytecode read", Rotation::cur()),
+            ("Bytecode write", Rotation::next()),
+        ] {
+            meta.lookup_any(name, |meta| {
+                let condition = condition(meta, CopyDataType::Bytecode, rotation);
+                vec![
+                    meta.query_advice(copy_table.id, rotation),
+                    meta.query_advice(copy_table.addr, rotation),
+                    meta.query_advice(value, rotation),
+                    meta.query_advice(is_code, rotation),
+                ]
+                .into_iter()
+                .zip(bytecode_table.table_exprs(meta))
+                .map(|(input, table)| (condition.clone() * input, table))
+                .collect()
+            });
+        }
+        meta.lookup_any("Tx call data read", |meta| {
+            let condition = condition(meta, CopyDataType::TxCalldata, Rotation::cur());
+            vec![
+                meta.query_advice(copy_table.id, Rotation::cur()),
+                TxContextFieldTag::CallData.expr(),
+                meta.query_advice(copy_table.addr, Rotation::cur()),
+                meta.query_advice(value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(tx_table.table_exprs(meta))
+            .map(|(input, table)| (condition.clone() * input, table))
+            .collect()
+        });
+        for column in addr_diff {
+            meta.lookup_any("Address difference byte", |meta| {
+                vec![(
+                    meta.query_fixed(q_step, Rotation::cur())
+                        * meta.query_advice(column, Rotation::cur()),
+                    meta.query_fixed(u8_table, Rotation::cur()),
+                )]
+            });
+        }
+
+        Self {
+            q_step,
+            q_first,
+            q_last,
+            copy_table,
+            is_last,
+            value,
+            value_acc,
+            is_pad,
+            is_code,
+            is_reads_first,
+            tag_bits,
+            addr_diff,
+            u8_table,
+            tx_table,
+            rw_table,
+            bytecode_table,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Returns the table of the data of the tx logs written by the copies.
+    pub fn log_data_table(&self) -> LogDataTable {
+        LogDataTable {
+            q_step: self.q_step,
+            // The tag of tx logs, 0b100, is the only one with its third bit set
+            is_tx_log: self.tag_bits.bits[2],
+            copy_table: self.copy_table,
+        }
+    }
+
+    pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "u8 table",
+            |mut region| {
+                for byte in 0..=u8::MAX {
+                    region.assign_fixed(
+                        || "u8 table",
+                        self.u8_table,
+                        byte as usize,
+                        || Ok(F::from(byte as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the copies in `max_copy_rows` rows, followed by padding steps.
+    /// Returns an error if they don't fit with at least one padding step,
+    /// whose all-zero rows are looked up by the disabled copy lookups.
+    #[tracing::instrument(name = "copy_circuit_assign", skip_all)]
+    pub fn assign_events(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        events: &[CopyEvent],
+        randomness: F,
+        max_copy_rows: usize,
+    ) -> Result<(), Error> {
+        let rows = events
+            .iter()
+            .flat_map(|event| event.rows(randomness))
+            .collect::<Vec<_>>();
+        let n_rows = max_copy_rows / 2 * 2;
+        if rows.len() + 2 > n_rows {
+            return Err(Error::Synthesis);
+        }
+
+        let tag_bits_chip = BinaryNumberChip::construct(self.tag_bits);
+        layouter.assign_region(
+            || "copy circuit",
+            |mut region| {
+                let padding = CopyRow::default();
+                for offset in 0..n_rows {
+                    let row = rows.get(offset).unwrap_or(&padding);
+                    let is_read = offset % 2 == 0;
+
+                    for (name, column, value) in [
+                        ("q_step", self.q_step, is_read),
+                        ("q_first", self.q_first, offset == 0),
+                        ("q_last", self.q_last, offset + 2 == n_rows),
+                    ] {
+                        region.assign_fixed(
+                            || format!("copy circuit {} {}", name, offset),
+                            column,
+                            offset,
+                            || Ok(F::from(value as u64)),
+                        )?;
+                    }
+
+                    self.copy_table.assign(&mut region, offset, row)?;
+                    for (name, column, value) in [
+                        ("is_last", self.is_last, F::from(row.is_last as u64)),
+                        ("value", self.value, F::from(row.value as u64)),
+                        ("value_acc", self.value_acc, row.value_acc),
+                        ("is_pad", self.is_pad, F::from(row.is_pad as u64)),
+                        ("is_code", self.is_code, F::from(row.is_code as u64)),
+                        (
+                            "is_reads_first",
+                            self.is_reads_first,
+                            F::from(row.is_reads_first as u64),
+                        ),
+                    ] {
+                        region.assign_advice(
+                            || format!("copy circuit {} {}", name, offset),
+                            column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                    tag_bits_chip.assign(
+                        &mut region,
+                        offset,
+                        &row.tag.map_or(PADDING_TAG, |tag| tag as usize),
+                    )?;
+
+                    let addr_diff = match (is_read, row.tag, row.is_pad) {
+                        (true, Some(_), true) => row.addr - row.src_addr_end,
+                        (true, Some(_), false) => row.src_addr_end - 1 - row.addr,
+                        _ => 0,
+                    };
+                    for (idx, column) in self.addr_diff.iter().enumerate() {
+                        region.assign_advice(
+                            || format!("copy circuit addr_diff {}", offset),
+                            *column,
+                            offset,
+                            || Ok(F::from((addr_diff >> (8 * idx)) & 0xff)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn load_tables(&self, layouter: &mut impl Layouter<F>, block: &Block<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "tx table",
+            |mut region| {
+                self.tx_table.assign(&mut region, 0, [F::zero(); 4])?;
+                for (offset, row) in block
+                    .txs
+                    .iter()
+                    .flat_map(|tx| tx.table_assignments(block.randomness))
+                    .enumerate()
+                {
+                    self.tx_table.assign(&mut region, offset + 1, row)?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_region(
+            || "rw table",
+            |mut region| {
+                self.rw_table.assign(&mut region, 0, &Default::default())?;
+                for (offset, rw) in block.rws.0.values().flatten().enumerate() {
+                    self.rw_table.assign(
+                        &mut region,
+                        offset + 1,
+                        &rw.table_assignment(block.randomness),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_region(
+            || "bytecode table",
+            |mut region| {
+                let rows = block
+                    .bytecodes
+                    .iter()
+                    .flat_map(|bytecode| bytecode.table_assignments(block.randomness));
+                for (offset, row) in std::iter::once([F::zero(); 4]).chain(rows).enumerate() {
+                    self.bytecode_table.assign(&mut region, offset, row)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// The table of the data of the tx logs written by the copy circuit, whose
+/// rows are the `(tx_id, log_id, length, data_rlc)` of the copies to a tx log,
+/// at their first read row.  The rows of the other copies and of the padding
+/// are all zeros.
+#[derive(Clone, Copy, Debug)]
+pub struct LogDataTable {
+    q_step: Column<Fixed>,
+    is_tx_log: Column<Advice>,
+    copy_table: CopyTable,
+}
+
+impl<F: Field> LookupTable<F, 4> for LogDataTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        vec![
+            self.q_step.into(),
+            self.is_tx_log.into(),
+            self.copy_table.is_first.into(),
+            self.copy_table.id.into(),
+            self.copy_table.log_id.into(),
+            self.copy_table.bytes_left.into(),
+            self.copy_table.rlc_acc.into(),
+        ]
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        [
+            "q_step",
+            "is_tx_log",
+            "is_first",
+            "id",
+            "log_id",
+            "bytes_left",
+            "rlc_acc",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 4] {
+        let condition = meta.query_fixed(self.q_step, Rotation::cur())
+            * meta.query_advice(self.copy_table.is_first, Rotation::cur())
+            * meta.query_advice(self.is_tx_log, Rotation::next());
+        [
+            meta.query_advice(self.copy_table.id, Rotation::next()),
+            meta.query_advice(self.copy_table.log_id, Rotation::next()),
+            meta.query_advice(self.copy_table.bytes_left, Rotation::cur()),
+            meta.query_advice(self.copy_table.rlc_acc, Rotation::cur()),
+        ]
+        .map(|value| condition.clone() * value)
+    }
+}
+
+/// Copy circuit that proves the copies of bytes of a block.
+#[derive(Clone, Default, Debug)]
+pub struct CopyCircuit<F> {
+    /// Number of rows of the copies, two per byte copied, and of the padding
+    pub max_copy_rows: usize,
+    /// Block with the copy events, and the tables they look up
+    pub block: Block<F>,
+}
+
+impl<F: Field> Circuit<F> for CopyCircuit<F> {
+    type Config = CopyCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            max_copy_rows: self.max_copy_rows,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let copy_table = CopyTable::construct(meta);
+        let tx_table = TxTable::construct(meta);
+        let rw_table = RwTable::construct(meta);
+        let bytecode_table = BytecodeTable::construct(meta);
+
+        let [randomness] = power_of_randomness_from_instance::<_, 1>(meta);
+
+        CopyCircuitConfig::configure(
+            meta,
+            randomness,
+            copy_table,
+            tx_table,
+            rw_table,
+            bytecode_table,
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load(&mut layouter)?;
+        config.load_tables(&mut layouter, &self.block)?;
+        config.assign_events(
+            &mut layouter,
+            &self.block.copy_events,
+            self.block.randomness,
+            self.max_copy_rows,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CopyCircuit, CopyCircuitConfig};
+    use crate::evm_circuit::{
+        table::{CopyDataType, LookupTable, RwTableTag, TxLogFieldTag},
+        witness::{Block, Bytecode, CopyEvent, Rw, RwMap, Transaction},
+    };
+    use bus_mapping::evm::OpcodeId;
+    use eth_types::Word;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::{MockProver, VerifyFailure},
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed},
+        poly::Rotation,
+    };
+    use pairing::bn256::Fr;
+    use std::collections::HashMap;
+
+    const K: u32 = 9;
+    const MAX_COPY_ROWS: usize = 200;
+
+    fn verify(block: Block<Fr>) -> Result<(), Vec<VerifyFailure>> {
+        let randomness = block.randomness;
+        let circuit = CopyCircuit {
+            max_copy_rows: MAX_COPY_ROWS,
+            block,
+        };
+        let prover =
+            MockProver::<Fr>::run(K, &circuit, vec![vec![randomness; (1 << K) - 64]]).unwrap();
+        prover.verify()
+    }
+
+    // Returns the rw operations done by the copy events, which read all the
+    // bytes before writing them when copying from memory to memory.
+    fn rws(copy_events: &[CopyEvent]) -> RwMap {
+        let mut rws = HashMap::<_, Vec<_>>::new();
+        for event in copy_events {
+            let mut rw_counter = event.rw_counter_start;
+            for (idx, (byte, _)) in event.bytes.iter().enumerate() {
+                if event.is_reads_first() {
+                    rw_counter = event.rw_counter_start + idx;
+                }
+                let src_addr = event.src_addr + idx as u64;
+                if event.src_type == CopyDataType::Memory && src_addr < event.src_addr_end {
+                    rws.entry(RwTableTag::Memory).or_default().push(Rw::Memory {
+                        rw_counter,
+                        is_write: false,
+                        call_id: event.src_id.as_usize(),
+                        memory_address: src_addr,
+                        byte: *byte,
+                    });
+                    rw_counter += 1;
+                }
+                if event.is_reads_first() {
+                    rw_counter = event.rw_counter_start + event.bytes.len() + idx;
+                }
+                let dst_addr = event.dst_addr + idx as u64;
+                let (tag, rw) = match event.dst_type {
+                    CopyDataType::Memory => (
+                        RwTableTag::Memory,
+                        Rw::Memory {
+                            rw_counter,
+                            is_write: true,
+                            call_id: event.dst_id.as_usize(),
+                            memory_address: dst_addr,
+                            byte: *byte,
+                        },
+                    ),
+                    CopyDataType::TxLog => (
+                        RwTableTag::TxLog,
+                        Rw::TxLog {
+                            rw_counter,
+                            is_write: true,
+                            tx_id: event.dst_id.as_usize(),
+                            log_id: event.log_id as usize,
+                            field_tag: TxLogFieldTag::Data,
+                            index: dst_addr as usize,
+                            value: Word::from(*byte),
+                        },
+                    ),
+                    _ => unreachable!(),
+                };
+                rws.entry(tag).or_default().push(rw);
+                rw_counter += 1;
+            }
+        }
+        RwMap(rws)
+    }
+
+    fn block(copy_events: Vec<CopyEvent>) -> Block<Fr> {
+        Block {
+            randomness: Fr::from(0x10000),
+            rws: rws(&copy_events),
+            copy_events,
+            ..Default::default()
+        }
+    }
+
+    fn memory_copy(bytes: &[u8], n_padding: usize) -> CopyEvent {
+        CopyEvent {
+            src_type: CopyDataType::Memory,
+            src_id: Word::from(1),
+            src_addr: 0x40,
+            src_addr_end: 0x40 + (bytes.len() - n_padding) as u64,
+            dst_type: CopyDataType::Memory,
+            dst_id: Word::from(1),
+            dst_addr: 0x100,
+            log_id: 0,
+            rw_counter_start: 1,
+            bytes: bytes.iter().map(|byte| (*byte, false)).collect(),
+        }
+    }
+
+    #[test]
+    fn copy_circuit_memory_to_memory() {
+        let event = memory_copy(&[1, 2, 3, 0, 0], 0);
+        assert_eq!(event.rw_inc(), 10);
+        assert_eq!(verify(block(vec![event])), Ok(()));
+
+        // The destination overlaps the source, whose bytes are all read first
+        let event = CopyEvent {
+            dst_addr: 0x42,
+            ..memory_copy(&[1, 2, 3, 1, 2], 0)
+        };
+        let rws = rws(&[event.clone()]);
+        let memory_rws = &rws.0[&RwTableTag::Memory];
+        let is_write = |rw: &Rw| matches!(rw, Rw::Memory { is_write: true, .. });
+        assert!(memory_rws[..5].iter().all(|rw| !is_write(rw)));
+        assert!(memory_rws[5..].iter().all(is_write));
+        assert_eq!(verify(block(vec![event])), Ok(()));
+
+        // The bytes read from memory aren't padded
+        assert!(verify(block(vec![memory_copy(&[1, 2, 3, 0, 0], 2)])).is_err());
+    }
+
+    #[cfg(feature = "real-prover")]
+    #[test]
+    fn copy_circuit_memory_to_memory_real_prover() {
+        let block = block(vec![memory_copy(&[1, 2, 3, 0, 0], 0)]);
+        let randomness = vec![vec![block.randomness; (1 << K) - 64]];
+        let circuit = CopyCircuit {
+            max_copy_rows: MAX_COPY_ROWS,
+            block,
+        };
+        crate::test_util::run_real_prover(K, &circuit, randomness).unwrap();
+    }
+
+    #[test]
+    fn copy_circuit_bytecode_and_call_data() {
+        let bytecode = Bytecode::new(vec![
+            OpcodeId::PUSH1.as_u8(),
+            OpcodeId::JUMPDEST.as_u8(),
+            OpcodeId::JUMPDEST.as_u8(),
+        ]);
+        let tx = Transaction {
+            id: 1,
+            call_data: vec![0xca, 0xfe],
+            call_data_length: 2,
+            ..Default::default()
+        };
+        let copy_events = vec![
+            CopyEvent {
+                src_type: CopyDataType::Bytecode,
+                src_id: bytecode.hash,
+                src_addr: 0,
+                src_addr_end: 3,
+                dst_type: CopyDataType::Memory,
+                dst_id: Word::from(1),
+                dst_addr: 0,
+                log_id: 0,
+                rw_counter_start: 1,
+                bytes: vec![
+                    (OpcodeId::PUSH1.as_u8(), true),
+                    (OpcodeId::JUMPDEST.as_u8(), false),
+                    (OpcodeId::JUMPDEST.as_u8(), true),
+                    (0, false),
+                ],
+            },
+            CopyEvent {
+                src_type: CopyDataType::TxCalldata,
+                src_id: Word::from(1),
+                src_addr: 1,
+                src_addr_end: 2,
+                dst_type: CopyDataType::TxLog,
+                dst_id: Word::from(1),
+                dst_addr: 0,
+                log_id: 1,
+                rw_counter_start: 5,
+                bytes: vec![(0xfe, false), (0, false)],
+            },
+        ];
+        let block = Block {
+            txs: vec![tx],
+            bytecodes: vec![bytecode],
+            ..block(copy_events)
+        };
+        assert_eq!(verify(block), Ok(()));
+    }
+
+    #[test]
+    fn copy_circuit_invalid_byte() {
+        let mut block = block(vec![memory_copy(&[1, 2, 3], 0)]);
+        // Write a byte different from the one read
+        let memory_rws = block.rws.0.get_mut(&RwTableTag::Memory).unwrap();
+        if let Some(Rw::Memory { byte, .. }) = memory_rws.last_mut() {
+            *byte = 4;
+        }
+        assert!(verify(block).is_err());
+    }
+
+    #[test]
+    fn copy_circuit_invalid_padding() {
+        // Read a non-zero byte after the end of the source
+        let mut event = CopyEvent {
+            dst_type: CopyDataType::TxLog,
+            log_id: 1,
+            ..memory_copy(&[1, 2, 0], 1)
+        };
+        assert_eq!(verify(block(vec![event.clone()])), Ok(()));
+        event.bytes[2].0 = 3;
+        assert!(verify(block(vec![event])).is_err());
+    }
+
+    #[test]
+    fn copy_circuit_too_many_bytes() {
+        let event = memory_copy(&[1; MAX_COPY_ROWS / 2], 0);
+        let circuit = CopyCircuit {
+            max_copy_rows: MAX_COPY_ROWS,
+            block: block(vec![event]),
+        };
+        assert!(MockProver::<Fr>::run(K, &circuit, vec![vec![Fr::zero(); (1 << K) - 64]]).is_err());
+    }
+
+    // Copy circuit which looks up `log_data` in its log data table.
+    #[derive(Default)]
+    struct LogDataCircuit {
+        copy_circuit: CopyCircuit<Fr>,
+        log_data: Vec<[Fr; 4]>,
+    }
+
+    impl Circuit<Fr> for LogDataCircuit {
+        type Config = (CopyCircuitConfig<Fr>, Column<Fixed>, [Column<Advice>; 4]);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let config = CopyCircuit::<Fr>::configure(meta);
+            let q_enable = meta.fixed_column();
+            let values = [(); 4].map(|_| meta.advice_column());
+            meta.lookup_any("Log data", |meta| {
+                let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+                values
+                    .iter()
+                    .zip(config.log_data_table().table_exprs(meta))
+                    .map(|(column, table)| {
+                        (
+                            q_enable.clone() * meta.query_advice(*column, Rotation::cur()),
+                            table,
+                        )
+                    })
+                    .collect()
+            });
+            (config, q_enable, values)
+        }
+
+        fn synthesize(
+            &self,
+            (config, q_enable, values): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            self.copy_circuit
+                .synthesize(config, layouter.namespace(|| "copy circuit"))?;
+            layouter.assign_region(
+                || "log data",
+                |mut region| {
+                    for (offset, row) in self.log_data.iter().enumerate() {
+                        region.assign_fixed(|| "q_enable", q_enable, offset, || Ok(Fr::one()))?;
+                        for (column, value) in values.iter().zip(row) {
+                            region.assign_advice(|| "log data", *column, offset, || Ok(*value))?;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn copy_circuit_log_data() {
+        let event = CopyEvent {
+            dst_type: CopyDataType::TxLog,
+            dst_id: Word::from(1),
+            dst_addr: 0,
+            log_id: 2,
+            ..memory_copy(&[1, 2, 3], 0)
+        };
+        let block = block(vec![event.clone()]);
+        let log_data = event.log_data_row(block.randomness).unwrap();
+        // The randomness is 0x10000
+        assert_eq!(log_data, [1u64, 2, 3, 0x1_0002_0003].map(Fr::from));
+        assert_eq!(
+            memory_copy(&[1, 2, 3], 0).log_data_row(block.randomness),
+            None
+        );
+
+        let verify = |log_data| {
+            let randomness = block.randomness;
+            let circuit = LogDataCircuit {
+                copy_circuit: CopyCircuit {
+                    max_copy_rows: MAX_COPY_ROWS,
+                    block: block.clone(),
+                },
+                log_data,
+            };
+            MockProver::<Fr>::run(K, &circuit, vec![vec![randomness; (1 << K) - 64]])
+                .unwrap()
+                .verify()
+        };
+        assert_eq!(verify(vec![log_data]), Ok(()));
+        // Neither another log nor other data are in the table
+        assert!(verify(vec![[1u64, 1, 3, 0x1_0002_0003].map(Fr::from)]).is_err());
+        assert!(verify(vec![[1u64, 2, 3, 0x1_0002_0004].map(Fr::from)]).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+#![allow(missing_docs)]
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Region,
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::evm_circuit::{table::LookupTable, witness::CopyRow};
+
+/// The copy table exported by the copy circuit, and looked up by the evm
+/// circuit.  Each byte copied has a read row followed by a write row, and a
+/// copy is looked up at its first read row, with its destination in the
+/// following write row.
+#[derive(Clone, Copy, Debug)]
+pub struct CopyTable {
+    pub is_first: Column<Advice>,
+    pub id: Column<Advice>,
+    pub tag: Column<Advice>,
+    pub addr: Column<Advice>,
+    pub src_addr_end: Column<Advice>,
+    pub log_id: Column<Advice>,
+    pub bytes_left: Column<Advice>,
+    pub rlc_acc: Column<Advice>,
+    pub rw_counter: Column<Advice>,
+    pub rw_inc_left: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F, 13> for CopyTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        [
+            self.is_first,
+            self.id,
+            self.tag,
+            self.addr,
+            self.src_addr_end,
+            self.log_id,
+            self.bytes_left,
+            self.rlc_acc,
+            self.rw_counter,
+            self.rw_inc_left,
+        ]
+        .map(Into::into)
+        .to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        [
+            "is_first",
+            "id",
+            "tag",
+            "addr",
+            "src_addr_end",
+            "log_id",
+            "bytes_left",
+            "rlc_acc",
+            "rw_counter",
+            "rw_inc_left",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 13] {
+        [
+            meta.query_advice(self.is_first, Rotation::cur()),
+            meta.query_advice(self.id, Rotation::cur()),
+            meta.query_advice(self.tag, Rotation::cur()),
+            meta.query_advice(self.id, Rotation::next()),
+            meta.query_advice(self.tag, Rotation::next()),
+            meta.query_advice(self.addr, Rotation::cur()),
+            meta.query_advice(self.src_addr_end, Rotation::cur()),
+            meta.query_advice(self.addr, Rotation::next()),
+            meta.query_advice(self.log_id, Rotation::next()),
+            meta.query_advice(self.bytes_left, Rotation::cur()),
+            meta.query_advice(self.rlc_acc, Rotation::cur()),
+            meta.query_advice(self.rw_counter, Rotation::cur()),
+            meta.query_advice(self.rw_inc_left, Rotation::cur()),
+        ]
+    }
+}
+impl CopyTable {
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            is_first: meta.advice_column(),
+            id: meta.advice_column(),
+            tag: meta.advice_column(),
+            addr: meta.advice_column(),
+            src_addr_end: meta.advice_column(),
+            log_id: meta.advice_column(),
+            bytes_left: meta.advice_column(),
+            rlc_acc: meta.advice_column(),
+            rw_counter: meta.advice_column(),
+            rw_inc_left: meta.advice_column(),
+        }
+    }
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: &CopyRow<F>,
+    ) -> Result<(), Error> {
+        for (column, value) in [
+            (self.is_first, F::from(row.is_first as u64)),
+            (self.id, row.id),
+            (self.tag, F::from(row.tag.map_or(0, |tag| tag as u64))),
+            (self.addr, F::from(row.addr)),
+            (self.src_addr_end, F::from(row.src_addr_end)),
+            (self.log_id, F::from(row.log_id)),
+            (self.bytes_left, F::from(row.bytes_left)),
+            (self.rlc_acc, row.rlc_acc),
+            (self.rw_counter, F::from(row.rw_counter)),
+            (self.rw_inc_left, F::from(row.rw_inc_left)),
+        ] {
+            region.assign_advice(
+                || "assign copy row on copy table",
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+}
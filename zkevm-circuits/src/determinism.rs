@@ -0,0 +1,240 @@
+//! Checker of the determinism of the circuit inputs and of the witness tables
+//! built from a block.
+//!
+//! The circuit inputs of a block are built several times, possibly on several
+//! threads at once, and the operation containers and the witness tables of all
+//! the runs are compared, so that an iteration over a `HashMap` or any other
+//! source of nondeterminism sneaking into the witnesses is caught.
+
+use crate::evm_circuit::witness::{block_convert, Block};
+use bus_mapping::{mock::BlockData, operation::OperationContainer};
+use eth_types::geth_types::GethData;
+use pairing::bn256::Fr as Fp;
+use std::{fmt, sync::Arc, thread};
+
+/// Randomness of the random linear combinations of the snapshots, which
+/// replaces the random one of the witness block so that runs are comparable.
+const RANDOMNESS: u64 = 0x100;
+
+/// Error of a determinism check.
+#[derive(Debug)]
+pub enum DeterminismError {
+    /// Building the circuit inputs failed.
+    Build(bus_mapping::Error),
+    /// A thread building the circuit inputs panicked.
+    Panic,
+    /// The part `what` of the run `run` differs from the first run.
+    Mismatch {
+        /// Index of the run, the first one being 0
+        run: usize,
+        /// Name of the part that differs
+        what: &'static str,
+    },
+}
+
+impl fmt::Display for DeterminismError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Build(err) => write!(f, "building the circuit inputs failed: {}", err),
+            Self::Panic => write!(f, "building the circuit inputs panicked"),
+            Self::Mismatch { run, what } => {
+                write!(f, "{} of run {} differs from the first run", what, run)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DeterminismError {}
+
+impl From<bus_mapping::Error> for DeterminismError {
+    fn from(err: bus_mapping::Error) -> Self {
+        Self::Build(err)
+    }
+}
+
+/// Circuit inputs and witness tables built from a block, with a fixed
+/// randomness.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// Operations of the circuit input builder
+    pub container: OperationContainer,
+    /// Debug output of the transactions of the witness block, which aren't
+    /// comparable otherwise
+    pub txs: String,
+    /// Rows of the rw table
+    pub rw_table: Vec<[Fp; 11]>,
+    /// Rows of the tx table
+    pub tx_table: Vec<[Fp; 4]>,
+    /// Rows of the bytecode table
+    pub bytecode_table: Vec<[Fp; 4]>,
+    /// Rows of the block table
+    pub block_table: Vec<[Fp; 3]>,
+    /// Inputs of the keccak hashes
+    pub keccak_inputs: Vec<Vec<u8>>,
+}
+
+impl Snapshot {
+    /// Builds the circuit inputs of `geth_data` and the witness tables.
+    pub fn new(geth_data: GethData) -> Result<Self, bus_mapping::Error> {
+        let block_data = BlockData::new_from_geth_data(geth_data);
+        let mut builder = block_data.new_circuit_input_builder();
+        builder.handle_block(&block_data.eth_block, &block_data.geth_traces)?;
+        let mut block = block_convert(&builder.block, &builder.code_db);
+        block.randomness = Fp::from(RANDOMNESS);
+        Ok(Self::from_block(builder.block.container, &block))
+    }
+
+    fn from_block(container: OperationContainer, block: &Block<Fp>) -> Self {
+        let randomness = block.randomness;
+        Self {
+            container,
+            txs: format!("{:?}", block.txs),
+            rw_table: block
+                .rws
+                .table_assignments(randomness)
+                .iter()
+                .map(|row| {
+                    [
+                        row.rw_counter,
+                        row.is_write,
+                        row.tag,
+                        row.key1,
+                        row.key2,
+                        row.key3,
+                        row.key4,
+                        row.value,
+                        row.value_prev,
+                        row.aux1,
+                        row.aux2,
+                    ]
+                })
+                .collect(),
+            tx_table: block
+                .txs
+                .iter()
+                .flat_map(|tx| tx.table_assignments(randomness))
+                .collect(),
+            bytecode_table: block
+                .bytecodes
+                .iter()
+                .flat_map(|bytecode| bytecode.table_assignments(randomness))
+                .collect(),
+            block_table: block.context.table_assignments(randomness),
+            keccak_inputs: block.keccak_inputs.clone(),
+        }
+    }
+
+    /// Returns the name of the first part of `other` that differs from `self`.
+    pub fn diff(&self, other: &Self) -> Option<&'static str> {
+        if self.container != other.container {
+            Some("operation container")
+        } else if self.txs != other.txs {
+            Some("witness transactions")
+        } else if self.rw_table != other.rw_table {
+            Some("rw table")
+        } else if self.tx_table != other.tx_table {
+            Some("tx table")
+        } else if self.bytecode_table != other.bytecode_table {
+            Some("bytecode table")
+        } else if self.block_table != other.block_table {
+            Some("block table")
+        } else if self.keccak_inputs != other.keccak_inputs {
+            Some("keccak inputs")
+        } else {
+            None
+        }
+    }
+}
+
+/// Builds the circuit inputs of `geth_data` `runs` times and checks that the
+/// operation containers and the witness tables of all the runs are identical.
+/// The first run is done on the current thread and the others are spread
+/// over `threads` threads running at once, the current one if `threads` is 0.
+pub fn check_determinism(
+    geth_data: &GethData,
+    runs: usize,
+    threads: usize,
+) -> Result<(), DeterminismError> {
+    let first = Snapshot::new(geth_data.clone())?;
+    let others = runs.saturating_sub(1);
+
+    let snapshots = if threads == 0 {
+        (0..others)
+            .map(|_| Snapshot::new(geth_data.clone()))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        let geth_data = Arc::new(geth_data.clone());
+        let handles: Vec<_> = (0..threads)
+            .map(|index| {
+                let geth_data = Arc::clone(&geth_data);
+                // Runs index, index + threads, ... of the other runs.
+                let runs = (index..others).step_by(threads).count();
+                thread::spawn(move || {
+                    (0..runs)
+                        .map(|_| Snapshot::new(geth_data.as_ref().clone()))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+            })
+            .collect();
+        let mut snapshots = Vec::with_capacity(others);
+        for handle in handles {
+            snapshots.extend(handle.join().map_err(|_| DeterminismError::Panic)??);
+        }
+        snapshots
+    };
+
+    for (run, snapshot) in snapshots.iter().enumerate() {
+        if let Some(what) = first.diff(snapshot) {
+            return Err(DeterminismError::Mismatch { run: run + 1, what });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_determinism, Snapshot};
+    use eth_types::{bytecode, Word};
+    use halo2_proofs::arithmetic::Field as HaloField;
+    use pairing::bn256::Fr as Fp;
+
+    fn geth_data() -> eth_types::geth_types::GethData {
+        let code = bytecode! {
+            PUSH1(0x20)
+            PUSH1(0x40)
+            MSTORE
+            PUSH1(0x40)
+            MLOAD
+            PUSH1(0x01)
+            SLOAD
+            PUSH1(0x02)
+            SSTORE
+            STOP
+        };
+        mock::new_single_tx_trace_code(&code).unwrap()
+    }
+
+    #[test]
+    fn determinism_mock_block() {
+        check_determinism(&geth_data(), 2, 0).unwrap();
+        check_determinism(&geth_data(), 4, 3).unwrap();
+    }
+
+    #[test]
+    fn determinism_detects_mismatch() {
+        let snapshot = Snapshot::new(geth_data()).unwrap();
+        assert_eq!(snapshot.diff(&snapshot.clone()), None);
+
+        let mut tampered = snapshot.clone();
+        tampered.rw_table.swap(1, 2);
+        assert_eq!(snapshot.diff(&tampered), Some("rw table"));
+
+        let mut tampered = snapshot.clone();
+        tampered.container.stack.get_mut(0).unwrap().op_mut().value = Word::from(1);
+        assert_eq!(snapshot.diff(&tampered), Some("operation container"));
+
+        let mut tampered = snapshot.clone();
+        tampered.block_table[0][1] += Fp::one();
+        assert_eq!(snapshot.diff(&tampered), Some("block table"));
+    }
+}
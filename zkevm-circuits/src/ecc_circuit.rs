@@ -0,0 +1,657 @@
+//! The ecc circuit implementation.
+//!
+//! It computes the additions and the scalar multiplications of BN254 points
+//! in the elliptic curve chip, and assigns the ecc table of
+//! `(op, p_x_rlc, p_y_rlc, q_x_rlc, q_y_rlc, r_x_rlc, r_y_rlc)`, which is
+//! looked up by the ecAdd and ecMul precompile gadgets once the EVM circuit
+//! supports precompiles.  The secp256k1 operations are computed by the same
+//! chip inside the sig circuit, which only exposes the recovered signers.
+//! The inputs of ecPairing aren't supported yet.
+//!
+//! The chip uses incomplete formulas, so the circuit only proves the
+//! additions of points with different x coordinates, and the multiplications
+//! whose result isn't the point at infinity.  The coordinates of the results
+//! are not constrained to be less than the modulus yet.
+//!
+//! The circuit has a fixed capacity of `max_add_ops` additions and
+//! `max_mul_ops` multiplications, so that its proving key doesn't depend on
+//! the block.  The operations of each kind are followed by padding operations
+//! up to the capacity.
+
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    ecc_table::EccTable,
+    evm_circuit::{
+        table::{EccOpTag, LookupTable},
+        util::{constraint_builder::BaseConstraintBuilder, RandomLinearCombination},
+    },
+    gadget::{
+        ecc::{Curve, EccConfig, Point},
+        non_native::{to_le_bytes, NonNativeContext, N_BYTES, N_LIMBS},
+        range_check::RangeTable,
+    },
+    util::{power_of_randomness_from_instance, Expr},
+};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Selector, VirtualCells},
+    poly::Rotation,
+};
+use num::{BigUint, One, Zero};
+use std::fmt;
+
+// Number of bytes of a limb.
+const N_LIMB_BYTES: usize = N_BYTES / N_LIMBS;
+// Number of integers of a row of the ecc table: the coordinates of p, q and r.
+const N_INTEGERS: usize = 6;
+
+/// Number of powers of the randomness used by the ecc circuit, which are the
+/// ones needed by the random linear combination of a coordinate.
+pub const POW_RAND_SIZE: usize = N_BYTES - 1;
+
+const MAX_DEGREE: usize = 5;
+
+/// An operation on BN254 points, together with its result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum EccOp {
+    /// `r = p + q`
+    Add { p: Point, q: Point, r: Point },
+    /// `r = scalar * p`
+    Mul { p: Point, scalar: BigUint, r: Point },
+}
+
+impl EccOp {
+    /// Returns the addition `p + q`, or `None` if it can't be proven by the
+    /// circuit.
+    pub(crate) fn add(p: Point, q: Point) -> Option<Self> {
+        if !Self::is_supported_point(&p) || !Self::is_supported_point(&q) || p.x == q.x {
+            return None;
+        }
+        let r = p.add(&q)?;
+        Some(Self::Add { p, q, r })
+    }
+
+    /// Returns the multiplication `scalar * p`, or `None` if it can't be
+    /// proven by the circuit.
+    pub(crate) fn mul(p: Point, scalar: BigUint) -> Option<Self> {
+        if !Self::is_supported_point(&p) || scalar.bits() > (N_BYTES * 8) as u64 {
+            return None;
+        }
+        let r = p.mul(&scalar)?;
+        Some(Self::Mul { p, scalar, r })
+    }
+
+    fn is_supported_point(point: &Point) -> bool {
+        let p = point.curve.base_field().value();
+        point.curve == Curve::Bn254 && point.x < p && point.y < p && point.is_on_curve()
+    }
+
+    // Returns the addition of the generator and its double, which pads the
+    // additions.
+    fn padding_add() -> Self {
+        let generator = Curve::Bn254.generator();
+        Self::add(
+            generator.clone(),
+            generator.mul(&BigUint::from(2u64)).unwrap(),
+        )
+        .unwrap()
+    }
+
+    // Returns the multiplication of the generator by 1, which pads the
+    // multiplications.
+    fn padding_mul() -> Self {
+        Self::mul(Curve::Bn254.generator(), BigUint::one()).unwrap()
+    }
+
+    fn tag(&self) -> EccOpTag {
+        match self {
+            Self::Add { .. } => EccOpTag::Add,
+            Self::Mul { .. } => EccOpTag::Mul,
+        }
+    }
+
+    // Returns the coordinates of p, q and r, with the scalar and 0 as q for a
+    // multiplication.
+    fn integers(&self) -> [BigUint; N_INTEGERS] {
+        match self {
+            Self::Add { p, q, r } => [
+                p.x.clone(),
+                p.y.clone(),
+                q.x.clone(),
+                q.y.clone(),
+                r.x.clone(),
+                r.y.clone(),
+            ],
+            Self::Mul { p, scalar, r } => [
+                p.x.clone(),
+                p.y.clone(),
+                scalar.clone(),
+                BigUint::zero(),
+                r.x.clone(),
+                r.y.clone(),
+            ],
+        }
+    }
+
+    /// Returns the row of the operation in the ecc table.
+    pub(crate) fn table_assignment<F: Field>(&self, randomness: F) -> [F; 7] {
+        let [p_x, p_y, q_x, q_y, r_x, r_y] = self.integers().map(|value| {
+            RandomLinearCombination::random_linear_combine(
+                to_le_bytes::<N_BYTES>(&value),
+                randomness,
+            )
+        });
+        [F::from(self.tag() as u64), p_x, p_y, q_x, q_y, r_x, r_y]
+    }
+}
+
+/// Config of the ecc circuit.
+#[derive(Clone, Debug)]
+pub struct EccCircuitConfig<F> {
+    ecc: EccConfig<F>,
+    // Enable the rows of the additions and of the multiplications in the ecc
+    // table
+    q_add: Selector,
+    q_mul: Selector,
+    ecc_table: EccTable,
+    // Limbs of the coordinates of p, q and r, copied from the chip, except for
+    // q of the multiplications, whose limbs are those of the scalar and 0
+    limbs: [[Column<Advice>; N_LIMBS]; N_INTEGERS],
+    // Little endian bytes of the coordinates of p, q and r
+    bytes: [[Column<Advice>; N_BYTES]; N_INTEGERS],
+    u8_table: RangeTable<8>,
+}
+
+impl<F> Annotate for EccCircuitConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_config(owner, "ecc_table", &self.ecc_table);
+        annotations.annotate_config(owner, "ecc", &self.ecc);
+        for (idx, limbs) in self.limbs.iter().enumerate() {
+            annotations.annotate_all(owner, &format!("limbs_{}", idx), *limbs);
+        }
+        for (idx, bytes) in self.bytes.iter().enumerate() {
+            annotations.annotate_all(owner, &format!("bytes_{}", idx), *bytes);
+        }
+        annotations.annotate_config(owner, "u8_table", &self.u8_table);
+    }
+}
+
+impl<F: Field> EccCircuitConfig<F> {
+    /// Configure the ecc circuit, which assigns the rows of `ecc_table`.
+    /// `power_of_randomness` holds `r^1, .., r^31` of the randomness `r` of
+    /// the tables.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Expression<F>; POW_RAND_SIZE],
+        ecc_table: EccTable,
+    ) -> Self {
+        let u8_table = RangeTable::construct(meta);
+        let ecc = EccConfig::configure(meta, u8_table);
+
+        let q_add = meta.complex_selector();
+        let q_mul = meta.complex_selector();
+        let limbs = [(); N_INTEGERS].map(|_| [(); N_LIMBS].map(|_| meta.advice_column()));
+        let bytes = [(); N_INTEGERS].map(|_| [(); N_BYTES].map(|_| meta.advice_column()));
+
+        for column in limbs.iter().flatten() {
+            meta.enable_equality(*column);
+        }
+
+        let from_bytes = |meta: &mut VirtualCells<F>, bytes: &[Column<Advice>]| {
+            bytes.iter().rev().fold(0.expr(), |acc, byte| {
+                acc * 256.expr() + meta.query_advice(*byte, Rotation::cur())
+            })
+        };
+
+        meta.create_gate("ECC operation in ecc table", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            let q_add = meta.query_selector(q_add);
+            let q_mul = meta.query_selector(q_mul);
+
+            for (limbs, bytes) in limbs.iter().zip(bytes.iter()) {
+                for (limb, bytes) in limbs.iter().zip(bytes.chunks(N_LIMB_BYTES)) {
+                    cb.require_equal(
+                        "coordinate limb is decomposed into bytes",
+                        meta.query_advice(*limb, Rotation::cur()),
+                        from_bytes(meta, bytes),
+                    );
+                }
+            }
+
+            let [op, p_x_rlc, p_y_rlc, q_x_rlc, q_y_rlc, r_x_rlc, r_y_rlc] =
+                ecc_table.table_exprs(meta);
+            cb.require_equal(
+                "op is the tag of the operation",
+                op,
+                q_add.clone() * EccOpTag::Add.expr() + q_mul.clone() * EccOpTag::Mul.expr(),
+            );
+            for (bytes, value) in
+                bytes
+                    .iter()
+                    .zip([p_x_rlc, p_y_rlc, q_x_rlc, q_y_rlc.clone(), r_x_rlc, r_y_rlc])
+            {
+                cb.require_equal(
+                    "rlc is the RLC of the coordinate",
+                    value,
+                    RandomLinearCombination::random_linear_combine_expr(
+                        bytes.map(|byte| meta.query_advice(byte, Rotation::cur())),
+                        &power_of_randomness,
+                    ),
+                );
+            }
+            cb.condition(q_mul.clone(), |cb| {
+                cb.require_zero("q_y_rlc is 0 for a multiplication", q_y_rlc);
+            });
+
+            cb.gate(q_add + q_mul)
+        });
+
+        for byte in bytes.iter().flatten() {
+            meta.lookup_any("coordinate bytes are bytes", |meta| {
+                let q_enable = meta.query_selector(q_add) + meta.query_selector(q_mul);
+                let byte = meta.query_advice(*byte, Rotation::cur());
+                let [u8_table] = u8_table.table_exprs(meta);
+
+                vec![(q_enable * byte, u8_table)]
+            });
+        }
+
+        Self {
+            ecc,
+            q_add,
+            q_mul,
+            ecc_table,
+            limbs,
+            bytes,
+            u8_table,
+        }
+    }
+
+    pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.u8_table.load(layouter)
+    }
+
+    /// Computes the operations, and assigns the ecc table with their rows
+    /// after a row of zeros.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        ops: &[EccOp],
+        randomness: F,
+    ) -> Result<(), Error> {
+        let mut assigned_ops = Vec::with_capacity(ops.len());
+        for op in ops {
+            assigned_ops.push(layouter.assign_region(
+                || "ECC operation",
+                |mut region| {
+                    let mut ctx = NonNativeContext::new(&mut region);
+                    self.assign_op(&mut ctx, op)
+                },
+            )?);
+        }
+
+        layouter.assign_region(
+            || "ecc table",
+            |mut region| {
+                self.ecc_table.assign(&mut region, 0, [F::zero(); 7])?;
+                for (idx, (op, limbs)) in ops.iter().zip(assigned_ops.iter()).enumerate() {
+                    let offset = idx + 1;
+                    let selector = match op {
+                        EccOp::Add { .. } => self.q_add,
+                        EccOp::Mul { .. } => self.q_mul,
+                    };
+                    selector.enable(&mut region, offset)?;
+                    self.ecc_table
+                        .assign(&mut region, offset, op.table_assignment(randomness))?;
+
+                    for (columns, limbs) in self.limbs.iter().zip(limbs.iter()) {
+                        match limbs {
+                            Some(limbs) => {
+                                for (column, limb) in columns.iter().zip(limbs.iter()) {
+                                    limb.copy_advice(
+                                        || "coordinate limb",
+                                        &mut region,
+                                        *column,
+                                        offset,
+                                    )?;
+                                }
+                            }
+                            None => {
+                                for column in columns.iter() {
+                                    region.assign_advice(
+                                        || "coordinate limb",
+                                        *column,
+                                        offset,
+                                        || Ok(F::zero()),
+                                    )?;
+                                }
+                            }
+                        }
+                    }
+                    for (columns, value) in self.bytes.iter().zip(op.integers()) {
+                        for (column, byte) in columns.iter().zip(to_le_bytes::<N_BYTES>(&value)) {
+                            region.assign_advice(
+                                || "coordinate byte",
+                                *column,
+                                offset,
+                                || Ok(F::from(byte as u64)),
+                            )?;
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // Computes an operation, returning the limbs of the coordinates of p, q
+    // and r, with the limbs of the scalar and none as q for a multiplication.
+    #[allow(clippy::type_complexity)]
+    fn assign_op(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        op: &EccOp,
+    ) -> Result<[Option<[AssignedCell<F, F>; N_LIMBS]>; N_INTEGERS], Error> {
+        let (p, q, r) = match op {
+            EccOp::Add { p, q, .. } => {
+                let p = self.ecc.assign_point(ctx, p)?;
+                let q = self.ecc.assign_point(ctx, q)?;
+                let r = self.ecc.add_point(ctx, &p, &q)?;
+                (p, [Some(q.x.limbs), Some(q.y.limbs)], r)
+            }
+            EccOp::Mul { p, scalar, .. } => {
+                let p = self.ecc.assign_point(ctx, p)?;
+                let scalar = self.ecc.integer.assign_witness(ctx, scalar.clone())?;
+                let r = self.ecc.mul(ctx, &scalar, &p)?;
+                (p, [Some(scalar.limbs), None], r)
+            }
+        };
+        let [q_x, q_y] = q;
+        Ok([
+            Some(p.x.limbs),
+            Some(p.y.limbs),
+            q_x,
+            q_y,
+            Some(r.x.limbs),
+            Some(r.y.limbs),
+        ])
+    }
+}
+
+/// Error when the operations don't fit in the ecc circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EccCircuitError {
+    /// There are more additions than the circuit can hold.
+    TooManyAddOps {
+        /// Number of additions
+        n_ops: usize,
+        /// Maximum number of additions of the circuit
+        max_ops: usize,
+    },
+    /// There are more multiplications than the circuit can hold.
+    TooManyMulOps {
+        /// Number of multiplications
+        n_ops: usize,
+        /// Maximum number of multiplications of the circuit
+        max_ops: usize,
+    },
+}
+
+impl fmt::Display for EccCircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyAddOps { n_ops, max_ops } => write!(
+                f,
+                "too many additions for the ecc circuit: {} > max_add_ops {}",
+                n_ops, max_ops
+            ),
+            Self::TooManyMulOps { n_ops, max_ops } => write!(
+                f,
+                "too many multiplications for the ecc circuit: {} > max_mul_ops {}",
+                n_ops, max_ops
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EccCircuitError {}
+
+/// Ecc circuit that computes the operations on BN254 points looked up by the
+/// other circuits.
+#[derive(Clone, Default, Debug)]
+pub struct EccCircuit<F> {
+    /// Randomness of the RLC of the tables
+    pub randomness: F,
+    /// Operations to compute
+    pub(crate) ops: Vec<EccOp>,
+    /// Maximum number of additions of the circuit
+    pub max_add_ops: usize,
+    /// Maximum number of multiplications of the circuit
+    pub max_mul_ops: usize,
+}
+
+impl<F: Field> EccCircuit<F> {
+    // Returns the additions followed by padding ones up to `max_add_ops`, then
+    // the multiplications followed by padding ones up to `max_mul_ops`.
+    fn padded_ops(&self) -> Vec<EccOp> {
+        let [add_ops, mul_ops] = [EccOpTag::Add, EccOpTag::Mul].map(|tag| {
+            self.ops
+                .iter()
+                .filter(|op| op.tag() == tag)
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+        let n_add_padding = self.max_add_ops.saturating_sub(add_ops.len());
+        let n_mul_padding = self.max_mul_ops.saturating_sub(mul_ops.len());
+        add_ops
+            .into_iter()
+            .chain(std::iter::repeat(EccOp::padding_add()).take(n_add_padding))
+            .chain(mul_ops)
+            .chain(std::iter::repeat(EccOp::padding_mul()).take(n_mul_padding))
+            .collect()
+    }
+
+    /// Checks that the operations fit in the capacity of the circuit.
+    pub fn check_capacity(&self) -> Result<(), EccCircuitError> {
+        let n_ops = |tag| self.ops.iter().filter(|op| op.tag() == tag).count();
+        let n_add_ops = n_ops(EccOpTag::Add);
+        if n_add_ops > self.max_add_ops {
+            return Err(EccCircuitError::TooManyAddOps {
+                n_ops: n_add_ops,
+                max_ops: self.max_add_ops,
+            });
+        }
+        let n_mul_ops = n_ops(EccOpTag::Mul);
+        if n_mul_ops > self.max_mul_ops {
+            return Err(EccCircuitError::TooManyMulOps {
+                n_ops: n_mul_ops,
+                max_ops: self.max_mul_ops,
+            });
+        }
+        Ok(())
+    }
+
+    /// Assign the ecc circuit.
+    #[tracing::instrument(name = "ecc_circuit_assign", skip_all)]
+    pub fn assign(
+        &self,
+        config: &EccCircuitConfig<F>,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        self.check_capacity().map_err(|_| Error::Synthesis)?;
+
+        config.load(layouter)?;
+        config.assign(layouter, &self.padded_ops(), self.randomness)
+    }
+}
+
+impl<F: Field> Circuit<F> for EccCircuit<F> {
+    type Config = EccCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            max_add_ops: self.max_add_ops,
+            max_mul_ops: self.max_mul_ops,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let ecc_table = EccTable::construct(meta);
+        let power_of_randomness = power_of_randomness_from_instance::<_, POW_RAND_SIZE>(meta);
+
+        EccCircuitConfig::configure(meta, power_of_randomness, ecc_table)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        self.assign(&config, &mut layouter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EccCircuit, EccCircuitError, EccOp};
+    use crate::gadget::{
+        ecc::{Curve, Point},
+        non_native::Modulus,
+        rlc::PowersOfRandomness,
+    };
+    use halo2_proofs::dev::{MockProver, VerifyFailure};
+    use num::BigUint;
+    use pairing::bn256::Fr;
+
+    const K: u32 = 14;
+    const MAX_ADD_OPS: usize = 2;
+    const MAX_MUL_OPS: usize = 1;
+
+    fn point(scalar: u64) -> Point {
+        Curve::Bn254
+            .generator()
+            .mul(&BigUint::from(scalar))
+            .unwrap()
+    }
+
+    fn circuit(ops: Vec<EccOp>) -> (EccCircuit<Fr>, Vec<Vec<Fr>>) {
+        let circuit = EccCircuit {
+            randomness: Fr::from(0x10000),
+            ops,
+            max_add_ops: MAX_ADD_OPS,
+            max_mul_ops: MAX_MUL_OPS,
+        };
+        let power_of_randomness = PowersOfRandomness::new(circuit.randomness)
+            .take(31)
+            .into_iter()
+            .map(|power| vec![power; (1 << K) - 64])
+            .collect();
+        (circuit, power_of_randomness)
+    }
+
+    fn verify(ops: Vec<EccOp>) -> Result<(), Vec<VerifyFailure>> {
+        let (circuit, power_of_randomness) = circuit(ops);
+        let prover = MockProver::<Fr>::run(K, &circuit, power_of_randomness).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn bn254_generator_order() {
+        let r = Modulus::Bn254Fr.value();
+        assert_eq!(Curve::Bn254.generator().mul(&r), None);
+        assert_eq!(point(3).neg().add(&point(3)), None);
+    }
+
+    #[test]
+    fn ecc_op_unsupported() {
+        assert_eq!(EccOp::add(point(3), point(3)), None);
+        assert_eq!(EccOp::add(point(3), point(3).neg()), None);
+        assert_eq!(EccOp::mul(point(3), Modulus::Bn254Fr.value()), None);
+        assert_eq!(
+            EccOp::mul(Curve::Secp256k1.generator(), BigUint::from(2u64)),
+            None
+        );
+    }
+
+    #[test]
+    fn ecc_circuit_valid() {
+        let ops = vec![
+            EccOp::add(point(3), point(5)).unwrap(),
+            EccOp::mul(point(7), BigUint::from(0xcafeu64)).unwrap(),
+        ];
+        assert_eq!(verify(ops), Ok(()));
+    }
+
+    #[cfg(feature = "real-prover")]
+    #[test]
+    fn ecc_circuit_valid_real_prover() {
+        let (circuit, power_of_randomness) = circuit(vec![
+            EccOp::add(point(3), point(5)).unwrap(),
+            EccOp::mul(point(7), BigUint::from(0xcafeu64)).unwrap(),
+        ]);
+        crate::test_util::run_real_prover(K, &circuit, power_of_randomness).unwrap();
+    }
+
+    #[test]
+    fn ecc_circuit_padding() {
+        assert_eq!(verify(vec![]), Ok(()));
+    }
+
+    #[test]
+    fn ecc_circuit_invalid_add() {
+        let mut op = EccOp::add(point(3), point(5)).unwrap();
+        if let EccOp::Add { r, .. } = &mut op {
+            *r = point(9);
+        }
+        assert!(verify(vec![op]).is_err());
+    }
+
+    #[test]
+    fn ecc_circuit_invalid_mul() {
+        let mut op = EccOp::mul(point(7), BigUint::from(0xcafeu64)).unwrap();
+        if let EccOp::Mul { r, .. } = &mut op {
+            *r = r.neg();
+        }
+        assert!(verify(vec![op]).is_err());
+    }
+
+    #[test]
+    fn ecc_circuit_capacity() {
+        let circuit = EccCircuit::<Fr> {
+            ops: vec![
+                EccOp::add(point(1), point(2)).unwrap(),
+                EccOp::add(point(2), point(3)).unwrap(),
+                EccOp::mul(point(1), BigUint::from(2u64)).unwrap(),
+            ],
+            max_add_ops: MAX_ADD_OPS,
+            max_mul_ops: MAX_MUL_OPS,
+            ..Default::default()
+        };
+        assert_eq!(circuit.check_capacity(), Ok(()));
+        assert_eq!(
+            EccCircuit {
+                max_mul_ops: 0,
+                ..circuit.clone()
+            }
+            .check_capacity(),
+            Err(EccCircuitError::TooManyMulOps {
+                n_ops: 1,
+                max_ops: 0
+            })
+        );
+        assert_eq!(
+            EccCircuit {
+                max_add_ops: 1,
+                ..circuit
+            }
+            .check_capacity(),
+            Err(EccCircuitError::TooManyAddOps {
+                n_ops: 2,
+                max_ops: 1
+            })
+        );
+    }
+}
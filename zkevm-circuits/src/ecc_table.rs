@@ -0,0 +1,89 @@
+#![allow(missing_docs)]
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Region,
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::evm_circuit::table::LookupTable;
+
+/// The ecc table exported by the ecc circuit, and looked up by the circuits
+/// that need the result of an operation on BN254 points.  Each operation has a
+/// row `(op, p_x_rlc, p_y_rlc, q_x_rlc, q_y_rlc, r_x_rlc, r_y_rlc)`, with its
+/// `EccOpTag` and the random linear combinations of the little endian bytes of
+/// the coordinates of its operands `p` and `q` and of its result `r`.  For a
+/// multiplication, `q_x_rlc` is the RLC of the scalar and `q_y_rlc` is 0.  The
+/// first row is all zeros, so that disabled lookups always find a row.
+#[derive(Clone, Copy, Debug)]
+pub struct EccTable {
+    pub op: Column<Advice>,
+    pub p_x_rlc: Column<Advice>,
+    pub p_y_rlc: Column<Advice>,
+    pub q_x_rlc: Column<Advice>,
+    pub q_y_rlc: Column<Advice>,
+    pub r_x_rlc: Column<Advice>,
+    pub r_y_rlc: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F, 7> for EccTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        self.advice_columns().map(Into::into).to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        [
+            "op", "p_x_rlc", "p_y_rlc", "q_x_rlc", "q_y_rlc", "r_x_rlc", "r_y_rlc",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 7] {
+        self.advice_columns()
+            .map(|column| meta.query_advice(column, Rotation::cur()))
+    }
+}
+
+impl EccTable {
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            op: meta.advice_column(),
+            p_x_rlc: meta.advice_column(),
+            p_y_rlc: meta.advice_column(),
+            q_x_rlc: meta.advice_column(),
+            q_y_rlc: meta.advice_column(),
+            r_x_rlc: meta.advice_column(),
+            r_y_rlc: meta.advice_column(),
+        }
+    }
+
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 7],
+    ) -> Result<(), Error> {
+        for (column, value) in self.advice_columns().iter().zip(row) {
+            region.assign_advice(
+                || "assign ecc row on ecc table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn advice_columns(&self) -> [Column<Advice>; 7] {
+        [
+            self.op,
+            self.p_x_rlc,
+            self.p_y_rlc,
+            self.q_x_rlc,
+            self.q_y_rlc,
+            self.r_x_rlc,
+            self.r_y_rlc,
+        ]
+    }
+}
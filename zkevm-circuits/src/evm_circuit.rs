@@ -1,8 +1,9 @@
 //! The EVM circuit implementation.
 
 #![allow(missing_docs)]
-use halo2_proofs::{circuit::Layouter, plonk::*};
+use halo2_proofs::{circuit::Layouter, plonk::*, poly::Rotation};
 
+pub mod coverage;
 mod execution;
 pub mod param;
 mod step;
@@ -11,49 +12,119 @@ pub(crate) mod util;
 pub mod table;
 pub mod witness;
 
-use eth_types::Field;
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    fixed_table::FixedTable,
+    util::Expr,
+};
+use eth_types::{evm_types::Hardfork, Field};
 use execution::ExecutionConfig;
+use std::collections::HashMap;
 use table::{FixedTableTag, LookupTable};
-use witness::Block;
+use witness::{Block, CHUNK_STATE_SIZE};
 
 use self::param::STEP_HEIGHT;
+pub use self::step::ExecutionState;
+
+/// Number of steps and rows used by an execution state in a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionStateStats {
+    pub execution_state: ExecutionState,
+    pub num_steps: usize,
+    pub num_rows: usize,
+}
+
+/// Returns the begin and end states of the chunk proven by the EVM circuit,
+/// see `Chunk::instance`, queried from `2 * CHUNK_STATE_SIZE` instance columns
+/// holding them on all the rows, like the powers of the randomness.
+pub fn chunk_states_from_instance<F: Field>(
+    meta: &mut ConstraintSystem<F>,
+) -> [[Expression<F>; CHUNK_STATE_SIZE]; 2] {
+    let columns = [(); 2].map(|_| [(); CHUNK_STATE_SIZE].map(|_| meta.instance_column()));
+    let mut chunk_states = None;
+
+    meta.create_gate("chunk states from instance", |meta| {
+        chunk_states = Some(
+            columns
+                .map(|columns| columns.map(|column| meta.query_instance(column, Rotation::cur()))),
+        );
+
+        [0.expr()]
+    });
+
+    chunk_states.unwrap()
+}
 
 /// EvmCircuit implements verification of execution trace of a block.
 #[derive(Clone, Debug)]
 pub struct EvmCircuit<F> {
-    fixed_table: [Column<Fixed>; 4],
+    hardfork: Hardfork,
+    fixed_table: FixedTable,
     execution: ExecutionConfig<F>,
 }
 
+impl<F> Annotate for EvmCircuit<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_config(owner, "fixed_table", &self.fixed_table);
+        self.execution.annotate(owner, annotations);
+    }
+}
+
 impl<F: Field> EvmCircuit<F> {
-    /// Configure EvmCircuit
-    pub fn configure<TxTable, RwTable, BytecodeTable, BlockTable>(
+    /// Configure EvmCircuit with the gas rules of `hardfork`, which only
+    /// proves the blocks of a chain whose hardfork is `hardfork`, and the
+    /// begin and end states of the chunk it proves, see
+    /// `chunk_states_from_instance`
+    #[allow(clippy::too_many_arguments)]
+    pub fn configure<
+        TxTable,
+        RwTable,
+        BytecodeTable,
+        BlockTable,
+        KeccakTable,
+        CopyTable,
+        ExpTable,
+    >(
         meta: &mut ConstraintSystem<F>,
         power_of_randomness: [Expression<F>; 31],
+        chunk_states: [[Expression<F>; CHUNK_STATE_SIZE]; 2],
+        hardfork: Hardfork,
         tx_table: TxTable,
         rw_table: RwTable,
         bytecode_table: BytecodeTable,
         block_table: BlockTable,
+        keccak_table: KeccakTable,
+        copy_table: CopyTable,
+        exp_table: ExpTable,
     ) -> Self
     where
         TxTable: LookupTable<F, 4>,
         RwTable: LookupTable<F, 11>,
         BytecodeTable: LookupTable<F, 4>,
         BlockTable: LookupTable<F, 3>,
+        KeccakTable: LookupTable<F, 3>,
+        CopyTable: LookupTable<F, 13>,
+        ExpTable: LookupTable<F, 3>,
     {
-        let fixed_table = [(); 4].map(|_| meta.fixed_column());
+        let fixed_table = FixedTable::construct(meta);
 
         let execution = ExecutionConfig::configure(
             meta,
             power_of_randomness,
+            chunk_states,
+            hardfork,
             fixed_table,
             tx_table,
             rw_table,
             bytecode_table,
             block_table,
+            keccak_table,
+            copy_table,
+            exp_table,
         );
 
         Self {
+            hardfork,
             fixed_table,
             execution,
         }
@@ -65,41 +136,90 @@ impl<F: Field> EvmCircuit<F> {
         layouter: &mut impl Layouter<F>,
         fixed_table_tags: Vec<FixedTableTag>,
     ) -> Result<(), Error> {
-        layouter.assign_region(
-            || "fixed table",
-            |mut region| {
-                for (offset, row) in std::iter::once([F::zero(); 4])
-                    .chain(fixed_table_tags.iter().map(|tag| tag.build()).flatten())
-                    .enumerate()
-                {
-                    for (column, value) in self.fixed_table.iter().zip(row) {
-                        region.assign_fixed(|| "", *column, offset, || Ok(value))?;
-                    }
-                }
+        self.fixed_table.load(layouter, &fixed_table_tags)
+    }
 
-                Ok(())
-            },
-        )
+    /// Returns the hardfork the circuit is configured with.
+    pub fn hardfork(&self) -> Hardfork {
+        self.hardfork
+    }
+
+    // Checks that the block is processed under the gas rules the circuit is
+    // configured with, as bus-mapping and the gadgets would otherwise
+    // disagree on the gas costs.
+    fn check_chain_config(&self, block: &Block<F>) -> Result<(), Error> {
+        if block.chain_config.hardfork != self.hardfork {
+            return Err(Error::Synthesis);
+        }
+        Ok(())
     }
 
     /// Assign block
+    #[tracing::instrument(name = "evm_circuit_assign", skip_all)]
     pub fn assign_block(
         &self,
         layouter: &mut impl Layouter<F>,
         block: &Block<F>,
     ) -> Result<(), Error> {
+        self.check_chain_config(block)?;
         self.execution.assign_block(layouter, block)
     }
 
     /// Assign exact steps in block without padding for unit test purpose
+    #[tracing::instrument(name = "evm_circuit_assign", skip_all)]
     pub fn assign_block_exact(
         &self,
         layouter: &mut impl Layouter<F>,
         block: &Block<F>,
     ) -> Result<(), Error> {
+        self.check_chain_config(block)?;
         self.execution.assign_block_exact(layouter, block)
     }
 
+    /// Calculate the number of rows required to assign all the steps in block,
+    /// including the rows of the next step queried by the last one.
+    pub fn get_num_rows_required(block: &Block<F>) -> usize {
+        let num_steps = block.txs.iter().map(|tx| tx.steps.len()).sum::<usize>();
+        (num_steps + 1) * STEP_HEIGHT
+    }
+
+    /// Returns the values of the instance columns of the chunk states, see
+    /// `chunk_states_from_instance`, on `n_rows` rows: the ones of the chunk
+    /// of `block`, or zeros if the block isn't split into chunks.
+    pub fn chunk_states_instance(block: &Block<F>, n_rows: usize) -> Vec<Vec<F>> {
+        block
+            .chunk
+            .clone()
+            .unwrap_or_default()
+            .instance(block.randomness)
+            .into_iter()
+            .map(|value| vec![value; n_rows])
+            .collect()
+    }
+
+    /// Calculate the rows used by each execution state in block, sorted by
+    /// the number of rows in descending order.
+    pub fn get_execution_state_stats(block: &Block<F>) -> Vec<ExecutionStateStats> {
+        let mut num_steps = HashMap::new();
+        for step in block.txs.iter().flat_map(|tx| tx.steps.iter()) {
+            *num_steps.entry(step.execution_state).or_insert(0) += 1;
+        }
+
+        let mut stats = ExecutionState::iterator()
+            .filter_map(|execution_state| {
+                num_steps
+                    .get(&execution_state)
+                    .map(|num_steps| ExecutionStateStats {
+                        execution_state,
+                        num_steps: *num_steps,
+                        num_rows: num_steps * STEP_HEIGHT,
+                    })
+            })
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| b.num_rows.cmp(&a.num_rows));
+        stats
+    }
+
     /// Calculate which rows are "actually" used in the circuit
     pub fn get_active_rows(block: &Block<F>) -> (Vec<usize>, Vec<usize>) {
         let max_offset = block.txs.iter().map(|tx| tx.steps.len()).sum::<usize>() * STEP_HEIGHT;
@@ -115,28 +235,43 @@ impl<F: Field> EvmCircuit<F> {
 pub mod test {
 
     use crate::{
+        annotation::{Annotate, ColumnAnnotations},
+        block_table::BlockTable,
+        bytecode_table::BytecodeTable,
+        copy_table::CopyTable,
         evm_circuit::{
+            chunk_states_from_instance,
+            execution::{execution_gadget_name, EXECUTION_STEP_REGION},
             param::STEP_HEIGHT,
             table::FixedTableTag,
-            witness::{Block, BlockContext, Bytecode, RwMap, Transaction},
-            EvmCircuit,
+            witness::{
+                keccak_table_rows, Block, BlockContext, Bytecode, CopyEvent, CopyRow, ExpEvent,
+                RwMap, Transaction, CHUNK_STATE_SIZE,
+            },
+            EvmCircuit, ExecutionState,
         },
+        exp_table::ExpTable,
+        gadget::rlc::PowersOfRandomness,
+        keccak_table::KeccakTable,
         rw_table::RwTable,
-        util::Expr,
+        tx_table::TxTable,
+        util::power_of_randomness_from_instance,
+    };
+    use eth_types::{
+        evm_types::{Hardfork, OpcodeId},
+        Field, Word,
     };
-    use eth_types::{evm_types::GasCost, Field, Word};
     use halo2_proofs::{
-        arithmetic::BaseExt,
         circuit::{Layouter, SimpleFloorPlanner},
-        dev::{MockProver, VerifyFailure},
-        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
-        poly::Rotation,
+        dev::{FailureLocation, MockProver, VerifyFailure},
+        plonk::{Circuit, ConstraintSystem, Error},
     };
     use pairing::bn256::Fr as Fp;
     use rand::{
         distributions::uniform::{SampleRange, SampleUniform},
         random, thread_rng, Rng,
     };
+    use std::{collections::HashMap, fmt};
 
     pub(crate) fn rand_range<T, R>(range: R) -> T
     where
@@ -164,13 +299,29 @@ pub mod test {
 
     #[derive(Clone)]
     pub struct TestCircuitConfig<F> {
-        tx_table: [Column<Advice>; 4],
+        tx_table: TxTable,
         rw_table: RwTable,
-        bytecode_table: [Column<Advice>; 4],
-        block_table: [Column<Advice>; 3],
+        bytecode_table: BytecodeTable,
+        block_table: BlockTable,
+        keccak_table: KeccakTable,
+        copy_table: CopyTable,
+        exp_table: ExpTable,
         evm_circuit: EvmCircuit<F>,
     }
 
+    impl<F> Annotate for TestCircuitConfig<F> {
+        fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+            annotations.annotate_config(owner, "tx_table", &self.tx_table);
+            annotations.annotate_config(owner, "rw_table", &self.rw_table);
+            annotations.annotate_config(owner, "bytecode_table", &self.bytecode_table);
+            annotations.annotate_config(owner, "block_table", &self.block_table);
+            annotations.annotate_config(owner, "keccak_table", &self.keccak_table);
+            annotations.annotate_config(owner, "copy_table", &self.copy_table);
+            annotations.annotate_config(owner, "exp_table", &self.exp_table);
+            annotations.annotate_config(owner, "evm_circuit", &self.evm_circuit);
+        }
+    }
+
     impl<F: Field> TestCircuitConfig<F> {
         fn load_txs(
             &self,
@@ -182,26 +333,12 @@ pub mod test {
                 || "tx table",
                 |mut region| {
                     let mut offset = 0;
-                    for column in self.tx_table {
-                        region.assign_advice(
-                            || "tx table all-zero row",
-                            column,
-                            offset,
-                            || Ok(F::zero()),
-                        )?;
-                    }
+                    self.tx_table.assign(&mut region, offset, [F::zero(); 4])?;
                     offset += 1;
 
                     for tx in txs.iter() {
                         for row in tx.table_assignments(randomness) {
-                            for (column, value) in self.tx_table.iter().zip(row) {
-                                region.assign_advice(
-                                    || format!("tx table row {}", offset),
-                                    *column,
-                                    offset,
-                                    || Ok(value),
-                                )?;
-                            }
+                            self.tx_table.assign(&mut region, offset, row)?;
                             offset += 1;
                         }
                     }
@@ -224,12 +361,8 @@ pub mod test {
                         .assign(&mut region, offset, &Default::default())?;
                     offset += 1;
 
-                    for rw in rws.0.values().flat_map(|rws| rws.iter()) {
-                        self.rw_table.assign(
-                            &mut region,
-                            offset,
-                            &rw.table_assignment(randomness),
-                        )?;
+                    for row in rws.table_assignments(randomness) {
+                        self.rw_table.assign(&mut region, offset, &row)?;
                         offset += 1;
                     }
                     Ok(())
@@ -247,26 +380,13 @@ pub mod test {
                 || "bytecode table",
                 |mut region| {
                     let mut offset = 0;
-                    for column in self.bytecode_table {
-                        region.assign_advice(
-                            || "bytecode table all-zero row",
-                            column,
-                            offset,
-                            || Ok(F::zero()),
-                        )?;
-                    }
+                    self.bytecode_table
+                        .assign(&mut region, offset, [F::zero(); 4])?;
                     offset += 1;
 
                     for bytecode in bytecodes.iter() {
                         for row in bytecode.table_assignments(randomness) {
-                            for (column, value) in self.bytecode_table.iter().zip(row) {
-                                region.assign_advice(
-                                    || format!("bytecode table row {}", offset),
-                                    *column,
-                                    offset,
-                                    || Ok(value),
-                                )?;
-                            }
+                            self.bytecode_table.assign(&mut region, offset, row)?;
                             offset += 1;
                         }
                     }
@@ -285,25 +405,84 @@ pub mod test {
                 || "block table",
                 |mut region| {
                     let mut offset = 0;
-                    for column in self.block_table {
-                        region.assign_advice(
-                            || "block table all-zero row",
-                            column,
-                            offset,
-                            || Ok(F::zero()),
-                        )?;
-                    }
+                    self.block_table
+                        .assign(&mut region, offset, [F::zero(); 3])?;
                     offset += 1;
 
                     for row in block.table_assignments(randomness) {
-                        for (column, value) in self.block_table.iter().zip(row) {
-                            region.assign_advice(
-                                || format!("block table row {}", offset),
-                                *column,
-                                offset,
-                                || Ok(value),
-                            )?;
-                        }
+                        self.block_table.assign(&mut region, offset, row)?;
+                        offset += 1;
+                    }
+
+                    Ok(())
+                },
+            )
+        }
+
+        fn load_keccaks(
+            &self,
+            layouter: &mut impl Layouter<F>,
+            inputs: &[Vec<u8>],
+            randomness: F,
+        ) -> Result<(), Error> {
+            let rows = keccak_table_rows(inputs, randomness);
+            layouter.assign_region(
+                || "keccak table",
+                |mut region| {
+                    let mut offset = 0;
+                    self.keccak_table
+                        .assign(&mut region, offset, [F::zero(); 3])?;
+                    offset += 1;
+
+                    for row in rows.iter() {
+                        self.keccak_table.assign(&mut region, offset, *row)?;
+                        offset += 1;
+                    }
+
+                    Ok(())
+                },
+            )
+        }
+
+        fn load_copies(
+            &self,
+            layouter: &mut impl Layouter<F>,
+            copy_events: &[CopyEvent],
+            randomness: F,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "copy table",
+                |mut region| {
+                    // The copies are followed by an all-zero step, which is
+                    // looked up by the disabled copy lookups
+                    let rows = copy_events
+                        .iter()
+                        .flat_map(|event| event.rows(randomness))
+                        .chain(vec![CopyRow::default(); 2]);
+                    for (offset, row) in rows.enumerate() {
+                        self.copy_table.assign(&mut region, offset, &row)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+
+        fn load_exps(
+            &self,
+            layouter: &mut impl Layouter<F>,
+            exp_events: &[ExpEvent],
+            randomness: F,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "exp table",
+                |mut region| {
+                    let mut offset = 0;
+                    self.exp_table.assign(&mut region, offset, [F::zero(); 3])?;
+                    offset += 1;
+
+                    for event in exp_events.iter() {
+                        let row = event.table_assignment(randomness);
+                        self.exp_table.assign(&mut region, offset, row)?;
                         offset += 1;
                     }
 
@@ -337,37 +516,37 @@ pub mod test {
         }
 
         fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let tx_table = [(); 4].map(|_| meta.advice_column());
+            let tx_table = TxTable::construct(meta);
             let rw_table = RwTable::construct(meta);
-            let bytecode_table = [(); 4].map(|_| meta.advice_column());
-            let block_table = [(); 3].map(|_| meta.advice_column());
-
-            let power_of_randomness = {
-                let columns = [(); 31].map(|_| meta.instance_column());
-                let mut power_of_randomness = None;
+            let bytecode_table = BytecodeTable::construct(meta);
+            let block_table = BlockTable::construct(meta);
+            let keccak_table = KeccakTable::construct(meta);
+            let copy_table = CopyTable::construct(meta);
+            let exp_table = ExpTable::construct(meta);
 
-                meta.create_gate("", |meta| {
-                    power_of_randomness =
-                        Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
-
-                    [0.expr()]
-                });
-
-                power_of_randomness.unwrap()
-            };
+            let power_of_randomness = power_of_randomness_from_instance::<_, 31>(meta);
+            let chunk_states = chunk_states_from_instance(meta);
 
             Self::Config {
                 tx_table,
                 rw_table,
                 bytecode_table,
                 block_table,
+                keccak_table,
+                copy_table,
+                exp_table,
                 evm_circuit: EvmCircuit::configure(
                     meta,
                     power_of_randomness,
+                    chunk_states,
+                    Hardfork::default(),
                     tx_table,
                     rw_table,
                     bytecode_table,
                     block_table,
+                    keccak_table,
+                    copy_table,
+                    exp_table,
                 ),
             }
         }
@@ -384,12 +563,203 @@ pub mod test {
             config.load_rws(&mut layouter, &self.block.rws, self.block.randomness)?;
             config.load_bytecodes(&mut layouter, &self.block.bytecodes, self.block.randomness)?;
             config.load_block(&mut layouter, &self.block.context, self.block.randomness)?;
+            config.load_keccaks(
+                &mut layouter,
+                &self.block.keccak_inputs,
+                self.block.randomness,
+            )?;
+            config.load_copies(
+                &mut layouter,
+                &self.block.copy_events,
+                self.block.randomness,
+            )?;
+            config.load_exps(&mut layouter, &self.block.exp_events, self.block.randomness)?;
             config
                 .evm_circuit
                 .assign_block_exact(&mut layouter, &self.block)
         }
     }
 
+    /// Location of an execution step in a block, which is used to report
+    /// where a `VerifyFailure` happens.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct StepLocation {
+        pub tx_index: usize,
+        pub call_index: usize,
+        pub step_index: usize,
+        pub execution_state: ExecutionState,
+        pub opcode: Option<OpcodeId>,
+        /// `ExecutionGadget::NAME` of the gadget which handles the step, if
+        /// any, which is also the name of its gates.
+        pub gadget: Option<&'static str>,
+    }
+
+    impl fmt::Display for StepLocation {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "tx {} call {} step {} ({:?}",
+                self.tx_index, self.call_index, self.step_index, self.execution_state
+            )?;
+            if let Some(opcode) = self.opcode {
+                write!(f, " with opcode {:?}", opcode)?;
+            }
+            if let Some(gadget) = self.gadget {
+                write!(f, " in gadget {}", gadget)?;
+            }
+            write!(f, ")")
+        }
+    }
+
+    impl StepLocation {
+        /// Returns the location of the step which is assigned at `offset` of
+        /// the execution step region.
+        pub fn from_offset<F>(block: &Block<F>, offset: usize) -> Option<Self> {
+            block
+                .txs
+                .iter()
+                .enumerate()
+                .flat_map(|(tx_index, tx)| {
+                    tx.steps
+                        .iter()
+                        .enumerate()
+                        .map(move |(step_index, step)| Self {
+                            tx_index,
+                            call_index: step.call_index,
+                            step_index,
+                            execution_state: step.execution_state,
+                            opcode: step.opcode,
+                            gadget: execution_gadget_name(step.execution_state),
+                        })
+                })
+                .nth(offset / STEP_HEIGHT)
+        }
+
+        /// Returns the location of the step where the `failure` happens, if
+        /// it's in the execution step region.
+        pub fn from_failure<F>(block: &Block<F>, failure: &VerifyFailure) -> Option<Self> {
+            let location = match failure {
+                VerifyFailure::ConstraintNotSatisfied { location, .. } => location,
+                VerifyFailure::Lookup { location, .. } => location,
+                _ => return None,
+            };
+            match location {
+                // Region has no accessor of its name, so we match it by the
+                // formatted one, which ends with the quoted name.
+                FailureLocation::InRegion { region, offset }
+                    if region
+                        .to_string()
+                        .ends_with(&format!("('{}')", EXECUTION_STEP_REGION)) =>
+                {
+                    Self::from_offset(block, *offset)
+                }
+                _ => None,
+            }
+        }
+    }
+
+    /// Describes each failure with the location of the step where it happens,
+    /// since the row and column of a failure alone are hard to diagnose.
+    pub fn describe_failures<F>(block: &Block<F>, failures: &[VerifyFailure]) -> Vec<String> {
+        failures
+            .iter()
+            .map(|failure| match StepLocation::from_failure(block, failure) {
+                Some(location) => format!("{}: {}", location, failure),
+                None => failure.to_string(),
+            })
+            .collect()
+    }
+
+    /// Returns a side-by-side diff of the rw operations of the step where
+    /// `failure` happens, if it's in the execution step region: on the left
+    /// the ones the step expects, by its `rw_indices`, and on the right the
+    /// rows of the rw table its lookups find at the same rw counters, from
+    /// `step.rw_counter` on.  Rows which differ are marked with a `!`, which
+    /// usually points at a gadget and bus-mapping disagreeing on the order or
+    /// the number of the rw operations of the step.
+    pub fn diff_step_rws<F>(block: &Block<F>, failure: &VerifyFailure) -> Option<String> {
+        let location = StepLocation::from_failure(block, failure)?;
+        let step = &block.txs[location.tx_index].steps[location.step_index];
+
+        let table = block
+            .rws
+            .0
+            .values()
+            .flatten()
+            .map(|rw| (rw.rw_counter(), rw))
+            .collect::<HashMap<_, _>>();
+        let rows = step
+            .rw_indices
+            .iter()
+            .enumerate()
+            .map(|(idx, rw_index)| {
+                let rw_counter = step.rw_counter + idx;
+                let expected = format!("{:?}", block.rws[*rw_index]);
+                let found = table
+                    .get(&rw_counter)
+                    .map_or_else(|| "none".to_string(), |rw| format!("{:?}", rw));
+                (rw_counter, expected, found)
+            })
+            .collect::<Vec<_>>();
+
+        let width = rows
+            .iter()
+            .map(|(_, expected, _)| expected.len())
+            .max()
+            .unwrap_or_default()
+            .max("expected".len());
+        let mut diff = format!(
+            "{}: rw operations\n    {:>10} {:<width$} | table\n",
+            location,
+            "rw_counter",
+            "expected",
+            width = width
+        );
+        for (rw_counter, expected, found) in rows {
+            let marker = if expected == found { ' ' } else { '!' };
+            diff += &format!(
+                "  {} {:>10} {:<width$} | {}\n",
+                marker,
+                rw_counter,
+                expected,
+                found,
+                width = width
+            );
+        }
+        Some(diff)
+    }
+
+    /// Runs the MockProver of `circuit`, which contains the EVM circuit of
+    /// `block`.  On failure, every failure is printed with the location of
+    /// its step, followed by the diff of the rw operations of the steps where
+    /// they happen, see `diff_step_rws`.
+    pub fn run_mock_prover_with_diff<F: Field, C: Circuit<F>>(
+        k: u32,
+        circuit: &C,
+        instance: Vec<Vec<F>>,
+        block: &Block<F>,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let prover = MockProver::<F>::run(k, circuit, instance).unwrap();
+        prover.verify().map_err(|failures| {
+            print_failures(block, &failures);
+            failures
+        })
+    }
+
+    fn print_failures<F>(block: &Block<F>, failures: &[VerifyFailure]) {
+        for description in describe_failures(block, failures) {
+            eprintln!("{}", description);
+        }
+        let mut diffs = failures
+            .iter()
+            .filter_map(|failure| diff_step_rws(block, failure))
+            .collect::<Vec<_>>();
+        diffs.dedup();
+        for diff in diffs {
+            eprintln!("{}", diff);
+        }
+    }
+
     pub fn run_test_circuit<F: Field>(
         block: Block<F>,
         fixed_table_tags: Vec<FixedTableTag>,
@@ -409,19 +779,24 @@ pub mod test {
                 .map(|bytecode| bytecode.bytes.len())
                 .sum::<usize>(),
         ));
-
-        let power_of_randomness = (1..32)
-            .map(|exp| {
-                vec![
-                    block.randomness.pow(&[exp, 0, 0, 0]);
-                    block.txs.iter().map(|tx| tx.steps.len()).sum::<usize>() * STEP_HEIGHT
-                ]
-            })
+        let k = k.max(log2_ceil(64 + EvmCircuit::get_num_rows_required(&block)));
+
+        let n_rows = block.txs.iter().map(|tx| tx.steps.len()).sum::<usize>() * STEP_HEIGHT;
+        let instance = PowersOfRandomness::new(block.randomness)
+            .take(31)
+            .into_iter()
+            .map(|power| vec![power; n_rows])
+            .chain(EvmCircuit::chunk_states_instance(&block, n_rows))
             .collect();
         let (active_gate_rows, active_lookup_rows) = EvmCircuit::get_active_rows(&block);
         let circuit = TestCircuit::<F>::new(block, fixed_table_tags);
-        let prover = MockProver::<F>::run(k, &circuit, power_of_randomness).unwrap();
-        prover.verify_at_rows(active_gate_rows.into_iter(), active_lookup_rows.into_iter())
+        let prover = MockProver::<F>::run(k, &circuit, instance).unwrap();
+        prover
+            .verify_at_rows(active_gate_rows.into_iter(), active_lookup_rows.into_iter())
+            .map_err(|failures| {
+                print_failures(&circuit.block, &failures);
+                failures
+            })
     }
 
     pub fn run_test_circuit_incomplete_fixed_table<F: Field>(
@@ -447,27 +822,158 @@ pub mod test {
         run_test_circuit(block, FixedTableTag::iterator().collect())
     }
 
-    pub(crate) fn calc_memory_expension_gas_cost(
-        curr_memory_word_size: u64,
-        next_memory_word_size: u64,
-    ) -> u64 {
-        if next_memory_word_size <= curr_memory_word_size {
-            0
-        } else {
-            let total_cost = |mem_word_size| {
-                mem_word_size * GasCost::MEMORY.as_u64() + mem_word_size * mem_word_size / 512
-            };
-            total_cost(next_memory_word_size) - total_cost(curr_memory_word_size)
-        }
+    #[test]
+    fn execution_state_stats() {
+        use crate::evm_circuit::{witness::ExecStep, ExecutionStateStats};
+
+        let step = |execution_state| ExecStep {
+            execution_state,
+            ..Default::default()
+        };
+        let block = Block::<Fp> {
+            txs: vec![Transaction {
+                steps: vec![
+                    step(ExecutionState::BeginTx),
+                    step(ExecutionState::PUSH),
+                    step(ExecutionState::PUSH),
+                    step(ExecutionState::ADD),
+                    step(ExecutionState::STOP),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(EvmCircuit::get_num_rows_required(&block), 6 * STEP_HEIGHT);
+
+        let stats = EvmCircuit::get_execution_state_stats(&block);
+        assert_eq!(stats.len(), 4);
+        assert_eq!(
+            stats[0],
+            ExecutionStateStats {
+                execution_state: ExecutionState::PUSH,
+                num_steps: 2,
+                num_rows: 2 * STEP_HEIGHT,
+            }
+        );
+        assert_eq!(
+            stats.iter().map(|stats| stats.num_rows).sum::<usize>(),
+            5 * STEP_HEIGHT
+        );
+    }
+
+    #[test]
+    fn evm_circuit_rejects_other_hardfork() {
+        let mut block = Block::<Fp>::default();
+        block.chain_config.hardfork = Hardfork::Shanghai;
+        let circuit = TestCircuit::<Fp>::new(block, Vec::new());
+        assert!(matches!(
+            MockProver::<Fp>::run(8, &circuit, vec![vec![]; 31 + 2 * CHUNK_STATE_SIZE]),
+            Err(Error::Synthesis)
+        ));
+    }
+
+    #[test]
+    fn step_location_from_offset() {
+        use crate::evm_circuit::witness::ExecStep;
+
+        let block = Block::<Fp> {
+            txs: vec![
+                Transaction {
+                    steps: vec![
+                        ExecStep {
+                            execution_state: ExecutionState::BeginTx,
+                            ..Default::default()
+                        },
+                        ExecStep {
+                            execution_state: ExecutionState::EndTx,
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                },
+                Transaction {
+                    steps: vec![ExecStep {
+                        execution_state: ExecutionState::PUSH,
+                        opcode: Some(OpcodeId::PUSH1),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let location = StepLocation::from_offset(&block, 2 * STEP_HEIGHT + 3).unwrap();
+        assert_eq!(
+            location,
+            StepLocation {
+                tx_index: 1,
+                call_index: 0,
+                step_index: 0,
+                execution_state: ExecutionState::PUSH,
+                opcode: Some(OpcodeId::PUSH1),
+                gadget: Some("PUSH"),
+            }
+        );
+        assert_eq!(
+            location.to_string(),
+            "tx 1 call 0 step 0 (PUSH with opcode PUSH1 in gadget PUSH)"
+        );
+        assert_eq!(StepLocation::from_offset(&block, 3 * STEP_HEIGHT), None);
     }
 
-    pub(crate) fn calc_memory_copier_gas_cost(
-        curr_memory_word_size: u64,
-        next_memory_word_size: u64,
-        num_copy_bytes: u64,
-    ) -> u64 {
-        let num_words = (num_copy_bytes + 31) / 32;
-        num_words * GasCost::COPY.as_u64()
-            + calc_memory_expension_gas_cost(curr_memory_word_size, next_memory_word_size)
+    #[test]
+    fn diff_step_rws_marks_mismatches() {
+        use crate::evm_circuit::{
+            table::RwTableTag,
+            witness::{ExecStep, Rw},
+        };
+        use halo2_proofs::dev::metadata::Region;
+        use std::collections::HashMap;
+
+        let stack = |rw_counter, stack_pointer| Rw::Stack {
+            rw_counter,
+            is_write: false,
+            call_id: 1,
+            stack_pointer,
+            value: Word::from(0x42),
+        };
+        // The step expects its second rw operation at rw counter 2, where the
+        // table has none.
+        let block = Block::<Fp> {
+            txs: vec![Transaction {
+                steps: vec![ExecStep {
+                    execution_state: ExecutionState::POP,
+                    opcode: Some(OpcodeId::POP),
+                    rw_counter: 1,
+                    rw_indices: vec![(RwTableTag::Stack, 0), (RwTableTag::Stack, 1)],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            rws: RwMap(HashMap::from([(
+                RwTableTag::Stack,
+                vec![stack(1, 1023), stack(3, 1022)],
+            )])),
+            ..Default::default()
+        };
+        let failure = VerifyFailure::Lookup {
+            lookup_index: 0,
+            location: FailureLocation::InRegion {
+                region: Region::from((0, EXECUTION_STEP_REGION)),
+                offset: 3,
+            },
+        };
+
+        let diff = diff_step_rws(&block, &failure).unwrap();
+        let lines = diff.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "tx 0 call 0 step 0 (POP with opcode POP in gadget POP): rw operations"
+        );
+        assert!(lines[2].starts_with("             1 Stack { rw_counter: 1,"));
+        assert!(lines[3].starts_with("  !          2 Stack { rw_counter: 3,"));
+        assert!(lines[3].ends_with("| none"));
     }
 }
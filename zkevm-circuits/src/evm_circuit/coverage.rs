@@ -0,0 +1,236 @@
+//! Coverage of the opcodes, the precompiles and the error paths exercised by
+//! the traces of blocks, against the ones implemented in bus-mapping and in
+//! the EVM circuit, to prioritize the missing ones.
+
+use crate::evm_circuit::{execution::implemented_execution_states, ExecutionState};
+use bus_mapping::{circuit_input_builder::parse_step_reported_error, evm::is_opcode_supported};
+use eth_types::{evm_types::OpcodeId, GethExecStep, GethExecTrace, Word};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Names of the precompiles, the address of the `n`th one being `n + 1`.
+const PRECOMPILES: [&str; 9] = [
+    "ecRecover",
+    "sha256",
+    "ripemd160",
+    "identity",
+    "modexp",
+    "ecAdd",
+    "ecMul",
+    "ecPairing",
+    "blake2f",
+];
+
+/// Number of steps exercising a feature, and where it is implemented.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CoverageEntry {
+    /// Number of steps exercising the feature
+    pub count: usize,
+    /// Whether bus-mapping handles the feature
+    pub bus_mapping: bool,
+    /// Whether a gadget of the EVM circuit handles the feature
+    pub evm_circuit: bool,
+}
+
+impl CoverageEntry {
+    fn new(bus_mapping: bool, evm_circuit: bool) -> Self {
+        Self {
+            count: 0,
+            bus_mapping,
+            evm_circuit,
+        }
+    }
+}
+
+/// Coverage matrix of the features exercised by traces.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Coverage {
+    /// Number of traces, one per transaction
+    pub traces: usize,
+    /// Number of steps of the traces
+    pub steps: usize,
+    /// Entries of all the defined opcodes, and of the undefined ones
+    /// executed, by name
+    pub opcodes: BTreeMap<String, CoverageEntry>,
+    /// Entries of all the precompiles, by name, counting the calls to them
+    pub precompiles: BTreeMap<String, CoverageEntry>,
+    /// Entries of the errors of the steps, by name of their `ExecError`, or by
+    /// geth message for the ones bus-mapping doesn't handle
+    pub errors: BTreeMap<String, CoverageEntry>,
+    #[serde(skip)]
+    implemented: Vec<ExecutionState>,
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Coverage {
+    /// Creates the coverage matrix of no trace.
+    pub fn new() -> Self {
+        let implemented = implemented_execution_states();
+        let opcodes = (0..=u8::MAX)
+            .map(OpcodeId::from)
+            .filter(|op| !matches!(op, OpcodeId::INVALID(byte) if *byte != 0xfe))
+            .map(|op| (format!("{:?}", op), opcode_entry(op, &implemented)))
+            .collect();
+        // Neither bus-mapping nor the EVM circuit handle the calls to the
+        // precompiles yet.
+        let precompiles = PRECOMPILES
+            .iter()
+            .map(|name| (name.to_string(), CoverageEntry::new(false, false)))
+            .collect();
+        Self {
+            traces: 0,
+            steps: 0,
+            opcodes,
+            precompiles,
+            errors: BTreeMap::new(),
+            implemented,
+        }
+    }
+
+    /// Adds the steps of `traces` to the matrix.
+    pub fn add_traces(&mut self, traces: &[GethExecTrace]) {
+        for trace in traces {
+            self.traces += 1;
+            for step in trace.struct_logs.iter() {
+                self.add_step(step);
+            }
+        }
+    }
+
+    fn add_step(&mut self, step: &GethExecStep) {
+        let implemented = &self.implemented;
+        self.steps += 1;
+
+        self.opcodes
+            .entry(format!("{:?}", step.op))
+            .or_insert_with(|| opcode_entry(step.op, implemented))
+            .count += 1;
+
+        if let Some(index) = precompile_index(step) {
+            if let Some(entry) = self.precompiles.get_mut(PRECOMPILES[index]) {
+                entry.count += 1;
+            }
+        }
+
+        if let Some(error) = &step.error {
+            let (name, entry) = match parse_step_reported_error(&step.op, error) {
+                Some(exec_error) => {
+                    let state = ExecutionState::from(&exec_error);
+                    (
+                        format!("{:?}", exec_error),
+                        CoverageEntry::new(true, implemented.contains(&state)),
+                    )
+                }
+                // Drop the details of the message, such as the stack sizes of
+                // a stack underflow.
+                None => (
+                    error.split(" (").next().unwrap_or(error).to_string(),
+                    CoverageEntry::new(false, false),
+                ),
+            };
+            self.errors.entry(name).or_insert(entry).count += 1;
+        }
+    }
+
+    /// Returns the features exercised by the traces which bus-mapping or the
+    /// EVM circuit doesn't handle, as `(kind, name, entry)`, the most
+    /// exercised first.
+    pub fn missing(&self) -> Vec<(&'static str, &str, &CoverageEntry)> {
+        let mut missing: Vec<_> = [
+            ("opcode", &self.opcodes),
+            ("precompile", &self.precompiles),
+            ("error", &self.errors),
+        ]
+        .iter()
+        .flat_map(|&(kind, entries)| {
+            entries
+                .iter()
+                .filter(|(_, entry)| entry.count > 0 && !(entry.bus_mapping && entry.evm_circuit))
+                .map(move |(name, entry)| (kind, name.as_str(), entry))
+        })
+        .collect();
+        missing.sort_by(|a, b| b.2.count.cmp(&a.2.count));
+        missing
+    }
+}
+
+fn opcode_entry(op: OpcodeId, implemented: &[ExecutionState]) -> CoverageEntry {
+    CoverageEntry::new(
+        is_opcode_supported(&op),
+        ExecutionState::from_opcode(op).map_or(false, |state| implemented.contains(&state)),
+    )
+}
+
+/// Returns the index in [`PRECOMPILES`] of the precompile called by `step`, if
+/// any.
+fn precompile_index(step: &GethExecStep) -> Option<usize> {
+    if !matches!(
+        step.op,
+        OpcodeId::CALL | OpcodeId::CALLCODE | OpcodeId::DELEGATECALL | OpcodeId::STATICCALL
+    ) {
+        return None;
+    }
+    let address = step.stack.nth_last(1).ok()?;
+    if address.is_zero() || address > Word::from(PRECOMPILES.len()) {
+        return None;
+    }
+    Some(address.as_usize() - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Coverage;
+    use eth_types::bytecode;
+
+    #[test]
+    fn coverage_of_trace() {
+        let code = bytecode! {
+            PUSH1(0x20)
+            PUSH1(0x00)
+            PUSH1(0x20)
+            PUSH1(0x00)
+            PUSH1(0x04)
+            GAS
+            STATICCALL
+            PUSH1(0x02)
+            DIV
+            POP
+            STOP
+        };
+        let mut geth_traces = mock::new_single_tx_trace_code(&code).unwrap().geth_traces;
+        geth_traces[0].struct_logs[8].error = Some("stack underflow (0 <=> 2)".to_string());
+        geth_traces[0].struct_logs[9].error = Some("write protection".to_string());
+
+        let mut coverage = Coverage::new();
+        coverage.add_traces(&geth_traces);
+        assert_eq!(coverage.traces, 1);
+        assert_eq!(coverage.steps, geth_traces[0].struct_logs.len());
+
+        let push1 = &coverage.opcodes["PUSH1"];
+        assert_eq!(push1.count, 6);
+        assert!(push1.bus_mapping && push1.evm_circuit);
+        let div = &coverage.opcodes["DIV"];
+        assert_eq!(div.count, 1);
+        assert!(div.bus_mapping && !div.evm_circuit);
+        assert_eq!(coverage.opcodes["SHA3"].count, 0);
+
+        assert_eq!(coverage.precompiles["identity"].count, 1);
+        assert_eq!(coverage.precompiles["sha256"].count, 0);
+
+        let underflow = &coverage.errors["StackUnderflow"];
+        assert_eq!(underflow.count, 1);
+        assert!(underflow.bus_mapping);
+        let write_protection = &coverage.errors["write protection"];
+        assert!(!write_protection.bus_mapping && !write_protection.evm_circuit);
+
+        let missing = coverage.missing();
+        assert!(missing.contains(&("opcode", "DIV", div)));
+        assert!(missing.contains(&("precompile", "identity", &coverage.precompiles["identity"])));
+        assert!(!missing.iter().any(|(_, name, _)| *name == "PUSH1"));
+    }
+}
@@ -1,23 +1,29 @@
 use crate::{
+    annotation::{Annotate, ColumnAnnotations},
     evm_circuit::{
         param::{STEP_HEIGHT, STEP_WIDTH},
         step::{ExecutionState, Preset, Step},
         table::{FixedTableTag, Lookup, LookupTable, Table},
-        util::constraint_builder::ConstraintBuilder,
-        witness::{Block, Call, ExecStep, Transaction},
+        util::{constraint_builder::ConstraintBuilder, StepRegion},
+        witness::{Block, Call, ExecStep, Transaction, CHUNK_STATE_SIZE},
     },
+    fixed_table::FixedTable,
     util::Expr,
 };
-use eth_types::Field;
+use eth_types::{evm_types::Hardfork, Field};
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{Layouter, Region},
-    plonk::{Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    circuit::Layouter,
+    plonk::{ConstraintSystem, Error, Expression, Selector},
     poly::Rotation,
 };
+use pairing::bn256::Fr;
+use rayon::prelude::*;
 use std::{collections::HashMap, iter};
 
 mod add;
+mod basefee;
+mod begin_chunk;
 mod begin_tx;
 mod bitwise;
 mod byte;
@@ -26,16 +32,21 @@ mod calldataload;
 mod calldatasize;
 mod caller;
 mod callvalue;
+mod chainid;
 mod coinbase;
 mod comparator;
+mod create;
 mod dup;
 mod end_block;
+mod end_chunk;
 mod end_tx;
 mod error_oog_static_memory;
+mod exp;
 mod gas;
 mod jump;
 mod jumpdest;
 mod jumpi;
+mod mcopy;
 mod memory;
 mod memory_copy;
 mod msize;
@@ -54,6 +65,8 @@ mod swap;
 mod timestamp;
 
 use add::AddGadget;
+use basefee::BaseFeeGadget;
+use begin_chunk::BeginChunkGadget;
 use begin_tx::BeginTxGadget;
 use bitwise::BitwiseGadget;
 use byte::ByteGadget;
@@ -62,16 +75,21 @@ use calldataload::CallDataLoadGadget;
 use calldatasize::CallDataSizeGadget;
 use caller::CallerGadget;
 use callvalue::CallValueGadget;
+use chainid::ChainIdGadget;
 use coinbase::CoinbaseGadget;
 use comparator::ComparatorGadget;
+use create::{Create1Gadget, Create2Gadget};
 use dup::DupGadget;
 use end_block::EndBlockGadget;
+use end_chunk::EndChunkGadget;
 use end_tx::EndTxGadget;
 use error_oog_static_memory::ErrorOOGStaticMemoryGadget;
+use exp::ExpGadget;
 use gas::GasGadget;
 use jump::JumpGadget;
 use jumpdest::JumpdestGadget;
 use jumpi::JumpiGadget;
+use mcopy::McopyGadget;
 use memory::MemoryGadget;
 use memory_copy::CopyToMemoryGadget;
 use msize::MsizeGadget;
@@ -89,6 +107,10 @@ use stop::StopGadget;
 use swap::SwapGadget;
 use timestamp::TimestampGadget;
 
+/// Number of steps whose cell values are computed together by a worker thread
+/// of `ExecutionConfig::assign_block`.
+const STEPS_PER_CHUNK: usize = 64;
+
 pub(crate) trait ExecutionGadget<F: FieldExt> {
     const NAME: &'static str;
 
@@ -98,7 +120,7 @@ pub(crate) trait ExecutionGadget<F: FieldExt> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         transaction: &Transaction,
@@ -107,6 +129,86 @@ pub(crate) trait ExecutionGadget<F: FieldExt> {
     ) -> Result<(), Error>;
 }
 
+/// Name of the region where [`ExecutionConfig`] assigns the execution steps.
+pub(crate) const EXECUTION_STEP_REGION: &str = "Execution step";
+
+/// Returns the execution states handled by a gadget of the
+/// [`ExecutionConfig`], the steps of the other ones can't be assigned yet.
+pub(crate) fn implemented_execution_states() -> Vec<ExecutionState> {
+    implemented_execution_gadgets()
+        .into_iter()
+        .map(|(execution_state, _)| execution_state)
+        .collect()
+}
+
+/// Returns the [`ExecutionGadget::NAME`] of the gadget which handles
+/// `execution_state`, if it's implemented.
+#[cfg(any(feature = "test", test))]
+pub(crate) fn execution_gadget_name(execution_state: ExecutionState) -> Option<&'static str> {
+    implemented_execution_gadgets()
+        .into_iter()
+        .find(|(state, _)| *state == execution_state)
+        .map(|(_, name)| name)
+}
+
+/// Returns the execution state and the name of each gadget of the
+/// [`ExecutionConfig`].
+fn implemented_execution_gadgets() -> Vec<(ExecutionState, &'static str)> {
+    macro_rules! execution_gadgets {
+        ($($gadget:ident),* $(,)?) => {
+            vec![$((
+                <$gadget<Fr> as ExecutionGadget<Fr>>::EXECUTION_STATE,
+                <$gadget<Fr> as ExecutionGadget<Fr>>::NAME,
+            )),*]
+        };
+    }
+    execution_gadgets![
+        AddGadget,
+        BaseFeeGadget,
+        MulGadget,
+        BitwiseGadget,
+        BeginChunkGadget,
+        BeginTxGadget,
+        ByteGadget,
+        CallDataCopyGadget,
+        CallDataLoadGadget,
+        CallDataSizeGadget,
+        CallerGadget,
+        CallValueGadget,
+        ChainIdGadget,
+        ComparatorGadget,
+        Create1Gadget,
+        Create2Gadget,
+        DupGadget,
+        EndBlockGadget,
+        EndChunkGadget,
+        EndTxGadget,
+        ErrorOOGStaticMemoryGadget,
+        ExpGadget,
+        JumpGadget,
+        JumpdestGadget,
+        JumpiGadget,
+        McopyGadget,
+        GasGadget,
+        MemoryGadget,
+        CopyToMemoryGadget,
+        PcGadget,
+        PopGadget,
+        PushGadget,
+        SignedComparatorGadget,
+        SignextendGadget,
+        StopGadget,
+        SwapGadget,
+        MsizeGadget,
+        CoinbaseGadget,
+        TimestampGadget,
+        SelfbalanceGadget,
+        NumberGadget,
+        SloadGadget,
+        SstoreGadget,
+    ]
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct ExecutionConfig<F> {
     q_step: Selector,
@@ -115,8 +217,10 @@ pub(crate) struct ExecutionConfig<F> {
     step: Step<F>,
     presets_map: HashMap<ExecutionState, Vec<Preset<F>>>,
     add_gadget: AddGadget<F>,
+    basefee_gadget: BaseFeeGadget<F>,
     mul_gadget: MulGadget<F>,
     bitwise_gadget: BitwiseGadget<F>,
+    begin_chunk_gadget: BeginChunkGadget<F>,
     begin_tx_gadget: BeginTxGadget<F>,
     byte_gadget: ByteGadget<F>,
     calldatacopy_gadget: CallDataCopyGadget<F>,
@@ -124,14 +228,20 @@ pub(crate) struct ExecutionConfig<F> {
     calldatasize_gadget: CallDataSizeGadget<F>,
     caller_gadget: CallerGadget<F>,
     call_value_gadget: CallValueGadget<F>,
+    chainid_gadget: ChainIdGadget<F>,
     comparator_gadget: ComparatorGadget<F>,
+    create_gadget: Create1Gadget<F>,
+    create2_gadget: Create2Gadget<F>,
     dup_gadget: DupGadget<F>,
     end_block_gadget: EndBlockGadget<F>,
+    end_chunk_gadget: EndChunkGadget<F>,
     end_tx_gadget: EndTxGadget<F>,
     error_oog_static_memory_gadget: ErrorOOGStaticMemoryGadget<F>,
+    exp_gadget: ExpGadget<F>,
     jump_gadget: JumpGadget<F>,
     jumpdest_gadget: JumpdestGadget<F>,
     jumpi_gadget: JumpiGadget<F>,
+    mcopy_gadget: McopyGadget<F>,
     gas_gadget: GasGadget<F>,
     memory_gadget: MemoryGadget<F>,
     copy_to_memory_gadget: CopyToMemoryGadget<F>,
@@ -151,21 +261,48 @@ pub(crate) struct ExecutionConfig<F> {
     sstore_gadget: SstoreGadget<F>,
 }
 
+impl<F> Annotate for ExecutionConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        // All the rows of a step are laid out in the same columns
+        if let Some(row) = self.step.rows.first() {
+            annotations.annotate(owner, "qs_byte_lookup", row.qs_byte_lookup.column());
+            annotations.annotate_all(owner, "cells", row.cells.iter().map(|cell| cell.column()));
+        }
+    }
+}
+
 impl<F: Field> ExecutionConfig<F> {
-    pub(crate) fn configure<TxTable, RwTable, BytecodeTable, BlockTable>(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn configure<
+        TxTable,
+        RwTable,
+        BytecodeTable,
+        BlockTable,
+        KeccakTable,
+        CopyTable,
+        ExpTable,
+    >(
         meta: &mut ConstraintSystem<F>,
         power_of_randomness: [Expression<F>; 31],
-        fixed_table: [Column<Fixed>; 4],
+        chunk_states: [[Expression<F>; CHUNK_STATE_SIZE]; 2],
+        hardfork: Hardfork,
+        fixed_table: FixedTable,
         tx_table: TxTable,
         rw_table: RwTable,
         bytecode_table: BytecodeTable,
         block_table: BlockTable,
+        keccak_table: KeccakTable,
+        copy_table: CopyTable,
+        exp_table: ExpTable,
     ) -> Self
     where
         TxTable: LookupTable<F, 4>,
         RwTable: LookupTable<F, 11>,
         BytecodeTable: LookupTable<F, 4>,
         BlockTable: LookupTable<F, 3>,
+        KeccakTable: LookupTable<F, 3>,
+        CopyTable: LookupTable<F, 13>,
+        ExpTable: LookupTable<F, 3>,
     {
         let q_step = meta.complex_selector();
         let q_step_first = meta.complex_selector();
@@ -177,6 +314,7 @@ impl<F: Field> ExecutionConfig<F> {
         let step_next = Step::new(meta, qs_byte_lookup, advices, true);
         let mut independent_lookups = Vec::new();
         let mut presets_map = HashMap::new();
+        let [begin_state, end_state] = chunk_states;
 
         meta.create_gate("Constrain execution state", |meta| {
             let q_step = meta.query_selector(q_step);
@@ -217,6 +355,11 @@ impl<F: Field> ExecutionConfig<F> {
                                 ExecutionState::EndBlock,
                                 vec![ExecutionState::EndBlock],
                             ),
+                            (
+                                "EndChunk can only transit to EndChunk",
+                                ExecutionState::EndChunk,
+                                vec![ExecutionState::EndChunk],
+                            ),
                         ]
                         .map(|(name, from, to)| {
                             (
@@ -263,27 +406,70 @@ impl<F: Field> ExecutionConfig<F> {
                     .map(move |(name, poly)| (name, (1.expr() - q_step_last.clone()) * poly))
             };
 
+            // A chunk of a block begins where the previous one ends, and ends
+            // where the next one begins.
             let _first_step_check = {
-                let begin_tx_selector =
-                    step_curr.execution_state_selector([ExecutionState::BeginTx]);
+                let begin_selector = step_curr.execution_state_selector([
+                    ExecutionState::BeginTx,
+                    ExecutionState::BeginChunk,
+                ]);
                 iter::once((
-                    "First step should be BeginTx",
-                    q_step_first * (1.expr() - begin_tx_selector),
+                    "First step should be BeginTx or BeginChunk",
+                    q_step_first.clone() * (1.expr() - begin_selector),
                 ))
             };
 
             let _last_step_check = {
-                let end_block_selector =
-                    step_curr.execution_state_selector([ExecutionState::EndBlock]);
+                let end_selector = step_curr.execution_state_selector([
+                    ExecutionState::EndBlock,
+                    ExecutionState::EndChunk,
+                ]);
                 iter::once((
-                    "Last step should be EndBlock",
-                    q_step_last * (1.expr() - end_block_selector),
+                    "Last step should be EndBlock or EndChunk",
+                    q_step_last * (1.expr() - end_selector),
                 ))
             };
 
+            let begin_chunk_check = iter::once((
+                "Only the first step can be BeginChunk",
+                (1.expr() - q_step_first)
+                    * step_curr.execution_state_selector([ExecutionState::BeginChunk]),
+            ));
+
+            // The BeginChunk and EndChunk steps have the begin and end states
+            // of the chunk in the public inputs, on which the proofs of the
+            // chunks of a block are stitched.  The fingerprint of the carried
+            // rw operations, which follows the cells of the step state, is
+            // constrained by the state circuit.
+            let chunk_state_checks = iter::empty()
+                .chain([
+                    (
+                        "BeginChunk has the begin state of the chunk",
+                        ExecutionState::BeginChunk,
+                        begin_state,
+                    ),
+                    (
+                        "EndChunk has the end state of the chunk",
+                        ExecutionState::EndChunk,
+                        end_state,
+                    ),
+                ])
+                .flat_map(|(name, execution_state, chunk_state)| {
+                    let selector = step_curr.execution_state_selector([execution_state]);
+                    step_curr
+                        .state
+                        .cells()
+                        .iter()
+                        .zip(chunk_state.iter())
+                        .map(|(cell, value)| (name, selector.clone() * (cell.expr() - value.clone())))
+                        .collect::<Vec<_>>()
+                });
+
             iter::once(sum_to_one)
                 .chain(bool_checks)
                 .chain(execution_state_transition)
+                .chain(begin_chunk_check)
+                .chain(chunk_state_checks)
                 .map(move |(name, poly)| (name, q_step.clone() * poly))
                 // TODO: Enable these after test of CALLDATACOPY is complete.
                 // .chain(first_step_check)
@@ -317,6 +503,7 @@ impl<F: Field> ExecutionConfig<F> {
                     q_step,
                     q_step_first,
                     &power_of_randomness,
+                    hardfork,
                     &step_curr,
                     &step_next,
                     &mut independent_lookups,
@@ -330,8 +517,10 @@ impl<F: Field> ExecutionConfig<F> {
             q_step_first,
             q_step_last,
             add_gadget: configure_gadget!(),
+            basefee_gadget: configure_gadget!(),
             mul_gadget: configure_gadget!(),
             bitwise_gadget: configure_gadget!(),
+            begin_chunk_gadget: configure_gadget!(),
             begin_tx_gadget: configure_gadget!(),
             byte_gadget: configure_gadget!(),
             calldatacopy_gadget: configure_gadget!(),
@@ -339,14 +528,20 @@ impl<F: Field> ExecutionConfig<F> {
             calldatasize_gadget: configure_gadget!(),
             caller_gadget: configure_gadget!(),
             call_value_gadget: configure_gadget!(),
+            chainid_gadget: configure_gadget!(),
             comparator_gadget: configure_gadget!(),
+            create_gadget: configure_gadget!(),
+            create2_gadget: configure_gadget!(),
             dup_gadget: configure_gadget!(),
             end_block_gadget: configure_gadget!(),
+            end_chunk_gadget: configure_gadget!(),
             end_tx_gadget: configure_gadget!(),
             error_oog_static_memory_gadget: configure_gadget!(),
+            exp_gadget: configure_gadget!(),
             jump_gadget: configure_gadget!(),
             jumpdest_gadget: configure_gadget!(),
             jumpi_gadget: configure_gadget!(),
+            mcopy_gadget: configure_gadget!(),
             gas_gadget: configure_gadget!(),
             memory_gadget: configure_gadget!(),
             copy_to_memory_gadget: configure_gadget!(),
@@ -376,6 +571,9 @@ impl<F: Field> ExecutionConfig<F> {
             rw_table,
             bytecode_table,
             block_table,
+            keccak_table,
+            copy_table,
+            exp_table,
             independent_lookups,
         );
 
@@ -388,6 +586,7 @@ impl<F: Field> ExecutionConfig<F> {
         q_step: Selector,
         q_step_first: Selector,
         power_of_randomness: &[Expression<F>; 31],
+        hardfork: Hardfork,
         step_curr: &Step<F>,
         step_next: &Step<F>,
         independent_lookups: &mut Vec<Vec<Lookup<F>>>,
@@ -398,6 +597,7 @@ impl<F: Field> ExecutionConfig<F> {
             step_next,
             power_of_randomness,
             G::EXECUTION_STATE,
+            hardfork,
         );
 
         let gadget = G::configure(&mut cb);
@@ -431,20 +631,34 @@ impl<F: Field> ExecutionConfig<F> {
     }
 
     #[allow(clippy::too_many_arguments)]
-    fn configure_lookup<TxTable, RwTable, BytecodeTable, BlockTable>(
+    fn configure_lookup<
+        TxTable,
+        RwTable,
+        BytecodeTable,
+        BlockTable,
+        KeccakTable,
+        CopyTable,
+        ExpTable,
+    >(
         meta: &mut ConstraintSystem<F>,
         q_step: Selector,
-        fixed_table: [Column<Fixed>; 4],
+        fixed_table: FixedTable,
         tx_table: TxTable,
         rw_table: RwTable,
         bytecode_table: BytecodeTable,
         block_table: BlockTable,
+        keccak_table: KeccakTable,
+        copy_table: CopyTable,
+        exp_table: ExpTable,
         independent_lookups: Vec<Vec<Lookup<F>>>,
     ) where
         TxTable: LookupTable<F, 4>,
         RwTable: LookupTable<F, 11>,
         BytecodeTable: LookupTable<F, 4>,
         BlockTable: LookupTable<F, 3>,
+        KeccakTable: LookupTable<F, 3>,
+        CopyTable: LookupTable<F, 13>,
+        ExpTable: LookupTable<F, 3>,
     {
         // Because one and only one ExecutionState is enabled at a step, we then
         // know only one of independent_lookups will be enabled at a step, so we
@@ -498,6 +712,9 @@ impl<F: Field> ExecutionConfig<F> {
         lookup!(Table::Rw, rw_table, "RW table");
         lookup!(Table::Bytecode, bytecode_table, "Bytecode table");
         lookup!(Table::Block, block_table, "Block table");
+        lookup!(Table::Keccak, keccak_table, "Keccak table");
+        lookup!(Table::Copy, copy_table, "Copy table");
+        lookup!(Table::Exp, exp_table, "Exp table");
     }
 
     pub fn assign_block(
@@ -505,22 +722,17 @@ impl<F: Field> ExecutionConfig<F> {
         layouter: &mut impl Layouter<F>,
         block: &Block<F>,
     ) -> Result<(), Error> {
+        let (num_steps, chunks) = self.assign_steps(block)?;
         layouter.assign_region(
-            || "Execution step",
+            || EXECUTION_STEP_REGION,
             |mut region| {
-                let mut offset = 0;
+                self.q_step_first.enable(&mut region, 0)?;
 
-                self.q_step_first.enable(&mut region, offset)?;
-
-                for transaction in &block.txs {
-                    for step in &transaction.steps {
-                        let call = &transaction.calls[step.call_index];
-
-                        self.q_step.enable(&mut region, offset)?;
-                        self.assign_exec_step(&mut region, offset, block, transaction, call, step)?;
-
-                        offset += STEP_HEIGHT;
-                    }
+                for offset in (0..num_steps).map(|idx| idx * STEP_HEIGHT) {
+                    self.q_step.enable(&mut region, offset)?;
+                }
+                for chunk in chunks.iter() {
+                    chunk.assign_to(&mut region)?;
                 }
                 Ok(())
             },
@@ -538,34 +750,62 @@ impl<F: Field> ExecutionConfig<F> {
         layouter: &mut impl Layouter<F>,
         block: &Block<F>,
     ) -> Result<(), Error> {
+        let (num_steps, chunks) = self.assign_steps(block)?;
         layouter.assign_region(
-            || "Execution step",
+            || EXECUTION_STEP_REGION,
             |mut region| {
-                let mut offset = 0;
+                self.q_step_first.enable(&mut region, 0)?;
 
-                self.q_step_first.enable(&mut region, offset)?;
-
-                for transaction in &block.txs {
-                    for step in &transaction.steps {
-                        let call = &transaction.calls[step.call_index];
-
-                        self.q_step.enable(&mut region, offset)?;
-                        self.assign_exec_step(&mut region, offset, block, transaction, call, step)?;
-
-                        offset += STEP_HEIGHT;
-                    }
+                for offset in (0..num_steps).map(|idx| idx * STEP_HEIGHT) {
+                    self.q_step.enable(&mut region, offset)?;
+                }
+                for chunk in chunks.iter() {
+                    chunk.assign_to(&mut region)?;
                 }
 
-                self.q_step_last.enable(&mut region, offset - STEP_HEIGHT)?;
+                self.q_step_last
+                    .enable(&mut region, (num_steps - 1) * STEP_HEIGHT)?;
 
                 Ok(())
             },
         )
     }
 
+    // Returns the number of steps of the block and the values of their cells,
+    // by chunks of `STEPS_PER_CHUNK` steps which are computed in parallel.
+    // They are computed once outside of the region, since the floor planner
+    // may synthesize the region more than once, and only copied to it.
+    fn assign_steps(&self, block: &Block<F>) -> Result<(usize, Vec<StepRegion<F>>), Error> {
+        let steps = block
+            .txs
+            .iter()
+            .flat_map(|transaction| {
+                transaction
+                    .steps
+                    .iter()
+                    .map(move |step| (transaction, &transaction.calls[step.call_index], step))
+            })
+            .collect::<Vec<_>>();
+
+        let chunks = steps
+            .par_chunks(STEPS_PER_CHUNK)
+            .enumerate()
+            .map(|(chunk_idx, chunk)| {
+                let mut region = StepRegion::default();
+                for (idx, (transaction, call, step)) in chunk.iter().enumerate() {
+                    let offset = (chunk_idx * STEPS_PER_CHUNK + idx) * STEP_HEIGHT;
+                    self.assign_exec_step(&mut region, offset, block, transaction, call, step)?;
+                }
+                Ok(region)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok((steps.len(), chunks))
+    }
+
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         transaction: &Transaction,
@@ -595,9 +835,12 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::EndBlock => {
                 assign_exec_step!(self.end_block_gadget)
             }
+            ExecutionState::BeginChunk => assign_exec_step!(self.begin_chunk_gadget),
+            ExecutionState::EndChunk => assign_exec_step!(self.end_chunk_gadget),
             ExecutionState::STOP => assign_exec_step!(self.stop_gadget),
             ExecutionState::ADD => assign_exec_step!(self.add_gadget),
             ExecutionState::MUL => assign_exec_step!(self.mul_gadget),
+            ExecutionState::EXP => assign_exec_step!(self.exp_gadget),
             ExecutionState::BITWISE => assign_exec_step!(self.bitwise_gadget),
             ExecutionState::SIGNEXTEND => {
                 assign_exec_step!(self.signextend_gadget)
@@ -617,6 +860,7 @@ impl<F: Field> ExecutionConfig<F> {
                 assign_exec_step!(self.jumpdest_gadget)
             }
             ExecutionState::GAS => assign_exec_step!(self.gas_gadget),
+            ExecutionState::MCOPY => assign_exec_step!(self.mcopy_gadget),
             ExecutionState::PUSH => assign_exec_step!(self.push_gadget),
             ExecutionState::DUP => assign_exec_step!(self.dup_gadget),
             ExecutionState::SWAP => assign_exec_step!(self.swap_gadget),
@@ -631,7 +875,9 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::NUMBER => {
                 assign_exec_step!(self.number_gadget)
             }
+            ExecutionState::CHAINID => assign_exec_step!(self.chainid_gadget),
             ExecutionState::SELFBALANCE => assign_exec_step!(self.selfbalance_gadget),
+            ExecutionState::BASEFEE => assign_exec_step!(self.basefee_gadget),
             ExecutionState::SLOAD => assign_exec_step!(self.sload_gadget),
             ExecutionState::SSTORE => assign_exec_step!(self.sstore_gadget),
             ExecutionState::CALLDATACOPY => {
@@ -649,9 +895,29 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::CALLDATASIZE => {
                 assign_exec_step!(self.calldatasize_gadget)
             }
+            ExecutionState::CREATE => assign_exec_step!(self.create_gadget),
+            ExecutionState::CREATE2 => assign_exec_step!(self.create2_gadget),
             _ => unimplemented!(),
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::STEPS_PER_CHUNK;
+    use crate::test_util::run_test_circuits;
+    use bus_mapping::evm::OpcodeId;
+    use eth_types::{Bytecode, Word};
+
+    #[test]
+    fn steps_of_several_chunks() {
+        let mut bytecode = Bytecode::default();
+        for value in 0..STEPS_PER_CHUNK as u64 {
+            bytecode.push(1, Word::from(value)).write_op(OpcodeId::POP);
+        }
+        bytecode.write_op(OpcodeId::STOP);
+        assert_eq!(run_test_circuits(bytecode), Ok(()));
+    }
+}
@@ -5,8 +5,8 @@ use crate::{
         util::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
-            math_gadget::{AddWordsGadget, PairSelectGadget},
-            select,
+            math_gadget::AddWordsGadget,
+            select, Cell, StepRegion,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -14,7 +14,7 @@ use crate::{
 };
 use bus_mapping::evm::OpcodeId;
 use eth_types::Field;
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 // AddGadget verifies ADD and SUB at the same time by an extra swap flag,
 // when it's ADD, we annotate stack as [a, b, ...] and [c, ...],
@@ -24,7 +24,7 @@ use halo2_proofs::{circuit::Region, plonk::Error};
 pub(crate) struct AddGadget<F> {
     same_context: SameContextGadget<F>,
     add_words: AddWordsGadget<F, 2, false>,
-    is_sub: PairSelectGadget<F>,
+    is_sub: Cell<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for AddGadget<F> {
@@ -40,19 +40,15 @@ impl<F: Field> ExecutionGadget<F> for AddGadget<F> {
         let c = cb.query_word();
         let add_words = AddWordsGadget::construct(cb, [a.clone(), b.clone()], c.clone());
 
-        // Swap a and c if opcode is SUB
-        let is_sub = PairSelectGadget::construct(
-            cb,
-            opcode.expr(),
-            OpcodeId::SUB.expr(),
-            OpcodeId::ADD.expr(),
-        );
+        // Swap a and c if opcode is SUB, which is bound to the opcode by the
+        // responsible opcode lookup as the index of SUB is 1.
+        let is_sub = cb.query_bool();
 
         // ADD: Pop a and b from the stack, push c on the stack
         // SUB: Pop c and b from the stack, push a on the stack
-        cb.stack_pop(select::expr(is_sub.expr().0, c.expr(), a.expr()));
+        cb.stack_pop(select::expr(is_sub.expr(), c.expr(), a.expr()));
         cb.stack_pop(b.expr());
-        cb.stack_push(select::expr(is_sub.expr().0, a.expr(), c.expr()));
+        cb.stack_push(select::expr(is_sub.expr(), a.expr(), c.expr()));
 
         // State transition
         let step_state_transition = StepStateTransition {
@@ -62,7 +58,12 @@ impl<F: Field> ExecutionGadget<F> for AddGadget<F> {
             gas_left: Delta(-OpcodeId::ADD.constant_gas_cost().expr()),
             ..StepStateTransition::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_with_opcode_index(
+            cb,
+            opcode,
+            is_sub.expr(),
+            step_state_transition,
+        );
 
         Self {
             same_context,
@@ -73,7 +74,7 @@ impl<F: Field> ExecutionGadget<F> for AddGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -93,9 +94,7 @@ impl<F: Field> ExecutionGadget<F> for AddGadget<F> {
         self.is_sub.assign(
             region,
             offset,
-            F::from(opcode.as_u64()),
-            F::from(OpcodeId::SUB.as_u64()),
-            F::from(OpcodeId::ADD.as_u64()),
+            Some(F::from((opcode == OpcodeId::SUB) as u64)),
         )?;
 
         Ok(())
@@ -0,0 +1,50 @@
+use crate::evm_circuit::{
+    execution::ExecutionGadget,
+    step::ExecutionState,
+    util::{
+        constraint_builder::{ConstraintBuilder, StepStateTransition},
+        StepRegion,
+    },
+    witness::{Block, Call, ExecStep, Transaction},
+};
+use eth_types::Field;
+use halo2_proofs::plonk::Error;
+use std::marker::PhantomData;
+
+/// First step of a chunk which isn't the first one of its block, holding the
+/// state the execution resumes from, which is the begin state of the chunk in
+/// the public inputs and the one the previous chunk ends with.
+#[derive(Clone, Debug)]
+pub(crate) struct BeginChunkGadget<F> {
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for BeginChunkGadget<F> {
+    const NAME: &'static str = "BeginChunk";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::BeginChunk;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        // The state is the begin state of the chunk in the public inputs, see
+        // the chunk state checks of `ExecutionConfig`.
+
+        // The execution resumes in the next step without any change of state.
+        cb.require_step_state_transition(StepStateTransition::default());
+
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        _region: &mut StepRegion<F>,
+        _offset: usize,
+        _: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        _step: &ExecStep,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
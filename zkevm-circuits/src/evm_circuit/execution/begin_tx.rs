@@ -1,17 +1,17 @@
 use crate::{
     evm_circuit::{
         execution::ExecutionGadget,
-        param::N_BYTES_GAS,
+        param::{N_BYTES_CALLDATASIZE, N_BYTES_GAS},
         step::ExecutionState,
         table::{AccountFieldTag, CallContextFieldTag, TxContextFieldTag},
         util::{
-            common_gadget::TransferWithGasFeeGadget,
+            common_gadget::{ContractCreateGadget, TransferWithGasFeeGadget},
             constraint_builder::{
-                ConstraintBuilder, StepStateTransition,
+                ConstraintBuilder, ReversionInfo, StepStateTransition,
                 Transition::{Delta, To},
             },
-            math_gadget::{MulWordByU64Gadget, RangeCheckGadget},
-            select, Cell, RandomLinearCombination, Word,
+            math_gadget::{ConstantDivisionGadget, MulWordByU64Gadget, RangeCheckGadget},
+            select, Cell, RandomLinearCombination, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -20,7 +20,7 @@ use crate::{
 use eth_types::evm_types::GasCost;
 use eth_types::Field;
 use eth_types::{ToLittleEndian, ToScalar};
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
 pub(crate) struct BeginTxGadget<F> {
@@ -32,11 +32,12 @@ pub(crate) struct BeginTxGadget<F> {
     tx_caller_address: Cell<F>,
     tx_callee_address: Cell<F>,
     tx_is_create: Cell<F>,
+    contract_create: ContractCreateGadget<F, false>,
     tx_value: Word<F>,
     tx_call_data_length: Cell<F>,
     tx_call_data_gas_cost: Cell<F>,
-    rw_counter_end_of_reversion: Cell<F>,
-    is_persistent: Cell<F>,
+    tx_call_data_word_length: Option<ConstantDivisionGadget<F, N_BYTES_CALLDATASIZE>>,
+    reversion_info: ReversionInfo<F>,
     sufficient_gas_left: RangeCheckGadget<F, N_BYTES_GAS>,
     transfer_with_gas_fee: TransferWithGasFeeGadget<F>,
     code_hash: Cell<F>,
@@ -51,12 +52,8 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         // Use rw_counter of the step which triggers next call as its call_id.
         let call_id = cb.curr.state.rw_counter.clone();
 
-        let [tx_id, rw_counter_end_of_reversion, is_persistent] = [
-            CallContextFieldTag::TxId,
-            CallContextFieldTag::RwCounterEndOfReversion,
-            CallContextFieldTag::IsPersistent,
-        ]
-        .map(|field_tag| cb.call_context(Some(call_id.expr()), field_tag));
+        let tx_id = cb.call_context(Some(call_id.expr()), CallContextFieldTag::TxId);
+        let mut reversion_info = cb.reversion_info(Some(call_id.expr()));
 
         let [tx_nonce, tx_gas, tx_caller_address, tx_callee_address, tx_is_create, tx_call_data_length, tx_call_data_gas_cost] =
             [
@@ -103,10 +100,48 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             GasCost::TX.expr(),
         ) + tx_call_data_gas_cost.expr();
 
+        // Since Shanghai (EIP-3860) a creation transaction is also charged for
+        // every word of its init code.
+        let init_code_word_cost = cb.hardfork().init_code_word_cost();
+        let tx_call_data_word_length = (init_code_word_cost != GasCost::ZERO).then(|| {
+            ConstantDivisionGadget::construct(cb, tx_call_data_length.expr() + 31.expr(), 32)
+        });
+        let intrinsic_gas_cost = match &tx_call_data_word_length {
+            Some(tx_call_data_word_length) => {
+                intrinsic_gas_cost
+                    + tx_is_create.expr()
+                        * init_code_word_cost.expr()
+                        * tx_call_data_word_length.quotient()
+            }
+            None => intrinsic_gas_cost,
+        };
+
         // Check gas_left is sufficient
         let gas_left = tx_gas.expr() - intrinsic_gas_cost;
         let sufficient_gas_left = RangeCheckGadget::construct(cb, gas_left.clone());
 
+        // For contract creation transaction, the callee is the contract
+        // address derived from caller's address and nonce.
+        let contract_create = cb.condition(tx_is_create.expr(), |cb| {
+            let contract_create = ContractCreateGadget::construct(cb);
+            cb.require_equal(
+                "Contract creation caller address is tx caller address",
+                contract_create.caller_address(),
+                tx_caller_address.expr(),
+            );
+            cb.require_equal(
+                "Contract creation nonce is tx nonce",
+                contract_create.nonce(),
+                tx_nonce.expr(),
+            );
+            contract_create
+        });
+        let callee_address = select::expr(
+            tx_is_create.expr(),
+            contract_create.address(),
+            tx_callee_address.expr(),
+        );
+
         // Prepare access list of caller and callee
         cb.account_access_list_write(
             tx_id.expr(),
@@ -117,7 +152,7 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         );
         cb.account_access_list_write(
             tx_id.expr(),
-            tx_callee_address.expr(),
+            callee_address.clone(),
             1.expr(),
             0.expr(),
             None,
@@ -127,20 +162,19 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         let transfer_with_gas_fee = TransferWithGasFeeGadget::construct(
             cb,
             tx_caller_address.expr(),
-            tx_callee_address.expr(),
+            callee_address.clone(),
             tx_value.clone(),
             mul_gas_fee_by_gas.product().clone(),
-            is_persistent.expr(),
-            rw_counter_end_of_reversion.expr(),
+            &mut reversion_info,
         );
 
-        // TODO: Handle creation transaction
+        // TODO: Handle init code execution of creation transaction
         // TODO: Handle precompiled
 
         // Read code_hash of callee
         let code_hash = cb.query_cell();
         cb.account_read(
-            tx_callee_address.expr(),
+            callee_address.clone(),
             AccountFieldTag::CodeHash,
             code_hash.expr(),
         );
@@ -149,7 +183,7 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         for (field_tag, value) in [
             (CallContextFieldTag::Depth, 1.expr()),
             (CallContextFieldTag::CallerAddress, tx_caller_address.expr()),
-            (CallContextFieldTag::CalleeAddress, tx_callee_address.expr()),
+            (CallContextFieldTag::CalleeAddress, callee_address),
             (CallContextFieldTag::CallDataOffset, 0.expr()),
             (
                 CallContextFieldTag::CallDataLength,
@@ -185,13 +219,13 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             //   - Read CallContext LastCalleeId
             //   - Read CallContext LastCalleeReturnDataOffset
             //   - Read CallContext LastCalleeReturnDataLength
-            rw_counter: Delta(19.expr()),
+            rw_counter: Delta(cb.rw_counter_offset()),
             call_id: To(call_id.expr()),
             is_root: To(true.expr()),
             is_create: To(false.expr()),
             code_source: To(code_hash.expr()),
             gas_left: To(gas_left),
-            state_write_counter: To(2.expr()),
+            state_write_counter: To(cb.state_write_counter_offset().expr()),
             ..StepStateTransition::new_context()
         });
 
@@ -204,11 +238,12 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             tx_caller_address,
             tx_callee_address,
             tx_is_create,
+            contract_create,
             tx_value,
             tx_call_data_length,
             tx_call_data_gas_cost,
-            rw_counter_end_of_reversion,
-            is_persistent,
+            tx_call_data_word_length,
+            reversion_info,
             sufficient_gas_left,
             transfer_with_gas_fee,
             code_hash,
@@ -217,7 +252,7 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         tx: &Transaction,
@@ -244,6 +279,16 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             .assign(region, offset, tx.callee_address.to_scalar())?;
         self.tx_is_create
             .assign(region, offset, Some(F::from(tx.is_create as u64)))?;
+        if tx.is_create {
+            self.contract_create.assign(
+                region,
+                offset,
+                tx.caller_address,
+                tx.nonce,
+                None,
+                block.randomness,
+            )?;
+        }
         self.tx_call_data_length.assign(
             region,
             offset,
@@ -251,13 +296,15 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         )?;
         self.tx_call_data_gas_cost
             .assign(region, offset, Some(F::from(tx.call_data_gas_cost)))?;
-        self.rw_counter_end_of_reversion.assign(
+        if let Some(tx_call_data_word_length) = &self.tx_call_data_word_length {
+            tx_call_data_word_length.assign(region, offset, tx.call_data_length as u128 + 31)?;
+        }
+        self.reversion_info.assign(
             region,
             offset,
-            Some(F::from(call.rw_counter_end_of_reversion as u64)),
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
         )?;
-        self.is_persistent
-            .assign(region, offset, Some(F::from(call.is_persistent as u64)))?;
         self.sufficient_gas_left
             .assign(region, offset, F::from(tx.gas - step.gas_cost))?;
         self.transfer_with_gas_fee.assign(
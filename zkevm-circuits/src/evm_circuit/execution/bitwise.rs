@@ -6,7 +6,7 @@ use crate::{
         util::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
-            Word,
+            StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -15,7 +15,7 @@ use crate::{
 use eth_types::evm_types::OpcodeId;
 use eth_types::Field;
 use eth_types::ToLittleEndian;
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
 pub(crate) struct BitwiseGadget<F> {
@@ -43,9 +43,10 @@ impl<F: Field> ExecutionGadget<F> for BitwiseGadget<F> {
 
         // Because opcode AND, OR, and XOR are continuous, so we can make the
         // FixedTableTag of them also continuous, and use the opcode delta from
-        // OpcodeId::AND as the delta to FixedTableTag::BitwiseAnd.
-        let tag =
-            FixedTableTag::BitwiseAnd.expr() + (opcode.expr() - OpcodeId::AND.as_u64().expr());
+        // OpcodeId::AND, which is also its responsible opcode index, as the
+        // delta to FixedTableTag::BitwiseAnd.
+        let opcode_index = opcode.expr() - OpcodeId::AND.as_u64().expr();
+        let tag = FixedTableTag::BitwiseAnd.expr() + opcode_index.clone();
         for idx in 0..32 {
             cb.add_lookup(
                 "Bitwise lookup",
@@ -68,7 +69,12 @@ impl<F: Field> ExecutionGadget<F> for BitwiseGadget<F> {
             gas_left: Delta(-OpcodeId::AND.constant_gas_cost().expr()),
             ..Default::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_with_opcode_index(
+            cb,
+            opcode,
+            opcode_index,
+            step_state_transition,
+        );
 
         Self {
             same_context,
@@ -80,7 +86,7 @@ impl<F: Field> ExecutionGadget<F> for BitwiseGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -12,7 +12,7 @@ use crate::{
             },
             from_bytes,
             memory_gadget::{MemoryAddressGadget, MemoryCopierGasGadget, MemoryExpansionGadget},
-            Cell, MemoryAddress,
+            Cell, MemoryAddress, StepRegion,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -21,7 +21,7 @@ use crate::{
 use bus_mapping::evm::OpcodeId;
 use eth_types::Field;
 use eth_types::ToLittleEndian;
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 use std::convert::TryInto;
 
 #[derive(Clone, Debug)]
@@ -168,7 +168,7 @@ impl<F: Field> ExecutionGadget<F> for CallDataCopyGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         tx: &Transaction,
@@ -231,11 +231,11 @@ mod test {
         execution::memory_copy::test::make_memory_copy_steps,
         step::ExecutionState,
         table::{CallContextFieldTag, RwTableTag},
-        test::{calc_memory_copier_gas_cost, rand_bytes, run_test_circuit_incomplete_fixed_table},
+        test::{rand_bytes, run_test_circuit_incomplete_fixed_table},
         witness::{Block, Bytecode, Call, CodeSource, ExecStep, Rw, RwMap, Transaction},
     };
     use eth_types::{
-        evm_types::{GasCost, OpcodeId},
+        evm_types::{gas::memory_copier_gas_cost, GasCost, OpcodeId},
         ToBigEndian, Word,
     };
     use halo2_proofs::arithmetic::BaseExt;
@@ -307,7 +307,7 @@ mod test {
             (memory_offset.as_u64() + length.as_u64() + 31) / 32
         };
         let gas_cost = GasCost::FASTEST.as_u64()
-            + calc_memory_copier_gas_cost(0, next_memory_word_size, length.as_u64());
+            + memory_copier_gas_cost(0, next_memory_word_size, length.as_u64());
 
         let mut steps = vec![ExecStep {
             rw_indices: vec![
@@ -471,7 +471,7 @@ mod test {
             )
         };
         let gas_cost = GasCost::FASTEST.as_u64()
-            + calc_memory_copier_gas_cost(
+            + memory_copier_gas_cost(
                 curr_memory_word_size,
                 next_memory_word_size,
                 length.as_u64(),
@@ -7,7 +7,7 @@ use crate::{
         util::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
-            from_bytes, RandomLinearCombination,
+            from_bytes, RandomLinearCombination, StepRegion,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -15,7 +15,7 @@ use crate::{
 };
 use bus_mapping::evm::OpcodeId;
 use eth_types::{Field, ToLittleEndian};
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 use std::convert::TryInto;
 
 #[derive(Clone, Debug)]
@@ -62,7 +62,7 @@ impl<F: Field> ExecutionGadget<F> for CallDataSizeGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _tx: &Transaction,
@@ -6,15 +6,15 @@ use crate::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
             from_bytes,
-            math_gadget::{ComparisonGadget, IsEqualGadget},
-            select, Cell, Word,
+            math_gadget::ComparisonGadget,
+            select, Cell, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian};
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
 pub(crate) struct ComparatorGadget<F> {
@@ -24,8 +24,8 @@ pub(crate) struct ComparatorGadget<F> {
     result: Cell<F>,
     comparison_lo: ComparisonGadget<F, 16>,
     comparison_hi: ComparisonGadget<F, 16>,
-    is_eq: IsEqualGadget<F>,
-    is_gt: IsEqualGadget<F>,
+    is_eq: Cell<F>,
+    is_gt: Cell<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for ComparatorGadget<F> {
@@ -40,10 +40,13 @@ impl<F: Field> ExecutionGadget<F> for ComparatorGadget<F> {
         let b = cb.query_word();
 
         // Check if opcode is EQ
-        let is_eq = IsEqualGadget::construct(cb, opcode.expr(), OpcodeId::EQ.expr());
+        let is_eq = cb.query_bool();
         // Check if opcode is GT. For GT we swap the stack inputs so that we
         // actually do greater than instead of smaller than.
-        let is_gt = IsEqualGadget::construct(cb, opcode.expr(), OpcodeId::GT.expr());
+        let is_gt = cb.query_bool();
+        // Both selectors are bound to the opcode by the responsible opcode
+        // lookup, as LT, GT and EQ have index 0, 1 and 2.
+        let opcode_index = is_gt.expr() + 2.expr() * is_eq.expr();
 
         // `a[0..16] <= b[0..16]`
         let comparison_lo = ComparisonGadget::construct(
@@ -90,7 +93,12 @@ impl<F: Field> ExecutionGadget<F> for ComparatorGadget<F> {
             gas_left: Delta(-OpcodeId::LT.constant_gas_cost().expr()),
             ..Default::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_with_opcode_index(
+            cb,
+            opcode,
+            opcode_index,
+            step_state_transition,
+        );
 
         Self {
             same_context,
@@ -106,7 +114,7 @@ impl<F: Field> ExecutionGadget<F> for ComparatorGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -121,19 +129,15 @@ impl<F: Field> ExecutionGadget<F> for ComparatorGadget<F> {
         self.is_eq.assign(
             region,
             offset,
-            F::from(opcode.as_u8() as u64),
-            F::from(OpcodeId::EQ.as_u8() as u64),
+            Some(F::from((opcode == OpcodeId::EQ) as u64)),
         )?;
 
         // swap when doing GT
-        let is_gt = self.is_gt.assign(
-            region,
-            offset,
-            F::from(opcode.as_u8() as u64),
-            F::from(OpcodeId::GT.as_u8() as u64),
-        )?;
+        let is_gt = opcode == OpcodeId::GT;
+        self.is_gt
+            .assign(region, offset, Some(F::from(is_gt as u64)))?;
 
-        let indices = if is_gt == F::one() {
+        let indices = if is_gt {
             [step.rw_indices[1], step.rw_indices[0]]
         } else {
             [step.rw_indices[0], step.rw_indices[1]]
@@ -0,0 +1,451 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        table::{AccountFieldTag, CallContextFieldTag, CopyDataType},
+        util::{
+            common_gadget::{ContractCreateGadget, TransferGadget},
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition,
+                Transition::{Delta, To},
+            },
+            math_gadget::{ConstantDivisionGadget, RangeCheckGadget},
+            memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget, MemoryWordSizeGadget},
+            Cell, RandomLinearCombination, StepRegion, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    gadget::rlc,
+    util::Expr,
+};
+use eth_types::{evm_types::GasCost, keccak256, Field, ToLittleEndian, U256};
+use halo2_proofs::plonk::Error;
+
+/// Gadget for CREATE and CREATE2, which enter the init code copied from
+/// memory in a new call to the contract at the address derived by the
+/// [`ContractCreateGadget`].  The caller nonce is increased and the address is
+/// warmed in the caller, while the contract nonce is set to 1 and the value is
+/// transferred in the created call, which is reverted with it.  The address,
+/// or 0 when the creation fails, is pushed to the stack of the caller, which
+/// resumes from the state saved in its call context.
+///
+/// Only the creations which execute their init code are handled, so the init
+/// code isn't empty.
+#[derive(Clone, Debug)]
+pub(crate) struct CreateGadget<F, const IS_CREATE2: bool> {
+    opcode: Cell<F>,
+    value: Word<F>,
+    memory_address: MemoryAddressGadget<F>,
+    contract_create: ContractCreateGadget<F, IS_CREATE2>,
+    is_success: Cell<F>,
+    tx_id: Cell<F>,
+    depth: Cell<F>,
+    reversion_info: ReversionInfo<F>,
+    is_warm_prev: Cell<F>,
+    // Random linear combination of the init code, and its hash
+    init_code_rlc: Cell<F>,
+    code_hash: Cell<F>,
+    callee_reversion_info: ReversionInfo<F>,
+    transfer: TransferGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    // Size in words of the init code, charged when hashed by CREATE2 and
+    // since Shanghai (EIP-3860)
+    init_code_word_size: Option<MemoryWordSizeGadget<F>>,
+    sufficient_gas_left: RangeCheckGadget<F, N_BYTES_GAS>,
+    // All but one 64th of the gas left is given to the created call (EIP-150)
+    caller_gas_left: ConstantDivisionGadget<F, N_BYTES_GAS>,
+}
+
+pub(crate) type Create1Gadget<F> = CreateGadget<F, false>;
+pub(crate) type Create2Gadget<F> = CreateGadget<F, true>;
+
+impl<F: Field, const IS_CREATE2: bool> ExecutionGadget<F> for CreateGadget<F, IS_CREATE2> {
+    const NAME: &'static str = if IS_CREATE2 { "CREATE2" } else { "CREATE" };
+
+    const EXECUTION_STATE: ExecutionState = if IS_CREATE2 {
+        ExecutionState::CREATE2
+    } else {
+        ExecutionState::CREATE
+    };
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.responsible_opcode_lookup(opcode.expr(), 0.expr());
+
+        // Pop value, offset, length, and salt for CREATE2, from stack
+        let value = cb.query_word();
+        let offset = cb.query_cell();
+        let length = cb.query_rlc();
+        cb.stack_pop(value.expr());
+        cb.stack_pop(offset.expr());
+        cb.stack_pop(length.expr());
+        let memory_address = MemoryAddressGadget::construct(cb, offset, length);
+        cb.require_equal(
+            "init code isn't empty",
+            memory_address.has_length(),
+            1.expr(),
+        );
+
+        let contract_create = ContractCreateGadget::construct(cb);
+        let code_hash = cb.query_cell();
+        if let Some((salt, salt_code_hash)) = contract_create.salt_and_code_hash() {
+            cb.stack_pop(salt);
+            cb.require_equal(
+                "CREATE2 address is derived from the hash of the init code",
+                salt_code_hash,
+                code_hash.expr(),
+            );
+        }
+
+        // Push the address of the contract, or 0 if the creation fails
+        let is_success = cb.query_bool();
+        let address_rlc = contract_create.address_rlc(cb.power_of_randomness());
+        cb.stack_push(is_success.expr() * address_rlc);
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let depth = cb.call_context(None, CallContextFieldTag::Depth);
+        cb.call_context_lookup(
+            false.expr(),
+            None,
+            CallContextFieldTag::CalleeAddress,
+            contract_create.caller_address(),
+        );
+        let mut reversion_info = cb.reversion_info(None);
+
+        // Increase caller's nonce and warm the address of the contract, which
+        // are only reverted with the caller
+        cb.account_write(
+            contract_create.caller_address(),
+            AccountFieldTag::Nonce,
+            contract_create.nonce() + 1.expr(),
+            contract_create.nonce(),
+            Some(&mut reversion_info),
+        );
+        let is_warm_prev = cb.query_bool();
+        cb.account_access_list_write(
+            tx_id.expr(),
+            contract_create.address(),
+            1.expr(),
+            is_warm_prev.expr(),
+            Some(&mut reversion_info),
+        );
+
+        // Copy the init code from memory to the bytecode of its hash
+        let init_code_rlc = cb.query_cell();
+        let call_id = cb.curr.state.call_id.expr();
+        cb.copy_table_lookup(
+            call_id.clone(),
+            CopyDataType::Memory,
+            code_hash.expr(),
+            CopyDataType::Bytecode,
+            memory_address.offset(),
+            memory_address.address(),
+            0.expr(),
+            0.expr(),
+            memory_address.length(),
+            init_code_rlc.expr(),
+            memory_address.length(),
+        );
+        cb.keccak_table_lookup(
+            init_code_rlc.expr(),
+            memory_address.length(),
+            code_hash.expr(),
+        );
+
+        // Use rw_counter of the step which triggers next call as its call_id.
+        let callee_call_id = cb.curr.state.rw_counter.clone();
+        let mut callee_reversion_info = cb.reversion_info(Some(callee_call_id.expr()));
+
+        // Set the nonce of the contract to 1 (EIP-161) and transfer the value
+        // to it
+        cb.account_write(
+            contract_create.address(),
+            AccountFieldTag::Nonce,
+            1.expr(),
+            0.expr(),
+            Some(&mut callee_reversion_info),
+        );
+        let transfer = TransferGadget::construct(
+            cb,
+            contract_create.caller_address(),
+            contract_create.address(),
+            value.clone(),
+            &mut callee_reversion_info,
+        );
+
+        // Calculate the next memory size and the gas cost, which includes the
+        // hashing of the init code for CREATE2, and every word of the init code
+        // since Shanghai (EIP-3860)
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [memory_address.address()],
+        );
+        let init_code_word_gas = cb.hardfork().init_code_word_cost().as_u64()
+            + if IS_CREATE2 {
+                GasCost::SHA3_WORD.as_u64()
+            } else {
+                0
+            };
+        let init_code_word_size = (init_code_word_gas != 0)
+            .then(|| MemoryWordSizeGadget::construct(cb, memory_address.length()));
+        let gas_cost = GasCost::CREATE.expr()
+            + memory_expansion.gas_cost()
+            + match &init_code_word_size {
+                Some(init_code_word_size) => init_code_word_gas.expr() * init_code_word_size.expr(),
+                None => 0.expr(),
+            };
+        let gas_left = cb.curr.state.gas_left.expr() - gas_cost;
+        let sufficient_gas_left = RangeCheckGadget::construct(cb, gas_left.clone());
+        let caller_gas_left = ConstantDivisionGadget::construct(cb, gas_left.clone(), 64);
+
+        // Setup next call's context.
+        for (field_tag, value) in [
+            (CallContextFieldTag::CallerId, call_id),
+            (CallContextFieldTag::TxId, tx_id.expr()),
+            (CallContextFieldTag::Depth, depth.expr() + 1.expr()),
+            (
+                CallContextFieldTag::CallerAddress,
+                contract_create.caller_address(),
+            ),
+            (
+                CallContextFieldTag::CalleeAddress,
+                contract_create.address(),
+            ),
+            (CallContextFieldTag::CallDataOffset, 0.expr()),
+            (CallContextFieldTag::CallDataLength, 0.expr()),
+            (CallContextFieldTag::Value, value.expr()),
+            (CallContextFieldTag::IsSuccess, is_success.expr()),
+            (CallContextFieldTag::IsStatic, 0.expr()),
+            (CallContextFieldTag::LastCalleeId, 0.expr()),
+            (CallContextFieldTag::LastCalleeReturnDataOffset, 0.expr()),
+            (CallContextFieldTag::LastCalleeReturnDataLength, 0.expr()),
+        ] {
+            cb.call_context_lookup(false.expr(), Some(callee_call_id.expr()), field_tag, value);
+        }
+
+        // Save the state the caller resumes from
+        let n_pop: usize = if IS_CREATE2 { 4 } else { 3 };
+        for (field_tag, value) in [
+            (
+                CallContextFieldTag::ProgramCounter,
+                cb.curr.state.program_counter.expr() + 1.expr(),
+            ),
+            (
+                CallContextFieldTag::StackPointer,
+                cb.curr.state.stack_pointer.expr() + (n_pop - 1).expr(),
+            ),
+            (CallContextFieldTag::GasLeft, caller_gas_left.quotient()),
+            (
+                CallContextFieldTag::MemorySize,
+                memory_expansion.next_memory_word_size(),
+            ),
+            (
+                CallContextFieldTag::StateWriteCounter,
+                cb.curr.state.state_write_counter.expr() + 2.expr(),
+            ),
+        ] {
+            cb.call_context_lookup(true.expr(), None, field_tag, value);
+        }
+
+        cb.require_step_state_transition(StepStateTransition {
+            // n_pop + 1 stack, 5 + 7 + 13 call context, 2 + 3 state writes and
+            // a memory read of each byte of the init code
+            rw_counter: Delta(cb.rw_counter_offset()),
+            call_id: To(callee_call_id.expr()),
+            is_root: To(false.expr()),
+            is_create: To(true.expr()),
+            code_source: To(code_hash.expr()),
+            gas_left: To(gas_left - caller_gas_left.quotient()),
+            // The nonce and the balances written in the created call
+            state_write_counter: To(3.expr()),
+            ..StepStateTransition::new_context()
+        });
+
+        Self {
+            opcode,
+            value,
+            memory_address,
+            contract_create,
+            is_success,
+            tx_id,
+            depth,
+            reversion_info,
+            is_warm_prev,
+            init_code_rlc,
+            code_hash,
+            callee_reversion_info,
+            transfer,
+            memory_expansion,
+            init_code_word_size,
+            sufficient_gas_left,
+            caller_gas_left,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut StepRegion<F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let n_pop = if IS_CREATE2 { 4 } else { 3 };
+        let [value, memory_offset, length] =
+            [step.rw_indices[0], step.rw_indices[1], step.rw_indices[2]]
+                .map(|idx| block.rws[idx].stack_value());
+        let salt = IS_CREATE2.then(|| block.rws[step.rw_indices[3]].stack_value());
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        let memory_address =
+            self.memory_address
+                .assign(region, offset, memory_offset, length, block.randomness)?;
+
+        // The memory reads of the init code follow the caller nonce and the
+        // access list writes
+        let (_, nonce) = block.rws[step.rw_indices[n_pop + 6]].account_value_pair();
+        let (_, is_warm_prev) = block.rws[step.rw_indices[n_pop + 7]].tx_access_list_value_pair();
+        let init_code = step.rw_indices[n_pop + 8..n_pop + 8 + length.as_usize()]
+            .iter()
+            .map(|idx| block.rws[*idx].memory_value())
+            .collect::<Vec<_>>();
+        let code_hash = U256::from_big_endian(&keccak256(&init_code));
+
+        self.contract_create.assign(
+            region,
+            offset,
+            call.callee_address,
+            nonce.as_u64(),
+            salt.map(|salt| (salt, code_hash)),
+            block.randomness,
+        )?;
+
+        let callee = tx
+            .calls
+            .iter()
+            .find(|callee| callee.id == step.rw_counter)
+            .ok_or(Error::Synthesis)?;
+        self.is_success
+            .assign(region, offset, Some(F::from(callee.is_success as u64)))?;
+        self.tx_id
+            .assign(region, offset, Some(F::from(tx.id as u64)))?;
+        self.depth
+            .assign(region, offset, Some(F::from(call.depth as u64)))?;
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+        self.is_warm_prev
+            .assign(region, offset, Some(F::from(is_warm_prev as u64)))?;
+
+        self.init_code_rlc.assign(
+            region,
+            offset,
+            Some(rlc::value(&init_code, block.randomness)),
+        )?;
+        self.code_hash.assign(
+            region,
+            offset,
+            Some(RandomLinearCombination::random_linear_combine(
+                code_hash.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+
+        self.callee_reversion_info.assign(
+            region,
+            offset,
+            callee.rw_counter_end_of_reversion,
+            callee.is_persistent,
+        )?;
+        let rw_offset = n_pop + 8 + init_code.len();
+        let [caller_balance_pair, callee_balance_pair] = [
+            step.rw_indices[rw_offset + 3],
+            step.rw_indices[rw_offset + 4],
+        ]
+        .map(|idx| block.rws[idx].account_value_pair());
+        self.transfer.assign(
+            region,
+            offset,
+            caller_balance_pair,
+            callee_balance_pair,
+            value,
+        )?;
+
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [memory_address],
+        )?;
+        if let Some(init_code_word_size) = &self.init_code_word_size {
+            init_code_word_size.assign(region, offset, length.as_u64())?;
+        }
+        debug_assert_eq!(
+            step.gas_cost,
+            block
+                .chain_config
+                .hardfork
+                .create_gas_cost(IS_CREATE2, length.as_u64())
+                .as_u64()
+                + memory_expansion_gas_cost as u64
+        );
+        let gas_left = step.gas_left - step.gas_cost;
+        self.sufficient_gas_left
+            .assign(region, offset, F::from(gas_left))?;
+        self.caller_gas_left
+            .assign(region, offset, gas_left as u128)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use bus_mapping::evm::OpcodeId;
+    use eth_types::{bytecode, Word};
+
+    fn test_ok(is_create2: bool) {
+        // The init code is a single STOP, at the end of the zeroed word
+        let mut code = bytecode! {
+            PUSH1(0x00)
+            PUSH1(0x00)
+            MSTORE
+        };
+        if is_create2 {
+            code.push(2, Word::from(0x1234)); // salt
+        }
+        code.push(1, Word::one()); // length
+        code.push(1, Word::from(31)); // offset
+        code.push(1, Word::zero()); // value
+        code.write_op(if is_create2 {
+            OpcodeId::CREATE2
+        } else {
+            OpcodeId::CREATE
+        });
+        code.write_op(OpcodeId::STOP);
+
+        assert_eq!(run_test_circuits(code), Ok(()));
+    }
+
+    #[test]
+    fn create_gadget_simple() {
+        test_ok(false);
+    }
+
+    #[test]
+    fn create2_gadget_simple() {
+        test_ok(true);
+    }
+}
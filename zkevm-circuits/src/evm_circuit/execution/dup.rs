@@ -5,14 +5,14 @@ use crate::{
         util::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
-            Cell, Word,
+            Cell, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian};
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
 pub(crate) struct DupGadget<F> {
@@ -31,11 +31,12 @@ impl<F: Field> ExecutionGadget<F> for DupGadget<F> {
         let value = cb.query_cell();
 
         // The stack index we have to peek, deduced from the 'x' value of 'dupx'
-        // The offset starts at 0 for DUP1
+        // The offset starts at 0 for DUP1, so it's also the responsible opcode
+        // index
         let dup_offset = opcode.expr() - OpcodeId::DUP1.expr();
 
         // Peek the value at `dup_offset` and push the value on the stack
-        cb.stack_lookup(false.expr(), dup_offset, value.expr());
+        cb.stack_lookup(false.expr(), dup_offset.clone(), value.expr());
         cb.stack_push(value.expr());
 
         // State transition
@@ -46,7 +47,12 @@ impl<F: Field> ExecutionGadget<F> for DupGadget<F> {
             gas_left: Delta(-OpcodeId::DUP1.constant_gas_cost().expr()),
             ..Default::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_with_opcode_index(
+            cb,
+            opcode,
+            dup_offset,
+            step_state_transition,
+        );
 
         Self {
             same_context,
@@ -56,7 +62,7 @@ impl<F: Field> ExecutionGadget<F> for DupGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -0,0 +1,44 @@
+use crate::evm_circuit::{
+    execution::ExecutionGadget,
+    step::ExecutionState,
+    util::{constraint_builder::ConstraintBuilder, StepRegion},
+    witness::{Block, Call, ExecStep, Transaction},
+};
+use eth_types::Field;
+use halo2_proofs::plonk::Error;
+use std::marker::PhantomData;
+
+/// Last step of a chunk which isn't the last one of its block, holding the
+/// state of the first step of the next chunk, which the previous step
+/// transits to and is the end state of the chunk in the public inputs.
+#[derive(Clone, Debug)]
+pub(crate) struct EndChunkGadget<F> {
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for EndChunkGadget<F> {
+    const NAME: &'static str = "EndChunk";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::EndChunk;
+
+    fn configure(_: &mut ConstraintBuilder<F>) -> Self {
+        // The state is the end state of the chunk in the public inputs, see the
+        // chunk state checks of `ExecutionConfig`.
+
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        _region: &mut StepRegion<F>,
+        _offset: usize,
+        _: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        _step: &ExecStep,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
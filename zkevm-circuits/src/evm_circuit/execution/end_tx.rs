@@ -10,14 +10,14 @@ use crate::{
             math_gadget::{
                 AddWordsGadget, ConstantDivisionGadget, MinMaxGadget, MulWordByU64Gadget,
             },
-            Cell,
+            Cell, StepRegion,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
-use eth_types::{evm_types::MAX_REFUND_QUOTIENT_OF_GAS_USED, Field, ToScalar};
-use halo2_proofs::{circuit::Region, plonk::Error};
+use eth_types::{Field, ToScalar};
+use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
 pub(crate) struct EndTxGadget<F> {
@@ -53,7 +53,7 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
         let max_refund = ConstantDivisionGadget::construct(
             cb,
             gas_used.clone(),
-            MAX_REFUND_QUOTIENT_OF_GAS_USED as u64,
+            cb.hardfork().max_refund_quotient_of_gas_used(),
         );
         let refund = cb.query_cell();
         cb.tx_refund_read(tx_id.expr(), refund.expr());
@@ -138,7 +138,7 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         tx: &Transaction,
@@ -210,19 +210,11 @@ mod test {
     use crate::evm_circuit::{
         test::run_test_circuit_incomplete_fixed_table, witness::block_convert,
     };
-    use eth_types::{self, address, geth_types::Account, Address, Word};
+    use eth_types::Word;
+    use mock::{eth, MockTransaction, TestContext, MOCK_ACCOUNTS};
 
-    fn test_ok(txs: Vec<eth_types::Transaction>) {
-        let accounts = txs
-            .iter()
-            .map(|tx| Account {
-                address: tx.from,
-                balance: Word::from(10).pow(20.into()),
-                ..Default::default()
-            })
-            .collect::<Vec<_>>();
-        let block_data =
-            bus_mapping::mock::BlockData::new_from_geth_data(mock::new(accounts, txs).unwrap());
+    fn test_ok<const NACC: usize, const NTX: usize>(ctx: TestContext<NACC, NTX>) {
+        let block_data = bus_mapping::mock::BlockData::new_from_geth_data(ctx.into());
         let mut builder = block_data.new_circuit_input_builder();
         builder
             .handle_block(&block_data.eth_block, &block_data.geth_traces)
@@ -232,46 +224,32 @@ mod test {
         assert_eq!(run_test_circuit_incomplete_fixed_table(block), Ok(()));
     }
 
-    fn mock_tx(from: Address, gas: Option<u64>, gas_price: Option<Word>) -> eth_types::Transaction {
-        let to = address!("0x00000000000000000000000000000000000000ff");
-        let minimal_gas = Word::from(21000);
-        let two_gwei = Word::from(2_000_000_000);
-        eth_types::Transaction {
-            from,
-            to: Some(to),
-            gas: gas.map(Word::from).unwrap_or(minimal_gas),
-            gas_price: gas_price.or(Some(two_gwei)),
-            ..Default::default()
-        }
+    // Sets the minimal gas and a gas price of 2 gwei to the transfer `tx`.
+    fn mock_tx(tx: &mut MockTransaction) -> &mut MockTransaction {
+        tx.to(MOCK_ACCOUNTS[2])
+            .gas(Word::from(21000))
+            .gas_price(Word::from(2_000_000_000))
     }
 
     #[test]
     fn end_tx_gadget_simple() {
         // TODO: Enable this with respective code when SSTORE is implemented.
-        // Tx with non-capped refund
-        // test_ok(vec![mock_tx(
-        //     address!("0x00000000000000000000000000000000000000fe"),
-        //     Some(27000),
-        //     None,
-        // )]);
-        // Tx with capped refund
-        // test_ok(vec![mock_tx(
-        //     address!("0x00000000000000000000000000000000000000fe"),
-        //     Some(65000),
-        //     None,
-        // )]);
+        // Tx with non-capped refund, with a gas of 27000
+        // Tx with capped refund, with a gas of 65000
         // Multiple txs
-        test_ok(vec![
-            mock_tx(
-                address!("0x00000000000000000000000000000000000000fd"),
-                None,
-                None,
-            ),
-            mock_tx(
-                address!("0x00000000000000000000000000000000000000fe"),
-                None,
-                None,
-            ),
-        ]);
+        test_ok(
+            TestContext::<2, 2>::new(
+                |accs| {
+                    accs[0].address(MOCK_ACCOUNTS[0]).balance(eth(100));
+                    accs[1].address(MOCK_ACCOUNTS[1]).balance(eth(100));
+                },
+                |txs, accs| {
+                    mock_tx(&mut txs[0]).from(accs[0].address);
+                    mock_tx(&mut txs[1]).from(accs[1].address);
+                },
+                |_block| {},
+            )
+            .unwrap(),
+        );
     }
 }
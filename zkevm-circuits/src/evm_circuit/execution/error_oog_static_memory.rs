@@ -5,16 +5,16 @@ use crate::{
         step::ExecutionState,
         util::{
             constraint_builder::ConstraintBuilder,
-            math_gadget::{IsEqualGadget, IsZeroGadget, RangeCheckGadget},
+            math_gadget::{IsZeroGadget, RangeCheckGadget},
             memory_gadget::{address_high, address_low, MemoryExpansionGadget},
-            Cell, Word,
+            Cell, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian};
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
 pub(crate) struct ErrorOOGStaticMemoryGadget<F> {
@@ -32,7 +32,8 @@ pub(crate) struct ErrorOOGStaticMemoryGadget<F> {
     // expansion gas cost could be at most 2^61 - 2^27, due to the constant
     // division by 512, which still fits in 8 bytes.
     insufficient_gas: RangeCheckGadget<F, N_BYTES_GAS>,
-    is_mstore8: IsEqualGadget<F>,
+    is_mstore: Cell<F>,
+    is_mstore8: Cell<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
@@ -43,12 +44,19 @@ impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
     // Support other OOG due to pure memory including CREATE, RETURN and REVERT
     fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
         let opcode = cb.query_cell();
+        // Check if this is an MSTORE or an MSTORE8, both selectors are bound to
+        // the opcode by the responsible opcode lookup, as MLOAD, MSTORE and
+        // MSTORE8 have index 0, 1 and 2.
+        let is_mstore = cb.query_bool();
+        let is_mstore8 = cb.query_bool();
+        cb.responsible_opcode_lookup(
+            opcode.expr(),
+            is_mstore.expr() + 2.expr() * is_mstore8.expr(),
+        );
 
         // Query address by a full word
         let address = cb.query_word();
 
-        // Check if this is an MSTORE8
-        let is_mstore8 = IsEqualGadget::construct(cb, opcode.expr(), OpcodeId::MSTORE8.expr());
         let is_not_mstore8 = 1.expr() - is_mstore8.expr();
 
         // Get the next memory size and the gas cost for this memory access
@@ -83,13 +91,14 @@ impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
             address_in_range,
             memory_expansion,
             insufficient_gas,
+            is_mstore,
             is_mstore8,
         }
     }
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -103,13 +112,15 @@ impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
         self.address
             .assign(region, offset, Some(address.to_le_bytes()))?;
 
-        // Check if this is an MSTORE8
-        let is_mstore8 = self.is_mstore8.assign(
+        // Check if this is an MSTORE or an MSTORE8
+        self.is_mstore.assign(
             region,
             offset,
-            F::from(opcode.as_u64()),
-            F::from(OpcodeId::MSTORE8.as_u64()),
+            Some(F::from((opcode == OpcodeId::MSTORE) as u64)),
         )?;
+        let is_mstore8 = opcode == OpcodeId::MSTORE8;
+        self.is_mstore8
+            .assign(region, offset, Some(F::from(is_mstore8 as u64)))?;
 
         // Address in range check
         self.address_in_range.assign(
@@ -123,8 +134,7 @@ impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
             region,
             offset,
             step.memory_word_size(),
-            [address_low::value(address.to_le_bytes())
-                + if is_mstore8 == F::one() { 1 } else { 32 }],
+            [address_low::value(address.to_le_bytes()) + if is_mstore8 { 1 } else { 32 }],
         )?;
 
         // Gas insufficient check
@@ -0,0 +1,157 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
+            math_gadget::ByteSizeGadget,
+            StepRegion, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{evm_types::GasCost, Field, ToLittleEndian};
+use halo2_proofs::plonk::Error;
+
+// ExpGadget verifies EXP: base^exponent mod 2^256 is equal to result, by a
+// lookup to the exp table assigned by the exp circuit.
+#[derive(Clone, Debug)]
+pub(crate) struct ExpGadget<F> {
+    same_context: SameContextGadget<F>,
+    base: Word<F>,
+    exponent: Word<F>,
+    result: Word<F>,
+    exponent_byte_size: ByteSizeGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ExpGadget<F> {
+    const NAME: &'static str = "EXP";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::EXP;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let base = cb.query_word();
+        let exponent = cb.query_word();
+        let result = cb.query_word();
+
+        // Pop base and exponent from the stack, push result on the stack
+        cb.stack_pop(base.expr());
+        cb.stack_pop(exponent.expr());
+        cb.stack_push(result.expr());
+
+        // base^exponent == result (mod 2^256)
+        cb.exp_table_lookup(base.expr(), exponent.expr(), result.expr());
+
+        // The dynamic gas cost is charged for every byte of the exponent
+        let exponent_byte_size = ByteSizeGadget::construct(cb, &exponent);
+        let gas_cost = OpcodeId::EXP.constant_gas_cost().expr()
+            + GasCost::EXP_BYTE_TIMES.expr() * exponent_byte_size.byte_size();
+
+        // State transition
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(3.expr()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(1.expr()),
+            gas_left: Delta(-gas_cost),
+            ..Default::default()
+        };
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            base,
+            exponent,
+            result,
+            exponent_byte_size,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut StepRegion<F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+        let indices = [step.rw_indices[0], step.rw_indices[1], step.rw_indices[2]];
+        let [base, exponent, result] = indices.map(|idx| block.rws[idx].stack_value());
+        self.base.assign(region, offset, Some(base.to_le_bytes()))?;
+        self.exponent
+            .assign(region, offset, Some(exponent.to_le_bytes()))?;
+        self.result
+            .assign(region, offset, Some(result.to_le_bytes()))?;
+        self.exponent_byte_size.assign(region, offset, exponent)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        evm_circuit::{
+            test::{rand_word, run_test_circuit_incomplete_fixed_table},
+            witness::block_convert,
+        },
+        test_util::run_test_circuits,
+    };
+    use bus_mapping::mock::BlockData;
+    use eth_types::{bytecode, Bytecode, Word};
+
+    fn exp_bytecode(base: Word, exponent: Word) -> Bytecode {
+        bytecode! {
+            PUSH32(exponent)
+            PUSH32(base)
+            EXP
+            STOP
+        }
+    }
+
+    fn test_ok(base: Word, exponent: Word) {
+        assert_eq!(run_test_circuits(exp_bytecode(base, exponent)), Ok(()));
+    }
+
+    #[test]
+    fn exp_gadget_simple() {
+        test_ok(Word::from(3), Word::from(5));
+        test_ok(Word::from(2), Word::from(256));
+    }
+
+    #[test]
+    fn exp_gadget_edge_exponents() {
+        for exponent in [Word::zero(), Word::one(), Word::one() << 255, Word::MAX] {
+            test_ok(Word::from(3), exponent);
+            test_ok(Word::zero(), exponent);
+            test_ok(Word::MAX, exponent);
+        }
+    }
+
+    #[test]
+    fn exp_gadget_rand() {
+        test_ok(rand_word(), rand_word());
+    }
+
+    #[test]
+    fn exp_gadget_invalid_exp_table() {
+        let block_data = BlockData::new_from_geth_data(
+            mock::new_single_tx_trace_code(&exp_bytecode(Word::from(3), Word::from(5))).unwrap(),
+        );
+        let mut builder = block_data.new_circuit_input_builder();
+        builder
+            .handle_block(&block_data.eth_block, &block_data.geth_traces)
+            .unwrap();
+        let mut block = block_convert(&builder.block, &builder.code_db);
+        assert_eq!(block.exp_events[0].result, Word::from(243));
+
+        // The exp table row doesn't match the result pushed on the stack
+        block.exp_events[0].result = Word::from(242);
+        assert!(run_test_circuit_incomplete_fixed_table(block).is_err());
+    }
+}
@@ -5,6 +5,7 @@ use crate::{
         util::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
+            StepRegion,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -12,7 +13,7 @@ use crate::{
 };
 use bus_mapping::evm::OpcodeId;
 use eth_types::Field;
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
 pub(crate) struct JumpdestGadget<F> {
@@ -39,7 +40,7 @@ impl<F: Field> ExecutionGadget<F> for JumpdestGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         _: &Block<F>,
         _: &Transaction,
@@ -0,0 +1,361 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_MEMORY_WORD_SIZE,
+        step::ExecutionState,
+        table::CopyDataType,
+        util::{
+            common_gadget::SameContextGadget,
+            constraint_builder::{
+                ConstraintBuilder, StepStateTransition,
+                Transition::{Delta, To},
+            },
+            memory_gadget::{MemoryAddressGadget, MemoryCopierGasGadget, MemoryExpansionGadget},
+            Cell, StepRegion,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    gadget::rlc,
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::Field;
+use halo2_proofs::plonk::Error;
+
+/// Gadget for MCOPY (EIP-5656), which copies `length` bytes of memory from
+/// `src_offset` to `dst_offset`. The copy itself is looked up in the copy
+/// table, whose copies from memory to memory read all the bytes of the source
+/// before writing the first one, so that the source and the destination can
+/// overlap.
+#[derive(Clone, Debug)]
+pub(crate) struct McopyGadget<F> {
+    same_context: SameContextGadget<F>,
+    dst_memory_address: MemoryAddressGadget<F>,
+    src_memory_address: MemoryAddressGadget<F>,
+    // Random linear combination of the bytes copied
+    rlc_acc: Cell<F>,
+    memory_expansion: MemoryExpansionGadget<F, 2, N_BYTES_MEMORY_WORD_SIZE>,
+    memory_copier_gas: MemoryCopierGasGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for McopyGadget<F> {
+    const NAME: &'static str = "MCOPY";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::MCOPY;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let dst_offset = cb.query_cell();
+        let src_offset = cb.query_cell();
+        let length = cb.query_rlc();
+
+        // Pop dst_offset, src_offset, length from stack
+        cb.stack_pop(dst_offset.expr());
+        cb.stack_pop(src_offset.expr());
+        cb.stack_pop(length.expr());
+
+        // Both the source and the destination range can expand the memory
+        let dst_memory_address = MemoryAddressGadget::construct(cb, dst_offset, length.clone());
+        let src_memory_address = MemoryAddressGadget::construct(cb, src_offset, length);
+
+        // Calculate the next memory size and the gas cost for this memory
+        // access
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [dst_memory_address.address(), src_memory_address.address()],
+        );
+        let memory_copier_gas = MemoryCopierGasGadget::construct(
+            cb,
+            dst_memory_address.length(),
+            memory_expansion.gas_cost(),
+        );
+
+        // Copy the bytes if length != 0, reading all of them before writing
+        // the first one
+        let rlc_acc = cb.query_cell();
+        let call_id = cb.curr.state.call_id.expr();
+        cb.condition(dst_memory_address.has_length(), |cb| {
+            cb.copy_table_lookup(
+                call_id.clone(),
+                CopyDataType::Memory,
+                call_id,
+                CopyDataType::Memory,
+                src_memory_address.offset(),
+                src_memory_address.address(),
+                dst_memory_address.offset(),
+                0.expr(),
+                dst_memory_address.length(),
+                rlc_acc.expr(),
+                2.expr() * dst_memory_address.length(),
+            );
+        });
+
+        // State transition
+        let step_state_transition = StepStateTransition {
+            // 3 stack pop, and a memory read and write of each byte copied
+            rw_counter: Delta(cb.rw_counter_offset()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(3.expr()),
+            gas_left: Delta(
+                -(OpcodeId::MCOPY.constant_gas_cost().expr() + memory_copier_gas.gas_cost()),
+            ),
+            memory_word_size: To(memory_expansion.next_memory_word_size()),
+            ..Default::default()
+        };
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            dst_memory_address,
+            src_memory_address,
+            rlc_acc,
+            memory_expansion,
+            memory_copier_gas,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut StepRegion<F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let [dst_offset, src_offset, length] =
+            [step.rw_indices[0], step.rw_indices[1], step.rw_indices[2]]
+                .map(|idx| block.rws[idx].stack_value());
+        let dst_memory_address =
+            self.dst_memory_address
+                .assign(region, offset, dst_offset, length, block.randomness)?;
+        let src_memory_address =
+            self.src_memory_address
+                .assign(region, offset, src_offset, length, block.randomness)?;
+
+        // Memory expansion
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [dst_memory_address, src_memory_address],
+        )?;
+
+        self.memory_copier_gas.assign(
+            region,
+            offset,
+            length.as_u64(),
+            memory_expansion_gas_cost as u64,
+        )?;
+
+        // The bytes read follow the stack reads
+        let bytes = step.rw_indices[3..3 + length.as_usize()]
+            .iter()
+            .map(|idx| block.rws[*idx].memory_value())
+            .collect::<Vec<_>>();
+        self.rlc_acc
+            .assign(region, offset, Some(rlc::value(&bytes, block.randomness)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::evm_circuit::{
+        step::ExecutionState,
+        table::{CopyDataType, RwTableTag},
+        test::{rand_bytes, run_test_circuit_incomplete_fixed_table},
+        witness::{Block, Bytecode, Call, CodeSource, CopyEvent, ExecStep, Rw, RwMap, Transaction},
+    };
+    use eth_types::{
+        evm_types::{gas::memory_copier_gas_cost, GasCost, OpcodeId},
+        ToBigEndian, Word,
+    };
+    use halo2_proofs::arithmetic::BaseExt;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_ok(curr_memory_size: u64, dst_offset: Word, src_offset: Word, length: Word) {
+        let randomness = Fp::rand();
+        let bytecode = Bytecode::new(
+            [
+                vec![OpcodeId::PUSH32.as_u8()],
+                length.to_be_bytes().to_vec(),
+                vec![OpcodeId::PUSH32.as_u8()],
+                src_offset.to_be_bytes().to_vec(),
+                vec![OpcodeId::PUSH32.as_u8()],
+                dst_offset.to_be_bytes().to_vec(),
+                vec![OpcodeId::MCOPY.as_u8(), OpcodeId::STOP.as_u8()],
+            ]
+            .concat(),
+        );
+        let call_id = 1;
+        let bytes: Vec<u8> = rand_bytes(length.as_usize());
+
+        let mut rws = RwMap(
+            [(
+                RwTableTag::Stack,
+                vec![
+                    Rw::Stack {
+                        rw_counter: 1,
+                        is_write: false,
+                        call_id,
+                        stack_pointer: 1021,
+                        value: dst_offset,
+                    },
+                    Rw::Stack {
+                        rw_counter: 2,
+                        is_write: false,
+                        call_id,
+                        stack_pointer: 1022,
+                        value: src_offset,
+                    },
+                    Rw::Stack {
+                        rw_counter: 3,
+                        is_write: false,
+                        call_id,
+                        stack_pointer: 1023,
+                        value: length,
+                    },
+                ],
+            )]
+            .into(),
+        );
+        // All the bytes of the source are read before the first one is
+        // written
+        let memory_rws = bytes
+            .iter()
+            .enumerate()
+            .map(|(idx, byte)| (false, src_offset, idx, *byte))
+            .chain(
+                bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, byte)| (true, dst_offset, idx, *byte)),
+            )
+            .enumerate()
+            .map(|(rw_idx, (is_write, offset, idx, byte))| Rw::Memory {
+                rw_counter: 4 + rw_idx,
+                is_write,
+                call_id,
+                memory_address: offset.as_u64() + idx as u64,
+                byte,
+            })
+            .collect::<Vec<_>>();
+        let n_memory_rws = memory_rws.len();
+        rws.0.insert(RwTableTag::Memory, memory_rws);
+        let copy_events = if length.is_zero() {
+            vec![]
+        } else {
+            vec![CopyEvent {
+                src_type: CopyDataType::Memory,
+                src_id: Word::from(call_id as u64),
+                src_addr: src_offset.as_u64(),
+                src_addr_end: src_offset.as_u64() + length.as_u64(),
+                dst_type: CopyDataType::Memory,
+                dst_id: Word::from(call_id as u64),
+                dst_addr: dst_offset.as_u64(),
+                log_id: 0,
+                rw_counter_start: 4,
+                bytes: bytes.iter().map(|byte| (*byte, false)).collect(),
+            }]
+        };
+
+        let curr_memory_word_size = (curr_memory_size + 31) / 32;
+        let next_memory_word_size = if length.is_zero() {
+            curr_memory_word_size
+        } else {
+            [dst_offset, src_offset]
+                .iter()
+                .map(|offset| (offset.as_u64() + length.as_u64() + 31) / 32)
+                .fold(curr_memory_word_size, std::cmp::max)
+        };
+        let gas_cost = GasCost::FASTEST.as_u64()
+            + memory_copier_gas_cost(
+                curr_memory_word_size,
+                next_memory_word_size,
+                length.as_u64(),
+            );
+
+        let steps = vec![
+            ExecStep {
+                rw_indices: [
+                    vec![
+                        (RwTableTag::Stack, 0),
+                        (RwTableTag::Stack, 1),
+                        (RwTableTag::Stack, 2),
+                    ],
+                    (0..n_memory_rws)
+                        .map(|idx| (RwTableTag::Memory, idx))
+                        .collect(),
+                ]
+                .concat(),
+                execution_state: ExecutionState::MCOPY,
+                rw_counter: 1,
+                program_counter: 99,
+                stack_pointer: 1021,
+                gas_left: gas_cost,
+                gas_cost,
+                memory_size: curr_memory_word_size * 32,
+                opcode: Some(OpcodeId::MCOPY),
+                ..Default::default()
+            },
+            ExecStep {
+                execution_state: ExecutionState::STOP,
+                rw_counter: 4 + n_memory_rws,
+                program_counter: 100,
+                stack_pointer: 1024,
+                opcode: Some(OpcodeId::STOP),
+                memory_size: next_memory_word_size * 32,
+                ..Default::default()
+            },
+        ];
+
+        let block = Block {
+            randomness,
+            txs: vec![Transaction {
+                id: 1,
+                calls: vec![Call {
+                    id: call_id,
+                    is_root: true,
+                    is_create: false,
+                    code_source: CodeSource::Account(bytecode.hash),
+                    ..Default::default()
+                }],
+                steps,
+                ..Default::default()
+            }],
+            rws,
+            bytecodes: vec![bytecode],
+            copy_events,
+            ..Default::default()
+        };
+        assert_eq!(run_test_circuit_incomplete_fixed_table(block), Ok(()));
+    }
+
+    #[test]
+    fn mcopy_gadget_simple() {
+        test_ok(64, Word::from(0x40), Word::from(0), Word::from(10));
+        test_ok(0, Word::from(0), Word::from(0x40), Word::from(32));
+    }
+
+    #[test]
+    fn mcopy_gadget_long() {
+        test_ok(0, Word::from(0x100), Word::from(0x20), Word::from(90));
+    }
+
+    #[test]
+    fn mcopy_gadget_overlapping() {
+        test_ok(64, Word::from(0x10), Word::from(0x08), Word::from(32));
+    }
+
+    #[test]
+    fn mcopy_gadget_zero_length() {
+        test_ok(64, Word::from(0x40), Word::from(0x10), Word::from(0));
+        test_ok(0, Word::from(0x1000), Word::from(0x2000), Word::from(0));
+    }
+}
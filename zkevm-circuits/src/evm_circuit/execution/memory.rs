@@ -10,16 +10,15 @@ use crate::{
                 Transition::{Delta, To},
             },
             from_bytes,
-            math_gadget::IsEqualGadget,
             memory_gadget::MemoryExpansionGadget,
-            select, MemoryAddress, Word,
+            select, Cell, MemoryAddress, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian};
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 use std::convert::TryInto;
 
 #[derive(Clone, Debug)]
@@ -28,8 +27,8 @@ pub(crate) struct MemoryGadget<F> {
     address: MemoryAddress<F>,
     value: Word<F>,
     memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
-    is_mload: IsEqualGadget<F>,
-    is_mstore8: IsEqualGadget<F>,
+    is_mstore: Cell<F>,
+    is_mstore8: Cell<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for MemoryGadget<F> {
@@ -44,12 +43,16 @@ impl<F: Field> ExecutionGadget<F> for MemoryGadget<F> {
         let address = cb.query_rlc();
         let value = cb.query_word();
 
-        // Check if this is an MLOAD
-        let is_mload = IsEqualGadget::construct(cb, opcode.expr(), OpcodeId::MLOAD.expr());
-        // Check if this is an MSTORE8
-        let is_mstore8 = IsEqualGadget::construct(cb, opcode.expr(), OpcodeId::MSTORE8.expr());
+        // Check if this is an MSTORE or an MSTORE8, both selectors are bound to
+        // the opcode by the responsible opcode lookup, as MLOAD, MSTORE and
+        // MSTORE8 have index 0, 1 and 2.
+        let is_mstore = cb.query_bool();
+        let is_mstore8 = cb.query_bool();
+        let opcode_index = is_mstore.expr() + 2.expr() * is_mstore8.expr();
         // This is an MSTORE/MSTORE8
-        let is_store = 1.expr() - is_mload.expr();
+        let is_store = is_mstore.expr() + is_mstore8.expr();
+        // This is an MLOAD
+        let is_mload = 1.expr() - is_store.clone();
         // This in an MSTORE/MLOAD
         let is_not_mstore8 = 1.expr() - is_mstore8.expr();
 
@@ -67,8 +70,8 @@ impl<F: Field> ExecutionGadget<F> for MemoryGadget<F> {
         // For MLOAD push the value to the stack
         // FOR MSTORE pop the value from the stack
         cb.stack_lookup(
-            is_mload.expr(),
-            cb.stack_pointer_offset().expr() - is_mload.expr(),
+            is_mload.clone(),
+            cb.stack_pointer_offset().expr() - is_mload,
             value.expr(),
         );
 
@@ -121,21 +124,26 @@ impl<F: Field> ExecutionGadget<F> for MemoryGadget<F> {
             memory_word_size: To(memory_expansion.next_memory_word_size()),
             ..Default::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_with_opcode_index(
+            cb,
+            opcode,
+            opcode_index,
+            step_state_transition,
+        );
 
         Self {
             same_context,
             address,
             value,
             memory_expansion,
-            is_mload,
+            is_mstore,
             is_mstore8,
         }
     }
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -161,27 +169,22 @@ impl<F: Field> ExecutionGadget<F> for MemoryGadget<F> {
         self.value
             .assign(region, offset, Some(value.to_le_bytes()))?;
 
-        // Check if this is an MLOAD
-        self.is_mload.assign(
-            region,
-            offset,
-            F::from(opcode.as_u64()),
-            F::from(OpcodeId::MLOAD.as_u64()),
-        )?;
-        // Check if this is an MSTORE8
-        let is_mstore8 = self.is_mstore8.assign(
+        // Check if this is an MSTORE or an MSTORE8
+        self.is_mstore.assign(
             region,
             offset,
-            F::from(opcode.as_u64()),
-            F::from(OpcodeId::MSTORE8.as_u64()),
+            Some(F::from((opcode == OpcodeId::MSTORE) as u64)),
         )?;
+        let is_mstore8 = opcode == OpcodeId::MSTORE8;
+        self.is_mstore8
+            .assign(region, offset, Some(F::from(is_mstore8 as u64)))?;
 
         // Memory expansion
         self.memory_expansion.assign(
             region,
             offset,
             step.memory_word_size(),
-            [address.as_u64() + if is_mstore8 == F::one() { 1 } else { 32 }],
+            [address.as_u64() + if is_mstore8 { 1 } else { 32 }],
         )?;
 
         Ok(())
@@ -195,7 +198,7 @@ mod test {
         test_util::{test_circuits_using_bytecode, BytecodeTestConfig},
     };
     use eth_types::bytecode;
-    use eth_types::evm_types::{GasCost, OpcodeId};
+    use eth_types::evm_types::{gas::memory_gas_cost, GasCost, OpcodeId};
     use eth_types::Word;
     use std::iter;
 
@@ -260,7 +263,7 @@ mod test {
                 + 31;
             let memory_size = memory_address / 32;
 
-            GasCost::FASTEST.as_u64() + 3 * memory_size + memory_size * memory_size / 512
+            GasCost::FASTEST.as_u64() + memory_gas_cost(memory_size)
         };
 
         for opcode in [OpcodeId::MSTORE, OpcodeId::MLOAD, OpcodeId::MSTORE8] {
@@ -8,14 +8,14 @@ use crate::{
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
             math_gadget::ComparisonGadget,
             memory_gadget::BufferReaderGadget,
-            Cell,
+            Cell, StepRegion,
         },
         witness::{Block, Call, ExecStep, StepAuxiliaryData, Transaction},
     },
     util::Expr,
 };
 use eth_types::Field;
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 // The max number of bytes that can be copied in a step limited by the number
 // of cells in a step
@@ -62,12 +62,7 @@ impl<F: Field> ExecutionGadget<F> for CopyToMemoryGadget<F> {
             let read_flag = buffer_reader.read_flag(i);
             // Read bytes[i] from memory
             cb.condition(from_memory.clone() * read_flag.clone(), |cb| {
-                cb.memory_lookup(
-                    0.expr(),
-                    src_addr.expr() + i.expr(),
-                    buffer_reader.byte(i),
-                    None,
-                )
+                cb.memory_read(src_addr.expr() + i.expr(), buffer_reader.byte(i))
             });
             // Read bytes[i] from Tx
             cb.condition(from_tx.expr() * read_flag.clone(), |cb| {
@@ -80,12 +75,7 @@ impl<F: Field> ExecutionGadget<F> for CopyToMemoryGadget<F> {
             });
             // Write bytes[i] to memory when selectors[i] != 0
             cb.condition(buffer_reader.has_data(i), |cb| {
-                cb.memory_lookup(
-                    1.expr(),
-                    dst_addr.expr() + i.expr(),
-                    buffer_reader.byte(i),
-                    None,
-                )
+                cb.memory_write(dst_addr.expr() + i.expr(), buffer_reader.byte(i))
             });
         }
 
@@ -159,7 +149,7 @@ impl<F: Field> ExecutionGadget<F> for CopyToMemoryGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         tx: &Transaction,
@@ -5,21 +5,26 @@ use crate::{
         util::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
-            math_gadget::MulWordsGadget,
+            math_gadget::MulAddWordsGadget,
+            sum, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use bus_mapping::evm::OpcodeId;
-use eth_types::Field;
-use halo2_proofs::{circuit::Region, plonk::Error};
+use eth_types::{Field, ToLittleEndian};
+use halo2_proofs::plonk::Error;
 
-// MulGadget verifies MUL: a * b mod 2^256 is equal to c,
+// MulGadget verifies MUL: a * b mod 2^256 is equal to product,
 #[derive(Clone, Debug)]
 pub(crate) struct MulGadget<F> {
     same_context: SameContextGadget<F>,
-    mul_words: MulWordsGadget<F>,
+    a: Word<F>,
+    b: Word<F>,
+    c: Word<F>,
+    product: Word<F>,
+    mul_add_words: MulAddWordsGadget<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for MulGadget<F> {
@@ -32,12 +37,17 @@ impl<F: Field> ExecutionGadget<F> for MulGadget<F> {
 
         let a = cb.query_word();
         let b = cb.query_word();
+        let c = cb.query_word();
+        let product = cb.query_word();
 
         // Pop a and b from the stack, push product on the stack
         cb.stack_pop(a.expr());
         cb.stack_pop(b.expr());
-        let mul_words = MulWordsGadget::construct(cb, a, b);
-        cb.stack_push(mul_words.product().expr());
+        cb.stack_push(product.expr());
+
+        // a ⋅ b + 0 == product (mod 2^256)
+        cb.require_zero("c == 0", sum::expr(&c.cells));
+        let mul_add_words = MulAddWordsGadget::construct(cb, [&a, &b, &c, &product]);
 
         // State transition
         let step_state_transition = StepStateTransition {
@@ -51,13 +61,17 @@ impl<F: Field> ExecutionGadget<F> for MulGadget<F> {
 
         Self {
             same_context,
-            mul_words,
+            a,
+            b,
+            c,
+            product,
+            mul_add_words,
         }
     }
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -66,8 +80,14 @@ impl<F: Field> ExecutionGadget<F> for MulGadget<F> {
     ) -> Result<(), Error> {
         self.same_context.assign_exec_step(region, offset, step)?;
         let indices = [step.rw_indices[0], step.rw_indices[1], step.rw_indices[2]];
-        let [a, b, c] = indices.map(|idx| block.rws[idx].stack_value());
-        self.mul_words.assign(region, offset, a, b, c)
+        let [a, b, product] = indices.map(|idx| block.rws[idx].stack_value());
+        self.a.assign(region, offset, Some(a.to_le_bytes()))?;
+        self.b.assign(region, offset, Some(b.to_le_bytes()))?;
+        self.c.assign(region, offset, Some([0; 32]))?;
+        self.product
+            .assign(region, offset, Some(product.to_le_bytes()))?;
+        self.mul_add_words
+            .assign(region, offset, [a, b, eth_types::Word::zero(), product])
     }
 }
 
@@ -5,7 +5,7 @@ use crate::{
         util::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
-            sum, Cell, Word,
+            sum, Cell, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -13,7 +13,7 @@ use crate::{
 };
 use array_init::array_init;
 use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian};
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
 pub(crate) struct PushGadget<F> {
@@ -80,16 +80,11 @@ impl<F: Field> ExecutionGadget<F> for PushGadget<F> {
             );
         }
 
-        // Deduce the number of additional bytes to push than PUSH1. Note that
-        // num_additional_pushed = n - 1 where n is the suffix number of PUSH*.
-        let num_additional_pushed = opcode.expr() - OpcodeId::PUSH1.as_u64().expr();
         // Sum of selectors needs to be exactly the number of additional bytes
-        // that needs to be pushed.
-        cb.require_equal(
-            "Constrain sum of selectors equal to num_additional_pushed",
-            sum::expr(&selectors),
-            num_additional_pushed,
-        );
+        // that needs to be pushed than PUSH1, which is n - 1 where n is the
+        // suffix number of PUSH*. It's also the responsible opcode index, so
+        // it's constrained by the responsible opcode lookup.
+        let num_additional_pushed = sum::expr(&selectors);
 
         // Push the value on the stack
         cb.stack_push(value.expr());
@@ -103,7 +98,12 @@ impl<F: Field> ExecutionGadget<F> for PushGadget<F> {
             gas_left: Delta(-OpcodeId::PUSH1.constant_gas_cost().expr()),
             ..Default::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_with_opcode_index(
+            cb,
+            opcode,
+            num_additional_pushed,
+            step_state_transition,
+        );
 
         Self {
             same_context,
@@ -114,7 +114,7 @@ impl<F: Field> ExecutionGadget<F> for PushGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -6,15 +6,15 @@ use crate::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
             from_bytes,
-            math_gadget::{ComparisonGadget, IsEqualGadget, LtGadget},
-            select, Cell, Word,
+            math_gadget::{ComparisonGadget, LtGadget},
+            select, Cell, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian};
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 /// Gadget that implements the ExecutionGadget trait to handle the Opcodes SLT
 /// and SGT.
@@ -31,7 +31,7 @@ pub(crate) struct SignedComparatorGadget<F> {
     comparison_hi: ComparisonGadget<F, 16>,
     a_lt_b: Cell<F>,
 
-    is_sgt: IsEqualGadget<F>,
+    is_sgt: Cell<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for SignedComparatorGadget<F> {
@@ -47,8 +47,10 @@ impl<F: Field> ExecutionGadget<F> for SignedComparatorGadget<F> {
 
         // The Signed Comparator gadget is used for both opcodes SLT and SGT.
         // Depending on whether the opcode is SLT or SGT, we
-        // swap the order in which the inputs are placed on the stack.
-        let is_sgt = IsEqualGadget::construct(cb, opcode.expr(), OpcodeId::SGT.expr());
+        // swap the order in which the inputs are placed on the stack. The
+        // selector is bound to the opcode by the responsible opcode lookup, as
+        // the index of SGT is 1.
+        let is_sgt = cb.query_bool();
 
         // Both a and b are to be treated as two's complement signed 256-bit
         // (32 cells) integers. This means, the first bit denotes the sign
@@ -127,7 +129,12 @@ impl<F: Field> ExecutionGadget<F> for SignedComparatorGadget<F> {
             gas_left: Delta(-OpcodeId::SLT.constant_gas_cost().expr()),
             ..Default::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_with_opcode_index(
+            cb,
+            opcode,
+            is_sgt.expr(),
+            step_state_transition,
+        );
 
         Self {
             same_context,
@@ -144,7 +151,7 @@ impl<F: Field> ExecutionGadget<F> for SignedComparatorGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _transaction: &Transaction,
@@ -159,8 +166,7 @@ impl<F: Field> ExecutionGadget<F> for SignedComparatorGadget<F> {
         self.is_sgt.assign(
             region,
             offset,
-            F::from(opcode.as_u8() as u64),
-            F::from(OpcodeId::SGT.as_u8() as u64),
+            Some(F::from((opcode == OpcodeId::SGT) as u64)),
         )?;
         let indices = if opcode == OpcodeId::SGT {
             [step.rw_indices[1], step.rw_indices[0]]
@@ -8,7 +8,7 @@ use crate::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
             math_gadget::{IsEqualGadget, IsZeroGadget},
-            select, sum, Cell, Word,
+            select, sum, Cell, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -17,7 +17,7 @@ use crate::{
 use array_init::array_init;
 use bus_mapping::evm::OpcodeId;
 use eth_types::{Field, ToLittleEndian};
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
 pub(crate) struct SignextendGadget<F> {
@@ -153,7 +153,7 @@ impl<F: Field> ExecutionGadget<F> for SignextendGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -206,7 +206,7 @@ impl<F: Field> ExecutionGadget<F> for SignextendGadget<F> {
 #[cfg(test)]
 mod test {
     use crate::{evm_circuit::test::rand_word, test_util::run_test_circuits};
-    use eth_types::{bytecode, ToLittleEndian, Word};
+    use eth_types::{bytecode, Word, WordExt};
 
     fn test_ok(index: Word, value: Word, _result: Word) {
         let bytecode = bytecode! {
@@ -238,27 +238,13 @@ mod test {
 
     #[test]
     fn signextend_gadget_rand() {
-        let signextend = |index: Word, value: Word| -> Word {
-            if index < Word::from(32u8) {
-                let index = index.to_le_bytes()[0] as usize;
-                let mask = (Word::one() << (index * 8 + 7)) - Word::one();
-                if value.to_le_bytes()[index] >> 7 == 1 {
-                    value | (!mask)
-                } else {
-                    value & mask
-                }
-            } else {
-                value
-            }
-        };
-
         let index = rand_word();
         let value = rand_word();
-        test_ok(index, value, signextend(index, value));
+        test_ok(index, value, value.sign_extend(index));
         test_ok(
             index % Word::from(32u8),
             value,
-            signextend(index % Word::from(32u8), value),
+            value.sign_extend(index % Word::from(32u8)),
         );
     }
 
@@ -5,25 +5,23 @@ use crate::{
         table::CallContextFieldTag,
         util::{
             common_gadget::SameContextGadget,
-            constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
-            select, Cell, Word,
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition, Transition::Delta,
+            },
+            select, Cell, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar};
-use halo2_proofs::{
-    circuit::Region,
-    plonk::{Error, Expression},
-};
+use halo2_proofs::plonk::{Error, Expression};
 
 #[derive(Clone, Debug)]
 pub(crate) struct SloadGadget<F> {
     same_context: SameContextGadget<F>,
     tx_id: Cell<F>,
-    rw_counter_end_of_reversion: Cell<F>,
-    is_persistent: Cell<F>,
+    reversion_info: ReversionInfo<F>,
     callee_address: Cell<F>,
     key: Cell<F>,
     value: Cell<F>,
@@ -39,13 +37,9 @@ impl<F: Field> ExecutionGadget<F> for SloadGadget<F> {
     fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
         let opcode = cb.query_cell();
 
-        let [tx_id, rw_counter_end_of_reversion, is_persistent, callee_address] = [
-            CallContextFieldTag::TxId,
-            CallContextFieldTag::RwCounterEndOfReversion,
-            CallContextFieldTag::IsPersistent,
-            CallContextFieldTag::CalleeAddress,
-        ]
-        .map(|field_tag| cb.call_context(None, field_tag));
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let mut reversion_info = cb.reversion_info(None);
+        let callee_address = cb.call_context(None, CallContextFieldTag::CalleeAddress);
 
         let key = cb.query_cell();
         // Pop the key from the stack
@@ -70,20 +64,14 @@ impl<F: Field> ExecutionGadget<F> for SloadGadget<F> {
             key.expr(),
             true.expr(),
             is_warm.expr(),
-            Some(
-                (
-                    &is_persistent,
-                    rw_counter_end_of_reversion.expr() - cb.curr.state.state_write_counter.expr(),
-                )
-                    .into(),
-            ),
+            Some(&mut reversion_info),
         );
 
         let gas_cost = SloadGasGadget::construct(cb, is_warm.expr()).expr();
         let step_state_transition = StepStateTransition {
-            rw_counter: Delta(8.expr()),
+            rw_counter: Delta(cb.rw_counter_offset()),
             program_counter: Delta(1.expr()),
-            state_write_counter: Delta(1.expr()),
+            state_write_counter: Delta(cb.state_write_counter_offset().expr()),
             gas_left: Delta(-gas_cost),
             ..Default::default()
         };
@@ -92,8 +80,7 @@ impl<F: Field> ExecutionGadget<F> for SloadGadget<F> {
         Self {
             same_context,
             tx_id,
-            rw_counter_end_of_reversion,
-            is_persistent,
+            reversion_info,
             callee_address,
             key,
             value,
@@ -104,7 +91,7 @@ impl<F: Field> ExecutionGadget<F> for SloadGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         tx: &Transaction,
@@ -115,13 +102,12 @@ impl<F: Field> ExecutionGadget<F> for SloadGadget<F> {
 
         self.tx_id
             .assign(region, offset, Some(F::from(tx.id as u64)))?;
-        self.rw_counter_end_of_reversion.assign(
+        self.reversion_info.assign(
             region,
             offset,
-            Some(F::from(call.rw_counter_end_of_reversion as u64)),
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
         )?;
-        self.is_persistent
-            .assign(region, offset, Some(F::from(call.is_persistent as u64)))?;
         self.callee_address
             .assign(region, offset, call.callee_address.to_scalar())?;
 
@@ -196,7 +182,11 @@ mod test {
     };
 
     use bus_mapping::evm::OpcodeId;
-    use eth_types::{address, bytecode, evm_types::GasCost, ToWord, Word};
+    use eth_types::{
+        address, bytecode,
+        evm_types::gas::{call_data_gas_cost, sload_gas_cost},
+        ToWord, Word,
+    };
     use std::convert::TryInto;
 
     fn test_ok(
@@ -208,11 +198,7 @@ mod test {
     ) {
         let rw_counter_end_of_reversion = if is_persistent { 0 } else { 19 };
 
-        let call_data_gas_cost = tx
-            .input
-            .0
-            .iter()
-            .fold(0, |acc, byte| acc + if *byte == 0 { 4 } else { 16 });
+        let call_data_gas_cost = call_data_gas_cost(&tx.input);
 
         let randomness = rand_fp();
         let bytecode = Bytecode::from(&bytecode! {
@@ -270,16 +256,8 @@ mod test {
                         rw_counter: 9,
                         program_counter: 33,
                         stack_pointer: STACK_CAPACITY,
-                        gas_left: if is_warm {
-                            GasCost::WARM_STORAGE_READ_COST.as_u64()
-                        } else {
-                            GasCost::COLD_SLOAD_COST.as_u64()
-                        },
-                        gas_cost: if is_warm {
-                            GasCost::WARM_STORAGE_READ_COST.as_u64()
-                        } else {
-                            GasCost::COLD_SLOAD_COST.as_u64()
-                        },
+                        gas_left: sload_gas_cost(is_warm).as_u64(),
+                        gas_cost: sload_gas_cost(is_warm).as_u64(),
                         opcode: Some(OpcodeId::SLOAD),
                         ..Default::default()
                     },
@@ -5,26 +5,24 @@ use crate::{
         table::CallContextFieldTag,
         util::{
             common_gadget::SameContextGadget,
-            constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition, Transition::Delta,
+            },
             math_gadget::{IsEqualGadget, IsZeroGadget},
-            not, select, Cell, Word,
+            not, select, Cell, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar};
-use halo2_proofs::{
-    circuit::Region,
-    plonk::{Error, Expression},
-};
+use halo2_proofs::plonk::{Error, Expression};
 
 #[derive(Clone, Debug)]
 pub(crate) struct SstoreGadget<F> {
     same_context: SameContextGadget<F>,
     tx_id: Cell<F>,
-    rw_counter_end_of_reversion: Cell<F>,
-    is_persistent: Cell<F>,
+    reversion_info: ReversionInfo<F>,
     callee_address: Cell<F>,
     key: Cell<F>,
     value: Cell<F>,
@@ -44,13 +42,9 @@ impl<F: Field> ExecutionGadget<F> for SstoreGadget<F> {
     fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
         let opcode = cb.query_cell();
 
-        let [tx_id, rw_counter_end_of_reversion, is_persistent, callee_address] = [
-            CallContextFieldTag::TxId,
-            CallContextFieldTag::RwCounterEndOfReversion,
-            CallContextFieldTag::IsPersistent,
-            CallContextFieldTag::CalleeAddress,
-        ]
-        .map(|field_tag| cb.call_context(None, field_tag));
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let mut reversion_info = cb.reversion_info(None);
+        let callee_address = cb.call_context(None, CallContextFieldTag::CalleeAddress);
 
         let key = cb.query_cell();
         // Pop the key from the stack
@@ -69,13 +63,7 @@ impl<F: Field> ExecutionGadget<F> for SstoreGadget<F> {
             value_prev.expr(),
             tx_id.expr(),
             committed_value.expr(),
-            Some(
-                (
-                    &is_persistent,
-                    rw_counter_end_of_reversion.expr() - cb.curr.state.state_write_counter.expr(),
-                )
-                    .into(),
-            ),
+            Some(&mut reversion_info),
         );
 
         let is_warm = cb.query_bool();
@@ -85,15 +73,7 @@ impl<F: Field> ExecutionGadget<F> for SstoreGadget<F> {
             key.expr(),
             true.expr(),
             is_warm.expr(),
-            Some(
-                (
-                    &is_persistent,
-                    rw_counter_end_of_reversion.expr()
-                        - 1.expr()
-                        - cb.curr.state.state_write_counter.expr(),
-                )
-                    .into(),
-            ),
+            Some(&mut reversion_info),
         );
 
         let gas_cost = SstoreGasGadget::construct(
@@ -116,22 +96,14 @@ impl<F: Field> ExecutionGadget<F> for SstoreGadget<F> {
             tx_id.expr(),
             tx_refund.expr(),
             tx_refund_prev.expr(),
-            Some(
-                (
-                    &is_persistent,
-                    rw_counter_end_of_reversion.expr()
-                        - 2.expr()
-                        - cb.curr.state.state_write_counter.expr(),
-                )
-                    .into(),
-            ),
+            Some(&mut reversion_info),
         );
 
         let step_state_transition = StepStateTransition {
-            rw_counter: Delta(9.expr()),
+            rw_counter: Delta(cb.rw_counter_offset()),
             program_counter: Delta(1.expr()),
             stack_pointer: Delta(2.expr()),
-            state_write_counter: Delta(3.expr()),
+            state_write_counter: Delta(cb.state_write_counter_offset().expr()),
             gas_left: Delta(-gas_cost.expr()),
             ..Default::default()
         };
@@ -140,8 +112,7 @@ impl<F: Field> ExecutionGadget<F> for SstoreGadget<F> {
         Self {
             same_context,
             tx_id,
-            rw_counter_end_of_reversion,
-            is_persistent,
+            reversion_info,
             callee_address,
             key,
             value,
@@ -156,7 +127,7 @@ impl<F: Field> ExecutionGadget<F> for SstoreGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         tx: &Transaction,
@@ -167,13 +138,12 @@ impl<F: Field> ExecutionGadget<F> for SstoreGadget<F> {
 
         self.tx_id
             .assign(region, offset, Some(F::from(tx.id as u64)))?;
-        self.rw_counter_end_of_reversion.assign(
+        self.reversion_info.assign(
             region,
             offset,
-            Some(F::from(call.rw_counter_end_of_reversion as u64)),
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
         )?;
-        self.is_persistent
-            .assign(region, offset, Some(F::from(call.is_persistent as u64)))?;
         self.callee_address
             .assign(region, offset, call.callee_address.to_scalar())?;
 
@@ -309,7 +279,7 @@ impl<F: Field> SstoreGasGadget<F> {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         value: eth_types::Word,
         value_prev: eth_types::Word,
@@ -378,6 +348,7 @@ pub(crate) struct SstoreTxRefundGadget<F> {
     nz_ne_ne_case_refund: Cell<F>,
     ez_ne_ne_case_refund: Cell<F>,
     eq_ne_case_refund: Cell<F>,
+    sstore_clears_schedule: GasCost,
 }
 
 impl<F: Field> SstoreTxRefundGadget<F> {
@@ -388,6 +359,7 @@ impl<F: Field> SstoreTxRefundGadget<F> {
         value_prev: Cell<F>,
         committed_value: Cell<F>,
     ) -> Self {
+        let sstore_clears_schedule = cb.hardfork().sstore_clears_schedule();
         let value_prev_is_zero = IsZeroGadget::construct(cb, value_prev.expr());
         let value_is_zero = IsZeroGadget::construct(cb, value.expr());
         let original_is_zero = IsZeroGadget::construct(cb, committed_value.expr());
@@ -400,13 +372,13 @@ impl<F: Field> SstoreTxRefundGadget<F> {
         // original_value!=0&&value_prev!=0
         let nz_nz_allne_case_refund = cb.copy(select::expr(
             value_is_zero.expr(),
-            tx_refund_old.expr() + GasCost::SSTORE_CLEARS_SCHEDULE.expr(),
+            tx_refund_old.expr() + sstore_clears_schedule.expr(),
             tx_refund_old.expr(),
         ));
         // original_value, value_prev, value all are different; original_value!=0
         let nz_allne_case_refund = select::expr(
             value_prev_is_zero.expr(),
-            tx_refund_old.expr() - GasCost::SSTORE_CLEARS_SCHEDULE.expr(),
+            tx_refund_old.expr() - sstore_clears_schedule.expr(),
             nz_nz_allne_case_refund.expr(),
         );
         // original_value!=value_prev, value_prev!=value, original_value!=0
@@ -431,7 +403,7 @@ impl<F: Field> SstoreTxRefundGadget<F> {
         // original_value==value_prev, value_prev!=value
         let eq_ne_case_refund = cb.copy(select::expr(
             not::expr(original_is_zero.expr()) * value_is_zero.expr(),
-            tx_refund_old.expr() + GasCost::SSTORE_CLEARS_SCHEDULE.expr(),
+            tx_refund_old.expr() + sstore_clears_schedule.expr(),
             tx_refund_old.expr(),
         ));
         let tx_refund_new = select::expr(
@@ -460,6 +432,7 @@ impl<F: Field> SstoreTxRefundGadget<F> {
             nz_ne_ne_case_refund,
             ez_ne_ne_case_refund,
             eq_ne_case_refund,
+            sstore_clears_schedule,
         }
     }
 
@@ -471,7 +444,7 @@ impl<F: Field> SstoreTxRefundGadget<F> {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         tx_refund_old: u64,
         value: eth_types::Word,
@@ -537,7 +510,7 @@ impl<F: Field> SstoreTxRefundGadget<F> {
         )?;
 
         let nz_nz_allne_case_refund = if value == eth_types::Word::zero() {
-            tx_refund_old + GasCost::SSTORE_CLEARS_SCHEDULE.as_u64()
+            tx_refund_old + self.sstore_clears_schedule.as_u64()
         } else {
             tx_refund_old
         };
@@ -548,7 +521,7 @@ impl<F: Field> SstoreTxRefundGadget<F> {
         )?;
 
         let nz_allne_case_refund = if value_prev == eth_types::Word::zero() {
-            tx_refund_old - GasCost::SSTORE_CLEARS_SCHEDULE.as_u64()
+            tx_refund_old - self.sstore_clears_schedule.as_u64()
         } else {
             nz_nz_allne_case_refund
         };
@@ -570,7 +543,7 @@ impl<F: Field> SstoreTxRefundGadget<F> {
 
         let eq_ne_case_refund =
             if (committed_value != eth_types::Word::zero()) && (value == eth_types::Word::zero()) {
-                tx_refund_old + GasCost::SSTORE_CLEARS_SCHEDULE.as_u64()
+                tx_refund_old + self.sstore_clears_schedule.as_u64()
             } else {
                 tx_refund_old
             };
@@ -592,70 +565,16 @@ mod test {
     };
 
     use bus_mapping::evm::OpcodeId;
-    use eth_types::{address, bytecode, evm_types::GasCost, ToWord, Word};
+    use eth_types::{
+        address, bytecode,
+        evm_types::{
+            gas::{call_data_gas_cost, sstore_gas_cost},
+            GasCost, Hardfork,
+        },
+        ToWord, Word,
+    };
     use std::convert::TryInto;
 
-    fn calc_expected_gas_cost(
-        value: Word,
-        value_prev: Word,
-        committed_value: Word,
-        is_warm: bool,
-    ) -> u64 {
-        let warm_case_gas = if value_prev == value {
-            GasCost::SLOAD_GAS
-        } else if committed_value == value_prev {
-            if committed_value == Word::from(0) {
-                GasCost::SSTORE_SET_GAS
-            } else {
-                GasCost::SSTORE_RESET_GAS
-            }
-        } else {
-            GasCost::SLOAD_GAS
-        };
-        if is_warm {
-            warm_case_gas.as_u64()
-        } else {
-            warm_case_gas.as_u64() + GasCost::COLD_SLOAD_COST.as_u64()
-        }
-    }
-
-    fn calc_expected_tx_refund(
-        tx_refund_old: u64,
-        value: Word,
-        value_prev: Word,
-        committed_value: Word,
-    ) -> u64 {
-        let mut tx_refund_new = tx_refund_old;
-
-        if value_prev != value {
-            if committed_value == value_prev {
-                if (committed_value != Word::from(0)) && (value == Word::from(0)) {
-                    tx_refund_new += GasCost::SSTORE_CLEARS_SCHEDULE.as_u64();
-                }
-            } else {
-                if committed_value != Word::from(0) {
-                    if value_prev == Word::from(0) {
-                        tx_refund_new -= GasCost::SSTORE_CLEARS_SCHEDULE.as_u64()
-                    }
-                    if value == Word::from(0) {
-                        tx_refund_new += GasCost::SSTORE_CLEARS_SCHEDULE.as_u64()
-                    }
-                }
-                if committed_value == value {
-                    if committed_value == Word::from(0) {
-                        tx_refund_new +=
-                            GasCost::SSTORE_SET_GAS.as_u64() - GasCost::SLOAD_GAS.as_u64();
-                    } else {
-                        tx_refund_new +=
-                            GasCost::SSTORE_RESET_GAS.as_u64() - GasCost::SLOAD_GAS.as_u64();
-                    }
-                }
-            }
-        }
-
-        tx_refund_new
-    }
-
     fn test_ok(
         tx: eth_types::Transaction,
         key: Word,
@@ -665,17 +584,13 @@ mod test {
         is_warm: bool,
         result: bool,
     ) {
-        let gas = calc_expected_gas_cost(value, value_prev, committed_value, is_warm);
+        let gas = sstore_gas_cost(value, value_prev, committed_value, is_warm).as_u64();
         let tx_refund_old = GasCost::SSTORE_SET_GAS.as_u64();
         let tx_refund_new =
-            calc_expected_tx_refund(tx_refund_old, value, value_prev, committed_value);
+            Hardfork::default().sstore_refund(tx_refund_old, value, value_prev, committed_value);
         let rw_counter_end_of_reversion = if result { 0 } else { 14 };
 
-        let call_data_gas_cost = tx
-            .input
-            .0
-            .iter()
-            .fold(0, |acc, byte| acc + if *byte == 0 { 4 } else { 16 });
+        let call_data_gas_cost = call_data_gas_cost(&tx.input);
 
         let randomness = rand_fp();
         let bytecode = Bytecode::from(&bytecode! {
@@ -2,13 +2,13 @@ use crate::{
     evm_circuit::{
         execution::ExecutionGadget,
         step::ExecutionState,
-        util::{constraint_builder::ConstraintBuilder, Cell},
+        util::{constraint_builder::ConstraintBuilder, Cell, StepRegion},
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use eth_types::Field;
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
 pub(crate) struct StopGadget<F> {
@@ -23,6 +23,7 @@ impl<F: Field> ExecutionGadget<F> for StopGadget<F> {
     fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
         let opcode = cb.query_cell();
         cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.responsible_opcode_lookup(opcode.expr(), 0.expr());
 
         // Other constraints are ignored now for STOP to serve as a mocking
         // terminator
@@ -32,7 +33,7 @@ impl<F: Field> ExecutionGadget<F> for StopGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         _: &Block<F>,
         _: &Transaction,
@@ -5,14 +5,14 @@ use crate::{
         util::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
-            Cell, Word,
+            Cell, StepRegion, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian};
-use halo2_proofs::{circuit::Region, plonk::Error};
+use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
 pub(crate) struct SwapGadget<F> {
@@ -50,7 +50,13 @@ impl<F: Field> ExecutionGadget<F> for SwapGadget<F> {
             gas_left: Delta(-OpcodeId::SWAP1.constant_gas_cost().expr()),
             ..Default::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let opcode_index = opcode.expr() - OpcodeId::SWAP1.expr();
+        let same_context = SameContextGadget::construct_with_opcode_index(
+            cb,
+            opcode,
+            opcode_index,
+            step_state_transition,
+        );
 
         Self {
             same_context,
@@ -60,7 +66,7 @@ impl<F: Field> ExecutionGadget<F> for SwapGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -1,7 +1,7 @@
 use crate::{
     evm_circuit::{
         param::{N_CELLS_STEP_STATE, STEP_HEIGHT, STEP_WIDTH},
-        util::{Cell, RandomLinearCombination},
+        util::{Cell, RandomLinearCombination, StepRegion},
         witness::{Block, Call, CodeSource, ExecStep, Transaction},
     },
     util::Expr,
@@ -10,17 +10,19 @@ use bus_mapping::evm::OpcodeId;
 use eth_types::ToLittleEndian;
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::Region,
     plonk::{Advice, Column, ConstraintSystem, Error, Expression},
 };
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ExecutionState {
     // Internal state
     BeginTx,
     EndTx,
     EndBlock,
+    BeginChunk,
+    EndChunk,
     CopyToMemory,
     // Opcode successful cases
     STOP,
@@ -79,6 +81,7 @@ pub enum ExecutionState {
     MSIZE,
     GAS,
     JUMPDEST,
+    MCOPY,
     PUSH, // PUSH1, PUSH2, ..., PUSH32
     DUP,  // DUP1, DUP2, ..., DUP16
     SWAP, // SWAP1, SWAP2, ..., SWAP16
@@ -140,6 +143,8 @@ impl ExecutionState {
             Self::BeginTx,
             Self::EndTx,
             Self::EndBlock,
+            Self::BeginChunk,
+            Self::EndChunk,
             Self::CopyToMemory,
             Self::STOP,
             Self::ADD,
@@ -197,6 +202,7 @@ impl ExecutionState {
             Self::MSIZE,
             Self::GAS,
             Self::JUMPDEST,
+            Self::MCOPY,
             Self::PUSH,
             Self::DUP,
             Self::SWAP,
@@ -287,6 +293,15 @@ impl ExecutionState {
         )
     }
 
+    /// Returns the execution state which handles the successful execution of
+    /// `opcode`, if the evm circuit supports it.
+    pub fn from_opcode(opcode: OpcodeId) -> Option<Self> {
+        Self::iterator().find(|state| state.responsible_opcodes().contains(&opcode))
+    }
+
+    /// Returns the opcodes handled by the execution state. The index of an
+    /// opcode in the returned list is part of the responsible opcode lookup,
+    /// which the gadgets use to bind their opcode selectors.
     pub(crate) fn responsible_opcodes(&self) -> Vec<OpcodeId> {
         match self {
             Self::STOP => vec![OpcodeId::STOP],
@@ -347,6 +362,7 @@ impl ExecutionState {
             Self::MSIZE => vec![OpcodeId::MSIZE],
             Self::GAS => vec![OpcodeId::GAS],
             Self::JUMPDEST => vec![OpcodeId::JUMPDEST],
+            Self::MCOPY => vec![OpcodeId::MCOPY],
             Self::PUSH => vec![
                 OpcodeId::PUSH1,
                 OpcodeId::PUSH2,
@@ -433,6 +449,9 @@ impl ExecutionState {
             Self::STATICCALL => vec![OpcodeId::STATICCALL],
             Self::REVERT => vec![OpcodeId::REVERT],
             Self::SELFDESTRUCT => vec![OpcodeId::SELFDESTRUCT],
+            Self::ErrorOutOfGasStaticMemoryExpansion => {
+                vec![OpcodeId::MLOAD, OpcodeId::MSTORE, OpcodeId::MSTORE8]
+            }
             _ => vec![],
         }
     }
@@ -472,6 +491,25 @@ pub(crate) struct StepState<F> {
     pub(crate) state_write_counter: Cell<F>,
 }
 
+impl<F> StepState<F> {
+    /// Returns the cells of the state but the ones of the execution state, in
+    /// the order of the values of `ChunkState::instance`.
+    pub(crate) fn cells(&self) -> [&Cell<F>; N_CELLS_STEP_STATE] {
+        [
+            &self.rw_counter,
+            &self.call_id,
+            &self.is_root,
+            &self.is_create,
+            &self.code_source,
+            &self.program_counter,
+            &self.stack_pointer,
+            &self.gas_left,
+            &self.memory_word_size,
+            &self.state_write_counter,
+        ]
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct StepRow<F> {
     pub(crate) qs_byte_lookup: Cell<F>,
@@ -551,7 +589,7 @@ impl<F: FieldExt> Step<F> {
 
     pub(crate) fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
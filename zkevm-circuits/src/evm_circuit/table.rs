@@ -1,109 +1,52 @@
-use crate::{evm_circuit::step::ExecutionState, impl_expr};
+pub use crate::fixed_table::FixedTableTag;
+use crate::{impl_expr, util::Expr};
 use halo2_proofs::{
     arithmetic::FieldExt,
-    plonk::{Advice, Column, Expression, Fixed, VirtualCells},
+    plonk::{Advice, Any, Column, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
+use serde::{Deserialize, Serialize};
 
+/// A table that circuits can look up against.  The tables are passed to the
+/// circuits as typed handles, which know their own columns and how to query
+/// them, instead of raw column arrays.
 pub trait LookupTable<F: FieldExt, const W: usize> {
+    /// The columns of the table.
+    fn columns(&self) -> Vec<Column<Any>>;
+
+    /// The names of the columns, in the order of `columns`.
+    fn annotations(&self) -> Vec<String>;
+
+    /// The expressions of a row of the table, to be used as the right hand
+    /// side of a lookup.
     fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; W];
 }
 
 impl<F: FieldExt, const W: usize> LookupTable<F, W> for [Column<Advice>; W] {
+    fn columns(&self) -> Vec<Column<Any>> {
+        self.map(Into::into).to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        (0..W).map(|idx| format!("advice_{}", idx)).collect()
+    }
+
     fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; W] {
         self.map(|column| meta.query_advice(column, Rotation::cur()))
     }
 }
 
 impl<F: FieldExt, const W: usize> LookupTable<F, W> for [Column<Fixed>; W] {
-    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; W] {
-        self.map(|column| meta.query_fixed(column, Rotation::cur()))
+    fn columns(&self) -> Vec<Column<Any>> {
+        self.map(Into::into).to_vec()
     }
-}
 
-#[derive(Clone, Copy, Debug)]
-pub enum FixedTableTag {
-    Range5 = 1,
-    Range16,
-    Range32,
-    Range256,
-    Range512,
-    SignByte,
-    BitwiseAnd,
-    BitwiseOr,
-    BitwiseXor,
-    ResponsibleOpcode,
-}
-
-impl FixedTableTag {
-    pub fn iterator() -> impl Iterator<Item = Self> {
-        [
-            Self::Range5,
-            Self::Range16,
-            Self::Range32,
-            Self::Range256,
-            Self::Range512,
-            Self::SignByte,
-            Self::BitwiseAnd,
-            Self::BitwiseOr,
-            Self::BitwiseXor,
-            Self::ResponsibleOpcode,
-        ]
-        .iter()
-        .copied()
+    fn annotations(&self) -> Vec<String> {
+        (0..W).map(|idx| format!("fixed_{}", idx)).collect()
     }
 
-    pub fn build<F: FieldExt>(&self) -> Box<dyn Iterator<Item = [F; 4]>> {
-        let tag = F::from(*self as u64);
-        match self {
-            Self::Range5 => {
-                Box::new((0..5).map(move |value| [tag, F::from(value), F::zero(), F::zero()]))
-            }
-            Self::Range16 => {
-                Box::new((0..16).map(move |value| [tag, F::from(value), F::zero(), F::zero()]))
-            }
-            Self::Range32 => {
-                Box::new((0..32).map(move |value| [tag, F::from(value), F::zero(), F::zero()]))
-            }
-            Self::Range256 => {
-                Box::new((0..256).map(move |value| [tag, F::from(value), F::zero(), F::zero()]))
-            }
-            Self::Range512 => {
-                Box::new((0..512).map(move |value| [tag, F::from(value), F::zero(), F::zero()]))
-            }
-            Self::SignByte => Box::new((0..256).map(move |value| {
-                [
-                    tag,
-                    F::from(value),
-                    F::from((value >> 7) * 0xFFu64),
-                    F::zero(),
-                ]
-            })),
-            Self::BitwiseAnd => Box::new((0..256).flat_map(move |lhs| {
-                (0..256).map(move |rhs| [tag, F::from(lhs), F::from(rhs), F::from(lhs & rhs)])
-            })),
-            Self::BitwiseOr => Box::new((0..256).flat_map(move |lhs| {
-                (0..256).map(move |rhs| [tag, F::from(lhs), F::from(rhs), F::from(lhs | rhs)])
-            })),
-            Self::BitwiseXor => Box::new((0..256).flat_map(move |lhs| {
-                (0..256).map(move |rhs| [tag, F::from(lhs), F::from(rhs), F::from(lhs ^ rhs)])
-            })),
-            Self::ResponsibleOpcode => {
-                Box::new(ExecutionState::iterator().flat_map(move |execution_state| {
-                    execution_state
-                        .responsible_opcodes()
-                        .into_iter()
-                        .map(move |opcode| {
-                            [
-                                tag,
-                                F::from(execution_state.as_u64()),
-                                F::from(opcode.as_u64()),
-                                F::zero(),
-                            ]
-                        })
-                }))
-            }
-        }
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; W] {
+        self.map(|column| meta.query_fixed(column, Rotation::cur()))
     }
 }
 
@@ -130,11 +73,14 @@ pub enum BlockContextFieldTag {
     Difficulty,
     BaseFee,
     BlockHash,
+    ChainId,
+    WithdrawalsRoot,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RwTableTag {
-    Memory = 2,
+    Start = 1,
+    Memory,
     Stack,
     AccountStorage,
     TxAccessListAccount,
@@ -143,6 +89,8 @@ pub enum RwTableTag {
     Account,
     AccountDestructed,
     CallContext,
+    TxLog,
+    TxReceipt,
 }
 
 impl RwTableTag {
@@ -159,14 +107,28 @@ impl RwTableTag {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum AccountFieldTag {
     Nonce = 1,
     Balance,
     CodeHash,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TxLogFieldTag {
+    Address = 1,
+    Topic,
+    Data,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TxReceiptFieldTag {
+    PostStateOrStatus = 1,
+    CumulativeGasUsed,
+    LogLength,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CallContextFieldTag {
     RwCounterEndOfReversion = 1,
     CallerId,
@@ -197,12 +159,36 @@ pub enum CallContextFieldTag {
     StateWriteCounter,
 }
 
-impl_expr!(FixedTableTag);
+/// Type of the source or the destination of the bytes copied in the copy
+/// circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CopyDataType {
+    Memory = 1,
+    Bytecode,
+    TxCalldata,
+    TxLog,
+}
+
+impl CopyDataType {
+    pub const ALL: [Self; 4] = [Self::Memory, Self::Bytecode, Self::TxCalldata, Self::TxLog];
+}
+
+/// Operation of a row of the ecc table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EccOpTag {
+    /// Addition of two points
+    Add = 1,
+    /// Multiplication of a point by a scalar
+    Mul,
+}
+
 impl_expr!(TxContextFieldTag);
 impl_expr!(RwTableTag);
 impl_expr!(AccountFieldTag);
 impl_expr!(CallContextFieldTag);
 impl_expr!(BlockContextFieldTag);
+impl_expr!(CopyDataType);
+impl_expr!(EccOpTag);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub(crate) enum Table {
@@ -211,6 +197,9 @@ pub(crate) enum Table {
     Rw,
     Bytecode,
     Block,
+    Keccak,
+    Copy,
+    Exp,
 }
 
 #[derive(Clone, Debug)]
@@ -272,6 +261,57 @@ pub(crate) enum Lookup<F> {
         /// Value of the field.
         value: Expression<F>,
     },
+    /// Lookup to keccak table, which contains the hashes of all the inputs
+    /// that the block needs to hash.
+    Keccak {
+        /// Random linear combination of the input bytes, with the first byte
+        /// multiplied by the highest power of the randomness.
+        input_rlc: Expression<F>,
+        /// Length of the input in bytes.
+        input_len: Expression<F>,
+        /// Random linear combination of the output hash as an EVM word.
+        output_rlc: Expression<F>,
+    },
+    /// Lookup to copy table, which contains the first step of all the copies
+    /// of bytes done in the block.
+    Copy {
+        /// Id of the source, the call id for memory, the code hash for
+        /// bytecode or the tx id for tx call data.
+        src_id: Expression<F>,
+        /// Type of the source.
+        src_type: Expression<F>,
+        /// Id of the destination, the call id for memory or the tx id for a
+        /// tx log.
+        dst_id: Expression<F>,
+        /// Type of the destination.
+        dst_type: Expression<F>,
+        /// Address of the first byte to read.
+        src_addr: Expression<F>,
+        /// The bytes at or after this address are read as 0.
+        src_addr_end: Expression<F>,
+        /// Address of the first byte to write.
+        dst_addr: Expression<F>,
+        /// Id of the log in the tx, only used when the destination is a tx log.
+        log_id: Expression<F>,
+        /// Number of bytes copied.
+        length: Expression<F>,
+        /// Random linear combination of the bytes copied.
+        rlc_acc: Expression<F>,
+        /// Counter of the first read-write access done by the copy.
+        rw_counter: Expression<F>,
+        /// Number of read-write accesses done by the copy.
+        rw_inc: Expression<F>,
+    },
+    /// Lookup to exp table, which contains the exponentiations done by the
+    /// EXP opcode in the block.
+    Exp {
+        /// Random linear combination of the base.
+        base: Expression<F>,
+        /// Random linear combination of the exponent.
+        exponent: Expression<F>,
+        /// Random linear combination of `base^exponent (mod 2^256)`.
+        result: Expression<F>,
+    },
     /// Conditional lookup enabled by the first element.
     Conditional(Expression<F>, Box<Lookup<F>>),
 }
@@ -288,6 +328,9 @@ impl<F: FieldExt> Lookup<F> {
             Self::Rw { .. } => Table::Rw,
             Self::Bytecode { .. } => Table::Bytecode,
             Self::Block { .. } => Table::Block,
+            Self::Keccak { .. } => Table::Keccak,
+            Self::Copy { .. } => Table::Copy,
+            Self::Exp { .. } => Table::Exp,
             Self::Conditional(_, lookup) => lookup.table(),
         }
     }
@@ -326,6 +369,44 @@ impl<F: FieldExt> Lookup<F> {
             } => {
                 vec![field_tag.clone(), number.clone(), value.clone()]
             }
+            Self::Keccak {
+                input_rlc,
+                input_len,
+                output_rlc,
+            } => vec![input_rlc.clone(), input_len.clone(), output_rlc.clone()],
+            Self::Copy {
+                src_id,
+                src_type,
+                dst_id,
+                dst_type,
+                src_addr,
+                src_addr_end,
+                dst_addr,
+                log_id,
+                length,
+                rlc_acc,
+                rw_counter,
+                rw_inc,
+            } => vec![
+                1.expr(),
+                src_id.clone(),
+                src_type.clone(),
+                dst_id.clone(),
+                dst_type.clone(),
+                src_addr.clone(),
+                src_addr_end.clone(),
+                dst_addr.clone(),
+                log_id.clone(),
+                length.clone(),
+                rlc_acc.clone(),
+                rw_counter.clone(),
+                rw_inc.clone(),
+            ],
+            Self::Exp {
+                base,
+                exponent,
+                result,
+            } => vec![base.clone(), exponent.clone(), result.clone()],
             Self::Conditional(condition, lookup) => lookup
                 .input_exprs()
                 .into_iter()
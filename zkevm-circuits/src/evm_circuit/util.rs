@@ -1,8 +1,10 @@
-use crate::{evm_circuit::param::N_BYTES_MEMORY_ADDRESS, util::Expr};
-use eth_types::U256;
+use crate::{
+    evm_circuit::param::N_BYTES_MEMORY_ADDRESS,
+    util::{word, Expr},
+};
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{AssignedCell, Region},
+    circuit::Region,
     plonk::{Advice, Column, Error, Expression, VirtualCells},
     poly::Rotation,
 };
@@ -12,6 +14,43 @@ pub(crate) mod constraint_builder;
 pub(crate) mod math_gadget;
 pub(crate) mod memory_gadget;
 
+/// Advice values of a sequence of execution steps, computed before being
+/// assigned to the region of the execution steps.
+///
+/// The values of the steps only depend on the witness, so they are computed
+/// for chunks of steps in parallel, and then copied to the region in order,
+/// which has to be done sequentially.  Offsets are the ones of the region.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StepRegion<F> {
+    advices: Vec<(Column<Advice>, usize, Option<F>)>,
+}
+
+impl<F: FieldExt> StepRegion<F> {
+    pub(crate) fn assign_advice(
+        &mut self,
+        column: Column<Advice>,
+        offset: usize,
+        value: Option<F>,
+    ) -> Result<(), Error> {
+        self.advices.push((column, offset, value));
+        Ok(())
+    }
+
+    /// Assigns the recorded values to `region`, failing on the first unknown
+    /// value.
+    pub(crate) fn assign_to(&self, region: &mut Region<'_, F>) -> Result<(), Error> {
+        for (column, offset, value) in self.advices.iter() {
+            region.assign_advice(
+                || format!("Cell column: {:?} and offset: {}", column, offset),
+                *column,
+                *offset,
+                || value.ok_or(Error::Synthesis),
+            )?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct Cell<F> {
     // expression for constraint
@@ -32,21 +71,15 @@ impl<F: FieldExt> Cell<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         value: Option<F>,
-    ) -> Result<AssignedCell<F, F>, Error> {
-        region.assign_advice(
-            || {
-                format!(
-                    "Cell column: {:?} and rotation: {}",
-                    self.column, self.rotation
-                )
-            },
-            self.column,
-            offset + self.rotation,
-            || value.ok_or(Error::Synthesis),
-        )
+    ) -> Result<(), Error> {
+        region.assign_advice(self.column, offset + self.rotation, value)
+    }
+
+    pub(crate) fn column(&self) -> Column<Advice> {
+        self.column
     }
 }
 
@@ -104,10 +137,10 @@ impl<F: FieldExt, const N: usize> RandomLinearCombination<F, N> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         bytes: Option<[u8; N]>,
-    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    ) -> Result<(), Error> {
         bytes.map_or(Err(Error::Synthesis), |bytes| {
             self.cells
                 .iter()
@@ -125,6 +158,13 @@ impl<F: FieldExt, const N: usize> Expr<F> for RandomLinearCombination<F, N> {
 }
 
 pub(crate) type Word<F> = RandomLinearCombination<F, 32>;
+
+impl<F: FieldExt> Word<F> {
+    /// Returns the low and high 128-bit halves of the word.
+    pub(crate) fn to_word(&self) -> word::Word<Expression<F>> {
+        word::Word::from_bytes(&self.cells)
+    }
+}
 pub(crate) type MemoryAddress<F> = RandomLinearCombination<F, N_BYTES_MEMORY_ADDRESS>;
 
 /// Returns the sum of the passed in cells
@@ -273,11 +313,3 @@ pub(crate) fn pow_of_two<F: FieldExt>(by: usize) -> F {
 pub(crate) fn pow_of_two_expr<F: FieldExt>(by: usize) -> Expression<F> {
     Expression::Constant(pow_of_two(by))
 }
-
-/// Returns tuple consists of low and high part of U256
-pub(crate) fn split_u256(value: &U256) -> (U256, U256) {
-    (
-        U256([value.0[0], value.0[1], 0, 0]),
-        U256([value.0[2], value.0[3], 0, 0]),
-    )
-}
@@ -1,21 +1,20 @@
 use crate::{
     evm_circuit::{
-        param::N_BYTES_GAS,
-        table::{AccountFieldTag, FixedTableTag, Lookup},
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS, N_BYTES_U64},
+        table::AccountFieldTag,
         util::{
             constraint_builder::{ConstraintBuilder, ReversionInfo, StepStateTransition},
-            math_gadget::{AddWordsGadget, RangeCheckGadget},
-            Cell, Word,
+            from_bytes,
+            math_gadget::{AddWordsGadget, IsZeroGadget, LtGadget, RangeCheckGadget},
+            select, sum, Cell, RandomLinearCombination, StepRegion, Word,
         },
-        witness::ExecStep,
+        witness::{create2_address_keccak_input, create_address_keccak_input, ExecStep},
     },
     util::Expr,
 };
-use eth_types::{Field, U256};
-use halo2_proofs::{
-    circuit::Region,
-    plonk::{Error, Expression},
-};
+use array_init::array_init;
+use eth_types::{keccak256, Address, Field, ToLittleEndian, U256};
+use halo2_proofs::plonk::{Error, Expression};
 use std::convert::TryInto;
 
 /// Construction of execution state that stays in the same call context, which
@@ -32,19 +31,23 @@ impl<F: Field> SameContextGadget<F> {
         cb: &mut ConstraintBuilder<F>,
         opcode: Cell<F>,
         step_state_transition: StepStateTransition<F>,
+    ) -> Self {
+        Self::construct_with_opcode_index(cb, opcode, 0.expr(), step_state_transition)
+    }
+
+    /// Same as [`SameContextGadget::construct`], for execution states
+    /// responsible for several opcodes, where `opcode_index` is the index of
+    /// the opcode in
+    /// [`responsible_opcodes`](crate::evm_circuit::step::ExecutionState::
+    /// responsible_opcodes).
+    pub(crate) fn construct_with_opcode_index(
+        cb: &mut ConstraintBuilder<F>,
+        opcode: Cell<F>,
+        opcode_index: Expression<F>,
+        step_state_transition: StepStateTransition<F>,
     ) -> Self {
         cb.opcode_lookup(opcode.expr(), 1.expr());
-        cb.add_lookup(
-            "Responsible opcode lookup",
-            Lookup::Fixed {
-                tag: FixedTableTag::ResponsibleOpcode.expr(),
-                values: [
-                    cb.execution_state().as_u64().expr(),
-                    opcode.expr(),
-                    0.expr(),
-                ],
-            },
-        );
+        cb.responsible_opcode_lookup(opcode.expr(), opcode_index);
 
         // Check gas_left is sufficient
         let sufficient_gas_left = RangeCheckGadget::construct(cb, cb.next.state.gas_left.expr());
@@ -60,7 +63,7 @@ impl<F: Field> SameContextGadget<F> {
 
     pub(crate) fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         step: &ExecStep,
     ) -> Result<(), Error> {
@@ -90,7 +93,7 @@ impl<F: Field, const N_ADDENDS: usize, const INCREASE: bool>
         cb: &mut ConstraintBuilder<F>,
         address: Expression<F>,
         updates: Vec<Word<F>>,
-        reversion_info: Option<ReversionInfo<F>>,
+        reversion_info: Option<&mut ReversionInfo<F>>,
     ) -> Self {
         debug_assert!(updates.len() == N_ADDENDS - 1);
 
@@ -126,7 +129,7 @@ impl<F: Field, const N_ADDENDS: usize, const INCREASE: bool>
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         addends: Vec<U256>,
         sum: U256,
@@ -151,28 +154,23 @@ impl<F: Field> TransferWithGasFeeGadget<F> {
         receiver_address: Expression<F>,
         value: Word<F>,
         gas_fee: Word<F>,
-        is_persistent: Expression<F>,
-        rw_counter_end_of_reversion: Expression<F>,
+        reversion_info: &mut ReversionInfo<F>,
     ) -> Self {
         let sender = UpdateBalanceGadget::construct(
             cb,
             sender_address,
             vec![value.clone(), gas_fee],
-            Some((&is_persistent, &rw_counter_end_of_reversion).into()),
-        );
-        let receiver = UpdateBalanceGadget::construct(
-            cb,
-            receiver_address,
-            vec![value],
-            Some((is_persistent, rw_counter_end_of_reversion - 1.expr()).into()),
+            Some(reversion_info),
         );
+        let receiver =
+            UpdateBalanceGadget::construct(cb, receiver_address, vec![value], Some(reversion_info));
 
         Self { sender, receiver }
     }
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         (sender_balance, sender_balance_prev): (U256, U256),
         (receiver_balance, receiver_balance_prev): (U256, U256),
@@ -194,3 +192,354 @@ impl<F: Field> TransferWithGasFeeGadget<F> {
         Ok(())
     }
 }
+
+#[derive(Clone, Debug)]
+pub(crate) struct TransferGadget<F> {
+    sender: UpdateBalanceGadget<F, 2, false>,
+    receiver: UpdateBalanceGadget<F, 2, true>,
+}
+
+impl<F: Field> TransferGadget<F> {
+    pub(crate) fn construct(
+        cb: &mut ConstraintBuilder<F>,
+        sender_address: Expression<F>,
+        receiver_address: Expression<F>,
+        value: Word<F>,
+        reversion_info: &mut ReversionInfo<F>,
+    ) -> Self {
+        let sender = UpdateBalanceGadget::construct(
+            cb,
+            sender_address,
+            vec![value.clone()],
+            Some(reversion_info),
+        );
+        let receiver =
+            UpdateBalanceGadget::construct(cb, receiver_address, vec![value], Some(reversion_info));
+
+        Self { sender, receiver }
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut StepRegion<F>,
+        offset: usize,
+        (sender_balance, sender_balance_prev): (U256, U256),
+        (receiver_balance, receiver_balance_prev): (U256, U256),
+        value: U256,
+    ) -> Result<(), Error> {
+        self.sender.assign(
+            region,
+            offset,
+            vec![sender_balance, value],
+            sender_balance_prev,
+        )?;
+        self.receiver.assign(
+            region,
+            offset,
+            vec![receiver_balance_prev, value],
+            receiver_balance,
+        )?;
+        Ok(())
+    }
+}
+
+/// Returns `r**exp` as Expression, where `r` is the randomness of
+/// `power_of_randomness`.
+fn pow_of_randomness<F: Field>(power_of_randomness: &[Expression<F>], exp: usize) -> Expression<F> {
+    let n = power_of_randomness.len();
+    let rest = if exp % n == 0 {
+        1.expr()
+    } else {
+        power_of_randomness[exp % n - 1].clone()
+    };
+    (0..exp / n).fold(rest, |acc, _| acc * power_of_randomness[n - 1].clone())
+}
+
+/// Construction of the RLP encoding of an u64 value, which is a single byte
+/// when the value is in `[1, 0x80)`, and `0x80 + len ‖ value_be_bytes` with
+/// the minimal number of bytes otherwise.
+#[derive(Clone, Debug)]
+pub(crate) struct RlpU64Gadget<F> {
+    bytes: RandomLinearCombination<F, N_BYTES_U64>,
+    // One-hot encoding of the most significant non-zero byte, which is all
+    // zero when the value is 0.
+    is_most_significant_byte: [Cell<F>; N_BYTES_U64],
+    most_significant_byte_is_zero: IsZeroGadget<F>,
+    is_lt_0x80: LtGadget<F, 1>,
+    is_short: Cell<F>,
+    // r**n_bytes, where n_bytes is the number of the significant bytes.
+    randomness_pow_n_bytes: Cell<F>,
+    rlp_length: Expression<F>,
+    rlp_rlc: Expression<F>,
+    randomness_pow_rlp_length: Expression<F>,
+}
+
+impl<F: Field> RlpU64Gadget<F> {
+    pub(crate) fn construct(cb: &mut ConstraintBuilder<F>) -> Self {
+        let bytes = cb.query_rlc();
+        let is_most_significant_byte = [(); N_BYTES_U64].map(|_| cb.query_bool());
+        let is_non_zero = sum::expr(&is_most_significant_byte);
+        cb.require_boolean(
+            "At most one byte is the most significant byte",
+            is_non_zero.clone(),
+        );
+
+        // Bytes more significant than the most significant byte are zero
+        for (idx, byte) in bytes.cells.iter().enumerate() {
+            cb.require_zero(
+                "Byte more significant than the most significant byte is zero",
+                byte.expr() * (1.expr() - sum::expr(&is_most_significant_byte[idx..])),
+            );
+        }
+
+        // The most significant byte is non-zero
+        let most_significant_byte_is_zero = IsZeroGadget::construct(
+            cb,
+            sum::expr(
+                is_most_significant_byte
+                    .iter()
+                    .zip(bytes.cells.iter())
+                    .map(|(is_msb, byte)| is_msb.expr() * byte.expr()),
+            ),
+        );
+        cb.require_zero(
+            "The most significant byte is non-zero",
+            is_non_zero.clone() * most_significant_byte_is_zero.expr(),
+        );
+
+        // The value is encoded as a single byte when it's in [1, 0x80)
+        let is_lt_0x80 = LtGadget::construct(cb, bytes.cells[0].expr(), 0x80.expr());
+        let is_short = cb.copy(is_most_significant_byte[0].expr() * is_lt_0x80.expr());
+
+        let n_bytes = sum::expr(
+            is_most_significant_byte
+                .iter()
+                .enumerate()
+                .map(|(idx, is_msb)| is_msb.expr() * (idx + 1).expr()),
+        );
+        let randomness_pow_n_bytes = 1.expr() - is_non_zero
+            + sum::expr(
+                is_most_significant_byte
+                    .iter()
+                    .zip(cb.power_of_randomness().iter())
+                    .map(|(is_msb, randomness)| is_msb.expr() * randomness.clone()),
+            );
+        let randomness_pow_n_bytes = cb.copy(randomness_pow_n_bytes);
+
+        let rlp_length = select::expr(is_short.expr(), 1.expr(), 1.expr() + n_bytes.clone());
+        let rlp_rlc = select::expr(
+            is_short.expr(),
+            bytes.cells[0].expr(),
+            (0x80.expr() + n_bytes) * randomness_pow_n_bytes.expr() + bytes.expr(),
+        );
+        let randomness = cb.power_of_randomness()[0].clone();
+        let randomness_pow_rlp_length = select::expr(
+            is_short.expr(),
+            randomness.clone(),
+            randomness * randomness_pow_n_bytes.expr(),
+        );
+
+        Self {
+            bytes,
+            is_most_significant_byte,
+            most_significant_byte_is_zero,
+            is_lt_0x80,
+            is_short,
+            randomness_pow_n_bytes,
+            rlp_length,
+            rlp_rlc,
+            randomness_pow_rlp_length,
+        }
+    }
+
+    /// The encoded value
+    pub(crate) fn value(&self) -> Expression<F> {
+        from_bytes::expr(&self.bytes.cells)
+    }
+
+    /// Length of the RLP encoding in bytes
+    pub(crate) fn rlp_length(&self) -> Expression<F> {
+        self.rlp_length.clone()
+    }
+
+    /// Random linear combination of the RLP encoding, with the first byte
+    /// multiplied by the highest power of the randomness
+    pub(crate) fn rlp_rlc(&self) -> Expression<F> {
+        self.rlp_rlc.clone()
+    }
+
+    /// `r**rlp_length`, which is useful to concatenate the RLP encoding after
+    /// other bytes
+    pub(crate) fn randomness_pow_rlp_length(&self) -> Expression<F> {
+        self.randomness_pow_rlp_length.clone()
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut StepRegion<F>,
+        offset: usize,
+        value: u64,
+        randomness: F,
+    ) -> Result<(), Error> {
+        let bytes = value.to_le_bytes();
+        self.bytes.assign(region, offset, Some(bytes))?;
+
+        let n_bytes = N_BYTES_U64 - value.leading_zeros() as usize / 8;
+        for (idx, is_msb) in self.is_most_significant_byte.iter().enumerate() {
+            is_msb.assign(region, offset, Some(F::from((idx + 1 == n_bytes) as u64)))?;
+        }
+        self.most_significant_byte_is_zero.assign(
+            region,
+            offset,
+            if n_bytes == 0 {
+                F::zero()
+            } else {
+                F::from(bytes[n_bytes - 1] as u64)
+            },
+        )?;
+        self.is_lt_0x80
+            .assign(region, offset, F::from(bytes[0] as u64), F::from(0x80))?;
+        self.is_short.assign(
+            region,
+            offset,
+            Some(F::from((n_bytes == 1 && bytes[0] < 0x80) as u64)),
+        )?;
+        self.randomness_pow_n_bytes.assign(
+            region,
+            offset,
+            Some(randomness.pow(&[n_bytes as u64, 0, 0, 0])),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Construction of the address of a created contract, which is
+/// `keccak(rlp([sender, nonce]))[12..]` for CREATE and contract creation
+/// transaction, and `keccak(0xff ‖ sender ‖ salt ‖ code_hash)[12..]` for
+/// CREATE2. The hash is constrained by a lookup to the keccak table.
+#[derive(Clone, Debug)]
+pub(crate) struct ContractCreateGadget<F, const IS_CREATE2: bool> {
+    caller_address: RandomLinearCombination<F, N_BYTES_ACCOUNT_ADDRESS>,
+    nonce: RlpU64Gadget<F>,
+    // Salt and code hash, only used by CREATE2
+    salt_and_code_hash: Option<(Word<F>, Word<F>)>,
+    keccak_output: Word<F>,
+}
+
+impl<F: Field, const IS_CREATE2: bool> ContractCreateGadget<F, IS_CREATE2> {
+    pub(crate) fn construct(cb: &mut ConstraintBuilder<F>) -> Self {
+        let caller_address = cb.query_rlc();
+        let nonce = RlpU64Gadget::construct(cb);
+        let salt_and_code_hash = if IS_CREATE2 {
+            Some((cb.query_word(), cb.query_word()))
+        } else {
+            None
+        };
+        let keccak_output = cb.query_word();
+
+        let (input_rlc, input_len) = if let Some((salt, code_hash)) = salt_and_code_hash.as_ref() {
+            // 0xff ‖ sender ‖ salt ‖ code_hash
+            let power_of_randomness = cb.power_of_randomness();
+            (
+                0xff.expr() * pow_of_randomness(power_of_randomness, 84)
+                    + caller_address.expr() * pow_of_randomness(power_of_randomness, 64)
+                    + salt.expr() * pow_of_randomness(power_of_randomness, 32)
+                    + code_hash.expr(),
+                85.expr(),
+            )
+        } else {
+            // rlp([sender, nonce]), where the list header and the sender
+            // header are both a single byte since the payload is at most 30
+            // bytes.
+            let power_of_randomness = cb.power_of_randomness();
+            let payload_length = 21.expr() + nonce.rlp_length();
+            (
+                ((0xc0.expr() + payload_length.clone())
+                    * pow_of_randomness(power_of_randomness, 21)
+                    + (0x80 + N_BYTES_ACCOUNT_ADDRESS).expr()
+                        * pow_of_randomness(power_of_randomness, 20)
+                    + caller_address.expr())
+                    * nonce.randomness_pow_rlp_length()
+                    + nonce.rlp_rlc(),
+                1.expr() + payload_length,
+            )
+        };
+        cb.keccak_table_lookup(input_rlc, input_len, keccak_output.expr());
+
+        Self {
+            caller_address,
+            nonce,
+            salt_and_code_hash,
+            keccak_output,
+        }
+    }
+
+    pub(crate) fn caller_address(&self) -> Expression<F> {
+        from_bytes::expr(&self.caller_address.cells)
+    }
+
+    pub(crate) fn nonce(&self) -> Expression<F> {
+        self.nonce.value()
+    }
+
+    /// Address of the created contract
+    pub(crate) fn address(&self) -> Expression<F> {
+        from_bytes::expr(&self.keccak_output.cells[..N_BYTES_ACCOUNT_ADDRESS])
+    }
+
+    /// Address of the created contract as the random linear combination of
+    /// a word, like it's pushed to the stack
+    pub(crate) fn address_rlc(&self, power_of_randomness: &[Expression<F>]) -> Expression<F> {
+        RandomLinearCombination::<F, N_BYTES_ACCOUNT_ADDRESS>::random_linear_combine_expr(
+            array_init(|idx| self.keccak_output.cells[idx].expr()),
+            power_of_randomness,
+        )
+    }
+
+    /// Salt and code hash of the init code, only used by CREATE2
+    pub(crate) fn salt_and_code_hash(&self) -> Option<(Expression<F>, Expression<F>)> {
+        self.salt_and_code_hash
+            .as_ref()
+            .map(|(salt, code_hash)| (salt.expr(), code_hash.expr()))
+    }
+
+    /// Assigns the witness and returns the address of the created contract.
+    /// `salt_and_code_hash` is required only for CREATE2.
+    pub(crate) fn assign(
+        &self,
+        region: &mut StepRegion<F>,
+        offset: usize,
+        caller_address: Address,
+        nonce: u64,
+        salt_and_code_hash: Option<(U256, U256)>,
+        randomness: F,
+    ) -> Result<Address, Error> {
+        let mut caller_address_bytes = caller_address.to_fixed_bytes();
+        caller_address_bytes.reverse();
+        self.caller_address
+            .assign(region, offset, Some(caller_address_bytes))?;
+        self.nonce.assign(region, offset, nonce, randomness)?;
+
+        let input = match (self.salt_and_code_hash.as_ref(), salt_and_code_hash) {
+            (Some((salt, code_hash)), Some((salt_value, code_hash_value))) => {
+                salt.assign(region, offset, Some(salt_value.to_le_bytes()))?;
+                code_hash.assign(region, offset, Some(code_hash_value.to_le_bytes()))?;
+                create2_address_keccak_input(caller_address, salt_value, code_hash_value)
+            }
+            (None, None) => create_address_keccak_input(caller_address, nonce),
+            _ => return Err(Error::Synthesis),
+        };
+
+        let keccak_output = keccak256(&input);
+        self.keccak_output.assign(
+            region,
+            offset,
+            Some(U256::from_big_endian(&keccak_output).to_le_bytes()),
+        )?;
+
+        Ok(Address::from_slice(
+            &keccak_output.as_slice()[32 - N_BYTES_ACCOUNT_ADDRESS..],
+        ))
+    }
+}
@@ -3,14 +3,18 @@ use crate::{
         param::STACK_CAPACITY,
         step::{ExecutionState, Preset, Step},
         table::{
-            AccountFieldTag, CallContextFieldTag, FixedTableTag, Lookup, RwTableTag,
+            AccountFieldTag, CallContextFieldTag, CopyDataType, FixedTableTag, Lookup, RwTableTag,
             TxContextFieldTag,
         },
-        util::{Cell, RandomLinearCombination, Word},
+        util::{Cell, RandomLinearCombination, StepRegion, Word},
     },
     util::Expr,
 };
-use halo2_proofs::{arithmetic::FieldExt, plonk::Expression};
+use eth_types::evm_types::Hardfork;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::{Error, Expression},
+};
 use std::convert::TryInto;
 
 // Max degree allowed in all expressions passing through the ConstraintBuilder.
@@ -81,18 +85,49 @@ impl<F: FieldExt> StepStateTransition<F> {
     }
 }
 
-#[derive(Clone)]
+/// Reversion information of a call, used by reversible state writes to also
+/// add the lookup of their reversion, which happens at the end of the call
+/// when it's not persistent.
+///
+/// The reversions are done in reverse order, so the i-th reversible write of
+/// the call is reverted at `rw_counter_end_of_reversion - i`. The amount of
+/// reversible writes done so far is tracked in `state_write_counter`, so the
+/// gadgets don't need to count them by hand.
+#[derive(Clone, Debug)]
 pub(crate) struct ReversionInfo<F> {
-    is_persistent: Expression<F>,
-    rw_counter: Expression<F>,
+    rw_counter_end_of_reversion: Cell<F>,
+    is_persistent: Cell<F>,
+    state_write_counter: Expression<F>,
 }
 
-impl<F: FieldExt, E1: Expr<F>, E2: Expr<F>> From<(E1, E2)> for ReversionInfo<F> {
-    fn from((is_persistent, rw_counter): (E1, E2)) -> Self {
-        Self {
-            is_persistent: is_persistent.expr(),
-            rw_counter: rw_counter.expr(),
-        }
+impl<F: FieldExt> ReversionInfo<F> {
+    pub(crate) fn is_persistent(&self) -> Expression<F> {
+        self.is_persistent.expr()
+    }
+
+    /// Returns the rw_counter of the reversion of the next reversible write,
+    /// and increases the `state_write_counter`.
+    fn rw_counter_of_reversion(&mut self) -> Expression<F> {
+        let rw_counter = self.rw_counter_end_of_reversion.expr() - self.state_write_counter.clone();
+        self.state_write_counter = self.state_write_counter.clone() + 1.expr();
+        rw_counter
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut StepRegion<F>,
+        offset: usize,
+        rw_counter_end_of_reversion: usize,
+        is_persistent: bool,
+    ) -> Result<(), Error> {
+        self.rw_counter_end_of_reversion.assign(
+            region,
+            offset,
+            Some(F::from(rw_counter_end_of_reversion as u64)),
+        )?;
+        self.is_persistent
+            .assign(region, offset, Some(F::from(is_persistent as u64)))?;
+        Ok(())
     }
 }
 
@@ -198,6 +233,7 @@ pub(crate) struct ConstraintBuilder<'a, F> {
     pub(crate) next: &'a Step<F>,
     power_of_randomness: &'a [Expression<F>; 31],
     execution_state: ExecutionState,
+    hardfork: Hardfork,
     cb: BaseConstraintBuilder<F>,
     constraints_first_step: Vec<(&'static str, Expression<F>)>,
     lookups: Vec<(&'static str, Lookup<F>)>,
@@ -206,6 +242,7 @@ pub(crate) struct ConstraintBuilder<'a, F> {
     rw_counter_offset: Expression<F>,
     program_counter_offset: usize,
     stack_pointer_offset: i32,
+    state_write_counter_offset: usize,
     in_next_step: bool,
     condition: Option<Expression<F>>,
 }
@@ -216,12 +253,14 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         next: &'a Step<F>,
         power_of_randomness: &'a [Expression<F>; 31],
         execution_state: ExecutionState,
+        hardfork: Hardfork,
     ) -> Self {
         Self {
             curr,
             next,
             power_of_randomness,
             execution_state,
+            hardfork,
             cb: BaseConstraintBuilder::new(MAX_DEGREE),
             constraints_first_step: Vec::new(),
             lookups: Vec::new(),
@@ -230,6 +269,7 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
             rw_counter_offset: 0.expr(),
             program_counter_offset: 0,
             stack_pointer_offset: 0,
+            state_write_counter_offset: 0,
             in_next_step: false,
             condition: None,
         }
@@ -290,8 +330,8 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         self.power_of_randomness
     }
 
-    pub(crate) fn execution_state(&self) -> ExecutionState {
-        self.execution_state
+    pub(crate) fn hardfork(&self) -> Hardfork {
+        self.hardfork
     }
 
     pub(crate) fn rw_counter_offset(&self) -> Expression<F> {
@@ -306,6 +346,10 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         self.stack_pointer_offset
     }
 
+    pub(crate) fn state_write_counter_offset(&self) -> usize {
+        self.state_write_counter_offset
+    }
+
     // Query
 
     pub(crate) fn copy<E: Expr<F>>(&mut self, value: E) -> Cell<F> {
@@ -460,6 +504,8 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
 
     pub(crate) fn range_lookup(&mut self, value: Expression<F>, range: u64) {
         let (name, tag) = match range {
+            // No need to lookup for a boolean
+            2 => return self.require_boolean("Range2", value),
             5 => ("Range5", FixedTableTag::Range5),
             16 => ("Range16", FixedTableTag::Range16),
             32 => ("Range32", FixedTableTag::Range32),
@@ -510,6 +556,24 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         );
     }
 
+    /// Lookup the fixed table to check the current execution state is
+    /// responsible for the opcode, and that `opcode_index` is the index of the
+    /// opcode in [`ExecutionState::responsible_opcodes`]. Gadgets handling
+    /// several opcodes bind their opcode selectors through `opcode_index`.
+    pub(crate) fn responsible_opcode_lookup(
+        &mut self,
+        opcode: Expression<F>,
+        opcode_index: Expression<F>,
+    ) {
+        self.add_lookup(
+            "Responsible opcode lookup",
+            Lookup::Fixed {
+                tag: FixedTableTag::ResponsibleOpcode.expr(),
+                values: [self.execution_state.as_u64().expr(), opcode, opcode_index],
+            },
+        );
+    }
+
     // Tx context
 
     pub(crate) fn tx_context(
@@ -569,6 +633,84 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         );
     }
 
+    // Keccak
+
+    pub(crate) fn keccak_table_lookup(
+        &mut self,
+        input_rlc: Expression<F>,
+        input_len: Expression<F>,
+        output_rlc: Expression<F>,
+    ) {
+        self.add_lookup(
+            "Keccak table lookup",
+            Lookup::Keccak {
+                input_rlc,
+                input_len,
+                output_rlc,
+            },
+        );
+    }
+
+    // Copy
+
+    /// Add a Lookup::Copy of `length` bytes proven by the copy circuit, and
+    /// increase the rw_counter_offset by its `rw_inc` read-write accesses.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn copy_table_lookup(
+        &mut self,
+        src_id: Expression<F>,
+        src_type: CopyDataType,
+        dst_id: Expression<F>,
+        dst_type: CopyDataType,
+        src_addr: Expression<F>,
+        src_addr_end: Expression<F>,
+        dst_addr: Expression<F>,
+        log_id: Expression<F>,
+        length: Expression<F>,
+        rlc_acc: Expression<F>,
+        rw_inc: Expression<F>,
+    ) {
+        self.add_lookup(
+            "Copy lookup",
+            Lookup::Copy {
+                src_id,
+                src_type: src_type.expr(),
+                dst_id,
+                dst_type: dst_type.expr(),
+                src_addr,
+                src_addr_end,
+                dst_addr,
+                log_id,
+                length,
+                rlc_acc,
+                rw_counter: self.curr.state.rw_counter.expr() + self.rw_counter_offset.clone(),
+                rw_inc: rw_inc.clone(),
+            },
+        );
+        self.rw_counter_offset = self.rw_counter_offset.clone()
+            + self.cb.condition.clone().unwrap_or_else(|| 1.expr()) * rw_inc;
+    }
+
+    // Exp
+
+    /// Add a Lookup::Exp of `base^exponent (mod 2^256) == result`, proven by
+    /// the exp circuit.
+    pub(crate) fn exp_table_lookup(
+        &mut self,
+        base: Expression<F>,
+        exponent: Expression<F>,
+        result: Expression<F>,
+    ) {
+        self.add_lookup(
+            "Exp table lookup",
+            Lookup::Exp {
+                base,
+                exponent,
+                result,
+            },
+        );
+    }
+
     // Rw
 
     /// Add a Lookup::Rw without increasing the rw_counter_offset, which is
@@ -617,19 +759,18 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         name: &'static str,
         tag: RwTableTag,
         mut values: [Expression<F>; 8],
-        reversion_info: Option<ReversionInfo<F>>,
+        reversion_info: Option<&mut ReversionInfo<F>>,
     ) {
         debug_assert!(tag.is_reversible(), "Only reversible tags are state write");
 
         self.rw_lookup(name, true.expr(), tag, values.clone());
 
-        if let Some(ReversionInfo {
-            is_persistent,
-            rw_counter,
-        }) = reversion_info
-        {
+        if let Some(reversion_info) = reversion_info {
+            let rw_counter = reversion_info.rw_counter_of_reversion();
+            self.state_write_counter_offset += 1;
+
             // Revert if is_persistent is 0
-            self.condition(1.expr() - is_persistent, |cb| {
+            self.condition(1.expr() - reversion_info.is_persistent(), |cb| {
                 // Swap value and value_prev
                 values.swap(4, 5);
 
@@ -638,6 +779,31 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         }
     }
 
+    // Reversion
+
+    /// Reads `RwCounterEndOfReversion` and `IsPersistent` of the call. When
+    /// `call_id` is `None` it's the current call, whose following reversible
+    /// writes continue from the `state_write_counter` of the current step.
+    /// Otherwise it's a new call without any reversible write yet.
+    pub(crate) fn reversion_info(&mut self, call_id: Option<Expression<F>>) -> ReversionInfo<F> {
+        let state_write_counter = if call_id.is_some() {
+            0.expr()
+        } else {
+            self.curr.state.state_write_counter.expr()
+        };
+        let [rw_counter_end_of_reversion, is_persistent] = [
+            CallContextFieldTag::RwCounterEndOfReversion,
+            CallContextFieldTag::IsPersistent,
+        ]
+        .map(|field_tag| self.call_context(call_id.clone(), field_tag));
+
+        ReversionInfo {
+            rw_counter_end_of_reversion,
+            is_persistent,
+            state_write_counter,
+        }
+    }
+
     // Access list
 
     pub(crate) fn account_access_list_write(
@@ -646,7 +812,7 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         account_address: Expression<F>,
         value: Expression<F>,
         value_prev: Expression<F>,
-        reversion_info: Option<ReversionInfo<F>>,
+        reversion_info: Option<&mut ReversionInfo<F>>,
     ) -> Expression<F> {
         self.state_write(
             "TxAccessListAccount write",
@@ -674,7 +840,7 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         storage_key: Expression<F>,
         value: Expression<F>,
         value_prev: Expression<F>,
-        reversion_info: Option<ReversionInfo<F>>,
+        reversion_info: Option<&mut ReversionInfo<F>>,
     ) -> Expression<F> {
         self.state_write(
             "TxAccessListAccountStorage write",
@@ -720,7 +886,7 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         tx_id: Expression<F>,
         value: Expression<F>,
         value_prev: Expression<F>,
-        reversion_info: Option<ReversionInfo<F>>,
+        reversion_info: Option<&mut ReversionInfo<F>>,
     ) {
         self.state_write(
             "TxRefund write",
@@ -770,7 +936,7 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         field_tag: AccountFieldTag,
         value: Expression<F>,
         value_prev: Expression<F>,
-        reversion_info: Option<ReversionInfo<F>>,
+        reversion_info: Option<&mut ReversionInfo<F>>,
     ) {
         self.state_write(
             "Account write with reversion",
@@ -825,7 +991,7 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         value_prev: Expression<F>,
         tx_id: Expression<F>,
         committed_value: Expression<F>,
-        reversion_info: Option<ReversionInfo<F>>,
+        reversion_info: Option<&mut ReversionInfo<F>>,
     ) {
         self.state_write(
             "AccountStorage write",
@@ -941,6 +1107,14 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         );
     }
 
+    pub(crate) fn memory_read(&mut self, memory_address: Expression<F>, byte: Expression<F>) {
+        self.memory_lookup(false.expr(), memory_address, byte, None);
+    }
+
+    pub(crate) fn memory_write(&mut self, memory_address: Expression<F>, byte: Expression<F>) {
+        self.memory_lookup(true.expr(), memory_address, byte, None);
+    }
+
     pub(crate) fn memory_lookup_with_counter(
         &mut self,
         rw_counter: Expression<F>,
@@ -1,13 +1,14 @@
 use crate::{
     evm_circuit::util::{
         self, constraint_builder::ConstraintBuilder, from_bytes, pow_of_two, pow_of_two_expr,
-        select, split_u256, sum, Cell,
+        select, sum, Cell, StepRegion,
     },
-    util::Expr,
+    util::{word, Expr},
 };
 use eth_types::{Field, ToLittleEndian, ToScalar, Word};
 use halo2_proofs::plonk::Error;
-use halo2_proofs::{arithmetic::FieldExt, circuit::Region, plonk::Expression};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Expression};
+use num::BigUint;
 use std::convert::TryFrom;
 
 /// Returns `1` when `value == 0`, and returns `0` otherwise.
@@ -41,7 +42,7 @@ impl<F: FieldExt> IsZeroGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         value: F,
     ) -> Result<F, Error> {
@@ -78,7 +79,7 @@ impl<F: FieldExt> IsEqualGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         lhs: F,
         rhs: F,
@@ -112,20 +113,15 @@ impl<F: Field, const N_ADDENDS: usize, const CHECK_OVREFLOW: bool>
             Some(cb.query_cell())
         };
 
-        let addends_lo = &addends
+        let (addends_lo, addends_hi): (Vec<_>, Vec<_>) = addends
             .iter()
-            .map(|addend| from_bytes::expr(&addend.cells[..16]))
-            .collect::<Vec<_>>();
-        let addends_hi = &addends
-            .iter()
-            .map(|addend| from_bytes::expr(&addend.cells[16..]))
-            .collect::<Vec<_>>();
-        let sum_lo = from_bytes::expr(&sum.cells[..16]);
-        let sum_hi = from_bytes::expr(&sum.cells[16..]);
+            .map(|addend| addend.to_word().to_lo_hi())
+            .unzip();
+        let (sum_lo, sum_hi) = sum.to_word().to_lo_hi();
 
         cb.require_equal(
             "sum(addends_lo) == sum_lo + carry_lo ⋅ 2^128",
-            sum::expr(addends_lo),
+            sum::expr(&addends_lo),
             sum_lo + carry_lo.expr() * pow_of_two_expr(128),
         );
         cb.require_equal(
@@ -134,7 +130,7 @@ impl<F: Field, const N_ADDENDS: usize, const CHECK_OVREFLOW: bool>
             } else {
                 "sum(addends_hi) + carry_lo == sum_hi + carry_hi ⋅ 2^128"
             },
-            sum::expr(addends_hi) + carry_lo.expr(),
+            sum::expr(&addends_hi) + carry_lo.expr(),
             if CHECK_OVREFLOW {
                 sum_hi
             } else {
@@ -164,7 +160,7 @@ impl<F: Field, const N_ADDENDS: usize, const CHECK_OVREFLOW: bool>
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         addends: [Word; N_ADDENDS],
         sum: Word,
@@ -174,8 +170,11 @@ impl<F: Field, const N_ADDENDS: usize, const CHECK_OVREFLOW: bool>
         }
         self.sum.assign(region, offset, Some(sum.to_le_bytes()))?;
 
-        let (addends_lo, addends_hi): (Vec<_>, Vec<_>) = addends.iter().map(split_u256).unzip();
-        let (sum_lo, sum_hi) = split_u256(&sum);
+        let (addends_lo, addends_hi): (Vec<_>, Vec<_>) = addends
+            .iter()
+            .map(|addend| word::Word::from(*addend).to_lo_hi())
+            .unzip();
+        let (sum_lo, sum_hi) = word::Word::from(sum).to_lo_hi();
 
         let sum_of_addends_lo = addends_lo
             .into_iter()
@@ -207,187 +206,155 @@ impl<F: Field, const N_ADDENDS: usize, const CHECK_OVREFLOW: bool>
     }
 }
 
-/// Construction of 2 256-bit words mutiplication and result (modulo 2**256),
-/// which is useful for opcode MUL, DIV, SDIV and xxxMOD
+/// Construction of `a ⋅ b + c == d (mod 2^256)` for 256-bit words, which is
+/// shared by the opcodes multiplying words, like MUL, DIV, MOD, ADDMOD, MULMOD
+/// and EXP.  `a` and `b` are split into 64-bit limbs, `c` and `d` into 128-bit
+/// halves, and the carries of the low and high halves are range checked by
+/// their bytes.  The part of `a ⋅ b + c` above 2^256 is returned by
+/// `overflow`, which is 0 iff there is no overflow.
 #[derive(Clone, Debug)]
-pub(crate) struct MulWordsGadget<F> {
-    a: util::Word<F>,
-    b: util::Word<F>,
-    product: util::Word<F>,
-    //here we execute a multi-limbs multiplication, see spec or
-    //https://hackmd.io/HL0QhGUeQoSgIBt2el6fHA
-    // a, b and product is divided into 4 64-bit digits, call them a0 ~ a3, b0
-    // ~ b3 ... a * b = a0 * b0 + a1 * b0 ..., and let
-    // t0 = a0 * b0, contribute to 0 ~ 128 bit
-    // t1 = a0 * b1 + a1 * b0, contribute to 64 ~ 193 bit (include the carry)
-    // t2 = a0 * b2 + a2 * b0 + a1 * b1, contribute to above 128 bit
-    // t3 =  a0 * b3 + a3 * b0 + a2 * b1 + a1 * b2, contribute to above 192 bit
-    //
-    // so t0 ~ t1 include all contributions to the low 256bit of product,
-    // with a maxium 68bit radix (the part higher than 256bit) v1
-    // it is similar that we have v0 as the radix of contributions
-    // to the low 128bit of the product
-    // we can slightly relax the constraint of v0/v1 to 72bit so just
-    // use 9 bytes for them
-    v0: [Cell<F>; 9],
-    v1: [Cell<F>; 9],
-    /* finally we just prove:
-     *  t0 + t1 = <low 128 bit of product> + <radix v0>
-     *  t2 + t3 + <radix v0> = <high 128 bit of product> + <radix v1> */
+pub(crate) struct MulAddWordsGadget<F> {
+    carry_lo: [Cell<F>; 9],
+    carry_hi: [Cell<F>; 9],
+    overflow: Expression<F>,
 }
 
-impl<F: FieldExt> MulWordsGadget<F> {
-    pub(crate) fn construct(
-        cb: &mut ConstraintBuilder<F>,
-        a: util::Word<F>,
-        b: util::Word<F>,
-    ) -> Self {
-        let product = cb.query_word();
-        let v0 = array_init::array_init(|_| cb.query_byte());
-        let v1 = array_init::array_init(|_| cb.query_byte());
-
-        let mut a_limbs = vec![];
-        let mut b_limbs = vec![];
-        let mut c_limbs = vec![];
-        for virtual_idx in 0..4 {
-            let now_idx = (virtual_idx * 8) as usize;
-            a_limbs.push(from_bytes::expr(&a.cells[now_idx..now_idx + 8]));
-            b_limbs.push(from_bytes::expr(&b.cells[now_idx..now_idx + 8]));
-            c_limbs.push(from_bytes::expr(&product.cells[now_idx..now_idx + 8]));
-        }
-
-        let t0 = a_limbs[0].clone() * b_limbs[0].clone();
-        let t1 = a_limbs[0].clone() * b_limbs[1].clone() + a_limbs[1].clone() * b_limbs[0].clone();
-        let t2 = a_limbs[0].clone() * b_limbs[2].clone()
-            + a_limbs[1].clone() * b_limbs[1].clone()
-            + a_limbs[2].clone() * b_limbs[0].clone();
-        let t3 = a_limbs[0].clone() * b_limbs[3].clone()
-            + a_limbs[1].clone() * b_limbs[2].clone()
-            + a_limbs[2].clone() * b_limbs[1].clone()
-            + a_limbs[3].clone() * b_limbs[0].clone();
-
-        let cur_v0 = from_bytes::expr(&v0[..]);
-        let cur_v1 = from_bytes::expr(&v1[..]);
-
-        //radix_constant_64 == 2^64
-        //radix_constant_128 == 2^128
-        let radix_constant_64 = pow_of_two_expr(64);
-        let radix_constant_128 = pow_of_two_expr(128);
-        cb.require_equal(
-            "mul(multipliers_lo) == product_lo + radix_lo ⋅ 2^128",
-            cur_v0.clone() * radix_constant_128.clone(),
-            t0.expr() + t1.expr() * radix_constant_64.clone()
-                - (c_limbs[0].clone() + c_limbs[1].clone() * radix_constant_64.clone()),
-        );
-        cb.require_equal(
-            "mul(multipliers_high) == product_high + radix_high ⋅ 2^128",
-            cur_v1 * radix_constant_128,
-            cur_v0 + t2.expr() + t3.expr() * radix_constant_64.clone()
-                - (c_limbs[2].clone() + c_limbs[3].clone() * radix_constant_64),
+impl<F: FieldExt> MulAddWordsGadget<F> {
+    pub(crate) fn construct(cb: &mut ConstraintBuilder<F>, words: [&util::Word<F>; 4]) -> Self {
+        let carry_lo = cb.query_bytes();
+        let carry_hi = cb.query_bytes();
+
+        let limbs = words.map(|word| {
+            [0, 1, 2, 3].map(|idx| from_bytes::expr(&word.cells[idx * 8..(idx + 1) * 8]))
+        });
+        let (constraints, overflow) = Self::constraints(
+            limbs,
+            from_bytes::expr(&carry_lo),
+            from_bytes::expr(&carry_hi),
         );
+        for (name, lhs, rhs) in constraints {
+            cb.require_equal(name, lhs, rhs);
+        }
 
         Self {
-            a,
-            b,
-            product,
-            v0,
-            v1,
+            carry_lo,
+            carry_hi,
+            overflow,
         }
     }
 
+    /// Returns the constraints of `a ⋅ b + c` on the low and high 128 bits,
+    /// given the 64-bit limbs of `a`, `b`, `c` and `d` and the carries of the
+    /// low and high 128 bits, with the part of `a ⋅ b + c` above 2^256.  They
+    /// are shared with the circuits laying out the limbs and the carries in
+    /// their own columns, which range check them.
+    pub(crate) fn constraints(
+        limbs: [[Expression<F>; 4]; 4],
+        carry_lo: Expression<F>,
+        carry_hi: Expression<F>,
+    ) -> (
+        [(&'static str, Expression<F>, Expression<F>); 2],
+        Expression<F>,
+    ) {
+        let [a_limbs, b_limbs, c_limbs, d_limbs] = limbs;
+        let lo_hi = |limbs: &[Expression<F>; 4]| {
+            (
+                limbs[0].clone() + limbs[1].clone() * pow_of_two_expr(64),
+                limbs[2].clone() + limbs[3].clone() * pow_of_two_expr(64),
+            )
+        };
+        let (c_lo, c_hi) = lo_hi(&c_limbs);
+        let (d_lo, d_hi) = lo_hi(&d_limbs);
+
+        // t[k] is the sum of the products of the limbs a[i] ⋅ b[j] with
+        // i + j == k, which contributes to the bits from 64 ⋅ k
+        let t = [0, 1, 2, 3, 4, 5, 6].map(|k| {
+            (0..4)
+                .filter(|i| k >= *i && k - *i < 4)
+                .fold(0.expr(), |acc, i| {
+                    acc + a_limbs[i].clone() * b_limbs[k - i].clone()
+                })
+        });
+
+        let constraints = [
+            (
+                "a ⋅ b + c == d + carry_lo ⋅ 2^128 on the low 128 bits",
+                t[0].clone() + t[1].clone() * pow_of_two_expr(64) + c_lo,
+                d_lo + carry_lo.clone() * pow_of_two_expr(128),
+            ),
+            (
+                "a ⋅ b + c == d + carry_hi ⋅ 2^128 on the high 128 bits",
+                t[2].clone() + t[3].clone() * pow_of_two_expr(64) + c_hi + carry_lo,
+                d_hi + carry_hi.clone() * pow_of_two_expr(128),
+            ),
+        ];
+        let overflow = carry_hi
+            + t[4].clone()
+            + t[5].clone() * pow_of_two_expr(64)
+            + t[6].clone() * pow_of_two_expr(128);
+
+        (constraints, overflow)
+    }
+
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
-        a: Word,
-        b: Word,
-        product: Word,
+        words: [Word; 4],
     ) -> Result<(), Error> {
-        self.assign_witness(region, offset, &a, &b, &product)?;
-        self.a.assign(region, offset, Some(a.to_le_bytes()))?;
-        self.b.assign(region, offset, Some(b.to_le_bytes()))?;
-        self.product
-            .assign(region, offset, Some(product.to_le_bytes()))?;
+        for (cells, bytes) in [&self.carry_lo, &self.carry_hi]
+            .iter()
+            .zip(Self::carries(words)?)
+        {
+            for (cell, byte) in cells.iter().zip(bytes) {
+                cell.assign(region, offset, Some(F::from(byte as u64)))?;
+            }
+        }
+
         Ok(())
     }
 
-    pub(crate) fn product(&self) -> &util::Word<F> {
-        &self.product
-    }
+    /// Returns the little-endian bytes of the carries of the low and high 128
+    /// bits of `a ⋅ b + c`, whose low 256 bits are `d`, as constrained by
+    /// `constraints`.  Returns an error if they don't fit in 9 bytes.
+    pub(crate) fn carries(words: [Word; 4]) -> Result<[[u8; 9]; 2], Error> {
+        let [a, b, c, d] = words;
 
-    //assign t0 ~ t3 and v0, v1
-    fn assign_witness(
-        &self,
-        region: &mut Region<'_, F>,
-        offset: usize,
-        wa: &Word,
-        wb: &Word,
-        wc: &Word,
-    ) -> Result<(), Error> {
-        use num::BigUint;
-
-        let a = BigUint::from_bytes_le(&wa.to_le_bytes());
-        let b = BigUint::from_bytes_le(&wb.to_le_bytes());
-        let c = BigUint::from_bytes_le(&wc.to_le_bytes());
-        let constant_64 = BigUint::from(1u128 << 64);
-        let constant_128 = constant_64.clone() * constant_64.clone();
-        let a_limbs = a.to_u64_digits();
-        let b_limbs = b.to_u64_digits();
-        let c_limbs = c.to_u64_digits();
-        let mut t_digits = vec![];
-        for total_idx in 0..4 {
-            let mut rhs_sum = BigUint::from(0u128);
-            for a_id in 0..=total_idx {
-                let (a_idx, b_idx) = (a_id as usize, (total_idx - a_id) as usize);
-                let tmp_a = if a_limbs.len() > a_idx {
-                    BigUint::from(a_limbs[a_idx])
-                } else {
-                    BigUint::from(0u128)
-                };
-                let tmp_b = if b_limbs.len() > b_idx {
-                    BigUint::from(b_limbs[b_idx])
-                } else {
-                    BigUint::from(0u128)
-                };
-                rhs_sum = rhs_sum.clone() + tmp_a * tmp_b;
-            }
-            t_digits.push(rhs_sum);
-        }
+        let a_limbs = a.0;
+        let b_limbs = b.0;
+        let (c_lo, c_hi) = word::Word::from(c).to_lo_hi();
+        let (d_lo, d_hi) = word::Word::from(d).to_lo_hi();
 
-        let mut c_now = vec![];
-        for idx in 0..4 {
-            c_now.push(if c_limbs.len() > idx {
-                BigUint::from(c_limbs[idx])
-            } else {
-                BigUint::from(0u128)
-            })
+        let t = |k: usize| {
+            (0..4)
+                .filter(|i| k >= *i && k - *i < 4)
+                .fold(BigUint::from(0u64), |acc, i| {
+                    acc + BigUint::from(a_limbs[i]) * BigUint::from(b_limbs[k - i])
+                })
+        };
+        let to_biguint = |word: Word| BigUint::from_bytes_le(&word.to_le_bytes());
+        let pow_64 = BigUint::from(1u128 << 64);
+        let pow_128 = &pow_64 * &pow_64;
+
+        let carry_lo = (t(0) + t(1) * &pow_64 + to_biguint(c_lo) - to_biguint(d_lo)) / &pow_128;
+        let carry_hi =
+            (t(2) + t(3) * &pow_64 + to_biguint(c_hi) + &carry_lo - to_biguint(d_hi)) / &pow_128;
+
+        let mut carries = [[0u8; 9]; 2];
+        for (bytes, carry) in carries.iter_mut().zip([carry_lo, carry_hi]) {
+            let carry = carry.to_bytes_le();
+            if carry.len() > 9 {
+                return Err(Error::Synthesis);
+            }
+            bytes[..carry.len()].copy_from_slice(&carry);
         }
-        let v0 = (constant_64.clone() * &t_digits[1] + &t_digits[0]
-            - &c_now[0]
-            - constant_64.clone() * &c_now[1])
-            / &constant_128;
-        let v1 = (constant_64.clone() * &t_digits[3] + &v0 + &t_digits[2]
-            - &c_now[2]
-            - constant_64 * &c_now[3])
-            / &constant_128;
-
-        v0.to_bytes_le()
-            .into_iter()
-            .zip(self.v0.iter())
-            .try_for_each(|(bt, assignee)| -> Result<(), Error> {
-                assignee.assign(region, offset, Some(F::from(bt as u64)))?;
-                Ok(())
-            })?;
-
-        v1.to_bytes_le()
-            .into_iter()
-            .zip(self.v1.iter())
-            .try_for_each(|(bt, assignee)| -> Result<(), Error> {
-                assignee.assign(region, offset, Some(F::from(bt as u64)))?;
-                Ok(())
-            })?;
+        Ok(carries)
+    }
 
-        Ok(())
+    /// Returns the part of `a ⋅ b + c` above 2^256, which is 0 iff the
+    /// operation doesn't overflow.
+    pub(crate) fn overflow(&self) -> Expression<F> {
+        self.overflow.clone()
     }
 }
 
@@ -412,11 +379,8 @@ impl<F: FieldExt> MulWordByU64Gadget<F> {
             carry_lo: cb.query_bytes(),
         };
 
-        let multiplicand_lo = from_bytes::expr(&gadget.multiplicand.cells[..16]);
-        let multiplicand_hi = from_bytes::expr(&gadget.multiplicand.cells[16..]);
-
-        let product_lo = from_bytes::expr(&gadget.product.cells[..16]);
-        let product_hi = from_bytes::expr(&gadget.product.cells[16..]);
+        let (multiplicand_lo, multiplicand_hi) = gadget.multiplicand.to_word().to_lo_hi();
+        let (product_lo, product_hi) = gadget.product.to_word().to_lo_hi();
 
         let carry_lo = from_bytes::expr(&gadget.carry_lo[..8]);
 
@@ -437,7 +401,7 @@ impl<F: FieldExt> MulWordByU64Gadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         multiplicand: Word,
         multiplier: u64,
@@ -448,8 +412,8 @@ impl<F: FieldExt> MulWordByU64Gadget<F> {
         self.product
             .assign(region, offset, Some(product.to_le_bytes()))?;
 
-        let (multiplicand_lo, _) = split_u256(&multiplicand);
-        let (product_lo, _) = split_u256(&product);
+        let multiplicand_lo = *word::Word::from(multiplicand).lo();
+        let product_lo = *word::Word::from(product).lo();
 
         let carry_lo = (multiplicand_lo * multiplier - product_lo) >> 128;
         for (cell, byte) in self.carry_lo.iter().zip(
@@ -493,7 +457,7 @@ impl<F: Field, const N_BYTES: usize> RangeCheckGadget<F, N_BYTES> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         value: F,
     ) -> Result<(), Error> {
@@ -548,7 +512,7 @@ impl<F: Field, const N_BYTES: usize> LtGadget<F, N_BYTES> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         lhs: F,
         rhs: F,
@@ -602,7 +566,7 @@ impl<F: Field, const N_BYTES: usize> ComparisonGadget<F, N_BYTES> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         lhs: F,
         rhs: F,
@@ -617,56 +581,6 @@ impl<F: Field, const N_BYTES: usize> ComparisonGadget<F, N_BYTES> {
     }
 }
 
-/// Returns (is_a, is_b):
-/// - `is_a` is `1` when `value == a`, else `0`
-/// - `is_b` is `1` when `value == b`, else `0`
-/// `value` is required to be either `a` or `b`.
-/// The benefit of this gadget over `IsEqualGadget` is that the
-/// expression returned is a single value which will make
-/// future expressions depending on this result more efficient.
-#[derive(Clone, Debug)]
-pub struct PairSelectGadget<F> {
-    is_a: Cell<F>,
-    is_b: Expression<F>,
-}
-
-impl<F: FieldExt> PairSelectGadget<F> {
-    pub(crate) fn construct(
-        cb: &mut ConstraintBuilder<F>,
-        value: Expression<F>,
-        a: Expression<F>,
-        b: Expression<F>,
-    ) -> Self {
-        let is_a = cb.query_bool();
-        let is_b = 1.expr() - is_a.expr();
-
-        // Force `is_a` to be `0` when `value != a`
-        cb.add_constraint("is_a ⋅ (value - a)", is_a.expr() * (value.clone() - a));
-        // Force `1 - is_a` to be `0` when `value != b`
-        cb.add_constraint("(1 - is_a) ⋅ (value - b)", is_b.clone() * (value - b));
-
-        Self { is_a, is_b }
-    }
-
-    pub(crate) fn expr(&self) -> (Expression<F>, Expression<F>) {
-        (self.is_a.expr(), self.is_b.clone())
-    }
-
-    pub(crate) fn assign(
-        &self,
-        region: &mut Region<'_, F>,
-        offset: usize,
-        value: F,
-        a: F,
-        _b: F,
-    ) -> Result<(F, F), Error> {
-        let is_a = if value == a { F::one() } else { F::zero() };
-        self.is_a.assign(region, offset, Some(is_a))?;
-
-        Ok((is_a, F::one() - is_a))
-    }
-}
-
 /// Returns (quotient: numerator/denominator, remainder: numerator%denominator),
 /// with `numerator` an expression and `denominator` a constant.
 /// Input requirements:
@@ -722,7 +636,7 @@ impl<F: Field, const N_BYTES: usize> ConstantDivisionGadget<F, N_BYTES> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         numerator: u128,
     ) -> Result<(u128, u128), Error> {
@@ -775,7 +689,7 @@ impl<F: Field, const N_BYTES: usize> MinMaxGadget<F, N_BYTES> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         lhs: F,
         rhs: F,
@@ -789,6 +703,79 @@ impl<F: Field, const N_BYTES: usize> MinMaxGadget<F, N_BYTES> {
     }
 }
 
+/// Returns the number of bytes of a word without its leading zero bytes, which
+/// is 0 for the word 0.
+#[derive(Clone, Debug)]
+pub(crate) struct ByteSizeGadget<F> {
+    // `most_significant_byte[i]` is 1 when the byte size is `i`
+    most_significant_byte: [Cell<F>; 33],
+    // Inverse of the most significant non-zero byte
+    most_significant_byte_inverse: Cell<F>,
+}
+
+impl<F: Field> ByteSizeGadget<F> {
+    pub(crate) fn construct(cb: &mut ConstraintBuilder<F>, word: &util::Word<F>) -> Self {
+        let most_significant_byte = [(); 33].map(|_| cb.query_bool());
+        let most_significant_byte_inverse = cb.query_cell();
+
+        cb.require_equal(
+            "exactly one byte size is selected",
+            sum::expr(&most_significant_byte),
+            1.expr(),
+        );
+        for (size, is_size) in most_significant_byte.iter().enumerate() {
+            cb.condition(is_size.expr(), |cb| {
+                // The bytes above the most significant one are 0
+                for byte in word.cells[size..].iter() {
+                    cb.require_zero("leading byte is zero", byte.expr());
+                }
+                // and the most significant one isn't
+                if size > 0 {
+                    cb.require_equal(
+                        "most significant byte is non-zero",
+                        word.cells[size - 1].expr() * most_significant_byte_inverse.expr(),
+                        1.expr(),
+                    );
+                }
+            });
+        }
+
+        Self {
+            most_significant_byte,
+            most_significant_byte_inverse,
+        }
+    }
+
+    pub(crate) fn byte_size(&self) -> Expression<F> {
+        self.most_significant_byte
+            .iter()
+            .enumerate()
+            .fold(0.expr(), |acc, (size, is_size)| {
+                acc + is_size.expr() * size.expr()
+            })
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut StepRegion<F>,
+        offset: usize,
+        word: Word,
+    ) -> Result<usize, Error> {
+        let bytes = word.to_le_bytes();
+        let size = (word.bits() + 7) / 8;
+        for (idx, is_size) in self.most_significant_byte.iter().enumerate() {
+            is_size.assign(region, offset, Some(F::from((idx == size) as u64)))?;
+        }
+        let inverse = match size {
+            0 => F::zero(),
+            _ => F::from(bytes[size - 1] as u64).invert().unwrap(),
+        };
+        self.most_significant_byte_inverse
+            .assign(region, offset, Some(inverse))?;
+        Ok(size)
+    }
+}
+
 // This function generates a Lagrange polynomial in the range [start, end) which
 // will be evaluated to 1 when `exp == value`, otherwise 0
 pub(crate) fn generate_lagrange_base_polynomial<
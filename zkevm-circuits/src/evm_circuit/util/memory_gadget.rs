@@ -5,16 +5,18 @@ use crate::{
             constraint_builder::ConstraintBuilder,
             from_bytes,
             math_gadget::{ConstantDivisionGadget, IsZeroGadget, MinMaxGadget, RangeCheckGadget},
-            select, sum, Cell, MemoryAddress, Word,
+            select, sum, Cell, MemoryAddress, StepRegion, Word,
         },
     },
     util::Expr,
 };
 use array_init::array_init;
-use eth_types::{evm_types::GasCost, Field, ToLittleEndian, U256};
+use eth_types::{
+    evm_types::{gas::memory_expansion_gas_cost, GasCost},
+    Field, ToLittleEndian, U256,
+};
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::Region,
     plonk::{Error, Expression},
 };
 use std::convert::TryInto;
@@ -94,7 +96,7 @@ impl<F: FieldExt> MemoryAddressGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         memory_offset: U256,
         memory_length: U256,
@@ -175,7 +177,7 @@ impl<F: Field> MemoryWordSizeGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         address: u64,
     ) -> Result<u64, Error> {
@@ -279,7 +281,7 @@ impl<F: Field, const N: usize, const N_BYTES_MEMORY_WORD_SIZE: usize>
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         curr_memory_word_size: u64,
         addresses: [u64; N],
@@ -309,21 +311,19 @@ impl<F: Field, const N: usize, const N_BYTES_MEMORY_WORD_SIZE: usize>
         }
 
         // Calculate the quad gas cost for the memory size
-        let (curr_quad_memory_cost, _) = self.curr_quad_memory_cost.assign(
+        self.curr_quad_memory_cost.assign(
             region,
             offset,
             (curr_memory_word_size as u128) * (curr_memory_word_size as u128),
         )?;
-        let (next_quad_memory_cost, _) = self.next_quad_memory_cost.assign(
+        self.next_quad_memory_cost.assign(
             region,
             offset,
             (next_memory_word_size as u128) * (next_memory_word_size as u128),
         )?;
 
         // Calculate the gas cost for the expansian
-        let memory_cost = GasCost::MEMORY_EXPANSION_LINEAR_COEFF.as_u64()
-            * (next_memory_word_size - curr_memory_word_size as u64)
-            + (next_quad_memory_cost - curr_quad_memory_cost) as u64;
+        let memory_cost = memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size);
 
         // Return the new memory size and the memory expansion gas cost
         Ok((next_memory_word_size, memory_cost))
@@ -376,7 +376,7 @@ impl<F: Field> MemoryCopierGasGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         num_bytes: u64,
         memory_expansion_gas_cost: u64,
@@ -487,7 +487,7 @@ impl<F: Field, const MAX_BYTES: usize, const ADDR_SIZE_IN_BYTES: usize>
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
+        region: &mut StepRegion<F>,
         offset: usize,
         addr_start: u64,
         addr_end: u64,
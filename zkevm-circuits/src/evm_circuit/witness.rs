@@ -3,22 +3,45 @@ use crate::evm_circuit::{
     param::{N_BYTES_WORD, STACK_CAPACITY},
     step::ExecutionState,
     table::{
-        AccountFieldTag, BlockContextFieldTag, CallContextFieldTag, RwTableTag, TxContextFieldTag,
+        AccountFieldTag, BlockContextFieldTag, CallContextFieldTag, CopyDataType, RwTableTag,
+        TxContextFieldTag, TxLogFieldTag, TxReceiptFieldTag,
     },
     util::RandomLinearCombination,
 };
-use bus_mapping::circuit_input_builder::{self, ExecError, OogError};
-use bus_mapping::operation::{self, AccountField, CallContextField};
-use eth_types::evm_types::OpcodeId;
-use eth_types::{Address, Field, ToLittleEndian, ToScalar, ToWord, Word};
+use crate::gadget::rlc;
+use crate::mpt_table::{MptProofType, MptUpdateRow};
+use bus_mapping::circuit_input_builder::{self, CircuitsParams, ExecError, OogError};
+use bus_mapping::operation::{self, AccountField, CallContextField, TxLogField, TxReceiptField};
+use eth_types::evm_types::{gas::call_data_gas_cost, OpcodeId};
+use eth_types::{
+    keccak256, AccessList, Address, ChainConfig, Field, ToBigEndian, ToLittleEndian, ToScalar,
+    ToWord, Word,
+};
 use halo2_proofs::arithmetic::{BaseExt, FieldExt};
 use pairing::bn256::Fr as Fp;
-use sha3::{Digest, Keccak256};
-use std::{collections::HashMap, convert::TryInto, iter};
+use rayon::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::{TryFrom, TryInto},
+    io, iter,
+    sync::Arc,
+};
 
-#[derive(Debug, Default, Clone)]
+mod chunk;
+mod file;
+
+pub(crate) use chunk::carried_rw_factor;
+pub use chunk::{
+    carried_rws_fingerprint, check_chunks, Chunk, ChunkError, ChunkState, CHUNK_STATE_SIZE,
+};
+pub use file::{WitnessFileError, WITNESS_FILE_VERSION};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(bound = "F: Field")]
 pub struct Block<F> {
     /// The randomness for random linear combination
+    #[serde(with = "crate::util::serde_field")]
     pub randomness: F,
     /// Transactions in the block
     pub txs: Vec<Transaction>,
@@ -28,9 +51,198 @@ pub struct Block<F> {
     pub bytecodes: Vec<Bytecode>,
     /// The block context
     pub context: BlockContext,
+    /// Config of the chain of the block, whose hardfork the EVM circuit must
+    /// be configured with
+    pub chain_config: ChainConfig,
+    /// Inputs of all the keccak hashes done in the block
+    pub keccak_inputs: Vec<Vec<u8>>,
+    /// Copies of bytes done in the block
+    pub copy_events: Vec<CopyEvent>,
+    /// Exponentiations done in the block
+    pub exp_events: Vec<ExpEvent>,
+    /// Capacities of the circuits the block is proven with
+    pub circuits_params: CircuitsParams,
+    /// Position of the block in the block it's a chunk of, and the states it
+    /// begins and ends with, see [`Block::chunks`]
+    #[serde(default)]
+    pub chunk: Option<Chunk>,
 }
 
-#[derive(Debug, Default, Clone)]
+/// Returns the keccak table row of `input`, which consists of the random
+/// linear combination of the input bytes, the input length and the random
+/// linear combination of the output hash as an EVM word.
+pub fn keccak_table_assignments<F: Field>(input: &[u8], randomness: F) -> [F; 3] {
+    let input_rlc = rlc::value(input, randomness);
+    let output = Word::from_big_endian(&keccak256(input));
+    [
+        input_rlc,
+        F::from(input.len() as u64),
+        RandomLinearCombination::random_linear_combine(output.to_le_bytes(), randomness),
+    ]
+}
+
+/// Returns the keccak table rows of `inputs`, see [`keccak_table_assignments`],
+/// which are hashed in parallel.  They are computed before the table is
+/// assigned, since the floor planner may synthesize a region more than once.
+pub fn keccak_table_rows<F: Field>(inputs: &[Vec<u8>], randomness: F) -> Vec<[F; 3]> {
+    inputs
+        .par_iter()
+        .map(|input| keccak_table_assignments(input, randomness))
+        .collect()
+}
+
+/// Returns `rlp([sender, nonce])`, the keccak input of the address of a
+/// contract created by CREATE or by a contract creation transaction.
+pub fn create_address_keccak_input(sender: Address, nonce: u64) -> Vec<u8> {
+    let nonce_bytes = nonce.to_be_bytes();
+    let nonce_bytes = &nonce_bytes[nonce.leading_zeros() as usize / 8..];
+    let nonce_rlp = match nonce_bytes {
+        [byte] if *byte < 0x80 => vec![*byte],
+        _ => [vec![0x80 + nonce_bytes.len() as u8], nonce_bytes.to_vec()].concat(),
+    };
+    [
+        vec![0xc0 + 21 + nonce_rlp.len() as u8, 0x80 + 20],
+        sender.as_bytes().to_vec(),
+        nonce_rlp,
+    ]
+    .concat()
+}
+
+/// Returns `0xff ‖ sender ‖ salt ‖ code_hash`, the keccak input of the address
+/// of a contract created by CREATE2.
+pub fn create2_address_keccak_input(sender: Address, salt: Word, code_hash: Word) -> Vec<u8> {
+    [
+        vec![0xff],
+        sender.as_bytes().to_vec(),
+        salt.to_be_bytes().to_vec(),
+        code_hash.to_be_bytes().to_vec(),
+    ]
+    .concat()
+}
+
+/// Returns the RLP prefix of a string or a list of `len` bytes, where `offset`
+/// is 0x80 for a string and 0xc0 for a list.
+pub(crate) fn rlp_prefix(offset: u8, len: usize) -> Vec<u8> {
+    if len < 56 {
+        vec![offset + len as u8]
+    } else {
+        let len_bytes = len.to_be_bytes();
+        let len_bytes = &len_bytes[len.leading_zeros() as usize / 8..];
+        [
+            vec![offset + 55 + len_bytes.len() as u8],
+            len_bytes.to_vec(),
+        ]
+        .concat()
+    }
+}
+
+// Returns the RLP encoding of a string.
+fn rlp_bytes(bytes: &[u8]) -> Vec<u8> {
+    match bytes {
+        [byte] if *byte < 0x80 => vec![*byte],
+        _ => [rlp_prefix(0x80, bytes.len()), bytes.to_vec()].concat(),
+    }
+}
+
+// Returns the RLP encoding of a scalar, which has no leading zero bytes.
+fn rlp_word(word: Word) -> Vec<u8> {
+    let bytes = word.to_be_bytes();
+    rlp_bytes(&bytes[word.leading_zeros() as usize / 8..])
+}
+
+// Returns the RLP encoding of a list of already encoded items.
+fn rlp_list(items: Vec<u8>) -> Vec<u8> {
+    [rlp_prefix(0xc0, items.len()), items].concat()
+}
+
+// Returns the RLP encoding of an access list, which is a list of `[address,
+// [storage_key, ...]]`.
+fn rlp_access_list(access_list: &AccessList) -> Vec<u8> {
+    rlp_list(
+        access_list
+            .0
+            .iter()
+            .map(|item| {
+                let storage_keys = item
+                    .storage_keys
+                    .iter()
+                    .map(|key| rlp_bytes(key.as_bytes()))
+                    .collect::<Vec<_>>()
+                    .concat();
+                rlp_list([rlp_bytes(item.address.as_bytes()), rlp_list(storage_keys)].concat())
+            })
+            .collect::<Vec<_>>()
+            .concat(),
+    )
+}
+
+/// Returns the RLP encodings of the fields of a transaction signed by its
+/// sender, which are
+/// - `[nonce, gas_price, gas, to, value, data, chain_id, 0, 0]` for a legacy
+///   transaction with EIP-155,
+/// - `[nonce, gas_price, gas, to, value, data]` for a legacy transaction
+///   without EIP-155,
+/// - `[chain_id, nonce, gas_price, gas, to, value, data, access_list]` for an
+///   EIP-2930 transaction,
+/// - `[chain_id, nonce, max_priority_fee_per_gas, max_fee_per_gas, gas, to,
+///   value, data, access_list]` for an EIP-1559 transaction.
+pub(crate) fn tx_sign_rlp_fields(tx: &Transaction, chain_id: Word) -> Vec<Vec<u8>> {
+    let to = if tx.is_create {
+        Vec::new()
+    } else {
+        tx.callee_address.as_bytes().to_vec()
+    };
+    let fees = match tx.tx_type {
+        2 => vec![
+            rlp_word(tx.max_priority_fee_per_gas),
+            rlp_word(tx.max_fee_per_gas),
+        ],
+        _ => vec![rlp_word(tx.gas_price)],
+    };
+    let fields = [
+        vec![rlp_word(tx.nonce.into())],
+        fees,
+        vec![
+            rlp_word(tx.gas.into()),
+            rlp_bytes(&to),
+            rlp_word(tx.value),
+            rlp_bytes(&tx.call_data),
+        ],
+    ]
+    .concat();
+
+    match tx.tx_type {
+        0 if tx.is_eip155() => [
+            fields,
+            vec![
+                rlp_word(chain_id),
+                rlp_word(Word::zero()),
+                rlp_word(Word::zero()),
+            ],
+        ]
+        .concat(),
+        0 => fields,
+        _ => [
+            vec![rlp_word(chain_id)],
+            fields,
+            vec![rlp_access_list(&tx.access_list)],
+        ]
+        .concat(),
+    }
+}
+
+/// Returns the keccak input of the message hash signed by the sender of a
+/// transaction, which is the RLP encoding of the fields of
+/// [`tx_sign_rlp_fields`], prefixed by the type of a typed transaction.
+pub fn tx_sign_keccak_input(tx: &Transaction, chain_id: Word) -> Vec<u8> {
+    let tx_type = match tx.tx_type {
+        0 => Vec::new(),
+        tx_type => vec![tx_type as u8],
+    };
+    [tx_type, rlp_list(tx_sign_rlp_fields(tx, chain_id).concat())].concat()
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct BlockContext {
     /// The address of the miner for the block
     pub coinbase: Address,
@@ -46,6 +258,10 @@ pub struct BlockContext {
     pub base_fee: Word,
     /// The hash of previous blocks
     pub history_hashes: Vec<Word>,
+    /// The chain id
+    pub chain_id: Word,
+    /// The root of the withdrawals trie, zero before Shanghai
+    pub withdrawals_root: Word,
 }
 
 impl BlockContext {
@@ -88,6 +304,22 @@ impl BlockContext {
                         randomness,
                     ),
                 ],
+                [
+                    F::from(BlockContextFieldTag::ChainId as u64),
+                    F::zero(),
+                    RandomLinearCombination::random_linear_combine(
+                        self.chain_id.to_le_bytes(),
+                        randomness,
+                    ),
+                ],
+                [
+                    F::from(BlockContextFieldTag::WithdrawalsRoot as u64),
+                    F::zero(),
+                    RandomLinearCombination::random_linear_combine(
+                        self.withdrawals_root.to_le_bytes(),
+                        randomness,
+                    ),
+                ],
             ],
             self.history_hashes
                 .iter()
@@ -108,7 +340,7 @@ impl BlockContext {
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     /// The transaction identifier in the block
     pub id: usize,
@@ -132,6 +364,21 @@ pub struct Transaction {
     pub call_data_length: usize,
     /// The gas cost for transaction call data
     pub call_data_gas_cost: u64,
+    /// The type of the envelope, which is 0 for a legacy transaction, 1 for
+    /// EIP-2930 and 2 for EIP-1559
+    pub tx_type: u64,
+    /// The access list of a typed transaction
+    pub access_list: AccessList,
+    /// The max priority fee per gas of an EIP-1559 transaction
+    pub max_priority_fee_per_gas: Word,
+    /// The max fee per gas of an EIP-1559 transaction
+    pub max_fee_per_gas: Word,
+    /// The signature recovery value
+    pub v: u64,
+    /// The signature r
+    pub r: Word,
+    /// The signature s
+    pub s: Word,
     /// The calls made in the transaction
     pub calls: Vec<Call>,
     /// The steps executioned in the transaction
@@ -139,6 +386,18 @@ pub struct Transaction {
 }
 
 impl Transaction {
+    /// Whether the transaction is a legacy one signed with EIP-155, whose `v`
+    /// is `35 + 2 * chain_id + recovery_id` instead of `27 + recovery_id`.
+    pub fn is_eip155(&self) -> bool {
+        self.tx_type == 0 && self.v >= 35
+    }
+
+    /// Whether the chain id is signed with the transaction, which is the case
+    /// for typed transactions and legacy ones with EIP-155.
+    pub fn signs_chain_id(&self) -> bool {
+        self.tx_type != 0 || self.is_eip155()
+    }
+
     pub fn table_assignments<F: Field>(&self, randomness: F) -> Vec<[F; 4]> {
         [
             vec![
@@ -220,7 +479,7 @@ impl Transaction {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CodeSource {
     Account(Word),
 }
@@ -231,7 +490,7 @@ impl Default for CodeSource {
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Call {
     /// The unique identifier of call in the whole proof, using the
     /// `rw_counter` at the call step.
@@ -271,7 +530,7 @@ pub struct Call {
     pub is_static: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum StepAuxiliaryData {
     CopyToMemory {
         src_addr: u64,
@@ -283,7 +542,7 @@ pub enum StepAuxiliaryData {
     },
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ExecStep {
     /// The index in the Transaction calls
     pub call_index: usize,
@@ -321,7 +580,7 @@ impl ExecStep {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bytecode {
     pub hash: Word,
     pub bytes: Vec<u8>,
@@ -329,7 +588,7 @@ pub struct Bytecode {
 
 impl Bytecode {
     pub fn new(bytes: Vec<u8>) -> Self {
-        let hash = Word::from_big_endian(Keccak256::digest(&bytes).as_slice());
+        let hash = Word::from_big_endian(&keccak256(&bytes));
         Self { hash, bytes }
     }
 
@@ -386,7 +645,259 @@ impl Bytecode {
     }
 }
 
+/// A copy of bytes from a source to a destination, proven by the copy circuit
+/// and looked up in the copy table by the EVM circuit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CopyEvent {
+    /// Type of the source, which is memory, bytecode or tx call data
+    pub src_type: CopyDataType,
+    /// Call id for memory, code hash for bytecode or tx id for tx call data
+    pub src_id: Word,
+    /// Address of the first byte to read
+    pub src_addr: u64,
+    /// The bytes at or after this address are read as 0
+    pub src_addr_end: u64,
+    /// Type of the destination, which is memory, bytecode or a tx log
+    pub dst_type: CopyDataType,
+    /// Call id for memory, code hash for bytecode or tx id for a tx log
+    pub dst_id: Word,
+    /// Address of the first byte to write
+    pub dst_addr: u64,
+    /// Id of the log in the tx, only used when the destination is a tx log
+    pub log_id: u64,
+    /// Counter of the first read-write access
+    pub rw_counter_start: usize,
+    /// Bytes copied, with whether each one is code when read from bytecode
+    pub bytes: Vec<(u8, bool)>,
+}
+
+/// A row of the copy circuit, each byte copied has a read row followed by a
+/// write row.  Padding rows have no tag.
 #[derive(Debug, Default, Clone)]
+pub struct CopyRow<F> {
+    pub is_first: bool,
+    pub is_last: bool,
+    pub id: F,
+    pub tag: Option<CopyDataType>,
+    pub addr: u64,
+    pub src_addr_end: u64,
+    pub log_id: u64,
+    pub bytes_left: u64,
+    pub rlc_acc: F,
+    pub rw_counter: u64,
+    pub rw_inc_left: u64,
+    pub value: u8,
+    pub value_acc: F,
+    pub is_pad: bool,
+    pub is_code: bool,
+    pub is_reads_first: bool,
+}
+
+impl CopyEvent {
+    // Returns whether the byte at `idx` is at or after `src_addr_end`, which is
+    // read as 0.
+    fn is_pad(&self, idx: usize) -> bool {
+        self.src_addr + idx as u64 >= self.src_addr_end
+    }
+
+    // Returns whether the byte at `idx` is read from the rw table.
+    fn is_rw_read(&self, idx: usize) -> bool {
+        self.src_type == CopyDataType::Memory && !self.is_pad(idx)
+    }
+
+    // Returns whether the bytes are written to the rw table, rather than to
+    // the bytecode of a creation.
+    fn is_rw_write(&self) -> bool {
+        matches!(self.dst_type, CopyDataType::Memory | CopyDataType::TxLog)
+    }
+
+    /// Returns whether all the bytes are read before the first one is
+    /// written, which is the case of the copies from memory to memory, like
+    /// MCOPY's, so that the source and the destination can overlap
+    /// (EIP-5656).
+    pub fn is_reads_first(&self) -> bool {
+        self.src_type == CopyDataType::Memory && self.dst_type == CopyDataType::Memory
+    }
+
+    /// Returns the number of read-write accesses of the copy, a read of each
+    /// byte from memory and a write of each byte to memory or a tx log.  The
+    /// reads come first when [`Self::is_reads_first`], and are interleaved
+    /// with the writes otherwise.
+    pub fn rw_inc(&self) -> usize {
+        (0..self.bytes.len())
+            .filter(|idx| self.is_rw_read(*idx))
+            .count()
+            + if self.is_rw_write() {
+                self.bytes.len()
+            } else {
+                0
+            }
+    }
+
+    fn id<F: Field>(tag: CopyDataType, id: Word, randomness: F) -> F {
+        match tag {
+            CopyDataType::Bytecode => {
+                RandomLinearCombination::random_linear_combine(id.to_le_bytes(), randomness)
+            }
+            _ => F::from(id.low_u64()),
+        }
+    }
+
+    /// Returns the `(tx_id, log_id, length, data_rlc)` of the data of the tx
+    /// log written by the copy, which is its row in the log data table of the
+    /// copy circuit, or `None` if its destination isn't a tx log.
+    pub fn log_data_row<F: Field>(&self, randomness: F) -> Option<[F; 4]> {
+        (self.dst_type == CopyDataType::TxLog).then(|| {
+            [
+                F::from(self.dst_id.low_u64()),
+                F::from(self.log_id),
+                F::from(self.bytes.len() as u64),
+                rlc::value(self.bytes.iter().map(|(byte, _)| byte), randomness),
+            ]
+        })
+    }
+
+    /// Returns the rows of the copy in the copy circuit.
+    pub fn rows<F: Field>(&self, randomness: F) -> Vec<CopyRow<F>> {
+        let rlc_acc = rlc::value(self.bytes.iter().map(|(byte, _)| byte), randomness);
+        let src_id = Self::id(self.src_type, self.src_id, randomness);
+        let dst_id = Self::id(self.dst_type, self.dst_id, randomness);
+
+        let is_reads_first = self.is_reads_first();
+        let rw_counter_end = (self.rw_counter_start + self.rw_inc()) as u64;
+        let mut rw_counter = self.rw_counter_start as u64;
+        let mut value_acc = F::zero();
+        let mut rows = Vec::with_capacity(2 * self.bytes.len());
+        for (idx, (byte, is_code)) in self.bytes.iter().enumerate() {
+            let bytes_left = (self.bytes.len() - idx) as u64;
+            if is_reads_first {
+                rw_counter = (self.rw_counter_start + idx) as u64;
+            }
+            value_acc = value_acc * randomness + F::from(*byte as u64);
+            rows.push(CopyRow {
+                is_first: idx == 0,
+                is_last: false,
+                id: src_id,
+                tag: Some(self.src_type),
+                addr: self.src_addr + idx as u64,
+                src_addr_end: self.src_addr_end,
+                log_id: 0,
+                bytes_left,
+                rlc_acc,
+                rw_counter,
+                rw_inc_left: rw_counter_end - rw_counter,
+                value: *byte,
+                value_acc,
+                is_pad: self.is_pad(idx),
+                is_code: *is_code,
+                is_reads_first,
+            });
+            if self.is_rw_read(idx) {
+                rw_counter += 1;
+            }
+            if is_reads_first {
+                rw_counter = (self.rw_counter_start + self.bytes.len() + idx) as u64;
+            }
+            rows.push(CopyRow {
+                is_first: false,
+                is_last: idx + 1 == self.bytes.len(),
+                id: dst_id,
+                tag: Some(self.dst_type),
+                addr: self.dst_addr + idx as u64,
+                src_addr_end: 0,
+                log_id: self.log_id,
+                bytes_left,
+                rlc_acc,
+                rw_counter,
+                rw_inc_left: rw_counter_end - rw_counter,
+                value: *byte,
+                value_acc,
+                is_pad: false,
+                is_code: self.dst_type == CopyDataType::Bytecode && *is_code,
+                is_reads_first,
+            });
+            if self.is_rw_write() {
+                rw_counter += 1;
+            }
+        }
+        rows
+    }
+}
+
+/// An exponentiation `base^exponent (mod 2^256)` done by the EXP opcode,
+/// proven by the exp circuit and looked up in the exp table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpEvent {
+    /// Base of the exponentiation
+    pub base: Word,
+    /// Exponent of the exponentiation
+    pub exponent: Word,
+    /// Result of the exponentiation, modulo 2^256
+    pub result: Word,
+}
+
+/// A step of an exponentiation, which processes a bit of the exponent from
+/// the most significant one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpStep {
+    /// Bit of the exponent
+    pub bit: bool,
+    /// Square of the result of the previous step
+    pub square: Word,
+    /// The square multiplied by the base when the bit is set
+    pub result: Word,
+}
+
+impl ExpEvent {
+    /// Returns the exponentiation of `base` by `exponent`.
+    pub fn new(base: Word, exponent: Word) -> Self {
+        let result = Self::steps_of(base, exponent)
+            .last()
+            .map_or(Word::one(), |step| step.result);
+        Self {
+            base,
+            exponent,
+            result,
+        }
+    }
+
+    fn steps_of(base: Word, exponent: Word) -> Vec<ExpStep> {
+        let mut result = Word::one();
+        (0..256)
+            .rev()
+            .map(|idx| {
+                let bit = exponent.bit(idx);
+                let square = result.overflowing_mul(result).0;
+                result = if bit {
+                    square.overflowing_mul(base).0
+                } else {
+                    square
+                };
+                ExpStep {
+                    bit,
+                    square,
+                    result,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the 256 steps of the square-and-multiply exponentiation, one
+    /// per bit of the exponent.
+    pub fn steps(&self) -> Vec<ExpStep> {
+        Self::steps_of(self.base, self.exponent)
+    }
+
+    /// Returns the exp table row of the exponentiation, the random linear
+    /// combinations of its base, exponent and result.
+    pub fn table_assignment<F: Field>(&self, randomness: F) -> [F; 3] {
+        [self.base, self.exponent, self.result].map(|word| {
+            RandomLinearCombination::random_linear_combine(word.to_le_bytes(), randomness)
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct RwMap(pub HashMap<RwTableTag, Vec<Rw>>);
 
 impl std::ops::Index<(RwTableTag, usize)> for RwMap {
@@ -398,6 +909,18 @@ impl std::ops::Index<(RwTableTag, usize)> for RwMap {
 }
 
 impl RwMap {
+    /// Returns the rows of the rw table, grouped by tag in the order of the
+    /// tags, so that the assignment of the table doesn't depend on the
+    /// iteration order of the map.
+    pub fn table_assignments<F: Field>(&self, randomness: F) -> Vec<RwRow<F>> {
+        let mut tags: Vec<_> = self.0.keys().copied().collect();
+        tags.sort_by_key(|tag| *tag as usize);
+        tags.iter()
+            .flat_map(|tag| self.0[tag].iter())
+            .map(|rw| rw.table_assignment(randomness))
+            .collect()
+    }
+
     /// These "sorted_xx" methods are used in state circuit
     pub fn sorted_memory_rw(&self) -> Vec<Rw> {
         let mut sorted = self.0[&RwTableTag::Memory].clone();
@@ -439,8 +962,197 @@ impl RwMap {
     }
 }
 
-#[derive(Clone, Debug)]
+/// A state change that has to be proven by the MPT circuit
+#[derive(Clone, Copy, Debug)]
+pub struct MptUpdate {
+    pub rw_counter: usize,
+    pub proof_type: MptProofType,
+    pub address: Address,
+    pub storage_key: Word,
+    pub value_prev: Word,
+    pub value: Word,
+    pub root_prev: Word,
+    pub root: Word,
+}
+
+impl MptUpdate {
+    pub fn table_assignment<F: Field>(&self, randomness: F) -> MptUpdateRow<F> {
+        let rlc = |value: &Word| {
+            RandomLinearCombination::random_linear_combine(value.to_le_bytes(), randomness)
+        };
+        // Values are encoded the same way as in the rw table
+        let to_scalar = |value: &Word| match self.proof_type {
+            MptProofType::NonceMod => value.to_scalar().unwrap(),
+            _ => rlc(value),
+        };
+        MptUpdateRow {
+            address: self.address.to_scalar().unwrap(),
+            storage_key: rlc(&self.storage_key),
+            proof_type: F::from(self.proof_type as u64),
+            value_prev: to_scalar(&self.value_prev),
+            value: to_scalar(&self.value),
+            root_prev: rlc(&self.root_prev),
+            root: rlc(&self.root),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct MptUpdates(pub Vec<MptUpdate>);
+
+impl MptUpdates {
+    /// Collect the state changes of `rw_map`, which are:
+    /// - all the storage writes, the first access of each (address,
+    ///   storage_key) proving its committed value, which is the one in the
+    ///   previous block, without changing it.
+    /// - all the account accesses, the first access of each (address,
+    ///   field_tag) reading its initial value from the trie, and the reads
+    ///   proving that it's unchanged.
+    ///
+    /// The updates are in the order of the rows of the state circuit, which
+    /// chains their state roots from row to row: by (address, storage_key)
+    /// then rw_counter for the storage, and by (address, field_tag) then
+    /// rw_counter for the accounts, after the storage.  The MPT circuit is not
+    /// available, so the state roots are mocked as the number of state changes
+    /// that changed a value so far, from the root 0 of the previous block.
+    pub fn mock_from(rw_map: &RwMap) -> Self {
+        let mut updates = Vec::new();
+        let mut storage_rws = rw_map
+            .0
+            .get(&RwTableTag::AccountStorage)
+            .cloned()
+            .unwrap_or_default();
+        storage_rws.sort_by_key(|rw| match rw {
+            Rw::AccountStorage {
+                rw_counter,
+                account_address,
+                storage_key,
+                ..
+            } => (*account_address, *storage_key, *rw_counter),
+            _ => panic!("invalid storage rw"),
+        });
+        for (idx, rw) in storage_rws.iter().enumerate() {
+            if let Rw::AccountStorage {
+                rw_counter,
+                is_write: true,
+                account_address,
+                storage_key,
+                value,
+                ..
+            } = rw
+            {
+                // The value before the first access is its committed value.
+                let value_prev = match idx.checked_sub(1).map(|idx| &storage_rws[idx]) {
+                    Some(Rw::AccountStorage {
+                        account_address: address_prev,
+                        storage_key: storage_key_prev,
+                        value: value_prev,
+                        ..
+                    }) if (address_prev, storage_key_prev) == (account_address, storage_key) => {
+                        *value_prev
+                    }
+                    _ => *value,
+                };
+                updates.push(MptUpdate {
+                    rw_counter: *rw_counter,
+                    proof_type: MptProofType::StorageMod,
+                    address: *account_address,
+                    storage_key: *storage_key,
+                    value_prev,
+                    value: *value,
+                    root_prev: Word::zero(),
+                    root: Word::zero(),
+                });
+            }
+        }
+
+        let mut account_rws = rw_map
+            .0
+            .get(&RwTableTag::Account)
+            .cloned()
+            .unwrap_or_default();
+        account_rws.sort_by_key(|rw| match rw {
+            Rw::Account {
+                rw_counter,
+                account_address,
+                field_tag,
+                ..
+            } => (*account_address, *field_tag as u64, *rw_counter),
+            _ => panic!("invalid account rw"),
+        });
+        for rw in account_rws.iter() {
+            if let Rw::Account {
+                rw_counter,
+                account_address,
+                field_tag,
+                value,
+                value_prev,
+                ..
+            } = rw
+            {
+                updates.push(MptUpdate {
+                    rw_counter: *rw_counter,
+                    proof_type: match field_tag {
+                        AccountFieldTag::Nonce => MptProofType::NonceMod,
+                        AccountFieldTag::Balance => MptProofType::BalanceMod,
+                        AccountFieldTag::CodeHash => MptProofType::CodeHashMod,
+                    },
+                    address: *account_address,
+                    storage_key: Word::zero(),
+                    value_prev: *value_prev,
+                    value: *value,
+                    root_prev: Word::zero(),
+                    root: Word::zero(),
+                });
+            }
+        }
+
+        let mut root = Word::zero();
+        for update in updates.iter_mut() {
+            update.root_prev = root;
+            if update.value != update.value_prev {
+                root = root + Word::one();
+            }
+            update.root = root;
+        }
+        Self(updates)
+    }
+
+    /// Returns the state roots before and after the updates, which are the
+    /// ones of the previous block and of the block.
+    pub fn state_roots(&self) -> (Word, Word) {
+        match (self.0.first(), self.0.last()) {
+            (Some(first), Some(last)) => (first.root_prev, last.root),
+            // The mocked root of the previous block
+            _ => (Word::zero(), Word::zero()),
+        }
+    }
+
+    /// Returns the state change of kind `proof_type` done at `rw_counter` to
+    /// (address, storage_key)
+    pub fn get(
+        &self,
+        rw_counter: usize,
+        proof_type: MptProofType,
+        address: Address,
+        storage_key: Word,
+    ) -> Option<&MptUpdate> {
+        self.0.iter().find(|update| {
+            (
+                update.rw_counter,
+                update.proof_type,
+                update.address,
+                update.storage_key,
+            ) == (rw_counter, proof_type, address, storage_key)
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Rw {
+    Start {
+        rw_counter: usize,
+    },
     TxAccessListAccount {
         rw_counter: usize,
         is_write: bool,
@@ -512,6 +1224,22 @@ pub enum Rw {
         memory_address: u64,
         byte: u8,
     },
+    TxLog {
+        rw_counter: usize,
+        is_write: bool,
+        tx_id: usize,
+        log_id: usize,
+        field_tag: TxLogFieldTag,
+        index: usize,
+        value: Word,
+    },
+    TxReceipt {
+        rw_counter: usize,
+        is_write: bool,
+        tx_id: usize,
+        field_tag: TxReceiptFieldTag,
+        value: u64,
+    },
 }
 #[derive(Default, Clone, Copy)]
 pub struct RwRow<F: FieldExt> {
@@ -547,6 +1275,23 @@ impl<F: FieldExt> From<[F; 11]> for RwRow<F> {
 }
 
 impl Rw {
+    pub fn rw_counter(&self) -> usize {
+        match self {
+            Self::Start { rw_counter }
+            | Self::TxAccessListAccount { rw_counter, .. }
+            | Self::TxAccessListAccountStorage { rw_counter, .. }
+            | Self::TxRefund { rw_counter, .. }
+            | Self::Account { rw_counter, .. }
+            | Self::AccountStorage { rw_counter, .. }
+            | Self::AccountDestructed { rw_counter, .. }
+            | Self::CallContext { rw_counter, .. }
+            | Self::Stack { rw_counter, .. }
+            | Self::Memory { rw_counter, .. }
+            | Self::TxLog { rw_counter, .. }
+            | Self::TxReceipt { rw_counter, .. } => *rw_counter,
+        }
+    }
+
     pub fn tx_access_list_value_pair(&self) -> (bool, bool) {
         match self {
             Self::TxAccessListAccount {
@@ -624,6 +1369,20 @@ impl Rw {
 
     pub fn table_assignment<F: Field>(&self, randomness: F) -> RwRow<F> {
         match self {
+            Self::Start { rw_counter } => [
+                F::from(*rw_counter as u64),
+                F::zero(),
+                F::from(RwTableTag::Start as u64),
+                F::zero(),
+                F::zero(),
+                F::zero(),
+                F::zero(),
+                F::zero(),
+                F::zero(),
+                F::zero(),
+                F::zero(),
+            ]
+            .into(),
             Self::TxAccessListAccount {
                 rw_counter,
                 is_write,
@@ -823,7 +1582,76 @@ impl Rw {
                 ),
             ]
             .into(),
-            _ => unimplemented!(),
+            Self::TxLog {
+                rw_counter,
+                is_write,
+                tx_id,
+                log_id,
+                field_tag,
+                index,
+                value,
+            } => [
+                F::from(*rw_counter as u64),
+                F::from(*is_write as u64),
+                F::from(RwTableTag::TxLog as u64),
+                F::from(*tx_id as u64),
+                F::from(*log_id as u64),
+                F::from(*field_tag as u64),
+                F::from(*index as u64),
+                match field_tag {
+                    TxLogFieldTag::Address => value.to_scalar().unwrap(),
+                    TxLogFieldTag::Topic => RandomLinearCombination::random_linear_combine(
+                        value.to_le_bytes(),
+                        randomness,
+                    ),
+                    TxLogFieldTag::Data => F::from(value.low_u64()),
+                },
+                F::zero(),
+                F::zero(),
+                F::zero(),
+            ]
+            .into(),
+            Self::TxReceipt {
+                rw_counter,
+                is_write,
+                tx_id,
+                field_tag,
+                value,
+            } => [
+                F::from(*rw_counter as u64),
+                F::from(*is_write as u64),
+                F::from(RwTableTag::TxReceipt as u64),
+                F::from(*tx_id as u64),
+                F::zero(),
+                F::from(*field_tag as u64),
+                F::zero(),
+                F::from(*value),
+                F::zero(),
+                F::zero(),
+                F::zero(),
+            ]
+            .into(),
+            Self::AccountDestructed {
+                rw_counter,
+                is_write,
+                account_address,
+                value,
+                value_prev,
+                ..
+            } => [
+                F::from(*rw_counter as u64),
+                F::from(*is_write as u64),
+                F::from(RwTableTag::AccountDestructed as u64),
+                F::zero(),
+                account_address.to_scalar().unwrap(),
+                F::zero(),
+                F::zero(),
+                F::from(*value as u64),
+                F::from(*value_prev as u64),
+                F::zero(),
+                F::zero(),
+            ]
+            .into(),
         }
     }
 }
@@ -838,12 +1666,118 @@ impl From<&circuit_input_builder::Block> for BlockContext {
             difficulty: block.difficulty,
             base_fee: block.base_fee,
             history_hashes: block.history_hashes.clone(),
+            chain_id: block.chain_config.chain_id,
+            withdrawals_root: block.withdrawals_root,
+        }
+    }
+}
+
+impl From<&operation::Operation<operation::StackOp>> for Rw {
+    fn from(op: &operation::Operation<operation::StackOp>) -> Self {
+        Self::Stack {
+            rw_counter: op.rwc().into(),
+            is_write: op.rw().is_write(),
+            call_id: op.op().call_id(),
+            stack_pointer: usize::from(*op.op().address()),
+            value: *op.op().value(),
+        }
+    }
+}
+
+impl From<&operation::Operation<operation::MemoryOp>> for Rw {
+    fn from(op: &operation::Operation<operation::MemoryOp>) -> Self {
+        Self::Memory {
+            rw_counter: op.rwc().into(),
+            is_write: op.rw().is_write(),
+            call_id: op.op().call_id(),
+            memory_address: u64::from_le_bytes(
+                op.op().address().to_le_bytes()[..8].try_into().unwrap(),
+            ),
+            byte: op.op().value(),
         }
     }
 }
 
-impl From<&operation::OperationContainer> for RwMap {
-    fn from(container: &operation::OperationContainer) -> Self {
+/// The `Stack` and `Memory` rw operations of an `OperationContainer`, which may
+/// have been spilled to disk.  They are streamed back from it in sorted order
+/// as the state circuit assigns them, instead of being collected in memory.
+#[derive(Clone, Debug, Default)]
+pub struct StreamedRws(Arc<operation::OperationContainer>);
+
+impl StreamedRws {
+    /// Returns the number of the streamed operations.
+    pub fn len(&self) -> usize {
+        self.0.stack.len() + self.0.memory.len()
+    }
+
+    /// Returns true if there are no streamed operations.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the `Stack` operations in sorted order, which
+    /// reads the spilled ones as it goes.
+    pub fn sorted_stack(&self) -> io::Result<impl Iterator<Item = io::Result<Rw>> + '_> {
+        Ok(self
+            .0
+            .sorted_stack_stream()?
+            .map(|op| op.map(|op| Rw::from(&op))))
+    }
+
+    /// Returns an iterator over the `Memory` operations in sorted order, which
+    /// reads the spilled ones as it goes.
+    pub fn sorted_memory(&self) -> io::Result<impl Iterator<Item = io::Result<Rw>> + '_> {
+        Ok(self
+            .0
+            .sorted_memory_stream()?
+            .map(|op| op.map(|op| Rw::from(&op))))
+    }
+}
+
+impl RwMap {
+    /// Returns the rw operations of `container`, whose `Stack` and `Memory`
+    /// ones may have been spilled to disk, with the spilled ones referenced by
+    /// `steps` read back.  The steps of a block reference all its operations,
+    /// so the map has them all, as the EVM circuit needs.  Returns an error if
+    /// a spilled operation isn't referenced by `steps` or can't be read back.
+    pub fn from_streamed<'a>(
+        container: &operation::OperationContainer,
+        steps: impl IntoIterator<Item = &'a circuit_input_builder::ExecStep>,
+    ) -> io::Result<Self> {
+        let (mut stack, mut memory) = (HashSet::new(), HashSet::new());
+        for op_ref in steps
+            .into_iter()
+            .flat_map(|step| step.bus_mapping_instance.iter())
+        {
+            match op_ref.target() {
+                operation::Target::Stack => stack.insert(op_ref.as_usize()),
+                operation::Target::Memory => memory.insert(op_ref.as_usize()),
+                _ => false,
+            };
+        }
+        let mut rws = Self::without_stack_and_memory(container);
+        rws.0.insert(
+            RwTableTag::Stack,
+            resolve_rws(&container.stack, |index| stack.contains(&index))?,
+        );
+        rws.0.insert(
+            RwTableTag::Memory,
+            resolve_rws(&container.memory, |index| memory.contains(&index))?,
+        );
+        Ok(rws)
+    }
+
+    /// Returns the rw operations of `container` but its `Stack` and `Memory`
+    /// ones, which may have been spilled to disk, with the latter to be
+    /// streamed to the state circuit, see `StateCircuit::new_streamed`.
+    pub fn with_streamed(container: Arc<operation::OperationContainer>) -> (Self, StreamedRws) {
+        (
+            Self::without_stack_and_memory(&container),
+            StreamedRws(container),
+        )
+    }
+
+    fn without_stack_and_memory(container: &operation::OperationContainer) -> Self {
         let mut rws = HashMap::default();
 
         rws.insert(
@@ -991,32 +1925,40 @@ impl From<&operation::OperationContainer> for RwMap {
                 .collect(),
         );
         rws.insert(
-            RwTableTag::Stack,
+            RwTableTag::TxLog,
             container
-                .stack
+                .tx_log
                 .iter()
-                .map(|op| Rw::Stack {
+                .map(|op| Rw::TxLog {
                     rw_counter: op.rwc().into(),
                     is_write: op.rw().is_write(),
-                    call_id: op.op().call_id(),
-                    stack_pointer: usize::from(*op.op().address()),
-                    value: *op.op().value(),
+                    tx_id: op.op().tx_id,
+                    log_id: op.op().log_id,
+                    field_tag: match op.op().field {
+                        TxLogField::Address => TxLogFieldTag::Address,
+                        TxLogField::Topic => TxLogFieldTag::Topic,
+                        TxLogField::Data => TxLogFieldTag::Data,
+                    },
+                    index: op.op().index,
+                    value: op.op().value,
                 })
                 .collect(),
         );
         rws.insert(
-            RwTableTag::Memory,
+            RwTableTag::TxReceipt,
             container
-                .memory
+                .tx_receipt
                 .iter()
-                .map(|op| Rw::Memory {
+                .map(|op| Rw::TxReceipt {
                     rw_counter: op.rwc().into(),
                     is_write: op.rw().is_write(),
-                    call_id: op.op().call_id(),
-                    memory_address: u64::from_le_bytes(
-                        op.op().address().to_le_bytes()[..8].try_into().unwrap(),
-                    ),
-                    byte: op.op().value(),
+                    tx_id: op.op().tx_id,
+                    field_tag: match op.op().field {
+                        TxReceiptField::PostStateOrStatus => TxReceiptFieldTag::PostStateOrStatus,
+                        TxReceiptField::CumulativeGasUsed => TxReceiptFieldTag::CumulativeGasUsed,
+                        TxReceiptField::LogLength => TxReceiptFieldTag::LogLength,
+                    },
+                    value: op.op().value,
                 })
                 .collect(),
         );
@@ -1025,6 +1967,70 @@ impl From<&operation::OperationContainer> for RwMap {
     }
 }
 
+impl From<circuit_input_builder::CopyDataType> for CopyDataType {
+    fn from(tag: circuit_input_builder::CopyDataType) -> Self {
+        match tag {
+            circuit_input_builder::CopyDataType::Memory => Self::Memory,
+            circuit_input_builder::CopyDataType::Bytecode => Self::Bytecode,
+            circuit_input_builder::CopyDataType::TxCalldata => Self::TxCalldata,
+            circuit_input_builder::CopyDataType::TxLog => Self::TxLog,
+        }
+    }
+}
+
+impl From<&circuit_input_builder::CopyEvent> for CopyEvent {
+    fn from(event: &circuit_input_builder::CopyEvent) -> Self {
+        Self {
+            src_type: event.src_type.into(),
+            src_id: event.src_id,
+            src_addr: event.src_addr,
+            src_addr_end: event.src_addr_end,
+            dst_type: event.dst_type.into(),
+            dst_id: event.dst_id,
+            dst_addr: event.dst_addr,
+            log_id: event.log_id,
+            rw_counter_start: event.rw_counter_start.0,
+            bytes: event.bytes.clone(),
+        }
+    }
+}
+
+// Returns the rws of the operations of `store`, with the spilled ones whose
+// index satisfies `keep` read back, which must be all of them.
+fn resolve_rws<T>(
+    store: &operation::OperationStore<T>,
+    keep: impl FnMut(usize) -> bool,
+) -> io::Result<Vec<Rw>>
+where
+    T: operation::Op + Serialize + DeserializeOwned,
+    for<'a> Rw: From<&'a operation::Operation<T>>,
+{
+    let mut spilled = store.get_spilled(keep)?;
+    (0..store.len())
+        .map(|index| match spilled.remove(&index) {
+            Some(op) => Ok(Rw::from(&op)),
+            None => store.get(index).map(Rw::from),
+        })
+        .collect()
+}
+
+impl TryFrom<&operation::OperationContainer> for RwMap {
+    type Error = io::Error;
+
+    /// Returns the rw operations of `container`, with the ones spilled to disk
+    /// read back.
+    fn try_from(container: &operation::OperationContainer) -> io::Result<Self> {
+        let mut rws = Self::without_stack_and_memory(container);
+        rws.0
+            .insert(RwTableTag::Stack, resolve_rws(&container.stack, |_| true)?);
+        rws.0.insert(
+            RwTableTag::Memory,
+            resolve_rws(&container.memory, |_| true)?,
+        );
+        Ok(rws)
+    }
+}
+
 impl From<&ExecError> for ExecutionState {
     fn from(error: &ExecError) -> Self {
         match error {
@@ -1085,6 +2091,7 @@ impl From<&bus_mapping::circuit_input_builder::ExecStep> for ExecutionState {
         match step.op {
             OpcodeId::ADD => ExecutionState::ADD,
             OpcodeId::MUL => ExecutionState::MUL,
+            OpcodeId::EXP => ExecutionState::EXP,
             OpcodeId::SUB => ExecutionState::ADD,
             OpcodeId::EQ | OpcodeId::LT | OpcodeId::GT => ExecutionState::CMP,
             OpcodeId::SLT | OpcodeId::SGT => ExecutionState::SCMP,
@@ -1100,6 +2107,9 @@ impl From<&bus_mapping::circuit_input_builder::ExecStep> for ExecutionState {
             OpcodeId::MLOAD => ExecutionState::MEMORY,
             OpcodeId::MSTORE => ExecutionState::MEMORY,
             OpcodeId::MSTORE8 => ExecutionState::MEMORY,
+            OpcodeId::MCOPY => ExecutionState::MCOPY,
+            OpcodeId::CREATE => ExecutionState::CREATE,
+            OpcodeId::CREATE2 => ExecutionState::CREATE2,
             OpcodeId::JUMPDEST => ExecutionState::JUMPDEST,
             OpcodeId::JUMP => ExecutionState::JUMP,
             OpcodeId::JUMPI => ExecutionState::JUMPI,
@@ -1110,8 +2120,10 @@ impl From<&bus_mapping::circuit_input_builder::ExecStep> for ExecutionState {
             OpcodeId::COINBASE => ExecutionState::COINBASE,
             OpcodeId::TIMESTAMP => ExecutionState::TIMESTAMP,
             OpcodeId::NUMBER => ExecutionState::NUMBER,
+            OpcodeId::CHAINID => ExecutionState::CHAINID,
             OpcodeId::GAS => ExecutionState::GAS,
             OpcodeId::SELFBALANCE => ExecutionState::SELFBALANCE,
+            OpcodeId::BASEFEE => ExecutionState::BASEFEE,
             OpcodeId::SLOAD => ExecutionState::SLOAD,
             OpcodeId::SSTORE => ExecutionState::SSTORE,
             // TODO: Use better way to convert BeginTx and EndTx.
@@ -1180,10 +2192,14 @@ fn tx_convert(tx: &circuit_input_builder::Transaction, id: usize, is_last_tx: bo
         value: tx.value,
         call_data: tx.input.clone(),
         call_data_length: tx.input.len(),
-        call_data_gas_cost: tx
-            .input
-            .iter()
-            .fold(0, |acc, byte| acc + if *byte == 0 { 4 } else { 16 }),
+        call_data_gas_cost: call_data_gas_cost(&tx.input),
+        tx_type: tx.tx_type,
+        access_list: tx.access_list.clone(),
+        max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+        max_fee_per_gas: tx.max_fee_per_gas,
+        v: tx.v,
+        r: tx.r,
+        s: tx.s,
         calls: tx
             .calls()
             .iter()
@@ -1192,7 +2208,10 @@ fn tx_convert(tx: &circuit_input_builder::Transaction, id: usize, is_last_tx: bo
                 is_root: call.is_root,
                 is_create: call.is_create(),
                 code_source: match call.code_source {
-                    circuit_input_builder::CodeSource::Address(_) => {
+                    // The init code of a creation is in the bytecode table by
+                    // its hash too
+                    circuit_input_builder::CodeSource::Address(_)
+                    | circuit_input_builder::CodeSource::Memory => {
                         CodeSource::Account(call.code_hash.to_word())
                     }
                     _ => unimplemented!(),
@@ -1232,20 +2251,78 @@ fn tx_convert(tx: &circuit_input_builder::Transaction, id: usize, is_last_tx: bo
             .collect(),
     }
 }
+#[tracing::instrument(skip_all)]
 pub fn block_convert(
     block: &circuit_input_builder::Block,
     code_db: &bus_mapping::state_db::CodeDB,
 ) -> Block<Fp> {
+    let rws = RwMap::from_streamed(
+        &block.container,
+        block.txs().iter().flat_map(|tx| tx.steps()),
+    )
+    .expect("failed to read back the spilled rw operations");
+    let txs: Vec<_> = block
+        .txs()
+        .iter()
+        .enumerate()
+        .map(|(idx, tx)| tx_convert(tx, idx + 1, idx + 1 == block.txs().len()))
+        .collect();
+    // The exponentiations of the EXP steps, of the base and the exponent they
+    // pop from the stack
+    let exp_events = txs
+        .iter()
+        .flat_map(|tx| tx.steps.iter())
+        .filter(|step| step.execution_state == ExecutionState::EXP)
+        .map(|step| {
+            let [base, exponent] = [0, 1].map(|idx| rws[step.rw_indices[idx]].stack_value());
+            ExpEvent::new(base, exponent)
+        })
+        .collect();
+    // The init code hashed by the CREATE and CREATE2 steps, and the input of
+    // the address of the contract, of the caller nonce and the init code they
+    // read
+    let create_keccak_inputs = txs
+        .iter()
+        .flat_map(|tx| tx.steps.iter())
+        .filter_map(|step| {
+            let is_create2 = match step.execution_state {
+                ExecutionState::CREATE => false,
+                ExecutionState::CREATE2 => true,
+                _ => return None,
+            };
+            let n_pop = if is_create2 { 4 } else { 3 };
+            let length = rws[step.rw_indices[2]].stack_value().as_usize();
+            let init_code = step.rw_indices[n_pop + 8..n_pop + 8 + length]
+                .iter()
+                .map(|idx| rws[*idx].memory_value())
+                .collect::<Vec<_>>();
+            let (sender, nonce) = match &rws[step.rw_indices[n_pop + 6]] {
+                Rw::Account {
+                    account_address,
+                    value_prev,
+                    ..
+                } => (*account_address, value_prev.as_u64()),
+                _ => unreachable!("caller nonce write of a creation"),
+            };
+            let address_input = if is_create2 {
+                create2_address_keccak_input(
+                    sender,
+                    rws[step.rw_indices[3]].stack_value(),
+                    Word::from_big_endian(&keccak256(&init_code)),
+                )
+            } else {
+                create_address_keccak_input(sender, nonce)
+            };
+            Some(vec![init_code, address_input])
+        })
+        .flatten();
+
     Block {
         randomness: Fp::rand(),
         context: block.into(),
-        rws: RwMap::from(&block.container),
-        txs: block
-            .txs()
-            .iter()
-            .enumerate()
-            .map(|(idx, tx)| tx_convert(tx, idx + 1, idx + 1 == block.txs().len()))
-            .collect(),
+        chain_config: block.chain_config,
+        rws,
+        txs,
         bytecodes: block
             .txs()
             .iter()
@@ -1255,5 +2332,186 @@ pub fn block_convert(
                     .map(|call| Bytecode::new(code_db.0.get(&call.code_hash).unwrap().to_vec()))
             })
             .collect(),
+        keccak_inputs: block
+            .txs()
+            .iter()
+            .filter(|tx| tx.is_create())
+            .map(|tx| create_address_keccak_input(tx.from, tx.nonce))
+            .chain(create_keccak_inputs)
+            .collect(),
+        // The other copies are done by the multi-step copy gadgets of the EVM
+        // circuit.
+        copy_events: block.copy_events.iter().map(CopyEvent::from).collect(),
+        exp_events,
+        circuits_params: block.circuits_params,
+        chunk: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        block_convert, create2_address_keccak_input, create_address_keccak_input,
+        tx_sign_keccak_input, Block, Transaction,
+    };
+    use bus_mapping::{mock::BlockData, operation::OperationContainer};
+    use eth_types::{
+        address, bytecode, evm_types::OpcodeId, keccak256, AccessList, AccessListItem, Address,
+        Word, H256,
+    };
+    use pairing::bn256::Fr as Fp;
+
+    fn contract_address(keccak_input: Vec<u8>) -> Address {
+        Address::from_slice(&keccak256(&keccak_input)[12..])
+    }
+
+    #[test]
+    fn create_address() {
+        let sender = address!("0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        for (nonce, expected) in [
+            (0, address!("0xcd234a471b72ba2f1ccf0a70fcaba648a5eecd8d")),
+            (1, address!("0x343c43a37d37dff08ae8c4a11544c718abb4fcf8")),
+            (2, address!("0xf778b86fa74e846c4f0a1fbd1335fe81c00a0c91")),
+            (3, address!("0xfffd933a0bc612844eaf0c6fe3e5b8e9b6c1d19c")),
+        ] {
+            assert_eq!(
+                contract_address(create_address_keccak_input(sender, nonce)),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn create_address_keccak_input_nonce_encoding() {
+        let sender = Address::zero();
+        for (nonce, nonce_rlp) in [
+            (0, vec![0x80]),
+            (0x7f, vec![0x7f]),
+            (0x80, vec![0x81, 0x80]),
+            (0x0100, vec![0x82, 0x01, 0x00]),
+            (u64::MAX, [vec![0x88], vec![0xff; 8]].concat()),
+        ] {
+            let input = create_address_keccak_input(sender, nonce);
+            assert_eq!(input[0] as usize, 0xc0 + input.len() - 1);
+            assert_eq!(input[1], 0x94);
+            assert_eq!(input[22..], nonce_rlp);
+        }
+    }
+
+    #[test]
+    fn create2_address() {
+        // Example 0 of EIP-1014
+        let code_hash = Word::from_big_endian(&keccak256(&[0x00]));
+        assert_eq!(
+            contract_address(create2_address_keccak_input(
+                Address::zero(),
+                Word::zero(),
+                code_hash
+            )),
+            address!("0x4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38")
+        );
+    }
+
+    #[test]
+    fn tx_sign_keccak_input_encoding() {
+        // Example of EIP-155
+        let tx = Transaction {
+            nonce: 9,
+            gas_price: Word::from(20_000_000_000u64),
+            gas: 21000,
+            callee_address: address!("0x3535353535353535353535353535353535353535"),
+            value: Word::from(1_000_000_000_000_000_000u64),
+            v: 37,
+            ..Default::default()
+        };
+        assert_eq!(
+            tx_sign_keccak_input(&tx, Word::one()),
+            hex::decode(
+                "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7640000\
+                 80018080"
+            )
+            .unwrap()
+        );
+
+        // Without EIP-155, the chain id is not signed
+        let tx = Transaction { v: 27, ..tx };
+        assert_eq!(
+            tx_sign_keccak_input(&tx, Word::one()),
+            hex::decode(
+                "e9098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7640000\
+                 80"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn tx_sign_keccak_input_encoding_eip1559() {
+        let address = address!("0x3535353535353535353535353535353535353535");
+        let tx = Transaction {
+            gas: 21000,
+            callee_address: address,
+            tx_type: 2,
+            access_list: AccessList(vec![AccessListItem {
+                address,
+                storage_keys: vec![H256::from_low_u64_be(1)],
+            }]),
+            max_priority_fee_per_gas: Word::from(1),
+            max_fee_per_gas: Word::from(2),
+            v: 1,
+            ..Default::default()
+        };
+        // The access list has a long list prefix, with a short one of its item
+        assert_eq!(
+            tx_sign_keccak_input(&tx, Word::one()),
+            hex::decode(
+                "02f858018001028252089435353535353535353535353535353535353535358080f838f794353535\
+                 3535353535353535353535353535353535e1a0000000000000000000000000000000000000000000\
+                 0000000000000000000001"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let code = bytecode! {
+            PUSH1(0x20)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x20)
+            PUSH1(0x00)
+            SHA3
+            PUSH1(0x00)
+            SSTORE
+            STOP
+        };
+        let block_data =
+            BlockData::new_from_geth_data(mock::new_single_tx_trace_code(&code).unwrap());
+        let mut builder = block_data.new_circuit_input_builder();
+        builder
+            .handle_block(&block_data.eth_block, &block_data.geth_traces)
+            .unwrap();
+
+        let container = &builder.block.container;
+        let json = serde_json::to_string(container).unwrap();
+        let decoded: OperationContainer = serde_json::from_str(&json).unwrap();
+        assert_eq!(&decoded, container);
+
+        // The witness block isn't comparable, its serialization is.
+        let block = block_convert(&builder.block, &builder.code_db);
+        let json = serde_json::to_string(&block).unwrap();
+        let decoded: Block<Fp> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.randomness, block.randomness);
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+
+        let ops = [
+            OpcodeId::SHA3,
+            OpcodeId::INVALID(0xfe),
+            OpcodeId::INVALID(0x0c),
+        ];
+        let json = serde_json::to_string(&ops).unwrap();
+        assert_eq!(json, r#"["SHA3","INVALID","opcode 0x0c not defined"]"#);
+        assert_eq!(serde_json::from_str::<[OpcodeId; 3]>(&json).unwrap(), ops);
     }
 }
@@ -0,0 +1,780 @@
+//! Splitting of a witness block into chunks, which are proven by EVM and state
+//! circuits of their own, so that a block which doesn't fit in the circuits of
+//! any degree remains provable.
+//!
+//! The steps of a block are only split before the steps of opcodes, which are
+//! tied to the previous step by nothing else than the step state.  A chunk
+//! which isn't the first one begins with a `BeginChunk` step holding the state
+//! of its first step, and a chunk which isn't the last one ends with an
+//! `EndChunk` step holding the state of the first step of the next chunk.
+//! These states, with the fingerprint of the rw operations carried over them,
+//! are the [`Chunk`] of the block of every chunk, and the proofs of the chunks
+//! of a block are stitched by checking with [`check_chunks`] that every chunk
+//! begins with the state the previous one ends with.
+//!
+//! The begin and end states are public inputs of the EVM circuit of a chunk,
+//! see [`Chunk::instance`], to which the states of its `BeginChunk` and
+//! `EndChunk` steps are constrained, so that a chunk is only stitched on the
+//! states its proof is of.
+//!
+//! The rw operations of a chunk are the ones in the range of rw counters of
+//! its steps and the ones its steps look up, like the reversions done at the
+//! end of their call.  The state circuit of a chunk also needs the value of
+//! the states the chunk accesses before it, so the last operation of the
+//! previous chunks on each of these states is carried to the chunk as the
+//! write of its initial value.  The carried operations are the ones of the
+//! chunk before the rw counter of its begin state, whose fingerprint, see
+//! [`carried_rws_fingerprint`], is a public input of the state circuit of the
+//! chunk as well as of the EVM circuits of the chunk and of the previous one,
+//! so that the initial values of a chunk are the ones the previous chunk hands
+//! over rather than free writes.
+
+use super::{Block, CodeSource, ExecStep, Rw, RwMap, RwRow};
+use crate::evm_circuit::{
+    param::{N_BYTES_WORD, N_CELLS_STEP_STATE},
+    step::ExecutionState,
+    table::RwTableTag,
+    util::RandomLinearCombination,
+};
+use eth_types::{Field, ToLittleEndian, ToScalar, Word};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+/// Number of values of a [`ChunkState`] in the instance of the EVM circuit,
+/// one for every cell of the state of a step, and the fingerprint of the
+/// carried rw operations.
+pub const CHUNK_STATE_SIZE: usize = N_CELLS_STEP_STATE + 1;
+
+/// State of the execution at the boundary of two chunks of a block.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkState {
+    /// Rw counter of the step
+    pub rw_counter: usize,
+    /// Id of the transaction of the step
+    pub tx_id: usize,
+    /// Id of the call of the step
+    pub call_id: usize,
+    /// Whether the call of the step is a root call
+    pub is_root: bool,
+    /// Whether the call of the step is a create call
+    pub is_create: bool,
+    /// Hash of the code executed by the call of the step
+    pub code_hash: Word,
+    /// Program counter of the step
+    pub program_counter: u64,
+    /// Stack pointer of the step
+    pub stack_pointer: usize,
+    /// Amount of gas left at the step
+    pub gas_left: u64,
+    /// Memory size in bytes at the step
+    pub memory_size: u64,
+    /// Counter of the state writes of the call at the step
+    pub state_write_counter: usize,
+    /// Fingerprint of the rw operations carried to the chunk beginning with
+    /// the step, see [`carried_rws_fingerprint`], as a little endian word, or
+    /// 0 at the end of the last chunk
+    pub carried_rws: Word,
+}
+
+impl ChunkState {
+    /// Returns the values of the cells of the state of a step with this
+    /// state, in the order of `StepState`, followed by the fingerprint of the
+    /// carried rw operations, which are the values of the instance of the EVM
+    /// circuit.  The transaction id is left out, as it's the one of the call.
+    pub fn instance<F: Field>(&self, randomness: F) -> [F; CHUNK_STATE_SIZE] {
+        [
+            F::from(self.rw_counter as u64),
+            F::from(self.call_id as u64),
+            F::from(self.is_root as u64),
+            F::from(self.is_create as u64),
+            RandomLinearCombination::random_linear_combine(
+                self.code_hash.to_le_bytes(),
+                randomness,
+            ),
+            F::from(self.program_counter),
+            F::from(self.stack_pointer as u64),
+            F::from(self.gas_left),
+            F::from(self.memory_size / N_BYTES_WORD as u64),
+            F::from(self.state_write_counter as u64),
+            self.carried_rws.to_scalar().unwrap(),
+        ]
+    }
+}
+
+/// Returns the fingerprint of the rw operations of `rws` done before the rw
+/// counter `rw_counter`, which are the ones carried to a chunk beginning at
+/// it: the product of `r^11 + v_0 + v_1 r + ... + v_10 r^10` over the values
+/// `v_i` of their rows of the rw table, where `r` is `randomness`.  It's 1 if
+/// no operation is carried.
+pub fn carried_rws_fingerprint<F: Field>(rws: &RwMap, rw_counter: usize, randomness: F) -> F {
+    rws.0
+        .iter()
+        .filter(|(tag, _)| **tag != RwTableTag::Start)
+        .flat_map(|(_, rws)| rws.iter())
+        .filter(|rw| rw.rw_counter() < rw_counter)
+        .fold(F::one(), |fingerprint, rw| {
+            fingerprint * carried_rw_factor(&rw.table_assignment(randomness), randomness)
+        })
+}
+
+// Returns the factor of the fingerprint of the carried rw operations of the
+// row `row` of the rw table.
+pub(crate) fn carried_rw_factor<F: Field>(row: &RwRow<F>, randomness: F) -> F {
+    [
+        row.rw_counter,
+        row.is_write,
+        row.tag,
+        row.key1,
+        row.key2,
+        row.key3,
+        row.key4,
+        row.value,
+        row.value_prev,
+        row.aux1,
+        row.aux2,
+    ]
+    .iter()
+    .rev()
+    .fold(F::one(), |acc, value| acc * randomness + value)
+}
+
+/// Position of a chunk in its block and the states it begins and ends with.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chunk {
+    /// Index of the chunk in its block
+    pub index: usize,
+    /// Number of chunks of the block
+    pub n_chunks: usize,
+    /// State of the first step of the chunk
+    pub begin: ChunkState,
+    /// State of the first step of the next chunk, or of the last step of the
+    /// block for the last chunk
+    pub end: ChunkState,
+}
+
+impl Chunk {
+    /// Returns true if the chunk is the last one of its block.
+    pub fn is_last(&self) -> bool {
+        self.index + 1 == self.n_chunks
+    }
+
+    /// Returns the values of the instance columns of the chunk states of the
+    /// EVM circuit proving the chunk: the values of its begin state followed
+    /// by the ones of its end state, see [`ChunkState::instance`].
+    pub fn instance<F: Field>(&self, randomness: F) -> Vec<F> {
+        self.begin
+            .instance(randomness)
+            .iter()
+            .chain(self.end.instance(randomness).iter())
+            .copied()
+            .collect()
+    }
+}
+
+/// Error when splitting a block into chunks or stitching them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChunkError {
+    /// The block has no steps.
+    NoSteps,
+    /// The steps from a step a chunk may begin with to the next one don't fit
+    /// in a chunk.
+    StepsTooLong {
+        /// Rw counter of the first of the steps
+        rw_counter: usize,
+        /// Number of steps
+        n_steps: usize,
+    },
+    /// The chunk of this index is missing or out of order.
+    Missing {
+        /// Index of the chunk
+        index: usize,
+    },
+    /// The rw operations carried to the chunk of this index aren't the ones
+    /// the previous chunk ends with.
+    CarriedRws {
+        /// Index of the chunk
+        index: usize,
+    },
+    /// The chunk of this index doesn't begin with the state the previous
+    /// chunk ends with.
+    Discontinuity {
+        /// Index of the chunk
+        index: usize,
+    },
+    /// The states of the chunk of this index aren't the ones of the public
+    /// inputs of the proof of its EVM circuit.
+    Unproven {
+        /// Index of the chunk
+        index: usize,
+    },
+}
+
+impl fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSteps => write!(f, "the block has no steps to split into chunks"),
+            Self::StepsTooLong {
+                rw_counter,
+                n_steps,
+            } => write!(
+                f,
+                "the {} steps from rw counter {} can't be split and don't fit in a chunk",
+                n_steps, rw_counter
+            ),
+            Self::Missing { index } => write!(f, "chunk {} is missing", index),
+            Self::CarriedRws { index } => write!(
+                f,
+                "the rw operations carried to chunk {} aren't the ones the previous chunk ends with",
+                index
+            ),
+            Self::Discontinuity { index } => write!(
+                f,
+                "chunk {} doesn't begin where the previous chunk ends",
+                index
+            ),
+            Self::Unproven { index } => write!(
+                f,
+                "the states of chunk {} aren't the ones its EVM circuit proof is of",
+                index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ChunkError {}
+
+/// Checks that `chunks` are all the chunks of a block in order, every one
+/// beginning with the state the previous one ends with, and with the rw
+/// operations it hands over.
+pub fn check_chunks<'a>(chunks: impl IntoIterator<Item = &'a Chunk>) -> Result<(), ChunkError> {
+    let mut prev: Option<&Chunk> = None;
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        if chunk.index != index || prev.map_or(false, |prev| prev.n_chunks != chunk.n_chunks) {
+            return Err(ChunkError::Missing { index });
+        }
+        if prev.map_or(false, |prev| {
+            prev.end.carried_rws != chunk.begin.carried_rws
+        }) {
+            return Err(ChunkError::CarriedRws { index });
+        }
+        if prev.map_or(false, |prev| prev.end != chunk.begin) {
+            return Err(ChunkError::Discontinuity { index });
+        }
+        prev = Some(chunk);
+    }
+    match prev {
+        Some(last) if last.is_last() => Ok(()),
+        last => Err(ChunkError::Missing {
+            index: last.map_or(0, |last| last.index + 1),
+        }),
+    }
+}
+
+// Returns true if a chunk may begin with a step of `execution_state`, whose
+// previous step doesn't constrain anything else than its step state.
+fn can_begin_chunk(execution_state: ExecutionState) -> bool {
+    !matches!(
+        execution_state,
+        ExecutionState::BeginTx
+            | ExecutionState::EndTx
+            | ExecutionState::EndBlock
+            | ExecutionState::BeginChunk
+            | ExecutionState::EndChunk
+            | ExecutionState::CopyToMemory
+    )
+}
+
+// Returns the rw operation `rw` done before a chunk, as the operation which
+// sets the initial value of its state in the chunk.
+fn carried(rw: &Rw) -> Rw {
+    let mut rw = rw.clone();
+    match &mut rw {
+        // The first access of an account field is a read of its value in the
+        // trie.
+        Rw::Account {
+            is_write,
+            value,
+            value_prev,
+            ..
+        } => {
+            *is_write = false;
+            *value_prev = *value;
+        }
+        // The first access of a storage slot is a write at rw counter 0.
+        Rw::AccountStorage {
+            rw_counter,
+            is_write,
+            value,
+            value_prev,
+            ..
+        } => {
+            *rw_counter = 0;
+            *is_write = true;
+            *value_prev = *value;
+        }
+        Rw::TxAccessListAccount {
+            is_write,
+            value,
+            value_prev,
+            ..
+        }
+        | Rw::TxAccessListAccountStorage {
+            is_write,
+            value,
+            value_prev,
+            ..
+        }
+        | Rw::AccountDestructed {
+            is_write,
+            value,
+            value_prev,
+            ..
+        } => {
+            *is_write = true;
+            *value_prev = *value;
+        }
+        Rw::TxRefund {
+            is_write,
+            value,
+            value_prev,
+            ..
+        } => {
+            *is_write = true;
+            *value_prev = *value;
+        }
+        Rw::CallContext { is_write, .. }
+        | Rw::Stack { is_write, .. }
+        | Rw::Memory { is_write, .. }
+        | Rw::TxLog { is_write, .. }
+        | Rw::TxReceipt { is_write, .. } => *is_write = true,
+        Rw::Start { .. } => {}
+    }
+    rw
+}
+
+impl<F: Field> Block<F> {
+    /// Splits the block into chunks of at most `max_steps` steps, their
+    /// `BeginChunk` and `EndChunk` steps included, whose steps do at most
+    /// `max_rws` rw operations.  The operations carried to the chunks come on
+    /// top of these.
+    pub fn chunks(&self, max_steps: usize, max_rws: usize) -> Result<Vec<Self>, ChunkError> {
+        // Positions of the steps of the block, as the index of their
+        // transaction and their index in it.
+        let steps = self
+            .txs
+            .iter()
+            .enumerate()
+            .flat_map(|(tx_idx, tx)| (0..tx.steps.len()).map(move |step_idx| (tx_idx, step_idx)))
+            .collect::<Vec<_>>();
+        if steps.is_empty() {
+            return Err(ChunkError::NoSteps);
+        }
+        let step = |pos: usize| {
+            let (tx_idx, step_idx) = steps[pos];
+            &self.txs[tx_idx].steps[step_idx]
+        };
+        // Number of rw operations done by the steps before every position.
+        let n_rws = prefix_sums((0..steps.len()).map(|pos| step(pos).rw_indices.len()));
+        let fits = |start: usize, end: usize| {
+            let n_chunk_steps = end - start + (start > 0) as usize + (end < steps.len()) as usize;
+            n_chunk_steps <= max_steps && n_rws[end] - n_rws[start] <= max_rws
+        };
+
+        // Every chunk ends at the last position it may end at which fits.
+        let mut bounds = vec![0];
+        let mut last_end = 0;
+        for end in (1..=steps.len())
+            .filter(|pos| *pos == steps.len() || can_begin_chunk(step(*pos).execution_state))
+        {
+            let start = *bounds.last().unwrap();
+            if !fits(start, end) {
+                if last_end == start || !fits(last_end, end) {
+                    return Err(ChunkError::StepsTooLong {
+                        rw_counter: step(last_end).rw_counter,
+                        n_steps: end - last_end,
+                    });
+                }
+                bounds.push(last_end);
+            }
+            last_end = end;
+        }
+        bounds.push(steps.len());
+
+        let state = |pos: usize| {
+            let (tx_idx, step_idx) = steps[pos.min(steps.len() - 1)];
+            let tx = &self.txs[tx_idx];
+            let step = &tx.steps[step_idx];
+            let call = &tx.calls[step.call_index];
+            ChunkState {
+                rw_counter: step.rw_counter,
+                tx_id: tx.id,
+                call_id: call.id,
+                is_root: call.is_root,
+                is_create: call.is_create,
+                code_hash: match call.code_source {
+                    CodeSource::Account(code_hash) => code_hash,
+                },
+                program_counter: step.program_counter,
+                stack_pointer: step.stack_pointer,
+                gas_left: step.gas_left,
+                memory_size: step.memory_size,
+                state_write_counter: step.state_write_counter,
+                // Set with the rw operations of the chunk beginning with the
+                // step, see `Block::chunk`.
+                carried_rws: Word::zero(),
+            }
+        };
+
+        let n_chunks = bounds.len() - 1;
+        let mut chunks = bounds
+            .windows(2)
+            .enumerate()
+            .map(|(index, bound)| {
+                let chunk = Chunk {
+                    index,
+                    n_chunks,
+                    begin: state(bound[0]),
+                    end: state(bound[1]),
+                };
+                self.chunk(&steps, bound[0]..bound[1], chunk)
+            })
+            .collect::<Vec<_>>();
+        // Every chunk but the last one ends with the state the next one begins
+        // with, and hands over the rw operations carried to it.
+        for index in 1..n_chunks {
+            let carried_rws = chunks[index].chunk.as_ref().unwrap().begin.carried_rws;
+            chunks[index - 1].chunk.as_mut().unwrap().end.carried_rws = carried_rws;
+        }
+        Ok(chunks)
+    }
+
+    // Returns the block of the chunk of the steps at `range` of `steps`, whose
+    // begin state gets the fingerprint of the rw operations carried to it.
+    fn chunk(
+        &self,
+        steps: &[(usize, usize)],
+        range: std::ops::Range<usize>,
+        mut chunk: Chunk,
+    ) -> Self {
+        let boundary_step = |pos: usize, execution_state: ExecutionState| {
+            let (tx_idx, step_idx) = steps[pos];
+            ExecStep {
+                execution_state,
+                rw_indices: Vec::new(),
+                gas_cost: 0,
+                opcode: None,
+                aux_data: None,
+                ..self.txs[tx_idx].steps[step_idx].clone()
+            }
+        };
+
+        let mut txs = Vec::new();
+        let mut tx_idx_prev = None;
+        for &(tx_idx, step_idx) in &steps[range.clone()] {
+            if tx_idx_prev != Some(tx_idx) {
+                let mut tx = self.txs[tx_idx].clone();
+                tx.steps.clear();
+                txs.push(tx);
+                tx_idx_prev = Some(tx_idx);
+            }
+            let tx = txs.last_mut().unwrap();
+            tx.steps.push(self.txs[tx_idx].steps[step_idx].clone());
+        }
+        if range.start > 0 {
+            let begin_chunk = boundary_step(range.start, ExecutionState::BeginChunk);
+            txs[0].steps.insert(0, begin_chunk);
+        }
+        if range.end < steps.len() {
+            let end_chunk = boundary_step(range.end, ExecutionState::EndChunk);
+            txs.last_mut().unwrap().steps.push(end_chunk);
+        }
+
+        // The rw operations of the chunk are the ones in the range of rw
+        // counters of its steps and the ones looked up by its steps.
+        let rw_counters_start = if range.start == 0 {
+            0
+        } else {
+            chunk.begin.rw_counter
+        };
+        let rw_counters_end = if range.end < steps.len() {
+            chunk.end.rw_counter
+        } else {
+            usize::MAX
+        };
+        let rw_counters = rw_counters_start..rw_counters_end;
+        let looked_up = txs
+            .iter()
+            .flat_map(|tx| tx.steps.iter())
+            .flat_map(|step| step.rw_indices.iter().copied())
+            .collect::<HashSet<_>>();
+        let mut rws = HashMap::new();
+        let mut indices = HashMap::new();
+        for (tag, tag_rws) in self.rws.0.iter() {
+            let in_chunk = tag_rws
+                .iter()
+                .enumerate()
+                .filter(|(idx, rw)| {
+                    rw_counters.contains(&rw.rw_counter()) || looked_up.contains(&(*tag, *idx))
+                })
+                .collect::<Vec<_>>();
+            let mut chunk_rws = if range.start > 0 {
+                self.carried_rws(*tag, tag_rws, &in_chunk, rw_counters.start)
+            } else {
+                Vec::new()
+            };
+            for (idx, rw) in in_chunk {
+                indices.insert((*tag, idx), chunk_rws.len());
+                chunk_rws.push(rw.clone());
+            }
+            rws.insert(*tag, chunk_rws);
+        }
+        for step in txs.iter_mut().flat_map(|tx| tx.steps.iter_mut()) {
+            for (tag, idx) in step.rw_indices.iter_mut() {
+                *idx = indices[&(*tag, *idx)];
+            }
+        }
+        let rws = RwMap(rws);
+        let carried_rws = carried_rws_fingerprint(&rws, chunk.begin.rw_counter, self.randomness);
+        chunk.begin.carried_rws = Word::from_little_endian(carried_rws.to_repr().as_ref());
+
+        Self {
+            randomness: self.randomness,
+            txs,
+            rws,
+            bytecodes: self.bytecodes.clone(),
+            context: self.context.clone(),
+            chain_config: self.chain_config,
+            keccak_inputs: self.keccak_inputs.clone(),
+            copy_events: self.copy_events.clone(),
+            exp_events: self.exp_events.clone(),
+            circuits_params: self.circuits_params,
+            chunk: Some(chunk),
+        }
+    }
+
+    // Returns the rw operations of tag `tag` done before the rw counter
+    // `begin` which are carried to a chunk whose operations of this tag are
+    // `in_chunk`: the last operation on every state the chunk accesses.  The
+    // logs and receipts are sequences checked from their beginning, so they
+    // are all carried.  The stack positions of a call are checked to be
+    // contiguous, so the ones of all the calls the chunk accesses are carried.
+    fn carried_rws(
+        &self,
+        tag: RwTableTag,
+        tag_rws: &[Rw],
+        in_chunk: &[(usize, &Rw)],
+        begin: usize,
+    ) -> Vec<Rw> {
+        let before = tag_rws.iter().filter(|rw| rw.rw_counter() < begin);
+        if in_chunk.is_empty() || tag == RwTableTag::Start {
+            return Vec::new();
+        }
+        if matches!(tag, RwTableTag::TxLog | RwTableTag::TxReceipt) {
+            return before.map(carried).collect();
+        }
+
+        // The keys of the rw table telling apart the states, of which only the
+        // call id is kept for the stack.
+        let keys = |rw: &Rw| {
+            let row = rw.table_assignment(self.randomness);
+            let keys = [row.tag, row.key1, row.key2, row.key3, row.key4].map(|key| key.to_repr());
+            let n_state_keys = if tag == RwTableTag::Stack { 2 } else { 5 };
+            (keys[..n_state_keys].to_vec(), keys)
+        };
+        let accessed = in_chunk
+            .iter()
+            .map(|(_, rw)| keys(rw).0)
+            .collect::<HashSet<_>>();
+        let mut last = HashMap::new();
+        for rw in before {
+            let (state, rw_keys) = keys(rw);
+            if accessed.contains(&state) {
+                let last_rw = last.entry(rw_keys).or_insert(rw);
+                if last_rw.rw_counter() < rw.rw_counter() {
+                    *last_rw = rw;
+                }
+            }
+        }
+        let mut carried_rws = last.into_values().map(carried).collect::<Vec<_>>();
+        carried_rws.sort_by_key(|rw| rw.rw_counter());
+        carried_rws
+    }
+}
+
+// Returns the sums of the first 0, 1, ..., n values of `values`.
+fn prefix_sums(values: impl Iterator<Item = usize>) -> Vec<usize> {
+    let mut sums = vec![0];
+    for value in values {
+        sums.push(sums.last().unwrap() + value);
+    }
+    sums
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_chunks, Chunk, ChunkError};
+    use crate::{
+        evm_circuit::{
+            step::ExecutionState,
+            test::run_test_circuit_complete_fixed_table,
+            witness::{block_convert, Block},
+        },
+        state_circuit::{StateCircuit, DEFAULT_MAX_DEGREE},
+        test_util::{test_circuits_using_witness_block, BytecodeTestConfig},
+    };
+    use bus_mapping::mock::BlockData;
+    use eth_types::{bytecode, evm_types::OpcodeId, ToScalar, Word};
+    use halo2_proofs::dev::MockProver;
+    use pairing::bn256::Fr;
+
+    fn block() -> Block<Fr> {
+        let mut code = bytecode! {
+            PUSH1(0x2a)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x01)
+            PUSH1(0x02)
+        };
+        for value in 0..8u64 {
+            code.push(1, Word::from(value)).write_op(OpcodeId::POP);
+        }
+        code.append(&bytecode! {
+            ADD
+            POP
+            PUSH1(0x00)
+            MLOAD
+            POP
+            STOP
+        });
+        let block_data =
+            BlockData::new_from_geth_data(mock::new_single_tx_trace_code(&code).unwrap());
+        let mut builder = block_data.new_circuit_input_builder();
+        builder
+            .handle_block(&block_data.eth_block, &block_data.geth_traces)
+            .unwrap();
+        block_convert(&builder.block, &builder.code_db)
+    }
+
+    #[test]
+    fn chunks_are_stitched_and_provable() {
+        let block = block();
+        let chunks = block.chunks(12, usize::MAX).unwrap();
+        assert!(chunks.len() > 2);
+        check_chunks(chunks.iter().map(|chunk| chunk.chunk.as_ref().unwrap())).unwrap();
+
+        for chunk in chunks.iter() {
+            let info = chunk.chunk.as_ref().unwrap();
+            let steps = chunk
+                .txs
+                .iter()
+                .flat_map(|tx| tx.steps.iter())
+                .collect::<Vec<_>>();
+            assert!(steps.len() <= 12);
+            assert_eq!(
+                steps[0].execution_state == ExecutionState::BeginChunk,
+                info.index > 0
+            );
+            assert_eq!(
+                steps.last().unwrap().execution_state == ExecutionState::EndChunk,
+                !info.is_last()
+            );
+            assert_eq!(
+                test_circuits_using_witness_block(chunk.clone(), BytecodeTestConfig::default()),
+                Ok(()),
+                "chunk {}",
+                info.index
+            );
+        }
+
+        // The steps of all the chunks are the ones of the block.
+        let n_steps = |block: &Block<Fr>| {
+            block
+                .txs
+                .iter()
+                .flat_map(|tx| tx.steps.iter())
+                .filter(|step| {
+                    !matches!(
+                        step.execution_state,
+                        ExecutionState::BeginChunk | ExecutionState::EndChunk
+                    )
+                })
+                .count()
+        };
+        assert_eq!(chunks.iter().map(n_steps).sum::<usize>(), n_steps(&block));
+    }
+
+    #[test]
+    fn chunk_states_are_public_inputs() {
+        let chunks = block().chunks(12, usize::MAX).unwrap();
+        assert!(chunks.len() > 2);
+        for tamper in [
+            (|chunk: &mut Chunk| chunk.begin.gas_left += 1) as fn(&mut Chunk),
+            |chunk| chunk.begin.code_hash = chunk.begin.code_hash + 1,
+            |chunk| chunk.end.program_counter += 1,
+        ] {
+            let mut block = chunks[1].clone();
+            tamper(block.chunk.as_mut().unwrap());
+            assert!(run_test_circuit_complete_fixed_table(block).is_err());
+        }
+    }
+
+    #[test]
+    fn carried_rws_are_public_inputs() {
+        type TestStateCircuit = StateCircuit<Fr, true, 2000, 100, 1023, 2000, DEFAULT_MAX_DEGREE>;
+        let chunks = block().chunks(12, usize::MAX).unwrap();
+        let block = &chunks[1];
+        let chunk = block.chunk.as_ref().unwrap();
+        let circuit = TestStateCircuit::new(block.randomness, &block.rws)
+            .unwrap()
+            .with_chunk(Some(chunk));
+        let mut instance = circuit.instance();
+        let carried_rws = chunk.begin.carried_rws.to_scalar().unwrap();
+        assert_ne!(carried_rws, Fr::one());
+        assert_eq!(instance.last().unwrap(), &vec![carried_rws]);
+
+        let k = TestStateCircuit::min_k();
+        let prover = MockProver::<Fr>::run(k, &circuit, instance.clone()).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        instance.last_mut().unwrap()[0] += Fr::one();
+        let prover = MockProver::<Fr>::run(k, &circuit, instance).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn chunks_out_of_order() {
+        let block = block();
+        let mut chunks = block
+            .chunks(12, usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|chunk| chunk.chunk.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            check_chunks(&chunks[..chunks.len() - 1]),
+            Err(ChunkError::Missing {
+                index: chunks.len() - 1
+            })
+        );
+        chunks[1].begin.carried_rws = chunks[1].begin.carried_rws + 1;
+        assert_eq!(
+            check_chunks(&chunks),
+            Err(ChunkError::CarriedRws { index: 1 })
+        );
+        chunks[1].begin.carried_rws = chunks[0].end.carried_rws;
+        chunks[1].begin.gas_left += 1;
+        assert_eq!(
+            check_chunks(&chunks),
+            Err(ChunkError::Discontinuity { index: 1 })
+        );
+        chunks.swap(0, 1);
+        assert_eq!(check_chunks(&chunks), Err(ChunkError::Missing { index: 0 }));
+
+        assert!(matches!(
+            block.chunks(2, usize::MAX),
+            Err(ChunkError::StepsTooLong { .. })
+        ));
+    }
+}
@@ -0,0 +1,178 @@
+//! Saving and loading of witness blocks, so that the witnesses are generated
+//! on one machine and proven on another one, the files being shared directly
+//! or through an object storage.
+//!
+//! A witness block holds no random linear combination: they are computed from
+//! its randomness when the block is assigned to the circuits.  The randomness
+//! of a loaded block may thus be replaced by the challenge of the prover with
+//! [`Block::with_randomness`] before proving it.
+
+use super::Block;
+use eth_types::Field;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+/// Version of the format of the witness files, which is bumped whenever the
+/// witness block changes in a way that breaks the loading of older files.
+pub const WITNESS_FILE_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+#[serde(bound = "F: Field")]
+struct WitnessFileRef<'a, F> {
+    version: u32,
+    block: &'a Block<F>,
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "F: Field")]
+struct WitnessFile<F> {
+    version: u32,
+    block: Block<F>,
+}
+
+// Header of a witness file, which is read first so that a file of another
+// version is reported as such instead of as a malformed block.
+#[derive(Deserialize)]
+struct WitnessFileHeader {
+    version: u32,
+}
+
+/// Error of saving or loading a witness block.
+#[derive(Debug)]
+pub enum WitnessFileError {
+    /// Reading or writing the file failed.
+    Io(io::Error),
+    /// The block couldn't be encoded or decoded.
+    Json(serde_json::Error),
+    /// The file has another version than [`WITNESS_FILE_VERSION`].
+    Version(u32),
+}
+
+impl fmt::Display for WitnessFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "witness file i/o failed: {}", err),
+            Self::Json(err) => write!(f, "invalid witness file: {}", err),
+            Self::Version(version) => write!(
+                f,
+                "witness file of version {}, expected version {}",
+                version, WITNESS_FILE_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WitnessFileError {}
+
+impl From<io::Error> for WitnessFileError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for WitnessFileError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl<F: Field> Block<F> {
+    /// Writes the block to `writer`.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), WitnessFileError> {
+        let file = WitnessFileRef {
+            version: WITNESS_FILE_VERSION,
+            block: self,
+        };
+        Ok(serde_json::to_writer(writer, &file)?)
+    }
+
+    /// Reads a block written by [`Block::write_to`] from `reader`.
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self, WitnessFileError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let header: WitnessFileHeader = serde_json::from_slice(&bytes)?;
+        if header.version != WITNESS_FILE_VERSION {
+            return Err(WitnessFileError::Version(header.version));
+        }
+        let file: WitnessFile<F> = serde_json::from_slice(&bytes)?;
+        debug_assert_eq!(file.version, WITNESS_FILE_VERSION);
+        Ok(file.block)
+    }
+
+    /// Saves the block to the file at `path`, replacing it if it exists.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), WitnessFileError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Loads the block saved to the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WitnessFileError> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Returns the block with the randomness of its random linear
+    /// combinations replaced by `randomness`.
+    pub fn with_randomness(self, randomness: F) -> Self {
+        Self { randomness, ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WitnessFileError, WITNESS_FILE_VERSION};
+    use crate::evm_circuit::witness::{block_convert, Block};
+    use bus_mapping::mock::BlockData;
+    use eth_types::bytecode;
+    use pairing::bn256::Fr as Fp;
+
+    #[test]
+    fn witness_file_roundtrip() {
+        let code = bytecode! {
+            PUSH1(0x20)
+            PUSH1(0x40)
+            MSTORE
+            PUSH1(0x01)
+            SLOAD
+            STOP
+        };
+        let block_data =
+            BlockData::new_from_geth_data(mock::new_single_tx_trace_code(&code).unwrap());
+        let mut builder = block_data.new_circuit_input_builder();
+        builder
+            .handle_block(&block_data.eth_block, &block_data.geth_traces)
+            .unwrap();
+        let block = block_convert(&builder.block, &builder.code_db);
+
+        let mut bytes = Vec::new();
+        block.write_to(&mut bytes).unwrap();
+        let loaded = Block::<Fp>::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(loaded.randomness, block.randomness);
+        assert_eq!(
+            serde_json::to_string(&loaded).unwrap(),
+            serde_json::to_string(&block).unwrap()
+        );
+
+        let randomness = Fp::from(0x100);
+        let loaded = loaded.with_randomness(randomness);
+        assert_eq!(
+            loaded.context.table_assignments(randomness),
+            block.context.table_assignments(randomness)
+        );
+
+        let json = String::from_utf8(bytes).unwrap().replacen(
+            &format!("\"version\":{}", WITNESS_FILE_VERSION),
+            "\"version\":0",
+            1,
+        );
+        assert!(matches!(
+            Block::<Fp>::read_from(json.as_bytes()),
+            Err(WitnessFileError::Version(0))
+        ));
+    }
+}
@@ -0,0 +1,689 @@
+//! The exp circuit implementation.
+//!
+//! It proves the exponentiations `base^exponent (mod 2^256)` done by the EXP
+//! opcode, and assigns the exp table looked up by the EVM circuit.  Each
+//! exponentiation takes `N_ROWS_PER_EXP` rows: a row with the bytes of the
+//! base, a row with the bytes of the exponent, and then a square row followed
+//! by a multiply row for each bit of the exponent, from the most significant
+//! one.  The square row squares the result of the previous bit, and the
+//! multiply row multiplies the square by the base when the bit is set, both
+//! with the constraints of `MulAddWordsGadget` over the 64-bit limbs of the
+//! words.  The bits are accumulated into the high and low 128 bits of the
+//! exponent, and the result is the one of the last multiply row, where the exp
+//! table row is.
+
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    evm_circuit::{
+        param::N_BYTES_WORD,
+        util::{
+            constraint_builder::BaseConstraintBuilder, from_bytes, math_gadget::MulAddWordsGadget,
+            pow_of_two_expr, select, RandomLinearCombination,
+        },
+        witness::{Block, ExpEvent},
+    },
+    exp_table::ExpTable,
+    util::{power_of_randomness_from_instance, Expr},
+};
+use eth_types::{Field, ToLittleEndian, Word, WordExt};
+use halo2_proofs::{
+    circuit::{Layouter, Region, SimpleFloorPlanner},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed},
+    poly::Rotation,
+};
+
+const MAX_DEGREE: usize = 6;
+
+/// Number of rows of an exponentiation, the base and exponent rows followed by
+/// a square and a multiply row for each of the 256 bits of the exponent.
+pub const N_ROWS_PER_EXP: usize = 2 + 2 * 256;
+
+// Number of bytes of the carries of `MulAddWordsGadget`, which are less than
+// 2^72.
+const N_BYTES_CARRY: usize = 9;
+
+// Returns the 64-bit limbs of a word from its little-endian bytes.
+fn limbs<F: Field>(bytes: &[Expression<F>]) -> [Expression<F>; 4] {
+    [0usize, 1, 2, 3].map(|idx| from_bytes::expr(&bytes[8 * idx..8 * (idx + 1)]))
+}
+
+// Constrains `a * b + c == d (mod 2^256)` with the constraints of
+// `MulAddWordsGadget`, where the words are given by their 64-bit limbs, with
+// the carries of the low and high 128 bits.
+fn mul_add_constraints<F: Field>(
+    cb: &mut BaseConstraintBuilder<F>,
+    [a, b, c, d]: [&[Expression<F>; 4]; 4],
+    carry_lo: Expression<F>,
+    carry_hi: Expression<F>,
+) {
+    let (constraints, _) = MulAddWordsGadget::constraints(
+        [a.clone(), b.clone(), c.clone(), d.clone()],
+        carry_lo,
+        carry_hi,
+    );
+    for (name, lhs, rhs) in constraints {
+        cb.require_equal(name, lhs, rhs);
+    }
+}
+
+/// Config of the exp circuit.
+#[derive(Clone, Debug)]
+pub struct ExpCircuitConfig<F> {
+    // Enables all the rows of the exponentiations
+    q_enable: Column<Fixed>,
+    // Enables the base row of each exponentiation
+    q_base: Column<Fixed>,
+    // Enables the exponent row of each exponentiation
+    q_exponent: Column<Fixed>,
+    // Enables the square rows
+    q_square: Column<Fixed>,
+    // Enables the square row of the most significant bit, which squares 1
+    q_first_square: Column<Fixed>,
+    // Enables the multiply rows
+    q_multiply: Column<Fixed>,
+    // Enables the multiply rows of the most significant bit of the high and
+    // low 128 bits of the exponent
+    q_bits_start: Column<Fixed>,
+    // Enables the multiply row of the least significant bit of the high 128
+    // bits of the exponent
+    q_hi_end: Column<Fixed>,
+    // Enables the last multiply row of each exponentiation
+    q_last: Column<Fixed>,
+    exp_table: ExpTable,
+    // Little-endian bytes of the base, of the exponent or of the result of the
+    // row
+    bytes: [Column<Advice>; N_BYTES_WORD],
+    // Little-endian bytes of the carries of the multiplication of the row
+    carry_lo: [Column<Advice>; N_BYTES_CARRY],
+    carry_hi: [Column<Advice>; N_BYTES_CARRY],
+    // Bit of the exponent of the multiply rows
+    bit: Column<Advice>,
+    // Bits of the exponent accumulated on the multiply rows, restarting at
+    // the low 128 bits
+    exponent_acc: Column<Advice>,
+    // 64-bit limbs of the base
+    base: [Column<Advice>; 4],
+    exponent_lo: Column<Advice>,
+    exponent_hi: Column<Advice>,
+    base_rlc: Column<Advice>,
+    exponent_rlc: Column<Advice>,
+    u8_table: Column<Fixed>,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F> Annotate for ExpCircuitConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_config(owner, "exp_table", &self.exp_table);
+        annotations.annotate(owner, "q_enable", self.q_enable);
+        annotations.annotate(owner, "q_base", self.q_base);
+        annotations.annotate(owner, "q_exponent", self.q_exponent);
+        annotations.annotate(owner, "q_square", self.q_square);
+        annotations.annotate(owner, "q_first_square", self.q_first_square);
+        annotations.annotate(owner, "q_multiply", self.q_multiply);
+        annotations.annotate(owner, "q_bits_start", self.q_bits_start);
+        annotations.annotate(owner, "q_hi_end", self.q_hi_end);
+        annotations.annotate(owner, "q_last", self.q_last);
+        annotations.annotate_all(owner, "bytes", self.bytes);
+        annotations.annotate_all(owner, "carry_lo", self.carry_lo);
+        annotations.annotate_all(owner, "carry_hi", self.carry_hi);
+        annotations.annotate(owner, "bit", self.bit);
+        annotations.annotate(owner, "exponent_acc", self.exponent_acc);
+        annotations.annotate_all(owner, "base", self.base);
+        annotations.annotate(owner, "exponent_lo", self.exponent_lo);
+        annotations.annotate(owner, "exponent_hi", self.exponent_hi);
+        annotations.annotate(owner, "base_rlc", self.base_rlc);
+        annotations.annotate(owner, "exponent_rlc", self.exponent_rlc);
+        annotations.annotate(owner, "u8_table", self.u8_table);
+    }
+}
+
+impl<F: Field> ExpCircuitConfig<F> {
+    /// Configure the exp circuit, which assigns the rows of `exp_table`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Expression<F>; 31],
+        exp_table: ExpTable,
+    ) -> Self {
+        let [q_enable, q_base, q_exponent, q_square, q_first_square] =
+            [(); 5].map(|_| meta.fixed_column());
+        let [q_multiply, q_bits_start, q_hi_end, q_last] = [(); 4].map(|_| meta.fixed_column());
+        let bytes = [(); N_BYTES_WORD].map(|_| meta.advice_column());
+        let carry_lo = [(); N_BYTES_CARRY].map(|_| meta.advice_column());
+        let carry_hi = [(); N_BYTES_CARRY].map(|_| meta.advice_column());
+        let [bit, exponent_acc, exponent_lo, exponent_hi, base_rlc, exponent_rlc] =
+            [(); 6].map(|_| meta.advice_column());
+        let base = [(); 4].map(|_| meta.advice_column());
+        let u8_table = meta.fixed_column();
+
+        meta.create_gate("Exp step", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+
+            let q_base = meta.query_fixed(q_base, Rotation::cur());
+            let q_exponent = meta.query_fixed(q_exponent, Rotation::cur());
+            let q_square = meta.query_fixed(q_square, Rotation::cur());
+            let q_first_square = meta.query_fixed(q_first_square, Rotation::cur());
+            let q_multiply = meta.query_fixed(q_multiply, Rotation::cur());
+            let q_bits_start = meta.query_fixed(q_bits_start, Rotation::cur());
+            let q_hi_end = meta.query_fixed(q_hi_end, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+
+            let d_prev = limbs(&bytes.map(|column| meta.query_advice(column, Rotation::prev())));
+            let bytes = bytes.map(|column| meta.query_advice(column, Rotation::cur()));
+            let d = limbs(&bytes);
+            let rlc = RandomLinearCombination::<F, N_BYTES_WORD>::random_linear_combine_expr(
+                bytes,
+                &power_of_randomness,
+            );
+            let carry_lo = from_bytes::expr(
+                &carry_lo.map(|column| meta.query_advice(column, Rotation::cur())),
+            );
+            let carry_hi = from_bytes::expr(
+                &carry_hi.map(|column| meta.query_advice(column, Rotation::cur())),
+            );
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let exponent_acc_prev = meta.query_advice(exponent_acc, Rotation(-2));
+            let exponent_acc = meta.query_advice(exponent_acc, Rotation::cur());
+            let base_prev = base.map(|column| meta.query_advice(column, Rotation::prev()));
+            let base = base.map(|column| meta.query_advice(column, Rotation::cur()));
+            let [exponent_lo, exponent_hi, base_rlc, exponent_rlc] =
+                [exponent_lo, exponent_hi, base_rlc, exponent_rlc].map(|column| {
+                    (
+                        meta.query_advice(column, Rotation::cur()),
+                        meta.query_advice(column, Rotation::prev()),
+                    )
+                });
+            let table = [exp_table.base, exp_table.exponent, exp_table.result]
+                .map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = [1.expr(), 0.expr(), 0.expr(), 0.expr()];
+            let zero = [0.expr(), 0.expr(), 0.expr(), 0.expr()];
+            let radix_64 = pow_of_two_expr(64);
+
+            cb.condition(q_base.clone(), |cb| {
+                for (base, d) in base.iter().zip(d.iter()) {
+                    cb.require_equal("base is the bytes of the row", base.clone(), d.clone());
+                }
+                cb.require_equal(
+                    "base_rlc is the random linear combination of the bytes",
+                    base_rlc.0.clone(),
+                    rlc.clone(),
+                );
+            });
+            cb.condition(1.expr() - q_base, |cb| {
+                for (base, base_prev) in base.iter().zip(base_prev.iter()) {
+                    cb.require_equal("same base", base.clone(), base_prev.clone());
+                }
+                cb.require_equal("same base_rlc", base_rlc.0.clone(), base_rlc.1.clone());
+            });
+            cb.condition(q_exponent, |cb| {
+                cb.require_equal(
+                    "exponent_lo is the low 128 bits of the bytes",
+                    exponent_lo.0.clone(),
+                    d[0].clone() + d[1].clone() * radix_64.clone(),
+                );
+                cb.require_equal(
+                    "exponent_hi is the high 128 bits of the bytes",
+                    exponent_hi.0.clone(),
+                    d[2].clone() + d[3].clone() * radix_64.clone(),
+                );
+                cb.require_equal(
+                    "exponent_rlc is the random linear combination of the bytes",
+                    exponent_rlc.0.clone(),
+                    rlc.clone(),
+                );
+            });
+            cb.condition(q_square.clone() + q_multiply.clone(), |cb| {
+                for (name, column) in [
+                    ("same exponent_lo", &exponent_lo),
+                    ("same exponent_hi", &exponent_hi),
+                    ("same exponent_rlc", &exponent_rlc),
+                ] {
+                    cb.require_equal(name, column.0.clone(), column.1.clone());
+                }
+            });
+
+            // The square of the result of the previous bit, which is 1 for the
+            // most significant bit
+            let result_prev = [0usize, 1, 2, 3].map(|idx| {
+                select::expr(
+                    q_first_square.clone(),
+                    one[idx].clone(),
+                    d_prev[idx].clone(),
+                )
+            });
+            cb.condition(q_square, |cb| {
+                mul_add_constraints(
+                    cb,
+                    [&result_prev, &result_prev, &zero, &d],
+                    carry_lo.clone(),
+                    carry_hi.clone(),
+                );
+            });
+
+            // The square multiplied by the base when the bit is set, or by 1
+            let multiplier = [0usize, 1, 2, 3]
+                .map(|idx| select::expr(bit.clone(), base[idx].clone(), one[idx].clone()));
+            cb.condition(q_multiply, |cb| {
+                cb.require_boolean("bit is boolean", bit.clone());
+                mul_add_constraints(
+                    cb,
+                    [&d_prev, &multiplier, &zero, &d],
+                    carry_lo.clone(),
+                    carry_hi.clone(),
+                );
+                cb.require_equal(
+                    "exponent_acc := exponent_acc_prev ⋅ 2 + bit",
+                    exponent_acc.clone(),
+                    (1.expr() - q_bits_start) * exponent_acc_prev * 2.expr() + bit.clone(),
+                );
+            });
+            cb.condition(q_hi_end, |cb| {
+                cb.require_equal(
+                    "high 128 bits of the exponent",
+                    exponent_acc.clone(),
+                    exponent_hi.0.clone(),
+                );
+            });
+
+            cb.condition(q_last.clone(), |cb| {
+                cb.require_equal(
+                    "low 128 bits of the exponent",
+                    exponent_acc.clone(),
+                    exponent_lo.0.clone(),
+                );
+                for (name, table, value) in [
+                    ("exp table base", &table[0], &base_rlc.0),
+                    ("exp table exponent", &table[1], &exponent_rlc.0),
+                    ("exp table result", &table[2], &rlc),
+                ] {
+                    cb.require_equal(name, table.clone(), value.clone());
+                }
+            });
+            cb.condition(1.expr() - q_last, |cb| {
+                for table in table.iter() {
+                    cb.require_zero("exp table is 0 out of the last row", table.clone());
+                }
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        for column in bytes.iter().chain(carry_lo.iter()).chain(carry_hi.iter()) {
+            meta.lookup_any("Exp byte range", |meta| {
+                vec![(
+                    meta.query_fixed(q_enable, Rotation::cur())
+                        * meta.query_advice(*column, Rotation::cur()),
+                    meta.query_fixed(u8_table, Rotation::cur()),
+                )]
+            });
+        }
+
+        Self {
+            q_enable,
+            q_base,
+            q_exponent,
+            q_square,
+            q_first_square,
+            q_multiply,
+            q_bits_start,
+            q_hi_end,
+            q_last,
+            exp_table,
+            bytes,
+            carry_lo,
+            carry_hi,
+            bit,
+            exponent_acc,
+            base,
+            exponent_lo,
+            exponent_hi,
+            base_rlc,
+            exponent_rlc,
+            u8_table,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "u8 table",
+            |mut region| {
+                for byte in 0..=u8::MAX {
+                    region.assign_fixed(
+                        || "u8 table",
+                        self.u8_table,
+                        byte as usize,
+                        || Ok(F::from(byte as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the exponentiations in `max_exp_events` slots of
+    /// `N_ROWS_PER_EXP` rows, the unused ones with `0^0`.  Returns an error if
+    /// there are more exponentiations than slots.
+    #[tracing::instrument(name = "exp_circuit_assign", skip_all)]
+    pub fn assign_events(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        events: &[ExpEvent],
+        randomness: F,
+        max_exp_events: usize,
+    ) -> Result<(), Error> {
+        if events.len() > max_exp_events {
+            return Err(Error::Synthesis);
+        }
+
+        layouter.assign_region(
+            || "exp circuit",
+            |mut region| {
+                let padding = ExpEvent::new(Word::zero(), Word::zero());
+                for idx in 0..max_exp_events {
+                    let event = events.get(idx).unwrap_or(&padding);
+                    self.assign_event(&mut region, idx * N_ROWS_PER_EXP, event, randomness)?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn assign_event(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        event: &ExpEvent,
+        randomness: F,
+    ) -> Result<(), Error> {
+        let rlc = |word: Word| {
+            RandomLinearCombination::random_linear_combine(word.to_le_bytes(), randomness)
+        };
+        let [exponent_lo, exponent_hi] = event.exponent.to_le_limbs::<2>();
+
+        // The word, the carries, the bit and the accumulated exponent bits of
+        // each row
+        let mut rows = vec![
+            (event.base, [[0; N_BYTES_CARRY]; 2], false, 0),
+            (event.exponent, [[0; N_BYTES_CARRY]; 2], false, 0),
+        ];
+        let mut result = Word::one();
+        let mut exponent_acc = 0u128;
+        for (idx, step) in event.steps().into_iter().enumerate() {
+            let multiplier = if step.bit { event.base } else { Word::one() };
+            exponent_acc = (if idx % 128 == 0 { 0 } else { exponent_acc << 1 }) | step.bit as u128;
+            rows.push((
+                step.square,
+                MulAddWordsGadget::<F>::carries([result, result, Word::zero(), step.square])?,
+                false,
+                0,
+            ));
+            rows.push((
+                step.result,
+                MulAddWordsGadget::<F>::carries([
+                    step.square,
+                    multiplier,
+                    Word::zero(),
+                    step.result,
+                ])?,
+                step.bit,
+                exponent_acc,
+            ));
+            result = step.result;
+        }
+
+        for (idx, (word, [carry_lo, carry_hi], bit, exponent_acc)) in rows.into_iter().enumerate() {
+            let offset = offset + idx;
+            // Index of the bit of the square and multiply rows
+            let bit_idx = idx.checked_sub(2).map(|idx| (idx / 2, idx % 2 == 1));
+            for (name, column, value) in [
+                ("q_enable", self.q_enable, true),
+                ("q_base", self.q_base, idx == 0),
+                ("q_exponent", self.q_exponent, idx == 1),
+                (
+                    "q_square",
+                    self.q_square,
+                    matches!(bit_idx, Some((_, false))),
+                ),
+                (
+                    "q_first_square",
+                    self.q_first_square,
+                    bit_idx == Some((0, false)),
+                ),
+                (
+                    "q_multiply",
+                    self.q_multiply,
+                    matches!(bit_idx, Some((_, true))),
+                ),
+                (
+                    "q_bits_start",
+                    self.q_bits_start,
+                    matches!(bit_idx, Some((0, true)) | Some((128, true))),
+                ),
+                ("q_hi_end", self.q_hi_end, bit_idx == Some((127, true))),
+                ("q_last", self.q_last, bit_idx == Some((255, true))),
+            ] {
+                region.assign_fixed(
+                    || format!("exp circuit {} {}", name, offset),
+                    column,
+                    offset,
+                    || Ok(F::from(value as u64)),
+                )?;
+            }
+
+            for (columns, bytes) in [
+                (&self.bytes[..], &word.to_le_bytes()[..]),
+                (&self.carry_lo[..], &carry_lo[..]),
+                (&self.carry_hi[..], &carry_hi[..]),
+            ] {
+                for (column, byte) in columns.iter().zip(bytes) {
+                    region.assign_advice(
+                        || format!("exp circuit byte {}", offset),
+                        *column,
+                        offset,
+                        || Ok(F::from(*byte as u64)),
+                    )?;
+                }
+            }
+            for (column, limb) in self.base.iter().zip(event.base.0) {
+                region.assign_advice(
+                    || format!("exp circuit base {}", offset),
+                    *column,
+                    offset,
+                    || Ok(F::from(limb)),
+                )?;
+            }
+            for (name, column, value) in [
+                ("bit", self.bit, F::from(bit as u64)),
+                (
+                    "exponent_acc",
+                    self.exponent_acc,
+                    F::from_u128(exponent_acc),
+                ),
+                (
+                    "exponent_lo",
+                    self.exponent_lo,
+                    F::from_u128(exponent_lo.as_u128()),
+                ),
+                (
+                    "exponent_hi",
+                    self.exponent_hi,
+                    F::from_u128(exponent_hi.as_u128()),
+                ),
+                ("base_rlc", self.base_rlc, rlc(event.base)),
+                ("exponent_rlc", self.exponent_rlc, rlc(event.exponent)),
+            ] {
+                region.assign_advice(
+                    || format!("exp circuit {} {}", name, offset),
+                    column,
+                    offset,
+                    || Ok(value),
+                )?;
+            }
+
+            let table_row = if bit_idx == Some((255, true)) {
+                event.table_assignment(randomness)
+            } else {
+                [F::zero(); 3]
+            };
+            self.exp_table.assign(region, offset, table_row)?;
+        }
+        Ok(())
+    }
+}
+
+/// Exp circuit that proves the exponentiations of a block.
+#[derive(Clone, Default, Debug)]
+pub struct ExpCircuit<F> {
+    /// Number of exponentiations that fit in the circuit, each one takes
+    /// `N_ROWS_PER_EXP` rows
+    pub max_exp_events: usize,
+    /// Block with the exponentiations
+    pub block: Block<F>,
+}
+
+impl<F: Field> Circuit<F> for ExpCircuit<F> {
+    type Config = ExpCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            max_exp_events: self.max_exp_events,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let exp_table = ExpTable::construct(meta);
+
+        let power_of_randomness = power_of_randomness_from_instance::<_, 31>(meta);
+
+        ExpCircuitConfig::configure(meta, power_of_randomness, exp_table)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load(&mut layouter)?;
+        config.assign_events(
+            &mut layouter,
+            &self.block.exp_events,
+            self.block.randomness,
+            self.max_exp_events,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExpCircuit;
+    use crate::{
+        evm_circuit::{
+            util::math_gadget::MulAddWordsGadget,
+            witness::{Block, ExpEvent},
+        },
+        gadget::rlc::PowersOfRandomness,
+    };
+    use eth_types::Word;
+    use halo2_proofs::{
+        arithmetic::Field as HaloField,
+        dev::{MockProver, VerifyFailure},
+    };
+    use pairing::bn256::Fr;
+
+    const K: u32 = 11;
+    const MAX_EXP_EVENTS: usize = 3;
+
+    fn circuit(exp_events: Vec<ExpEvent>) -> (ExpCircuit<Fr>, Vec<Vec<Fr>>) {
+        let randomness = Fr::from(0x10000);
+        let circuit = ExpCircuit {
+            max_exp_events: MAX_EXP_EVENTS,
+            block: Block {
+                randomness,
+                exp_events,
+                ..Default::default()
+            },
+        };
+        let power_of_randomness = PowersOfRandomness::new(randomness)
+            .take(31)
+            .into_iter()
+            .map(|power| vec![power; (1 << K) - 64])
+            .collect();
+        (circuit, power_of_randomness)
+    }
+
+    fn verify(exp_events: Vec<ExpEvent>) -> Result<(), Vec<VerifyFailure>> {
+        let (circuit, power_of_randomness) = circuit(exp_events);
+        let prover = MockProver::<Fr>::run(K, &circuit, power_of_randomness).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn exp_event_result() {
+        for (base, exponent, result) in [
+            (Word::from(3), Word::from(5), Word::from(243)),
+            (Word::from(2), Word::from(255), Word::one() << 255),
+            (Word::from(2), Word::from(256), Word::zero()),
+            (Word::zero(), Word::zero(), Word::one()),
+            (Word::MAX, Word::from(3), Word::MAX),
+        ] {
+            assert_eq!(ExpEvent::new(base, exponent).result, result);
+        }
+    }
+
+    #[test]
+    fn mul_add_carries_of_max_words() {
+        // (2^256 - 1)^2 + 2^256 - 1 == 2^512 - 2^256, whose carries fit in the
+        // carry columns
+        assert!(
+            MulAddWordsGadget::<Fr>::carries([Word::MAX, Word::MAX, Word::MAX, Word::zero()])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn exp_circuit_valid() {
+        let exp_events = vec![
+            ExpEvent::new(Word::from(3), Word::from(5)),
+            ExpEvent::new(Word::from(7), (Word::one() << 200) + Word::from(3)),
+            ExpEvent::new(Word::MAX - Word::from(0x1234), Word::MAX),
+        ];
+        assert_eq!(verify(exp_events), Ok(()));
+    }
+
+    #[cfg(feature = "real-prover")]
+    #[test]
+    fn exp_circuit_real_prover() {
+        let (circuit, power_of_randomness) = circuit(vec![
+            ExpEvent::new(Word::from(3), Word::from(5)),
+            ExpEvent::new(Word::MAX - Word::from(0x1234), Word::MAX),
+        ]);
+        crate::test_util::run_real_prover(K, &circuit, power_of_randomness).unwrap();
+    }
+
+    #[test]
+    fn exp_circuit_padding() {
+        assert_eq!(verify(vec![]), Ok(()));
+    }
+
+    #[test]
+    fn exp_circuit_invalid_result() {
+        let mut event = ExpEvent::new(Word::from(3), Word::from(5));
+        event.result = Word::from(242);
+        assert!(verify(vec![event]).is_err());
+    }
+
+    #[test]
+    fn exp_circuit_too_many_events() {
+        let circuit = ExpCircuit {
+            max_exp_events: MAX_EXP_EVENTS,
+            block: Block {
+                exp_events: vec![ExpEvent::new(Word::one(), Word::one()); MAX_EXP_EVENTS + 1],
+                ..Default::default()
+            },
+        };
+        let power_of_randomness = vec![vec![Fr::zero(); (1 << K) - 64]; 31];
+        assert!(MockProver::<Fr>::run(K, &circuit, power_of_randomness).is_err());
+    }
+}
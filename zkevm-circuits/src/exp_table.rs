@@ -0,0 +1,65 @@
+#![allow(missing_docs)]
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Region,
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::evm_circuit::table::LookupTable;
+
+/// The exp table exported by the exp circuit, and looked up by the EVM circuit
+/// for the EXP opcode.  Each exponentiation has a row with the random linear
+/// combinations of its base, exponent and result, all the other rows are 0.
+#[derive(Clone, Copy, Debug)]
+pub struct ExpTable {
+    pub base: Column<Advice>,
+    pub exponent: Column<Advice>,
+    pub result: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F, 3> for ExpTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        [self.base, self.exponent, self.result]
+            .map(Into::into)
+            .to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        ["base", "exponent", "result"].map(String::from).to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 3] {
+        [
+            meta.query_advice(self.base, Rotation::cur()),
+            meta.query_advice(self.exponent, Rotation::cur()),
+            meta.query_advice(self.result, Rotation::cur()),
+        ]
+    }
+}
+
+impl ExpTable {
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            base: meta.advice_column(),
+            exponent: meta.advice_column(),
+            result: meta.advice_column(),
+        }
+    }
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 3],
+    ) -> Result<(), Error> {
+        for (column, value) in [self.base, self.exponent, self.result].iter().zip(row) {
+            region.assign_advice(
+                || "assign exp row on exp table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+}
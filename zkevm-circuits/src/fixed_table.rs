@@ -0,0 +1,213 @@
+//! The fixed table shared by the circuits: the byte ranges and bitwise
+//! operations, the powers of 2 and the per-opcode information, each in the
+//! rows of its [`FixedTableTag`].  The rows are generated here and loaded once
+//! per synthesis, with only the tags the circuit needs.
+
+#![allow(missing_docs)]
+use crate::{
+    evm_circuit::{table::LookupTable, ExecutionState},
+    impl_expr,
+    util::word::Word,
+};
+use eth_types::{Field, U256};
+use halo2_proofs::{
+    circuit::Layouter,
+    plonk::{Any, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug)]
+pub enum FixedTableTag {
+    Range5 = 1,
+    Range16,
+    Range32,
+    Range256,
+    Range512,
+    SignByte,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ResponsibleOpcode,
+    Pow2,
+    ConstantGasCost,
+}
+
+impl FixedTableTag {
+    pub fn iterator() -> impl Iterator<Item = Self> {
+        [
+            Self::Range5,
+            Self::Range16,
+            Self::Range32,
+            Self::Range256,
+            Self::Range512,
+            Self::SignByte,
+            Self::BitwiseAnd,
+            Self::BitwiseOr,
+            Self::BitwiseXor,
+            Self::ResponsibleOpcode,
+            Self::Pow2,
+            Self::ConstantGasCost,
+        ]
+        .iter()
+        .copied()
+    }
+
+    pub fn build<F: Field>(&self) -> Box<dyn Iterator<Item = [F; 4]>> {
+        let tag = F::from(*self as u64);
+        match self {
+            Self::Range5 => {
+                Box::new((0..5).map(move |value| [tag, F::from(value), F::zero(), F::zero()]))
+            }
+            Self::Range16 => {
+                Box::new((0..16).map(move |value| [tag, F::from(value), F::zero(), F::zero()]))
+            }
+            Self::Range32 => {
+                Box::new((0..32).map(move |value| [tag, F::from(value), F::zero(), F::zero()]))
+            }
+            Self::Range256 => {
+                Box::new((0..256).map(move |value| [tag, F::from(value), F::zero(), F::zero()]))
+            }
+            Self::Range512 => {
+                Box::new((0..512).map(move |value| [tag, F::from(value), F::zero(), F::zero()]))
+            }
+            Self::SignByte => Box::new((0..256).map(move |value| {
+                [
+                    tag,
+                    F::from(value),
+                    F::from((value >> 7) * 0xFFu64),
+                    F::zero(),
+                ]
+            })),
+            Self::BitwiseAnd => Box::new((0..256).flat_map(move |lhs| {
+                (0..256).map(move |rhs| [tag, F::from(lhs), F::from(rhs), F::from(lhs & rhs)])
+            })),
+            Self::BitwiseOr => Box::new((0..256).flat_map(move |lhs| {
+                (0..256).map(move |rhs| [tag, F::from(lhs), F::from(rhs), F::from(lhs | rhs)])
+            })),
+            Self::BitwiseXor => Box::new((0..256).flat_map(move |lhs| {
+                (0..256).map(move |rhs| [tag, F::from(lhs), F::from(rhs), F::from(lhs ^ rhs)])
+            })),
+            // (execution state, opcode, index of the opcode among the ones the
+            // execution state is responsible for)
+            Self::ResponsibleOpcode => {
+                Box::new(ExecutionState::iterator().flat_map(move |execution_state| {
+                    execution_state
+                        .responsible_opcodes()
+                        .into_iter()
+                        .enumerate()
+                        .map(move |(index, opcode)| {
+                            [
+                                tag,
+                                F::from(execution_state.as_u64()),
+                                F::from(opcode.as_u64()),
+                                F::from(index as u64),
+                            ]
+                        })
+                }))
+            }
+            // (n, lo, hi) of the 128-bit halves of 2^n
+            Self::Pow2 => Box::new((0..256usize).map(move |n| {
+                let (lo, hi) = Word::<F>::from_u256(U256::one() << n).to_lo_hi();
+                [tag, F::from(n as u64), lo, hi]
+            })),
+            // (opcode, constant gas cost) of the opcodes handled by an
+            // execution state
+            Self::ConstantGasCost => {
+                let gas_costs: BTreeMap<_, _> = ExecutionState::iterator()
+                    .flat_map(|execution_state| execution_state.responsible_opcodes())
+                    .map(|opcode| (opcode.as_u64(), opcode.constant_gas_cost().as_u64()))
+                    .collect();
+                Box::new(
+                    gas_costs
+                        .into_iter()
+                        .map(move |(opcode, gas)| [tag, F::from(opcode), F::from(gas), F::zero()]),
+                )
+            }
+        }
+    }
+}
+
+impl_expr!(FixedTableTag);
+
+/// The fixed table looked up by the evm circuit.  Each row is a `(tag, value1,
+/// value2, value3)`, whose values are the ones of the [`FixedTableTag`].  Its
+/// first row is all zeros, so that disabled lookups always find a row.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedTable {
+    pub tag: Column<Fixed>,
+    pub values: [Column<Fixed>; 3],
+}
+
+impl<F: Field> LookupTable<F, 4> for FixedTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        self.fixed_columns().map(Into::into).to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        ["tag", "value1", "value2", "value3"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 4] {
+        [
+            meta.query_fixed(self.tag, Rotation::cur()),
+            meta.query_fixed(self.values[0], Rotation::cur()),
+            meta.query_fixed(self.values[1], Rotation::cur()),
+            meta.query_fixed(self.values[2], Rotation::cur()),
+        ]
+    }
+}
+
+impl FixedTable {
+    pub fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            tag: meta.fixed_column(),
+            values: [(); 3].map(|_| meta.fixed_column()),
+        }
+    }
+
+    /// Assign the rows of `tags`, after the row of zeros.
+    pub fn load<F: Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        tags: &[FixedTableTag],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "fixed table",
+            |mut region| {
+                for (offset, row) in std::iter::once([F::zero(); 4])
+                    .chain(tags.iter().flat_map(|tag| tag.build()))
+                    .enumerate()
+                {
+                    for (column, value) in self.fixed_columns().iter().zip(row) {
+                        region.assign_fixed(|| "fixed table row", *column, offset, || Ok(value))?;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    fn fixed_columns(&self) -> [Column<Fixed>; 4] {
+        [self.tag, self.values[0], self.values[1], self.values[2]]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FixedTableTag;
+    use pairing::bn256::Fr as Fp;
+
+    #[test]
+    fn pow2_rows() {
+        let rows: Vec<[Fp; 4]> = FixedTableTag::Pow2.build().collect();
+        let tag = Fp::from(FixedTableTag::Pow2 as u64);
+
+        assert_eq!(rows.len(), 256);
+        assert_eq!(rows[0], [tag, Fp::from(0), Fp::from(1), Fp::from(0)]);
+        assert_eq!(rows[130], [tag, Fp::from(130), Fp::from(0), Fp::from(4)]);
+    }
+}
@@ -15,6 +15,14 @@ impl<T, F: FieldExt> Variable<T, F> {
     }
 }
 
+pub(crate) mod binary_number;
+pub(crate) mod comparator;
+pub(crate) mod ecc;
 pub(crate) mod evm_word;
+pub(crate) mod is_equal;
 pub(crate) mod is_zero;
+pub(crate) mod less_than;
 pub(crate) mod monotone;
+pub(crate) mod non_native;
+pub(crate) mod range_check;
+pub(crate) mod rlc;
@@ -0,0 +1,322 @@
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    evm_circuit::util::{and, not},
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Chip, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// A small enum or tag which is encoded in `N` bits.
+pub(crate) trait AsBits<const N: usize> {
+    /// Returns the little-endian bits of the value.
+    fn as_bits(&self) -> [bool; N];
+}
+
+impl<const N: usize> AsBits<N> for usize {
+    fn as_bits(&self) -> [bool; N] {
+        assert!(*self < 1 << N, "{} doesn't fit in {} bits", self, N);
+        array_init::array_init(|idx| (*self >> idx) & 1 == 1)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BinaryNumberConfig<T, const N: usize> {
+    /// Little-endian bits of the value.
+    pub bits: [Column<Advice>; N],
+    _marker: PhantomData<T>,
+}
+
+impl<T, const N: usize> Annotate for BinaryNumberConfig<T, N> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_all(owner, "bits", self.bits);
+    }
+}
+
+impl<T: AsBits<N>, const N: usize> BinaryNumberConfig<T, N> {
+    /// Returns the expression which is 1 when the value at `rotation` is
+    /// `value`, and 0 otherwise.  Its degree is `N`, instead of the number of
+    /// possible values of an interpolated selector.
+    pub fn value_equals<F: Field>(
+        &self,
+        meta: &mut VirtualCells<F>,
+        value: T,
+        rotation: Rotation,
+    ) -> Expression<F> {
+        let bits = self.bits.map(|bit| meta.query_advice(bit, rotation));
+        Self::value_equals_expr(value, bits)
+    }
+
+    /// Returns the expression which is 1 when `bits` encode `value`, and 0
+    /// otherwise.
+    pub fn value_equals_expr<F: Field>(value: T, bits: [Expression<F>; N]) -> Expression<F> {
+        bits_equal(value.as_bits(), bits)
+    }
+
+    /// Returns the expression which is 1 when the value at `rotation` is
+    /// `value`, and 0 when it's another one of `values`.  Only the bits which
+    /// tell `value` apart from the other values are queried, so its degree can
+    /// be lower than `N` when the bits are constrained to be one of `values`.
+    pub fn value_equals_among<F: Field>(
+        &self,
+        meta: &mut VirtualCells<F>,
+        value: T,
+        values: &[T],
+        rotation: Rotation,
+    ) -> Expression<F> {
+        let bits = value.as_bits();
+        let others: Vec<[bool; N]> = values
+            .iter()
+            .map(|other| other.as_bits())
+            .filter(|other| *other != bits)
+            .collect();
+        // The smallest set of bits in which `value` differs from each other
+        // value
+        let mask = (0usize..1 << N)
+            .filter(|mask| {
+                others.iter().all(|other| {
+                    (0..N).any(|idx| (mask >> idx) & 1 == 1 && other[idx] != bits[idx])
+                })
+            })
+            .min_by_key(|mask| mask.count_ones())
+            .unwrap();
+        and::expr((0..N).filter(|idx| (mask >> idx) & 1 == 1).map(|idx| {
+            let bit = meta.query_advice(self.bits[idx], rotation);
+            if bits[idx] {
+                bit
+            } else {
+                not::expr(bit)
+            }
+        }))
+    }
+
+    /// Returns the expression of the value encoded at `rotation`.
+    pub fn value<F: Field>(&self, meta: &mut VirtualCells<F>, rotation: Rotation) -> Expression<F> {
+        self.bits.iter().rev().fold(0.expr(), |acc, bit| {
+            acc * 2.expr() + meta.query_advice(*bit, rotation)
+        })
+    }
+}
+
+// Returns the expression which is 1 when `exprs` are the bits `bits`, and 0
+// otherwise.
+fn bits_equal<F: Field, const N: usize>(
+    bits: [bool; N],
+    exprs: [Expression<F>; N],
+) -> Expression<F> {
+    and::expr(
+        bits.iter()
+            .zip(exprs)
+            .map(|(bit, expr)| if *bit { expr } else { not::expr(expr) }),
+    )
+}
+
+/// BinaryNumberChip encodes a value of a small enum or tag in `N` boolean
+/// advice columns, so that each of its variants can be selected with an
+/// expression of degree `N`.  The bits are constrained to encode `value`, and
+/// to be one of the canonical `values`, so that the selectors of all the
+/// variants sum to 1.
+pub(crate) struct BinaryNumberChip<F, T, const N: usize> {
+    config: BinaryNumberConfig<T, N>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field, T: AsBits<N> + Copy, const N: usize> BinaryNumberChip<F, T, N> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value: Column<Advice>,
+        values: impl IntoIterator<Item = T>,
+    ) -> BinaryNumberConfig<T, N> {
+        let config = BinaryNumberConfig {
+            bits: [(); N].map(|_| meta.advice_column()),
+            _marker: PhantomData,
+        };
+        let canonical: Vec<[bool; N]> = values.into_iter().map(|value| value.as_bits()).collect();
+
+        meta.create_gate("binary number", |meta| {
+            let q_enable = q_enable(meta);
+            let bits = config
+                .bits
+                .map(|bit| meta.query_advice(bit, Rotation::cur()));
+
+            let mut constraints = bits
+                .iter()
+                .map(|bit| bit.clone() * not::expr(bit))
+                .collect::<Vec<_>>();
+            constraints.push(
+                meta.query_advice(value, Rotation::cur()) - config.value(meta, Rotation::cur()),
+            );
+            // Every encoding which isn't a canonical value is disallowed
+            for value in 0usize..1 << N {
+                let bits_value = AsBits::<N>::as_bits(&value);
+                if !canonical.contains(&bits_value) {
+                    constraints.push(bits_equal(bits_value, bits.clone()));
+                }
+            }
+
+            constraints
+                .into_iter()
+                .map(move |poly| q_enable.clone() * poly)
+                .collect::<Vec<_>>()
+        });
+
+        config
+    }
+
+    pub fn construct(config: BinaryNumberConfig<T, N>) -> Self {
+        BinaryNumberChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Witnesses the bits of `value`.
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: &T,
+    ) -> Result<(), Error> {
+        for (idx, (column, bit)) in self.config.bits.iter().zip(value.as_bits()).enumerate() {
+            region.assign_advice(
+                || format!("binary number bit {}", idx),
+                *column,
+                offset,
+                || Ok(F::from(bit as u64)),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: Field, T: AsBits<N> + Copy, const N: usize> Chip<F> for BinaryNumberChip<F, T, N> {
+    type Config = BinaryNumberConfig<T, N>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BinaryNumberChip, BinaryNumberConfig};
+    use eth_types::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
+        poly::Rotation,
+    };
+    use pairing::bn256::Fr as Fp;
+
+    const VALUES: [usize; 5] = [0, 1, 2, 5, 6];
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig {
+        q_enable: Selector,
+        value: Column<Advice>,
+        is_five: Column<Advice>,
+        binary_number: BinaryNumberConfig<usize, 3>,
+    }
+
+    #[derive(Default)]
+    struct TestCircuit {
+        // Values with their bits, which are the bits of the value if `None`
+        values: Vec<(usize, Option<usize>)>,
+    }
+
+    impl<F: Field> Circuit<F> for TestCircuit {
+        type Config = TestCircuitConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q_enable = meta.selector();
+            let [value, is_five] = [(); 2].map(|_| meta.advice_column());
+
+            let binary_number: BinaryNumberConfig<usize, 3> = BinaryNumberChip::configure(
+                meta,
+                |meta| meta.query_selector(q_enable),
+                value,
+                VALUES,
+            );
+
+            meta.create_gate("check value equals", |meta| {
+                let q_enable = meta.query_selector(q_enable);
+                let is_five = meta.query_advice(is_five, Rotation::cur());
+                vec![
+                    q_enable.clone()
+                        * (binary_number.value_equals(meta, 5, Rotation::cur()) - is_five.clone()),
+                    q_enable
+                        * (binary_number.value_equals_among(meta, 5, &VALUES, Rotation::cur())
+                            - is_five),
+                ]
+            });
+
+            Self::Config {
+                q_enable,
+                value,
+                is_five,
+                binary_number,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = BinaryNumberChip::construct(config.binary_number);
+
+            layouter.assign_region(
+                || "witness",
+                |mut region| {
+                    for (offset, (value, bits)) in self.values.iter().enumerate() {
+                        config.q_enable.enable(&mut region, offset)?;
+                        for (column, value) in [
+                            (config.value, *value),
+                            (config.is_five, (*value == 5) as usize),
+                        ] {
+                            region.assign_advice(
+                                || "",
+                                column,
+                                offset,
+                                || Ok(F::from(value as u64)),
+                            )?;
+                        }
+                        chip.assign(&mut region, offset, &bits.unwrap_or(*value))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn verify(values: Vec<(usize, Option<usize>)>) -> bool {
+        let circuit = TestCircuit { values };
+        let prover = MockProver::<Fp>::run(5, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn binary_number_chip() {
+        assert!(verify(VALUES.iter().map(|value| (*value, None)).collect()));
+        // value which isn't canonical
+        assert!(!verify(vec![(3, None)]));
+        // bits which don't encode the value
+        assert!(!verify(vec![(5, Some(6))]));
+    }
+}
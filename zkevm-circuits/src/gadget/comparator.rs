@@ -0,0 +1,246 @@
+use super::{
+    is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
+    less_than::{LtChip, LtConfig, LtInstruction},
+};
+use crate::evm_circuit::util::sum;
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Chip, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+
+pub(crate) trait ComparatorInstruction<F: Field> {
+    /// Given `lhs` and `rhs` of `N_BYTES` bytes, witnesses the cells of the
+    /// less-than chip, and the inverse of the sum of the bytes of `diff`.
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: F,
+        rhs: F,
+    ) -> Result<(), Error>;
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ComparatorConfig<F, const N_BYTES: usize> {
+    pub lt: LtConfig<F, N_BYTES>,
+    pub eq: IsZeroConfig<F>,
+}
+
+impl<F: Field, const N_BYTES: usize> ComparatorConfig<F, N_BYTES> {
+    /// Returns `(lt, eq)` at the row the comparison is enabled at:
+    /// - `lt` is 1 when `lhs < rhs`, and 0 otherwise
+    /// - `eq` is 1 when `lhs == rhs`, and 0 otherwise
+    pub fn expr(&self, meta: &mut VirtualCells<F>) -> (Expression<F>, Expression<F>) {
+        (
+            self.lt.is_lt(meta, Rotation::cur()),
+            self.eq.is_zero_expression.clone(),
+        )
+    }
+}
+
+/// ComparatorChip compares `lhs` and `rhs` of `N_BYTES` bytes, on top of the
+/// [`LtChip`], which can be used for `lhs <= rhs` or `lhs > rhs` too.  Since
+/// the bytes of `diff` are all 0 iff `lhs == rhs`, the equality costs a single
+/// inverse column instead of another comparison.
+pub(crate) struct ComparatorChip<F, const N_BYTES: usize> {
+    config: ComparatorConfig<F, N_BYTES>,
+}
+
+impl<F: Field, const N_BYTES: usize> ComparatorChip<F, N_BYTES> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
+        lhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        rhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        u8_table: Column<Fixed>,
+        diff_inv: Column<Advice>,
+    ) -> ComparatorConfig<F, N_BYTES> {
+        let lt = LtChip::configure(meta, &q_enable, lhs, rhs, u8_table);
+        let eq = IsZeroChip::configure(
+            meta,
+            |meta| q_enable(meta),
+            |meta| sum::expr(&lt.diff(meta, Rotation::cur())),
+            diff_inv,
+        );
+
+        ComparatorConfig { lt, eq }
+    }
+
+    pub fn construct(config: ComparatorConfig<F, N_BYTES>) -> Self {
+        ComparatorChip { config }
+    }
+}
+
+impl<F: Field, const N_BYTES: usize> ComparatorInstruction<F> for ComparatorChip<F, N_BYTES> {
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: F,
+        rhs: F,
+    ) -> Result<(), Error> {
+        let lt_chip = LtChip::construct(self.config.lt);
+        lt_chip.assign(region, offset, lhs, rhs)?;
+
+        let (_, diff) = lt_chip.witness(lhs, rhs);
+        let eq_chip = IsZeroChip::construct(self.config.eq.clone());
+        eq_chip.assign(region, offset, Some(sum::value(&diff)))
+    }
+}
+
+impl<F: Field, const N_BYTES: usize> Chip<F> for ComparatorChip<F, N_BYTES> {
+    type Config = ComparatorConfig<F, N_BYTES>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ComparatorChip, ComparatorConfig, ComparatorInstruction};
+    use eth_types::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Selector},
+        poly::Rotation,
+    };
+    use pairing::bn256::Fr as Fp;
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig<F> {
+        q_enable: Selector,
+        lhs: Column<Advice>,
+        rhs: Column<Advice>,
+        check_lt: Column<Advice>,
+        check_eq: Column<Advice>,
+        u8_table: Column<Fixed>,
+        comparator: ComparatorConfig<F, 1>,
+    }
+
+    #[derive(Default)]
+    struct TestCircuit {
+        // checks[i] = (values[i].0 < values[i].1, values[i].0 == values[i].1)
+        values: Vec<(u64, u64)>,
+        checks: Vec<(bool, bool)>,
+    }
+
+    impl<F: Field> Circuit<F> for TestCircuit {
+        type Config = TestCircuitConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q_enable = meta.complex_selector();
+            let [lhs, rhs, check_lt, check_eq, diff_inv] = [(); 5].map(|_| meta.advice_column());
+            let u8_table = meta.fixed_column();
+
+            let comparator = ComparatorChip::configure(
+                meta,
+                |meta| meta.query_selector(q_enable),
+                |meta| meta.query_advice(lhs, Rotation::cur()),
+                |meta| meta.query_advice(rhs, Rotation::cur()),
+                u8_table,
+                diff_inv,
+            );
+
+            let config = Self::Config {
+                q_enable,
+                lhs,
+                rhs,
+                check_lt,
+                check_eq,
+                u8_table,
+                comparator,
+            };
+
+            meta.create_gate("check comparator", |meta| {
+                let q_enable = meta.query_selector(q_enable);
+                let (lt, eq) = config.comparator.expr(meta);
+                let check_lt = meta.query_advice(check_lt, Rotation::cur());
+                let check_eq = meta.query_advice(check_eq, Rotation::cur());
+                vec![
+                    q_enable.clone() * (lt - check_lt),
+                    q_enable * (eq - check_eq),
+                ]
+            });
+
+            config
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = ComparatorChip::construct(config.comparator.clone());
+
+            layouter.assign_region(
+                || "u8 table",
+                |mut region| {
+                    for idx in 0..256 {
+                        region.assign_fixed(
+                            || "u8 table row",
+                            config.u8_table,
+                            idx,
+                            || Ok(F::from(idx as u64)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+            layouter.assign_region(
+                || "witness",
+                |mut region| {
+                    for (offset, ((lhs, rhs), (lt, eq))) in
+                        self.values.iter().zip(self.checks.iter()).enumerate()
+                    {
+                        config.q_enable.enable(&mut region, offset)?;
+                        for (column, value) in [
+                            (config.lhs, *lhs),
+                            (config.rhs, *rhs),
+                            (config.check_lt, *lt as u64),
+                            (config.check_eq, *eq as u64),
+                        ] {
+                            region.assign_advice(|| "", column, offset, || Ok(F::from(value)))?;
+                        }
+                        chip.assign(&mut region, offset, F::from(*lhs), F::from(*rhs))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn verify(values: Vec<(u64, u64)>, checks: Vec<(bool, bool)>) -> bool {
+        let circuit = TestCircuit { values, checks };
+        let prover = MockProver::<Fp>::run(9, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn comparator_chip() {
+        let values = vec![(1, 2), (2, 1), (7, 7), (0, 255), (0, 0)];
+        let checks = vec![
+            (true, false),
+            (false, false),
+            (false, true),
+            (true, false),
+            (false, true),
+        ];
+        assert!(verify(values.clone(), checks));
+        assert!(!verify(values.clone(), vec![(false, false); 5]));
+        assert!(!verify(values, vec![(false, true); 5]));
+    }
+}
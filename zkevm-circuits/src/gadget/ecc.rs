@@ -0,0 +1,374 @@
+//! Arithmetic of the points of the short Weierstrass curves `y^2 = x^3 + b` of
+//! secp256k1 and BN254: the computation of the witness, and a chip that
+//! constrains it with the non-native field arithmetic of their base fields.
+//!
+//! The chip uses incomplete addition formulas, so it never adds points with
+//! the same x coordinate, and never doubles a point of y coordinate 0.  The
+//! point at infinity has no representation.
+
+use super::non_native::{
+    from_hex, invert, AssignedInteger, Modulus, NonNativeConfig, NonNativeContext, SCALAR_BITS,
+};
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    gadget::range_check::RangeTable,
+};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::AssignedCell,
+    plonk::{ConstraintSystem, Error},
+};
+use num::{BigUint, Integer, One, Zero};
+
+/// A curve the chip computes on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Curve {
+    /// The curve of the Ethereum signatures
+    Secp256k1,
+    /// The curve of the ecAdd, ecMul and ecPairing precompiles, also known as
+    /// alt_bn128
+    Bn254,
+}
+
+impl Curve {
+    /// Returns the modulus of the coordinates of the points.
+    pub(crate) fn base_field(&self) -> Modulus {
+        match self {
+            Self::Secp256k1 => Modulus::Secp256k1Fp,
+            Self::Bn254 => Modulus::Bn254Fq,
+        }
+    }
+
+    /// Returns the order of the group of the points.
+    pub(crate) fn scalar_field(&self) -> Modulus {
+        match self {
+            Self::Secp256k1 => Modulus::Secp256k1Fq,
+            Self::Bn254 => Modulus::Bn254Fr,
+        }
+    }
+
+    // Returns `b` of the equation of the curve.
+    fn b(&self) -> u64 {
+        match self {
+            Self::Secp256k1 => 7,
+            Self::Bn254 => 3,
+        }
+    }
+
+    /// Returns the generator of the curve.
+    pub(crate) fn generator(&self) -> Point {
+        let (x, y) = match self {
+            Self::Secp256k1 => (
+                from_hex("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+                from_hex("483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"),
+            ),
+            Self::Bn254 => (BigUint::one(), BigUint::from(2u64)),
+        };
+        Point { curve: *self, x, y }
+    }
+
+    /// Returns the point with the smallest x coordinate besides the
+    /// generator, whose discrete logarithm is unknown.  It's used as the
+    /// initial value of the accumulator of the scalar multiplications, so that
+    /// they never add the point at infinity.
+    pub(crate) fn aux_generator(&self) -> Point {
+        let generator = self.generator();
+        (1u64..)
+            .filter_map(|x| Point::from_x(*self, BigUint::from(x), false))
+            .find(|point| point.x != generator.x)
+            .unwrap()
+    }
+}
+
+/// An affine point of a curve.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Point {
+    pub(crate) curve: Curve,
+    pub(crate) x: BigUint,
+    pub(crate) y: BigUint,
+}
+
+impl Point {
+    /// Returns the point of `curve` with x coordinate `x` and the given parity
+    /// of its y coordinate, if any.
+    pub(crate) fn from_x(curve: Curve, x: BigUint, is_odd: bool) -> Option<Self> {
+        let p = curve.base_field().value();
+        let y2 = (&x * &x * &x + curve.b()) % &p;
+        // p = 3 mod 4, so the square root is y2^((p + 1) / 4)
+        let y = y2.modpow(&((&p + 1u64) / 4u64), &p);
+        if (&y * &y) % &p != y2 {
+            return None;
+        }
+        let y = if y.is_odd() == is_odd { y } else { &p - y };
+        Some(Self { curve, x, y })
+    }
+
+    /// Returns whether the point is on the curve `y^2 = x^3 + b`.
+    pub(crate) fn is_on_curve(&self) -> bool {
+        let p = self.curve.base_field().value();
+        (&self.y * &self.y) % &p == (&self.x * &self.x * &self.x + self.curve.b()) % &p
+    }
+
+    /// Returns `-self`.
+    pub(crate) fn neg(&self) -> Self {
+        let p = self.curve.base_field().value();
+        Self {
+            curve: self.curve,
+            x: self.x.clone(),
+            y: (&p - &self.y) % &p,
+        }
+    }
+
+    /// Returns `self + other`, or `None` for the point at infinity.
+    pub(crate) fn add(&self, other: &Self) -> Option<Self> {
+        let p = self.curve.base_field().value();
+        let lambda = if self.x == other.x {
+            if (&self.y + &other.y) % &p == BigUint::zero() {
+                return None;
+            }
+            (BigUint::from(3u64) * &self.x * &self.x * invert(&(&self.y * 2u64), &p)) % &p
+        } else {
+            ((&other.y + &p - &self.y) * invert(&((&other.x + &p - &self.x) % &p), &p)) % &p
+        };
+        let x = (&lambda * &lambda + &p * 2u64 - &self.x - &other.x) % &p;
+        let y = (&lambda * ((&self.x + &p - &x) % &p) + &p - &self.y) % &p;
+        Some(Self {
+            curve: self.curve,
+            x,
+            y,
+        })
+    }
+
+    /// Returns `scalar * self`, or `None` for the point at infinity.
+    pub(crate) fn mul(&self, scalar: &BigUint) -> Option<Self> {
+        let add = |lhs: Option<Self>, rhs: Option<Self>| match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => lhs.add(&rhs),
+            (lhs, None) => lhs,
+            (None, rhs) => rhs,
+        };
+        (0..scalar.bits()).rev().fold(None, |acc, idx| {
+            let acc = add(acc.clone(), acc);
+            if scalar.bit(idx) {
+                add(acc, Some(self.clone()))
+            } else {
+                acc
+            }
+        })
+    }
+}
+
+/// A point assigned in the circuit.
+#[derive(Clone, Debug)]
+pub(crate) struct AssignedPoint<F: Field> {
+    pub(crate) curve: Curve,
+    pub(crate) x: AssignedInteger<F>,
+    pub(crate) y: AssignedInteger<F>,
+}
+
+impl<F: Field> AssignedPoint<F> {
+    fn value(&self) -> Point {
+        Point {
+            curve: self.curve,
+            x: self.x.value.clone(),
+            y: self.y.value.clone(),
+        }
+    }
+}
+
+/// Config of the elliptic curve chip.
+#[derive(Clone, Debug)]
+pub(crate) struct EccConfig<F> {
+    /// The non-native field arithmetic chip the points are computed with
+    pub(crate) integer: NonNativeConfig<F>,
+}
+
+impl<F> Annotate for EccConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_config(owner, "integer", &self.integer);
+    }
+}
+
+impl<F: Field> EccConfig<F> {
+    /// Configure the chip, with the bytes looked up in `u8_table`.
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>, u8_table: RangeTable<8>) -> Self {
+        Self {
+            integer: NonNativeConfig::configure(meta, u8_table),
+        }
+    }
+
+    /// Assigns a free point, which is constrained to be on its curve.
+    pub(crate) fn assign_point(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        point: &Point,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let integer = &self.integer;
+        let modulus = point.curve.base_field();
+        let x = integer.assign_witness(ctx, point.x.clone())?;
+        let y = integer.assign_witness(ctx, point.y.clone())?;
+
+        // y^2 = x^3 + b
+        let b = integer.constant(ctx, BigUint::from(point.curve.b()))?;
+        let y2 = integer.mul(ctx, modulus, &y, &y)?;
+        let x2 = integer.mul(ctx, modulus, &x, &x)?;
+        let x3_plus_b = integer.mul_add(ctx, modulus, &x2, &x, &b)?;
+        integer.assert_equal(ctx, &y2, &x3_plus_b)?;
+
+        Ok(AssignedPoint {
+            curve: point.curve,
+            x,
+            y,
+        })
+    }
+
+    fn constant_point(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        point: &Point,
+    ) -> Result<AssignedPoint<F>, Error> {
+        Ok(AssignedPoint {
+            curve: point.curve,
+            x: self.integer.constant(ctx, point.x.clone())?,
+            y: self.integer.constant(ctx, point.y.clone())?,
+        })
+    }
+
+    // Returns the point (x, y) for the slope `lambda` of the line through `p`
+    // and the other point with x coordinate `x_other`.
+    fn point_from_slope(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        lambda: &AssignedInteger<F>,
+        p: &AssignedPoint<F>,
+        x_other: &AssignedInteger<F>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let integer = &self.integer;
+        let modulus = p.curve.base_field();
+        // x = lambda^2 - x_p - x_other
+        let lambda2 = integer.mul(ctx, modulus, lambda, lambda)?;
+        let x = integer.sub(ctx, modulus, &lambda2, &p.x)?;
+        let x = integer.sub(ctx, modulus, &x, x_other)?;
+        // y = lambda * (x_p - x) - y_p
+        let dx = integer.sub(ctx, modulus, &p.x, &x)?;
+        let y = integer.mul(ctx, modulus, lambda, &dx)?;
+        let y = integer.sub(ctx, modulus, &y, &p.y)?;
+        Ok(AssignedPoint {
+            curve: p.curve,
+            x,
+            y,
+        })
+    }
+
+    /// Returns `p + q`, constraining their x coordinates to be different.
+    pub(crate) fn add_point(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        p: &AssignedPoint<F>,
+        q: &AssignedPoint<F>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        debug_assert_eq!(p.curve, q.curve);
+        let integer = &self.integer;
+        let modulus = p.curve.base_field();
+        // lambda = (y_q - y_p) / (x_q - x_p)
+        let dy = integer.sub(ctx, modulus, &q.y, &p.y)?;
+        let dx = integer.sub(ctx, modulus, &q.x, &p.x)?;
+        let dx_inv = integer.invert(ctx, modulus, &dx)?;
+        let lambda = integer.mul(ctx, modulus, &dy, &dx_inv)?;
+        self.point_from_slope(ctx, &lambda, p, &q.x)
+    }
+
+    /// Returns `2 * p`, constraining its y coordinate not to be 0.
+    pub(crate) fn double_point(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        p: &AssignedPoint<F>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let integer = &self.integer;
+        let modulus = p.curve.base_field();
+        // lambda = 3 * x^2 / (2 * y)
+        let three = integer.constant(ctx, BigUint::from(3u64))?;
+        let x2 = integer.mul(ctx, modulus, &p.x, &p.x)?;
+        let numerator = integer.mul(ctx, modulus, &three, &x2)?;
+        let denominator = integer.add(ctx, modulus, &p.y, &p.y)?;
+        let denominator_inv = integer.invert(ctx, modulus, &denominator)?;
+        let lambda = integer.mul(ctx, modulus, &numerator, &denominator_inv)?;
+        self.point_from_slope(ctx, &lambda, p, &p.x)
+    }
+
+    // Returns the point of `points` at the index `bit_0 + 2 * bit_1`.
+    fn select_point(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        bits: [&AssignedCell<F, F>; 2],
+        points: &[AssignedPoint<F>; 4],
+    ) -> Result<AssignedPoint<F>, Error> {
+        let [x, y] = [
+            [&points[0].x, &points[1].x, &points[2].x, &points[3].x],
+            [&points[0].y, &points[1].y, &points[2].y, &points[3].y],
+        ];
+        Ok(AssignedPoint {
+            curve: points[0].curve,
+            x: self.integer.select(ctx, bits, x)?,
+            y: self.integer.select(ctx, bits, y)?,
+        })
+    }
+
+    /// Returns `u1 * G + u2 * p`, where `G` is the generator of the curve of
+    /// `p`.
+    ///
+    /// The scalars are multiplied together bit by bit, starting with an
+    /// accumulator at the auxiliary generator `A`, so that the incomplete
+    /// addition formulas can be used:
+    ///
+    /// `acc = 2 * acc + (A + bit_1 * G + bit_2 * p)`
+    ///
+    /// which results in `(2^257 - 1) * A + u1 * G + u2 * p` at the end.
+    pub(crate) fn mul_add_generator(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        u1: &AssignedInteger<F>,
+        u2: &AssignedInteger<F>,
+        p: &AssignedPoint<F>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let aux = p.curve.aux_generator();
+        let aux_plus_generator = aux.add(&p.curve.generator()).unwrap();
+        let offset = aux
+            .mul(&((BigUint::one() << (SCALAR_BITS + 1)) - 1u64))
+            .unwrap();
+
+        let aux = self.constant_point(ctx, &aux)?;
+        let aux_plus_generator = self.constant_point(ctx, &aux_plus_generator)?;
+        let aux_plus_p = self.add_point(ctx, &aux, p)?;
+        let aux_plus_generator_plus_p = self.add_point(ctx, &aux_plus_generator, p)?;
+        let table = [
+            aux.clone(),
+            aux_plus_generator,
+            aux_plus_p,
+            aux_plus_generator_plus_p,
+        ];
+
+        let bits = self.integer.assign_scalar_bits(ctx, [u1, u2])?;
+        let mut acc = aux;
+        for [bit_1, bit_2] in bits.iter() {
+            acc = self.double_point(ctx, &acc)?;
+            let point = self.select_point(ctx, [bit_1, bit_2], &table)?;
+            acc = self.add_point(ctx, &acc, &point)?;
+        }
+
+        let offset_neg = self.constant_point(ctx, &offset.neg())?;
+        let result = self.add_point(ctx, &acc, &offset_neg)?;
+        debug_assert!(result.value().is_on_curve());
+        Ok(result)
+    }
+
+    /// Returns `scalar * p`, which must not be the point at infinity.
+    pub(crate) fn mul(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        scalar: &AssignedInteger<F>,
+        p: &AssignedPoint<F>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let zero = self.integer.constant(ctx, BigUint::zero())?;
+        self.mul_add_generator(ctx, &zero, scalar, p)
+    }
+}
@@ -7,14 +7,12 @@
 //! looked up. Instead, it will be folded into the bus mapping lookup.
 
 use crate::gadget::Variable;
-use digest::{FixedOutput, Input};
-use eth_types::Field;
+use eth_types::{keccak256, Field};
 use halo2_proofs::{
     circuit::Region,
     plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
     poly::Rotation,
 };
-use sha3::{Digest, Keccak256};
 use std::convert::TryInto;
 
 #[cfg(test)]
@@ -23,12 +21,9 @@ use halo2_proofs::circuit::Layouter;
 // r = hash([0, 1, ..., 255])
 // TODO: Move into crate-level `constants` file.
 pub(crate) fn r<F: Field>() -> F {
-    let mut hasher = Keccak256::new();
-    for byte in 0..=u8::MAX {
-        hasher.process(&[byte]);
-    }
+    let bytes: Vec<u8> = (0..=u8::MAX).collect();
     let mut r = [0; 64];
-    r[..32].copy_from_slice(hasher.fixed_result().as_slice());
+    r[..32].copy_from_slice(&keccak256(&bytes));
     F::from_bytes_wide(&r)
 }
 
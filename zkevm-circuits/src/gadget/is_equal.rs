@@ -0,0 +1,274 @@
+use super::is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction};
+use crate::annotation::{Annotate, ColumnAnnotations};
+use halo2_proofs::{
+    circuit::{Chip, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+use pairing::arithmetic::FieldExt;
+
+pub(crate) trait IsEqualInstruction<F: FieldExt> {
+    /// Given `lhs` and `rhs` to be checked if they are equal, witnesses
+    /// `inv0(lhs - rhs)`.
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: Option<F>,
+        rhs: Option<F>,
+    ) -> Result<(), Error>;
+
+    /// Given the `(offset, lhs, rhs)` to be checked if they are equal,
+    /// witnesses all their `inv0(lhs - rhs)` at the cost of a single field
+    /// inversion.
+    fn assign_batch(
+        &self,
+        region: &mut Region<'_, F>,
+        values: impl IntoIterator<Item = (usize, F, F)>,
+    ) -> Result<(), Error>;
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct IsEqualConfig<F> {
+    pub is_zero: IsZeroConfig<F>,
+}
+
+impl<F> Annotate for IsEqualConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        self.is_zero.annotate(owner, annotations);
+    }
+}
+
+impl<F: FieldExt> IsEqualConfig<F> {
+    /// Returns the expression which is 1 if `lhs == rhs` at the row the check
+    /// is enabled at, and 0 otherwise.
+    pub fn expr(&self) -> Expression<F> {
+        self.is_zero.is_zero_expression.clone()
+    }
+
+    /// Returns the expression which is 1 if `lhs == rhs`, and 0 otherwise,
+    /// when `lhs` and `rhs` are the checked values at `rotation`.
+    pub fn expr_at(
+        &self,
+        meta: &mut VirtualCells<F>,
+        rotation: Rotation,
+        lhs: Expression<F>,
+        rhs: Expression<F>,
+    ) -> Expression<F> {
+        self.is_zero.expr_at(meta, rotation, lhs - rhs)
+    }
+}
+
+/// IsEqualChip checks whether `lhs == rhs`, with the [`IsZeroChip`] of their
+/// difference, so its inverse column can be shared the same way.
+pub(crate) struct IsEqualChip<F> {
+    config: IsEqualConfig<F>,
+}
+
+impl<F: FieldExt> IsEqualChip<F> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        lhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        rhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value_inv: Column<Advice>,
+    ) -> IsEqualConfig<F> {
+        let is_zero = IsZeroChip::configure(
+            meta,
+            q_enable,
+            |meta| {
+                let lhs = lhs(meta);
+                lhs - rhs(meta)
+            },
+            value_inv,
+        );
+
+        IsEqualConfig { is_zero }
+    }
+
+    pub fn construct(config: IsEqualConfig<F>) -> Self {
+        IsEqualChip { config }
+    }
+}
+
+impl<F: FieldExt> IsEqualInstruction<F> for IsEqualChip<F> {
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: Option<F>,
+        rhs: Option<F>,
+    ) -> Result<(), Error> {
+        let is_zero_chip = IsZeroChip::construct(self.config.is_zero.clone());
+        is_zero_chip.assign(region, offset, lhs.zip(rhs).map(|(lhs, rhs)| lhs - rhs))
+    }
+
+    fn assign_batch(
+        &self,
+        region: &mut Region<'_, F>,
+        values: impl IntoIterator<Item = (usize, F, F)>,
+    ) -> Result<(), Error> {
+        let is_zero_chip = IsZeroChip::construct(self.config.is_zero.clone());
+        is_zero_chip.assign_batch(
+            region,
+            values
+                .into_iter()
+                .map(|(offset, lhs, rhs)| (offset, lhs - rhs)),
+        )
+    }
+}
+
+impl<F: FieldExt> Chip<F> for IsEqualChip<F> {
+    type Config = IsEqualConfig<F>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IsEqualChip, IsEqualConfig, IsEqualInstruction};
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
+        poly::Rotation,
+    };
+    use pairing::bn256::Fr as Fp;
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig<F> {
+        q_enable: Selector,
+        lhs: Column<Advice>,
+        rhs: Column<Advice>,
+        check: Column<Advice>,
+        is_equal: IsEqualConfig<F>,
+    }
+
+    #[derive(Default)]
+    struct TestCircuit {
+        // checks[i] = values[i].0 == values[i].1
+        values: Vec<(u64, u64)>,
+        checks: Vec<bool>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for TestCircuit {
+        type Config = TestCircuitConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q_enable = meta.complex_selector();
+            let [lhs, rhs, check, value_inv] = [(); 4].map(|_| meta.advice_column());
+
+            let is_equal = IsEqualChip::configure(
+                meta,
+                |meta| meta.query_selector(q_enable),
+                |meta| meta.query_advice(lhs, Rotation::cur()),
+                |meta| meta.query_advice(rhs, Rotation::cur()),
+                value_inv,
+            );
+
+            let config = Self::Config {
+                q_enable,
+                lhs,
+                rhs,
+                check,
+                is_equal,
+            };
+
+            meta.create_gate("check is_equal", |meta| {
+                let q_enable = meta.query_selector(q_enable);
+                let check_cur = meta.query_advice(check, Rotation::cur());
+                // The check of the next row, with the expression at the next row
+                let check_next = meta.query_advice(check, Rotation::next());
+                let lhs_next = meta.query_advice(lhs, Rotation::next());
+                let rhs_next = meta.query_advice(rhs, Rotation::next());
+                let is_equal_next =
+                    config
+                        .is_equal
+                        .expr_at(meta, Rotation::next(), lhs_next, rhs_next);
+
+                vec![
+                    q_enable.clone() * (config.is_equal.expr() - check_cur),
+                    q_enable * (is_equal_next - check_next),
+                ]
+            });
+
+            config
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = IsEqualChip::construct(config.is_equal.clone());
+
+            layouter.assign_region(
+                || "witness",
+                |mut region| {
+                    for (offset, ((lhs, rhs), check)) in
+                        self.values.iter().zip(self.checks.iter()).enumerate()
+                    {
+                        config.q_enable.enable(&mut region, offset)?;
+                        for (column, value) in [
+                            (config.lhs, *lhs),
+                            (config.rhs, *rhs),
+                            (config.check, *check as u64),
+                        ] {
+                            region.assign_advice(|| "", column, offset, || Ok(F::from(value)))?;
+                        }
+                    }
+                    // The row after the last one is checked too
+                    for column in [config.lhs, config.rhs, config.check] {
+                        region.assign_advice(|| "", column, self.values.len(), || Ok(F::one()))?;
+                    }
+                    chip.assign(
+                        &mut region,
+                        self.values.len(),
+                        Some(F::one()),
+                        Some(F::one()),
+                    )?;
+                    chip.assign_batch(
+                        &mut region,
+                        self.values
+                            .iter()
+                            .enumerate()
+                            .map(|(offset, (lhs, rhs))| (offset, F::from(*lhs), F::from(*rhs))),
+                    )
+                },
+            )
+        }
+    }
+
+    fn verify(values: Vec<(u64, u64)>, checks: Vec<bool>) -> bool {
+        let circuit = TestCircuit { values, checks };
+        let prover = MockProver::<Fp>::run(5, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn is_equal_chip() {
+        let values = vec![(1, 2), (3, 3), (0, 0), (5, 0), (0, 5)];
+        assert!(verify(
+            values.clone(),
+            vec![false, true, true, false, false]
+        ));
+        assert!(!verify(
+            values.clone(),
+            vec![false, false, true, false, false]
+        ));
+        assert!(!verify(values, vec![true, true, true, false, false]));
+    }
+}
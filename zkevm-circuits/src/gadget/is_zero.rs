@@ -1,3 +1,4 @@
+use crate::annotation::{Annotate, ColumnAnnotations};
 use halo2_proofs::{
     circuit::{Chip, Region},
     plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
@@ -16,6 +17,15 @@ pub(crate) trait IsZeroInstruction<F: FieldExt> {
         offset: usize,
         value: Option<F>,
     ) -> Result<(), Error>;
+
+    /// Given the `values` to be checked if they are zero, with the offsets they
+    /// are checked at, witnesses all their `inv0(value)` at the cost of a
+    /// single field inversion.
+    fn assign_batch(
+        &self,
+        region: &mut Region<'_, F>,
+        values: impl IntoIterator<Item = (usize, F)>,
+    ) -> Result<(), Error>;
 }
 
 #[derive(Clone, Debug)]
@@ -26,6 +36,31 @@ pub(crate) struct IsZeroConfig<F> {
     pub is_zero_expression: Expression<F>,
 }
 
+impl<F> Annotate for IsZeroConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate(owner, "value_inv", self.value_inv);
+    }
+}
+
+impl<F: FieldExt> IsZeroConfig<F> {
+    /// Returns the expression which is 1 if `value` is zero, and 0 otherwise,
+    /// when `value` is the checked value at `rotation`, so it can be used in
+    /// gates at other rows than the one the check is enabled at.
+    pub fn expr_at(
+        &self,
+        meta: &mut VirtualCells<F>,
+        rotation: Rotation,
+        value: Expression<F>,
+    ) -> Expression<F> {
+        let value_inv = meta.query_advice(self.value_inv, rotation);
+        Expression::Constant(F::one()) - value * value_inv
+    }
+}
+
+/// IsZeroChip checks whether `value` is zero with its inverse.  Checks which
+/// are never enabled at the same row, like the ones of different tags or
+/// execution states, can share their `value_inv` column, by configuring the
+/// chip once for each of them with the same column.
 pub(crate) struct IsZeroChip<F> {
     config: IsZeroConfig<F>,
 }
@@ -101,6 +136,49 @@ impl<F: FieldExt> IsZeroInstruction<F> for IsZeroChip<F> {
 
         Ok(())
     }
+
+    fn assign_batch(
+        &self,
+        region: &mut Region<'_, F>,
+        values: impl IntoIterator<Item = (usize, F)>,
+    ) -> Result<(), Error> {
+        let (offsets, mut inverses): (Vec<_>, Vec<_>) = values.into_iter().unzip();
+        batch_invert(&mut inverses);
+
+        for (offset, value_inv) in offsets.into_iter().zip(inverses) {
+            region.assign_advice(
+                || "witness inverse of value",
+                self.config.value_inv,
+                offset,
+                || Ok(value_inv),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces each of `values` with its inverse, or 0 if it is zero, with
+/// Montgomery's trick, so that a single field inversion is done.
+pub(crate) fn batch_invert<F: FieldExt>(values: &mut [F]) {
+    // products[i] is the product of the non-zero values before the i-th one
+    let mut products = Vec::with_capacity(values.len());
+    let mut product = F::one();
+    for value in values.iter() {
+        products.push(product);
+        if *value != F::zero() {
+            product *= *value;
+        }
+    }
+
+    let mut product_inv = product.invert().unwrap();
+    for (value, product) in values.iter_mut().zip(products).rev() {
+        if *value != F::zero() {
+            let value_inv = product_inv * product;
+            product_inv *= *value;
+            *value = value_inv;
+        }
+    }
 }
 
 impl<F: FieldExt> Chip<F> for IsZeroChip<F> {
@@ -118,9 +196,9 @@ impl<F: FieldExt> Chip<F> for IsZeroChip<F> {
 
 #[cfg(test)]
 mod test {
-    use super::{IsZeroChip, IsZeroConfig, IsZeroInstruction};
+    use super::{batch_invert, IsZeroChip, IsZeroConfig, IsZeroInstruction};
     use halo2_proofs::{
-        arithmetic::FieldExt,
+        arithmetic::{Field as HaloField, FieldExt},
         circuit::{Layouter, SimpleFloorPlanner},
         dev::MockProver,
         plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
@@ -427,4 +505,14 @@ mod test {
         try_test_circuit_error!(vec![(1, 2), (3, 4), (5, 6)], vec![true, true, true]);
         try_test_circuit_error!(vec![(1, 1), (3, 4), (6, 6)], vec![false, true, false]);
     }
+
+    #[test]
+    fn batch_invert_values() {
+        let values = [3, 0, 1, 7, 0, 1 << 40].map(Fp::from);
+        let mut inverses = values;
+        batch_invert(&mut inverses);
+        for (value, inverse) in values.iter().zip(inverses) {
+            assert_eq!(inverse, value.invert().unwrap_or(Fp::zero()));
+        }
+    }
 }
@@ -0,0 +1,286 @@
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    evm_circuit::util::pow_of_two,
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Chip, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+
+pub(crate) trait LtInstruction<F: Field> {
+    /// Given `lhs` and `rhs` of `N_BYTES` bytes:
+    ///   - witnesses `lt`, which is 1 when `lhs < rhs`, and 0 otherwise
+    ///   - witnesses the bytes of `diff`, which is `lhs - rhs` if `lhs >= rhs`,
+    ///     and `lhs - rhs + 256^N_BYTES` otherwise
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: F,
+        rhs: F,
+    ) -> Result<(), Error>;
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct LtConfig<F, const N_BYTES: usize> {
+    pub lt: Column<Advice>,
+    /// Little-endian bytes of `diff`, each looked up in the u8 table.
+    pub diff: [Column<Advice>; N_BYTES],
+    /// The range of the inputs, `256^N_BYTES`.
+    pub range: F,
+}
+
+impl<F, const N_BYTES: usize> Annotate for LtConfig<F, N_BYTES> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate(owner, "lt", self.lt);
+        annotations.annotate_all(owner, "diff", self.diff);
+    }
+}
+
+impl<F: Field, const N_BYTES: usize> LtConfig<F, N_BYTES> {
+    /// Returns the expression which is 1 when `lhs < rhs` at `rotation`, and
+    /// 0 otherwise, so it can be used in gates at other rows than the one the
+    /// comparison is enabled at.
+    pub fn is_lt(&self, meta: &mut VirtualCells<F>, rotation: Rotation) -> Expression<F> {
+        meta.query_advice(self.lt, rotation)
+    }
+
+    /// Returns the expressions of the bytes of `diff` at `rotation`.
+    pub fn diff(&self, meta: &mut VirtualCells<F>, rotation: Rotation) -> [Expression<F>; N_BYTES] {
+        self.diff.map(|column| meta.query_advice(column, rotation))
+    }
+}
+
+/// LtChip checks whether `lhs < rhs` for values of `N_BYTES` bytes, which must
+/// be less than 31 so that `lhs - rhs + 256^N_BYTES` doesn't wrap around the
+/// field.  The difference is decomposed into bytes range checked against
+/// `u8_table`, a fixed column holding `0..256` loaded by the caller.
+pub(crate) struct LtChip<F, const N_BYTES: usize> {
+    config: LtConfig<F, N_BYTES>,
+}
+
+impl<F: Field, const N_BYTES: usize> LtChip<F, N_BYTES> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
+        lhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        rhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        u8_table: Column<Fixed>,
+    ) -> LtConfig<F, N_BYTES> {
+        let lt = meta.advice_column();
+        let diff = [(); N_BYTES].map(|_| meta.advice_column());
+        let range = pow_of_two(N_BYTES * 8);
+
+        meta.create_gate("lt gate", |meta| {
+            let q_enable = q_enable(meta);
+            let lt = meta.query_advice(lt, Rotation::cur());
+            let diff = diff.iter().rev().fold(0.expr(), |acc, column| {
+                acc * 256u64.expr() + meta.query_advice(*column, Rotation::cur())
+            });
+
+            // lhs - rhs == diff - lt ⋅ range
+            let poly1 = lhs(meta) - rhs(meta) - diff + lt.clone() * Expression::Constant(range);
+            // lt is boolean
+            let poly2 = lt.clone() * (1.expr() - lt);
+
+            vec![q_enable.clone() * poly1, q_enable * poly2]
+        });
+
+        for column in diff {
+            meta.lookup_any("lt diff byte in range", |meta| {
+                let q_enable = q_enable(meta);
+                let byte = meta.query_advice(column, Rotation::cur());
+                vec![(q_enable * byte, meta.query_fixed(u8_table, Rotation::cur()))]
+            });
+        }
+
+        LtConfig { lt, diff, range }
+    }
+
+    pub fn construct(config: LtConfig<F, N_BYTES>) -> Self {
+        LtChip { config }
+    }
+
+    /// Returns whether `lhs < rhs`, with the little-endian bytes of `diff`.
+    pub(crate) fn witness(&self, lhs: F, rhs: F) -> (bool, [u8; N_BYTES]) {
+        let lt = lhs < rhs;
+        let diff = (lhs - rhs) + if lt { self.config.range } else { F::zero() };
+        let repr = diff.to_repr();
+        (lt, array_init::array_init(|idx| repr[idx]))
+    }
+}
+
+impl<F: Field, const N_BYTES: usize> LtInstruction<F> for LtChip<F, N_BYTES> {
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: F,
+        rhs: F,
+    ) -> Result<(), Error> {
+        let config = self.config();
+        let (lt, diff) = self.witness(lhs, rhs);
+
+        region.assign_advice(
+            || "witness lt",
+            config.lt,
+            offset,
+            || Ok(F::from(lt as u64)),
+        )?;
+        for (idx, (column, byte)) in config.diff.iter().zip(diff).enumerate() {
+            region.assign_advice(
+                || format!("witness diff byte {}", idx),
+                *column,
+                offset,
+                || Ok(F::from(byte as u64)),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: Field, const N_BYTES: usize> Chip<F> for LtChip<F, N_BYTES> {
+    type Config = LtConfig<F, N_BYTES>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LtChip, LtConfig, LtInstruction};
+    use eth_types::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Selector},
+        poly::Rotation,
+    };
+    use pairing::bn256::Fr as Fp;
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig<F> {
+        q_enable: Selector,
+        lhs: Column<Advice>,
+        rhs: Column<Advice>,
+        check: Column<Advice>,
+        u8_table: Column<Fixed>,
+        lt: LtConfig<F, 2>,
+    }
+
+    #[derive(Default)]
+    struct TestCircuit {
+        // checks[i] = values[i].0 < values[i].1
+        values: Vec<(u64, u64)>,
+        checks: Vec<bool>,
+    }
+
+    impl<F: Field> Circuit<F> for TestCircuit {
+        type Config = TestCircuitConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q_enable = meta.complex_selector();
+            let [lhs, rhs, check] = [(); 3].map(|_| meta.advice_column());
+            let u8_table = meta.fixed_column();
+
+            let lt = LtChip::configure(
+                meta,
+                |meta| meta.query_selector(q_enable),
+                |meta| meta.query_advice(lhs, Rotation::cur()),
+                |meta| meta.query_advice(rhs, Rotation::cur()),
+                u8_table,
+            );
+
+            meta.create_gate("check lt", |meta| {
+                let q_enable = meta.query_selector(q_enable);
+                let check = meta.query_advice(check, Rotation::cur());
+                vec![q_enable * (lt.is_lt(meta, Rotation::cur()) - check)]
+            });
+
+            Self::Config {
+                q_enable,
+                lhs,
+                rhs,
+                check,
+                u8_table,
+                lt,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = LtChip::construct(config.lt);
+
+            layouter.assign_region(
+                || "u8 table",
+                |mut region| {
+                    for idx in 0..256 {
+                        region.assign_fixed(
+                            || "u8 table row",
+                            config.u8_table,
+                            idx,
+                            || Ok(F::from(idx as u64)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+            layouter.assign_region(
+                || "witness",
+                |mut region| {
+                    for (offset, ((lhs, rhs), check)) in
+                        self.values.iter().zip(self.checks.iter()).enumerate()
+                    {
+                        config.q_enable.enable(&mut region, offset)?;
+                        for (column, value) in [
+                            (config.lhs, *lhs),
+                            (config.rhs, *rhs),
+                            (config.check, *check as u64),
+                        ] {
+                            region.assign_advice(|| "", column, offset, || Ok(F::from(value)))?;
+                        }
+                        chip.assign(&mut region, offset, F::from(*lhs), F::from(*rhs))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn verify(values: Vec<(u64, u64)>, checks: Vec<bool>) -> bool {
+        let circuit = TestCircuit { values, checks };
+        let prover = MockProver::<Fp>::run(9, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn lt_chip() {
+        let values = vec![(1, 2), (2, 1), (5, 5), (0, 65535), (65535, 0)];
+        assert!(verify(
+            values.clone(),
+            vec![true, false, false, true, false]
+        ));
+        assert!(!verify(values, vec![true, true, false, true, false]));
+        // the difference of inputs out of range isn't decomposed in 2 bytes
+        assert!(!verify(vec![(0, 65537)], vec![true]));
+        assert!(!verify(vec![(65536, 0)], vec![false]));
+    }
+}
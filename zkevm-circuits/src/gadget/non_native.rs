@@ -0,0 +1,669 @@
+//! Non-native field arithmetic: the integers modulo the base and scalar fields
+//! of secp256k1 and BN254, and a chip that constrains their arithmetic over
+//! the native field.
+//!
+//! Integers are represented as 4 limbs of 64 bits, little endian.  Every row
+//! of the chip proves `a * b + c = q * m + r` as integers for a modulus `m`,
+//! where `r` and `q` are decomposed into bytes.  The identity is checked
+//! modulo 2^320, by propagating the carries of the limbs, and modulo the
+//! native field, which together imply that it holds over the integers.
+
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    evm_circuit::{table::LookupTable, util::constraint_builder::BaseConstraintBuilder},
+    gadget::range_check::RangeTable,
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{AssignedCell, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells},
+    poly::Rotation,
+};
+use num::{BigInt, BigUint, Integer, One, Zero};
+use std::{collections::HashMap, convert::TryInto, marker::PhantomData};
+
+/// Number of limbs of an integer.
+pub(crate) const N_LIMBS: usize = 4;
+/// Number of bytes of an integer.
+pub(crate) const N_BYTES: usize = 32;
+// Number of bits of a limb.
+const LIMB_BITS: usize = 64;
+// Number of bytes of a carry of the limbs multiplication.
+const N_CARRY_BYTES: usize = 9;
+// The carries are offset by 2^CARRY_OFFSET_BITS to make them positive.
+const CARRY_OFFSET_BITS: usize = 70;
+/// Number of bits of a scalar.
+pub(crate) const SCALAR_BITS: usize = N_LIMBS * LIMB_BITS;
+
+const MAX_DEGREE: usize = 5;
+
+pub(crate) fn from_hex(hex: &str) -> BigUint {
+    BigUint::parse_bytes(hex.as_bytes(), 16).unwrap()
+}
+
+/// A modulus the chip reduces by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Modulus {
+    /// The base field of secp256k1
+    Secp256k1Fp,
+    /// The scalar field of secp256k1
+    Secp256k1Fq,
+    /// The base field of BN254
+    Bn254Fq,
+    /// The scalar field of BN254, which is the native field
+    Bn254Fr,
+}
+
+impl Modulus {
+    const ALL: [Self; 4] = [
+        Self::Secp256k1Fp,
+        Self::Secp256k1Fq,
+        Self::Bn254Fq,
+        Self::Bn254Fr,
+    ];
+
+    /// Returns the value of the modulus.
+    pub(crate) fn value(&self) -> BigUint {
+        match self {
+            Self::Secp256k1Fp => {
+                from_hex("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f")
+            }
+            Self::Secp256k1Fq => {
+                from_hex("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141")
+            }
+            Self::Bn254Fq => {
+                from_hex("30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47")
+            }
+            Self::Bn254Fr => {
+                from_hex("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001")
+            }
+        }
+    }
+}
+
+/// Returns the inverse of `value` modulo the prime `modulus`, or 0 when
+/// `value` is 0.
+pub(crate) fn invert(value: &BigUint, modulus: &BigUint) -> BigUint {
+    value.modpow(&(modulus - 2u64), modulus)
+}
+
+/// Returns the limbs of `value`, which must be less than 2^256.
+pub(crate) fn to_limbs(value: &BigUint) -> [u64; N_LIMBS] {
+    let mut limbs = [0; N_LIMBS];
+    for (limb, digit) in limbs.iter_mut().zip(value.to_u64_digits()) {
+        *limb = digit;
+    }
+    limbs
+}
+
+/// Returns the little endian bytes of `value`, which must be less than
+/// 2^(8 * N).
+pub(crate) fn to_le_bytes<const N: usize>(value: &BigUint) -> [u8; N] {
+    let mut bytes = [0; N];
+    for (byte, digit) in bytes.iter_mut().zip(value.to_bytes_le()) {
+        *byte = digit;
+    }
+    bytes
+}
+
+/// An integer assigned as limbs in the circuit, together with its value.
+#[derive(Clone, Debug)]
+pub(crate) struct AssignedInteger<F: Field> {
+    pub(crate) limbs: [AssignedCell<F, F>; N_LIMBS],
+    pub(crate) value: BigUint,
+}
+
+/// The region the rows of the chip are assigned in.
+pub(crate) struct NonNativeContext<'r, 'a, F: Field> {
+    pub(crate) region: &'r mut Region<'a, F>,
+    // Offset of the next integer row
+    offset: usize,
+    // Offset of the next scalar bits row
+    bits_offset: usize,
+    // Constants already assigned in the region
+    constants: HashMap<BigUint, AssignedInteger<F>>,
+}
+
+impl<'r, 'a, F: Field> NonNativeContext<'r, 'a, F> {
+    pub(crate) fn new(region: &'r mut Region<'a, F>) -> Self {
+        Self {
+            region,
+            offset: 0,
+            bits_offset: 0,
+            constants: HashMap::new(),
+        }
+    }
+}
+
+/// Config of the non-native field arithmetic chip.
+#[derive(Clone, Debug)]
+pub(crate) struct NonNativeConfig<F> {
+    // Enables the byte decomposition of q and r
+    q_enable: Selector,
+    // Enables `a * b + c = q * m + r` for each modulus of `Modulus::ALL`
+    q_mul: [Selector; 4],
+    // Enables the selection of r among a, b, c and q
+    q_select: Selector,
+    // Enables r to be the constant
+    q_constant: Selector,
+    a: [Column<Advice>; N_LIMBS],
+    b: [Column<Advice>; N_LIMBS],
+    c: [Column<Advice>; N_LIMBS],
+    q: [Column<Advice>; N_LIMBS],
+    r: [Column<Advice>; N_LIMBS],
+    q_bytes: [Column<Advice>; N_BYTES],
+    r_bytes: [Column<Advice>; N_BYTES],
+    carry_bytes: [[Column<Advice>; N_CARRY_BYTES]; N_LIMBS + 1],
+    select_bits: [Column<Advice>; 2],
+    constant: [Column<Fixed>; N_LIMBS],
+
+    // Enables the first bit of a limb of a scalar
+    q_bits_first: Selector,
+    // Enables the following bits of a limb of a scalar
+    q_bits_next: Selector,
+    // Bits of two scalars, most significant first
+    scalar_bits: [Column<Advice>; 2],
+    // Accumulated value of the bits of the current limb
+    scalar_acc: [Column<Advice>; 2],
+
+    _marker: PhantomData<F>,
+}
+
+impl<F> Annotate for NonNativeConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        for (name, limbs) in [
+            ("a", self.a),
+            ("b", self.b),
+            ("c", self.c),
+            ("q", self.q),
+            ("r", self.r),
+        ] {
+            annotations.annotate_all(owner, name, limbs);
+        }
+        annotations.annotate_all(owner, "q_bytes", self.q_bytes);
+        annotations.annotate_all(owner, "r_bytes", self.r_bytes);
+        for (idx, carry_bytes) in self.carry_bytes.iter().enumerate() {
+            annotations.annotate_all(owner, &format!("carry_bytes_{}", idx), *carry_bytes);
+        }
+        annotations.annotate_all(owner, "select_bits", self.select_bits);
+        annotations.annotate_all(owner, "constant", self.constant);
+        annotations.annotate_all(owner, "scalar_bits", self.scalar_bits);
+        annotations.annotate_all(owner, "scalar_acc", self.scalar_acc);
+    }
+}
+
+impl<F: Field> NonNativeConfig<F> {
+    /// Configure the chip, with the bytes looked up in `u8_table`.
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>, u8_table: RangeTable<8>) -> Self {
+        let q_enable = meta.complex_selector();
+        let q_mul = [(); 4].map(|_| meta.selector());
+        let q_select = meta.selector();
+        let q_constant = meta.selector();
+        let [a, b, c, q, r] = [(); 5].map(|_| [(); N_LIMBS].map(|_| meta.advice_column()));
+        let q_bytes = [(); N_BYTES].map(|_| meta.advice_column());
+        let r_bytes = [(); N_BYTES].map(|_| meta.advice_column());
+        let carry_bytes =
+            [(); N_LIMBS + 1].map(|_| [(); N_CARRY_BYTES].map(|_| meta.advice_column()));
+        let select_bits = [(); 2].map(|_| meta.advice_column());
+        let constant = [(); N_LIMBS].map(|_| meta.fixed_column());
+        let q_bits_first = meta.selector();
+        let q_bits_next = meta.selector();
+        let scalar_bits = [(); 2].map(|_| meta.advice_column());
+        let scalar_acc = [(); 2].map(|_| meta.advice_column());
+
+        for column in a
+            .iter()
+            .chain(b.iter())
+            .chain(c.iter())
+            .chain(q.iter())
+            .chain(r.iter())
+            .chain(select_bits.iter())
+            .chain(scalar_bits.iter())
+            .chain(scalar_acc.iter())
+        {
+            meta.enable_equality(*column);
+        }
+
+        let two_pow_limb = F::from_u128(1 << LIMB_BITS);
+        let from_limbs = |limbs: &[Expression<F>; N_LIMBS]| {
+            limbs.iter().rev().fold(0.expr(), |acc, limb| {
+                acc * Expression::Constant(two_pow_limb) + limb.clone()
+            })
+        };
+        let from_le_bytes = |meta: &mut VirtualCells<F>, bytes: &[Column<Advice>]| {
+            bytes.iter().rev().fold(0.expr(), |acc, byte| {
+                acc * 256.expr() + meta.query_advice(*byte, Rotation::cur())
+            })
+        };
+
+        meta.create_gate("q and r are decomposed into bytes", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            for (limbs, bytes) in [(q, q_bytes), (r, r_bytes)] {
+                for (limb, bytes) in limbs.iter().zip(bytes.chunks(LIMB_BITS / 8)) {
+                    cb.require_equal(
+                        "limb is decomposed into bytes",
+                        meta.query_advice(*limb, Rotation::cur()),
+                        from_le_bytes(meta, bytes),
+                    );
+                }
+            }
+            cb.gate(meta.query_selector(q_enable))
+        });
+        for byte in q_bytes
+            .iter()
+            .chain(r_bytes.iter())
+            .chain(carry_bytes.iter().flatten())
+        {
+            meta.lookup_any("integer bytes are bytes", |meta| {
+                let q_enable = meta.query_selector(q_enable);
+                let byte = meta.query_advice(*byte, Rotation::cur());
+                let [u8_table] = u8_table.table_exprs(meta);
+
+                vec![(q_enable * byte, u8_table)]
+            });
+        }
+
+        for (q_mul, modulus) in q_mul.iter().zip(Modulus::ALL) {
+            meta.create_gate("a * b + c = q * modulus + r", |meta| {
+                let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+                let [a, b, c, q, r] = [a, b, c, q, r]
+                    .map(|limbs| limbs.map(|limb| meta.query_advice(limb, Rotation::cur())));
+                let modulus_limbs =
+                    to_limbs(&modulus.value()).map(|limb| Expression::Constant(F::from(limb)));
+                let carries = carry_bytes.map(|bytes| {
+                    from_le_bytes(meta, &bytes)
+                        - Expression::Constant(F::from_u128(1 << CARRY_OFFSET_BITS))
+                });
+
+                // The identity holds modulo 2^320, so the lowest 5 limbs of
+                // a * b + c - q * m - r are 0 after propagating the carries.
+                let mut carry_prev = 0.expr();
+                for (idx, carry) in carries.iter().enumerate() {
+                    let mut limb = (0..N_LIMBS)
+                        .filter(|i| idx >= *i && idx - i < N_LIMBS)
+                        .fold(0.expr(), |acc, i| {
+                            acc + a[i].clone() * b[idx - i].clone()
+                                - q[i].clone() * modulus_limbs[idx - i].clone()
+                        });
+                    if idx < N_LIMBS {
+                        limb = limb + c[idx].clone() - r[idx].clone();
+                    }
+                    cb.require_equal(
+                        "limb plus previous carry is carry * 2^64",
+                        limb + carry_prev,
+                        carry.clone() * Expression::Constant(two_pow_limb),
+                    );
+                    carry_prev = carry.clone();
+                }
+
+                // The identity holds modulo the native field.
+                cb.require_equal(
+                    "a * b + c = q * modulus + r in the native field",
+                    from_limbs(&a) * from_limbs(&b) + from_limbs(&c),
+                    from_limbs(&q) * from_limbs(&modulus_limbs) + from_limbs(&r),
+                );
+
+                cb.gate(meta.query_selector(*q_mul))
+            });
+        }
+
+        meta.create_gate("r is selected among a, b, c and q", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            let [bit_0, bit_1] = select_bits.map(|bit| meta.query_advice(bit, Rotation::cur()));
+            for idx in 0..N_LIMBS {
+                let [a, b, c, q, r] =
+                    [a, b, c, q, r].map(|limbs| meta.query_advice(limbs[idx], Rotation::cur()));
+                cb.require_equal(
+                    "r = a, b, c or q for bits 00, 10, 01 or 11",
+                    r,
+                    a.clone()
+                        + bit_0.clone() * (b.clone() - a.clone())
+                        + bit_1.clone() * (c.clone() - a.clone())
+                        + bit_0.clone() * bit_1.clone() * (q - c - b + a),
+                );
+            }
+            cb.gate(meta.query_selector(q_select))
+        });
+
+        meta.create_gate("r is the constant", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            for (r, constant) in r.iter().zip(constant.iter()) {
+                cb.require_equal(
+                    "r limb is the constant limb",
+                    meta.query_advice(*r, Rotation::cur()),
+                    meta.query_fixed(*constant, Rotation::cur()),
+                );
+            }
+            cb.gate(meta.query_selector(q_constant))
+        });
+
+        for (q_bits, is_first) in [(q_bits_first, true), (q_bits_next, false)] {
+            meta.create_gate("scalar bits", |meta| {
+                let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+                for (bit, acc) in scalar_bits.iter().zip(scalar_acc.iter()) {
+                    let bit = meta.query_advice(*bit, Rotation::cur());
+                    let acc_prev = if is_first {
+                        0.expr()
+                    } else {
+                        meta.query_advice(*acc, Rotation::prev())
+                    };
+                    cb.require_boolean("scalar bit is boolean", bit.clone());
+                    cb.require_equal(
+                        "acc = 2 * acc_prev + bit",
+                        meta.query_advice(*acc, Rotation::cur()),
+                        acc_prev * 2.expr() + bit,
+                    );
+                }
+                cb.gate(meta.query_selector(q_bits))
+            });
+        }
+
+        Self {
+            q_enable,
+            q_mul,
+            q_select,
+            q_constant,
+            a,
+            b,
+            c,
+            q,
+            r,
+            q_bytes,
+            r_bytes,
+            carry_bytes,
+            select_bits,
+            constant,
+            q_bits_first,
+            q_bits_next,
+            scalar_bits,
+            scalar_acc,
+            _marker: PhantomData,
+        }
+    }
+
+    // Assigns a row with the operands `a`, `b` and `c`, returning the assigned
+    // `q` and `r`.
+    fn assign_row(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        operands: [Option<&AssignedInteger<F>>; 3],
+        q: BigUint,
+        r: BigUint,
+        carries: [[u8; N_CARRY_BYTES]; N_LIMBS + 1],
+    ) -> Result<(AssignedInteger<F>, AssignedInteger<F>), Error> {
+        let offset = ctx.offset;
+        ctx.offset += 1;
+
+        self.q_enable.enable(ctx.region, offset)?;
+        for (columns, operand) in [self.a, self.b, self.c].iter().zip(operands) {
+            if let Some(operand) = operand {
+                for (column, limb) in columns.iter().zip(operand.limbs.iter()) {
+                    limb.copy_advice(|| "operand limb", ctx.region, *column, offset)?;
+                }
+            }
+        }
+        let mut assign_integer = |columns: [Column<Advice>; N_LIMBS],
+                                  bytes_columns: [Column<Advice>; N_BYTES],
+                                  value: BigUint| {
+            for (column, byte) in bytes_columns.iter().zip(to_le_bytes::<N_BYTES>(&value)) {
+                ctx.region.assign_advice(
+                    || "integer byte",
+                    *column,
+                    offset,
+                    || Ok(F::from(byte as u64)),
+                )?;
+            }
+            let mut limbs = Vec::with_capacity(N_LIMBS);
+            for (column, limb) in columns.iter().zip(to_limbs(&value)) {
+                limbs.push(ctx.region.assign_advice(
+                    || "integer limb",
+                    *column,
+                    offset,
+                    || Ok(F::from(limb)),
+                )?);
+            }
+            Ok::<_, Error>(AssignedInteger {
+                limbs: limbs.try_into().unwrap(),
+                value,
+            })
+        };
+        let q = assign_integer(self.q, self.q_bytes, q)?;
+        let r = assign_integer(self.r, self.r_bytes, r)?;
+        for (columns, carry) in self.carry_bytes.iter().zip(carries) {
+            for (column, byte) in columns.iter().zip(carry) {
+                ctx.region.assign_advice(
+                    || "carry byte",
+                    *column,
+                    offset,
+                    || Ok(F::from(byte as u64)),
+                )?;
+            }
+        }
+
+        Ok((q, r))
+    }
+
+    /// Assigns a free integer less than 2^256.
+    pub(crate) fn assign_witness(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        value: BigUint,
+    ) -> Result<AssignedInteger<F>, Error> {
+        let (_, r) = self.assign_row(
+            ctx,
+            [None, None, None],
+            BigUint::zero(),
+            value,
+            Default::default(),
+        )?;
+        Ok(r)
+    }
+
+    /// Assigns a constant, which is assigned only once per region.
+    pub(crate) fn constant(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        value: BigUint,
+    ) -> Result<AssignedInteger<F>, Error> {
+        if let Some(constant) = ctx.constants.get(&value) {
+            return Ok(constant.clone());
+        }
+
+        let offset = ctx.offset;
+        self.q_constant.enable(ctx.region, offset)?;
+        for (column, limb) in self.constant.iter().zip(to_limbs(&value)) {
+            ctx.region
+                .assign_fixed(|| "constant limb", *column, offset, || Ok(F::from(limb)))?;
+        }
+        let (_, constant) = self.assign_row(
+            ctx,
+            [None, None, None],
+            BigUint::zero(),
+            value.clone(),
+            Default::default(),
+        )?;
+        ctx.constants.insert(value, constant.clone());
+
+        Ok(constant)
+    }
+
+    /// Constrains `a` and `b` to be the same integer.
+    pub(crate) fn assert_equal(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        a: &AssignedInteger<F>,
+        b: &AssignedInteger<F>,
+    ) -> Result<(), Error> {
+        for (a, b) in a.limbs.iter().zip(b.limbs.iter()) {
+            ctx.region.constrain_equal(a.cell(), b.cell())?;
+        }
+        Ok(())
+    }
+
+    /// Returns `(a * b + c) mod modulus`.
+    pub(crate) fn mul_add(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        modulus: Modulus,
+        a: &AssignedInteger<F>,
+        b: &AssignedInteger<F>,
+        c: &AssignedInteger<F>,
+    ) -> Result<AssignedInteger<F>, Error> {
+        let m = modulus.value();
+        let (q, r) = (&a.value * &b.value + &c.value).div_rem(&m);
+
+        // The carries of the limbs of a * b + c - q * m - r, in the lowest 5
+        // limbs.
+        let [a_limbs, b_limbs, c_limbs, q_limbs, r_limbs, m_limbs] =
+            [&a.value, &b.value, &c.value, &q, &r, &m].map(to_limbs);
+        let mut carries = [[0; N_CARRY_BYTES]; N_LIMBS + 1];
+        let mut carry_prev = BigInt::zero();
+        for (idx, carry_bytes) in carries.iter_mut().enumerate() {
+            let mut limb = carry_prev;
+            for i in (0..N_LIMBS).filter(|i| idx >= *i && idx - i < N_LIMBS) {
+                limb += BigInt::from(a_limbs[i]) * b_limbs[idx - i];
+                limb -= BigInt::from(q_limbs[i]) * m_limbs[idx - i];
+            }
+            if idx < N_LIMBS {
+                limb += BigInt::from(c_limbs[idx]) - r_limbs[idx];
+            }
+            let carry = limb >> LIMB_BITS;
+            *carry_bytes = to_le_bytes(
+                &(&carry + (BigInt::one() << CARRY_OFFSET_BITS))
+                    .to_biguint()
+                    .unwrap(),
+            );
+            carry_prev = carry;
+        }
+
+        let selector = self.q_mul[Modulus::ALL.iter().position(|m| *m == modulus).unwrap()];
+        selector.enable(ctx.region, ctx.offset)?;
+        let (_, r) = self.assign_row(ctx, [Some(a), Some(b), Some(c)], q, r, carries)?;
+        Ok(r)
+    }
+
+    /// Returns `(a * b) mod modulus`.
+    pub(crate) fn mul(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        modulus: Modulus,
+        a: &AssignedInteger<F>,
+        b: &AssignedInteger<F>,
+    ) -> Result<AssignedInteger<F>, Error> {
+        let zero = self.constant(ctx, BigUint::zero())?;
+        self.mul_add(ctx, modulus, a, b, &zero)
+    }
+
+    /// Returns `(a + b) mod modulus`.
+    pub(crate) fn add(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        modulus: Modulus,
+        a: &AssignedInteger<F>,
+        b: &AssignedInteger<F>,
+    ) -> Result<AssignedInteger<F>, Error> {
+        let one = self.constant(ctx, BigUint::one())?;
+        self.mul_add(ctx, modulus, &one, a, b)
+    }
+
+    /// Returns `(a - b) mod modulus`.
+    pub(crate) fn sub(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        modulus: Modulus,
+        a: &AssignedInteger<F>,
+        b: &AssignedInteger<F>,
+    ) -> Result<AssignedInteger<F>, Error> {
+        let minus_one = self.constant(ctx, modulus.value() - 1u64)?;
+        self.mul_add(ctx, modulus, &minus_one, b, a)
+    }
+
+    /// Returns the inverse of `a` modulo `modulus`, which also constrains `a`
+    /// not to be 0.
+    pub(crate) fn invert(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        modulus: Modulus,
+        a: &AssignedInteger<F>,
+    ) -> Result<AssignedInteger<F>, Error> {
+        let one = self.constant(ctx, BigUint::one())?;
+        let a_inv = self.assign_witness(ctx, invert(&a.value, &modulus.value()))?;
+        let product = self.mul(ctx, modulus, a, &a_inv)?;
+        self.assert_equal(ctx, &product, &one)?;
+        Ok(a_inv)
+    }
+
+    /// Returns the integer of `integers` at the index `bit_0 + 2 * bit_1`.
+    pub(crate) fn select(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        [bit_0, bit_1]: [&AssignedCell<F, F>; 2],
+        integers: [&AssignedInteger<F>; 4],
+    ) -> Result<AssignedInteger<F>, Error> {
+        let index = bit_0.value().map_or(0, |bit| (*bit == F::one()) as usize)
+            + 2 * bit_1.value().map_or(0, |bit| (*bit == F::one()) as usize);
+        let offset = ctx.offset;
+        self.q_select.enable(ctx.region, offset)?;
+        for (column, bit) in self.select_bits.iter().zip([bit_0, bit_1]) {
+            bit.copy_advice(|| "select bit", ctx.region, *column, offset)?;
+        }
+        let [a, b, c, q] = integers;
+        let (q_assigned, r) = self.assign_row(
+            ctx,
+            [Some(a), Some(b), Some(c)],
+            q.value.clone(),
+            integers[index].value.clone(),
+            Default::default(),
+        )?;
+        self.assert_equal(ctx, &q_assigned, q)?;
+        Ok(r)
+    }
+
+    /// Assigns the bits of the scalars, returning the bit cells, most
+    /// significant first.
+    pub(crate) fn assign_scalar_bits(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        scalars: [&AssignedInteger<F>; 2],
+    ) -> Result<Vec<[AssignedCell<F, F>; 2]>, Error> {
+        let mut bits = Vec::with_capacity(SCALAR_BITS);
+        for limb_idx in (0..N_LIMBS).rev() {
+            let mut accs = scalars.map(|_| 0u64);
+            for bit_idx in (0..LIMB_BITS).rev() {
+                let offset = ctx.bits_offset;
+                ctx.bits_offset += 1;
+
+                let selector = if bit_idx == LIMB_BITS - 1 {
+                    self.q_bits_first
+                } else {
+                    self.q_bits_next
+                };
+                selector.enable(ctx.region, offset)?;
+                let mut row_bits = Vec::with_capacity(2);
+                for (idx, scalar) in scalars.iter().enumerate() {
+                    let bit = (to_limbs(&scalar.value)[limb_idx] >> bit_idx) & 1;
+                    accs[idx] = (accs[idx] << 1) | bit;
+                    row_bits.push(ctx.region.assign_advice(
+                        || "scalar bit",
+                        self.scalar_bits[idx],
+                        offset,
+                        || Ok(F::from(bit)),
+                    )?);
+                    let acc = ctx.region.assign_advice(
+                        || "scalar acc",
+                        self.scalar_acc[idx],
+                        offset,
+                        || Ok(F::from(accs[idx])),
+                    )?;
+                    if bit_idx == 0 {
+                        ctx.region
+                            .constrain_equal(acc.cell(), scalar.limbs[limb_idx].cell())?;
+                    }
+                }
+                bits.push(row_bits.try_into().unwrap());
+            }
+        }
+        Ok(bits)
+    }
+}
@@ -0,0 +1,277 @@
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    evm_circuit::table::LookupTable,
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Chip, Layouter, Region},
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// A fixed column holding all the values of `K` bits, `0..2^K`, which can be
+/// shared by all the range checks of `K`-bit chunks of a circuit.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RangeTable<const K: usize>(pub Column<Fixed>);
+
+impl<const K: usize> RangeTable<K> {
+    /// Construct a new RangeTable
+    pub fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self(meta.fixed_column())
+    }
+
+    /// Assign the values `0..2^K` to the table.
+    pub fn load<F: Field>(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || format!("u{} table", K),
+            |mut region| {
+                for idx in 0..1 << K {
+                    region.assign_fixed(
+                        || format!("u{} table row", K),
+                        self.0,
+                        idx,
+                        || Ok(F::from(idx as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+impl<F: Field, const K: usize> LookupTable<F, 1> for RangeTable<K> {
+    fn columns(&self) -> Vec<Column<Any>> {
+        vec![self.0.into()]
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        vec![format!("u{}", K)]
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 1] {
+        [meta.query_fixed(self.0, Rotation::cur())]
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RangeCheckConfig<F, const K: usize, const N_CHUNKS: usize> {
+    /// Little-endian `K`-bit chunks of the value, each looked up in the range
+    /// table.
+    pub chunks: [Column<Advice>; N_CHUNKS],
+    _marker: PhantomData<F>,
+}
+
+impl<F, const K: usize, const N_CHUNKS: usize> Annotate for RangeCheckConfig<F, K, N_CHUNKS> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_all(owner, "chunks", self.chunks);
+    }
+}
+
+impl<F: Field, const K: usize, const N_CHUNKS: usize> RangeCheckConfig<F, K, N_CHUNKS> {
+    /// Returns the expressions of the chunks at `rotation`.
+    pub fn chunks(
+        &self,
+        meta: &mut VirtualCells<F>,
+        rotation: Rotation,
+    ) -> [Expression<F>; N_CHUNKS] {
+        self.chunks
+            .map(|column| meta.query_advice(column, rotation))
+    }
+}
+
+/// RangeCheckChip checks that `value` is less than `2^(K ⋅ N_CHUNKS)`, by
+/// decomposing it into `N_CHUNKS` chunks of `K` bits, each looked up in a
+/// [`RangeTable`] of `K` bits.  The chunk width is picked per instantiation,
+/// so the same chip covers byte decompositions against the u8 table and
+/// wider chunks against bigger tables, when the circuit has the rows for it.
+pub(crate) struct RangeCheckChip<F, const K: usize, const N_CHUNKS: usize> {
+    config: RangeCheckConfig<F, K, N_CHUNKS>,
+}
+
+impl<F: Field, const K: usize, const N_CHUNKS: usize> RangeCheckChip<F, K, N_CHUNKS> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        range_table: RangeTable<K>,
+    ) -> RangeCheckConfig<F, K, N_CHUNKS> {
+        // The composition of the chunks must not wrap around the field, and
+        // the chunks are witnessed as u64.
+        assert!(K < 64 && K * N_CHUNKS <= 248);
+
+        let config = RangeCheckConfig {
+            chunks: [(); N_CHUNKS].map(|_| meta.advice_column()),
+            _marker: PhantomData,
+        };
+
+        meta.create_gate("range check value", |meta| {
+            let q_enable = q_enable(meta);
+            let value = value(meta);
+            let composed = config
+                .chunks(meta, Rotation::cur())
+                .iter()
+                .rev()
+                .fold(0.expr(), |acc, chunk| {
+                    acc * Expression::Constant(F::from(1 << K)) + chunk.clone()
+                });
+
+            vec![q_enable * (value - composed)]
+        });
+
+        for column in config.chunks {
+            meta.lookup_any("range check chunk in range", |meta| {
+                let q_enable = q_enable(meta);
+                let chunk = meta.query_advice(column, Rotation::cur());
+                let [range] = range_table.table_exprs(meta);
+                vec![(q_enable * chunk, range)]
+            });
+        }
+
+        config
+    }
+
+    pub fn construct(config: RangeCheckConfig<F, K, N_CHUNKS>) -> Self {
+        RangeCheckChip { config }
+    }
+
+    /// Returns the little-endian `K`-bit chunks of `value`, ignoring its bits
+    /// above `K ⋅ N_CHUNKS`.
+    pub(crate) fn witness(value: F) -> [u64; N_CHUNKS] {
+        let repr = value.to_repr();
+        array_init::array_init(|idx| {
+            (0..K).fold(0, |acc, bit| {
+                let position = idx * K + bit;
+                acc | (((repr[position / 8] >> (position % 8)) & 1) as u64) << bit
+            })
+        })
+    }
+
+    /// Witnesses the chunks of `value`.
+    pub fn assign(&self, region: &mut Region<'_, F>, offset: usize, value: F) -> Result<(), Error> {
+        for (idx, (column, chunk)) in self
+            .config
+            .chunks
+            .iter()
+            .zip(Self::witness(value))
+            .enumerate()
+        {
+            region.assign_advice(
+                || format!("range check chunk {}", idx),
+                *column,
+                offset,
+                || Ok(F::from(chunk)),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: Field, const K: usize, const N_CHUNKS: usize> Chip<F> for RangeCheckChip<F, K, N_CHUNKS> {
+    type Config = RangeCheckConfig<F, K, N_CHUNKS>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RangeCheckChip, RangeCheckConfig, RangeTable};
+    use eth_types::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
+        poly::Rotation,
+    };
+    use pairing::bn256::Fr as Fp;
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig<F> {
+        q_enable: Selector,
+        value: Column<Advice>,
+        u4_table: RangeTable<4>,
+        range_check: RangeCheckConfig<F, 4, 3>,
+    }
+
+    #[derive(Default)]
+    struct TestCircuit {
+        values: Vec<u64>,
+    }
+
+    impl<F: Field> Circuit<F> for TestCircuit {
+        type Config = TestCircuitConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q_enable = meta.complex_selector();
+            let value = meta.advice_column();
+            let u4_table = RangeTable::construct(meta);
+
+            let range_check = RangeCheckChip::configure(
+                meta,
+                |meta| meta.query_selector(q_enable),
+                |meta| meta.query_advice(value, Rotation::cur()),
+                u4_table,
+            );
+
+            Self::Config {
+                q_enable,
+                value,
+                u4_table,
+                range_check,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(config.range_check);
+
+            config.u4_table.load(&mut layouter)?;
+            layouter.assign_region(
+                || "witness",
+                |mut region| {
+                    for (offset, value) in self.values.iter().enumerate() {
+                        config.q_enable.enable(&mut region, offset)?;
+                        region.assign_advice(
+                            || "value",
+                            config.value,
+                            offset,
+                            || Ok(F::from(*value)),
+                        )?;
+                        chip.assign(&mut region, offset, F::from(*value))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn verify(values: Vec<u64>) -> bool {
+        let circuit = TestCircuit { values };
+        let prover = MockProver::<Fp>::run(6, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn range_check_chip() {
+        assert!(verify(vec![0, 1, 15, 16, 0xabc, 4095]));
+        // values of more than 12 bits aren't decomposed in 3 chunks of 4 bits
+        assert!(!verify(vec![4096]));
+        assert!(!verify(vec![0xfffff]));
+    }
+}
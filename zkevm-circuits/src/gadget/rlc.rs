@@ -0,0 +1,216 @@
+//! Random linear combinations of byte sequences, accumulated from the first
+//! byte: the RLC of `b_0, b_1, ..., b_{n-1}` is
+//! `b_0 ⋅ r^(n-1) + b_1 ⋅ r^(n-2) + ... + b_{n-1}`, which is the way the
+//! bytecode, keccak, tx and copy tables encode their byte sequences.
+use crate::evm_circuit::util::not;
+use halo2_proofs::{
+    circuit::{Chip, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+use pairing::arithmetic::FieldExt;
+
+/// Returns the random linear combination of `bytes`.
+pub fn value<'a, F: FieldExt>(bytes: impl IntoIterator<Item = &'a u8>, randomness: F) -> F {
+    extend(F::zero(), bytes, randomness)
+}
+
+/// Returns the random linear combination `acc` extended with `bytes`, as if
+/// they were appended to the bytes `acc` is the RLC of.
+pub fn extend<'a, F: FieldExt>(
+    acc: F,
+    bytes: impl IntoIterator<Item = &'a u8>,
+    randomness: F,
+) -> F {
+    bytes
+        .into_iter()
+        .fold(acc, |acc, byte| acc * randomness + F::from(*byte as u64))
+}
+
+/// Returns the running random linear combinations of `bytes`, which are the
+/// RLCs of all the non-empty prefixes of `bytes`.
+pub fn running<'a, F: FieldExt>(bytes: impl IntoIterator<Item = &'a u8>, randomness: F) -> Vec<F> {
+    bytes
+        .into_iter()
+        .scan(F::zero(), |acc, byte| {
+            *acc = *acc * randomness + F::from(*byte as u64);
+            Some(*acc)
+        })
+        .collect()
+}
+
+/// The powers of the randomness, `r, r^2, ...`, which are computed once and
+/// cached, instead of exponentiating the randomness for every use.
+#[derive(Clone, Debug)]
+pub struct PowersOfRandomness<F> {
+    randomness: F,
+    // powers[i] = r^(i + 1)
+    powers: Vec<F>,
+}
+
+impl<F: FieldExt> PowersOfRandomness<F> {
+    /// Construct the powers of `randomness`.
+    pub fn new(randomness: F) -> Self {
+        Self {
+            randomness,
+            powers: vec![randomness],
+        }
+    }
+
+    /// Returns `r^exp`.
+    pub fn pow(&mut self, exp: usize) -> F {
+        if exp == 0 {
+            return F::one();
+        }
+        while self.powers.len() < exp {
+            let power = *self.powers.last().unwrap() * self.randomness;
+            self.powers.push(power);
+        }
+        self.powers[exp - 1]
+    }
+
+    /// Returns the first `n` powers, `r, r^2, ..., r^n`.
+    pub fn take(&mut self, n: usize) -> Vec<F> {
+        self.pow(n);
+        self.powers[..n].to_vec()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RunningRlcConfig {
+    /// The running random linear combination of the bytes.
+    pub acc: Column<Advice>,
+}
+
+impl RunningRlcConfig {
+    /// Returns the expression of the running RLC at `rotation`.
+    pub fn expr<F: FieldExt>(
+        &self,
+        meta: &mut VirtualCells<F>,
+        rotation: Rotation,
+    ) -> Expression<F> {
+        meta.query_advice(self.acc, rotation)
+    }
+}
+
+/// RunningRlcChip accumulates the random linear combination of a byte
+/// sequence laid out one byte per row, in `acc`: it is the byte at the row
+/// a sequence starts at, and `acc_prev ⋅ r + byte` at the rows which continue
+/// the sequence of the previous one.
+pub(crate) struct RunningRlcChip<F> {
+    config: RunningRlcConfig,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> RunningRlcChip<F> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        q_continue: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        byte: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        randomness: Expression<F>,
+        acc: Column<Advice>,
+    ) -> RunningRlcConfig {
+        let config = RunningRlcConfig { acc };
+
+        meta.create_gate("running rlc", |meta| {
+            let q_enable = q_enable(meta);
+            let q_continue = q_continue(meta);
+            let byte = byte(meta);
+            let acc = config.expr(meta, Rotation::cur());
+            let acc_prev = config.expr(meta, Rotation::prev());
+
+            vec![
+                // acc := acc_prev ⋅ r + byte when continuing
+                q_enable.clone()
+                    * q_continue.clone()
+                    * (acc.clone() - (acc_prev * randomness + byte.clone())),
+                // acc := byte when starting
+                q_enable * not::expr(q_continue) * (acc - byte),
+            ]
+        });
+
+        config
+    }
+
+    pub fn construct(config: RunningRlcConfig) -> Self {
+        RunningRlcChip {
+            config,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Witnesses the running RLCs of `bytes`, from the row `offset` on, and
+    /// returns the RLC of all of them.
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        bytes: &[u8],
+        randomness: F,
+    ) -> Result<F, Error> {
+        let accs = running(bytes, randomness);
+        for (idx, acc) in accs.iter().enumerate() {
+            region.assign_advice(
+                || format!("running rlc {}", idx),
+                self.config.acc,
+                offset + idx,
+                || Ok(*acc),
+            )?;
+        }
+        Ok(accs.last().copied().unwrap_or_else(F::zero))
+    }
+}
+
+impl<F: FieldExt> Chip<F> for RunningRlcChip<F> {
+    type Config = RunningRlcConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{extend, running, value, PowersOfRandomness};
+    use halo2_proofs::arithmetic::Field as HaloField;
+    use pairing::bn256::Fr as Fp;
+
+    #[test]
+    fn rlc_values() {
+        let randomness = Fp::from(0x100);
+        let bytes = [1, 2, 3, 0, 5];
+
+        assert_eq!(value(&bytes, randomness), Fp::from(0x0102030005u64));
+        assert_eq!(
+            extend(value(&bytes[..2], randomness), &bytes[2..], randomness),
+            value(&bytes, randomness)
+        );
+        assert_eq!(
+            running(&bytes, randomness),
+            [0x01u64, 0x0102, 0x010203, 0x01020300, 0x0102030005].map(Fp::from)
+        );
+    }
+
+    #[test]
+    fn powers_of_randomness() {
+        let randomness = Fp::from(7);
+        let mut powers = PowersOfRandomness::new(randomness);
+
+        assert_eq!(powers.pow(0), Fp::one());
+        assert_eq!(powers.pow(5), randomness.pow(&[5, 0, 0, 0]));
+        assert_eq!(
+            powers.take(3),
+            vec![
+                randomness,
+                randomness.square(),
+                randomness.pow(&[3, 0, 0, 0])
+            ]
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Versioned layout of the instance column of the proofs verified on chain.
+//!
+//! The verifier contracts read the fields of the instance at fixed offsets, so
+//! the layout of `INSTANCE_LAYOUT` only changes along with
+//! `INSTANCE_LAYOUT_VERSION`, which is the first cell of the instance.  An
+//! instance of another version, or of another length, is rejected by
+//! `check_instance` before proving, instead of producing a proof that the
+//! deployed verifier misreads.
+//!
+//! The 256-bit fields are split in their high and low 128-bit halves, since
+//! they don't fit in a field element.  The KZG accumulator of the verified
+//! proofs is made of the coordinates of its two points, each one split in
+//! `ACCUMULATOR_LIMB_BITS`-bit limbs, least significant first.
+
+use eth_types::{Field, Word, WordFieldExt};
+use std::fmt;
+
+/// Version of the instance layout, to be bumped whenever `INSTANCE_LAYOUT` or
+/// the length of a field changes.
+pub const INSTANCE_LAYOUT_VERSION: u64 = 1;
+
+/// Number of bits of a limb of a coordinate of the accumulator.
+pub const ACCUMULATOR_LIMB_BITS: usize = 68;
+
+/// Number of limbs of the accumulator: 4 limbs for each coordinate of its 2
+/// points.
+pub const N_ACCUMULATOR_LIMBS: usize = 2 * 2 * 4;
+
+/// A field of the instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstanceField {
+    /// `INSTANCE_LAYOUT_VERSION`
+    Version,
+    /// Hash of the block before the proven blocks
+    ParentHash,
+    /// State root before the proven blocks
+    PrevStateRoot,
+    /// Hash of the last proven block
+    BlockHash,
+    /// State root after the proven blocks
+    StateRoot,
+    /// Digest of the public inputs of the proven blocks, which commits their
+    /// transactions
+    TxDigest,
+    /// Limbs of the KZG accumulator of the verified proofs
+    Accumulator,
+}
+
+impl InstanceField {
+    /// Number of cells of the field.
+    pub fn n_cells(&self) -> usize {
+        match self {
+            Self::Version => 1,
+            Self::Accumulator => N_ACCUMULATOR_LIMBS,
+            _ => 2,
+        }
+    }
+}
+
+/// Fields of the instance, in order.
+pub const INSTANCE_LAYOUT: [InstanceField; 7] = [
+    InstanceField::Version,
+    InstanceField::ParentHash,
+    InstanceField::PrevStateRoot,
+    InstanceField::BlockHash,
+    InstanceField::StateRoot,
+    InstanceField::TxDigest,
+    InstanceField::Accumulator,
+];
+
+/// Returns the offset of the first cell of `field` in the instance.
+pub fn instance_offset(field: InstanceField) -> usize {
+    INSTANCE_LAYOUT
+        .iter()
+        .take_while(|other| **other != field)
+        .map(InstanceField::n_cells)
+        .sum()
+}
+
+/// Returns the number of cells of the instance.
+pub fn instance_len() -> usize {
+    INSTANCE_LAYOUT.iter().map(InstanceField::n_cells).sum()
+}
+
+/// Error when an instance doesn't follow the current layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstanceError {
+    /// The instance doesn't have `instance_len()` cells.
+    Length {
+        /// Number of cells of the instance
+        len: usize,
+    },
+    /// The version of the instance isn't `INSTANCE_LAYOUT_VERSION`.
+    Version,
+    /// A cell of a 256-bit field doesn't fit in 128 bits.
+    Overflow {
+        /// Field of the cell
+        field: InstanceField,
+    },
+}
+
+impl fmt::Display for InstanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Length { len } => write!(
+                f,
+                "instance of {} cells instead of {} of the layout version {}",
+                len,
+                instance_len(),
+                INSTANCE_LAYOUT_VERSION
+            ),
+            Self::Version => write!(
+                f,
+                "instance isn't of the layout version {}",
+                INSTANCE_LAYOUT_VERSION
+            ),
+            Self::Overflow { field } => write!(f, "instance half of {:?} overflows", field),
+        }
+    }
+}
+
+impl std::error::Error for InstanceError {}
+
+/// Checks that `instance` follows the current layout, its length and its
+/// version.
+pub fn check_instance<F: Field>(instance: &[F]) -> Result<(), InstanceError> {
+    if instance.len() != instance_len() {
+        return Err(InstanceError::Length {
+            len: instance.len(),
+        });
+    }
+    if instance[instance_offset(InstanceField::Version)] != F::from(INSTANCE_LAYOUT_VERSION) {
+        return Err(InstanceError::Version);
+    }
+    Ok(())
+}
+
+/// Values of the fields of the instance.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PublicInstance<F> {
+    /// Hash of the block before the proven blocks
+    pub parent_hash: Word,
+    /// State root before the proven blocks
+    pub prev_state_root: Word,
+    /// Hash of the last proven block
+    pub block_hash: Word,
+    /// State root after the proven blocks
+    pub state_root: Word,
+    /// Digest of the public inputs of the proven blocks
+    pub tx_digest: Word,
+    /// Limbs of the KZG accumulator of the verified proofs
+    pub accumulator: [F; N_ACCUMULATOR_LIMBS],
+}
+
+impl<F: Field> PublicInstance<F> {
+    /// Returns the instance of the current layout.
+    pub fn to_instance(&self) -> Vec<F> {
+        let mut instance = vec![F::zero(); instance_len()];
+        instance[instance_offset(InstanceField::Version)] = F::from(INSTANCE_LAYOUT_VERSION);
+        for (field, word) in [
+            (InstanceField::ParentHash, self.parent_hash),
+            (InstanceField::PrevStateRoot, self.prev_state_root),
+            (InstanceField::BlockHash, self.block_hash),
+            (InstanceField::StateRoot, self.state_root),
+            (InstanceField::TxDigest, self.tx_digest),
+        ] {
+            let offset = instance_offset(field);
+            let (hi, lo) = word.to_field_hi_lo();
+            instance[offset..offset + 2].copy_from_slice(&[hi, lo]);
+        }
+        let offset = instance_offset(InstanceField::Accumulator);
+        instance[offset..offset + N_ACCUMULATOR_LIMBS].copy_from_slice(&self.accumulator);
+        instance
+    }
+
+    /// Returns the values of the fields of `instance`, after checking that it
+    /// follows the current layout.
+    pub fn from_instance(instance: &[F]) -> Result<Self, InstanceError> {
+        check_instance(instance)?;
+        let word = |field: InstanceField| -> Result<Word, InstanceError> {
+            let offset = instance_offset(field);
+            Word::from_field_hi_lo(&instance[offset], &instance[offset + 1])
+                .ok_or(InstanceError::Overflow { field })
+        };
+        let offset = instance_offset(InstanceField::Accumulator);
+        let mut accumulator = [F::zero(); N_ACCUMULATOR_LIMBS];
+        accumulator.copy_from_slice(&instance[offset..offset + N_ACCUMULATOR_LIMBS]);
+        Ok(Self {
+            parent_hash: word(InstanceField::ParentHash)?,
+            prev_state_root: word(InstanceField::PrevStateRoot)?,
+            block_hash: word(InstanceField::BlockHash)?,
+            state_root: word(InstanceField::StateRoot)?,
+            tx_digest: word(InstanceField::TxDigest)?,
+            accumulator,
+        })
+    }
+}
+
+/// Returns the high and the low 128-bit halves of `word`.
+#[deprecated(note = "use `WordFieldExt::to_field_hi_lo`, which names the order of the halves")]
+pub fn word_halves<F: Field>(word: Word) -> [F; 2] {
+    let (hi, lo) = word.to_field_hi_lo();
+    [hi, lo]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_instance, instance_len, instance_offset, InstanceError, InstanceField,
+        PublicInstance, INSTANCE_LAYOUT_VERSION, N_ACCUMULATOR_LIMBS,
+    };
+    use eth_types::Word;
+    use halo2_proofs::arithmetic::FieldExt;
+    use pairing::bn256::Fr;
+
+    fn public_instance() -> PublicInstance<Fr> {
+        PublicInstance {
+            parent_hash: Word::from(0xb0) << 200,
+            prev_state_root: Word::from(0x50),
+            block_hash: Word::MAX,
+            state_root: Word::from(0x51),
+            tx_digest: Word::from(0xd1) << 128,
+            accumulator: [(); N_ACCUMULATOR_LIMBS].map(|_| Fr::from(0xacc)),
+        }
+    }
+
+    // Changing these offsets breaks the deployed verifiers, so it requires a
+    // new `INSTANCE_LAYOUT_VERSION`.
+    #[test]
+    fn instance_layout_v1() {
+        assert_eq!(INSTANCE_LAYOUT_VERSION, 1);
+        for (field, offset) in [
+            (InstanceField::Version, 0),
+            (InstanceField::ParentHash, 1),
+            (InstanceField::PrevStateRoot, 3),
+            (InstanceField::BlockHash, 5),
+            (InstanceField::StateRoot, 7),
+            (InstanceField::TxDigest, 9),
+            (InstanceField::Accumulator, 11),
+        ] {
+            assert_eq!(instance_offset(field), offset);
+        }
+        assert_eq!(instance_len(), 27);
+    }
+
+    #[test]
+    fn instance_roundtrip() {
+        let instance = public_instance().to_instance();
+        assert_eq!(check_instance(&instance), Ok(()));
+        assert_eq!(
+            PublicInstance::from_instance(&instance),
+            Ok(public_instance())
+        );
+    }
+
+    #[test]
+    fn instance_incompatible() {
+        let mut instance = public_instance().to_instance();
+        assert_eq!(
+            check_instance(&instance[1..]),
+            Err(InstanceError::Length {
+                len: instance_len() - 1
+            })
+        );
+
+        instance[instance_offset(InstanceField::Version)] = Fr::from(INSTANCE_LAYOUT_VERSION + 1);
+        assert_eq!(check_instance(&instance), Err(InstanceError::Version));
+
+        let mut instance = public_instance().to_instance();
+        instance[instance_offset(InstanceField::StateRoot)] =
+            Fr::from_u128(u128::MAX) * Fr::from(4);
+        assert_eq!(
+            PublicInstance::from_instance(&instance),
+            Err(InstanceError::Overflow {
+                field: InstanceField::StateRoot
+            })
+        );
+    }
+}
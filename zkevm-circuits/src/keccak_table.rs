@@ -0,0 +1,100 @@
+#![allow(missing_docs)]
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Region,
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::evm_circuit::table::LookupTable;
+use std::collections::HashSet;
+
+/// The keccak table, looked up by the circuits which hash their witnesses.
+/// Each row is a `(input_rlc, input_len, output_rlc)` of a hashed input, as
+/// built by `keccak_table_assignments`.  The lookups find a hash by its input
+/// alone, so an input hashed several times has a single row, see
+/// [`dedup_keccak_inputs`].
+#[derive(Clone, Copy, Debug)]
+pub struct KeccakTable {
+    pub input_rlc: Column<Advice>,
+    pub input_len: Column<Advice>,
+    pub output_rlc: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F, 3> for KeccakTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        [self.input_rlc, self.input_len, self.output_rlc]
+            .map(Into::into)
+            .to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        ["input_rlc", "input_len", "output_rlc"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 3] {
+        [
+            meta.query_advice(self.input_rlc, Rotation::cur()),
+            meta.query_advice(self.input_len, Rotation::cur()),
+            meta.query_advice(self.output_rlc, Rotation::cur()),
+        ]
+    }
+}
+
+impl KeccakTable {
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            input_rlc: meta.advice_column(),
+            input_len: meta.advice_column(),
+            output_rlc: meta.advice_column(),
+        }
+    }
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 3],
+    ) -> Result<(), Error> {
+        for (column, value) in [self.input_rlc, self.input_len, self.output_rlc]
+            .iter()
+            .zip(row)
+        {
+            region.assign_advice(
+                || "assign keccak row on keccak table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the distinct `inputs`, in the order they first appear.  The same
+/// input is often hashed several times in a block, like the code of a
+/// contract called twice, the public key of the sender of several
+/// transactions or the padding signatures, which all need a single row of
+/// the keccak table.
+pub fn dedup_keccak_inputs(inputs: impl IntoIterator<Item = Vec<u8>>) -> Vec<Vec<u8>> {
+    let mut seen = HashSet::new();
+    inputs
+        .into_iter()
+        .filter(|input| seen.insert(input.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dedup_keccak_inputs;
+
+    #[test]
+    fn dedup_inputs() {
+        let inputs = vec![vec![1, 2], vec![], vec![0, 1, 2], vec![1, 2], vec![]];
+        assert_eq!(
+            dedup_keccak_inputs(inputs),
+            vec![vec![1, 2], vec![], vec![0, 1, 2]]
+        );
+    }
+}
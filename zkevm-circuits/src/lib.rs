@@ -10,11 +10,35 @@
 #![deny(missing_docs)]
 #![deny(unsafe_code)]
 
+pub mod annotation;
+pub mod block_table;
 pub mod bytecode_circuit;
+pub mod bytecode_table;
+pub mod copy_circuit;
+pub mod copy_table;
+pub mod determinism;
+pub mod ecc_circuit;
+pub mod ecc_table;
 pub mod evm_circuit;
+pub mod exp_circuit;
+pub mod exp_table;
+pub mod fixed_table;
 pub mod gadget;
+pub mod instance;
+pub mod keccak_table;
+pub mod mpt_table;
+pub mod pi_circuit;
 pub mod rw_table;
+pub mod sha256_circuit;
+pub mod sha256_table;
+pub mod sig_circuit;
+pub mod sig_table;
 pub mod state_circuit;
+pub mod super_circuit;
+#[cfg(test)]
+mod table_consistency;
 #[cfg(test)]
 pub mod test_util;
+pub mod tx_circuit;
+pub mod tx_table;
 pub mod util;
@@ -0,0 +1,123 @@
+#![allow(missing_docs)]
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Region,
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::evm_circuit::table::LookupTable;
+
+/// The kind of state change proven by a row of the mpt table.  The account
+/// ones share their values with `AccountFieldTag`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MptProofType {
+    NonceMod = 1,
+    BalanceMod,
+    CodeHashMod,
+    StorageMod,
+}
+
+/// A row of the mpt table
+#[derive(Default, Clone, Copy, Debug)]
+pub struct MptUpdateRow<F: FieldExt> {
+    pub address: F,
+    pub storage_key: F,
+    pub proof_type: F,
+    pub value_prev: F,
+    pub value: F,
+    pub root_prev: F,
+    pub root: F,
+}
+
+/// The mpt table shared between state circuit and mpt circuit, where each row
+/// is a state change that moves the state root from `root_prev` to `root`
+#[derive(Clone, Copy)]
+pub struct MptTable {
+    pub address: Column<Advice>,
+    pub storage_key: Column<Advice>,
+    pub proof_type: Column<Advice>,
+    pub value_prev: Column<Advice>,
+    pub value: Column<Advice>,
+    pub root_prev: Column<Advice>,
+    pub root: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F, 7> for MptTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        [
+            self.address,
+            self.storage_key,
+            self.proof_type,
+            self.value_prev,
+            self.value,
+            self.root_prev,
+            self.root,
+        ]
+        .map(Into::into)
+        .to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        [
+            "address",
+            "storage_key",
+            "proof_type",
+            "value_prev",
+            "value",
+            "root_prev",
+            "root",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 7] {
+        [
+            meta.query_advice(self.address, Rotation::cur()),
+            meta.query_advice(self.storage_key, Rotation::cur()),
+            meta.query_advice(self.proof_type, Rotation::cur()),
+            meta.query_advice(self.value_prev, Rotation::cur()),
+            meta.query_advice(self.value, Rotation::cur()),
+            meta.query_advice(self.root_prev, Rotation::cur()),
+            meta.query_advice(self.root, Rotation::cur()),
+        ]
+    }
+}
+impl MptTable {
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            address: meta.advice_column(),
+            storage_key: meta.advice_column(),
+            proof_type: meta.advice_column(),
+            value_prev: meta.advice_column(),
+            value: meta.advice_column(),
+            root_prev: meta.advice_column(),
+            root: meta.advice_column(),
+        }
+    }
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: &MptUpdateRow<F>,
+    ) -> Result<(), Error> {
+        for (column, value) in [
+            (self.address, row.address),
+            (self.storage_key, row.storage_key),
+            (self.proof_type, row.proof_type),
+            (self.value_prev, row.value_prev),
+            (self.value, row.value),
+            (self.root_prev, row.root_prev),
+            (self.root, row.root),
+        ] {
+            region.assign_advice(
+                || "assign mpt row on mpt table",
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+}
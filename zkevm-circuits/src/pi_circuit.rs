@@ -0,0 +1,1028 @@
+//! The PI circuit implementation.
+//!
+//! It assigns the block table and the tx table looked up by the EVM circuit,
+//! and commits all their cells, with the fields of the block header which
+//! aren't in the block table, as raw public inputs.  The tables have a fixed
+//! number of rows, given by the maximum number of txs and of call data bytes,
+//! so that the layout of the raw public inputs doesn't depend on the block.
+//! The raw public inputs are, in order, the block table rows, the block hash,
+//! the parent hash, the state root, the previous state root and the digest of
+//! the previous block, each one split in its high and low 128-bit halves, the
+//! tx table rows and the withdrawal rows.  The withdrawals of a post-Shanghai
+//! block are committed with a fixed number of rows as well, given by the
+//! maximum number of withdrawals, and their root is the `WithdrawalsRoot` field
+//! of the block table.
+//!
+//! The raw public inputs are compressed into the keccak digest of their 32-byte
+//! big-endian representations, which is looked up in the keccak table.  The
+//! public input of the circuit is the digest, followed by the digest of the
+//! previous block and the block header fields, each one split in its high and
+//! low 128-bit halves, so that the proofs of the blocks of a batch can be
+//! chained by their public inputs when they are aggregated.  This way
+//! the cost of verifying a proof doesn't depend on the size of the block, and
+//! the digests of the blocks of a batch are chained, so the digest of the last
+//! block commits the txs of all of them.
+
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    block_table::BlockTable,
+    evm_circuit::{
+        table::{LookupTable, TxContextFieldTag},
+        util::constraint_builder::BaseConstraintBuilder,
+        witness::{keccak_table_assignments, BlockContext, Transaction},
+    },
+    gadget::rlc,
+    keccak_table::KeccakTable,
+    tx_table::TxTable,
+    util::{power_of_randomness_from_instance, Expr},
+};
+use eth_types::{
+    geth_types::Withdrawal, keccak256, Field, ToBigEndian, ToScalar, Word, WordFieldExt,
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, VirtualCells,
+    },
+    poly::Rotation,
+};
+use std::{fmt, iter};
+
+const MAX_DEGREE: usize = 6;
+
+/// Number of previous block hashes in the block table.
+pub const N_HISTORY_HASHES: usize = 256;
+
+// Number of block table rows of the block fields, out of the block hashes.
+const N_BLOCK_FIELDS: usize = 8;
+
+// Number of tx table rows of each tx, out of the call data bytes.
+const N_TX_FIELDS: usize = 9;
+
+/// Number of rows of the block table, with the all-zero first row.
+pub const BLOCK_TABLE_LEN: usize = 1 + N_BLOCK_FIELDS + N_HISTORY_HASHES;
+
+/// Returns the number of rows of the tx table, with the all-zero first row.
+pub fn tx_table_len(max_txs: usize, max_calldata: usize) -> usize {
+    1 + max_txs * N_TX_FIELDS + max_calldata
+}
+
+/// Number of fields of a withdrawal row: the withdrawal index, the validator
+/// index, the recipient address and the amount in Gwei.
+pub const N_WITHDRAWAL_FIELDS: usize = 4;
+
+/// Number of cells of the public input of the PI circuit, the halves of the
+/// digest, of the digest of the previous block and of the 4 block header
+/// fields.
+pub const N_PI_INSTANCE: usize = 12;
+
+/// Returns the number of raw public inputs of the PI circuit.
+pub fn n_public_inputs(max_txs: usize, max_calldata: usize, max_withdrawals: usize) -> usize {
+    3 * BLOCK_TABLE_LEN
+        + 10
+        + 4 * tx_table_len(max_txs, max_calldata)
+        + N_WITHDRAWAL_FIELDS * max_withdrawals
+}
+
+/// Error when building the public inputs of the PI circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PiCircuitError {
+    /// The block has more previous block hashes than the block table holds.
+    TooManyHistoryHashes(usize),
+    /// The block has more txs than the circuit can hold.
+    TooManyTxs {
+        /// Number of txs of the block
+        n_txs: usize,
+        /// Maximum number of txs of the circuit
+        max_txs: usize,
+    },
+    /// The txs of the block have more call data bytes than the circuit can
+    /// hold.
+    TooManyCallDataBytes {
+        /// Number of call data bytes of the txs of the block
+        n_bytes: usize,
+        /// Maximum number of call data bytes of the circuit
+        max_calldata: usize,
+    },
+    /// The block has more withdrawals than the circuit can hold.
+    TooManyWithdrawals {
+        /// Number of withdrawals of the block
+        n_withdrawals: usize,
+        /// Maximum number of withdrawals of the circuit
+        max_withdrawals: usize,
+    },
+}
+
+impl fmt::Display for PiCircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyHistoryHashes(n_hashes) => write!(
+                f,
+                "too many history hashes for the pi circuit: {} > {}",
+                n_hashes, N_HISTORY_HASHES
+            ),
+            Self::TooManyTxs { n_txs, max_txs } => write!(
+                f,
+                "too many txs for the pi circuit: {} > max_txs {}",
+                n_txs, max_txs
+            ),
+            Self::TooManyCallDataBytes {
+                n_bytes,
+                max_calldata,
+            } => write!(
+                f,
+                "too many call data bytes for the pi circuit: {} > max_calldata {}",
+                n_bytes, max_calldata
+            ),
+            Self::TooManyWithdrawals {
+                n_withdrawals,
+                max_withdrawals,
+            } => write!(
+                f,
+                "too many withdrawals for the pi circuit: {} > max_withdrawals {}",
+                n_withdrawals, max_withdrawals
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PiCircuitError {}
+
+/// The data of a block committed by the PI circuit.
+#[derive(Clone, Default, Debug)]
+pub struct PublicData {
+    /// The block context, in the block table
+    pub block_context: BlockContext,
+    /// The hash of the block
+    pub block_hash: Word,
+    /// The hash of the parent block
+    pub parent_hash: Word,
+    /// The state root after the block
+    pub state_root: Word,
+    /// The state root before the block
+    pub prev_state_root: Word,
+    /// The digest of the previous block of the batch, zero for the first one
+    pub prev_digest: Word,
+    /// The txs of the block, in the tx table
+    pub txs: Vec<Transaction>,
+    /// The validator withdrawals of the block, empty before Shanghai
+    pub withdrawals: Vec<Withdrawal>,
+}
+
+impl PublicData {
+    /// Returns the rows of the block table, padded with all-zero rows.
+    pub fn block_table_rows<F: Field>(&self, randomness: F) -> Result<Vec<[F; 3]>, PiCircuitError> {
+        let n_hashes = self.block_context.history_hashes.len();
+        if n_hashes > N_HISTORY_HASHES {
+            return Err(PiCircuitError::TooManyHistoryHashes(n_hashes));
+        }
+
+        let mut rows = vec![[F::zero(); 3]];
+        rows.extend(self.block_context.table_assignments(randomness));
+        rows.resize(BLOCK_TABLE_LEN, [F::zero(); 3]);
+        Ok(rows)
+    }
+
+    /// Returns the high and low halves of the block hash, the parent hash,
+    /// the state root, the previous state root and the digest of the previous
+    /// block.
+    pub fn header_values<F: Field>(&self) -> [F; 10] {
+        let mut values = [F::zero(); 10];
+        for (halves, word) in values.chunks_mut(2).zip([
+            self.block_hash,
+            self.parent_hash,
+            self.state_root,
+            self.prev_state_root,
+            self.prev_digest,
+        ]) {
+            let (hi, lo) = word.to_field_hi_lo();
+            halves.copy_from_slice(&[hi, lo]);
+        }
+        values
+    }
+
+    /// Returns the rows of the tx table, the fields of `max_txs` txs followed
+    /// by `max_calldata` call data bytes, padded with all-zero rows.
+    pub fn tx_table_rows<F: Field>(
+        &self,
+        randomness: F,
+        max_txs: usize,
+        max_calldata: usize,
+    ) -> Result<Vec<[F; 4]>, PiCircuitError> {
+        if self.txs.len() > max_txs {
+            return Err(PiCircuitError::TooManyTxs {
+                n_txs: self.txs.len(),
+                max_txs,
+            });
+        }
+        let n_bytes = self.txs.iter().map(|tx| tx.call_data.len()).sum::<usize>();
+        if n_bytes > max_calldata {
+            return Err(PiCircuitError::TooManyCallDataBytes {
+                n_bytes,
+                max_calldata,
+            });
+        }
+
+        let call_data_tag = F::from(TxContextFieldTag::CallData as u64);
+        let (mut fields, mut call_data) = (vec![[F::zero(); 4]], Vec::new());
+        for tx in self.txs.iter() {
+            let (tx_call_data, tx_fields): (Vec<_>, Vec<_>) = tx
+                .table_assignments(randomness)
+                .into_iter()
+                .partition(|row| row[1] == call_data_tag);
+            debug_assert_eq!(tx_fields.len(), N_TX_FIELDS);
+            fields.extend(tx_fields);
+            call_data.extend(tx_call_data);
+        }
+        fields.resize(1 + max_txs * N_TX_FIELDS, [F::zero(); 4]);
+        call_data.resize(max_calldata, [F::zero(); 4]);
+        Ok([fields, call_data].concat())
+    }
+
+    /// Returns the rows of the withdrawals, padded with all-zero rows to
+    /// `max_withdrawals` rows.
+    pub fn withdrawal_rows<F: Field>(
+        &self,
+        max_withdrawals: usize,
+    ) -> Result<Vec<[F; N_WITHDRAWAL_FIELDS]>, PiCircuitError> {
+        if self.withdrawals.len() > max_withdrawals {
+            return Err(PiCircuitError::TooManyWithdrawals {
+                n_withdrawals: self.withdrawals.len(),
+                max_withdrawals,
+            });
+        }
+
+        let mut rows: Vec<_> = self
+            .withdrawals
+            .iter()
+            .map(|withdrawal| {
+                [
+                    F::from(withdrawal.index),
+                    F::from(withdrawal.validator_index),
+                    withdrawal.address.to_scalar().unwrap(),
+                    F::from(withdrawal.amount),
+                ]
+            })
+            .collect();
+        rows.resize(max_withdrawals, [F::zero(); N_WITHDRAWAL_FIELDS]);
+        Ok(rows)
+    }
+
+    /// Returns the raw public inputs of the PI circuit.
+    pub fn raw_public_inputs<F: Field>(
+        &self,
+        randomness: F,
+        max_txs: usize,
+        max_calldata: usize,
+        max_withdrawals: usize,
+    ) -> Result<Vec<F>, PiCircuitError> {
+        Ok(self
+            .block_table_rows(randomness)?
+            .into_iter()
+            .flatten()
+            .chain(self.header_values())
+            .chain(
+                self.tx_table_rows(randomness, max_txs, max_calldata)?
+                    .into_iter()
+                    .flatten(),
+            )
+            .chain(self.withdrawal_rows(max_withdrawals)?.into_iter().flatten())
+            .collect())
+    }
+
+    /// Returns the input of the digest of the raw public inputs, the
+    /// concatenation of their 32-byte big-endian representations.
+    pub fn digest_input<F: Field>(
+        &self,
+        randomness: F,
+        max_txs: usize,
+        max_calldata: usize,
+        max_withdrawals: usize,
+    ) -> Result<Vec<u8>, PiCircuitError> {
+        Ok(self
+            .raw_public_inputs(randomness, max_txs, max_calldata, max_withdrawals)?
+            .iter()
+            .flat_map(|value| Word::from_field_le(value).to_be_bytes())
+            .collect())
+    }
+
+    /// Returns the keccak digest of the raw public inputs.
+    pub fn digest<F: Field>(
+        &self,
+        randomness: F,
+        max_txs: usize,
+        max_calldata: usize,
+        max_withdrawals: usize,
+    ) -> Result<Word, PiCircuitError> {
+        let input = self.digest_input(randomness, max_txs, max_calldata, max_withdrawals)?;
+        Ok(Word::from_big_endian(&keccak256(&input)))
+    }
+
+    /// Returns the public input of the PI circuit, the halves of the digest,
+    /// of the digest of the previous block and of the block header fields.
+    pub fn instance<F: Field>(
+        &self,
+        randomness: F,
+        max_txs: usize,
+        max_calldata: usize,
+        max_withdrawals: usize,
+    ) -> Result<Vec<F>, PiCircuitError> {
+        let (hi, lo) = self
+            .digest(randomness, max_txs, max_calldata, max_withdrawals)?
+            .to_field_hi_lo();
+        let header_values = self.header_values();
+        // The digest of the previous block is the last header value
+        Ok([hi, lo]
+            .iter()
+            .chain(&header_values[8..])
+            .chain(&header_values[..8])
+            .copied()
+            .collect())
+    }
+}
+
+/// Config of the PI circuit.
+#[derive(Clone, Debug)]
+pub struct PiCircuitConfig<F> {
+    // Enables the rows of the raw public inputs
+    q_rpi: Column<Fixed>,
+    // Enables the row of the first raw public input
+    q_first: Column<Fixed>,
+    // Enables the row of the digest, following the raw public inputs
+    q_digest: Column<Fixed>,
+    // Number of bytes of the digest input, on the row of the digest
+    rpi_len: Column<Fixed>,
+    block_table: BlockTable,
+    // The block header fields which aren't in the block table
+    header: Column<Advice>,
+    tx_table: TxTable,
+    // The withdrawal rows
+    withdrawals: [Column<Advice>; N_WITHDRAWAL_FIELDS],
+    keccak_table: KeccakTable,
+    // A raw public input or the digest, copied from the cells of the tables
+    rpi: Column<Advice>,
+    // Big-endian bytes of `rpi`
+    rpi_bytes: [Column<Advice>; 32],
+    // Random linear combination of the bytes of the raw public inputs so far
+    rpi_rlc_acc: Column<Advice>,
+    // High and low halves of the digest, on the row of the digest
+    digest: [Column<Advice>; 2],
+    u8_table: Column<Fixed>,
+    pi: Column<Instance>,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F> Annotate for PiCircuitConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate(owner, "q_rpi", self.q_rpi);
+        annotations.annotate(owner, "q_first", self.q_first);
+        annotations.annotate(owner, "q_digest", self.q_digest);
+        annotations.annotate(owner, "rpi_len", self.rpi_len);
+        annotations.annotate_config(owner, "block_table", &self.block_table);
+        annotations.annotate(owner, "header", self.header);
+        annotations.annotate_config(owner, "tx_table", &self.tx_table);
+        annotations.annotate_all(owner, "withdrawals", self.withdrawals);
+        annotations.annotate_config(owner, "keccak_table", &self.keccak_table);
+        annotations.annotate(owner, "rpi", self.rpi);
+        annotations.annotate_all(owner, "rpi_bytes", self.rpi_bytes);
+        annotations.annotate(owner, "rpi_rlc_acc", self.rpi_rlc_acc);
+        annotations.annotate_all(owner, "digest", self.digest);
+        annotations.annotate(owner, "u8_table", self.u8_table);
+        annotations.annotate(owner, "pi", self.pi);
+    }
+}
+
+impl<F: Field> PiCircuitConfig<F> {
+    /// Configure the PI circuit, which assigns the rows of `block_table` and
+    /// `tx_table`, and looks up the digest of the raw public inputs in
+    /// `keccak_table`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Expression<F>; 31],
+        block_table: BlockTable,
+        tx_table: TxTable,
+        keccak_table: KeccakTable,
+    ) -> Self {
+        let [q_rpi, q_first, q_digest, rpi_len, u8_table] = [(); 5].map(|_| meta.fixed_column());
+        let [header, rpi, rpi_rlc_acc] = [(); 3].map(|_| meta.advice_column());
+        let digest = [(); 2].map(|_| meta.advice_column());
+        let rpi_bytes = [(); 32].map(|_| meta.advice_column());
+        let withdrawals = [(); N_WITHDRAWAL_FIELDS].map(|_| meta.advice_column());
+        let pi = meta.instance_column();
+
+        for column in [block_table.tag, block_table.index, block_table.value] {
+            meta.enable_equality(column);
+        }
+        for column in [
+            header,
+            rpi,
+            tx_table.tx_id,
+            tx_table.tag,
+            tx_table.index,
+            tx_table.value,
+        ] {
+            meta.enable_equality(column);
+        }
+        for column in withdrawals.iter().chain(digest.iter()) {
+            meta.enable_equality(*column);
+        }
+        meta.enable_equality(pi);
+
+        // Random linear combination of the bytes of the row, the first one with
+        // the highest power as in the keccak table.
+        let bytes_rlc = |meta: &mut VirtualCells<F>| {
+            rpi_bytes
+                .iter()
+                .rev()
+                .zip(iter::once(1.expr()).chain(power_of_randomness.iter().cloned()))
+                .fold(0.expr(), |acc, (column, randomness)| {
+                    acc + meta.query_advice(*column, Rotation::cur()) * randomness
+                })
+        };
+
+        meta.create_gate("PI digest", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+
+            let q_rpi = meta.query_fixed(q_rpi, Rotation::cur());
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_digest = meta.query_fixed(q_digest, Rotation::cur());
+            let rpi = meta.query_advice(rpi, Rotation::cur());
+            let rlc_acc = meta.query_advice(rpi_rlc_acc, Rotation::cur());
+            let rlc_acc_prev = meta.query_advice(rpi_rlc_acc, Rotation::prev());
+            let bytes_rlc = bytes_rlc(meta);
+
+            cb.require_equal(
+                "rpi is the big-endian bytes",
+                rpi,
+                rpi_bytes.iter().fold(0.expr(), |acc, column| {
+                    acc * 256u64.expr() + meta.query_advice(*column, Rotation::cur())
+                }),
+            );
+            cb.condition(q_rpi.clone(), |cb| {
+                cb.require_equal(
+                    "rpi_rlc_acc := rpi_rlc_acc_prev ⋅ r^32 + rlc(bytes)",
+                    rlc_acc,
+                    (1.expr() - q_first)
+                        * rlc_acc_prev
+                        * power_of_randomness[30].clone()
+                        * power_of_randomness[0].clone()
+                        + bytes_rlc,
+                );
+            });
+            cb.condition(q_digest.clone(), |cb| {
+                for (half, bytes) in digest.iter().zip(rpi_bytes.chunks(16)) {
+                    cb.require_equal(
+                        "digest half is the big-endian bytes",
+                        meta.query_advice(*half, Rotation::cur()),
+                        bytes.iter().fold(0.expr(), |acc, column| {
+                            acc * 256u64.expr() + meta.query_advice(*column, Rotation::cur())
+                        }),
+                    );
+                }
+            });
+
+            cb.gate(q_rpi + q_digest)
+        });
+
+        meta.lookup_any("PI digest keccak", |meta| {
+            let q_digest = meta.query_fixed(q_digest, Rotation::cur());
+            vec![
+                meta.query_advice(rpi_rlc_acc, Rotation::prev()),
+                meta.query_fixed(rpi_len, Rotation::cur()),
+                bytes_rlc(meta),
+            ]
+            .into_iter()
+            .zip(keccak_table.table_exprs(meta))
+            .map(|(input, table)| (q_digest.clone() * input, table))
+            .collect()
+        });
+        for column in rpi_bytes {
+            meta.lookup_any("PI byte range", |meta| {
+                vec![(
+                    (meta.query_fixed(q_rpi, Rotation::cur())
+                        + meta.query_fixed(q_digest, Rotation::cur()))
+                        * meta.query_advice(column, Rotation::cur()),
+                    meta.query_fixed(u8_table, Rotation::cur()),
+                )]
+            });
+        }
+
+        Self {
+            q_rpi,
+            q_first,
+            q_digest,
+            rpi_len,
+            block_table,
+            header,
+            tx_table,
+            withdrawals,
+            keccak_table,
+            rpi,
+            rpi_bytes,
+            rpi_rlc_acc,
+            digest,
+            u8_table,
+            pi,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "u8 table",
+            |mut region| {
+                for byte in 0..=u8::MAX {
+                    region.assign_fixed(
+                        || "u8 table",
+                        self.u8_table,
+                        byte as usize,
+                        || Ok(F::from(byte as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // Assigns the `rows` of a table, and returns their cells row by row.
+    fn assign_rows<const N: usize>(
+        region: &mut Region<'_, F>,
+        name: &str,
+        columns: [Column<Advice>; N],
+        rows: &[[F; N]],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let mut cells = Vec::with_capacity(N * rows.len());
+        for (offset, row) in rows.iter().enumerate() {
+            for (column, value) in columns.iter().zip(row) {
+                cells.push(region.assign_advice(
+                    || format!("{} row {}", name, offset),
+                    *column,
+                    offset,
+                    || Ok(*value),
+                )?);
+            }
+        }
+        Ok(cells)
+    }
+
+    // Assigns a raw public input or the digest, with its big-endian bytes.
+    fn assign_rpi_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        bytes: [u8; 32],
+        rlc_acc: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let value = bytes.iter().fold(F::zero(), |acc, byte| {
+            acc * F::from(256) + F::from(*byte as u64)
+        });
+        for (column, byte) in self.rpi_bytes.iter().zip(bytes.iter()) {
+            region.assign_advice(
+                || format!("rpi byte {}", offset),
+                *column,
+                offset,
+                || Ok(F::from(*byte as u64)),
+            )?;
+        }
+        region.assign_advice(
+            || format!("rpi_rlc_acc {}", offset),
+            self.rpi_rlc_acc,
+            offset,
+            || Ok(rlc_acc),
+        )?;
+        region.assign_advice(|| format!("rpi {}", offset), self.rpi, offset, || Ok(value))
+    }
+
+    /// Assign the block table, the block header fields, the tx table and the
+    /// withdrawals, and constrain the keccak digest of their cells and the
+    /// digest of the previous block to be the public input.
+    #[tracing::instrument(name = "pi_circuit_assign", skip_all)]
+    pub fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        public_data: &PublicData,
+        randomness: F,
+        max_txs: usize,
+        max_calldata: usize,
+        max_withdrawals: usize,
+    ) -> Result<(), Error> {
+        let block_rows = public_data
+            .block_table_rows(randomness)
+            .map_err(|_| Error::Synthesis)?;
+        let tx_rows = public_data
+            .tx_table_rows(randomness, max_txs, max_calldata)
+            .map_err(|_| Error::Synthesis)?;
+        let withdrawal_rows = public_data
+            .withdrawal_rows(max_withdrawals)
+            .map_err(|_| Error::Synthesis)?;
+        let digest = public_data
+            .digest(randomness, max_txs, max_calldata, max_withdrawals)
+            .map_err(|_| Error::Synthesis)?;
+
+        let block_table = self.block_table;
+        let block_cells = layouter.assign_region(
+            || "block table",
+            |mut region| {
+                Self::assign_rows(
+                    &mut region,
+                    "block table",
+                    [block_table.tag, block_table.index, block_table.value],
+                    &block_rows,
+                )
+            },
+        )?;
+        let header_cells = layouter.assign_region(
+            || "block header",
+            |mut region| {
+                let rows = public_data.header_values().map(|value| [value]);
+                Self::assign_rows(&mut region, "block header", [self.header], &rows)
+            },
+        )?;
+        let tx_table = self.tx_table;
+        let tx_cells = layouter.assign_region(
+            || "tx table",
+            |mut region| {
+                Self::assign_rows(
+                    &mut region,
+                    "tx table",
+                    [tx_table.tx_id, tx_table.tag, tx_table.index, tx_table.value],
+                    &tx_rows,
+                )
+            },
+        )?;
+        let withdrawal_cells = layouter.assign_region(
+            || "withdrawals",
+            |mut region| {
+                Self::assign_rows(
+                    &mut region,
+                    "withdrawals",
+                    self.withdrawals,
+                    &withdrawal_rows,
+                )
+            },
+        )?;
+
+        let digest_cells = layouter.assign_region(
+            || "pi digest",
+            |mut region| {
+                let mut rlc_acc = F::zero();
+                let cells = block_cells
+                    .iter()
+                    .chain(header_cells.iter())
+                    .chain(tx_cells.iter())
+                    .chain(withdrawal_cells.iter())
+                    .collect::<Vec<_>>();
+                for (offset, cell) in cells.iter().enumerate() {
+                    for (name, column, value) in [
+                        ("q_rpi", self.q_rpi, true),
+                        ("q_first", self.q_first, offset == 0),
+                    ] {
+                        region.assign_fixed(
+                            || format!("{} {}", name, offset),
+                            column,
+                            offset,
+                            || Ok(F::from(value as u64)),
+                        )?;
+                    }
+
+                    let value = cell.value().copied().ok_or(Error::Synthesis)?;
+                    let bytes = Word::from_field_le(&value).to_be_bytes();
+                    rlc_acc = rlc::extend(rlc_acc, bytes.iter(), randomness);
+                    let rpi_cell = self.assign_rpi_row(&mut region, offset, bytes, rlc_acc)?;
+                    region.constrain_equal(cell.cell(), rpi_cell.cell())?;
+                }
+
+                let offset = cells.len();
+                for (name, column, value) in [
+                    ("q_digest", self.q_digest, F::one()),
+                    ("rpi_len", self.rpi_len, F::from(32 * offset as u64)),
+                ] {
+                    region.assign_fixed(|| name, column, offset, || Ok(value))?;
+                }
+                self.assign_rpi_row(&mut region, offset, digest.to_be_bytes(), F::zero())?;
+                let (hi, lo) = digest.to_field_hi_lo();
+                let mut cells = vec![];
+                for (column, half) in self.digest.iter().zip([hi, lo]) {
+                    cells.push(region.assign_advice(
+                        || "digest half",
+                        *column,
+                        offset,
+                        || Ok(half),
+                    )?);
+                }
+                Ok(cells)
+            },
+        )?;
+        // The halves of the digest of the previous block are the last header
+        // cells
+        let (header_cells, prev_digest_cells) = header_cells.split_at(header_cells.len() - 2);
+        for (row, cell) in digest_cells
+            .iter()
+            .chain(prev_digest_cells)
+            .chain(header_cells)
+            .enumerate()
+        {
+            layouter.constrain_instance(cell.cell(), self.pi, row)?;
+        }
+        Ok(())
+    }
+
+    fn load_keccak_table(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        public_data: &PublicData,
+        randomness: F,
+        max_txs: usize,
+        max_calldata: usize,
+        max_withdrawals: usize,
+    ) -> Result<(), Error> {
+        let input = public_data
+            .digest_input(randomness, max_txs, max_calldata, max_withdrawals)
+            .map_err(|_| Error::Synthesis)?;
+        layouter.assign_region(
+            || "keccak table",
+            |mut region| {
+                self.keccak_table.assign(&mut region, 0, [F::zero(); 3])?;
+                self.keccak_table.assign(
+                    &mut region,
+                    1,
+                    keccak_table_assignments(&input, randomness),
+                )
+            },
+        )
+    }
+}
+
+/// PI circuit that commits the block, tx and withdrawal data of a block with a
+/// keccak digest as public input.
+#[derive(Clone, Default, Debug)]
+pub struct PiCircuit<F> {
+    /// Maximum number of txs of the tx table
+    pub max_txs: usize,
+    /// Maximum number of call data bytes of the tx table
+    pub max_calldata: usize,
+    /// Maximum number of withdrawals
+    pub max_withdrawals: usize,
+    /// The randomness for random linear combination
+    pub randomness: F,
+    /// The data of the block
+    pub public_data: PublicData,
+}
+
+impl<F: Field> Circuit<F> for PiCircuit<F> {
+    type Config = PiCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            max_txs: self.max_txs,
+            max_calldata: self.max_calldata,
+            max_withdrawals: self.max_withdrawals,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let block_table = BlockTable::construct(meta);
+        let tx_table = TxTable::construct(meta);
+        let keccak_table = KeccakTable::construct(meta);
+
+        let power_of_randomness = power_of_randomness_from_instance::<_, 31>(meta);
+
+        PiCircuitConfig::configure(
+            meta,
+            power_of_randomness,
+            block_table,
+            tx_table,
+            keccak_table,
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load(&mut layouter)?;
+        config.load_keccak_table(
+            &mut layouter,
+            &self.public_data,
+            self.randomness,
+            self.max_txs,
+            self.max_calldata,
+            self.max_withdrawals,
+        )?;
+        config.assign(
+            &mut layouter,
+            &self.public_data,
+            self.randomness,
+            self.max_txs,
+            self.max_calldata,
+            self.max_withdrawals,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        n_public_inputs, PiCircuit, PiCircuitError, PublicData, N_HISTORY_HASHES, N_PI_INSTANCE,
+    };
+    use crate::{
+        evm_circuit::witness::{BlockContext, Transaction},
+        gadget::rlc::PowersOfRandomness,
+    };
+    use eth_types::{address, geth_types::Withdrawal, Word};
+    use halo2_proofs::{
+        arithmetic::Field as HaloField,
+        dev::{MockProver, VerifyFailure},
+    };
+    use pairing::bn256::Fr;
+
+    const K: u32 = 11;
+    const MAX_TXS: usize = 2;
+    const MAX_CALLDATA: usize = 8;
+    const MAX_WITHDRAWALS: usize = 2;
+
+    fn randomness() -> Fr {
+        Fr::from(0x100)
+    }
+
+    fn circuit(public_data: PublicData, pi: Vec<Fr>) -> (PiCircuit<Fr>, Vec<Vec<Fr>>) {
+        let circuit = PiCircuit {
+            max_txs: MAX_TXS,
+            max_calldata: MAX_CALLDATA,
+            max_withdrawals: MAX_WITHDRAWALS,
+            randomness: randomness(),
+            public_data,
+        };
+        let mut instance: Vec<_> = PowersOfRandomness::new(randomness())
+            .take(31)
+            .into_iter()
+            .map(|power| vec![power; (1 << K) - 64])
+            .collect();
+        instance.push(pi);
+        (circuit, instance)
+    }
+
+    fn verify(public_data: PublicData, pi: Vec<Fr>) -> Result<(), Vec<VerifyFailure>> {
+        let (circuit, instance) = circuit(public_data, pi);
+        let prover = MockProver::<Fr>::run(K, &circuit, instance).unwrap();
+        prover.verify()
+    }
+
+    fn public_data() -> PublicData {
+        PublicData {
+            block_context: BlockContext {
+                coinbase: address!("0x00000000000000000000000000000000c014ba5e"),
+                gas_limit: 0x1000000,
+                number: Word::from(0x100),
+                timestamp: Word::from(0x1234),
+                base_fee: Word::from(7),
+                history_hashes: vec![Word::from(0xff), Word::from(0xfe)],
+                chain_id: Word::from(1),
+                withdrawals_root: Word::from(0x4895),
+                ..Default::default()
+            },
+            block_hash: Word::from(0xabcd),
+            parent_hash: Word::from(0xff),
+            state_root: Word::from(0x5678),
+            prev_state_root: Word::from(0x1234),
+            prev_digest: Word::MAX - Word::from(0xd1),
+            txs: vec![
+                Transaction {
+                    id: 1,
+                    nonce: 1,
+                    gas: 21000,
+                    gas_price: Word::from(10),
+                    call_data: vec![1, 2, 3],
+                    call_data_length: 3,
+                    ..Default::default()
+                },
+                Transaction {
+                    id: 2,
+                    nonce: 2,
+                    gas: 30000,
+                    gas_price: Word::from(10),
+                    ..Default::default()
+                },
+            ],
+            withdrawals: vec![Withdrawal {
+                index: 3,
+                validator_index: 5,
+                address: address!("0x0000000000000000000000000000000000000123"),
+                amount: 32_000_000_000,
+            }],
+        }
+    }
+
+    #[test]
+    fn pi_circuit_valid() {
+        let public_data = public_data();
+        let raw_public_inputs = public_data
+            .raw_public_inputs(randomness(), MAX_TXS, MAX_CALLDATA, MAX_WITHDRAWALS)
+            .unwrap();
+        assert_eq!(
+            raw_public_inputs.len(),
+            n_public_inputs(MAX_TXS, MAX_CALLDATA, MAX_WITHDRAWALS)
+        );
+        let pi = public_data
+            .instance(randomness(), MAX_TXS, MAX_CALLDATA, MAX_WITHDRAWALS)
+            .unwrap();
+        assert_eq!(pi.len(), N_PI_INSTANCE);
+        assert_eq!(verify(public_data, pi), Ok(()));
+    }
+
+    #[cfg(feature = "real-prover")]
+    #[test]
+    fn pi_circuit_valid_real_prover() {
+        let public_data = public_data();
+        let pi = public_data
+            .instance(randomness(), MAX_TXS, MAX_CALLDATA, MAX_WITHDRAWALS)
+            .unwrap();
+        let (circuit, instance) = circuit(public_data, pi);
+        crate::test_util::run_real_prover(K, &circuit, instance).unwrap();
+    }
+
+    #[test]
+    fn pi_circuit_withdrawal_rows() {
+        let rows = public_data()
+            .withdrawal_rows::<Fr>(MAX_WITHDRAWALS)
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                [
+                    Fr::from(3),
+                    Fr::from(5),
+                    Fr::from(0x123),
+                    Fr::from(32_000_000_000)
+                ],
+                [Fr::zero(); 4],
+            ]
+        );
+    }
+
+    #[test]
+    fn pi_circuit_invalid_public_input() {
+        let public_data = public_data();
+        let pi = public_data
+            .instance(randomness(), MAX_TXS, MAX_CALLDATA, MAX_WITHDRAWALS)
+            .unwrap();
+        // Prove another state root than the one of the digest
+        let other_public_data = PublicData {
+            state_root: Word::from(0x5679),
+            ..public_data.clone()
+        };
+        assert!(verify(other_public_data, pi.clone()).is_err());
+        // Prove another withdrawal amount than the one of the digest
+        let mut other_public_data = public_data.clone();
+        other_public_data.withdrawals[0].amount += 1;
+        assert!(verify(other_public_data, pi.clone()).is_err());
+        // Prove another digest of the previous block than the public one
+        let other_public_data = PublicData {
+            prev_digest: Word::from(0xd1),
+            ..public_data.clone()
+        };
+        assert!(verify(other_public_data, pi.clone()).is_err());
+        for row in 0..N_PI_INSTANCE {
+            let mut other_pi = pi.clone();
+            other_pi[row] += Fr::one();
+            assert!(verify(public_data.clone(), other_pi).is_err());
+        }
+    }
+
+    #[test]
+    fn pi_circuit_capacity() {
+        let mut public_data = public_data();
+        public_data.withdrawals = vec![Withdrawal::default(); MAX_WITHDRAWALS + 1];
+        assert_eq!(
+            public_data.raw_public_inputs(Fr::one(), MAX_TXS, MAX_CALLDATA, MAX_WITHDRAWALS),
+            Err(PiCircuitError::TooManyWithdrawals {
+                n_withdrawals: MAX_WITHDRAWALS + 1,
+                max_withdrawals: MAX_WITHDRAWALS,
+            })
+        );
+        public_data.txs[1].call_data = vec![0; MAX_CALLDATA - 2];
+        assert_eq!(
+            public_data.raw_public_inputs(Fr::one(), MAX_TXS, MAX_CALLDATA, MAX_WITHDRAWALS),
+            Err(PiCircuitError::TooManyCallDataBytes {
+                n_bytes: MAX_CALLDATA + 1,
+                max_calldata: MAX_CALLDATA,
+            })
+        );
+        public_data.txs.push(Transaction::default());
+        assert_eq!(
+            public_data.raw_public_inputs(Fr::one(), MAX_TXS, MAX_CALLDATA, MAX_WITHDRAWALS),
+            Err(PiCircuitError::TooManyTxs {
+                n_txs: MAX_TXS + 1,
+                max_txs: MAX_TXS,
+            })
+        );
+        public_data.block_context.history_hashes = vec![Word::zero(); N_HISTORY_HASHES + 1];
+        assert_eq!(
+            public_data.raw_public_inputs(Fr::one(), MAX_TXS, MAX_CALLDATA, MAX_WITHDRAWALS),
+            Err(PiCircuitError::TooManyHistoryHashes(N_HISTORY_HASHES + 1))
+        );
+    }
+}
@@ -2,7 +2,7 @@
 use halo2_proofs::{
     arithmetic::FieldExt,
     circuit::Region,
-    plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, VirtualCells},
     poly::Rotation,
 };
 
@@ -25,6 +25,42 @@ pub struct RwTable {
 }
 
 impl<F: FieldExt> LookupTable<F, 11> for RwTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        [
+            self.rw_counter,
+            self.is_write,
+            self.tag,
+            self.key1,
+            self.key2,
+            self.key3,
+            self.key4,
+            self.value,
+            self.value_prev,
+            self.aux1,
+            self.aux2,
+        ]
+        .map(Into::into)
+        .to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        [
+            "rw_counter",
+            "is_write",
+            "tag",
+            "key1",
+            "key2",
+            "key3",
+            "key4",
+            "value",
+            "value_prev",
+            "aux1",
+            "aux2",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
     fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 11] {
         [
             meta.query_advice(self.rw_counter, Rotation::cur()),
@@ -0,0 +1,1286 @@
+//! The SHA-256 circuit implementation.
+//!
+//! It hashes its inputs with SHA-256 and assigns the SHA-256 table of
+//! `(input_rlc, input_len, digest_rlc)`, which is looked up by the SHA-256
+//! precompile gadget once the EVM circuit supports precompiles, the same way
+//! the keccak table is looked up for the keccak hashes.
+//!
+//! Every block of 64 bytes of a padded input takes `ROWS_PER_BLOCK` rows: 4
+//! rows with the state the block is compressed from, one row per round of the
+//! compression, and 4 rows with the state after the block.  Each row holds the
+//! bits of the words `a` and `e` of the state, since the other words of the
+//! state are the `a` and `e` of the previous rounds:
+//!
+//! | rows  | a                | e                | w                   |
+//! | ----- | ---------------- | ---------------- | ------------------- |
+//! | 0..4  | `H3, H2, H1, H0` | `H7, H6, H5, H4` |                     |
+//! | 4..68 | `a` of round t   | `e` of round t   | `W_t` of round t    |
+//! | 68..72| `H3, H2, H1, H0` | `H7, H6, H5, H4` |                     |
+//!
+//! The first 16 rounds also decompose their message word into bytes, which
+//! are accumulated into the RLC and the length of the input until its padding.
+//! The padding is constrained to be the `0x80` byte, zeros and the length in
+//! bits of the input, in the fewest blocks.  The row after the last block of
+//! an input holds the row of the input in the SHA-256 table.
+//!
+//! The circuit has a fixed capacity of `max_blocks` blocks, so that its
+//! proving key doesn't depend on the block.  The blocks of the inputs are
+//! followed by blocks of the empty input up to the capacity.
+
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    evm_circuit::{
+        table::LookupTable,
+        util::{constraint_builder::BaseConstraintBuilder, RandomLinearCombination},
+    },
+    gadget::rlc,
+    sha256_table::Sha256Table,
+    util::{power_of_randomness_from_instance, Expr},
+};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Layouter, Region, SimpleFloorPlanner},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells,
+    },
+    poly::Rotation,
+};
+use std::{convert::TryInto, fmt, marker::PhantomData};
+
+/// Number of bytes of a SHA-256 digest.
+pub const N_DIGEST_BYTES: usize = 32;
+// Number of bytes of a block.
+const N_BLOCK_BYTES: usize = 64;
+// Number of bits of a word.
+const WORD_BITS: usize = 32;
+// Number of bytes of a word.
+const N_WORD_BYTES: usize = 4;
+// Number of words of the message of a block.
+const N_MESSAGE_WORDS: usize = 16;
+// Number of rounds of the compression of a block.
+const N_ROUNDS: usize = 64;
+// Number of rows holding a state, before and after the rounds of a block.
+const N_STATE_ROWS: usize = 4;
+/// Number of rows of a block.
+pub const ROWS_PER_BLOCK: usize = N_STATE_ROWS + N_ROUNDS + N_STATE_ROWS;
+// Number of bits of a carry of a sum of words.
+const CARRY_BITS: usize = 3;
+
+/// Number of powers of the randomness used by the SHA-256 circuit, which are
+/// the ones needed by the random linear combination of a digest.
+pub const POW_RAND_SIZE: usize = N_DIGEST_BYTES - 1;
+
+const MAX_DEGREE: usize = 5;
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const ROUND_CONSTANTS: [u32; N_ROUNDS] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// Rotations of the Σ0 and Σ1 functions of the rounds.
+const BIG_SIGMA_0: [u32; 3] = [2, 13, 22];
+const BIG_SIGMA_1: [u32; 3] = [6, 11, 25];
+// Rotations and shift of the σ0 and σ1 functions of the message schedule.
+const SMALL_SIGMA_0: [u32; 3] = [7, 18, 3];
+const SMALL_SIGMA_1: [u32; 3] = [17, 19, 10];
+
+// Returns `ROTR^r0(x) ^ ROTR^r1(x) ^ ROTR^r2(x)`, or `SHR^r2(x)` as the last
+// term when `shift` is true.
+fn sigma(x: u32, [r0, r1, r2]: [u32; 3], shift: bool) -> u32 {
+    x.rotate_right(r0) ^ x.rotate_right(r1) ^ if shift { x >> r2 } else { x.rotate_right(r2) }
+}
+
+// Returns the message words of the blocks of the padded input.
+fn padded_blocks(input: &[u8]) -> Vec<[u32; N_MESSAGE_WORDS]> {
+    let mut padded = input.to_vec();
+    padded.push(0x80);
+    while padded.len() % N_BLOCK_BYTES != N_BLOCK_BYTES - 8 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&(input.len() as u64 * 8).to_be_bytes());
+
+    padded
+        .chunks(N_BLOCK_BYTES)
+        .map(|block| {
+            let mut words = [0; N_MESSAGE_WORDS];
+            for (word, bytes) in words.iter_mut().zip(block.chunks(N_WORD_BYTES)) {
+                *word = u32::from_be_bytes(bytes.try_into().unwrap());
+            }
+            words
+        })
+        .collect()
+}
+
+// Returns the number of blocks of the padded input of length `input_len`.
+fn n_blocks(input_len: usize) -> usize {
+    (input_len + 8) / N_BLOCK_BYTES + 1
+}
+
+// The compression of a block.
+struct Compression {
+    // Message schedule
+    w: [u32; N_ROUNDS],
+    // The words a and e after each round
+    a: [u32; N_ROUNDS],
+    e: [u32; N_ROUNDS],
+    // Carries of the sums of a, e and w of each round
+    carries: [[u64; 3]; N_ROUNDS],
+}
+
+impl Compression {
+    fn new(h: &[u32; 8], block: &[u32; N_MESSAGE_WORDS]) -> Self {
+        let mut w = [0; N_ROUNDS];
+        let mut carries = [[0; 3]; N_ROUNDS];
+        w[..N_MESSAGE_WORDS].copy_from_slice(block);
+        for t in N_MESSAGE_WORDS..N_ROUNDS {
+            let sum = sigma(w[t - 2], SMALL_SIGMA_1, true) as u64
+                + w[t - 7] as u64
+                + sigma(w[t - 15], SMALL_SIGMA_0, true) as u64
+                + w[t - 16] as u64;
+            w[t] = sum as u32;
+            carries[t][2] = sum >> WORD_BITS;
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *h;
+        let mut a_values = [0; N_ROUNDS];
+        let mut e_values = [0; N_ROUNDS];
+        for t in 0..N_ROUNDS {
+            let ch = (e & f) ^ (!e & g);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t1 = h as u64
+                + sigma(e, BIG_SIGMA_1, false) as u64
+                + ch as u64
+                + ROUND_CONSTANTS[t] as u64
+                + w[t] as u64;
+            let t2 = sigma(a, BIG_SIGMA_0, false) as u64 + maj as u64;
+            let [a_sum, e_sum] = [t1 + t2, d as u64 + t1];
+            h = g;
+            g = f;
+            f = e;
+            e = e_sum as u32;
+            d = c;
+            c = b;
+            b = a;
+            a = a_sum as u32;
+            a_values[t] = a;
+            e_values[t] = e;
+            carries[t][0] = a_sum >> WORD_BITS;
+            carries[t][1] = e_sum >> WORD_BITS;
+        }
+
+        Self {
+            w,
+            a: a_values,
+            e: e_values,
+            carries,
+        }
+    }
+
+    // Returns the state after the compression, `a, b, c, d, e, f, g, h`.
+    fn state(&self) -> [u32; 8] {
+        let last = N_ROUNDS - 1;
+        [
+            self.a[last],
+            self.a[last - 1],
+            self.a[last - 2],
+            self.a[last - 3],
+            self.e[last],
+            self.e[last - 1],
+            self.e[last - 2],
+            self.e[last - 3],
+        ]
+    }
+}
+
+/// Returns the SHA-256 digest of `input`.
+pub fn sha256(input: &[u8]) -> [u8; N_DIGEST_BYTES] {
+    let h = padded_blocks(input).iter().fold(IV, |h, block| {
+        let state = Compression::new(&h, block).state();
+        let mut h_next = h;
+        for (word, value) in h_next.iter_mut().zip(state) {
+            *word = word.wrapping_add(value);
+        }
+        h_next
+    });
+    let mut digest = [0; N_DIGEST_BYTES];
+    for (bytes, word) in digest.chunks_mut(N_WORD_BYTES).zip(h) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+/// Returns the row of `input` in the SHA-256 table, `(input_rlc, input_len,
+/// digest_rlc)`, where the RLC of the digest is the one of its little endian
+/// bytes.
+pub fn sha256_table_assignments<F: Field>(input: &[u8], randomness: F) -> [F; 3] {
+    let mut digest = sha256(input);
+    digest.reverse();
+    [
+        rlc::value(input, randomness),
+        F::from(input.len() as u64),
+        RandomLinearCombination::random_linear_combine(digest, randomness),
+    ]
+}
+
+// The witness of a row of the circuit.
+#[derive(Clone, Debug, Default)]
+struct Sha256Row<F> {
+    a: u32,
+    e: u32,
+    w: u32,
+    carries: [u64; 3],
+    is_padding: [bool; N_WORD_BYTES],
+    input_rlc: [F; N_WORD_BYTES],
+    input_len: u64,
+    is_first: bool,
+    is_final: bool,
+    table: [F; 3],
+}
+
+// Returns the rows of the blocks of `input`.
+fn sha256_rows<F: Field>(input: &[u8], randomness: F) -> Vec<Sha256Row<F>> {
+    let blocks = padded_blocks(input);
+    let mut rows = Vec::with_capacity(blocks.len() * ROWS_PER_BLOCK);
+    let mut h = IV;
+    // The values carried from a row to the next one
+    let mut carried = Sha256Row::<F>::default();
+    for (block_idx, block) in blocks.iter().enumerate() {
+        carried.is_first = block_idx == 0;
+        carried.is_final = block_idx == blocks.len() - 1;
+
+        for idx in 0..N_STATE_ROWS {
+            rows.push(Sha256Row {
+                a: h[3 - idx],
+                e: h[7 - idx],
+                ..carried.clone()
+            });
+        }
+
+        let compression = Compression::new(&h, block);
+        for t in 0..N_ROUNDS {
+            if t < N_MESSAGE_WORDS {
+                let mut input_rlc = carried.input_rlc[N_WORD_BYTES - 1];
+                for (idx, byte) in compression.w[t].to_be_bytes().iter().enumerate() {
+                    let offset = block_idx * N_BLOCK_BYTES + t * N_WORD_BYTES + idx;
+                    carried.is_padding[idx] = offset >= input.len();
+                    if !carried.is_padding[idx] {
+                        input_rlc = rlc::extend(input_rlc, &[*byte], randomness);
+                        carried.input_len += 1;
+                    }
+                    carried.input_rlc[idx] = input_rlc;
+                }
+            }
+            rows.push(Sha256Row {
+                a: compression.a[t],
+                e: compression.e[t],
+                w: compression.w[t],
+                carries: compression.carries[t],
+                ..carried.clone()
+            });
+        }
+
+        let mut carries = [0; 8];
+        for ((word, carry), value) in h
+            .iter_mut()
+            .zip(carries.iter_mut())
+            .zip(compression.state())
+        {
+            let sum = *word as u64 + value as u64;
+            *word = sum as u32;
+            *carry = sum >> WORD_BITS;
+        }
+        for idx in 0..N_STATE_ROWS {
+            rows.push(Sha256Row {
+                a: h[3 - idx],
+                e: h[7 - idx],
+                carries: [carries[3 - idx], carries[7 - idx], 0],
+                ..carried.clone()
+            });
+        }
+    }
+    rows.last_mut().unwrap().table = sha256_table_assignments(input, randomness);
+
+    rows
+}
+
+// Returns the word of the little endian `bits`.
+fn from_bits<F: Field>(bits: &[Expression<F>]) -> Expression<F> {
+    bits.iter()
+        .rev()
+        .fold(0.expr(), |acc, bit| acc * 2.expr() + bit.clone())
+}
+
+// Returns `lhs ^ rhs` of the bits `lhs` and `rhs`.
+fn xor<F: Field>(lhs: Expression<F>, rhs: Expression<F>) -> Expression<F> {
+    lhs.clone() + rhs.clone() - 2.expr() * lhs * rhs
+}
+
+// Returns the word of `sigma(x)` of the bits of `x`.
+fn sigma_expr<F: Field>(
+    x: &[Expression<F>; WORD_BITS],
+    [r0, r1, r2]: [u32; 3],
+    shift: bool,
+) -> Expression<F> {
+    let bits = (0..WORD_BITS)
+        .map(|idx| {
+            let rotr = |r: u32| x[(idx + r as usize) % WORD_BITS].clone();
+            let last = if !shift {
+                rotr(r2)
+            } else if idx + (r2 as usize) < WORD_BITS {
+                x[idx + r2 as usize].clone()
+            } else {
+                0.expr()
+            };
+            xor(xor(rotr(r0), rotr(r1)), last)
+        })
+        .collect::<Vec<_>>();
+    from_bits(&bits)
+}
+
+/// Config of the SHA-256 circuit.
+#[derive(Clone, Debug)]
+pub struct Sha256CircuitConfig<F> {
+    // Enables all the rows of the blocks
+    q_enable: Selector,
+    // Enable the first row of the first block, and of the next blocks
+    q_first_block: Selector,
+    q_next_block: Selector,
+    // Enables the rows of a block after its first one
+    q_block_rows: Selector,
+    // Enable the state rows before the rounds of the first block, and of the
+    // next blocks
+    q_init_first: Selector,
+    q_init_next: Selector,
+    // Enables the rounds
+    q_round: Selector,
+    // Enables the rounds whose message word is computed by the message
+    // schedule
+    q_schedule: Selector,
+    // Enables the rounds of the message words of the block
+    q_message: Selector,
+    // Enables the rounds of the message words before the length of the input
+    q_message_data: Selector,
+    // Enable the rounds of the high and the low word of the length of the
+    // input
+    q_length_hi: Selector,
+    q_length_lo: Selector,
+    // Enables the rows which don't accumulate input bytes, besides the first
+    // row of a block
+    q_carry: Selector,
+    // Enables the state rows after the rounds
+    q_output: Selector,
+    // Enable the last row of a block, which holds the row of the SHA-256
+    // table, and the other rows
+    q_export: Selector,
+    q_not_export: Selector,
+    round_constant: Column<Fixed>,
+    // Words a and e of the initial state
+    iv: [Column<Fixed>; 2],
+    // Little endian bits of the words a, e and w of the row
+    a_bits: [Column<Advice>; WORD_BITS],
+    e_bits: [Column<Advice>; WORD_BITS],
+    w_bits: [Column<Advice>; WORD_BITS],
+    // Little endian bits of the carries of the sums of a, e and w
+    carry_bits: [[Column<Advice>; CARRY_BITS]; 3],
+    // Whether each byte of the message word is padding
+    is_padding: [Column<Advice>; N_WORD_BYTES],
+    // RLC of the input up to each byte of the message word
+    input_rlc: [Column<Advice>; N_WORD_BYTES],
+    // Number of bytes of the input up to the message word
+    input_len: Column<Advice>,
+    // Whether the block is the first and the last one of its input
+    is_first: Column<Advice>,
+    is_final: Column<Advice>,
+    sha256_table: Sha256Table,
+    _marker: PhantomData<F>,
+}
+
+impl<F> Annotate for Sha256CircuitConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_config(owner, "sha256_table", &self.sha256_table);
+        annotations.annotate(owner, "round_constant", self.round_constant);
+        annotations.annotate_all(owner, "iv", self.iv);
+        annotations.annotate_all(owner, "a_bits", self.a_bits);
+        annotations.annotate_all(owner, "e_bits", self.e_bits);
+        annotations.annotate_all(owner, "w_bits", self.w_bits);
+        for (idx, carry_bits) in self.carry_bits.iter().enumerate() {
+            annotations.annotate_all(owner, &format!("carry_bits_{}", idx), *carry_bits);
+        }
+        annotations.annotate_all(owner, "is_padding", self.is_padding);
+        annotations.annotate_all(owner, "input_rlc", self.input_rlc);
+        annotations.annotate(owner, "input_len", self.input_len);
+        annotations.annotate(owner, "is_first", self.is_first);
+        annotations.annotate(owner, "is_final", self.is_final);
+    }
+}
+
+impl<F: Field> Sha256CircuitConfig<F> {
+    /// Configure the SHA-256 circuit, which assigns the rows of
+    /// `sha256_table`.  `power_of_randomness` holds `r^1, .., r^31` of the
+    /// randomness `r` of the tables.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Expression<F>; POW_RAND_SIZE],
+        sha256_table: Sha256Table,
+    ) -> Self {
+        let q_enable = meta.selector();
+        let q_first_block = meta.selector();
+        let q_next_block = meta.selector();
+        let q_block_rows = meta.selector();
+        let q_init_first = meta.selector();
+        let q_init_next = meta.selector();
+        let q_round = meta.selector();
+        let q_schedule = meta.selector();
+        let q_message = meta.selector();
+        let q_message_data = meta.selector();
+        let q_length_hi = meta.selector();
+        let q_length_lo = meta.selector();
+        let q_carry = meta.selector();
+        let q_output = meta.selector();
+        let q_export = meta.selector();
+        let q_not_export = meta.selector();
+        let round_constant = meta.fixed_column();
+        let iv = [(); 2].map(|_| meta.fixed_column());
+        let [a_bits, e_bits, w_bits] =
+            [(); 3].map(|_| [(); WORD_BITS].map(|_| meta.advice_column()));
+        let carry_bits = [(); 3].map(|_| [(); CARRY_BITS].map(|_| meta.advice_column()));
+        let is_padding = [(); N_WORD_BYTES].map(|_| meta.advice_column());
+        let input_rlc = [(); N_WORD_BYTES].map(|_| meta.advice_column());
+        let [input_len, is_first, is_final] = [(); 3].map(|_| meta.advice_column());
+
+        let two_pow_32 = Expression::Constant(F::from(1 << WORD_BITS));
+        // The columns whose value is carried from the last byte of the
+        // previous message word
+        let carried = [
+            input_rlc[N_WORD_BYTES - 1],
+            input_len,
+            is_padding[N_WORD_BYTES - 1],
+        ];
+
+        let query_bits =
+            |meta: &mut VirtualCells<F>, bits: [Column<Advice>; WORD_BITS], rotation: i32| {
+                bits.map(|bit| meta.query_advice(bit, Rotation(rotation)))
+            };
+        let query_word =
+            |meta: &mut VirtualCells<F>, bits: [Column<Advice>; WORD_BITS], rotation: i32| {
+                from_bits(&query_bits(meta, bits, rotation))
+            };
+        let query_carries = |meta: &mut VirtualCells<F>| {
+            carry_bits
+                .map(|bits| from_bits(&bits.map(|bit| meta.query_advice(bit, Rotation::cur()))))
+        };
+        // Returns the bytes of the message word of the row, most significant
+        // first, together with whether the padding started before each of
+        // them.
+        let padding_bytes = |meta: &mut VirtualCells<F>| {
+            let w = query_bits(meta, w_bits, 0);
+            let mut is_padding_prev =
+                meta.query_advice(is_padding[N_WORD_BYTES - 1], Rotation::prev());
+            let mut bytes = Vec::with_capacity(N_WORD_BYTES);
+            for (idx, is_padding) in is_padding.iter().enumerate() {
+                let byte = from_bits(&w[(N_WORD_BYTES - 1 - idx) * 8..(N_WORD_BYTES - idx) * 8]);
+                bytes.push((is_padding_prev, byte));
+                is_padding_prev = meta.query_advice(*is_padding, Rotation::cur());
+            }
+            bytes
+        };
+
+        meta.create_gate("bits are boolean", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            for bit in a_bits
+                .iter()
+                .chain(e_bits.iter())
+                .chain(w_bits.iter())
+                .chain(carry_bits.iter().flatten())
+            {
+                cb.require_boolean("bit is boolean", meta.query_advice(*bit, Rotation::cur()));
+            }
+            cb.gate(meta.query_selector(q_enable))
+        });
+
+        meta.create_gate("first row of the first block", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            cb.require_equal(
+                "first block is the first one of its input",
+                meta.query_advice(is_first, Rotation::cur()),
+                1.expr(),
+            );
+            cb.require_boolean(
+                "is_final is boolean",
+                meta.query_advice(is_final, Rotation::cur()),
+            );
+            for column in carried {
+                cb.require_zero(
+                    "accumulated input starts empty",
+                    meta.query_advice(column, Rotation::cur()),
+                );
+            }
+            cb.gate(meta.query_selector(q_first_block))
+        });
+
+        meta.create_gate("first row of the next blocks", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            let is_first = meta.query_advice(is_first, Rotation::cur());
+            cb.require_equal(
+                "block is the first one of its input after the final one of the previous input",
+                is_first.clone(),
+                meta.query_advice(is_final, Rotation::prev()),
+            );
+            cb.require_boolean(
+                "is_final is boolean",
+                meta.query_advice(is_final, Rotation::cur()),
+            );
+            for column in carried {
+                cb.require_equal(
+                    "accumulated input starts empty or continues the previous block",
+                    meta.query_advice(column, Rotation::cur()),
+                    (1.expr() - is_first.clone()) * meta.query_advice(column, Rotation::prev()),
+                );
+            }
+            cb.gate(meta.query_selector(q_next_block))
+        });
+
+        meta.create_gate("rows of a block", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            for column in [is_first, is_final] {
+                cb.require_equal(
+                    "is_first and is_final are the same in all rows of a block",
+                    meta.query_advice(column, Rotation::cur()),
+                    meta.query_advice(column, Rotation::prev()),
+                );
+            }
+            cb.gate(meta.query_selector(q_block_rows))
+        });
+
+        meta.create_gate("accumulated input is carried", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            for column in carried {
+                cb.require_equal(
+                    "accumulated input is the one of the previous row",
+                    meta.query_advice(column, Rotation::cur()),
+                    meta.query_advice(column, Rotation::prev()),
+                );
+            }
+            cb.gate(meta.query_selector(q_carry))
+        });
+
+        meta.create_gate("state before the rounds of the first block", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            for (bits, iv) in [a_bits, e_bits].iter().zip(iv) {
+                cb.require_equal(
+                    "state of the first block is the initial state",
+                    query_word(meta, *bits, 0),
+                    meta.query_fixed(iv, Rotation::cur()),
+                );
+            }
+            cb.gate(meta.query_selector(q_init_first))
+        });
+
+        meta.create_gate("state before the rounds of the next blocks", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            let is_first = meta.query_advice(is_first, Rotation::cur());
+            for (bits, iv) in [a_bits, e_bits].iter().zip(iv) {
+                cb.require_equal(
+                    "state is the initial state or the state after the previous block",
+                    query_word(meta, *bits, 0),
+                    is_first.clone() * meta.query_fixed(iv, Rotation::cur())
+                        + (1.expr() - is_first.clone())
+                            * query_word(meta, *bits, -(N_STATE_ROWS as i32)),
+                );
+            }
+            cb.gate(meta.query_selector(q_init_next))
+        });
+
+        meta.create_gate("round", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            // The state before the round is a, b, c, d, e, f, g, h
+            let [a, b, c] = [-1, -2, -3].map(|rotation| query_bits(meta, a_bits, rotation));
+            let [e, f, g] = [-1, -2, -3].map(|rotation| query_bits(meta, e_bits, rotation));
+            let d = query_word(meta, a_bits, -4);
+            let h = query_word(meta, e_bits, -4);
+            let w = query_word(meta, w_bits, 0);
+
+            let ch = (0..WORD_BITS)
+                .map(|idx| {
+                    e[idx].clone() * f[idx].clone() + (1.expr() - e[idx].clone()) * g[idx].clone()
+                })
+                .collect::<Vec<_>>();
+            let maj = (0..WORD_BITS)
+                .map(|idx| {
+                    let (a, b, c) = (a[idx].clone(), b[idx].clone(), c[idx].clone());
+                    a.clone() * b.clone() + a.clone() * c.clone() + b.clone() * c.clone()
+                        - 2.expr() * a * b * c
+                })
+                .collect::<Vec<_>>();
+            let t1 = h
+                + sigma_expr(&e, BIG_SIGMA_1, false)
+                + from_bits(&ch)
+                + meta.query_fixed(round_constant, Rotation::cur())
+                + w;
+            let t2 = sigma_expr(&a, BIG_SIGMA_0, false) + from_bits(&maj);
+
+            let [carry_a, carry_e, _] = query_carries(meta);
+            cb.require_equal(
+                "a = T1 + T2 mod 2^32",
+                query_word(meta, a_bits, 0) + carry_a * two_pow_32.clone(),
+                t1.clone() + t2,
+            );
+            cb.require_equal(
+                "e = d + T1 mod 2^32",
+                query_word(meta, e_bits, 0) + carry_e * two_pow_32.clone(),
+                d + t1,
+            );
+            cb.gate(meta.query_selector(q_round))
+        });
+
+        meta.create_gate("message schedule", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            let [w_2, w_15] = [-2, -15].map(|rotation| query_bits(meta, w_bits, rotation));
+            let [_, _, carry_w] = query_carries(meta);
+            cb.require_equal(
+                "w = σ1(w_-2) + w_-7 + σ0(w_-15) + w_-16 mod 2^32",
+                query_word(meta, w_bits, 0) + carry_w * two_pow_32.clone(),
+                sigma_expr(&w_2, SMALL_SIGMA_1, true)
+                    + query_word(meta, w_bits, -7)
+                    + sigma_expr(&w_15, SMALL_SIGMA_0, true)
+                    + query_word(meta, w_bits, -16),
+            );
+            cb.gate(meta.query_selector(q_schedule))
+        });
+
+        meta.create_gate(
+            "message word bytes are accumulated until the padding",
+            |meta| {
+                let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+                let randomness = power_of_randomness[0].clone();
+                let mut input_rlc_prev =
+                    meta.query_advice(input_rlc[N_WORD_BYTES - 1], Rotation::prev());
+                let mut len = meta.query_advice(input_len, Rotation::prev());
+                for (idx, (is_padding_prev, byte)) in padding_bytes(meta).into_iter().enumerate() {
+                    let is_padding = meta.query_advice(is_padding[idx], Rotation::cur());
+                    let input_rlc = meta.query_advice(input_rlc[idx], Rotation::cur());
+                    cb.require_boolean("is_padding is boolean", is_padding.clone());
+                    cb.require_zero(
+                        "padding isn't followed by input bytes",
+                        is_padding_prev.clone() * (1.expr() - is_padding.clone()),
+                    );
+                    cb.require_zero(
+                        "padding starts with 0x80",
+                        (is_padding.clone() - is_padding_prev) * (byte.clone() - 0x80.expr()),
+                    );
+                    cb.require_equal(
+                        "input_rlc accumulates the bytes before the padding",
+                        input_rlc.clone(),
+                        is_padding.clone() * input_rlc_prev.clone()
+                            + (1.expr() - is_padding.clone())
+                                * (input_rlc_prev * randomness.clone() + byte),
+                    );
+                    len = len + 1.expr() - is_padding;
+                    input_rlc_prev = input_rlc;
+                }
+                cb.require_equal(
+                    "input_len counts the bytes before the padding",
+                    meta.query_advice(input_len, Rotation::cur()),
+                    len,
+                );
+                cb.gate(meta.query_selector(q_message))
+            },
+        );
+
+        meta.create_gate("padding bytes after 0x80 are 0", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            for (is_padding_prev, byte) in padding_bytes(meta) {
+                cb.require_zero("padding byte is 0", is_padding_prev * byte);
+            }
+            cb.gate(meta.query_selector(q_message_data))
+        });
+
+        meta.create_gate("high word of the length of the input", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            let is_final = meta.query_advice(is_final, Rotation::cur());
+            cb.require_equal(
+                "padding starts before the length in the final block only",
+                meta.query_advice(is_padding[N_WORD_BYTES - 1], Rotation::prev()),
+                is_final.clone(),
+            );
+            for (is_padding_prev, byte) in padding_bytes(meta) {
+                cb.require_zero(
+                    "padding byte is 0 in a block before the final one",
+                    (1.expr() - is_final.clone()) * is_padding_prev * byte,
+                );
+            }
+            cb.require_zero(
+                "high word of the length in bits is 0",
+                is_final * query_word(meta, w_bits, 0),
+            );
+            cb.gate(meta.query_selector(q_length_hi))
+        });
+
+        meta.create_gate("low word of the length of the input", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            let is_final = meta.query_advice(is_final, Rotation::cur());
+            for (is_padding_prev, byte) in padding_bytes(meta) {
+                cb.require_zero(
+                    "padding byte is 0 in a block before the final one",
+                    (1.expr() - is_final.clone()) * is_padding_prev * byte,
+                );
+            }
+            cb.require_zero(
+                "low word of the length in bits is 8 * input_len",
+                is_final
+                    * (query_word(meta, w_bits, 0)
+                        - 8.expr() * meta.query_advice(input_len, Rotation::cur())),
+            );
+            cb.gate(meta.query_selector(q_length_lo))
+        });
+
+        meta.create_gate("state after the rounds", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            let [carry_a, carry_e, _] = query_carries(meta);
+            for (bits, carry) in [(a_bits, carry_a), (e_bits, carry_e)] {
+                cb.require_equal(
+                    "state after the block is the sum of the states before and after the rounds",
+                    query_word(meta, bits, 0) + carry * two_pow_32.clone(),
+                    query_word(meta, bits, -((N_STATE_ROWS + N_ROUNDS) as i32))
+                        + query_word(meta, bits, -(N_STATE_ROWS as i32)),
+                );
+            }
+            cb.gate(meta.query_selector(q_output))
+        });
+
+        meta.create_gate("row of SHA-256 table", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            let is_final = meta.query_advice(is_final, Rotation::cur());
+            // The words of the digest are H0, .., H3 and H4, .., H7 in the
+            // last rows of a and e
+            let mut digest_bytes = Vec::with_capacity(N_DIGEST_BYTES);
+            for bits in [a_bits, e_bits] {
+                for rotation in 0..N_STATE_ROWS as i32 {
+                    let word = query_bits(meta, bits, -rotation);
+                    for idx in (0..N_WORD_BYTES).rev() {
+                        digest_bytes.push(from_bits(&word[idx * 8..(idx + 1) * 8]));
+                    }
+                }
+            }
+            digest_bytes.reverse();
+            let digest_rlc =
+                RandomLinearCombination::<F, N_DIGEST_BYTES>::random_linear_combine_expr(
+                    digest_bytes.try_into().unwrap(),
+                    &power_of_randomness,
+                );
+
+            let [table_input_rlc, table_input_len, table_digest_rlc] =
+                sha256_table.table_exprs(meta);
+            cb.require_equal(
+                "input_rlc of the final block is in the table",
+                table_input_rlc,
+                is_final.clone() * meta.query_advice(input_rlc[N_WORD_BYTES - 1], Rotation::cur()),
+            );
+            cb.require_equal(
+                "input_len of the final block is in the table",
+                table_input_len,
+                is_final.clone() * meta.query_advice(input_len, Rotation::cur()),
+            );
+            cb.require_equal(
+                "digest_rlc of the final block is in the table",
+                table_digest_rlc,
+                is_final * digest_rlc,
+            );
+            cb.gate(meta.query_selector(q_export))
+        });
+
+        meta.create_gate("not a row of SHA-256 table", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            for value in sha256_table.table_exprs(meta) {
+                cb.require_zero("row of SHA-256 table is 0", value);
+            }
+            cb.gate(meta.query_selector(q_not_export))
+        });
+
+        Self {
+            q_enable,
+            q_first_block,
+            q_next_block,
+            q_block_rows,
+            q_init_first,
+            q_init_next,
+            q_round,
+            q_schedule,
+            q_message,
+            q_message_data,
+            q_length_hi,
+            q_length_lo,
+            q_carry,
+            q_output,
+            q_export,
+            q_not_export,
+            round_constant,
+            iv,
+            a_bits,
+            e_bits,
+            w_bits,
+            carry_bits,
+            is_padding,
+            input_rlc,
+            input_len,
+            is_first,
+            is_final,
+            sha256_table,
+            _marker: PhantomData,
+        }
+    }
+
+    // Enables the selectors and assigns the fixed columns of the row at
+    // `offset`, which depend only on its position in its block.
+    fn assign_fixed_row(&self, region: &mut Region<'_, F>, offset: usize) -> Result<(), Error> {
+        let is_first_block = offset < ROWS_PER_BLOCK;
+        let row_idx = offset % ROWS_PER_BLOCK;
+
+        let mut selectors = vec![self.q_enable];
+        if row_idx == 0 {
+            selectors.push(if is_first_block {
+                self.q_first_block
+            } else {
+                self.q_next_block
+            });
+        } else {
+            selectors.push(self.q_block_rows);
+        }
+        let mut round_constant = 0;
+        let mut iv = [0; 2];
+        if row_idx < N_STATE_ROWS {
+            selectors.push(if is_first_block {
+                self.q_init_first
+            } else {
+                self.q_init_next
+            });
+            if row_idx > 0 {
+                selectors.push(self.q_carry);
+            }
+            iv = [IV[3 - row_idx], IV[7 - row_idx]];
+        } else if row_idx < N_STATE_ROWS + N_ROUNDS {
+            let t = row_idx - N_STATE_ROWS;
+            selectors.push(self.q_round);
+            round_constant = ROUND_CONSTANTS[t];
+            if t < N_MESSAGE_WORDS - 2 {
+                selectors.extend([self.q_message, self.q_message_data]);
+            } else if t == N_MESSAGE_WORDS - 2 {
+                selectors.extend([self.q_message, self.q_length_hi]);
+            } else if t == N_MESSAGE_WORDS - 1 {
+                selectors.extend([self.q_message, self.q_length_lo]);
+            } else {
+                selectors.extend([self.q_schedule, self.q_carry]);
+            }
+        } else {
+            selectors.extend([self.q_output, self.q_carry]);
+        }
+        selectors.push(if row_idx == ROWS_PER_BLOCK - 1 {
+            self.q_export
+        } else {
+            self.q_not_export
+        });
+
+        for selector in selectors {
+            selector.enable(region, offset)?;
+        }
+        region.assign_fixed(
+            || "round constant",
+            self.round_constant,
+            offset,
+            || Ok(F::from(round_constant as u64)),
+        )?;
+        for (column, value) in self.iv.iter().zip(iv) {
+            region.assign_fixed(|| "iv", *column, offset, || Ok(F::from(value as u64)))?;
+        }
+        Ok(())
+    }
+
+    // Assigns the advice columns of the row at `offset`.
+    fn assign_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: &Sha256Row<F>,
+    ) -> Result<(), Error> {
+        for (bits, word) in [
+            (self.a_bits, row.a),
+            (self.e_bits, row.e),
+            (self.w_bits, row.w),
+        ] {
+            for (idx, column) in bits.iter().enumerate() {
+                region.assign_advice(
+                    || "word bit",
+                    *column,
+                    offset,
+                    || Ok(F::from(((word >> idx) & 1) as u64)),
+                )?;
+            }
+        }
+        for (bits, carry) in self.carry_bits.iter().zip(row.carries) {
+            for (idx, column) in bits.iter().enumerate() {
+                region.assign_advice(
+                    || "carry bit",
+                    *column,
+                    offset,
+                    || Ok(F::from((carry >> idx) & 1)),
+                )?;
+            }
+        }
+        for (column, value) in self
+            .is_padding
+            .iter()
+            .zip(row.is_padding.map(|is_padding| F::from(is_padding as u64)))
+            .chain(self.input_rlc.iter().zip(row.input_rlc))
+            .chain([
+                (&self.input_len, F::from(row.input_len)),
+                (&self.is_first, F::from(row.is_first as u64)),
+                (&self.is_final, F::from(row.is_final as u64)),
+            ])
+        {
+            region.assign_advice(|| "input", *column, offset, || Ok(value))?;
+        }
+        self.sha256_table.assign(region, offset, row.table)
+    }
+
+    // Assigns the rows of the blocks.
+    fn assign(&self, layouter: &mut impl Layouter<F>, rows: &[Sha256Row<F>]) -> Result<(), Error> {
+        layouter.assign_region(
+            || "SHA-256 blocks",
+            |mut region| {
+                for (offset, row) in rows.iter().enumerate() {
+                    self.assign_fixed_row(&mut region, offset)?;
+                    self.assign_row(&mut region, offset, row)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Error when the inputs don't fit in the SHA-256 circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Sha256CircuitError {
+    /// The padded inputs have more blocks than the circuit can hold.
+    TooManyBlocks {
+        /// Number of blocks of the padded inputs
+        n_blocks: usize,
+        /// Maximum number of blocks of the circuit
+        max_blocks: usize,
+    },
+}
+
+impl fmt::Display for Sha256CircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyBlocks {
+                n_blocks,
+                max_blocks,
+            } => write!(
+                f,
+                "too many blocks for the SHA-256 circuit: {} > max_blocks {}",
+                n_blocks, max_blocks
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Sha256CircuitError {}
+
+/// SHA-256 circuit that hashes the inputs looked up by the other circuits.
+#[derive(Clone, Default, Debug)]
+pub struct Sha256Circuit<F> {
+    /// Randomness of the RLC of the tables
+    pub randomness: F,
+    /// Inputs to hash
+    pub inputs: Vec<Vec<u8>>,
+    /// Maximum number of blocks of the padded inputs of the circuit, the ones
+    /// after the blocks of the inputs are the block of the empty input
+    pub max_blocks: usize,
+}
+
+impl<F: Field> Sha256Circuit<F> {
+    // Returns the rows of the blocks of the inputs, followed by the ones of
+    // the empty input up to `max_blocks`.
+    fn padded_rows(&self) -> Vec<Sha256Row<F>> {
+        let mut rows = self
+            .inputs
+            .iter()
+            .flat_map(|input| sha256_rows(input, self.randomness))
+            .collect::<Vec<_>>();
+        let padding_rows = sha256_rows(&[], self.randomness);
+        while rows.len() < self.max_blocks * ROWS_PER_BLOCK {
+            rows.extend(padding_rows.iter().cloned());
+        }
+        rows
+    }
+
+    /// Checks that the blocks of the padded inputs fit in the capacity of the
+    /// circuit.
+    pub fn check_capacity(&self) -> Result<(), Sha256CircuitError> {
+        let n_blocks = self.inputs.iter().map(|input| n_blocks(input.len())).sum();
+        if n_blocks > self.max_blocks {
+            return Err(Sha256CircuitError::TooManyBlocks {
+                n_blocks,
+                max_blocks: self.max_blocks,
+            });
+        }
+        Ok(())
+    }
+
+    /// Assign the SHA-256 circuit.
+    #[tracing::instrument(name = "sha256_circuit_assign", skip_all)]
+    pub fn assign(
+        &self,
+        config: &Sha256CircuitConfig<F>,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        self.check_capacity().map_err(|_| Error::Synthesis)?;
+
+        config.assign(layouter, &self.padded_rows())
+    }
+}
+
+impl<F: Field> Circuit<F> for Sha256Circuit<F> {
+    type Config = Sha256CircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            max_blocks: self.max_blocks,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let sha256_table = Sha256Table::construct(meta);
+        let power_of_randomness = power_of_randomness_from_instance::<_, POW_RAND_SIZE>(meta);
+
+        Sha256CircuitConfig::configure(meta, power_of_randomness, sha256_table)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        self.assign(&config, &mut layouter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        n_blocks, padded_blocks, sha256, sha256_table_assignments, Sha256Circuit,
+        Sha256CircuitConfig, Sha256CircuitError, POW_RAND_SIZE,
+    };
+    use crate::{
+        evm_circuit::table::LookupTable, gadget::rlc::PowersOfRandomness,
+        sha256_table::Sha256Table, util::power_of_randomness_from_instance,
+    };
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::{MockProver, VerifyFailure},
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
+        poly::Rotation,
+    };
+    use pairing::bn256::Fr;
+
+    const K: u32 = 10;
+    const MAX_BLOCKS: usize = 4;
+    const TWO_BLOCKS_INPUT: &[u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+
+    // Circuit that looks up rows in the SHA-256 table of its SHA-256 circuit.
+    #[derive(Default)]
+    struct Sha256LookupCircuit {
+        sha256_circuit: Sha256Circuit<Fr>,
+        lookups: Vec<[Fr; 3]>,
+    }
+
+    impl Circuit<Fr> for Sha256LookupCircuit {
+        type Config = (Sha256CircuitConfig<Fr>, Selector, [Column<Advice>; 3]);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let sha256_table = Sha256Table::construct(meta);
+            let power_of_randomness = power_of_randomness_from_instance::<_, POW_RAND_SIZE>(meta);
+            let q_lookup = meta.complex_selector();
+            let columns = [(); 3].map(|_| meta.advice_column());
+
+            meta.lookup_any("Row in SHA-256 table", |meta| {
+                let q_lookup = meta.query_selector(q_lookup);
+                columns
+                    .iter()
+                    .map(|column| meta.query_advice(*column, Rotation::cur()))
+                    .zip(sha256_table.table_exprs(meta))
+                    .map(|(value, table)| (q_lookup.clone() * value, table))
+                    .collect()
+            });
+
+            (
+                Sha256CircuitConfig::configure(meta, power_of_randomness, sha256_table),
+                q_lookup,
+                columns,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, q_lookup, columns): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            self.sha256_circuit.assign(&config, &mut layouter)?;
+            layouter.assign_region(
+                || "lookups",
+                |mut region| {
+                    for (offset, row) in self.lookups.iter().enumerate() {
+                        q_lookup.enable(&mut region, offset)?;
+                        for (column, value) in columns.iter().zip(row) {
+                            region.assign_advice(|| "lookup", *column, offset, || Ok(*value))?;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn verify(inputs: Vec<Vec<u8>>, lookups: Vec<[Fr; 3]>) -> Result<(), Vec<VerifyFailure>> {
+        let randomness = Fr::from(0x10000);
+        let circuit = Sha256LookupCircuit {
+            sha256_circuit: Sha256Circuit {
+                randomness,
+                inputs,
+                max_blocks: MAX_BLOCKS,
+            },
+            lookups,
+        };
+        let power_of_randomness = PowersOfRandomness::new(randomness)
+            .take(POW_RAND_SIZE)
+            .into_iter()
+            .map(|power| vec![power; (1 << K) - 64])
+            .collect();
+        let prover = MockProver::<Fr>::run(K, &circuit, power_of_randomness).unwrap();
+        prover.verify()
+    }
+
+    fn inputs() -> Vec<Vec<u8>> {
+        vec![b"abc".to_vec(), TWO_BLOCKS_INPUT.to_vec()]
+    }
+
+    #[test]
+    fn sha256_digest() {
+        for (input, digest) in [
+            (
+                &b""[..],
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                &b"abc"[..],
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                TWO_BLOCKS_INPUT,
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            ),
+        ] {
+            assert_eq!(sha256(input).to_vec(), hex::decode(digest).unwrap());
+        }
+    }
+
+    #[test]
+    fn sha256_n_blocks() {
+        for input_len in 0..200 {
+            assert_eq!(
+                n_blocks(input_len),
+                padded_blocks(&vec![0; input_len]).len()
+            );
+        }
+    }
+
+    #[test]
+    fn sha256_circuit_valid() {
+        let randomness = Fr::from(0x10000);
+        let lookups = inputs()
+            .iter()
+            .chain(std::iter::once(&vec![]))
+            .map(|input| sha256_table_assignments(input, randomness))
+            .collect();
+        assert_eq!(verify(inputs(), lookups), Ok(()));
+    }
+
+    // The SHA-256 circuit alone, as the lookup circuit has no shape without
+    // its witnesses.
+    #[cfg(feature = "real-prover")]
+    #[test]
+    fn sha256_circuit_real_prover() {
+        let randomness = Fr::from(0x10000);
+        let circuit = Sha256Circuit {
+            randomness,
+            inputs: inputs(),
+            max_blocks: MAX_BLOCKS,
+        };
+        let power_of_randomness = PowersOfRandomness::new(randomness)
+            .take(POW_RAND_SIZE)
+            .into_iter()
+            .map(|power| vec![power; (1 << K) - 64])
+            .collect();
+        crate::test_util::run_real_prover(K, &circuit, power_of_randomness).unwrap();
+    }
+
+    #[test]
+    fn sha256_circuit_padding() {
+        let lookups = vec![sha256_table_assignments(&[], Fr::from(0x10000))];
+        assert_eq!(verify(vec![], lookups), Ok(()));
+    }
+
+    #[test]
+    fn sha256_circuit_invalid_digest() {
+        let mut lookup = sha256_table_assignments(b"abc", Fr::from(0x10000));
+        lookup[2] += Fr::from(1);
+        assert!(verify(inputs(), vec![lookup]).is_err());
+    }
+
+    #[test]
+    fn sha256_circuit_invalid_len() {
+        // The bytes of an input followed by its padding are not the input
+        let mut padded_input = b"abc".to_vec();
+        padded_input.push(0x80);
+        let lookup = sha256_table_assignments(&padded_input, Fr::from(0x10000));
+        assert!(verify(inputs(), vec![lookup]).is_err());
+    }
+
+    #[test]
+    fn sha256_circuit_capacity() {
+        let circuit = Sha256Circuit::<Fr> {
+            inputs: inputs(),
+            max_blocks: 3,
+            ..Default::default()
+        };
+        assert_eq!(circuit.check_capacity(), Ok(()));
+        assert_eq!(
+            Sha256Circuit {
+                max_blocks: 2,
+                ..circuit
+            }
+            .check_capacity(),
+            Err(Sha256CircuitError::TooManyBlocks {
+                n_blocks: 3,
+                max_blocks: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,72 @@
+#![allow(missing_docs)]
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Region,
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::evm_circuit::table::LookupTable;
+
+/// The SHA-256 table exported by the SHA-256 circuit, and looked up by the
+/// circuits which hash their witnesses with SHA-256.  Each hashed input has a
+/// row `(input_rlc, input_len, digest_rlc)`, as built by
+/// `sha256_table_assignments`, all the other rows are 0.
+#[derive(Clone, Copy, Debug)]
+pub struct Sha256Table {
+    pub input_rlc: Column<Advice>,
+    pub input_len: Column<Advice>,
+    pub digest_rlc: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F, 3> for Sha256Table {
+    fn columns(&self) -> Vec<Column<Any>> {
+        [self.input_rlc, self.input_len, self.digest_rlc]
+            .map(Into::into)
+            .to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        ["input_rlc", "input_len", "digest_rlc"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 3] {
+        [
+            meta.query_advice(self.input_rlc, Rotation::cur()),
+            meta.query_advice(self.input_len, Rotation::cur()),
+            meta.query_advice(self.digest_rlc, Rotation::cur()),
+        ]
+    }
+}
+
+impl Sha256Table {
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            input_rlc: meta.advice_column(),
+            input_len: meta.advice_column(),
+            digest_rlc: meta.advice_column(),
+        }
+    }
+
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 3],
+    ) -> Result<(), Error> {
+        for (column, value) in [self.input_rlc, self.input_len, self.digest_rlc]
+            .iter()
+            .zip(row)
+        {
+            region.assign_advice(
+                || "assign sha256 row on sha256 table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+}
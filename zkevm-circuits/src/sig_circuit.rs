@@ -0,0 +1,1203 @@
+//! The sig circuit implementation.
+//!
+//! It verifies ECDSA signatures over secp256k1 and assigns the sig table of
+//! `(msg_hash_rlc, v, r_rlc, s_rlc, address, is_valid)`, which is looked up by
+//! the circuits that need the signer of a signature instead of verifying it
+//! themselves: the tx circuit for the signatures of the transactions, and the
+//! ecrecover precompile once the EVM circuit supports precompiles.  A
+//! signature is verified once however many times it's looked up, so the
+//! duplicated signatures share a single verification and row.
+//!
+//! A valid signature is verified against the public key `pk` of its signer in
+//! the elliptic curve chip: with the message hash `z` and the signature
+//! `(r, s)`, `(z / s) * G + (r / s) * pk` is the point of x coordinate `r`
+//! modulo n, whose y coordinate has the parity of the recovery id `v`.  The
+//! address of the signer is the last 20 bytes of `keccak(pk.x ‖ pk.y)`, which
+//! is looked up in the keccak table.
+//!
+//! An invalid signature has `is_valid` 0 and address 0, and the circuit proves
+//! why no public key is recovered from it:
+//! - r or s isn't in `[1, n)`, which is checked on their bytes,
+//! - r isn't the x coordinate of a point of the curve, which is proven with a
+//!   square root of `-(r^3 + 7)`, as -1 isn't a square modulo p, or
+//! - the public key `(s * R - z * G) / r` is the point at infinity, which is
+//!   verified as a valid signature without the term `(r / s) * pk`, so that `(z
+//!   / s) * G` is the signature point `R`.
+//!
+//! The elliptic curve chip verifies the padding signature instead of the
+//! signatures of the first two cases, so that every signature has the same
+//! layout.
+//!
+//! The circuit has a fixed capacity of `max_signatures` signatures, so that
+//! its proving key doesn't depend on the block.  The unique signatures are
+//! followed by a padding signature up to the capacity.
+
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    evm_circuit::{
+        table::LookupTable,
+        util::{constraint_builder::BaseConstraintBuilder, RandomLinearCombination},
+        witness::keccak_table_rows,
+    },
+    gadget::{
+        ecc::{Curve, EccConfig, Point},
+        non_native::{invert, to_le_bytes, to_limbs, Modulus, NonNativeContext, N_BYTES, N_LIMBS},
+        range_check::RangeTable,
+    },
+    keccak_table::{dedup_keccak_inputs, KeccakTable},
+    sig_table::SigTable,
+    util::{power_of_randomness_from_instance, Expr},
+};
+use eth_types::{keccak256, Field};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Selector, VirtualCells},
+    poly::Rotation,
+};
+use num::{BigUint, Integer, One, Zero};
+use std::{collections::HashSet, fmt};
+
+/// Number of bytes of a public key, which are its coordinates in big endian.
+pub(crate) const N_PK_BYTES: usize = 2 * N_BYTES;
+// Number of bytes of an address.
+const N_ADDRESS_BYTES: usize = 20;
+// Number of bytes of a limb.
+const N_LIMB_BYTES: usize = N_BYTES / N_LIMBS;
+
+/// Number of powers of the randomness used by the sig circuit, which are the
+/// ones needed by the random linear combination of a public key.
+pub const POW_RAND_SIZE: usize = N_PK_BYTES - 1;
+
+const MAX_DEGREE: usize = 5;
+
+/// Recovers the public key that signed `msg_hash` with the signature `(r, s)`
+/// and the recovery id `recovery_id`, which is the parity of the y coordinate
+/// of the signature point.
+pub(crate) fn recover_pk(
+    msg_hash: &BigUint,
+    (r, s): (&BigUint, &BigUint),
+    recovery_id: u8,
+) -> Option<Point> {
+    if !is_in_range(r) || !is_in_range(s) {
+        return None;
+    }
+    let n = Modulus::Secp256k1Fq.value();
+    let point = Point::from_x(Curve::Secp256k1, r.clone(), recovery_id & 1 == 1)?;
+    let r_inv = invert(r, &n);
+    // pk = r^-1 * (s * R - z * G)
+    let u1 = (&n - (msg_hash * &r_inv) % &n) % &n;
+    let u2 = (s * &r_inv) % &n;
+    match (Curve::Secp256k1.generator().mul(&u1), point.mul(&u2)) {
+        (Some(lhs), Some(rhs)) => lhs.add(&rhs),
+        (lhs, None) => lhs,
+        (None, rhs) => rhs,
+    }
+}
+
+// Returns whether `value` is in `[1, n)`, with `n` the order of secp256k1.
+fn is_in_range(value: &BigUint) -> bool {
+    !value.is_zero() && value < &Modulus::Secp256k1Fq.value()
+}
+
+/// A signature of a message hash, together with the public key of its signer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SignData {
+    pub(crate) msg_hash: BigUint,
+    pub(crate) signature: (BigUint, BigUint),
+    /// Parity of the y coordinate of the signature point
+    pub(crate) recovery_id: u8,
+    /// Public key recovered from the signature, `None` if the signature is
+    /// invalid
+    pub(crate) pk: Option<Point>,
+}
+
+impl SignData {
+    /// Returns the signature of the message hash 1 with the secret key 1 and
+    /// the nonce 1, whose signature point and public key are the generator.
+    pub(crate) fn padding() -> Self {
+        let n = Modulus::Secp256k1Fq.value();
+        let generator = Curve::Secp256k1.generator();
+        let msg_hash = BigUint::one();
+        // s = (z + r * sk) / k = z + r
+        let r = &generator.x % &n;
+        let s = (&msg_hash + &r) % &n;
+        Self {
+            msg_hash,
+            signature: (r, s),
+            recovery_id: generator.y.is_odd() as u8,
+            pk: Some(generator),
+        }
+    }
+
+    /// Returns whether r is the x coordinate of a point of the curve.
+    fn is_x_on_curve(&self) -> bool {
+        Point::from_x(Curve::Secp256k1, self.signature.0.clone(), false).is_some()
+    }
+
+    // Returns whether the elliptic curve chip verifies the signature itself
+    // instead of the padding signature, which is when r and s are in range
+    // and r is the x coordinate of a point of the curve.
+    fn is_verified(&self) -> bool {
+        let (r, s) = &self.signature;
+        is_in_range(r) && is_in_range(s) && self.is_x_on_curve()
+    }
+
+    // Returns the signature verified by the elliptic curve chip, with the
+    // generator as public key when it's the point at infinity.
+    fn verified(&self) -> Self {
+        if self.is_verified() {
+            Self {
+                pk: Some(self.verified_pk()),
+                ..self.clone()
+            }
+        } else {
+            Self::padding()
+        }
+    }
+
+    // Returns the public key verified by the elliptic curve chip, which is
+    // the generator for the invalid signatures.
+    fn verified_pk(&self) -> Point {
+        self.pk
+            .clone()
+            .unwrap_or_else(|| Curve::Secp256k1.generator())
+    }
+
+    /// Returns the big endian bytes of the coordinates of the verified public
+    /// key.
+    pub(crate) fn pk_bytes(&self) -> [u8; N_PK_BYTES] {
+        let pk = self.verified_pk();
+        let mut bytes = [0; N_PK_BYTES];
+        for (chunk, coordinate) in bytes.chunks_mut(N_BYTES).zip([&pk.x, &pk.y]) {
+            let coordinate = coordinate.to_bytes_be();
+            chunk[N_BYTES - coordinate.len()..].copy_from_slice(&coordinate);
+        }
+        bytes
+    }
+
+    /// Returns the little endian bytes of the keccak hash of the public key.
+    fn pk_hash_le_bytes(&self) -> [u8; N_BYTES] {
+        let mut bytes = [0; N_BYTES];
+        bytes.copy_from_slice(&keccak256(&self.pk_bytes()));
+        bytes.reverse();
+        bytes
+    }
+
+    // Returns the message hash, the signature and the recovery id, which
+    // identify a signature of the sig table.
+    fn key(&self) -> (&BigUint, &BigUint, &BigUint, u8) {
+        (
+            &self.msg_hash,
+            &self.signature.0,
+            &self.signature.1,
+            self.recovery_id,
+        )
+    }
+
+    /// Returns the row of the signature in the sig table, whose address is 0
+    /// if the signature is invalid.
+    pub(crate) fn table_assignment<F: Field>(&self, randomness: F) -> [F; 6] {
+        let rlc = |value: &BigUint| {
+            RandomLinearCombination::random_linear_combine(to_le_bytes(value), randomness)
+        };
+        let is_valid = self.pk.is_some();
+        let address = if is_valid {
+            self.pk_hash_le_bytes()[..N_ADDRESS_BYTES]
+                .iter()
+                .rev()
+                .fold(F::zero(), |acc, byte| {
+                    acc * F::from(256) + F::from(*byte as u64)
+                })
+        } else {
+            F::zero()
+        };
+        [
+            rlc(&self.msg_hash),
+            F::from(self.recovery_id as u64),
+            rlc(&self.signature.0),
+            rlc(&self.signature.1),
+            address,
+            F::from(is_valid as u64),
+        ]
+    }
+}
+
+// Columns that check whether an integer of 32 bytes is in `[1, n)`, with `n`
+// the order of secp256k1.
+#[derive(Clone, Copy, Debug)]
+struct RangeCheckColumns {
+    // Inverse of the sum of the limbs of the integer, 0 if it's 0
+    limbs_sum_inv: Column<Advice>,
+    // Whether the integer is less than n
+    is_lt: Column<Advice>,
+    // Little endian bytes of `n - 1 - value` if the integer is less than n,
+    // of `value - n` otherwise
+    diff_bytes: [Column<Advice>; N_BYTES],
+    // Borrows of the subtraction of the limbs of the difference
+    borrows: [Column<Advice>; N_LIMBS - 1],
+    // Whether the integer is in `[1, n)`
+    in_range: Column<Advice>,
+}
+
+impl Annotate for RangeCheckColumns {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate(owner, "limbs_sum_inv", self.limbs_sum_inv);
+        annotations.annotate(owner, "is_lt", self.is_lt);
+        annotations.annotate_all(owner, "diff_bytes", self.diff_bytes);
+        annotations.annotate_all(owner, "borrows", self.borrows);
+        annotations.annotate(owner, "in_range", self.in_range);
+    }
+}
+
+impl RangeCheckColumns {
+    fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            limbs_sum_inv: meta.advice_column(),
+            is_lt: meta.advice_column(),
+            diff_bytes: [(); N_BYTES].map(|_| meta.advice_column()),
+            borrows: [(); N_LIMBS - 1].map(|_| meta.advice_column()),
+            in_range: meta.advice_column(),
+        }
+    }
+
+    // Constrains `in_range` for the integer of the little endian `bytes`.
+    fn constrain<F: Field>(
+        &self,
+        meta: &mut VirtualCells<F>,
+        cb: &mut BaseConstraintBuilder<F>,
+        bytes: &[Column<Advice>; N_BYTES],
+    ) {
+        let two_pow_limb = Expression::Constant(F::from_u128(1 << (8 * N_LIMB_BYTES)));
+        let [limbs_sum_inv, is_lt, in_range] = [self.limbs_sum_inv, self.is_lt, self.in_range]
+            .map(|column| meta.query_advice(column, Rotation::cur()));
+        let limbs = bytes
+            .chunks(N_LIMB_BYTES)
+            .map(|bytes| from_le_bytes(meta, bytes))
+            .collect::<Vec<_>>();
+        let diff_limbs = self
+            .diff_bytes
+            .chunks(N_LIMB_BYTES)
+            .map(|bytes| from_le_bytes(meta, bytes))
+            .collect::<Vec<_>>();
+
+        let limbs_sum = limbs.iter().fold(0.expr(), |acc, limb| acc + limb.clone());
+        let is_zero = 1.expr() - limbs_sum.clone() * limbs_sum_inv;
+        cb.require_zero(
+            "integer is 0 or limbs_sum_inv is the inverse of the sum of its limbs",
+            limbs_sum * is_zero.clone(),
+        );
+
+        // The difference is n - 1 - value or value - n, whose subtraction of
+        // the limbs has no borrow out of the highest limb.
+        let n = Modulus::Secp256k1Fq.value();
+        let [n_limbs, n_minus_one_limbs] = [&n, &(&n - 1u64)]
+            .map(|value| to_limbs(value).map(|limb| Expression::Constant(F::from(limb))));
+        cb.require_boolean("is_lt is boolean", is_lt.clone());
+        let mut borrow_prev = 0.expr();
+        for (idx, (limb, diff_limb)) in limbs.iter().zip(diff_limbs).enumerate() {
+            let borrow = match self.borrows.get(idx) {
+                Some(borrow) => {
+                    let borrow = meta.query_advice(*borrow, Rotation::cur());
+                    cb.require_boolean("borrow is boolean", borrow.clone());
+                    borrow
+                }
+                None => 0.expr(),
+            };
+            let lhs = is_lt.clone() * (n_minus_one_limbs[idx].clone() - limb.clone())
+                + (1.expr() - is_lt.clone()) * (limb.clone() - n_limbs[idx].clone());
+            cb.require_equal(
+                "difference limb is lhs - rhs - borrow_prev + borrow * 2^64",
+                diff_limb,
+                lhs - borrow_prev + borrow.clone() * two_pow_limb.clone(),
+            );
+            borrow_prev = borrow;
+        }
+
+        cb.require_equal(
+            "in_range is whether the integer isn't 0 and is less than n",
+            in_range,
+            (1.expr() - is_zero) * is_lt,
+        );
+    }
+
+    fn assign<F: Field>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: &BigUint,
+    ) -> Result<(), Error> {
+        let n = Modulus::Secp256k1Fq.value();
+        let is_lt = value < &n;
+        let (lhs, rhs) = if is_lt {
+            (&n - 1u64, value.clone())
+        } else {
+            (value.clone(), n)
+        };
+        let diff_bytes = to_le_bytes::<N_BYTES>(&(&lhs - &rhs));
+        let [lhs, rhs] = [&lhs, &rhs].map(to_limbs);
+        let mut borrows = [0; N_LIMBS - 1];
+        let mut borrow_prev = 0;
+        for (idx, borrow) in borrows.iter_mut().enumerate() {
+            *borrow = ((lhs[idx] as i128) - (rhs[idx] as i128) - borrow_prev < 0) as i128;
+            borrow_prev = *borrow;
+        }
+        let limbs_sum = to_limbs(value)
+            .iter()
+            .fold(F::zero(), |acc, limb| acc + F::from(*limb));
+
+        for (column, byte) in self.diff_bytes.iter().zip(diff_bytes) {
+            region.assign_advice(
+                || "difference byte",
+                *column,
+                offset,
+                || Ok(F::from(byte as u64)),
+            )?;
+        }
+        for (column, borrow) in self.borrows.iter().zip(borrows) {
+            region.assign_advice(|| "borrow", *column, offset, || Ok(F::from(borrow as u64)))?;
+        }
+        for (name, column, value) in [
+            (
+                "limbs_sum_inv",
+                self.limbs_sum_inv,
+                limbs_sum.invert().unwrap_or(F::zero()),
+            ),
+            ("is_lt", self.is_lt, F::from(is_lt as u64)),
+            (
+                "in_range",
+                self.in_range,
+                F::from(is_in_range(value) as u64),
+            ),
+        ] {
+            region.assign_advice(|| name, column, offset, || Ok(value))?;
+        }
+        Ok(())
+    }
+}
+
+// Returns the value of the little endian `bytes`.
+fn from_le_bytes<F: Field>(meta: &mut VirtualCells<F>, bytes: &[Column<Advice>]) -> Expression<F> {
+    bytes.iter().rev().fold(0.expr(), |acc, byte| {
+        acc * 256.expr() + meta.query_advice(*byte, Rotation::cur())
+    })
+}
+
+// Cells of the ECDSA verification of a signature, which are copied to its row.
+struct AssignedEcdsa<F: Field> {
+    // Limbs of the coordinates of the public key, of the message hash, of r
+    // and of s of the verified signature
+    limbs: [[AssignedCell<F, F>; N_LIMBS]; 5],
+    // Lowest limb of the y coordinate of the signature point
+    point_y_limb: AssignedCell<F, F>,
+    // Whether the public key is verified, 0 when it's the point at infinity
+    has_pk: AssignedCell<F, F>,
+    // Limbs of r of the signature, which is checked to be the x coordinate of
+    // a point of the curve or not
+    x_limbs: [AssignedCell<F, F>; N_LIMBS],
+    // Whether r is the x coordinate of a point of the curve
+    is_x_on_curve: AssignedCell<F, F>,
+}
+
+/// Config of the sig circuit.
+#[derive(Clone, Debug)]
+pub struct SigCircuitConfig<F> {
+    ecc: EccConfig<F>,
+    // Enables the rows of the signatures in the sig table
+    q_enable: Selector,
+    sig_table: SigTable,
+    // Limbs of the message hash, of r and of s, copied from the ECDSA
+    // verification
+    msg_hash_limbs: [Column<Advice>; N_LIMBS],
+    r_limbs: [Column<Advice>; N_LIMBS],
+    s_limbs: [Column<Advice>; N_LIMBS],
+    // Limbs of the coordinates of the public key, copied from the ECDSA
+    // verification
+    pk_limbs: [[Column<Advice>; N_LIMBS]; 2],
+    // Lowest limb of the y coordinate of the signature point, copied from the
+    // ECDSA verification
+    point_y_limb: Column<Advice>,
+    // Little endian bytes of the message hash, of r and of s
+    msg_hash_bytes: [Column<Advice>; N_BYTES],
+    r_bytes: [Column<Advice>; N_BYTES],
+    s_bytes: [Column<Advice>; N_BYTES],
+    // Big endian bytes of the coordinates of the public key
+    pk_bytes: [Column<Advice>; N_PK_BYTES],
+    // Little endian bytes of the keccak hash of the public key
+    pk_hash_bytes: [Column<Advice>; N_BYTES],
+    // Little endian bytes of the lowest limb of the y coordinate of the
+    // signature point, whose first byte is `v + 2 * point_y_half`
+    point_y_bytes: [Column<Advice>; N_LIMB_BYTES],
+    point_y_half: Column<Advice>,
+    // Whether the public key is verified, copied from the ECDSA verification
+    has_pk: Column<Advice>,
+    // Limbs of r, and whether it's the x coordinate of a point of the curve,
+    // copied from the ECDSA verification
+    x_limbs: [Column<Advice>; N_LIMBS],
+    is_x_on_curve: Column<Advice>,
+    // Checks of r and s being in `[1, n)`
+    r_range_check: RangeCheckColumns,
+    s_range_check: RangeCheckColumns,
+    // Whether the elliptic curve chip verifies the signature instead of the
+    // padding signature
+    is_verified: Column<Advice>,
+    u8_table: RangeTable<8>,
+}
+
+impl<F> Annotate for SigCircuitConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_config(owner, "sig_table", &self.sig_table);
+        annotations.annotate_config(owner, "ecc", &self.ecc);
+        annotations.annotate_all(owner, "msg_hash_limbs", self.msg_hash_limbs);
+        annotations.annotate_all(owner, "r_limbs", self.r_limbs);
+        annotations.annotate_all(owner, "s_limbs", self.s_limbs);
+        for (idx, pk_limbs) in self.pk_limbs.iter().enumerate() {
+            annotations.annotate_all(owner, &format!("pk_limbs_{}", idx), *pk_limbs);
+        }
+        annotations.annotate(owner, "point_y_limb", self.point_y_limb);
+        annotations.annotate_all(owner, "msg_hash_bytes", self.msg_hash_bytes);
+        annotations.annotate_all(owner, "r_bytes", self.r_bytes);
+        annotations.annotate_all(owner, "s_bytes", self.s_bytes);
+        annotations.annotate_all(owner, "pk_bytes", self.pk_bytes);
+        annotations.annotate_all(owner, "pk_hash_bytes", self.pk_hash_bytes);
+        annotations.annotate_all(owner, "point_y_bytes", self.point_y_bytes);
+        annotations.annotate(owner, "point_y_half", self.point_y_half);
+        annotations.annotate(owner, "has_pk", self.has_pk);
+        annotations.annotate_all(owner, "x_limbs", self.x_limbs);
+        annotations.annotate(owner, "is_x_on_curve", self.is_x_on_curve);
+        annotations.annotate_config(owner, "r_range_check", &self.r_range_check);
+        annotations.annotate_config(owner, "s_range_check", &self.s_range_check);
+        annotations.annotate(owner, "is_verified", self.is_verified);
+        annotations.annotate_config(owner, "u8_table", &self.u8_table);
+    }
+}
+
+impl<F: Field> SigCircuitConfig<F> {
+    /// Configure the sig circuit, which assigns the rows of `sig_table`, and
+    /// looks up the hashes of the public keys in `keccak_table`.
+    /// `power_of_randomness` holds `r^1, .., r^63` of the randomness `r` of
+    /// the tables.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Expression<F>; POW_RAND_SIZE],
+        sig_table: SigTable,
+        keccak_table: KeccakTable,
+    ) -> Self {
+        let u8_table = RangeTable::construct(meta);
+        let ecc = EccConfig::configure(meta, u8_table);
+
+        let q_enable = meta.complex_selector();
+        let [msg_hash_limbs, r_limbs, s_limbs] =
+            [(); 3].map(|_| [(); N_LIMBS].map(|_| meta.advice_column()));
+        let pk_limbs = [(); 2].map(|_| [(); N_LIMBS].map(|_| meta.advice_column()));
+        let point_y_limb = meta.advice_column();
+        let [msg_hash_bytes, r_bytes, s_bytes, pk_hash_bytes] =
+            [(); 4].map(|_| [(); N_BYTES].map(|_| meta.advice_column()));
+        let pk_bytes = [(); N_PK_BYTES].map(|_| meta.advice_column());
+        let point_y_bytes = [(); N_LIMB_BYTES].map(|_| meta.advice_column());
+        let point_y_half = meta.advice_column();
+        let [has_pk, is_x_on_curve, is_verified] = [(); 3].map(|_| meta.advice_column());
+        let x_limbs = [(); N_LIMBS].map(|_| meta.advice_column());
+        let [r_range_check, s_range_check] = [(); 2].map(|_| RangeCheckColumns::construct(meta));
+
+        for column in pk_limbs
+            .iter()
+            .flatten()
+            .chain(msg_hash_limbs.iter())
+            .chain(r_limbs.iter())
+            .chain(s_limbs.iter())
+            .chain(x_limbs.iter())
+            .chain([point_y_limb, has_pk, is_x_on_curve].iter())
+        {
+            meta.enable_equality(*column);
+        }
+
+        let padding = SignData::padding();
+        let rlc = |meta: &mut VirtualCells<F>, bytes: &[Column<Advice>; N_BYTES]| {
+            RandomLinearCombination::random_linear_combine_expr(
+                bytes.map(|byte| meta.query_advice(byte, Rotation::cur())),
+                &power_of_randomness,
+            )
+        };
+
+        meta.create_gate("Signature in sig table", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+
+            for (limbs, bytes) in pk_limbs.iter().zip(pk_bytes.chunks(N_BYTES)) {
+                let le_bytes = bytes.iter().rev().copied().collect::<Vec<_>>();
+                for (limb, bytes) in limbs.iter().zip(le_bytes.chunks(N_LIMB_BYTES)) {
+                    cb.require_equal(
+                        "public key limb is decomposed into bytes",
+                        meta.query_advice(*limb, Rotation::cur()),
+                        from_le_bytes(meta, bytes),
+                    );
+                }
+            }
+            let [has_pk, is_x_on_curve, is_verified] = [has_pk, is_x_on_curve, is_verified]
+                .map(|column| meta.query_advice(column, Rotation::cur()));
+            r_range_check.constrain(meta, &mut cb, &r_bytes);
+            s_range_check.constrain(meta, &mut cb, &s_bytes);
+            cb.require_boolean("is_x_on_curve is boolean", is_x_on_curve.clone());
+            cb.require_equal(
+                "the signature is verified when r and s are in range and r is on the curve",
+                is_verified.clone(),
+                meta.query_advice(r_range_check.in_range, Rotation::cur())
+                    * meta.query_advice(s_range_check.in_range, Rotation::cur())
+                    * is_x_on_curve,
+            );
+            for (limb, bytes) in x_limbs.iter().zip(r_bytes.chunks(N_LIMB_BYTES)) {
+                cb.require_equal(
+                    "x limb is decomposed into the bytes of r",
+                    meta.query_advice(*limb, Rotation::cur()),
+                    from_le_bytes(meta, bytes),
+                );
+            }
+
+            // The padding signature is verified instead of the signature if
+            // it isn't verified.
+            let (padding_r, padding_s) = &padding.signature;
+            for (limbs, bytes, padding) in [
+                (msg_hash_limbs, msg_hash_bytes, &padding.msg_hash),
+                (r_limbs, r_bytes, padding_r),
+                (s_limbs, s_bytes, padding_s),
+            ] {
+                for ((limb, bytes), padding_limb) in limbs
+                    .iter()
+                    .zip(bytes.chunks(N_LIMB_BYTES))
+                    .zip(to_limbs(padding))
+                {
+                    cb.require_equal(
+                        "verified signature limb is decomposed into bytes, or is the padding one",
+                        meta.query_advice(*limb, Rotation::cur()),
+                        is_verified.clone() * from_le_bytes(meta, bytes)
+                            + (1.expr() - is_verified.clone()) * padding_limb.expr(),
+                    );
+                }
+            }
+            cb.require_equal(
+                "signature point limb is decomposed into bytes",
+                meta.query_advice(point_y_limb, Rotation::cur()),
+                from_le_bytes(meta, &point_y_bytes),
+            );
+
+            let [msg_hash_rlc, v, r_rlc, s_rlc, address, is_valid] = sig_table.table_exprs(meta);
+            cb.require_boolean("v is a bit", v.clone());
+            cb.require_equal(
+                "v is the parity of the y coordinate of the signature point, or the padding one",
+                meta.query_advice(point_y_bytes[0], Rotation::cur()),
+                is_verified.clone() * v
+                    + (1.expr() - is_verified.clone()) * padding.recovery_id.expr()
+                    + 2.expr() * meta.query_advice(point_y_half, Rotation::cur()),
+            );
+            cb.require_boolean("is_valid is boolean", is_valid.clone());
+            cb.require_zero(
+                "the signature is invalid if it isn't verified",
+                is_valid.clone() * (1.expr() - is_verified.clone()),
+            );
+            cb.require_equal(
+                "the public key is verified unless the signature is verified and invalid",
+                has_pk,
+                1.expr() - is_verified + is_valid.clone(),
+            );
+            cb.require_equal(
+                "address is the last 20 bytes of the public key hash, or 0 if invalid",
+                address,
+                is_valid * from_le_bytes(meta, &pk_hash_bytes[..N_ADDRESS_BYTES]),
+            );
+            for (name, value, bytes) in [
+                (
+                    "msg_hash_rlc is the RLC of the message hash",
+                    msg_hash_rlc,
+                    &msg_hash_bytes,
+                ),
+                ("r_rlc is the RLC of r", r_rlc, &r_bytes),
+                ("s_rlc is the RLC of s", s_rlc, &s_bytes),
+            ] {
+                cb.require_equal(name, value, rlc(meta, bytes));
+            }
+
+            cb.gate(meta.query_selector(q_enable))
+        });
+
+        for byte in pk_bytes
+            .iter()
+            .chain(pk_hash_bytes.iter())
+            .chain(msg_hash_bytes.iter())
+            .chain(r_bytes.iter())
+            .chain(s_bytes.iter())
+            .chain(point_y_bytes.iter())
+            .chain(std::iter::once(&point_y_half))
+            .chain(r_range_check.diff_bytes.iter())
+            .chain(s_range_check.diff_bytes.iter())
+        {
+            meta.lookup_any("signature bytes are bytes", |meta| {
+                let q_enable = meta.query_selector(q_enable);
+                let byte = meta.query_advice(*byte, Rotation::cur());
+                let [u8_table] = u8_table.table_exprs(meta);
+
+                vec![(q_enable * byte, u8_table)]
+            });
+        }
+
+        meta.lookup_any("Public key hash in keccak table", |meta| {
+            let q_enable = meta.query_selector(q_enable);
+            let pk_rlc = pk_bytes
+                .iter()
+                .enumerate()
+                .fold(0.expr(), |acc, (idx, byte)| {
+                    let byte = meta.query_advice(*byte, Rotation::cur());
+                    acc + if idx == N_PK_BYTES - 1 {
+                        byte
+                    } else {
+                        byte * power_of_randomness[N_PK_BYTES - 2 - idx].clone()
+                    }
+                });
+            let pk_hash_rlc = rlc(meta, &pk_hash_bytes);
+
+            vec![pk_rlc, (N_PK_BYTES as u64).expr(), pk_hash_rlc]
+                .into_iter()
+                .zip(keccak_table.table_exprs(meta))
+                .map(|(value, table)| (q_enable.clone() * value, table))
+                .collect()
+        });
+
+        Self {
+            ecc,
+            q_enable,
+            sig_table,
+            msg_hash_limbs,
+            r_limbs,
+            s_limbs,
+            pk_limbs,
+            point_y_limb,
+            msg_hash_bytes,
+            r_bytes,
+            s_bytes,
+            pk_bytes,
+            pk_hash_bytes,
+            point_y_bytes,
+            point_y_half,
+            has_pk,
+            x_limbs,
+            is_x_on_curve,
+            r_range_check,
+            s_range_check,
+            is_verified,
+            u8_table,
+        }
+    }
+
+    pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.u8_table.load(layouter)
+    }
+
+    /// Verifies the signatures, and assigns the sig table with their rows
+    /// after a row of zeros.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        sign_datas: &[SignData],
+        randomness: F,
+    ) -> Result<(), Error> {
+        let mut assigned_ecdsas = Vec::with_capacity(sign_datas.len());
+        for sign_data in sign_datas {
+            assigned_ecdsas.push(layouter.assign_region(
+                || "ECDSA verification",
+                |mut region| {
+                    let mut ctx = NonNativeContext::new(&mut region);
+                    self.assign_ecdsa(&mut ctx, sign_data)
+                },
+            )?);
+        }
+
+        layouter.assign_region(
+            || "sig table",
+            |mut region| {
+                self.sig_table.assign(&mut region, 0, [F::zero(); 6])?;
+                for (idx, (sign_data, ecdsa)) in
+                    sign_datas.iter().zip(assigned_ecdsas.iter()).enumerate()
+                {
+                    let offset = idx + 1;
+                    self.q_enable.enable(&mut region, offset)?;
+                    self.sig_table.assign(
+                        &mut region,
+                        offset,
+                        sign_data.table_assignment(randomness),
+                    )?;
+
+                    let columns = [
+                        self.pk_limbs[0],
+                        self.pk_limbs[1],
+                        self.msg_hash_limbs,
+                        self.r_limbs,
+                        self.s_limbs,
+                    ];
+                    for (columns, limbs) in columns
+                        .iter()
+                        .chain(std::iter::once(&self.x_limbs))
+                        .zip(ecdsa.limbs.iter().chain(std::iter::once(&ecdsa.x_limbs)))
+                    {
+                        for (column, limb) in columns.iter().zip(limbs.iter()) {
+                            limb.copy_advice(|| "signature limb", &mut region, *column, offset)?;
+                        }
+                    }
+                    for (column, cell) in [
+                        (self.has_pk, &ecdsa.has_pk),
+                        (self.is_x_on_curve, &ecdsa.is_x_on_curve),
+                    ] {
+                        cell.copy_advice(|| "signature flag", &mut region, column, offset)?;
+                    }
+                    let point_y_limb = ecdsa.point_y_limb.copy_advice(
+                        || "signature point limb",
+                        &mut region,
+                        self.point_y_limb,
+                        offset,
+                    )?;
+                    region.assign_advice(
+                        || "is_verified",
+                        self.is_verified,
+                        offset,
+                        || Ok(F::from(sign_data.is_verified() as u64)),
+                    )?;
+                    let (r, s) = &sign_data.signature;
+                    self.r_range_check.assign(&mut region, offset, r)?;
+                    self.s_range_check.assign(&mut region, offset, s)?;
+
+                    let mut point_y_bytes = [0; N_LIMB_BYTES];
+                    if let Some(limb) = point_y_limb.value() {
+                        point_y_bytes.copy_from_slice(&limb.to_repr()[..N_LIMB_BYTES]);
+                    }
+                    let [msg_hash_bytes, r_bytes, s_bytes] =
+                        [&sign_data.msg_hash, r, s].map(to_le_bytes::<N_BYTES>);
+                    for (column, byte) in self
+                        .pk_bytes
+                        .iter()
+                        .zip(sign_data.pk_bytes())
+                        .chain(self.pk_hash_bytes.iter().zip(sign_data.pk_hash_le_bytes()))
+                        .chain(self.msg_hash_bytes.iter().zip(msg_hash_bytes))
+                        .chain(self.r_bytes.iter().zip(r_bytes))
+                        .chain(self.s_bytes.iter().zip(s_bytes))
+                        .chain(self.point_y_bytes.iter().zip(point_y_bytes))
+                        .chain(std::iter::once((&self.point_y_half, point_y_bytes[0] >> 1)))
+                    {
+                        region.assign_advice(
+                            || "signature byte",
+                            *column,
+                            offset,
+                            || Ok(F::from(byte as u64)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // Verifies the signature `SignData::verified`, and checks whether r of
+    // the signature is the x coordinate of a point of the curve.
+    //
+    // With the message hash `z` and the signature `(r, s)`, it checks that
+    // `(z / s) * G + has_pk * (r / s) * pk` has x coordinate `r` modulo n,
+    // where `has_pk` is 0 only to prove that the public key is the point at
+    // infinity.
+    fn assign_ecdsa(
+        &self,
+        ctx: &mut NonNativeContext<'_, '_, F>,
+        sign_data: &SignData,
+    ) -> Result<AssignedEcdsa<F>, Error> {
+        let integer = &self.ecc.integer;
+        let n = Curve::Secp256k1.scalar_field();
+        let p = Curve::Secp256k1.base_field();
+        let verified = sign_data.verified();
+        let one = integer.constant(ctx, BigUint::one())?;
+        let zero = integer.constant(ctx, BigUint::zero())?;
+
+        // A boolean integer, whose limb 0 is constrained to be a bit in the
+        // row of the signature.
+        let assign_bit = |ctx: &mut NonNativeContext<'_, '_, F>, bit: bool| {
+            let integer_bit = integer.assign_witness(ctx, BigUint::from(bit as u64))?;
+            for (limb, zero_limb) in integer_bit.limbs.iter().zip(zero.limbs.iter()).skip(1) {
+                ctx.region.constrain_equal(limb.cell(), zero_limb.cell())?;
+            }
+            Ok::<_, Error>(integer_bit)
+        };
+        let has_pk = assign_bit(ctx, sign_data.pk.is_some() || !sign_data.is_verified())?;
+        let is_x_on_curve = sign_data.is_x_on_curve();
+        let x_on_curve = assign_bit(ctx, is_x_on_curve)?;
+
+        let pk = self.ecc.assign_point(ctx, &verified.verified_pk())?;
+        let msg_hash = integer.assign_witness(ctx, verified.msg_hash.clone())?;
+        let r = integer.assign_witness(ctx, verified.signature.0.clone())?;
+        let s = integer.assign_witness(ctx, verified.signature.1.clone())?;
+
+        let s_inv = integer.invert(ctx, n, &s)?;
+        let u1 = integer.mul(ctx, n, &msg_hash, &s_inv)?;
+        let u2 = integer.mul(ctx, n, &r, &s_inv)?;
+        let u2 = integer.mul(ctx, n, &u2, &has_pk)?;
+        let point = self.ecc.mul_add_generator(ctx, &u1, &u2, &pk)?;
+
+        // The x coordinate of the point is reduced modulo n.  It's possibly
+        // not canonical modulo p only when it's less than 2^256 - p, which is
+        // infeasible to reach, and so is a non canonical y coordinate, whose
+        // parity would differ.
+        let x = integer.mul_add(ctx, n, &one, &point.x, &zero)?;
+        integer.assert_equal(ctx, &x, &r)?;
+
+        // r is the x coordinate of a point of the curve iff r^3 + 7 is a
+        // square modulo p, and it isn't iff -(r^3 + 7) is one, as -1 isn't a
+        // square modulo p = 3 mod 4.  r^3 + 7 isn't 0, as the curve has no
+        // point of order 2.
+        let x_value = sign_data.signature.0.clone();
+        let p_value = p.value();
+        let y2_value = (&x_value * &x_value * &x_value + 7u64) % &p_value;
+        let target_value = if is_x_on_curve {
+            y2_value
+        } else {
+            (&p_value - y2_value) % &p_value
+        };
+        let sqrt_value = target_value.modpow(&((&p_value + 1u64) / 4u64), &p_value);
+
+        let x_actual = integer.assign_witness(ctx, x_value)?;
+        let seven = integer.constant(ctx, BigUint::from(7u64))?;
+        let two = integer.constant(ctx, BigUint::from(2u64))?;
+        let minus_one = integer.constant(ctx, &p_value - 1u64)?;
+        let x2 = integer.mul(ctx, p, &x_actual, &x_actual)?;
+        let y2 = integer.mul_add(ctx, p, &x2, &x_actual, &seven)?;
+        let sign = integer.mul_add(ctx, p, &two, &x_on_curve, &minus_one)?;
+        let target = integer.mul(ctx, p, &y2, &sign)?;
+        let sqrt = integer.assign_witness(ctx, sqrt_value)?;
+        let sqrt2 = integer.mul(ctx, p, &sqrt, &sqrt)?;
+        integer.assert_equal(ctx, &sqrt2, &target)?;
+
+        let [point_y_limb, ..] = point.y.limbs;
+        let [has_pk, ..] = has_pk.limbs;
+        let [is_x_on_curve, ..] = x_on_curve.limbs;
+        Ok(AssignedEcdsa {
+            limbs: [pk.x.limbs, pk.y.limbs, msg_hash.limbs, r.limbs, s.limbs],
+            point_y_limb,
+            has_pk,
+            x_limbs: x_actual.limbs,
+            is_x_on_curve,
+        })
+    }
+}
+
+/// Error when the signatures don't fit in the sig circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SigCircuitError {
+    /// There are more unique signatures than the circuit can hold.
+    TooManySignatures {
+        /// Number of unique signatures
+        n_signatures: usize,
+        /// Maximum number of signatures of the circuit
+        max_signatures: usize,
+    },
+}
+
+impl fmt::Display for SigCircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManySignatures {
+                n_signatures,
+                max_signatures,
+            } => write!(
+                f,
+                "too many signatures for the sig circuit: {} > max_signatures {}",
+                n_signatures, max_signatures
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SigCircuitError {}
+
+/// Sig circuit that verifies the signatures looked up by the other circuits.
+#[derive(Clone, Default, Debug)]
+pub struct SigCircuit<F> {
+    /// Randomness of the RLC of the tables
+    pub randomness: F,
+    /// Signatures to verify, possibly duplicated
+    pub(crate) sign_datas: Vec<SignData>,
+    /// Maximum number of unique signatures of the circuit, the ones after the
+    /// unique signatures are padding
+    pub max_signatures: usize,
+}
+
+impl<F: Field> SigCircuit<F> {
+    // Returns the signatures without duplicates, in the order they first
+    // appear.
+    fn unique_sign_datas(&self) -> Vec<&SignData> {
+        let mut keys = HashSet::new();
+        self.sign_datas
+            .iter()
+            .filter(|sign_data| keys.insert(sign_data.key()))
+            .collect()
+    }
+
+    // Returns the unique signatures followed by padding ones, up to
+    // `max_signatures`.
+    fn padded_sign_datas(&self) -> Vec<SignData> {
+        let sign_datas = self.unique_sign_datas();
+        let n_padding = self.max_signatures.saturating_sub(sign_datas.len());
+        sign_datas
+            .into_iter()
+            .cloned()
+            .chain(std::iter::repeat(SignData::padding()).take(n_padding))
+            .collect()
+    }
+
+    /// Checks that the unique signatures fit in the capacity of the circuit.
+    pub fn check_capacity(&self) -> Result<(), SigCircuitError> {
+        let n_signatures = self.unique_sign_datas().len();
+        if n_signatures > self.max_signatures {
+            return Err(SigCircuitError::TooManySignatures {
+                n_signatures,
+                max_signatures: self.max_signatures,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the distinct inputs of the keccak hashes looked up by the sig
+    /// circuit, the public keys of the signers, of the padding signatures
+    /// too.
+    pub fn keccak_inputs(&self) -> Vec<Vec<u8>> {
+        dedup_keccak_inputs(
+            self.padded_sign_datas()
+                .iter()
+                .map(|sign_data| sign_data.pk_bytes().to_vec()),
+        )
+    }
+
+    /// Assign the sig circuit, except for the keccak table, which is loaded
+    /// with `SigCircuit::keccak_inputs` by the circuit that owns it.
+    #[tracing::instrument(name = "sig_circuit_assign", skip_all)]
+    pub fn assign(
+        &self,
+        config: &SigCircuitConfig<F>,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        self.check_capacity().map_err(|_| Error::Synthesis)?;
+
+        config.load(layouter)?;
+        config.assign(layouter, &self.padded_sign_datas(), self.randomness)
+    }
+}
+
+impl<F: Field> Circuit<F> for SigCircuit<F> {
+    type Config = (SigCircuitConfig<F>, KeccakTable);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            max_signatures: self.max_signatures,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let sig_table = SigTable::construct(meta);
+        let keccak_table = KeccakTable::construct(meta);
+
+        let power_of_randomness = power_of_randomness_from_instance::<_, POW_RAND_SIZE>(meta);
+
+        (
+            SigCircuitConfig::configure(meta, power_of_randomness, sig_table, keccak_table),
+            keccak_table,
+        )
+    }
+
+    fn synthesize(
+        &self,
+        (config, keccak_table): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let rows = keccak_table_rows(&self.keccak_inputs(), self.randomness);
+        layouter.assign_region(
+            || "keccak table",
+            |mut region| {
+                for (offset, row) in std::iter::once([F::zero(); 3])
+                    .chain(rows.iter().copied())
+                    .enumerate()
+                {
+                    keccak_table.assign(&mut region, offset, row)?;
+                }
+                Ok(())
+            },
+        )?;
+        self.assign(&config, &mut layouter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{recover_pk, SigCircuit, SigCircuitError, SignData};
+    use crate::gadget::{
+        ecc::{Curve, Point},
+        non_native::{invert, Modulus},
+        rlc::PowersOfRandomness,
+    };
+    use halo2_proofs::dev::{MockProver, VerifyFailure};
+    use num::{BigUint, Integer};
+    use pairing::bn256::Fr;
+
+    const K: u32 = 15;
+    const MAX_SIGNATURES: usize = 2;
+
+    // Returns the signature of `msg_hash` with the secret key `sk` and the
+    // nonce `k`.
+    fn sign(msg_hash: u64, sk: u64, k: u64) -> SignData {
+        let n = Modulus::Secp256k1Fq.value();
+        let msg_hash = BigUint::from(msg_hash);
+        let generator = Curve::Secp256k1.generator();
+        let point = generator.mul(&BigUint::from(k)).unwrap();
+        let r = &point.x % &n;
+        let s = (invert(&BigUint::from(k), &n) * (&msg_hash + &r * sk)) % &n;
+        SignData {
+            msg_hash,
+            signature: (r, s),
+            recovery_id: point.y.is_odd() as u8,
+            pk: generator.mul(&BigUint::from(sk)),
+        }
+    }
+
+    fn circuit(sign_datas: Vec<SignData>) -> (SigCircuit<Fr>, Vec<Vec<Fr>>) {
+        let circuit = SigCircuit {
+            randomness: Fr::from(0x10000),
+            sign_datas,
+            max_signatures: MAX_SIGNATURES,
+        };
+        let power_of_randomness = PowersOfRandomness::new(circuit.randomness)
+            .take(63)
+            .into_iter()
+            .map(|power| vec![power; (1 << K) - 64])
+            .collect();
+        (circuit, power_of_randomness)
+    }
+
+    fn verify(sign_datas: Vec<SignData>) -> Result<(), Vec<VerifyFailure>> {
+        let (circuit, power_of_randomness) = circuit(sign_datas);
+        let prover = MockProver::<Fr>::run(K, &circuit, power_of_randomness).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn sign_data_padding() {
+        assert_eq!(SignData::padding(), sign(1, 1, 1));
+    }
+
+    #[test]
+    fn sig_circuit_valid() {
+        // The duplicated signature is verified once
+        let sign_datas = vec![
+            sign(0x1234, 0xcafe, 0x5678),
+            sign(0x4321, 0xcafe, 0x8765),
+            sign(0x1234, 0xcafe, 0x5678),
+        ];
+        assert_eq!(verify(sign_datas), Ok(()));
+    }
+
+    #[cfg(feature = "real-prover")]
+    #[test]
+    fn sig_circuit_valid_real_prover() {
+        let (circuit, power_of_randomness) = circuit(vec![sign(0x1234, 0xcafe, 0x5678)]);
+        crate::test_util::run_real_prover(K, &circuit, power_of_randomness).unwrap();
+    }
+
+    #[test]
+    fn sig_circuit_padding() {
+        assert_eq!(verify(vec![]), Ok(()));
+    }
+
+    #[test]
+    fn sig_circuit_invalid_signature() {
+        let mut sign_data = sign(0x1234, 0xcafe, 0x5678);
+        sign_data.signature.1 += 1u64;
+        assert!(verify(vec![sign_data]).is_err());
+    }
+
+    // Returns the signature with its public key recovered, which is `None`
+    // for an invalid signature.
+    fn with_recovered_pk(mut sign_data: SignData) -> SignData {
+        let (r, s) = &sign_data.signature;
+        sign_data.pk = recover_pk(&sign_data.msg_hash, (r, s), sign_data.recovery_id);
+        sign_data
+    }
+
+    #[test]
+    fn sig_circuit_invalid_out_of_range() {
+        let n = Modulus::Secp256k1Fq.value();
+        let mut zero_s = sign(0x1234, 0xcafe, 0x5678);
+        zero_s.signature.1 = BigUint::from(0u64);
+        let mut large_r = sign(0x1234, 0xcafe, 0x5678);
+        large_r.signature.0 = n;
+        let sign_datas = vec![with_recovered_pk(zero_s), with_recovered_pk(large_r)];
+        assert!(sign_datas.iter().all(|sign_data| sign_data.pk.is_none()));
+        assert_eq!(verify(sign_datas), Ok(()));
+    }
+
+    #[test]
+    fn sig_circuit_invalid_r_not_on_curve() {
+        let r = (1u64..)
+            .map(BigUint::from)
+            .find(|x| Point::from_x(Curve::Secp256k1, x.clone(), false).is_none())
+            .unwrap();
+        let mut sign_data = sign(0x1234, 0xcafe, 0x5678);
+        sign_data.signature.0 = r;
+        let sign_data = with_recovered_pk(sign_data);
+        assert_eq!(sign_data.pk, None);
+        assert_eq!(verify(vec![sign_data]), Ok(()));
+    }
+
+    #[test]
+    fn sig_circuit_invalid_pk_at_infinity() {
+        // s * R = z * G, so the public key (s * R - z * G) / r is the point
+        // at infinity.
+        let n = Modulus::Secp256k1Fq.value();
+        let k = BigUint::from(0x5678u64);
+        let point = Curve::Secp256k1.generator().mul(&k).unwrap();
+        let s = BigUint::from(0x1234u64);
+        let sign_data = with_recovered_pk(SignData {
+            msg_hash: (&s * &k) % &n,
+            signature: (&point.x % &n, s),
+            recovery_id: point.y.is_odd() as u8,
+            pk: None,
+        });
+        assert_eq!(sign_data.pk, None);
+        assert_eq!(verify(vec![sign_data]), Ok(()));
+    }
+
+    #[test]
+    fn sig_circuit_valid_signature_as_invalid() {
+        let mut sign_data = sign(0x1234, 0xcafe, 0x5678);
+        sign_data.pk = None;
+        assert!(verify(vec![sign_data]).is_err());
+    }
+
+    #[test]
+    fn sig_circuit_invalid_recovery_id() {
+        let mut sign_data = sign(0x1234, 0xcafe, 0x5678);
+        sign_data.recovery_id ^= 1;
+        assert!(verify(vec![sign_data]).is_err());
+    }
+
+    #[test]
+    fn sig_circuit_capacity() {
+        let circuit = SigCircuit::<Fr> {
+            sign_datas: vec![sign(1, 0xcafe, 2), sign(2, 0xcafe, 3), sign(1, 0xcafe, 2)],
+            max_signatures: MAX_SIGNATURES,
+            ..Default::default()
+        };
+        assert_eq!(circuit.check_capacity(), Ok(()));
+        assert_eq!(
+            SigCircuit {
+                max_signatures: 1,
+                ..circuit
+            }
+            .check_capacity(),
+            Err(SigCircuitError::TooManySignatures {
+                n_signatures: 2,
+                max_signatures: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,83 @@
+#![allow(missing_docs)]
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Region,
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::evm_circuit::table::LookupTable;
+
+/// The sig table exported by the sig circuit, and looked up by the circuits
+/// that need the signer of a signature.  Each verified signature has a row
+/// `(msg_hash_rlc, v, r_rlc, s_rlc, address, is_valid)`, with the random
+/// linear combinations of the little endian bytes of the message hash and of
+/// the signature, its recovery id `v` and the address of its signer.  The
+/// first row is all zeros, so that disabled lookups always find a row.
+#[derive(Clone, Copy, Debug)]
+pub struct SigTable {
+    pub msg_hash_rlc: Column<Advice>,
+    pub v: Column<Advice>,
+    pub r_rlc: Column<Advice>,
+    pub s_rlc: Column<Advice>,
+    pub address: Column<Advice>,
+    pub is_valid: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F, 6> for SigTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        self.advice_columns().map(Into::into).to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        ["msg_hash_rlc", "v", "r_rlc", "s_rlc", "address", "is_valid"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 6] {
+        self.advice_columns()
+            .map(|column| meta.query_advice(column, Rotation::cur()))
+    }
+}
+
+impl SigTable {
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            msg_hash_rlc: meta.advice_column(),
+            v: meta.advice_column(),
+            r_rlc: meta.advice_column(),
+            s_rlc: meta.advice_column(),
+            address: meta.advice_column(),
+            is_valid: meta.advice_column(),
+        }
+    }
+
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 6],
+    ) -> Result<(), Error> {
+        for (column, value) in self.advice_columns().iter().zip(row) {
+            region.assign_advice(
+                || "assign sig row on sig table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn advice_columns(&self) -> [Column<Advice>; 6] {
+        [
+            self.msg_hash_rlc,
+            self.v,
+            self.r_rlc,
+            self.s_rlc,
+            self.address,
+            self.is_valid,
+        ]
+    }
+}
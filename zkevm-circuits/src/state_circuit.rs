@@ -1,4 +1,5 @@
 //! The state circuit implementation.
 
+mod lexicographic_ordering;
 pub(crate) mod state;
-pub use state::StateCircuit;
+pub use state::{state_roots_instance, StateCircuit, StateCircuitError, DEFAULT_MAX_DEGREE};
@@ -0,0 +1,188 @@
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    evm_circuit::util::constraint_builder::BaseConstraintBuilder,
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::Region,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+// Number of bits needed to encode an index into the limbs.
+const N_INDEX_BITS: usize = 7;
+
+/// Degree of the check that the limbs before the first different one are
+/// equal, which is the minimum degree of the gates of the ordering.
+pub(crate) const MIN_DEGREE: usize = N_INDEX_BITS + 2;
+
+/// Config that constrains the limbs of every enabled row to be strictly greater
+/// than the limbs of the previous row, in lexicographic order.
+///
+/// The index of the first limb that differs between the two rows is witnessed
+/// in binary.  All the limbs before it must be equal, and the difference of
+/// the limbs at it must be in the range `[1, 256)`.  Since every limb is a
+/// byte, the sum of the squared differences of the limbs before it can't
+/// overflow, so it being zero means that all of them are zero.
+#[derive(Clone, Debug)]
+pub(crate) struct LexicographicOrderingConfig<F, const N_LIMBS: usize> {
+    first_different_limb: [Column<Advice>; N_INDEX_BITS],
+    limb_difference: Column<Advice>,
+    _marker: PhantomData<F>,
+}
+
+impl<F, const N_LIMBS: usize> Annotate for LexicographicOrderingConfig<F, N_LIMBS> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_all(owner, "first_different_limb", self.first_different_limb);
+        annotations.annotate(owner, "limb_difference", self.limb_difference);
+    }
+}
+
+impl<F: Field, const N_LIMBS: usize> LexicographicOrderingConfig<F, N_LIMBS> {
+    // The index of the first different limb must fit in its bits.
+    const N_LIMBS_CHECK: () = assert!(
+        N_LIMBS <= 1 << N_INDEX_BITS,
+        "N_LIMBS doesn't fit in the bits of first_different_limb"
+    );
+
+    /// Configure the ordering of `limbs`, which are the most significant first
+    /// and are all required to be bytes.  `u8_table` is a fixed column holding
+    /// all the values in `[0, 256)`, and `max_degree` is at least
+    /// [`MIN_DEGREE`].
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        s_enable: Column<Fixed>,
+        limbs: [Column<Advice>; N_LIMBS],
+        u8_table: Column<Fixed>,
+        max_degree: usize,
+    ) -> Self {
+        let () = Self::N_LIMBS_CHECK;
+
+        let first_different_limb = [(); N_INDEX_BITS].map(|_| meta.advice_column());
+        let limb_difference = meta.advice_column();
+
+        meta.create_gate("Lexicographic ordering", |meta| {
+            let mut cb = BaseConstraintBuilder::new(max_degree);
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+
+            for bit in first_different_limb.iter() {
+                cb.require_boolean(
+                    "first_different_limb bit is boolean",
+                    meta.query_advice(*bit, Rotation::cur()),
+                );
+            }
+
+            let index_is = (0..N_LIMBS)
+                .map(|idx| Self::index_is(meta, first_different_limb, idx))
+                .collect::<Vec<_>>();
+            let differences = limbs
+                .iter()
+                .map(|limb| {
+                    meta.query_advice(*limb, Rotation::cur())
+                        - meta.query_advice(*limb, Rotation::prev())
+                })
+                .collect::<Vec<_>>();
+
+            cb.require_equal(
+                "first_different_limb is in range",
+                index_is
+                    .iter()
+                    .fold(0.expr(), |acc, index_is| acc + index_is.clone()),
+                1.expr(),
+            );
+
+            let mut squared_differences_before = 0.expr();
+            for (index_is, difference) in index_is.iter().zip(differences.iter()) {
+                cb.require_zero(
+                    "limbs before first_different_limb are equal",
+                    index_is.clone() * squared_differences_before.clone(),
+                );
+                squared_differences_before =
+                    squared_differences_before + difference.clone() * difference.clone();
+            }
+
+            cb.require_equal(
+                "limb_difference is the difference of limbs at first_different_limb",
+                meta.query_advice(limb_difference, Rotation::cur()),
+                index_is
+                    .into_iter()
+                    .zip(differences)
+                    .fold(0.expr(), |acc, (index_is, difference)| {
+                        acc + index_is * difference
+                    }),
+            );
+
+            cb.gate(s_enable)
+        });
+
+        meta.lookup_any("limb_difference is positive", |meta| {
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+            let limb_difference = meta.query_advice(limb_difference, Rotation::cur());
+            let u8_table = meta.query_fixed(u8_table, Rotation::cur());
+
+            vec![(s_enable * (limb_difference - 1.expr()), u8_table)]
+        });
+
+        Self {
+            first_different_limb,
+            limb_difference,
+            _marker: PhantomData,
+        }
+    }
+
+    // Returns an expression that is 1 when `first_different_limb` is `idx`,
+    // otherwise 0.
+    fn index_is(
+        meta: &mut VirtualCells<F>,
+        first_different_limb: [Column<Advice>; N_INDEX_BITS],
+        idx: usize,
+    ) -> Expression<F> {
+        first_different_limb
+            .iter()
+            .enumerate()
+            .fold(1.expr(), |acc, (bit_idx, bit)| {
+                let bit = meta.query_advice(*bit, Rotation::cur());
+                if (idx >> bit_idx) & 1 == 1 {
+                    acc * bit
+                } else {
+                    acc * (1.expr() - bit)
+                }
+            })
+    }
+
+    /// Assign the index of the first different limb of `cur` and `prev`.  When
+    /// the limbs are all the same, the last index is assigned, which makes the
+    /// `limb_difference` lookup fail.
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        cur: &[u8; N_LIMBS],
+        prev: &[u8; N_LIMBS],
+    ) -> Result<(), Error> {
+        let index = cur
+            .iter()
+            .zip(prev.iter())
+            .position(|(cur, prev)| cur != prev)
+            .unwrap_or(N_LIMBS - 1);
+
+        for (bit_idx, bit) in self.first_different_limb.iter().enumerate() {
+            region.assign_advice(
+                || format!("first_different_limb bit {}", bit_idx),
+                *bit,
+                offset,
+                || Ok(F::from(((index >> bit_idx) & 1) as u64)),
+            )?;
+        }
+        region.assign_advice(
+            || "limb_difference",
+            self.limb_difference,
+            offset,
+            || Ok(F::from(cur[index] as u64) - F::from(prev[index] as u64)),
+        )?;
+
+        Ok(())
+    }
+}
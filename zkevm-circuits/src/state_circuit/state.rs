@@ -1,30 +1,46 @@
+use super::lexicographic_ordering::{LexicographicOrderingConfig, MIN_DEGREE};
 use crate::{
+    annotation::{Annotate, ColumnAnnotations},
     evm_circuit::{
-        table::RwTableTag,
+        table::{AccountFieldTag, LookupTable, RwTableTag, TxLogFieldTag, TxReceiptFieldTag},
         util::{
             constraint_builder::BaseConstraintBuilder,
-            math_gadget::generate_lagrange_base_polynomial,
+            math_gadget::generate_lagrange_base_polynomial, RandomLinearCombination,
+        },
+        witness::{
+            carried_rw_factor, carried_rws_fingerprint, Chunk, MptUpdates, Rw, RwMap, RwRow,
+            StreamedRws,
         },
-        witness::{RwMap, RwRow},
     },
     gadget::{
-        is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
+        binary_number::{BinaryNumberChip, BinaryNumberConfig},
+        is_equal::{IsEqualChip, IsEqualConfig, IsEqualInstruction},
+        range_check::{RangeCheckChip, RangeCheckConfig, RangeTable},
+        rlc::PowersOfRandomness,
         Variable,
     },
+    mpt_table::{MptProofType, MptTable, MptUpdateRow},
+    rw_table::RwTable,
+    util::{power_of_randomness_from_instance, Expr},
 };
-use eth_types::Field;
+use bus_mapping::operation::OperationContainer;
+use eth_types::{Field, ToBigEndian, ToLittleEndian, Word};
 use halo2_proofs::{
     circuit::{Layouter, Region, SimpleFloorPlanner},
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, VirtualCells,
+    },
     poly::Rotation,
 };
 
+use itertools::Itertools;
 use pairing::arithmetic::FieldExt;
+use std::{convert::TryInto, fmt, io, sync::Arc};
 
 /*
 (FIXME) Example state table:
 
-|            |          |       |    keys(4)  |key2_limbs(8)|   key4_bytes(32)   |                |
+|            |          |       |    keys(4)  |key2_bytes(20)|  key4_bytes(32)   |                |
 | rw_counter | is_write | value | tag  | ...  | ...  | ...  |      |      |      |  storage_key   |
 -------------------------------------------------------------------------------------------------------
 |     0      |     1    |  0    |  1   |      |      |      |      |      |      |                |   // init row (write value 0)
@@ -42,18 +58,79 @@ use pairing::arithmetic::FieldExt;
 */
 
 // tag:
-// 1 - first row of either target (Note: only the first row, not all init rows)
+// 1 - start (padding rows at the beginning of the circuit)
 // 2 - memory
 // 3 - stack
 // 4 - storage
+// 5 - tx access list account
+// 6 - tx access list account storage
+// 7 - tx refund
+// 8 - account
+// 9 - account destructed
+// 10 - call context
+// 11 - tx log
+// 12 - tx receipt
 
 const EMPTY_TAG: usize = 0;
-const START_TAG: usize = 1;
+const START_TAG: usize = RwTableTag::Start as usize;
 const MEMORY_TAG: usize = RwTableTag::Memory as usize;
 const STACK_TAG: usize = RwTableTag::Stack as usize;
 const STORAGE_TAG: usize = RwTableTag::AccountStorage as usize;
-
-const MAX_DEGREE: usize = 15;
+const TX_ACCESS_LIST_ACCOUNT_TAG: usize = RwTableTag::TxAccessListAccount as usize;
+const TX_ACCESS_LIST_ACCOUNT_STORAGE_TAG: usize = RwTableTag::TxAccessListAccountStorage as usize;
+const TX_REFUND_TAG: usize = RwTableTag::TxRefund as usize;
+const ACCOUNT_TAG: usize = RwTableTag::Account as usize;
+const ACCOUNT_DESTRUCTED_TAG: usize = RwTableTag::AccountDestructed as usize;
+const CALL_CONTEXT_TAG: usize = RwTableTag::CallContext as usize;
+const TX_LOG_TAG: usize = RwTableTag::TxLog as usize;
+const TX_RECEIPT_TAG: usize = RwTableTag::TxReceipt as usize;
+const TAGS: [usize; 13] = [
+    EMPTY_TAG,
+    START_TAG,
+    MEMORY_TAG,
+    STACK_TAG,
+    STORAGE_TAG,
+    TX_ACCESS_LIST_ACCOUNT_TAG,
+    TX_ACCESS_LIST_ACCOUNT_STORAGE_TAG,
+    TX_REFUND_TAG,
+    ACCOUNT_TAG,
+    ACCOUNT_DESTRUCTED_TAG,
+    CALL_CONTEXT_TAG,
+    TX_LOG_TAG,
+    TX_RECEIPT_TAG,
+];
+
+// Tags of the rw operations assigned to the state circuit, which are all the
+// ones of the rw table but the Start padding rows
+pub(crate) const RW_TABLE_TAGS: [RwTableTag; 11] = [
+    RwTableTag::Memory,
+    RwTableTag::Stack,
+    RwTableTag::AccountStorage,
+    RwTableTag::TxAccessListAccount,
+    RwTableTag::TxAccessListAccountStorage,
+    RwTableTag::TxRefund,
+    RwTableTag::Account,
+    RwTableTag::AccountDestructed,
+    RwTableTag::CallContext,
+    RwTableTag::TxLog,
+    RwTableTag::TxReceipt,
+];
+
+// Number of rows whose keys are compared with the ones of the rows before them
+// with a single field inversion.
+const KEYS_BATCH_SIZE: usize = 1 << 12;
+
+// A row of the state circuit, with the bytes it's sorted by and the update of
+// the mpt table of its state change, if it's one.
+type StateRow<F> = (RwRow<F>, [u8; N_LIMBS], Option<MptUpdateRow<F>>);
+
+/// Maximum degree of the gates of the state circuits of the tests, benchmarks
+/// and provers, which is a parameter of the circuit.
+pub const DEFAULT_MAX_DEGREE: usize = 15;
+
+/// Number of byte limbs a rw row is sorted by:
+/// tag (1) | key1 (4) | key2 (20) | key3 (4) | key4 (32) | rw_counter (4)
+pub(crate) const N_LIMBS: usize = 65;
 
 /// A mapping derived from witnessed operations.
 #[derive(Clone, Debug)]
@@ -77,29 +154,128 @@ pub struct Config<
     const RW_COUNTER_MAX: usize,
     const MEMORY_ADDRESS_MAX: usize,
     const STACK_ADDRESS_MAX: usize,
-    const ROWS_MAX: usize,
+    // Maximum number of rw operations, the unused rows are padded with Start
+    // rows so that the number of enabled rows doesn't depend on the witness.
+    const MAX_RWS: usize,
+    // Maximum degree of the gates, which must be at least the degree of the
+    // lexicographic ordering
+    const MAX_DEGREE: usize,
 > {
     s_enable: Column<Fixed>,
     rw_counter: Column<Advice>,
     is_write: Column<Advice>,
     keys: [Column<Advice>; 5],
+    // bits of the tag, which select the constraints of each tag
+    tag_bits: BinaryNumberConfig<usize, 4>,
 
-    // helper column used for IsZero chip
+    // helper column used for IsEqual chips
     keys_diff_inv: [Column<Advice>; 5],
 
-    key2_limbs: [Column<Advice>; 8],
+    // big-endian byte decompositions of the keys, used to sort the rows
+    key1_bytes: [Column<Advice>; 4],
+    key2_bytes: [Column<Advice>; 20],
+    key3_bytes: [Column<Advice>; 4],
     key4_bytes: [Column<Advice>; 32],
+    rw_counter_bytes: [Column<Advice>; 4],
     value: Column<Advice>,
+    value_prev: Column<Advice>,
+    // byte decomposition of the cumulative gas used by a tx
+    gas_used_diff: RangeCheckConfig<F, 8, 8>,
     auxs: [Column<Advice>; 2],
+    // state roots before and after the state change of a row
+    state_root_prev: Column<Advice>,
+    state_root: Column<Advice>,
+    // state roots of the previous block and of the block, at rows 0 and 1
+    state_roots: Column<Instance>,
+    // whether the row is one of the rw operations done before the chunk of the
+    // rows, which are carried to it, and the byte decomposition of the distance
+    // of its rw counter to the one the chunk begins at
+    is_carried: Column<Advice>,
+    carried_diff: RangeCheckConfig<F, 8, 4>,
+    // fingerprint of the carried rows up to the row
+    carried_rws: Column<Advice>,
+    // fingerprint of all the carried rows, at row 0
+    carried_rws_fingerprint: Column<Instance>,
 
     // helper chips here
-    key_is_same_with_prev: [IsZeroConfig<F>; 5],
+    key_is_same_with_prev: [IsEqualConfig<F>; 5],
+    lexicographic_ordering: LexicographicOrderingConfig<F, N_LIMBS>,
 
     // range tables here, TODO: organize them to a single struct?
-    rw_counter_table: Column<Fixed>,
     stack_address_table_zero: Column<Fixed>,
     memory_address_table_zero: Column<Fixed>,
-    memory_value_table: Column<Fixed>,
+    u8_table: RangeTable<8>,
+
+    mpt_table: MptTable,
+}
+
+impl<
+        F: FieldExt,
+        const SANITY_CHECK: bool,
+        const RW_COUNTER_MAX: usize,
+        const MEMORY_ADDRESS_MAX: usize,
+        const STACK_ADDRESS_MAX: usize,
+        const MAX_RWS: usize,
+        const MAX_DEGREE: usize,
+    > Annotate
+    for Config<
+        F,
+        SANITY_CHECK,
+        RW_COUNTER_MAX,
+        MEMORY_ADDRESS_MAX,
+        STACK_ADDRESS_MAX,
+        MAX_RWS,
+        MAX_DEGREE,
+    >
+{
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate(owner, "s_enable", self.s_enable);
+        annotations.annotate(owner, "rw_counter", self.rw_counter);
+        annotations.annotate(owner, "is_write", self.is_write);
+        annotations.annotate_all(owner, "keys", self.keys);
+        annotations.annotate_all(owner, "keys_diff_inv", self.keys_diff_inv);
+        annotations.annotate_all(owner, "key1_bytes", self.key1_bytes);
+        annotations.annotate_all(owner, "key2_bytes", self.key2_bytes);
+        annotations.annotate_all(owner, "key3_bytes", self.key3_bytes);
+        annotations.annotate_all(owner, "key4_bytes", self.key4_bytes);
+        annotations.annotate_all(owner, "rw_counter_bytes", self.rw_counter_bytes);
+        annotations.annotate(owner, "value", self.value);
+        annotations.annotate(owner, "value_prev", self.value_prev);
+        annotations.annotate_all(owner, "auxs", self.auxs);
+        annotations.annotate(owner, "state_root_prev", self.state_root_prev);
+        annotations.annotate(owner, "state_root", self.state_root);
+        annotations.annotate(owner, "state_roots", self.state_roots);
+        annotations.annotate(owner, "is_carried", self.is_carried);
+        annotations.annotate(owner, "carried_rws", self.carried_rws);
+        annotations.annotate(
+            owner,
+            "carried_rws_fingerprint",
+            self.carried_rws_fingerprint,
+        );
+        annotations.annotate(
+            owner,
+            "stack_address_table_zero",
+            self.stack_address_table_zero,
+        );
+        annotations.annotate(
+            owner,
+            "memory_address_table_zero",
+            self.memory_address_table_zero,
+        );
+        annotations.annotate_config(owner, "tag_bits", &self.tag_bits);
+        annotations.annotate_config(owner, "gas_used_diff", &self.gas_used_diff);
+        annotations.annotate_config(owner, "carried_diff", &self.carried_diff);
+        for (idx, is_same) in self.key_is_same_with_prev.iter().enumerate() {
+            annotations.annotate_config(owner, &format!("key_is_same_with_prev_{}", idx), is_same);
+        }
+        annotations.annotate_config(
+            owner,
+            "lexicographic_ordering",
+            &self.lexicographic_ordering,
+        );
+        annotations.annotate_config(owner, "u8_table", &self.u8_table);
+        annotations.annotate_config(owner, "mpt_table", &self.mpt_table);
+    }
 }
 
 impl<
@@ -108,9 +284,36 @@ impl<
         const RW_COUNTER_MAX: usize,
         const MEMORY_ADDRESS_MAX: usize,
         const STACK_ADDRESS_MAX: usize,
-        const ROWS_MAX: usize,
-    > Config<F, SANITY_CHECK, RW_COUNTER_MAX, MEMORY_ADDRESS_MAX, STACK_ADDRESS_MAX, ROWS_MAX>
+        const MAX_RWS: usize,
+        const MAX_DEGREE: usize,
+    >
+    Config<
+        F,
+        SANITY_CHECK,
+        RW_COUNTER_MAX,
+        MEMORY_ADDRESS_MAX,
+        STACK_ADDRESS_MAX,
+        MAX_RWS,
+        MAX_DEGREE,
+    >
 {
+    // Checks of the parameters, which fail the compilation of the circuits
+    // whose parameters don't fit in the columns they're assigned to.
+    const PARAMS_CHECK: () = {
+        assert!(
+            (RW_COUNTER_MAX as u64) < 1 << 32,
+            "RW_COUNTER_MAX doesn't fit in the 4 bytes of rw_counter"
+        );
+        assert!(
+            (MEMORY_ADDRESS_MAX as u64) < 1 << 32 && (STACK_ADDRESS_MAX as u64) < 1 << 32,
+            "MEMORY_ADDRESS_MAX or STACK_ADDRESS_MAX doesn't fit in the 4 bytes of key3"
+        );
+        assert!(
+            MAX_DEGREE >= MIN_DEGREE,
+            "MAX_DEGREE is less than the degree of the lexicographic ordering"
+        );
+    };
+
     fn tag(&self) -> Column<Advice> {
         self.keys[0]
     }
@@ -123,25 +326,65 @@ impl<
     fn storage_key(&self) -> Column<Advice> {
         self.keys[4]
     }
+    fn byte_columns(&self) -> impl Iterator<Item = &Column<Advice>> {
+        self.key1_bytes
+            .iter()
+            .chain(self.key2_bytes.iter())
+            .chain(self.key3_bytes.iter())
+            .chain(self.key4_bytes.iter())
+            .chain(self.rw_counter_bytes.iter())
+    }
 
-    /// Set up custom gates and lookup arguments for this configuration.
-    pub(crate) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
-        let rw_counter = meta.advice_column();
-        let is_write = meta.advice_column();
-        let keys = [(); 5].map(|_| meta.advice_column());
+    /// Set up custom gates and lookup arguments for this configuration, whose
+    /// rows are the ones of `rw_table`, and whose random linear combinations
+    /// use the powers `r, r^2, ..., r^31` of the randomness
+    /// `power_of_randomness`.  The rows before the rw counter
+    /// `rw_counter_begin` are the ones carried to the chunk of the rows, see
+    /// `Block::chunks`.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        rw_table: RwTable,
+        power_of_randomness: [Expression<F>; 31],
+        rw_counter_begin: Expression<F>,
+    ) -> Self {
+        let () = Self::PARAMS_CHECK;
+
+        let rw_counter = rw_table.rw_counter;
+        let is_write = rw_table.is_write;
+        let keys = [
+            rw_table.tag,
+            rw_table.key1,
+            rw_table.key2,
+            rw_table.key3,
+            rw_table.key4,
+        ];
         let keys_diff_inv = [(); 5].map(|_| meta.advice_column());
-        let key2_limbs = [(); 8].map(|_| meta.advice_column());
+        let key1_bytes = [(); 4].map(|_| meta.advice_column());
+        let key2_bytes = [(); 20].map(|_| meta.advice_column());
+        let key3_bytes = [(); 4].map(|_| meta.advice_column());
         let key4_bytes = [(); 32].map(|_| meta.advice_column());
-        let auxs = [(); 2].map(|_| meta.advice_column());
+        let rw_counter_bytes = [(); 4].map(|_| meta.advice_column());
+        let auxs = [rw_table.aux1, rw_table.aux2];
+        let state_root_prev = meta.advice_column();
+        let state_root = meta.advice_column();
+        let state_roots = meta.instance_column();
+        meta.enable_equality(state_root);
+        meta.enable_equality(state_roots);
+        let is_carried = meta.advice_column();
+        let carried_rws = meta.advice_column();
+        let carried_rws_fingerprint = meta.instance_column();
+        meta.enable_equality(carried_rws);
+        meta.enable_equality(carried_rws_fingerprint);
+        let mpt_table = MptTable::construct(meta);
 
         let s_enable = meta.fixed_column();
 
-        let value = meta.advice_column();
+        let value = rw_table.value;
+        let value_prev = rw_table.value_prev;
 
-        let rw_counter_table = meta.fixed_column();
         let memory_address_table_zero = meta.fixed_column();
         let stack_address_table_zero = meta.fixed_column();
-        let memory_value_table = meta.fixed_column();
+        let u8_table = RangeTable::construct(meta);
 
         let new_cb = || BaseConstraintBuilder::<F>::new(MAX_DEGREE);
 
@@ -151,37 +394,79 @@ impl<
 
         let one = Expression::Constant(F::from(1));
 
+        // The tag is one of the supported tags, so exactly one of the tag selectors
+        // is enabled
+        let tag_bits = BinaryNumberChip::configure(
+            meta,
+            |meta| meta.query_fixed(s_enable, Rotation::cur()),
+            tag,
+            TAGS,
+        );
         let q_tag_is = |meta: &mut VirtualCells<F>, tag_value: usize| {
-            let tag_cur = meta.query_advice(tag, Rotation::cur());
-            let all_possible_values = EMPTY_TAG..=STORAGE_TAG;
-            generate_lagrange_base_polynomial(tag_cur, tag_value, all_possible_values)
+            tag_bits.value_equals(meta, tag_value, Rotation::cur())
         };
+        let q_start = |meta: &mut VirtualCells<F>| q_tag_is(meta, START_TAG);
         let q_memory = |meta: &mut VirtualCells<F>| q_tag_is(meta, MEMORY_TAG);
         let q_stack = |meta: &mut VirtualCells<F>| q_tag_is(meta, STACK_TAG);
         let q_storage = |meta: &mut VirtualCells<F>| q_tag_is(meta, STORAGE_TAG);
-
-        let key_is_same_with_prev: [IsZeroConfig<F>; 5] = [0, 1, 2, 3, 4].map(|idx| {
-            IsZeroChip::configure(
+        let q_tx_access_list_account =
+            |meta: &mut VirtualCells<F>| q_tag_is(meta, TX_ACCESS_LIST_ACCOUNT_TAG);
+        let q_tx_access_list_account_storage =
+            |meta: &mut VirtualCells<F>| q_tag_is(meta, TX_ACCESS_LIST_ACCOUNT_STORAGE_TAG);
+        let q_tx_refund = |meta: &mut VirtualCells<F>| q_tag_is(meta, TX_REFUND_TAG);
+        let q_account = |meta: &mut VirtualCells<F>| q_tag_is(meta, ACCOUNT_TAG);
+        let q_account_destructed =
+            |meta: &mut VirtualCells<F>| q_tag_is(meta, ACCOUNT_DESTRUCTED_TAG);
+        let q_call_context = |meta: &mut VirtualCells<F>| q_tag_is(meta, CALL_CONTEXT_TAG);
+        let q_tx_log = |meta: &mut VirtualCells<F>| q_tag_is(meta, TX_LOG_TAG);
+        let q_tx_receipt = |meta: &mut VirtualCells<F>| q_tag_is(meta, TX_RECEIPT_TAG);
+
+        let key_is_same_with_prev: [IsEqualConfig<F>; 5] = [0, 1, 2, 3, 4].map(|idx| {
+            IsEqualChip::configure(
                 meta,
                 |meta| meta.query_fixed(s_enable, Rotation::cur()),
-                |meta| {
-                    let value_cur = meta.query_advice(keys[idx], Rotation::cur());
-                    let value_prev = meta.query_advice(keys[idx], Rotation::prev());
-                    value_cur - value_prev
-                },
+                |meta| meta.query_advice(keys[idx], Rotation::cur()),
+                |meta| meta.query_advice(keys[idx], Rotation::prev()),
                 keys_diff_inv[idx],
             )
         });
 
         let q_all_keys_same = |_meta: &mut VirtualCells<F>| {
-            key_is_same_with_prev[0].is_zero_expression.clone()
-                * key_is_same_with_prev[1].is_zero_expression.clone()
-                * key_is_same_with_prev[2].is_zero_expression.clone()
-                * key_is_same_with_prev[3].is_zero_expression.clone()
-                * key_is_same_with_prev[4].is_zero_expression.clone()
+            key_is_same_with_prev[0].expr()
+                * key_is_same_with_prev[1].expr()
+                * key_is_same_with_prev[2].expr()
+                * key_is_same_with_prev[3].expr()
+                * key_is_same_with_prev[4].expr()
         };
         let q_not_all_keys_same = |meta: &mut VirtualCells<F>| one.clone() - q_all_keys_same(meta);
 
+        // Rows are sorted by (tag, key1, key2, key3, key4, rw_counter), compared byte
+        // by byte.
+        let limbs: [Column<Advice>; N_LIMBS] = std::iter::once(tag)
+            .chain(key1_bytes)
+            .chain(key2_bytes)
+            .chain(key3_bytes)
+            .chain(key4_bytes)
+            .chain(rw_counter_bytes)
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let lexicographic_ordering =
+            LexicographicOrderingConfig::configure(meta, s_enable, limbs, u8_table.0, MAX_DEGREE);
+        for (name, column) in std::iter::once(("tag is a byte", tag)).chain(
+            limbs[1..]
+                .iter()
+                .map(|column| ("key and rw_counter bytes are bytes", *column)),
+        ) {
+            meta.lookup_any(name, |meta| {
+                let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+                let byte = meta.query_advice(column, Rotation::cur());
+                let [u8_table] = u8_table.table_exprs(meta);
+
+                vec![(s_enable * byte, u8_table)]
+            });
+        }
+
         ///////////////////////// General constraints /////////////////////////////////
         // Constraints that affect all rows, no matter which Tag they use
         meta.create_gate("General constraints", |meta| {
@@ -192,11 +477,62 @@ impl<
             let value_cur = meta.query_advice(value, Rotation::cur());
             let value_prev = meta.query_advice(value, Rotation::prev());
 
-            // TODO: 0. key0, key1, key3 are in the expected range
-
-            // TODO: 1. key2 is linear combination of 10 x 16bit limbs and also in range
+            // 0. key1, key3 and rw_counter are linear combinations of 4 bytes, so they
+            // are in range
+            //
+            // 1. key2 is linear combination of 20 bytes, so it's in range
+            let from_be_bytes = |meta: &mut VirtualCells<F>, bytes: &[Column<Advice>]| {
+                bytes.iter().fold(0.expr(), |acc, byte| {
+                    acc * 256.expr() + meta.query_advice(*byte, Rotation::cur())
+                })
+            };
+            for (name, value, bytes) in [
+                ("key1 is decomposed into bytes", keys[1], &key1_bytes[..]),
+                ("key2 is decomposed into bytes", keys[2], &key2_bytes[..]),
+                ("key3 is decomposed into bytes", keys[3], &key3_bytes[..]),
+                (
+                    "rw_counter is decomposed into bytes",
+                    rw_counter,
+                    &rw_counter_bytes[..],
+                ),
+            ] {
+                cb.require_equal(
+                    name,
+                    meta.query_advice(value, Rotation::cur()),
+                    from_be_bytes(meta, bytes),
+                );
+            }
 
-            // TODO: 2. key4 is RLC encoded
+            // 2. key4 is the RLC of key4_bytes for the storage and storage access list
+            // rows, whose storage key is a word.  For the other rows, whose key4 is a
+            // log index or 0, it's the linear combination of the 8 low key4_bytes, the
+            // other ones being 0, so that the bytes the rows are sorted by are unique.
+            let is_key4_rlc = q_storage(meta) + q_tx_access_list_account_storage(meta);
+            let key4_bytes_le = key4_bytes
+                .iter()
+                .rev()
+                .map(|byte| meta.query_advice(*byte, Rotation::cur()))
+                .collect::<Vec<_>>();
+            cb.require_equal(
+                "key4 is the RLC of key4_bytes",
+                is_key4_rlc.clone() * meta.query_advice(keys[4], Rotation::cur()),
+                is_key4_rlc.clone()
+                    * RandomLinearCombination::<F, 32>::random_linear_combine_expr(
+                        key4_bytes_le.clone().try_into().unwrap(),
+                        &power_of_randomness,
+                    ),
+            );
+            cb.require_equal(
+                "key4 is decomposed into bytes",
+                (one.clone() - is_key4_rlc.clone()) * meta.query_advice(keys[4], Rotation::cur()),
+                (one.clone() - is_key4_rlc.clone()) * from_be_bytes(meta, &key4_bytes[24..]),
+            );
+            for byte in key4_bytes_le[8..].iter() {
+                cb.require_zero(
+                    "high key4_bytes are 0",
+                    (one.clone() - is_key4_rlc.clone()) * byte.clone(),
+                );
+            }
 
             // 3. is_write is boolean
             cb.require_boolean("is_write should be boolean", is_write);
@@ -210,9 +546,8 @@ impl<
             // - The corresponding keys in the following column must be increasing.
             //
             // key4 is RLC encoded, so it doesn't keep the order.  We use the key4 bytes
-            // decomposition instead.  Since we will use a chain of comparison gadgets,
-            // we try to merge multiple keys together to reduce the number of required
-            // gadgets.
+            // decomposition instead.  All the keys and the rw_counter are compared as a
+            // single sequence of bytes by `lexicographic_ordering`.
 
             // 6. Read consistency
             // When a row is READ
@@ -230,20 +565,34 @@ impl<
         //
         // When tag is not Start and all the keys are equal in two consecutive a rows:
         // - The corresponding rwc must be strictly increasing.
-        // TODO: rewrite using range check gates rather than lookup
-        meta.lookup_any("rw counter monotonicity", |meta| {
+        //
+        // This follows from the lexicographic ordering, since rw_counter is compared
+        // last and rows are required to be strictly increasing.
+
+        ///////////////////////// Start related constraints /////////////////////////
+
+        // Start rows pad the unused rows at the beginning of the circuit.  Since they
+        // are sorted before all the other tags, and rows are strictly increasing,
+        // they can't be used to skip any rw operation.
+        meta.create_gate("Start operation", |meta| {
+            let mut cb = new_cb();
             let s_enable = meta.query_fixed(s_enable, Rotation::cur());
-            let rw_counter_table = meta.query_fixed(rw_counter_table, Rotation::cur());
-            let rw_counter_prev = meta.query_advice(rw_counter, Rotation::prev());
-            let rw_counter = meta.query_advice(rw_counter, Rotation::cur());
 
-            vec![(
-                s_enable * q_all_keys_same(meta)
-                    * (rw_counter - rw_counter_prev - one.clone()), /*
-                                                                     * - 1 because it needs to
-                                                                     *   be strictly monotone */
-                rw_counter_table,
-            )]
+            // 0. All the keys and values are 0
+            for key in keys[1..].iter() {
+                cb.require_zero("key is 0", meta.query_advice(*key, Rotation::cur()));
+            }
+            cb.require_zero(
+                "is_write is 0",
+                meta.query_advice(is_write, Rotation::cur()),
+            );
+            cb.require_zero("value is 0", meta.query_advice(value, Rotation::cur()));
+            cb.require_zero(
+                "value_prev is 0",
+                meta.query_advice(value_prev, Rotation::cur()),
+            );
+
+            cb.gate(s_enable * q_start(meta))
         });
 
         ///////////////////////// Memory related constraints /////////////////////////
@@ -291,9 +640,9 @@ impl<
         meta.lookup_any("Memory value in allowed range", |meta| {
             let q_memory = q_memory(meta);
             let value = meta.query_advice(value, Rotation::cur());
-            let memory_value_table = meta.query_fixed(memory_value_table, Rotation::cur());
+            let [u8_table] = u8_table.table_exprs(meta);
 
-            vec![(q_memory * value, memory_value_table)]
+            vec![(q_memory * value, u8_table)]
         });
 
         ///////////////////////// Stack related constraints /////////////////////////
@@ -340,8 +689,8 @@ impl<
             let mut cb = new_cb();
             let s_enable = meta.query_fixed(s_enable, Rotation::cur());
             let q_stack = q_stack(meta);
-            let tag_is_same_with_prev = key_is_same_with_prev[0].is_zero_expression.clone();
-            let call_id_same_with_prev = key_is_same_with_prev[1].is_zero_expression.clone();
+            let tag_is_same_with_prev = key_is_same_with_prev[0].expr();
+            let call_id_same_with_prev = key_is_same_with_prev[1].expr();
             let stack_ptr = meta.query_advice(keys[3], Rotation::cur());
             let stack_ptr_prev = meta.query_advice(keys[3], Rotation::prev());
             cb.require_boolean(
@@ -365,7 +714,6 @@ impl<
             let key3 = meta.query_advice(keys[3], Rotation::cur());
 
             // TODO: cold VS warm
-            // TODO: connection to MPT on first and last access for each (address, key)
 
             // 0. Unused keys are 0
             cb.require_zero("key1 is 0", key1);
@@ -390,59 +738,527 @@ impl<
             cb.gate(s_enable * q_storage)
         });
 
+        // 2. State changes are proven by the MPT circuit
+        //
+        // When a row is WRITE:
+        // - (address, storage_key, value_prev, value, root_prev, root) must be in the
+        //   mpt table, where value_prev is the value of the previous row, or the value
+        //   itself for the first access, whose value is the committed one in the
+        //   previous block
+        meta.lookup_any("Storage write in mpt table", |meta| {
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+            let is_write = meta.query_advice(is_write, Rotation::cur());
+            let enable = s_enable * q_storage(meta) * is_write;
+            let value_cur = meta.query_advice(value, Rotation::cur());
+            let value_prev_row = meta.query_advice(value, Rotation::prev());
+            let value_prev = value_prev_row.clone()
+                + q_not_all_keys_same(meta) * (value_cur.clone() - value_prev_row);
+
+            vec![
+                meta.query_advice(keys[2], Rotation::cur()),
+                meta.query_advice(keys[4], Rotation::cur()),
+                (MptProofType::StorageMod as u64).expr(),
+                value_prev,
+                value_cur,
+                meta.query_advice(state_root_prev, Rotation::cur()),
+                meta.query_advice(state_root, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(mpt_table.table_exprs(meta).to_vec())
+            .map(|(input, table)| (enable.clone() * input, table))
+            .collect()
+        });
+
+        ///////////////////////// Account related constraints /////////////////////////
+
+        meta.create_gate("Account operation", |meta| {
+            let mut cb = new_cb();
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+            let is_write = meta.query_advice(is_write, Rotation::cur());
+            let q_read = one.clone() - is_write;
+            let key1 = meta.query_advice(keys[1], Rotation::cur());
+            let field_tag = meta.query_advice(keys[3], Rotation::cur());
+            let key4 = meta.query_advice(keys[4], Rotation::cur());
+            let value_cur = meta.query_advice(value, Rotation::cur());
+            let value_prev_cur = meta.query_advice(value_prev, Rotation::cur());
+            let value_prev_row = meta.query_advice(value, Rotation::prev());
+
+            // 0. Unused keys are 0
+            cb.require_zero("key1 is 0", key1);
+            cb.require_zero("key4 is 0", key4);
+
+            // 1. field_tag is one of the account fields
+            cb.require_in_set(
+                "field_tag in account fields",
+                field_tag,
+                [
+                    AccountFieldTag::Nonce,
+                    AccountFieldTag::Balance,
+                    AccountFieldTag::CodeHash,
+                ]
+                .iter()
+                .map(|field_tag| (*field_tag as u64).expr())
+                .collect(),
+            );
+
+            // 2. Read doesn't change the value
+            cb.require_zero(
+                "if read, value should be same with value_prev",
+                q_read * (value_cur - value_prev_cur.clone()),
+            );
+
+            // 3. Write-read consistency
+            //
+            // When all the keys are equal in two consecutive rows:
+            // - value_prev must be the value of the previous row
+            cb.require_zero(
+                "if keys are same, value_prev should be the value of prev row",
+                q_all_keys_same(meta) * (value_prev_cur - value_prev_row),
+            );
+
+            cb.gate(s_enable * q_account(meta))
+        });
+
+        // 4. Initial values and state changes are proven by the MPT circuit
+        //
+        // For every row, (address, field_tag, value_prev, value, root_prev, root)
+        // must be in the mpt table.  At the first access of (address, field_tag),
+        // value_prev is the value in the trie, and a read proves that the value is
+        // unchanged.
+        meta.lookup_any("Account access in mpt table", |meta| {
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+            let enable = s_enable * q_account(meta);
+
+            vec![
+                meta.query_advice(keys[2], Rotation::cur()),
+                meta.query_advice(keys[4], Rotation::cur()),
+                // The proof types of account fields are the field tags
+                meta.query_advice(keys[3], Rotation::cur()),
+                meta.query_advice(value_prev, Rotation::cur()),
+                meta.query_advice(value, Rotation::cur()),
+                meta.query_advice(state_root_prev, Rotation::cur()),
+                meta.query_advice(state_root, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(mpt_table.table_exprs(meta).to_vec())
+            .map(|(input, table)| (enable.clone() * input, table))
+            .collect()
+        });
+
+        ///////////////////////// Tx access list related constraints ///////////////
+
+        meta.create_gate("TxAccessList operation", |meta| {
+            let mut cb = new_cb();
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+            let q_tx_access_list_account = q_tx_access_list_account(meta);
+            let q_tx_access_list =
+                q_tx_access_list_account.clone() + q_tx_access_list_account_storage(meta);
+            let is_write = meta.query_advice(is_write, Rotation::cur());
+            let value_cur = meta.query_advice(value, Rotation::cur());
+            let value_prev_cur = meta.query_advice(value_prev, Rotation::cur());
+            let value_prev_row = meta.query_advice(value, Rotation::prev());
+
+            // 0. Unused keys are 0, key4 is only the storage key of the storage
+            // access list
+            cb.require_zero("key3 is 0", meta.query_advice(keys[3], Rotation::cur()));
+            cb.require_zero(
+                "key4 is 0 for account access list",
+                q_tx_access_list_account * meta.query_advice(keys[4], Rotation::cur()),
+            );
+
+            // 1. The value is whether the account or storage slot is warm
+            cb.require_boolean("value is boolean", value_cur.clone());
+            cb.require_zero(
+                "if read, value should be same with value_prev",
+                (one.clone() - is_write) * (value_cur - value_prev_cur.clone()),
+            );
+
+            // 2. Nothing is warm before the first access in a tx, and value_prev
+            // is the value of the previous row for the other ones
+            cb.require_zero(
+                "first access has value_prev 0",
+                q_not_all_keys_same(meta) * value_prev_cur.clone(),
+            );
+            cb.require_zero(
+                "if keys are same, value_prev should be the value of prev row",
+                q_all_keys_same(meta) * (value_prev_cur - value_prev_row),
+            );
+
+            cb.gate(s_enable * q_tx_access_list)
+        });
+
+        ///////////////////////// Tx refund related constraints /////////////////////
+
+        meta.create_gate("TxRefund operation", |meta| {
+            let mut cb = new_cb();
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+            let is_write = meta.query_advice(is_write, Rotation::cur());
+            let value_cur = meta.query_advice(value, Rotation::cur());
+            let value_prev_cur = meta.query_advice(value_prev, Rotation::cur());
+            let value_prev_row = meta.query_advice(value, Rotation::prev());
+
+            // 0. Unused keys are 0
+            for idx in [2, 3, 4] {
+                cb.require_zero(
+                    "key2, key3 and key4 are 0",
+                    meta.query_advice(keys[idx], Rotation::cur()),
+                );
+            }
+
+            // 1. The refund of a tx starts from 0, and value_prev is the value of the
+            // previous row
+            cb.require_zero(
+                "if read, value should be same with value_prev",
+                (one.clone() - is_write) * (value_cur - value_prev_cur.clone()),
+            );
+            cb.require_zero(
+                "first access has value_prev 0",
+                q_not_all_keys_same(meta) * value_prev_cur.clone(),
+            );
+            cb.require_zero(
+                "if keys are same, value_prev should be the value of prev row",
+                q_all_keys_same(meta) * (value_prev_cur - value_prev_row),
+            );
+
+            cb.gate(s_enable * q_tx_refund(meta))
+        });
+
+        ///////////////////////// Account destructed related constraints ////////////
+
+        meta.create_gate("AccountDestructed operation", |meta| {
+            let mut cb = new_cb();
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+            let value_cur = meta.query_advice(value, Rotation::cur());
+            let value_prev_cur = meta.query_advice(value_prev, Rotation::cur());
+            let value_prev_row = meta.query_advice(value, Rotation::prev());
+
+            // 0. Unused keys are 0
+            for idx in [1, 3, 4] {
+                cb.require_zero(
+                    "key1, key3 and key4 are 0",
+                    meta.query_advice(keys[idx], Rotation::cur()),
+                );
+            }
+
+            // 1. An account isn't destructed before its first access, and value_prev
+            // is the value of the previous row
+            cb.require_boolean("value is boolean", value_cur);
+            cb.require_zero(
+                "first access has value_prev 0",
+                q_not_all_keys_same(meta) * value_prev_cur.clone(),
+            );
+            cb.require_zero(
+                "if keys are same, value_prev should be the value of prev row",
+                q_all_keys_same(meta) * (value_prev_cur - value_prev_row),
+            );
+
+            cb.gate(s_enable * q_account_destructed(meta))
+        });
+
+        ///////////////////////// Call context related constraints ///////////////////
+
+        meta.create_gate("CallContext operation", |meta| {
+            let mut cb = new_cb();
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+
+            // TODO: value of the first access of a call context field
+
+            // 0. Unused keys are 0, the reads are checked by the read consistency of
+            // the general constraints
+            cb.require_zero("key2 is 0", meta.query_advice(keys[2], Rotation::cur()));
+            cb.require_zero("key4 is 0", meta.query_advice(keys[4], Rotation::cur()));
+            cb.require_zero(
+                "value_prev is 0",
+                meta.query_advice(value_prev, Rotation::cur()),
+            );
+
+            cb.gate(s_enable * q_call_context(meta))
+        });
+
+        ///////////////////////// State root related constraints //////////////////
+
+        // The state roots are chained from row to row, from the one of the previous
+        // block, at the row before the first one, to the one of the block at the last
+        // row, which are both in the instance.  Only the rows looked up in the mpt
+        // table may change the state root.
+        meta.create_gate("State root", |meta| {
+            let mut cb = new_cb();
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+            let is_write = meta.query_advice(is_write, Rotation::cur());
+            let state_root_prev = meta.query_advice(state_root_prev, Rotation::cur());
+            let state_root_cur = meta.query_advice(state_root, Rotation::cur());
+
+            cb.require_equal(
+                "state_root_prev is the state_root of the previous row",
+                state_root_prev.clone(),
+                meta.query_advice(state_root, Rotation::prev()),
+            );
+            cb.require_zero(
+                "state_root is unchanged by the rows not in the mpt table",
+                (one.clone() - q_storage(meta) - q_account(meta))
+                    * (state_root_cur.clone() - state_root_prev.clone()),
+            );
+            cb.require_zero(
+                "state_root is unchanged by the storage reads",
+                q_storage(meta) * (one.clone() - is_write) * (state_root_cur - state_root_prev),
+            );
+
+            cb.gate(s_enable)
+        });
+
+        ///////////////////////// Carried rows related constraints //////////////////
+
+        // The rows of the rw operations done before the chunk of the rows, whose rw
+        // counter is less than the one the chunk begins at, are the ones carried to
+        // it.  Their fingerprint, see `carried_rws_fingerprint`, is the product of
+        // the factors `r^11 + v_0 + v_1 r + ... + v_10 r^10` of their values `v_i`,
+        // accumulated from row to row up to the last one, which is in the instance.
+        let carried_diff = RangeCheckChip::configure(
+            meta,
+            |meta| meta.query_fixed(s_enable, Rotation::cur()) * (one.clone() - q_start(meta)),
+            |meta| {
+                let is_carried = meta.query_advice(is_carried, Rotation::cur());
+                let rw_counter = meta.query_advice(rw_counter, Rotation::cur());
+                is_carried.clone() * (rw_counter_begin.clone() - 1.expr() - rw_counter.clone())
+                    + (1.expr() - is_carried) * (rw_counter - rw_counter_begin)
+            },
+            u8_table,
+        );
+        meta.create_gate("Carried rows", |meta| {
+            let mut cb = new_cb();
+            let s_enable_cur = meta.query_fixed(s_enable, Rotation::cur());
+            let s_enable_prev = meta.query_fixed(s_enable, Rotation::prev());
+            let is_carried = meta.query_advice(is_carried, Rotation::cur());
+            let row = rw_table.table_exprs(meta);
+            let factor = row[1..].iter().zip(power_of_randomness.iter()).fold(
+                row[0].clone() + power_of_randomness[10].clone(),
+                |factor, (value, power)| factor + value.clone() * power.clone(),
+            );
+            // The fingerprint before the first row is the one of no row.
+            let carried_rws_prev = s_enable_prev.clone()
+                * meta.query_advice(carried_rws, Rotation::prev())
+                + one.clone()
+                - s_enable_prev;
+
+            cb.require_boolean("is_carried is boolean", is_carried.clone());
+            cb.require_zero(
+                "Start rows aren't carried",
+                q_start(meta) * is_carried.clone(),
+            );
+            cb.require_equal(
+                "carried_rws is multiplied by the factors of the carried rows",
+                meta.query_advice(carried_rws, Rotation::cur()),
+                carried_rws_prev * (one.clone() + is_carried * (factor - one.clone())),
+            );
+
+            cb.gate(s_enable_cur)
+        });
+
+        ///////////////////////// TxLog related constraints /////////////////////////
+
+        let q_field_tag_is = |meta: &mut VirtualCells<F>, field_tag: u64| {
+            let field_tag_cur = meta.query_advice(keys[3], Rotation::cur());
+            generate_lagrange_base_polynomial(field_tag_cur, field_tag as usize, 1..=3)
+        };
+
+        meta.create_gate("TxLog operation", |meta| {
+            let mut cb = new_cb();
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+            let is_write = meta.query_advice(is_write, Rotation::cur());
+            let log_id = meta.query_advice(keys[2], Rotation::cur());
+            let log_id_prev = meta.query_advice(keys[2], Rotation::prev());
+            let index = meta.query_advice(keys[4], Rotation::cur());
+            let index_prev = meta.query_advice(keys[4], Rotation::prev());
+            let [tag_is_same, tx_id_is_same, log_id_is_same, field_tag_is_same] =
+                [0, 1, 2, 3].map(|idx| key_is_same_with_prev[idx].expr());
+            let tx_is_same = tag_is_same * tx_id_is_same;
+            let log_is_same = tx_is_same.clone() * log_id_is_same;
+            let field_is_same = log_is_same * field_tag_is_same;
+
+            // 0. Logs are only written, once for each (tx_id, log_id, field_tag, index)
+            cb.require_equal("tx log is write", is_write, 1.expr());
+            cb.require_zero("tx log keys are different with prev", q_all_keys_same(meta));
+
+            // 1. field_tag is one of the log fields
+            cb.require_in_set(
+                "field_tag in log fields",
+                meta.query_advice(keys[3], Rotation::cur()),
+                [
+                    TxLogFieldTag::Address,
+                    TxLogFieldTag::Topic,
+                    TxLogFieldTag::Data,
+                ]
+                .iter()
+                .map(|field_tag| (*field_tag as u64).expr())
+                .collect(),
+            );
+
+            // 2. log_id starts from 1 in a tx, and increases by 0 or 1
+            cb.require_zero(
+                "log_id is 1 for first log of tx",
+                (1.expr() - tx_is_same.clone()) * (log_id.clone() - 1.expr()),
+            );
+            cb.require_boolean(
+                "log_id increases by 0 or 1",
+                tx_is_same * (log_id - log_id_prev),
+            );
+
+            // 3. index starts from 0 for a field of a log, and increases by 1
+            cb.require_zero(
+                "index is 0 for first row of field",
+                (1.expr() - field_is_same.clone()) * index.clone(),
+            );
+            cb.require_zero(
+                "index increases by 1",
+                field_is_same * (index.clone() - index_prev - 1.expr()),
+            );
+
+            // 4. A log has a single address and at most 4 topics
+            cb.require_zero(
+                "address index is 0",
+                q_field_tag_is(meta, TxLogFieldTag::Address as u64) * index.clone(),
+            );
+            cb.require_zero(
+                "topic index is less than 4",
+                q_field_tag_is(meta, TxLogFieldTag::Topic as u64)
+                    * (0..4).fold(1.expr(), |acc, idx| acc * (index.clone() - idx.expr())),
+            );
+
+            cb.gate(s_enable * q_tx_log(meta))
+        });
+
+        // 5. Data is a byte
+        meta.lookup_any("Log data in allowed range", |meta| {
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+            let q_data = q_tx_log(meta) * q_field_tag_is(meta, TxLogFieldTag::Data as u64);
+            let value = meta.query_advice(value, Rotation::cur());
+            let [u8_table] = u8_table.table_exprs(meta);
+
+            vec![(s_enable * q_data * value, u8_table)]
+        });
+
+        ///////////////////////// TxReceipt related constraints
+        ///////////////////////// /////////////////////////
+
+        meta.create_gate("TxReceipt operation", |meta| {
+            let mut cb = new_cb();
+            let s_enable = meta.query_fixed(s_enable, Rotation::cur());
+            let is_write = meta.query_advice(is_write, Rotation::cur());
+            let tx_id = meta.query_advice(keys[1], Rotation::cur());
+            let tx_id_prev = meta.query_advice(keys[1], Rotation::prev());
+            let field_tag = meta.query_advice(keys[3], Rotation::cur());
+            let field_tag_prev = meta.query_advice(keys[3], Rotation::prev());
+            let [tag_is_same, tx_id_is_same] = [0, 1].map(|idx| key_is_same_with_prev[idx].expr());
+            let tx_is_same = tag_is_same.clone() * tx_id_is_same;
+            let q_status = q_field_tag_is(meta, TxReceiptFieldTag::PostStateOrStatus as u64);
+
+            // 0. Receipts are only written, once for each (tx_id, field_tag)
+            cb.require_equal("tx receipt is write", is_write, 1.expr());
+            cb.require_zero(
+                "tx receipt keys are different with prev",
+                q_all_keys_same(meta),
+            );
+
+            // 1. Unused keys are 0
+            cb.require_zero("key2 is 0", meta.query_advice(keys[2], Rotation::cur()));
+            cb.require_zero("key4 is 0", meta.query_advice(keys[4], Rotation::cur()));
+
+            // 2. Every tx has all the fields in order, and tx_id starts from 1 and
+            // increases by 1
+            cb.require_in_set(
+                "field_tag in receipt fields",
+                field_tag.clone(),
+                [
+                    TxReceiptFieldTag::PostStateOrStatus,
+                    TxReceiptFieldTag::CumulativeGasUsed,
+                    TxReceiptFieldTag::LogLength,
+                ]
+                .iter()
+                .map(|field_tag| (*field_tag as u64).expr())
+                .collect(),
+            );
+            cb.require_zero(
+                "field_tag increases by 1 in a tx",
+                tx_is_same.clone() * (field_tag.clone() - field_tag_prev.clone() - 1.expr()),
+            );
+            cb.require_zero(
+                "field_tag is the first field for a new tx",
+                (1.expr() - tx_is_same.clone())
+                    * (field_tag - (TxReceiptFieldTag::PostStateOrStatus as u64).expr()),
+            );
+            cb.require_zero(
+                "tx_id is 1 for the first receipt",
+                (1.expr() - tag_is_same.clone()) * (tx_id.clone() - 1.expr()),
+            );
+            cb.require_zero(
+                "tx_id increases by 1",
+                (tag_is_same.clone() - tx_is_same.clone()) * (tx_id - tx_id_prev - 1.expr()),
+            );
+            cb.require_zero(
+                "previous tx has all the fields",
+                (tag_is_same.clone() - tx_is_same)
+                    * (field_tag_prev - (TxReceiptFieldTag::LogLength as u64).expr()),
+            );
+
+            // 3. Status is boolean
+            let value_cur = meta.query_advice(value, Rotation::cur());
+            cb.require_boolean("status is boolean", q_status * value_cur);
+
+            cb.gate(s_enable * q_tx_receipt(meta))
+        });
+        // 4. Cumulative gas used is monotonically increasing
+        //
+        // At the status row of a tx which is not the first one, the cumulative gas
+        // used of the tx is in the next row, and the one of the previous tx is 2
+        // rows before.  Their difference is 0 at all the other rows.
+        let gas_used_diff = RangeCheckChip::configure(
+            meta,
+            |meta| meta.query_fixed(s_enable, Rotation::cur()),
+            |meta| {
+                let tag_is_same = key_is_same_with_prev[0].expr();
+                let q_status = q_field_tag_is(meta, TxReceiptFieldTag::PostStateOrStatus as u64);
+                let gas_used = meta.query_advice(value, Rotation::next());
+                let gas_used_prev = meta.query_advice(value, Rotation(-2));
+                q_tx_receipt(meta) * q_status * tag_is_same * (gas_used - gas_used_prev)
+            },
+            u8_table,
+        );
+
         Config {
             rw_counter,
             value,
             is_write,
+            value_prev,
+            gas_used_diff,
             keys,
+            tag_bits,
             keys_diff_inv,
-            key2_limbs,
+            key1_bytes,
+            key2_bytes,
+            key3_bytes,
             key4_bytes,
+            rw_counter_bytes,
             auxs,
+            state_root_prev,
+            state_root,
+            state_roots,
+            is_carried,
+            carried_diff,
+            carried_rws,
+            carried_rws_fingerprint,
             s_enable,
             key_is_same_with_prev,
-            rw_counter_table,
+            lexicographic_ordering,
             memory_address_table_zero,
             stack_address_table_zero,
-            memory_value_table,
+            u8_table,
+            mpt_table,
         }
     }
 
     /// Load lookup table / other fixed constants for this configuration.
     pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        layouter
-            .assign_region(
-                || "rw counter table",
-                |mut region| {
-                    for idx in 0..=RW_COUNTER_MAX {
-                        region.assign_fixed(
-                            || "rw counter table",
-                            self.rw_counter_table,
-                            idx,
-                            || Ok(F::from(idx as u64)),
-                        )?;
-                    }
-                    Ok(())
-                },
-            )
-            .ok();
-
-        layouter
-            .assign_region(
-                || "memory value table",
-                |mut region| {
-                    for idx in 0..=255 {
-                        region.assign_fixed(
-                            || "memory value table",
-                            self.memory_value_table,
-                            idx,
-                            || Ok(F::from(idx as u64)),
-                        )?;
-                    }
-                    Ok(())
-                },
-            )
-            .ok();
+        self.u8_table.load(layouter).ok();
 
         layouter
             .assign_region(
@@ -477,70 +1293,309 @@ impl<
         )
     }
 
-    /// Assign cells.
+    /// Assign the state changes to the mpt table.
+    pub(crate) fn load_mpt_updates(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        randomness: F,
+        updates: &MptUpdates,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "mpt table",
+            |mut region| {
+                self.mpt_table.assign(&mut region, 0, &Default::default())?;
+                for (offset, update) in updates.0.iter().enumerate() {
+                    self.mpt_table.assign(
+                        &mut region,
+                        offset + 1,
+                        &update.table_assignment(randomness),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign cells, the rows of the rw operations of `rw_map` and `streamed`
+    /// in sorted order.  The streamed ones are read as they are assigned.  The
+    /// rows before the rw counter `rw_counter_begin` are the carried ones.
+    #[tracing::instrument(name = "state_circuit_assign", skip_all)]
     pub(crate) fn assign(
         &self,
         mut layouter: impl Layouter<F>,
         randomness: F,
         rw_map: &RwMap,
+        streamed: &StreamedRws,
+        updates: &MptUpdates,
+        rw_counter_begin: usize,
     ) -> Result<(), Error> {
-        let key_is_same_with_prev_chips: [IsZeroChip<F>; 5] = [0, 1, 2, 3, 4]
-            .map(|idx| IsZeroChip::construct(self.key_is_same_with_prev[idx].clone()));
-
-        layouter.assign_region(
+        let key_is_same_with_prev_chips: [IsEqualChip<F>; 5] = [0, 1, 2, 3, 4]
+            .map(|idx| IsEqualChip::construct(self.key_is_same_with_prev[idx].clone()));
+        let tag_bits_chip = BinaryNumberChip::construct(self.tag_bits);
+        let gas_used_diff_chip = RangeCheckChip::construct(self.gas_used_diff);
+        let carried_diff_chip = RangeCheckChip::construct(self.carried_diff);
+        let (state_root_prev, _) = updates.state_roots();
+
+        let (state_root_prev_cell, state_root_cell, carried_rws_cell) = layouter.assign_region(
             || "State operations",
             |mut region| {
-                // TODO: a "START_TAG" row should be inserted before all other rows in the final
-                // implmentation. Here we start from 1 to prevent some
-                // col.prev() problems since blinding rows are unavailable for constaints.
+                // We start from 1 to prevent some col.prev() problems since blinding rows
+                // are unavailable for constaints.
                 let mut offset = 1;
 
-                let mut rows: Vec<RwRow<F>> = [
-                    RwTableTag::Memory,
-                    RwTableTag::Stack,
-                    RwTableTag::AccountStorage,
-                ]
-                .iter()
-                .map(|tag| {
-                    rw_map.0[tag]
-                        .iter()
-                        .map(|rw| rw.table_assignment(randomness))
-                })
-                .flatten()
-                .collect();
-                rows.sort_by_key(|rw| (rw.tag, rw.key1, rw.key2, rw.key3, rw.key4, rw.rw_counter));
-
-                if rows.len() >= ROWS_MAX {
-                    panic!("too many storage operations");
+                // The state root of the previous block is the one of the row before the
+                // first one.
+                let mut state_root = RandomLinearCombination::random_linear_combine(
+                    state_root_prev.to_le_bytes(),
+                    randomness,
+                );
+                let state_root_prev_cell =
+                    region.assign_advice(|| "state root", self.state_root, 0, || Ok(state_root))?;
+                // The row before the first one is the all-zero row of the rw table, which
+                // is looked up when the lookups of the other circuits are disabled.
+                for column in [self.rw_counter, self.is_write, self.value, self.value_prev]
+                    .iter()
+                    .chain(self.keys.iter())
+                    .chain(self.auxs.iter())
+                {
+                    region.assign_advice(|| "rw table zero row", *column, 0, || Ok(F::zero()))?;
+                }
+                let mut state_root_cell = state_root_prev_cell.clone();
+                // The fingerprint of the carried rows before the first one is the one
+                // of no row.
+                let mut carried_rws = F::one();
+                let mut carried_rws_cell = region.assign_advice(
+                    || "carried rws",
+                    self.carried_rws,
+                    0,
+                    || Ok(carried_rws),
+                )?;
+
+                // The capacity is checked when building the witness, see
+                // `StateCircuit::new`.
+                let n_rws = n_rws(rw_map) + streamed.len();
+                if n_rws > MAX_RWS {
+                    return Err(Error::Synthesis);
                 }
-                for (index, row) in rows.iter().enumerate() {
-                    let row_prev = if index == 0 {
-                        RwRow::default()
-                    } else {
-                        rows[index - 1]
+                // Pad the unused rows with Start rows, so that all the MAX_RWS rows are
+                // enabled.  They are sorted before all the other rows.
+                let padding = (0..MAX_RWS - n_rws)
+                    .map(|rw_counter| Ok(self.row(&Rw::Start { rw_counter }, randomness, updates)));
+                let mut rows = RW_TABLE_TAGS
+                    .iter()
+                    .flat_map(|tag| rw_map.0.get(tag).into_iter().flatten())
+                    .map(|rw| self.row(rw, randomness, updates))
+                    .collect::<Vec<_>>();
+                rows.sort_by_key(|(_, limbs, _)| *limbs);
+                // The streamed operations are sorted already, and merged into the rows as
+                // they are read, with the errors reading them first.
+                let by_limbs =
+                    |lhs: &io::Result<StateRow<F>>, rhs: &io::Result<StateRow<F>>| match (lhs, rhs)
+                    {
+                        (Ok(lhs), Ok(rhs)) => lhs.1 <= rhs.1,
+                        (lhs, _) => lhs.is_err(),
                     };
-                    self.assign_row(
-                        &mut region,
+                let stack = streamed
+                    .sorted_stack()
+                    .map_err(|_| Error::Synthesis)?
+                    .map(|rw| rw.map(|rw| self.row(&rw, randomness, updates)));
+                let memory = streamed
+                    .sorted_memory()
+                    .map_err(|_| Error::Synthesis)?
+                    .map(|rw| rw.map(|rw| self.row(&rw, randomness, updates)));
+                let mut rows = padding
+                    .chain(
+                        rows.into_iter()
+                            .map(Ok)
+                            .merge_by(stack, by_limbs)
+                            .merge_by(memory, by_limbs),
+                    )
+                    .peekable();
+
+                // The two rows before the row, and the keys of the rows compared with the
+                // ones of the rows before them, which are assigned by batches
+                let mut rows_prev = [RwRow::default(); 2];
+                let mut limbs_prev = [0; N_LIMBS];
+                let mut keys_batch = Vec::with_capacity(KEYS_BATCH_SIZE);
+                while let Some(row) = rows.next() {
+                    let (row, limbs, update) = row.map_err(|_| Error::Synthesis)?;
+                    let row_prev = rows_prev[1];
+                    self.assign_row(&mut region, offset, row)?;
+                    tag_bits_chip.assign(&mut region, offset, &(limbs[0] as usize))?;
+                    for (column, limb) in self.byte_columns().zip(limbs[1..].iter()) {
+                        region.assign_advice(
+                            || "key and rw_counter bytes",
+                            *column,
+                            offset,
+                            || Ok(F::from(*limb as u64)),
+                        )?;
+                    }
+                    self.lexicographic_ordering
+                        .assign(&mut region, offset, &limbs, &limbs_prev)?;
+                    // At the status row of a tx which is not the first one, assign the
+                    // difference of the cumulative gas used with the previous tx.
+                    let gas_used_diff = match rows.peek() {
+                        Some(Ok((row_next, _, _)))
+                            if row.tag == F::from(TX_RECEIPT_TAG as u64)
+                                && row.key3
+                                    == F::from(TxReceiptFieldTag::PostStateOrStatus as u64)
+                                && row_prev.tag == row.tag =>
+                        {
+                            row_next.value - rows_prev[0].value
+                        }
+                        _ => F::zero(),
+                    };
+                    gas_used_diff_chip.assign(&mut region, offset, gas_used_diff)?;
+                    // The rows which aren't state changes keep the state root.
+                    let (root_prev, root) = update.map_or((state_root, state_root), |update| {
+                        (update.root_prev, update.root)
+                    });
+                    region.assign_advice(
+                        || "state root prev",
+                        self.state_root_prev,
                         offset,
-                        *row,
-                        row_prev,
-                        &key_is_same_with_prev_chips,
+                        || Ok(root_prev),
                     )?;
+                    state_root_cell = region.assign_advice(
+                        || "state root",
+                        self.state_root,
+                        offset,
+                        || Ok(root),
+                    )?;
+                    state_root = root;
+                    // The rows before the chunk, but the Start ones, are carried to it.
+                    let row_rw_counter = row.rw_counter.get_lower_128() as usize;
+                    let is_start = limbs[0] as usize == START_TAG;
+                    let is_carried = !is_start && row_rw_counter < rw_counter_begin;
+                    region.assign_advice(
+                        || "is carried",
+                        self.is_carried,
+                        offset,
+                        || Ok(F::from(is_carried as u64)),
+                    )?;
+                    let carried_diff = match (is_start, is_carried) {
+                        (true, _) => 0,
+                        (false, true) => rw_counter_begin - 1 - row_rw_counter,
+                        (false, false) => row_rw_counter - rw_counter_begin,
+                    };
+                    carried_diff_chip.assign(&mut region, offset, F::from(carried_diff as u64))?;
+                    if is_carried {
+                        carried_rws *= carried_rw_factor(&row, randomness);
+                    }
+                    carried_rws_cell = region.assign_advice(
+                        || "carried rws",
+                        self.carried_rws,
+                        offset,
+                        || Ok(carried_rws),
+                    )?;
+
+                    // The rows are assigned from offset 1, and the first one is compared
+                    // with a row of zeros.
+                    keys_batch.push((offset, Self::keys(&row), Self::keys(&row_prev)));
+                    if keys_batch.len() == KEYS_BATCH_SIZE || rows.peek().is_none() {
+                        for (idx, chip) in key_is_same_with_prev_chips.iter().enumerate() {
+                            chip.assign_batch(
+                                &mut region,
+                                keys_batch.iter().map(|(offset, keys, keys_prev)| {
+                                    (*offset, keys[idx], keys_prev[idx])
+                                }),
+                            )?;
+                        }
+                        keys_batch.clear();
+                    }
+
+                    rows_prev = [row_prev, row];
+                    limbs_prev = limbs;
                     offset += 1;
                 }
 
-                Ok(())
+                Ok((state_root_prev_cell, state_root_cell, carried_rws_cell))
             },
+        )?;
+
+        layouter.constrain_instance(state_root_prev_cell.cell(), self.state_roots, 0)?;
+        layouter.constrain_instance(state_root_cell.cell(), self.state_roots, 1)?;
+        layouter.constrain_instance(carried_rws_cell.cell(), self.carried_rws_fingerprint, 0)
+    }
+
+    // Returns the row of `rw`.
+    fn row(&self, rw: &Rw, randomness: F, updates: &MptUpdates) -> StateRow<F> {
+        let row = rw.table_assignment(randomness);
+        let limbs = Self::limbs(&row, rw);
+        let update = match *rw {
+            Rw::AccountStorage {
+                rw_counter,
+                account_address,
+                storage_key,
+                ..
+            } => updates.get(
+                rw_counter,
+                MptProofType::StorageMod,
+                account_address,
+                storage_key,
+            ),
+            Rw::Account {
+                rw_counter,
+                account_address,
+                field_tag,
+                ..
+            } => updates.get(
+                rw_counter,
+                match field_tag {
+                    AccountFieldTag::Nonce => MptProofType::NonceMod,
+                    AccountFieldTag::Balance => MptProofType::BalanceMod,
+                    AccountFieldTag::CodeHash => MptProofType::CodeHashMod,
+                },
+                account_address,
+                Word::zero(),
+            ),
+            _ => None,
+        };
+        (
+            row,
+            limbs,
+            update.map(|update| update.table_assignment(randomness)),
         )
     }
 
+    // Returns the keys of a row, in the order of `keys`.
+    fn keys(row: &RwRow<F>) -> [F; 5] {
+        [row.tag, row.key1, row.key2, row.key3, row.key4]
+    }
+
+    // Returns the bytes a row is sorted by, in the order of the limbs of
+    // `lexicographic_ordering`.
+    fn limbs(row: &RwRow<F>, rw: &Rw) -> [u8; N_LIMBS] {
+        let to_be_bytes = |value: F, n_bytes: usize| {
+            let mut bytes = value.to_repr()[..n_bytes].to_vec();
+            bytes.reverse();
+            bytes
+        };
+        let key4 = match rw {
+            Rw::AccountStorage { storage_key, .. }
+            | Rw::TxAccessListAccountStorage { storage_key, .. } => *storage_key,
+            Rw::TxLog { index, .. } => Word::from(*index),
+            _ => Word::zero(),
+        };
+
+        [
+            to_be_bytes(row.tag, 1),
+            to_be_bytes(row.key1, 4),
+            to_be_bytes(row.key2, 20),
+            to_be_bytes(row.key3, 4),
+            key4.to_be_bytes().to_vec(),
+            to_be_bytes(row.rw_counter, 4),
+        ]
+        .concat()
+        .try_into()
+        .unwrap()
+    }
+
     fn assign_row(
         &self,
         region: &mut Region<'_, F>,
         offset: usize,
         row: RwRow<F>,
-        row_prev: RwRow<F>,
-        diff_is_zero_chips: &[IsZeroChip<F>; 5],
     ) -> Result<(), Error> {
         let address = row.key3;
         let rw_counter = row.rw_counter;
@@ -548,9 +1603,6 @@ impl<
         let is_write = row.is_write;
 
         // check witness sanity
-        if offset > ROWS_MAX {
-            panic!("too many storage operations");
-        }
         if SANITY_CHECK {
             if rw_counter > F::from(RW_COUNTER_MAX as u64) {
                 panic!("rw_counter out of range");
@@ -572,34 +1624,92 @@ impl<
         region.assign_fixed(|| "enable row", self.s_enable, offset, || Ok(F::one()))?;
         region.assign_advice(|| "rw counter", self.rw_counter, offset, || Ok(rw_counter))?;
         region.assign_advice(|| "value", self.value, offset, || Ok(value))?;
+        region.assign_advice(
+            || "value_prev",
+            self.value_prev,
+            offset,
+            || Ok(row.value_prev),
+        )?;
         region.assign_advice(|| "is_write", self.is_write, offset, || Ok(is_write))?;
 
-        for (i, diff_is_zero_chip) in diff_is_zero_chips.iter().enumerate() {
-            let (value, diff) = match i {
-                // FIXME: find a better way here
-                0 => (row.tag, row.tag - row_prev.tag),
-                1 => (row.key1, row.key1 - row_prev.key1),
-                2 => (row.key2, row.key2 - row_prev.key2),
-                3 => (row.key3, row.key3 - row_prev.key3),
-                4 => (row.key4, row.key4 - row_prev.key4),
-                _ => unreachable!(),
-            };
+        for (i, value) in Self::keys(&row).iter().enumerate() {
             region.assign_advice(
                 || format!("assign key{}", i),
                 self.keys[i],
                 offset,
-                || Ok(value),
+                || Ok(*value),
             )?;
-            diff_is_zero_chip.assign(region, offset, Some(diff))?;
         }
 
         region.assign_advice(|| "aux1", self.auxs[0], offset, || Ok(row.aux1))?;
         region.assign_advice(|| "aux2", self.auxs[1], offset, || Ok(row.aux2))?;
 
-        Ok(())
+        Ok(())
+    }
+}
+
+// Returns the number of rw operations of `rw_map` assigned to the state
+// circuit.
+fn n_rws(rw_map: &RwMap) -> usize {
+    RW_TABLE_TAGS
+        .iter()
+        .map(|tag| rw_map.0.get(tag).map_or(0, |rws| rws.len()))
+        .sum()
+}
+
+/// Returns the values of the instance column of the state roots: the RLCs of
+/// the state roots of the previous block and of the block, before and after
+/// `updates`.
+pub fn state_roots_instance<F: FieldExt>(updates: &MptUpdates, randomness: F) -> Vec<F> {
+    let (state_root_prev, state_root) = updates.state_roots();
+    [state_root_prev, state_root]
+        .iter()
+        .map(|root| RandomLinearCombination::random_linear_combine(root.to_le_bytes(), randomness))
+        .collect()
+}
+
+/// Returns the rw counter the chunk of the rw operations of the state circuit
+/// begins at, queried from an instance column holding it on all the rows, like
+/// the powers of the randomness.
+pub fn rw_counter_begin_from_instance<F: Field>(meta: &mut ConstraintSystem<F>) -> Expression<F> {
+    let column = meta.instance_column();
+    let mut rw_counter_begin = None;
+
+    meta.create_gate("rw counter begin from instance", |meta| {
+        rw_counter_begin = Some(meta.query_instance(column, Rotation::cur()));
+
+        [0.expr()]
+    });
+
+    rw_counter_begin.unwrap()
+}
+
+/// Error when building the witness of the state circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateCircuitError {
+    /// The block has more rw operations than the circuit can hold.
+    TooManyRws {
+        /// Number of rw operations of the block
+        n_rws: usize,
+        /// Maximum number of rw operations of the circuit
+        max_rws: usize,
+    },
+}
+
+impl fmt::Display for StateCircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyRws { n_rws, max_rws } => write!(
+                f,
+                "too many rw operations for the state circuit: {} > max_rws {}",
+                n_rws, max_rws
+            ),
+        }
     }
 }
 
+impl std::error::Error for StateCircuitError {}
+
 /// State Circuit struct.
 #[derive(Default)]
 pub struct StateCircuit<
@@ -608,30 +1718,141 @@ pub struct StateCircuit<
     const RW_COUNTER_MAX: usize,
     const MEMORY_ADDRESS_MAX: usize,
     const STACK_ADDRESS_MAX: usize,
-    const ROWS_MAX: usize,
+    const MAX_RWS: usize,
+    const MAX_DEGREE: usize,
 > {
     /// randomness used in linear combination
     pub randomness: F,
     /// witness for rw map
     pub rw_map: RwMap,
+    /// witness for the stack and memory rw operations streamed to the circuit,
+    /// see [`StateCircuit::new_streamed`]
+    pub streamed: StreamedRws,
+    /// witness for the state changes proven by the mpt circuit
+    pub updates: MptUpdates,
+    /// rw counter the chunk of the rw operations begins at, the ones before
+    /// it are carried to the chunk, see [`StateCircuit::with_chunk`]
+    pub rw_counter_begin: usize,
 }
 
 impl<
-        F: FieldExt,
+        F: Field,
         const SANITY_CHECK: bool,
         const RW_COUNTER_MAX: usize,
         const MEMORY_ADDRESS_MAX: usize,
         const STACK_ADDRESS_MAX: usize,
-        const ROWS_MAX: usize,
+        const MAX_RWS: usize,
+        const MAX_DEGREE: usize,
+    >
+    StateCircuit<
+        F,
+        SANITY_CHECK,
+        RW_COUNTER_MAX,
+        MEMORY_ADDRESS_MAX,
+        STACK_ADDRESS_MAX,
+        MAX_RWS,
+        MAX_DEGREE,
     >
-    StateCircuit<F, SANITY_CHECK, RW_COUNTER_MAX, MEMORY_ADDRESS_MAX, STACK_ADDRESS_MAX, ROWS_MAX>
 {
-    /// Use rw_map to build a StateCircuit instance
-    pub fn new(randomness: F, rw_map: &RwMap) -> Self {
-        Self {
+    /// Returns the smallest `k` of the circuits of these parameters, whose
+    /// `2^k` rows fit the `MAX_RWS` rw rows and the fixed tables, and leave 64
+    /// rows for the blinding factors.
+    pub fn min_k() -> u32 {
+        let n_rows = [
+            MAX_RWS + 1,
+            1 << 8,
+            MEMORY_ADDRESS_MAX + 1,
+            STACK_ADDRESS_MAX + 1,
+        ]
+        .iter()
+        .max()
+        .unwrap()
+            + 64;
+        usize::BITS - (n_rows - 1).leading_zeros()
+    }
+
+    /// Use rw_map to build a StateCircuit instance.  Returns an error if
+    /// rw_map has more than `MAX_RWS` rw operations.
+    pub fn new(randomness: F, rw_map: &RwMap) -> Result<Self, StateCircuitError> {
+        let n_rws = n_rws(rw_map);
+        if n_rws > MAX_RWS {
+            return Err(StateCircuitError::TooManyRws {
+                n_rws,
+                max_rws: MAX_RWS,
+            });
+        }
+
+        Ok(Self {
             randomness,
             rw_map: rw_map.clone(),
+            streamed: StreamedRws::default(),
+            updates: MptUpdates::mock_from(rw_map),
+            rw_counter_begin: 0,
+        })
+    }
+
+    /// Sets the rw counter the rw operations of the circuit begin at to the
+    /// one of `chunk`, the chunk of the block they are the ones of, so that
+    /// the rw operations before it are proven to be the ones carried to the
+    /// chunk.  The rw operations of a chunk are all in `rw_map`.
+    pub fn with_chunk(self, chunk: Option<&Chunk>) -> Self {
+        Self {
+            rw_counter_begin: chunk.map_or(0, |chunk| chunk.begin.rw_counter),
+            ..self
+        }
+    }
+
+    /// Builds a StateCircuit instance from the rw operations of `container`,
+    /// whose stack and memory operations may have been spilled to disk.  These
+    /// are streamed back from it when the circuit is synthesized instead of
+    /// being collected, see [`RwMap::with_streamed`].  Returns an error if
+    /// there are more than `MAX_RWS` rw operations.
+    pub fn new_streamed(
+        randomness: F,
+        container: Arc<OperationContainer>,
+    ) -> Result<Self, StateCircuitError> {
+        let (rw_map, streamed) = RwMap::with_streamed(container);
+        let n_rws = n_rws(&rw_map) + streamed.len();
+        if n_rws > MAX_RWS {
+            return Err(StateCircuitError::TooManyRws {
+                n_rws,
+                max_rws: MAX_RWS,
+            });
         }
+
+        Ok(Self {
+            randomness,
+            updates: MptUpdates::mock_from(&rw_map),
+            rw_map,
+            streamed,
+            rw_counter_begin: 0,
+        })
+    }
+
+    /// Returns the values of the instance columns: the powers `r, r^2, ...,
+    /// r^31` of the randomness and the rw counter the chunk begins at on all
+    /// the rows of the rw operations, the state roots of the previous block
+    /// and of the block, and the fingerprint of the rw operations carried to
+    /// the chunk.
+    pub fn instance(&self) -> Vec<Vec<F>> {
+        PowersOfRandomness::new(self.randomness)
+            .take(31)
+            .into_iter()
+            .map(|power| vec![power; MAX_RWS + 1])
+            .chain(std::iter::once(vec![
+                F::from(self.rw_counter_begin as u64);
+                MAX_RWS + 1
+            ]))
+            .chain(std::iter::once(state_roots_instance(
+                &self.updates,
+                self.randomness,
+            )))
+            .chain(std::iter::once(vec![carried_rws_fingerprint(
+                &self.rw_map,
+                self.rw_counter_begin,
+                self.randomness,
+            )]))
+            .collect()
     }
 }
 
@@ -641,7 +1862,8 @@ impl<
         const RW_COUNTER_MAX: usize,
         const MEMORY_ADDRESS_MAX: usize,
         const STACK_ADDRESS_MAX: usize,
-        const ROWS_MAX: usize,
+        const MAX_RWS: usize,
+        const MAX_DEGREE: usize,
     > Circuit<F>
     for StateCircuit<
         F,
@@ -649,11 +1871,19 @@ impl<
         RW_COUNTER_MAX,
         MEMORY_ADDRESS_MAX,
         STACK_ADDRESS_MAX,
-        ROWS_MAX,
+        MAX_RWS,
+        MAX_DEGREE,
     >
 {
-    type Config =
-        Config<F, SANITY_CHECK, RW_COUNTER_MAX, MEMORY_ADDRESS_MAX, STACK_ADDRESS_MAX, ROWS_MAX>;
+    type Config = Config<
+        F,
+        SANITY_CHECK,
+        RW_COUNTER_MAX,
+        MEMORY_ADDRESS_MAX,
+        STACK_ADDRESS_MAX,
+        MAX_RWS,
+        MAX_DEGREE,
+    >;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
@@ -661,7 +1891,10 @@ impl<
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        Config::configure(meta)
+        let rw_table = RwTable::construct(meta);
+        let power_of_randomness = power_of_randomness_from_instance::<_, 31>(meta);
+        let rw_counter_begin = rw_counter_begin_from_instance(meta);
+        Config::configure(meta, rw_table, power_of_randomness, rw_counter_begin)
     }
 
     fn synthesize(
@@ -670,7 +1903,15 @@ impl<
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
         config.load(&mut layouter)?;
-        config.assign(layouter, self.randomness, &self.rw_map)?;
+        config.load_mpt_updates(&mut layouter, self.randomness, &self.updates)?;
+        config.assign(
+            layouter,
+            self.randomness,
+            &self.rw_map,
+            &self.streamed,
+            &self.updates,
+            self.rw_counter_begin,
+        )?;
 
         Ok(())
     }
@@ -679,22 +1920,25 @@ impl<
 mod tests {
     use super::*;
     use bus_mapping::operation::{
-        MemoryOp, Operation, OperationContainer, RWCounter, StackOp, StorageOp, RW,
+        AccountField, AccountOp, MemoryOp, Operation, OperationContainer, RWCounter, StackOp,
+        StorageOp, TxLogField, TxLogOp, TxReceiptField, TxReceiptOp, TxRefundOp, RW,
     };
     use eth_types::evm_types::{MemoryAddress, StackAddress};
     use eth_types::{address, bytecode, Word};
     use halo2_proofs::arithmetic::BaseExt;
     use halo2_proofs::dev::MockProver;
     use pairing::bn256::Fr;
+    use std::convert::TryFrom;
 
     macro_rules! test_state_circuit_ok {
         ($k:expr, $rw_counter_max:expr, $memory_rows_max:expr, $memory_address_max:expr, $stack_rows_max:expr, $stack_address_max:expr, $storage_rows_max:expr, $memory_ops:expr, $stack_ops:expr, $storage_ops:expr, $result:expr) => {{
-            let rw_map = RwMap::from(&OperationContainer {
-                memory: $memory_ops,
-                stack: $stack_ops,
+            let rw_map = RwMap::try_from(&OperationContainer {
+                memory: $memory_ops.into(),
+                stack: $stack_ops.into(),
                 storage: $storage_ops,
                 ..Default::default()
-            });
+            })
+            .unwrap();
             let circuit = StateCircuit::<
                 Fr,
                 true,
@@ -702,9 +1946,11 @@ mod tests {
                 $memory_address_max,
                 $stack_address_max,
                 { $memory_rows_max + $stack_rows_max + $storage_rows_max },
-            >::new(Fr::rand(), &rw_map);
+                DEFAULT_MAX_DEGREE,
+            >::new(Fr::rand(), &rw_map)
+            .unwrap();
 
-            let prover = MockProver::<Fr>::run($k, &circuit, vec![]).unwrap();
+            let prover = MockProver::<Fr>::run($k, &circuit, circuit.instance()).unwrap();
             let verify_result = prover.verify();
             assert!(verify_result.is_ok(), "verify err: {:#?}", verify_result);
         }};
@@ -712,12 +1958,13 @@ mod tests {
 
     macro_rules! test_state_circuit_error {
         ($k:expr, $rw_counter_max:expr, $memory_rows_max:expr, $memory_address_max:expr, $stack_rows_max:expr, $stack_address_max:expr, $storage_rows_max:expr, $memory_ops:expr, $stack_ops:expr, $storage_ops:expr) => {{
-            let rw_map = RwMap::from(&OperationContainer {
-                memory: $memory_ops,
-                stack: $stack_ops,
+            let rw_map = RwMap::try_from(&OperationContainer {
+                memory: $memory_ops.into(),
+                stack: $stack_ops.into(),
                 storage: $storage_ops,
                 ..Default::default()
-            });
+            })
+            .unwrap();
             let circuit = StateCircuit::<
                 Fr,
                 false,
@@ -725,9 +1972,11 @@ mod tests {
                 $memory_address_max,
                 $stack_address_max,
                 { $memory_rows_max + $stack_rows_max + $storage_rows_max },
-            >::new(Fr::rand(), &rw_map);
+                DEFAULT_MAX_DEGREE,
+            >::new(Fr::rand(), &rw_map)
+            .unwrap();
 
-            let prover = MockProver::<Fr>::run($k, &circuit, vec![]).unwrap();
+            let prover = MockProver::<Fr>::run($k, &circuit, circuit.instance()).unwrap();
             assert!(prover.verify().is_err());
         }};
     }
@@ -819,6 +2068,95 @@ mod tests {
         );
     }
 
+    #[test]
+    fn state_circuit_streamed() {
+        let dir =
+            std::env::temp_dir().join(format!("state-circuit-streamed-{}", std::process::id()));
+        let mut container = OperationContainer::new();
+        container.spill_to(&dir, 2).unwrap();
+        for (rwc, rw, address) in [
+            (12, RW::WRITE, 0),
+            (17, RW::WRITE, 1),
+            (24, RW::READ, 0),
+            (87, RW::READ, 1),
+        ] {
+            container
+                .insert(Operation::new(
+                    RWCounter::from(rwc),
+                    rw,
+                    MemoryOp::new(1, MemoryAddress::from(address), 32),
+                ))
+                .unwrap();
+            container
+                .insert(Operation::new(
+                    RWCounter::from(rwc),
+                    rw,
+                    StackOp::new(1, StackAddress::from(1023 - address), Word::from(32)),
+                ))
+                .unwrap();
+        }
+        assert!(container.memory.is_spilled() && container.stack.is_spilled());
+
+        // The spilled operations are read back for the EVM circuit, but only
+        // the ones the steps reference
+        let rw_map = RwMap::try_from(&container).unwrap();
+        assert_eq!(rw_map.0[&RwTableTag::Stack].len(), 4);
+        assert_eq!(rw_map.0[&RwTableTag::Memory].len(), 4);
+        assert!(RwMap::from_streamed(&container, std::iter::empty()).is_err());
+
+        let circuit =
+            StateCircuit::<Fr, true, 2000, 2, 1023, 1200, DEFAULT_MAX_DEGREE>::new_streamed(
+                Fr::rand(),
+                Arc::new(container),
+            )
+            .unwrap();
+        assert_eq!(circuit.streamed.len(), 8);
+        let prover = MockProver::<Fr>::run(12, &circuit, circuit.instance()).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "real-prover")]
+    #[test]
+    fn state_circuit_simple_real_prover() {
+        let memory_ops = vec![
+            Operation::new(
+                RWCounter::from(12),
+                RW::WRITE,
+                MemoryOp::new(1, MemoryAddress::from(0), 32),
+            ),
+            Operation::new(
+                RWCounter::from(24),
+                RW::READ,
+                MemoryOp::new(1, MemoryAddress::from(0), 32),
+            ),
+        ];
+        let stack_ops = vec![
+            Operation::new(
+                RWCounter::from(17),
+                RW::WRITE,
+                StackOp::new(1, StackAddress::from(1), Word::from(32)),
+            ),
+            Operation::new(
+                RWCounter::from(87),
+                RW::READ,
+                StackOp::new(1, StackAddress::from(1), Word::from(32)),
+            ),
+        ];
+        let rw_map = RwMap::try_from(&OperationContainer {
+            memory: memory_ops.into(),
+            stack: stack_ops.into(),
+            ..Default::default()
+        })
+        .unwrap();
+        let circuit = StateCircuit::<Fr, true, 2000, 2, 1023, 1200, DEFAULT_MAX_DEGREE>::new(
+            Fr::rand(),
+            &rw_map,
+        )
+        .unwrap();
+        crate::test_util::run_real_prover(12, &circuit, circuit.instance()).unwrap();
+    }
+
     #[test]
     fn no_stack_padding() {
         let memory_op_0 = Operation::new(
@@ -1382,6 +2720,464 @@ mod tests {
         );
     }
 
+    #[test]
+    fn storage_key_ordering() {
+        // The storage keys are sorted by their bytes, not by their RLC encoding.
+        let storage_op_0 = Operation::new(
+            RWCounter::from(0),
+            RW::WRITE,
+            StorageOp::new(
+                address!("0x0000000000000000000000000000000000000001"),
+                Word::from(0x0100),
+                Word::from(32),
+                Word::zero(),
+                1usize,
+                Word::zero(),
+            ),
+        );
+        let storage_op_1 = Operation::new(
+            RWCounter::from(0),
+            RW::WRITE,
+            StorageOp::new(
+                address!("0x0000000000000000000000000000000000000001"),
+                Word::from(0x01),
+                Word::from(32),
+                Word::zero(),
+                1usize,
+                Word::zero(),
+            ),
+        );
+        let storage_op_2 = Operation::new(
+            RWCounter::from(5),
+            RW::READ,
+            StorageOp::new(
+                address!("0x0000000000000000000000000000000000000001"),
+                Word::from(0x01),
+                Word::from(32),
+                Word::from(32),
+                1usize,
+                Word::zero(),
+            ),
+        );
+        let storage_op_3 = Operation::new(
+            RWCounter::from(0),
+            RW::WRITE,
+            StorageOp::new(
+                address!("0x0000000000000000000000000000000000000002"),
+                Word::from(0x01),
+                Word::from(32),
+                Word::zero(),
+                1usize,
+                Word::zero(),
+            ),
+        );
+
+        test_state_circuit_ok!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            vec![],
+            vec![],
+            vec![storage_op_0, storage_op_1, storage_op_2, storage_op_3],
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn storage_write_not_in_mpt_table() {
+        let storage_op_0 = Operation::new(
+            RWCounter::from(0),
+            RW::WRITE,
+            StorageOp::new(
+                address!("0x0000000000000000000000000000000000000001"),
+                Word::from(0x40),
+                Word::from(32),
+                Word::zero(),
+                1usize,
+                Word::zero(),
+            ),
+        );
+        let storage_op_1 = Operation::new(
+            RWCounter::from(18),
+            RW::WRITE,
+            StorageOp::new(
+                address!("0x0000000000000000000000000000000000000001"),
+                Word::from(0x40),
+                Word::from(33),
+                Word::from(32),
+                1usize,
+                Word::from(32),
+            ),
+        );
+
+        let rw_map = RwMap::try_from(&OperationContainer {
+            storage: vec![storage_op_0, storage_op_1],
+            ..Default::default()
+        })
+        .unwrap();
+        let mut circuit =
+            StateCircuit::<Fr, false, 2000, 100, 1023, 1000, 15>::new(Fr::rand(), &rw_map).unwrap();
+        assert_eq!(circuit.updates.0.len(), 2);
+
+        // Fails because the mpt circuit proves a different value
+        circuit.updates.0[1].value = Word::from(34);
+
+        let prover = MockProver::<Fr>::run(14, &circuit, circuit.instance()).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn storage_committed_value_not_in_mpt_table() {
+        let storage_op = Operation::new(
+            RWCounter::from(0),
+            RW::WRITE,
+            StorageOp::new(
+                address!("0x0000000000000000000000000000000000000001"),
+                Word::from(0x40),
+                Word::from(32),
+                Word::zero(),
+                1usize,
+                Word::zero(),
+            ),
+        );
+
+        let rw_map = RwMap::try_from(&OperationContainer {
+            storage: vec![storage_op],
+            ..Default::default()
+        })
+        .unwrap();
+        let mut circuit =
+            StateCircuit::<Fr, false, 2000, 100, 1023, 1000, 15>::new(Fr::rand(), &rw_map).unwrap();
+        assert_eq!(circuit.updates.0.len(), 1);
+
+        // Fails because the committed value isn't the one in the trie
+        circuit.updates.0[0].value_prev = Word::from(31);
+        circuit.updates.0[0].value = Word::from(31);
+
+        let prover = MockProver::<Fr>::run(14, &circuit, circuit.instance()).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn state_root_not_in_instance() {
+        let storage_op = Operation::new(
+            RWCounter::from(0),
+            RW::WRITE,
+            StorageOp::new(
+                address!("0x0000000000000000000000000000000000000001"),
+                Word::from(0x40),
+                Word::from(32),
+                Word::zero(),
+                1usize,
+                Word::zero(),
+            ),
+        );
+
+        let rw_map = RwMap::try_from(&OperationContainer {
+            storage: vec![storage_op],
+            ..Default::default()
+        })
+        .unwrap();
+        let circuit =
+            StateCircuit::<Fr, false, 2000, 100, 1023, 1000, 15>::new(Fr::rand(), &rw_map).unwrap();
+        let mut instance = circuit.instance();
+
+        // Fails because the state root of the block is another one, in the
+        // instance column before the one of the carried rw operations
+        let n_columns = instance.len();
+        *instance[n_columns - 2].last_mut().unwrap() += Fr::from(1);
+
+        let prover = MockProver::<Fr>::run(14, &circuit, instance).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    fn account_op(
+        rw_counter: usize,
+        rw: RW,
+        field: AccountField,
+        value: u64,
+        value_prev: u64,
+    ) -> Operation<AccountOp> {
+        Operation::new(
+            RWCounter::from(rw_counter),
+            rw,
+            AccountOp {
+                address: address!("0x0000000000000000000000000000000000000001"),
+                field,
+                value: Word::from(value),
+                value_prev: Word::from(value_prev),
+            },
+        )
+    }
+
+    fn verify_account_ops(account_ops: Vec<Operation<AccountOp>>) -> Result<(), ()> {
+        let rw_map = RwMap::try_from(&OperationContainer {
+            account: account_ops,
+            ..Default::default()
+        })
+        .unwrap();
+        let circuit =
+            StateCircuit::<Fr, false, 2000, 100, 1023, 1000, 15>::new(Fr::rand(), &rw_map).unwrap();
+        let prover = MockProver::<Fr>::run(14, &circuit, circuit.instance()).unwrap();
+        prover.verify().map_err(|_| ())
+    }
+
+    #[test]
+    fn account() {
+        assert_eq!(
+            verify_account_ops(vec![
+                account_op(1, RW::WRITE, AccountField::Nonce, 1, 0),
+                account_op(5, RW::READ, AccountField::Nonce, 1, 1),
+                account_op(2, RW::READ, AccountField::Balance, 100, 100),
+                account_op(3, RW::WRITE, AccountField::Balance, 90, 100),
+                account_op(4, RW::READ, AccountField::CodeHash, 7, 7),
+            ]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn account_inconsistent_value_prev() {
+        // Fails because value_prev is not the value written by the previous row
+        assert_eq!(
+            verify_account_ops(vec![
+                account_op(1, RW::WRITE, AccountField::Balance, 90, 100),
+                account_op(2, RW::WRITE, AccountField::Balance, 80, 100),
+            ]),
+            Err(())
+        );
+    }
+
+    #[test]
+    fn account_read_changes_value() {
+        // Fails because a read can't change the value
+        assert_eq!(
+            verify_account_ops(vec![account_op(1, RW::READ, AccountField::Nonce, 2, 1)]),
+            Err(())
+        );
+    }
+
+    fn tx_refund_op(
+        rw_counter: usize,
+        rw: RW,
+        value: u64,
+        value_prev: u64,
+    ) -> Operation<TxRefundOp> {
+        Operation::new(
+            RWCounter::from(rw_counter),
+            rw,
+            TxRefundOp {
+                tx_id: 1,
+                value,
+                value_prev,
+            },
+        )
+    }
+
+    fn verify_tx_refund_ops(tx_refund_ops: Vec<Operation<TxRefundOp>>) -> Result<(), ()> {
+        let rw_map = RwMap::try_from(&OperationContainer {
+            tx_refund: tx_refund_ops,
+            ..Default::default()
+        })
+        .unwrap();
+        let circuit =
+            StateCircuit::<Fr, false, 2000, 100, 1023, 1000, 15>::new(Fr::rand(), &rw_map).unwrap();
+        let prover = MockProver::<Fr>::run(14, &circuit, circuit.instance()).unwrap();
+        prover.verify().map_err(|_| ())
+    }
+
+    #[test]
+    fn tx_refund_ok() {
+        assert_eq!(
+            verify_tx_refund_ops(vec![
+                tx_refund_op(1, RW::WRITE, 4800, 0),
+                tx_refund_op(2, RW::READ, 4800, 4800),
+                tx_refund_op(3, RW::WRITE, 0, 4800),
+            ]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn tx_refund_not_from_zero() {
+        // Fails because the refund of a tx starts from 0
+        assert_eq!(
+            verify_tx_refund_ops(vec![tx_refund_op(1, RW::WRITE, 4800, 100)]),
+            Err(())
+        );
+    }
+
+    #[test]
+    fn tx_refund_inconsistent_value_prev() {
+        // Fails because value_prev is not the value written by the previous row
+        assert_eq!(
+            verify_tx_refund_ops(vec![
+                tx_refund_op(1, RW::WRITE, 4800, 0),
+                tx_refund_op(2, RW::WRITE, 9600, 0),
+            ]),
+            Err(())
+        );
+    }
+
+    #[test]
+    fn too_many_rws() {
+        let rw_map = RwMap::try_from(&OperationContainer {
+            account: vec![
+                account_op(1, RW::WRITE, AccountField::Nonce, 1, 0),
+                account_op(2, RW::READ, AccountField::Nonce, 1, 1),
+            ],
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            StateCircuit::<Fr, false, 2000, 100, 1023, 1, 15>::new(Fr::rand(), &rw_map).err(),
+            Some(StateCircuitError::TooManyRws {
+                n_rws: 2,
+                max_rws: 1
+            })
+        );
+    }
+
+    #[test]
+    fn min_k() {
+        assert_eq!(
+            StateCircuit::<Fr, false, 2000, 100, 1023, 2000, 15>::min_k(),
+            12
+        );
+        assert_eq!(
+            StateCircuit::<Fr, false, 60000000, 2000, 1300, 60000000, 15>::min_k(),
+            26
+        );
+    }
+
+    fn tx_log_op(
+        rw_counter: usize,
+        log_id: usize,
+        field: TxLogField,
+        index: usize,
+        value: Word,
+    ) -> Operation<TxLogOp> {
+        Operation::new(
+            RWCounter::from(rw_counter),
+            RW::WRITE,
+            TxLogOp {
+                tx_id: 1,
+                log_id,
+                field,
+                index,
+                value,
+            },
+        )
+    }
+
+    fn tx_receipt_ops(
+        rw_counter: usize,
+        tx_id: usize,
+        status: u64,
+        cumulative_gas_used: u64,
+        log_length: u64,
+    ) -> Vec<Operation<TxReceiptOp>> {
+        [
+            (TxReceiptField::PostStateOrStatus, status),
+            (TxReceiptField::CumulativeGasUsed, cumulative_gas_used),
+            (TxReceiptField::LogLength, log_length),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(idx, (field, value))| {
+            Operation::new(
+                RWCounter::from(rw_counter + idx),
+                RW::WRITE,
+                TxReceiptOp {
+                    tx_id,
+                    field: *field,
+                    value: *value,
+                },
+            )
+        })
+        .collect()
+    }
+
+    fn verify_tx_ops(
+        tx_log: Vec<Operation<TxLogOp>>,
+        tx_receipt: Vec<Operation<TxReceiptOp>>,
+    ) -> Result<(), ()> {
+        let rw_map = RwMap::try_from(&OperationContainer {
+            tx_log,
+            tx_receipt,
+            ..Default::default()
+        })
+        .unwrap();
+        let circuit =
+            StateCircuit::<Fr, false, 2000, 100, 1023, 1000, 15>::new(Fr::rand(), &rw_map).unwrap();
+        let prover = MockProver::<Fr>::run(14, &circuit, circuit.instance()).unwrap();
+        prover.verify().map_err(|_| ())
+    }
+
+    #[test]
+    fn tx_log_and_receipt() {
+        let tx_log = vec![
+            tx_log_op(1, 1, TxLogField::Address, 0, Word::from(0x1234)),
+            tx_log_op(2, 1, TxLogField::Topic, 0, Word::from(0xcafe)),
+            tx_log_op(3, 1, TxLogField::Topic, 1, Word::MAX),
+            tx_log_op(4, 1, TxLogField::Data, 0, Word::from(0xff)),
+            tx_log_op(5, 1, TxLogField::Data, 1, Word::from(0x01)),
+            tx_log_op(6, 2, TxLogField::Address, 0, Word::from(0x5678)),
+        ];
+        let tx_receipt = [
+            tx_receipt_ops(7, 1, 1, 21000, 2),
+            tx_receipt_ops(10, 2, 0, 42000, 0),
+            tx_receipt_ops(13, 3, 1, 42000, 0),
+        ]
+        .concat();
+
+        assert_eq!(verify_tx_ops(tx_log, tx_receipt), Ok(()));
+    }
+
+    #[test]
+    fn tx_log_too_many_topics() {
+        // Fails because a log has at most 4 topics
+        let tx_log = (0..5)
+            .map(|index| tx_log_op(index + 1, 1, TxLogField::Topic, index, Word::from(index)))
+            .collect();
+
+        assert_eq!(verify_tx_ops(tx_log, vec![]), Err(()));
+    }
+
+    #[test]
+    fn tx_log_data_not_byte() {
+        // Fails because log data is not a byte
+        let tx_log = vec![tx_log_op(1, 1, TxLogField::Data, 0, Word::from(0x100))];
+
+        assert_eq!(verify_tx_ops(tx_log, vec![]), Err(()));
+    }
+
+    #[test]
+    fn tx_receipt_invalid_status() {
+        // Fails because status is not boolean
+        assert_eq!(
+            verify_tx_ops(vec![], tx_receipt_ops(1, 1, 2, 21000, 0)),
+            Err(())
+        );
+    }
+
+    #[test]
+    fn tx_receipt_decreasing_cumulative_gas_used() {
+        // Fails because cumulative gas used decreases
+        let tx_receipt = [
+            tx_receipt_ops(1, 1, 1, 42000, 0),
+            tx_receipt_ops(4, 2, 1, 21000, 0),
+        ]
+        .concat();
+
+        assert_eq!(verify_tx_ops(vec![], tx_receipt), Err(()));
+    }
+
     #[test]
     fn trace() {
         let bytecode = bytecode! {
@@ -1400,8 +3196,8 @@ mod tests {
             .handle_block(&block.eth_block, &block.geth_traces)
             .unwrap();
 
-        let stack_ops = builder.block.container.sorted_stack();
-        let memory_ops = builder.block.container.sorted_memory();
+        let stack_ops = builder.block.container.sorted_stack().unwrap();
+        let memory_ops = builder.block.container.sorted_memory().unwrap();
         let storage_ops = builder.block.container.sorted_storage();
 
         test_state_circuit_ok!(
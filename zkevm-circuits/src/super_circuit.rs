@@ -0,0 +1,634 @@
+//! The super circuit implementation.
+//!
+//! It instantiates the EVM, state, tx, sig, bytecode, copy, exp and PI
+//! circuits in a single constraint system, so that a block is proven with a
+//! single proving key.  The rw, tx, bytecode, copy, exp and block tables looked
+//! up by the EVM circuit are the ones assigned and constrained by the state,
+//! tx, bytecode, copy, exp and PI circuits instead of trusted witnesses:
+//! - The rw table is assigned by the state circuit, whose rows are the ones of
+//!   the table.
+//! - The tx table is assigned by the tx circuit, which looks up the signatures
+//!   of the transactions in the sig table of the sig circuit.
+//! - The bytecode table is assigned by the bytecode circuit, which proves the
+//!   code hashes and which bytes are opcodes.
+//! - The copy table is assigned by the copy circuit, which looks up the tx, rw
+//!   and bytecode tables shared with the EVM circuit.
+//! - The exp table is assigned by the exp circuit, which proves the
+//!   exponentiations of the EXP opcode.
+//! - The block table is assigned by the PI circuit, which commits it with the
+//!   block header fields and the txs in the digest of its public input.  The
+//!   txs it commits are looked up in the tx table of the tx circuit, which
+//!   follows them with its padding txs.
+//!
+//! The keccak table is shared by all the circuits looking up hashes, and is
+//! loaded once with the inputs of all of them.  The `keccak256` crate only
+//! has a circuit of the keccak-f permutation, without the padding and the
+//! absorption of the inputs into the rows of the keccak table, so its rows
+//! are still trusted.  So are the rows of the MPT table, which are mocked
+//! from the rw operations until there is an MPT circuit.  Both are out of the
+//! scope of the super circuit until these circuits exist.
+//!
+//! The capacities of the subcircuits are the `CircuitsParams` of the block,
+//! chosen when building its witness.  The state circuit is sized by the
+//! `MAX_RWS` parameter of the super circuit instead, which has to be their
+//! `max_rws`.
+
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    block_table::BlockTable,
+    bytecode_circuit::bytecode_unroller::{unroll_bytecodes, Config as BytecodeConfig},
+    bytecode_table::BytecodeTable,
+    copy_circuit::CopyCircuitConfig,
+    copy_table::CopyTable,
+    evm_circuit::{
+        chunk_states_from_instance,
+        table::{FixedTableTag, LookupTable},
+        witness::{carried_rws_fingerprint, keccak_table_rows, Block, MptUpdates, StreamedRws},
+        EvmCircuit,
+    },
+    exp_circuit::ExpCircuitConfig,
+    exp_table::ExpTable,
+    gadget::rlc::PowersOfRandomness,
+    keccak_table::{dedup_keccak_inputs, KeccakTable},
+    pi_circuit::{PiCircuitConfig, PiCircuitError, PublicData},
+    rw_table::RwTable,
+    sig_circuit::SigCircuitConfig,
+    sig_table::SigTable,
+    state_circuit::{state::Config as StateConfig, state_roots_instance, DEFAULT_MAX_DEGREE},
+    tx_circuit::{TxCircuit, TxCircuitConfig, POW_RAND_SIZE},
+    tx_table::TxTable,
+    util::power_of_randomness_from_instance,
+};
+use eth_types::{evm_types::Hardfork, Field};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+/// Maximum stack address of the state circuit, the EVM stack has 1024 slots.
+const STACK_ADDRESS_MAX: usize = 1023;
+
+// Rw counter the chunk of the block begins at, the rw operations before it are
+// carried to the chunk.
+fn rw_counter_begin<F: Field>(block: &Block<F>) -> usize {
+    block
+        .chunk
+        .as_ref()
+        .map_or(0, |chunk| chunk.begin.rw_counter)
+}
+
+/// Config of the super circuit.
+#[derive(Clone)]
+pub struct SuperCircuitConfig<F: Field, const MEMORY_ADDRESS_MAX: usize, const MAX_RWS: usize> {
+    tx_table: TxTable,
+    rw_table: RwTable,
+    bytecode_table: BytecodeTable,
+    block_table: BlockTable,
+    keccak_table: KeccakTable,
+    copy_table: CopyTable,
+    exp_table: ExpTable,
+    sig_table: SigTable,
+    evm_circuit: EvmCircuit<F>,
+    state_circuit: StateConfig<
+        F,
+        false,
+        MAX_RWS,
+        MEMORY_ADDRESS_MAX,
+        STACK_ADDRESS_MAX,
+        MAX_RWS,
+        DEFAULT_MAX_DEGREE,
+    >,
+    tx_circuit: TxCircuitConfig<F>,
+    sig_circuit: SigCircuitConfig<F>,
+    bytecode_circuit: BytecodeConfig<F>,
+    copy_circuit: CopyCircuitConfig<F>,
+    exp_circuit: ExpCircuitConfig<F>,
+    // Tx table of the PI circuit, whose rows are looked up in the tx table
+    pi_tx_table: TxTable,
+    pi_circuit: PiCircuitConfig<F>,
+}
+
+impl<F: Field, const MEMORY_ADDRESS_MAX: usize, const MAX_RWS: usize> Annotate
+    for SuperCircuitConfig<F, MEMORY_ADDRESS_MAX, MAX_RWS>
+{
+    // The tables are named first, after the super circuit, since they are
+    // shared by the circuits.
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_config(owner, "tx_table", &self.tx_table);
+        annotations.annotate_config(owner, "rw_table", &self.rw_table);
+        annotations.annotate_config(owner, "bytecode_table", &self.bytecode_table);
+        annotations.annotate_config(owner, "block_table", &self.block_table);
+        annotations.annotate_config(owner, "keccak_table", &self.keccak_table);
+        annotations.annotate_config(owner, "copy_table", &self.copy_table);
+        annotations.annotate_config(owner, "exp_table", &self.exp_table);
+        annotations.annotate_config(owner, "sig_table", &self.sig_table);
+        annotations.annotate_config(owner, "evm_circuit", &self.evm_circuit);
+        annotations.annotate_config(owner, "state_circuit", &self.state_circuit);
+        annotations.annotate_config(owner, "tx_circuit", &self.tx_circuit);
+        annotations.annotate_config(owner, "sig_circuit", &self.sig_circuit);
+        annotations.annotate_config(owner, "bytecode_circuit", &self.bytecode_circuit);
+        annotations.annotate_config(owner, "copy_circuit", &self.copy_circuit);
+        annotations.annotate_config(owner, "exp_circuit", &self.exp_circuit);
+        annotations.annotate_config(owner, "pi_tx_table", &self.pi_tx_table);
+        annotations.annotate_config(owner, "pi_circuit", &self.pi_circuit);
+    }
+}
+
+impl<F: Field, const MEMORY_ADDRESS_MAX: usize, const MAX_RWS: usize>
+    SuperCircuitConfig<F, MEMORY_ADDRESS_MAX, MAX_RWS>
+{
+    fn load_keccaks(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        inputs: &[Vec<u8>],
+        randomness: F,
+    ) -> Result<(), Error> {
+        let rows = keccak_table_rows(inputs, randomness);
+        layouter.assign_region(
+            || "keccak table",
+            |mut region| {
+                for (offset, row) in std::iter::once([F::zero(); 3])
+                    .chain(rows.iter().copied())
+                    .enumerate()
+                {
+                    self.keccak_table.assign(&mut region, offset, row)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Super circuit that proves a block with all the subcircuits sharing their
+/// tables.
+#[derive(Clone, Default, Debug)]
+pub struct SuperCircuit<F, const MEMORY_ADDRESS_MAX: usize, const MAX_RWS: usize> {
+    /// Block to prove
+    pub block: Block<F>,
+    /// Tags of the fixed table of the EVM circuit to load
+    pub fixed_table_tags: Vec<FixedTableTag>,
+    /// Number of rows of the circuit, `2^k`, the bytecode circuit pads its
+    /// bytecodes up to the last usable one
+    pub size: usize,
+    /// Header fields and withdrawals of the block committed by the PI
+    /// circuit, whose block context and txs are the ones of `block`
+    pub header: PublicData,
+    /// Maximum number of withdrawals of the PI circuit
+    pub max_withdrawals: usize,
+}
+
+impl<F: Field, const MEMORY_ADDRESS_MAX: usize, const MAX_RWS: usize>
+    SuperCircuit<F, MEMORY_ADDRESS_MAX, MAX_RWS>
+{
+    /// Returns the data of the block committed by the PI circuit, the block
+    /// context and the txs of `block` with the fields of `header`.
+    pub fn public_data(&self) -> PublicData {
+        PublicData {
+            block_context: self.block.context.clone(),
+            txs: self.block.txs.clone(),
+            ..self.header.clone()
+        }
+    }
+
+    /// Returns the values of the instance columns: the powers of the
+    /// randomness on all the rows but the 64 last ones of the blinding factors,
+    /// the chunk states of the EVM circuit on the same rows, the state roots
+    /// and the fingerprint of the carried rw operations of the state
+    /// circuit, and the public input of the PI circuit.
+    pub fn instance(&self) -> Result<Vec<Vec<F>>, PiCircuitError> {
+        let randomness = self.block.randomness;
+        let params = self.block.circuits_params;
+        let pi = self.public_data().instance(
+            randomness,
+            params.max_txs,
+            params.max_calldata,
+            self.max_withdrawals,
+        )?;
+        Ok(PowersOfRandomness::new(randomness)
+            .take(POW_RAND_SIZE)
+            .into_iter()
+            .map(|power| vec![power; self.size - 64])
+            .chain(EvmCircuit::chunk_states_instance(
+                &self.block,
+                self.size - 64,
+            ))
+            .chain(std::iter::once(state_roots_instance(
+                &MptUpdates::mock_from(&self.block.rws),
+                randomness,
+            )))
+            .chain(std::iter::once(vec![carried_rws_fingerprint(
+                &self.block.rws,
+                rw_counter_begin(&self.block),
+                randomness,
+            )]))
+            .chain(std::iter::once(pi))
+            .collect())
+    }
+}
+
+impl<F: Field, const MEMORY_ADDRESS_MAX: usize, const MAX_RWS: usize> Circuit<F>
+    for SuperCircuit<F, MEMORY_ADDRESS_MAX, MAX_RWS>
+{
+    type Config = SuperCircuitConfig<F, MEMORY_ADDRESS_MAX, MAX_RWS>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            block: Block {
+                circuits_params: self.block.circuits_params,
+                chain_config: self.block.chain_config,
+                ..Default::default()
+            },
+            fixed_table_tags: self.fixed_table_tags.clone(),
+            size: self.size,
+            max_withdrawals: self.max_withdrawals,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let tx_table = TxTable::construct(meta);
+        let rw_table = RwTable::construct(meta);
+        let bytecode_table = BytecodeTable::construct(meta);
+        let block_table = BlockTable::construct(meta);
+        let keccak_table = KeccakTable::construct(meta);
+        let copy_table = CopyTable::construct(meta);
+        let exp_table = ExpTable::construct(meta);
+        let sig_table = SigTable::construct(meta);
+
+        let power_of_randomness = power_of_randomness_from_instance::<_, POW_RAND_SIZE>(meta);
+        let power_of_randomness_31: [_; 31] =
+            array_init::array_init(|idx| power_of_randomness[idx].clone());
+        let chunk_states = chunk_states_from_instance(meta);
+        // The rw operations of the state circuit are the ones of the chunk the
+        // EVM circuit begins with.
+        let rw_counter_begin = chunk_states[0][0].clone();
+
+        let evm_circuit = EvmCircuit::configure(
+            meta,
+            power_of_randomness_31.clone(),
+            chunk_states,
+            Hardfork::default(),
+            tx_table,
+            rw_table,
+            bytecode_table,
+            block_table,
+            keccak_table,
+            copy_table,
+            exp_table,
+        );
+        let state_circuit = StateConfig::configure(
+            meta,
+            rw_table,
+            power_of_randomness_31.clone(),
+            rw_counter_begin,
+        );
+        let tx_circuit = TxCircuitConfig::configure(
+            meta,
+            power_of_randomness[0].clone(),
+            tx_table,
+            keccak_table,
+            sig_table,
+        );
+        let sig_circuit =
+            SigCircuitConfig::configure(meta, power_of_randomness.clone(), sig_table, keccak_table);
+        let copy_circuit = CopyCircuitConfig::configure(
+            meta,
+            power_of_randomness[0].clone(),
+            copy_table,
+            tx_table,
+            rw_table,
+            bytecode_table,
+        );
+        let exp_circuit =
+            ExpCircuitConfig::configure(meta, power_of_randomness_31.clone(), exp_table);
+        // The PI circuit assigns the block table, and commits txs which are
+        // the ones of the tx table.  Its instance column is the last one.
+        let pi_tx_table = TxTable::construct(meta);
+        let pi_circuit = PiCircuitConfig::configure(
+            meta,
+            power_of_randomness_31,
+            block_table,
+            pi_tx_table,
+            keccak_table,
+        );
+        meta.lookup_any("PI tx in tx table", |meta| {
+            pi_tx_table
+                .table_exprs(meta)
+                .into_iter()
+                .zip(tx_table.table_exprs(meta))
+                .collect()
+        });
+        // The bytecode circuit is configured last, because it assigns up to
+        // the last usable row, which depends on the whole constraint system.
+        let bytecode_circuit = BytecodeConfig::configure(
+            meta,
+            power_of_randomness[0].clone(),
+            bytecode_table,
+            keccak_table,
+        );
+
+        Self::Config {
+            tx_table,
+            rw_table,
+            bytecode_table,
+            block_table,
+            keccak_table,
+            copy_table,
+            exp_table,
+            sig_table,
+            evm_circuit,
+            state_circuit,
+            tx_circuit,
+            sig_circuit,
+            bytecode_circuit,
+            copy_circuit,
+            exp_circuit,
+            pi_tx_table,
+            pi_circuit,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let block = &self.block;
+        let params = block.circuits_params;
+        // One padding row of the bytecode circuit is kept for the all-zero
+        // row of the bytecode table.
+        if params.max_rws != MAX_RWS
+            || params.max_bytecode > config.bytecode_circuit.max_bytecode_size(self.size) - 1
+        {
+            return Err(Error::Synthesis);
+        }
+
+        let tx_circuit = TxCircuit::new(
+            &block.chain_config,
+            block.randomness,
+            block.txs.clone(),
+            params.max_txs,
+            params.max_calldata,
+        );
+        let sig_circuit = tx_circuit.sig_circuit()?;
+        let bytecodes = unroll_bytecodes(&block.bytecodes, block.randomness, params.max_bytecode)
+            .map_err(|_| Error::Synthesis)?;
+        let public_data = self.public_data();
+        let pi_digest_input = public_data
+            .digest_input(
+                block.randomness,
+                params.max_txs,
+                params.max_calldata,
+                self.max_withdrawals,
+            )
+            .map_err(|_| Error::Synthesis)?;
+
+        let keccak_inputs = dedup_keccak_inputs(
+            block
+                .keccak_inputs
+                .iter()
+                .cloned()
+                .chain(bytecodes.iter().map(|bytecode| bytecode.bytes.clone()))
+                .chain(sig_circuit.keccak_inputs())
+                .chain(tx_circuit.keccak_inputs())
+                .chain(std::iter::once(pi_digest_input)),
+        );
+        let updates = MptUpdates::mock_from(&block.rws);
+        // The keccak and the MPT tables have an all-zero row before their
+        // rows
+        if keccak_inputs.len() + 1 > params.max_keccak_rows
+            || updates.0.len() + 1 > params.max_mpt_rows
+        {
+            return Err(Error::Synthesis);
+        }
+        config.load_keccaks(&mut layouter, &keccak_inputs, block.randomness)?;
+
+        config
+            .evm_circuit
+            .load_fixed_table(&mut layouter, self.fixed_table_tags.clone())?;
+        config
+            .evm_circuit
+            .assign_block_exact(&mut layouter, block)?;
+
+        config.state_circuit.load(&mut layouter)?;
+        config
+            .state_circuit
+            .load_mpt_updates(&mut layouter, block.randomness, &updates)?;
+        config.state_circuit.assign(
+            layouter.namespace(|| "state circuit"),
+            block.randomness,
+            &block.rws,
+            &StreamedRws::default(),
+            &updates,
+            rw_counter_begin(block),
+        )?;
+
+        sig_circuit.assign(&config.sig_circuit, &mut layouter)?;
+        tx_circuit.assign(&config.tx_circuit, &mut layouter)?;
+
+        config.bytecode_circuit.load(&mut layouter)?;
+        config.bytecode_circuit.assign(
+            layouter.namespace(|| "bytecode circuit"),
+            self.size,
+            &bytecodes,
+            block.randomness,
+        )?;
+
+        config.copy_circuit.load(&mut layouter)?;
+        config.copy_circuit.assign_events(
+            &mut layouter,
+            &block.copy_events,
+            block.randomness,
+            params.max_copy_rows,
+        )?;
+
+        config.exp_circuit.load(&mut layouter)?;
+        config.exp_circuit.assign_events(
+            &mut layouter,
+            &block.exp_events,
+            block.randomness,
+            params.max_exp_steps,
+        )?;
+
+        config.pi_circuit.load(&mut layouter)?;
+        config.pi_circuit.assign(
+            &mut layouter,
+            &public_data,
+            block.randomness,
+            params.max_txs,
+            params.max_calldata,
+            self.max_withdrawals,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SuperCircuit;
+    use crate::{
+        evm_circuit::witness::{block_convert, Block, Transaction},
+        pi_circuit::PublicData,
+        test_util::{get_fixed_table, FixedTableConfig},
+        tx_circuit::tests::sign,
+    };
+    use bus_mapping::{circuit_input_builder::CircuitsParams, mock::BlockData};
+    use eth_types::{bytecode, geth_types::Account, Word};
+    use halo2_proofs::{
+        dev::{MockProver, VerifyFailure},
+        plonk::Error,
+    };
+    use num::BigUint;
+    use pairing::bn256::Fr;
+
+    const K: u32 = 16;
+    const MEMORY_ADDRESS_MAX: usize = 100;
+    const MAX_RWS: usize = 200;
+
+    const CIRCUITS_PARAMS: CircuitsParams = CircuitsParams {
+        max_txs: 1,
+        max_calldata: 32,
+        max_rws: MAX_RWS,
+        max_bytecode: 512,
+        max_copy_rows: 100,
+        max_keccak_rows: 100,
+        max_exp_steps: 1,
+        max_mpt_rows: 100,
+    };
+
+    type TestSuperCircuit = SuperCircuit<Fr, MEMORY_ADDRESS_MAX, MAX_RWS>;
+
+    fn circuit(block: Block<Fr>) -> (TestSuperCircuit, Vec<Vec<Fr>>) {
+        let circuit = TestSuperCircuit {
+            block,
+            fixed_table_tags: get_fixed_table(FixedTableConfig::Incomplete),
+            size: 1 << K,
+            header: PublicData {
+                block_hash: Word::from(0xb1),
+                parent_hash: Word::from(0xb0),
+                ..Default::default()
+            },
+            max_withdrawals: 1,
+        };
+        let instance = circuit.instance().unwrap();
+        (circuit, instance)
+    }
+
+    fn run(block: Block<Fr>) -> Result<MockProver<Fr>, Error> {
+        let (circuit, instance) = circuit(block);
+        MockProver::<Fr>::run(K, &circuit, instance)
+    }
+
+    fn verify(block: Block<Fr>) -> Result<(), Vec<VerifyFailure>> {
+        run(block).unwrap().verify()
+    }
+
+    // Returns the block of a transaction from the signer of `sk` calling a
+    // contract with `code`, and its signature.
+    fn block(sk: &BigUint, code: eth_types::Bytecode) -> Block<Fr> {
+        let (_, from) = sign(Transaction::default(), sk, None);
+        let account = Account {
+            address: mock::new_tracer_tx().to.unwrap(),
+            code: code.to_vec().into(),
+            ..Default::default()
+        };
+        let mut eth_tx = mock::new_tx(&mock::new_block());
+        eth_tx.from = from;
+
+        let block_data = BlockData::new_from_geth_data_with_params(
+            mock::new(vec![account], vec![eth_tx]).unwrap(),
+            CIRCUITS_PARAMS,
+        );
+        let mut builder = block_data.new_circuit_input_builder();
+        builder
+            .handle_block(&block_data.eth_block, &block_data.geth_traces)
+            .unwrap();
+        let mut block = block_convert(&builder.block, &builder.code_db);
+
+        let chain_id = block.context.chain_id;
+        block.txs = block
+            .txs
+            .into_iter()
+            .map(|tx| sign(tx, sk, Some(chain_id)).0)
+            .collect();
+        block
+    }
+
+    #[test]
+    fn super_circuit_valid() {
+        let code = bytecode! {
+            PUSH1(0x20)
+            PUSH1(0x40)
+            ADD
+            STOP
+        };
+        let block = block(&BigUint::from(0x1234u64), code);
+        assert_eq!(verify(block), Ok(()));
+    }
+
+    // The EVM circuit is only proven with the real prover within the super
+    // circuit, which assigns the tables it looks up.
+    #[cfg(feature = "real-prover")]
+    #[test]
+    fn super_circuit_valid_real_prover() {
+        let code = bytecode! {
+            PUSH1(0x20)
+            PUSH1(0x40)
+            ADD
+            STOP
+        };
+        let (circuit, power_of_randomness) = circuit(block(&BigUint::from(0x1234u64), code));
+        crate::test_util::run_real_prover(K, &circuit, power_of_randomness).unwrap();
+    }
+
+    #[test]
+    fn super_circuit_invalid_bytecode() {
+        let code = bytecode! {
+            PUSH1(0x20)
+            STOP
+        };
+        let mut block = block(&BigUint::from(0x1234u64), code);
+        // The bytecode table is assigned by the bytecode circuit, so the bytes
+        // executed by the EVM circuit can't differ from the hashed ones
+        block.bytecodes[0].bytes[1] = 0x21;
+        assert!(verify(block).is_err());
+    }
+
+    #[test]
+    fn super_circuit_invalid_signature() {
+        let code = bytecode! {
+            STOP
+        };
+        let mut block = block(&BigUint::from(0x1234u64), code);
+        // The tx table is assigned by the tx circuit, so the caller executed by
+        // the EVM circuit has to be the signer of the transaction
+        block.txs[0].s = block.txs[0].s + Word::one();
+        assert!(verify(block).is_err());
+    }
+
+    #[test]
+    fn super_circuit_invalid_block_table() {
+        let code = bytecode! {
+            STOP
+        };
+        let block = block(&BigUint::from(0x1234u64), code);
+        let (_, instance) = circuit(block.clone());
+        // The block table is assigned by the PI circuit, so it can't differ
+        // from the block committed by the public input
+        let mut block = block;
+        block.context.timestamp = block.context.timestamp + Word::one();
+        let (circuit, _) = circuit(block);
+        let prover = MockProver::<Fr>::run(K, &circuit, instance).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn super_circuit_capacity() {
+        let code = bytecode! {
+            STOP
+        };
+        let mut block = block(&BigUint::from(0x1234u64), code);
+        block.circuits_params.max_keccak_rows = 1;
+        assert!(matches!(run(block), Err(Error::Synthesis)));
+    }
+}
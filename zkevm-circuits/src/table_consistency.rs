@@ -0,0 +1,228 @@
+//! Tests of the consistency of the tables shared by two circuits, one of them
+//! assigning the table and the other one looking it up.
+//!
+//! The rows exported by a circuit and the rows looked up by its partner are
+//! built from the same witness block with the same randomness, by the code of
+//! each circuit, and every looked up row has to be an exported one.  This
+//! catches a drift between the encodings of a table in two circuits, like a
+//! byte order or a tag, which the tests of a single circuit miss as they
+//! assign the tables they look up themselves.
+
+use crate::{
+    bytecode_circuit::bytecode_unroller::unroll_bytecodes,
+    evm_circuit::witness::{block_convert, keccak_table_assignments, Block, Bytecode, RwRow},
+    state_circuit::state::RW_TABLE_TAGS,
+};
+use bus_mapping::mock::BlockData;
+use eth_types::{evm_types::OpcodeId, Field, Word};
+use pairing::bn256::Fr;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::collections::HashSet;
+
+/// Rows of a table shared by two circuits.
+struct SharedTable<F> {
+    /// Name of the table and of its circuits
+    name: &'static str,
+    /// Rows assigned by the circuit exporting the table
+    exported: Vec<Vec<F>>,
+    /// Rows looked up by the circuit consuming the table
+    looked_up: Vec<Vec<F>>,
+}
+
+impl<F: Field> SharedTable<F> {
+    /// Returns the indices of the looked up rows which aren't exported.
+    fn missing_rows(&self) -> Vec<usize> {
+        let repr = |row: &Vec<F>| {
+            row.iter()
+                .flat_map(|value| value.to_repr().as_ref().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let exported = self.exported.iter().map(repr).collect::<HashSet<_>>();
+        self.looked_up
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| !exported.contains(&repr(row)))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    fn assert_consistent(&self) {
+        let missing = self.missing_rows();
+        assert!(
+            missing.is_empty(),
+            "{}: {} of the {} looked up rows aren't exported, the first one is {:?}",
+            self.name,
+            missing.len(),
+            self.looked_up.len(),
+            self.looked_up[missing[0]]
+        );
+    }
+}
+
+fn rw_row<F: Field>(row: RwRow<F>) -> Vec<F> {
+    vec![
+        row.rw_counter,
+        row.is_write,
+        row.tag,
+        row.key1,
+        row.key2,
+        row.key3,
+        row.key4,
+        row.value,
+        row.value_prev,
+        row.aux1,
+        row.aux2,
+    ]
+}
+
+/// The rw table assigned by the state circuit and looked up by the steps of
+/// the EVM circuit.
+fn evm_state_rw_table<F: Field>(block: &Block<F>) -> SharedTable<F> {
+    let exported = RW_TABLE_TAGS
+        .iter()
+        .flat_map(|tag| block.rws.0.get(tag).into_iter().flatten())
+        .map(|rw| rw_row(rw.table_assignment(block.randomness)))
+        .collect();
+    let looked_up = block
+        .txs
+        .iter()
+        .flat_map(|tx| tx.steps.iter())
+        .flat_map(|step| step.rw_indices.iter())
+        .map(|idx| rw_row(block.rws[*idx].table_assignment(block.randomness)))
+        .collect();
+    SharedTable {
+        name: "rw table of the state and EVM circuits",
+        exported,
+        looked_up,
+    }
+}
+
+/// The bytecode table assigned by the bytecode circuit and looked up by the
+/// EVM circuit.
+fn evm_bytecode_table<F: Field>(block: &Block<F>) -> SharedTable<F> {
+    let bytecodes = unroll_bytecodes(&block.bytecodes, block.randomness, usize::MAX).unwrap();
+    SharedTable {
+        name: "bytecode table of the bytecode and EVM circuits",
+        exported: bytecodes
+            .iter()
+            .flat_map(|bytecode| bytecode.table_rows())
+            .map(|row| row.to_vec())
+            .collect(),
+        looked_up: block
+            .bytecodes
+            .iter()
+            .flat_map(|bytecode| bytecode.table_assignments(block.randomness))
+            .map(|row| row.to_vec())
+            .collect(),
+    }
+}
+
+/// The keccak table assigned with the keccak inputs of the block, as in the
+/// super circuit, and looked up by the bytecode circuit.
+fn bytecode_keccak_table<F: Field>(block: &Block<F>) -> SharedTable<F> {
+    let bytecodes = unroll_bytecodes(&block.bytecodes, block.randomness, usize::MAX).unwrap();
+    SharedTable {
+        name: "keccak table of the bytecode circuit",
+        exported: block
+            .keccak_inputs
+            .iter()
+            .map(|input| keccak_table_assignments(input, block.randomness).to_vec())
+            .collect(),
+        looked_up: bytecodes
+            .iter()
+            .filter_map(|bytecode| bytecode.keccak_lookup(block.randomness))
+            .map(|row| row.to_vec())
+            .collect(),
+    }
+}
+
+fn rng(seed: u64) -> XorShiftRng {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    bytes[8..].copy_from_slice(&(!seed).to_le_bytes());
+    XorShiftRng::from_seed(bytes)
+}
+
+// Returns the witness block of a random program accessing the stack, the
+// memory and the storage, with a random randomness.
+fn random_block(rng: &mut XorShiftRng) -> Block<Fr> {
+    let mut code = eth_types::Bytecode::default();
+    for _ in 0..rng.gen_range(1..8) {
+        let value = Word::from(rng.gen::<u128>());
+        let offset = Word::from(rng.gen_range(0..256u64));
+        let key = Word::from(rng.gen_range(0..4u64));
+        match rng.gen_range(0..3) {
+            0 => {
+                code.push(32, value)
+                    .push(1, offset)
+                    .write_op(OpcodeId::MSTORE);
+                code.push(1, offset)
+                    .write_op(OpcodeId::MLOAD)
+                    .write_op(OpcodeId::POP);
+            }
+            1 => {
+                code.push(32, value).push(1, key).write_op(OpcodeId::SSTORE);
+                code.push(1, key)
+                    .write_op(OpcodeId::SLOAD)
+                    .write_op(OpcodeId::POP);
+            }
+            _ => {
+                code.push(32, value)
+                    .push(1, offset)
+                    .write_op(OpcodeId::ADD)
+                    .write_op(OpcodeId::POP);
+            }
+        }
+    }
+    code.write_op(OpcodeId::STOP);
+
+    let block_data = BlockData::new_from_geth_data(mock::new_single_tx_trace_code(&code).unwrap());
+    let mut builder = block_data.new_circuit_input_builder();
+    builder
+        .handle_block(&block_data.eth_block, &block_data.geth_traces)
+        .unwrap();
+    let mut block = block_convert(&builder.block, &builder.code_db);
+    block.randomness = Fr::from(rng.gen::<u64>());
+    block
+}
+
+#[test]
+fn tables_of_random_programs_are_consistent() {
+    for seed in 0..8 {
+        let block = random_block(&mut rng(seed));
+        evm_state_rw_table(&block).assert_consistent();
+        evm_bytecode_table(&block).assert_consistent();
+        bytecode_keccak_table(&block).assert_consistent();
+    }
+}
+
+#[test]
+fn bytecode_tables_of_random_bytecodes_are_consistent() {
+    let mut rng = rng(0);
+    for _ in 0..32 {
+        // Random bytes, whose last push may be truncated.
+        let len = rng.gen_range(1..100);
+        let bytes = (0..len).map(|_| rng.gen()).collect::<Vec<u8>>();
+        let block = Block {
+            randomness: Fr::from(rng.gen::<u64>()),
+            keccak_inputs: vec![bytes.clone()],
+            bytecodes: vec![Bytecode::new(bytes)],
+            ..Default::default()
+        };
+        evm_bytecode_table(&block).assert_consistent();
+        bytecode_keccak_table(&block).assert_consistent();
+    }
+}
+
+#[test]
+fn tables_with_other_randomness_are_inconsistent() {
+    let block = random_block(&mut rng(0));
+    let mut table = evm_bytecode_table(&block);
+    let other = Block {
+        randomness: block.randomness + Fr::from(1),
+        ..block.clone()
+    };
+    table.looked_up = evm_bytecode_table(&other).looked_up;
+    assert_eq!(table.missing_rows().len(), table.looked_up.len());
+}
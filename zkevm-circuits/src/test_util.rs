@@ -1,10 +1,22 @@
 use crate::{
     evm_circuit::{table::FixedTableTag, witness::Block},
-    state_circuit::StateCircuit,
+    state_circuit::{StateCircuit, DEFAULT_MAX_DEGREE},
 };
 use eth_types::evm_types::Gas;
 use halo2_proofs::dev::{MockProver, VerifyFailure};
+#[cfg(feature = "real-prover")]
+use halo2_proofs::{
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, SingleVerifier},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
 use pairing::bn256::Fr;
+#[cfg(feature = "real-prover")]
+use pairing::bn256::{Bn256, G1Affine};
+#[cfg(feature = "real-prover")]
+use rand::SeedableRng;
+#[cfg(feature = "real-prover")]
+use rand_xorshift::XorShiftRng;
 
 pub enum FixedTableConfig {
     Incomplete,
@@ -85,11 +97,66 @@ pub fn test_circuits_using_witness_block(
     // public input, since randomness in state circuit and evm
     // circuit must be same
     if config.enable_state_circuit_test {
-        let state_circuit =
-            StateCircuit::<Fr, true, 2000, 100, 1023, 2000>::new(block.randomness, &block.rws);
-        let prover = MockProver::<Fr>::run(12, &state_circuit, vec![]).unwrap();
+        type TestStateCircuit = StateCircuit<Fr, true, 2000, 100, 1023, 2000, DEFAULT_MAX_DEGREE>;
+        let state_circuit = TestStateCircuit::new(block.randomness, &block.rws)
+            .expect("too many rw operations for the state circuit")
+            .with_chunk(block.chunk.as_ref());
+        let prover = MockProver::<Fr>::run(
+            TestStateCircuit::min_k(),
+            &state_circuit,
+            state_circuit.instance(),
+        )
+        .unwrap();
         prover.verify()?;
     }
 
     Ok(())
 }
+
+/// Generates the keys of `circuit` of degree `k` from its shape alone, then
+/// proves and verifies it with the values `instances` of its instance
+/// columns, with the real prover instead of the `MockProver`.
+///
+/// The keys are generated from `circuit.without_witnesses()`, as the ones of
+/// a prover are, so that an assignment reading a witness value during the
+/// keygen fails here.  The blinding rows and the layout of the instance
+/// columns are only checked by the real prover too.
+#[cfg(feature = "real-prover")]
+pub fn run_real_prover<C: Circuit<Fr>>(
+    k: u32,
+    circuit: &C,
+    instances: Vec<Vec<Fr>>,
+) -> Result<(), Error> {
+    let params = Params::<G1Affine>::unsafe_setup::<Bn256>(k);
+    let vk = keygen_vk(&params, &circuit.without_witnesses())?;
+    let pk = keygen_pk(&params, vk, &circuit.without_witnesses())?;
+
+    let instances = instances.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    let rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof(
+        &params,
+        &pk,
+        std::slice::from_ref(circuit),
+        &[&instances[..]],
+        rng,
+        &mut transcript,
+    )?;
+    let proof = transcript.finalize();
+
+    let n_instance_rows = instances.iter().map(|column| column.len()).max();
+    let verifier_params = params.verifier(n_instance_rows.unwrap_or_default())?;
+    let strategy = SingleVerifier::new(&verifier_params);
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    verify_proof(
+        &verifier_params,
+        pk.get_vk(),
+        strategy,
+        &[&instances[..]],
+        &mut transcript,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,1115 @@
+//! The transaction circuit implementation.
+//!
+//! It assigns the tx table looked up by the EVM circuit and the PI circuit,
+//! whose call data length and gas cost are constrained to match its call data
+//! bytes, and looks up the signature of every transaction in the sig table, so
+//! that its caller address is the address of the signer instead of a free
+//! witness.  The signatures are verified by the sig circuit, which owns the sig
+//! table.  The signed message hash is the keccak hash of the RLP encoding of
+//! the transaction, prefixed by its type if it's a typed one, which is decoded
+//! into the values of the tx table.
+//!
+//! The circuit has a fixed capacity of `max_txs` transactions and
+//! `max_calldata` call data bytes, so that its proving key doesn't depend on
+//! the block.  The transactions of the block are followed by padding
+//! transactions up to `max_txs`, which are deterministic empty calls signed
+//! with the secret key 1, and the tx table and the RLP encodings are followed
+//! by padding rows up to the capacity.
+
+mod rlp;
+mod sign_verify;
+
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    evm_circuit::{
+        table::{LookupTable, TxContextFieldTag},
+        util::constraint_builder::BaseConstraintBuilder,
+        witness::{keccak_table_rows, tx_sign_keccak_input, Transaction},
+    },
+    gadget::{
+        ecc::Curve,
+        non_native::{invert, Modulus},
+        range_check::RangeTable,
+    },
+    keccak_table::{dedup_keccak_inputs, KeccakTable},
+    sig_circuit::{self, recover_pk, SigCircuit, SigCircuitConfig, SignData},
+    sig_table::SigTable,
+    tx_table::TxTable,
+    util::{power_of_randomness_from_instance, Expr},
+};
+use eth_types::{evm_types::GasCost, keccak256, Address, ChainConfig, Field, ToBigEndian, Word};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use num::{BigUint, Integer};
+use rlp::{RlpConfig, MAX_RLP_TX_BYTES};
+use sign_verify::SignVerifyConfig;
+use std::fmt;
+
+pub use eth_types::Eip155Rule;
+
+const MAX_DEGREE: usize = 6;
+
+/// Number of powers of the randomness used by the tx circuit together with the
+/// sig circuit verifying its signatures, which are the ones of the sig circuit.
+pub const POW_RAND_SIZE: usize = sig_circuit::POW_RAND_SIZE;
+
+/// Config of the tx circuit.
+#[derive(Clone, Debug)]
+pub struct TxCircuitConfig<F> {
+    q_enable: Column<Fixed>,
+    // Enables the first row of the first transaction
+    q_first: Column<Fixed>,
+    tx_table: TxTable,
+    // Inverses of the tag minus CallData, of the tag minus CallDataGasCost, of
+    // the tag, and of the value, which witness whether they are 0
+    call_data_inv: Column<Advice>,
+    gas_cost_inv: Column<Advice>,
+    tag_inv: Column<Advice>,
+    value_inv: Column<Advice>,
+    // Gas cost of the call data bytes so far
+    call_data_gas_cost_acc: Column<Advice>,
+    keccak_table: KeccakTable,
+    u8_table: RangeTable<8>,
+    rlp: RlpConfig<F>,
+    sign_verify: SignVerifyConfig<F>,
+}
+
+impl<F> Annotate for TxCircuitConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate_config(owner, "tx_table", &self.tx_table);
+        annotations.annotate(owner, "q_enable", self.q_enable);
+        annotations.annotate(owner, "q_first", self.q_first);
+        annotations.annotate(owner, "call_data_inv", self.call_data_inv);
+        annotations.annotate(owner, "gas_cost_inv", self.gas_cost_inv);
+        annotations.annotate(owner, "tag_inv", self.tag_inv);
+        annotations.annotate(owner, "value_inv", self.value_inv);
+        annotations.annotate(owner, "call_data_gas_cost_acc", self.call_data_gas_cost_acc);
+        annotations.annotate_config(owner, "keccak_table", &self.keccak_table);
+        annotations.annotate_config(owner, "u8_table", &self.u8_table);
+        annotations.annotate_config(owner, "rlp", &self.rlp);
+        annotations.annotate_config(owner, "sign_verify", &self.sign_verify);
+    }
+}
+
+impl<F: Field> TxCircuitConfig<F> {
+    /// Configure the tx circuit, which assigns the rows of `tx_table`, looks
+    /// up the hashes of the signed messages in `keccak_table`, and the
+    /// signatures in `sig_table`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        randomness: Expression<F>,
+        tx_table: TxTable,
+        keccak_table: KeccakTable,
+        sig_table: SigTable,
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_first = meta.fixed_column();
+        let [call_data_inv, gas_cost_inv, tag_inv, value_inv, call_data_gas_cost_acc] =
+            [(); 5].map(|_| meta.advice_column());
+        let u8_table = RangeTable::construct(meta);
+
+        let rlp = RlpConfig::configure(meta, randomness, u8_table, tx_table, keccak_table);
+        let sign_verify = SignVerifyConfig::configure(meta, tx_table, sig_table, rlp.clone());
+
+        // Returns whether the tag is CallData, whether the tag is
+        // CallDataGasCost, whether the tag is 0 (padding), and whether the
+        // value is 0, with the expressions that are 0 when their inverses are
+        // witnessed correctly.
+        let flags = |meta: &mut VirtualCells<F>, rotation| {
+            let tag = meta.query_advice(tx_table.tag, rotation);
+            let value = meta.query_advice(tx_table.value, rotation);
+            [
+                (
+                    tag.clone() - TxContextFieldTag::CallData.expr(),
+                    call_data_inv,
+                ),
+                (
+                    tag.clone() - TxContextFieldTag::CallDataGasCost.expr(),
+                    gas_cost_inv,
+                ),
+                (tag, tag_inv),
+                (value, value_inv),
+            ]
+            .map(|(value, inv)| {
+                let is_zero = 1.expr() - value.clone() * meta.query_advice(inv, rotation);
+                (is_zero.clone(), value * is_zero)
+            })
+        };
+
+        meta.create_gate("Tx table", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_next = meta.query_fixed(q_enable, Rotation::next());
+            let [cur, next] = [Rotation::cur(), Rotation::next()];
+            let [tx_id, tag, index, value, gas_cost_acc] = [
+                tx_table.tx_id,
+                tx_table.tag,
+                tx_table.index,
+                tx_table.value,
+                call_data_gas_cost_acc,
+            ]
+            .map(|column| [cur, next].map(|rotation| meta.query_advice(column, rotation)));
+            let value_prev = meta.query_advice(tx_table.value, Rotation::prev());
+            let flags_cur = flags(meta, cur);
+            for (_, inverse_check) in flags_cur.iter() {
+                cb.require_zero("inverse is witnessed", inverse_check.clone());
+            }
+            let [is_call_data, is_gas_cost, is_padding, _] = flags_cur.map(|(is_zero, _)| is_zero);
+            let [is_call_data_next, _, is_padding_next, is_zero_byte_next] =
+                flags(meta, next).map(|(is_zero, _)| is_zero);
+            let is_header = 1.expr() - is_call_data.clone() - is_gas_cost.clone();
+
+            cb.require_zero(
+                "only call data has an index",
+                (1.expr() - is_call_data.clone()) * index[0].clone(),
+            );
+            cb.condition(is_padding.clone(), |cb| {
+                cb.require_zero("padding has no tx_id", tx_id[0].clone());
+                cb.require_zero("padding has no value", value[0].clone());
+            });
+            cb.condition(q_first * (1.expr() - is_padding.clone()), |cb| {
+                cb.require_equal(
+                    "first transaction starts with nonce",
+                    tag[0].clone(),
+                    TxContextFieldTag::Nonce.expr(),
+                );
+                cb.require_equal("first transaction has tx_id 1", tx_id[0].clone(), 1.expr());
+            });
+            cb.require_zero(
+                "table ends with padding",
+                (1.expr() - q_next.clone()) * (1.expr() - is_padding.clone()),
+            );
+
+            // Fields of a transaction are in the order of their tags, from
+            // Nonce to CallDataGasCost, followed by the call data bytes
+            cb.condition(q_next.clone() * (is_header - is_padding.clone()), |cb| {
+                cb.require_equal("next field", tag[1].clone(), tag[0].clone() + 1.expr());
+                cb.require_equal("same transaction", tx_id[1].clone(), tx_id[0].clone());
+            });
+            cb.condition(
+                q_next.clone()
+                    * (is_call_data.clone() + is_gas_cost.clone())
+                    * (1.expr() - is_call_data_next.clone() - is_padding_next.clone()),
+                |cb| {
+                    cb.require_equal(
+                        "next transaction starts with nonce",
+                        tag[1].clone(),
+                        TxContextFieldTag::Nonce.expr(),
+                    );
+                    cb.require_equal(
+                        "next transaction has the next tx_id",
+                        tx_id[1].clone(),
+                        tx_id[0].clone() + 1.expr(),
+                    );
+                },
+            );
+            cb.condition(q_next.clone() * is_padding, |cb| {
+                cb.require_equal("padding is followed by padding", is_padding_next, 1.expr());
+            });
+
+            // Call data bytes
+            cb.condition(q_next.clone() * is_call_data_next.clone(), |cb| {
+                cb.require_zero(
+                    "call data follows call data gas cost or call data",
+                    1.expr() - is_call_data.clone() - is_gas_cost.clone(),
+                );
+                cb.require_equal("same transaction", tx_id[1].clone(), tx_id[0].clone());
+                cb.require_equal(
+                    "call data index starts at 0 and increases by 1",
+                    index[1].clone(),
+                    is_call_data.clone() * (index[0].clone() + 1.expr()),
+                );
+                cb.require_equal(
+                    "call data gas cost is 4 per zero byte and 16 per non-zero byte",
+                    gas_cost_acc[1].clone(),
+                    is_call_data.clone() * gas_cost_acc[0].clone() + 16.expr()
+                        - 12.expr() * is_zero_byte_next,
+                );
+            });
+            cb.condition(
+                q_next * is_gas_cost * (1.expr() - is_call_data_next.clone()),
+                |cb| {
+                    cb.require_zero("empty call data has no gas cost", value[0].clone());
+                    cb.require_zero("empty call data has length 0", value_prev);
+                },
+            );
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        // The length and gas cost of non-empty call data are the ones
+        // accumulated at its last byte
+        for (name, tag, value, offset) in [
+            (
+                "Call data length in tx table",
+                TxContextFieldTag::CallDataLength,
+                tx_table.index,
+                1u64,
+            ),
+            (
+                "Call data gas cost in tx table",
+                TxContextFieldTag::CallDataGasCost,
+                call_data_gas_cost_acc,
+                0u64,
+            ),
+        ] {
+            meta.lookup_any(name, |meta| {
+                let [(is_call_data, _), ..] = flags(meta, Rotation::cur());
+                let [(is_call_data_next, _), ..] = flags(meta, Rotation::next());
+                let condition = meta.query_fixed(q_enable, Rotation::cur())
+                    * is_call_data
+                    * (1.expr() - is_call_data_next);
+
+                [
+                    meta.query_advice(tx_table.tx_id, Rotation::cur()),
+                    tag.expr(),
+                    0.expr(),
+                    meta.query_advice(value, Rotation::cur()) + offset.expr(),
+                ]
+                .iter()
+                .zip(tx_table.table_exprs(meta))
+                .map(|(input, table)| (condition.clone() * input.clone(), table))
+                .collect()
+            });
+        }
+
+        Self {
+            q_enable,
+            q_first,
+            tx_table,
+            call_data_inv,
+            gas_cost_inv,
+            tag_inv,
+            value_inv,
+            call_data_gas_cost_acc,
+            keccak_table,
+            u8_table,
+            rlp,
+            sign_verify,
+        }
+    }
+
+    fn load_keccaks(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        inputs: &[Vec<u8>],
+        randomness: F,
+    ) -> Result<(), Error> {
+        let rows = keccak_table_rows(inputs, randomness);
+        layouter.assign_region(
+            || "keccak table",
+            |mut region| {
+                let mut offset = 0;
+                self.keccak_table
+                    .assign(&mut region, offset, [F::zero(); 3])?;
+                offset += 1;
+
+                for row in rows.iter() {
+                    self.keccak_table.assign(&mut region, offset, *row)?;
+                    offset += 1;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the tx table, with an all-zero row before the transactions and
+    /// padding rows after them, up to `max_calldata` call data bytes and one
+    /// more row.  The transactions are expected to be padded up to the
+    /// capacity of the circuit, so that the table has the same rows for every
+    /// block.
+    pub fn assign_txs(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        txs: &[Transaction],
+        max_calldata: usize,
+        randomness: F,
+    ) -> Result<(), Error> {
+        let n_calldata = txs.iter().map(|tx| tx.call_data.len()).sum::<usize>();
+        if n_calldata > max_calldata {
+            return Err(Error::Synthesis);
+        }
+        let n_padding_rows = max_calldata - n_calldata + 1;
+
+        layouter.assign_region(
+            || "tx table",
+            |mut region| {
+                let mut offset = 0;
+                self.tx_table.assign(&mut region, offset, [F::zero(); 4])?;
+                offset += 1;
+
+                for rows in txs
+                    .iter()
+                    .map(|tx| tx.table_assignments(randomness))
+                    .chain(std::iter::repeat(vec![[F::zero(); 4]]).take(n_padding_rows))
+                {
+                    let mut call_data_gas_cost_acc = 0;
+                    for row in rows {
+                        self.tx_table.assign(&mut region, offset, row)?;
+                        region.assign_fixed(
+                            || "tx table q_enable",
+                            self.q_enable,
+                            offset,
+                            || Ok(F::one()),
+                        )?;
+                        if offset == 1 {
+                            region.assign_fixed(
+                                || "tx table q_first",
+                                self.q_first,
+                                offset,
+                                || Ok(F::one()),
+                            )?;
+                        }
+
+                        let [_, tag, _, value] = row;
+                        if tag == F::from(TxContextFieldTag::CallData as u64) {
+                            call_data_gas_cost_acc += if value.is_zero_vartime() {
+                                GasCost::TX_DATA_ZERO
+                            } else {
+                                GasCost::TX_DATA_NON_ZERO
+                            }
+                            .as_u64();
+                        }
+                        for (column, value) in [
+                            (
+                                self.call_data_inv,
+                                tag - F::from(TxContextFieldTag::CallData as u64),
+                            ),
+                            (
+                                self.gas_cost_inv,
+                                tag - F::from(TxContextFieldTag::CallDataGasCost as u64),
+                            ),
+                            (self.tag_inv, tag),
+                            (self.value_inv, value),
+                        ] {
+                            region.assign_advice(
+                                || "tx table inverse",
+                                column,
+                                offset,
+                                || Ok(value.invert().unwrap_or(F::zero())),
+                            )?;
+                        }
+                        region.assign_advice(
+                            || "tx table call_data_gas_cost_acc",
+                            self.call_data_gas_cost_acc,
+                            offset,
+                            || Ok(F::from(call_data_gas_cost_acc)),
+                        )?;
+
+                        offset += 1;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Error when the transactions of a block don't fit in the tx circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TxCircuitError {
+    /// The block has more transactions than the circuit can hold.
+    TooManyTxs {
+        /// Number of transactions of the block
+        n_txs: usize,
+        /// Maximum number of transactions of the circuit
+        max_txs: usize,
+    },
+    /// The transactions of the block have more call data bytes than the
+    /// circuit can hold.
+    TooMuchCalldata {
+        /// Number of call data bytes of the transactions
+        n_bytes: usize,
+        /// Maximum number of call data bytes of the circuit
+        max_calldata: usize,
+    },
+    /// The RLP encodings of the transactions have more bytes than the circuit
+    /// can hold, which happens when their access lists don't fit in the
+    /// capacity left by the call data.
+    TooManyRlpBytes {
+        /// Number of bytes of the encodings of the transactions, padding
+        /// included
+        n_bytes: usize,
+        /// Maximum number of bytes of the encodings of the circuit
+        max_bytes: usize,
+    },
+}
+
+impl fmt::Display for TxCircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyTxs { n_txs, max_txs } => write!(
+                f,
+                "too many transactions for the tx circuit: {} > max_txs {}",
+                n_txs, max_txs
+            ),
+            Self::TooMuchCalldata {
+                n_bytes,
+                max_calldata,
+            } => write!(
+                f,
+                "too many call data bytes for the tx circuit: {} > max_calldata {}",
+                n_bytes, max_calldata
+            ),
+            Self::TooManyRlpBytes { n_bytes, max_bytes } => write!(
+                f,
+                "too many RLP bytes for the tx circuit: {} > {}",
+                n_bytes, max_bytes
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TxCircuitError {}
+
+/// Returns `tx` signed with the secret key `sk` and the nonce `k`, with
+/// EIP-155 if `chain_id` is given for a legacy transaction, together with the
+/// address of `sk`.
+pub(crate) fn sign_tx(
+    mut tx: Transaction,
+    sk: &BigUint,
+    k: &BigUint,
+    chain_id: Option<Word>,
+) -> (Transaction, Address) {
+    let n = Modulus::Secp256k1Fq.value();
+    let v = match (tx.tx_type, chain_id) {
+        (0, Some(chain_id)) => 35 + 2 * chain_id.as_u64(),
+        (0, None) => 27,
+        _ => 0,
+    };
+    tx.v = v;
+    let z = keccak256(&tx_sign_keccak_input(&tx, chain_id.unwrap_or_default()));
+    let z = BigUint::from_bytes_be(&z);
+    let generator = Curve::Secp256k1.generator();
+    let point = generator.mul(k).unwrap();
+    let r = &point.x % &n;
+    let s = (invert(k, &n) * (z + &r * sk)) % &n;
+    tx.r = Word::from_big_endian(&r.to_bytes_be());
+    tx.s = Word::from_big_endian(&s.to_bytes_be());
+    tx.v = v + point.y.is_odd() as u64;
+
+    let pk = generator.mul(sk).unwrap();
+    let mut pk_bytes = [0; 64];
+    for (chunk, coordinate) in pk_bytes.chunks_mut(32).zip([pk.x, pk.y]) {
+        let coordinate = coordinate.to_bytes_be();
+        chunk[32 - coordinate.len()..].copy_from_slice(&coordinate);
+    }
+    let address = Address::from_slice(&keccak256(&pk_bytes)[12..]);
+
+    (tx, address)
+}
+
+/// Returns the padding transaction of id `id`, an empty call to the zero
+/// address from the address of the secret key 1.  It's signed with the nonce
+/// 1, so that it's deterministic, and with the chain id if the chain allows
+/// it.
+pub(crate) fn padding_tx(id: usize, chain_id: Word, eip155_rule: Eip155Rule) -> Transaction {
+    let one = BigUint::from(1u64);
+    let chain_id = if eip155_rule.allowed()[1] {
+        Some(chain_id)
+    } else {
+        None
+    };
+    let (tx, caller_address) = sign_tx(
+        Transaction {
+            id,
+            ..Default::default()
+        },
+        &one,
+        &one,
+        chain_id,
+    );
+    Transaction {
+        caller_address,
+        ..tx
+    }
+}
+
+/// Returns the data signed by the sender of `tx`, with the public key
+/// recovered from its signature.
+fn sign_data(tx: &Transaction, chain_id: Word, eip155_rule: Eip155Rule) -> Result<SignData, Error> {
+    if tx.tx_type > 2 || !eip155_rule.allowed().contains(&tx.signs_chain_id()) {
+        return Err(Error::Synthesis);
+    }
+    let msg_hash = keccak256(&tx_sign_keccak_input(tx, chain_id));
+    let msg_hash = BigUint::from_bytes_be(&msg_hash);
+    let signature = (
+        BigUint::from_bytes_be(&tx.r.to_be_bytes()),
+        BigUint::from_bytes_be(&tx.s.to_be_bytes()),
+    );
+    let recovery_id = if tx.tx_type != 0 {
+        // v = recovery_id
+        tx.v
+    } else if tx.is_eip155() {
+        // v = recovery_id + 35 + 2 * chain_id
+        tx.v.wrapping_sub(35).wrapping_sub(2 * chain_id.low_u64())
+    } else {
+        // v = recovery_id + 27
+        tx.v.wrapping_sub(27)
+    };
+    if recovery_id > 1 {
+        return Err(Error::Synthesis);
+    }
+    let recovery_id = recovery_id as u8;
+    let pk =
+        recover_pk(&msg_hash, (&signature.0, &signature.1), recovery_id).ok_or(Error::Synthesis)?;
+    Ok(SignData {
+        msg_hash,
+        signature,
+        recovery_id,
+        pk: Some(pk),
+    })
+}
+
+/// Tx circuit that verifies the signatures of the transactions of a block.
+#[derive(Clone, Default, Debug)]
+pub struct TxCircuit<F> {
+    /// Randomness of the RLC of the tables
+    pub randomness: F,
+    /// Chain id the transactions are signed for
+    pub chain_id: Word,
+    /// Rule of the chain on signing its chain id
+    pub eip155_rule: Eip155Rule,
+    /// Transactions of the block
+    pub txs: Vec<Transaction>,
+    /// Maximum number of transactions of the circuit, the ones after the
+    /// transactions of the block are padding
+    pub max_txs: usize,
+    /// Maximum number of call data bytes of all the transactions
+    pub max_calldata: usize,
+}
+
+impl<F: Field> TxCircuit<F> {
+    /// Returns the tx circuit of the transactions `txs` of a block of the chain
+    /// `chain_config`.
+    pub fn new(
+        chain_config: &ChainConfig,
+        randomness: F,
+        txs: Vec<Transaction>,
+        max_txs: usize,
+        max_calldata: usize,
+    ) -> Self {
+        Self {
+            randomness,
+            chain_id: chain_config.chain_id,
+            eip155_rule: chain_config.eip155_rule,
+            txs,
+            max_txs,
+            max_calldata,
+        }
+    }
+
+    // Returns the maximum number of bytes of the RLP encodings of the
+    // transactions, a bound on the encoding of every transaction besides its
+    // call data, plus the call data bytes.
+    fn max_rlp_bytes(&self) -> usize {
+        self.max_txs * MAX_RLP_TX_BYTES + self.max_calldata
+    }
+
+    // Returns the transactions of the block followed by the padding ones, up
+    // to `max_txs`.
+    fn padded_txs(&self) -> Vec<Transaction> {
+        self.txs
+            .iter()
+            .cloned()
+            .chain(
+                (self.txs.len()..self.max_txs)
+                    .map(|idx| padding_tx(idx + 1, self.chain_id, self.eip155_rule)),
+            )
+            .collect()
+    }
+
+    fn sign_datas(&self, txs: &[Transaction]) -> Result<Vec<SignData>, Error> {
+        txs.iter()
+            .map(|tx| sign_data(tx, self.chain_id, self.eip155_rule))
+            .collect()
+    }
+
+    /// Checks that the transactions of the block fit in the capacity of the
+    /// circuit.
+    pub fn check_capacity(&self) -> Result<(), TxCircuitError> {
+        if self.txs.len() > self.max_txs {
+            return Err(TxCircuitError::TooManyTxs {
+                n_txs: self.txs.len(),
+                max_txs: self.max_txs,
+            });
+        }
+        let n_bytes = self.txs.iter().map(|tx| tx.call_data.len()).sum();
+        if n_bytes > self.max_calldata {
+            return Err(TxCircuitError::TooMuchCalldata {
+                n_bytes,
+                max_calldata: self.max_calldata,
+            });
+        }
+        let n_bytes = self
+            .padded_txs()
+            .iter()
+            .map(|tx| tx_sign_keccak_input(tx, self.chain_id).len())
+            .sum();
+        if n_bytes > self.max_rlp_bytes() {
+            return Err(TxCircuitError::TooManyRlpBytes {
+                n_bytes,
+                max_bytes: self.max_rlp_bytes(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the distinct inputs of the keccak hashes looked up by the tx
+    /// circuit, the signed messages, of the padding transactions too.
+    pub fn keccak_inputs(&self) -> Vec<Vec<u8>> {
+        dedup_keccak_inputs(
+            self.padded_txs()
+                .iter()
+                .map(|tx| tx_sign_keccak_input(tx, self.chain_id)),
+        )
+    }
+
+    /// Returns the sig circuit verifying the signatures of the transactions,
+    /// of the padding transactions too, which has room for a signature per
+    /// transaction.
+    pub fn sig_circuit(&self) -> Result<SigCircuit<F>, Error> {
+        Ok(SigCircuit {
+            randomness: self.randomness,
+            sign_datas: self.sign_datas(&self.padded_txs())?,
+            max_signatures: self.max_txs,
+        })
+    }
+
+    /// Assign the tx circuit, except for the keccak table, which is loaded
+    /// with `TxCircuit::keccak_inputs` by the circuit that owns it, and the sig
+    /// table, which is assigned by `TxCircuit::sig_circuit`.
+    #[tracing::instrument(name = "tx_circuit_assign", skip_all)]
+    pub fn assign(
+        &self,
+        config: &TxCircuitConfig<F>,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        self.check_capacity().map_err(|_| Error::Synthesis)?;
+        let txs = self.padded_txs();
+        let sign_datas = self.sign_datas(&txs)?;
+
+        config.u8_table.load(layouter)?;
+        config.rlp.load(layouter)?;
+        config
+            .sign_verify
+            .assign(layouter, &sign_datas, self.randomness)?;
+        config.rlp.assign(
+            layouter,
+            &txs,
+            self.chain_id,
+            self.eip155_rule,
+            self.randomness,
+            self.max_rlp_bytes(),
+        )?;
+        config.assign_txs(layouter, &txs, self.max_calldata, self.randomness)
+    }
+}
+
+impl<F: Field> Circuit<F> for TxCircuit<F> {
+    type Config = (TxCircuitConfig<F>, SigCircuitConfig<F>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            chain_id: self.chain_id,
+            eip155_rule: self.eip155_rule,
+            max_txs: self.max_txs,
+            max_calldata: self.max_calldata,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let tx_table = TxTable::construct(meta);
+        let keccak_table = KeccakTable::construct(meta);
+        let sig_table = SigTable::construct(meta);
+
+        let power_of_randomness = power_of_randomness_from_instance::<_, POW_RAND_SIZE>(meta);
+
+        (
+            TxCircuitConfig::configure(
+                meta,
+                power_of_randomness[0].clone(),
+                tx_table,
+                keccak_table,
+                sig_table,
+            ),
+            SigCircuitConfig::configure(meta, power_of_randomness, sig_table, keccak_table),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        (config, sig_config): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let sig_circuit = self.sig_circuit()?;
+        let keccak_inputs = dedup_keccak_inputs(
+            sig_circuit
+                .keccak_inputs()
+                .into_iter()
+                .chain(self.keccak_inputs()),
+        );
+        config.load_keccaks(&mut layouter, &keccak_inputs, self.randomness)?;
+        sig_circuit.assign(&sig_config, &mut layouter)?;
+        self.assign(&config, &mut layouter)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{padding_tx, sign_data, sign_tx, Eip155Rule, TxCircuit, TxCircuitError};
+    use crate::{
+        evm_circuit::witness::Transaction,
+        gadget::{non_native::Modulus, rlc::PowersOfRandomness},
+    };
+    use eth_types::{address, AccessList, AccessListItem, Address, Word, H256};
+    use halo2_proofs::dev::{MockProver, VerifyFailure};
+    use num::BigUint;
+    use pairing::bn256::Fr;
+    use rand::{Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    const K: u32 = 14;
+    const MAX_CALLDATA: usize = 128;
+
+    // Signs `tx` with the secret key `sk`, with EIP-155 if `chain_id` is given
+    // for a legacy transaction, returning it with its signature and the
+    // address of `sk`.
+    pub(crate) fn sign(
+        tx: Transaction,
+        sk: &BigUint,
+        chain_id: Option<Word>,
+    ) -> (Transaction, Address) {
+        let mut rng = XorShiftRng::seed_from_u64(2);
+        let k = BigUint::from_bytes_be(&rng.gen::<[u8; 32]>()) % Modulus::Secp256k1Fq.value();
+        sign_tx(tx, sk, &k, chain_id)
+    }
+
+    fn power_of_randomness(k: u32, randomness: Fr) -> Vec<Vec<Fr>> {
+        PowersOfRandomness::new(randomness)
+            .take(63)
+            .into_iter()
+            .map(|power| vec![power; (1 << k) - 64])
+            .collect()
+    }
+
+    fn verify(k: u32, circuit: TxCircuit<Fr>) -> Result<(), Vec<VerifyFailure>> {
+        let power_of_randomness = power_of_randomness(k, circuit.randomness);
+        let prover = MockProver::<Fr>::run(k, &circuit, power_of_randomness).unwrap();
+        prover.verify()
+    }
+
+    fn mock_tx() -> Transaction {
+        Transaction {
+            id: 1,
+            nonce: 1,
+            gas: 21000,
+            gas_price: Word::from(1_000_000_000u64),
+            callee_address: address!("0x00000000000000000000000000000000000000fe"),
+            value: Word::from(1000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tx_circuit_valid_signature() {
+        let chain_id = Word::from(1337);
+        let (mut tx, address) = sign(mock_tx(), &BigUint::from(0xcafeu64), Some(chain_id));
+        tx.caller_address = address;
+
+        let circuit = TxCircuit {
+            randomness: Fr::from(0x10000),
+            chain_id,
+            txs: vec![tx],
+            max_txs: 1,
+            max_calldata: MAX_CALLDATA,
+            ..Default::default()
+        };
+        assert_eq!(verify(K, circuit), Ok(()));
+    }
+
+    #[cfg(feature = "real-prover")]
+    #[test]
+    fn tx_circuit_valid_signature_real_prover() {
+        let chain_id = Word::from(1337);
+        let (mut tx, address) = sign(mock_tx(), &BigUint::from(0xcafeu64), Some(chain_id));
+        tx.caller_address = address;
+
+        let circuit = TxCircuit {
+            randomness: Fr::from(0x10000),
+            chain_id,
+            txs: vec![tx],
+            max_txs: 1,
+            max_calldata: MAX_CALLDATA,
+            ..Default::default()
+        };
+        let power_of_randomness = power_of_randomness(K, circuit.randomness);
+        crate::test_util::run_real_prover(K, &circuit, power_of_randomness).unwrap();
+    }
+
+    #[test]
+    fn tx_circuit_caller_is_not_signer() {
+        let chain_id = Word::from(1337);
+        let (mut tx, _) = sign(mock_tx(), &BigUint::from(0xcafeu64), Some(chain_id));
+        tx.caller_address = address!("0x00000000000000000000000000000000000000ca");
+
+        let circuit = TxCircuit {
+            randomness: Fr::from(0x10000),
+            chain_id,
+            txs: vec![tx],
+            max_txs: 1,
+            max_calldata: MAX_CALLDATA,
+            ..Default::default()
+        };
+        assert!(verify(K, circuit).is_err());
+    }
+
+    #[test]
+    fn tx_circuit_contract_creation_with_long_call_data() {
+        let chain_id = Word::from(1337);
+        let tx = Transaction {
+            callee_address: Address::zero(),
+            is_create: true,
+            call_data: (0..100).collect(),
+            call_data_length: 100,
+            call_data_gas_cost: 4 + 99 * 16,
+            ..mock_tx()
+        };
+        let (mut tx, address) = sign(tx, &BigUint::from(0xcafeu64), Some(chain_id));
+        tx.caller_address = address;
+
+        let circuit = TxCircuit {
+            randomness: Fr::from(0x10000),
+            chain_id,
+            txs: vec![tx],
+            max_txs: 1,
+            max_calldata: MAX_CALLDATA,
+            ..Default::default()
+        };
+        assert_eq!(verify(K, circuit), Ok(()));
+    }
+
+    #[test]
+    fn tx_circuit_call_data_gas_cost_mismatch() {
+        let chain_id = Word::from(1337);
+        let tx = Transaction {
+            call_data: vec![0, 1, 2],
+            call_data_length: 3,
+            call_data_gas_cost: 3 * 16,
+            ..mock_tx()
+        };
+        let (mut tx, address) = sign(tx, &BigUint::from(0xcafeu64), Some(chain_id));
+        tx.caller_address = address;
+
+        let circuit = TxCircuit {
+            randomness: Fr::from(0x10000),
+            chain_id,
+            txs: vec![tx],
+            max_txs: 1,
+            max_calldata: MAX_CALLDATA,
+            ..Default::default()
+        };
+        assert!(verify(K, circuit).is_err());
+    }
+
+    #[test]
+    fn tx_circuit_without_eip155() {
+        let chain_id = Word::from(1337);
+        let (mut tx, address) = sign(mock_tx(), &BigUint::from(0xcafeu64), None);
+        tx.caller_address = address;
+        assert!(sign_data(&tx, chain_id, Eip155Rule::Required).is_err());
+
+        let circuit = TxCircuit {
+            randomness: Fr::from(0x10000),
+            chain_id,
+            eip155_rule: Eip155Rule::Optional,
+            txs: vec![tx],
+            max_txs: 1,
+            max_calldata: MAX_CALLDATA,
+        };
+        assert_eq!(verify(K, circuit), Ok(()));
+    }
+
+    #[test]
+    fn tx_circuit_eip155_and_without_in_a_block() {
+        let chain_id = Word::from(1337);
+        let (mut tx1, address) = sign(mock_tx(), &BigUint::from(0xcafeu64), Some(chain_id));
+        tx1.caller_address = address;
+        let (mut tx2, address) = sign(
+            Transaction {
+                id: 2,
+                nonce: 2,
+                ..mock_tx()
+            },
+            &BigUint::from(0xcafeu64),
+            None,
+        );
+        tx2.caller_address = address;
+
+        let circuit = TxCircuit {
+            randomness: Fr::from(0x10000),
+            chain_id,
+            eip155_rule: Eip155Rule::Optional,
+            txs: vec![tx1, tx2],
+            max_txs: 2,
+            max_calldata: MAX_CALLDATA,
+        };
+        assert_eq!(verify(K + 1, circuit), Ok(()));
+    }
+
+    #[test]
+    fn tx_circuit_eip2930() {
+        let chain_id = Word::from(1337);
+        let tx = Transaction {
+            tx_type: 1,
+            access_list: AccessList(vec![
+                AccessListItem {
+                    address: address!("0x00000000000000000000000000000000000000fe"),
+                    storage_keys: vec![H256::zero(), H256::from_low_u64_be(1)],
+                },
+                AccessListItem {
+                    address: address!("0x00000000000000000000000000000000000000ca"),
+                    storage_keys: vec![],
+                },
+            ]),
+            ..mock_tx()
+        };
+        let (mut tx, address) = sign(tx, &BigUint::from(0xcafeu64), Some(chain_id));
+        tx.caller_address = address;
+
+        let circuit = TxCircuit {
+            randomness: Fr::from(0x10000),
+            chain_id,
+            txs: vec![tx],
+            max_txs: 1,
+            max_calldata: MAX_CALLDATA,
+            ..Default::default()
+        };
+        assert_eq!(verify(K, circuit), Ok(()));
+    }
+
+    #[test]
+    fn tx_circuit_eip1559() {
+        let chain_id = Word::from(1337);
+        let tx = Transaction {
+            tx_type: 2,
+            max_priority_fee_per_gas: Word::from(1_000_000_000u64),
+            max_fee_per_gas: Word::from(2_000_000_000u64),
+            call_data: vec![0, 1, 2],
+            call_data_length: 3,
+            call_data_gas_cost: 4 + 2 * 16,
+            ..mock_tx()
+        };
+        let (mut tx, address) = sign(tx, &BigUint::from(0xcafeu64), Some(chain_id));
+        tx.caller_address = address;
+        assert!(sign_data(&tx, chain_id, Eip155Rule::Unprotected).is_err());
+
+        let circuit = TxCircuit {
+            randomness: Fr::from(0x10000),
+            chain_id,
+            txs: vec![tx],
+            max_txs: 1,
+            max_calldata: MAX_CALLDATA,
+            ..Default::default()
+        };
+        assert_eq!(verify(K, circuit), Ok(()));
+    }
+
+    #[test]
+    fn tx_circuit_padding() {
+        let chain_id = Word::from(1337);
+        let (mut tx, address) = sign(mock_tx(), &BigUint::from(0xcafeu64), Some(chain_id));
+        tx.caller_address = address;
+        for eip155_rule in [Eip155Rule::Unprotected, Eip155Rule::Required] {
+            let tx = padding_tx(2, chain_id, eip155_rule);
+            assert!(sign_data(&tx, chain_id, eip155_rule).is_ok());
+        }
+
+        // The same circuit proves a block with fewer transactions and call
+        // data than its capacity
+        let circuit = TxCircuit {
+            randomness: Fr::from(0x10000),
+            chain_id,
+            txs: vec![tx],
+            max_txs: 2,
+            max_calldata: MAX_CALLDATA,
+            ..Default::default()
+        };
+        assert_eq!(verify(K + 1, circuit), Ok(()));
+    }
+
+    #[test]
+    fn tx_circuit_capacity() {
+        let tx = Transaction {
+            call_data: vec![0, 1, 2],
+            call_data_length: 3,
+            call_data_gas_cost: 4 + 2 * 16,
+            ..mock_tx()
+        };
+        let circuit = TxCircuit::<Fr> {
+            chain_id: Word::from(1337),
+            txs: vec![
+                tx.clone(),
+                Transaction {
+                    id: 2,
+                    ..tx.clone()
+                },
+            ],
+            max_txs: 2,
+            max_calldata: 6,
+            ..Default::default()
+        };
+        assert_eq!(circuit.check_capacity(), Ok(()));
+        assert_eq!(
+            TxCircuit {
+                max_txs: 1,
+                ..circuit.clone()
+            }
+            .check_capacity(),
+            Err(TxCircuitError::TooManyTxs {
+                n_txs: 2,
+                max_txs: 1
+            })
+        );
+        assert_eq!(
+            TxCircuit {
+                max_calldata: 5,
+                ..circuit.clone()
+            }
+            .check_capacity(),
+            Err(TxCircuitError::TooMuchCalldata {
+                n_bytes: 6,
+                max_calldata: 5
+            })
+        );
+
+        // The access list shares the capacity of the call data in the RLP
+        // encoding
+        let tx = Transaction {
+            tx_type: 1,
+            access_list: AccessList(vec![AccessListItem {
+                address: Address::zero(),
+                storage_keys: (0..10).map(H256::from_low_u64_be).collect(),
+            }]),
+            ..mock_tx()
+        };
+        assert!(matches!(
+            TxCircuit {
+                txs: vec![tx],
+                max_txs: 1,
+                max_calldata: 0,
+                ..circuit
+            }
+            .check_capacity(),
+            Err(TxCircuitError::TooManyRlpBytes { .. })
+        ));
+    }
+}
@@ -0,0 +1,1141 @@
+//! RLP encoding of the transactions, which binds the message hashes signed by
+//! their senders to the tx table.
+//!
+//! Every byte of the encoding of a transaction is assigned in a row, which is
+//! - `rlp([nonce, gas_price, gas, to, value, data, chain_id, 0, 0])` for a
+//!   legacy transaction signed with EIP-155,
+//! - `rlp([nonce, gas_price, gas, to, value, data])` for one signed without,
+//! - `0x01 ‖ rlp([chain_id, nonce, gas_price, gas, to, value, data,
+//!   access_list])` for an EIP-2930 transaction,
+//! - `0x02 ‖ rlp([chain_id, nonce, max_priority_fee_per_gas, max_fee_per_gas,
+//!   gas, to, value, data, access_list])` for an EIP-1559 transaction,
+//!
+//! and the fields of the list are parsed by the rules of RLP:
+//! - a byte in `[0x00, 0x80)` is a single byte string,
+//! - a byte in `[0x80, 0xb8)` prefixes a string of `byte - 0x80` bytes,
+//! - a byte in `[0xb8, 0xc0)` prefixes the `byte - 0xb7` big endian bytes of
+//!   the length of the string,
+//!
+//! and the same for the list with an offset of 0x40.  The value of every field
+//! is looked up in the tx table, and the RLC of the encoding is looked up in
+//! the keccak table together with the RLC of its hash.  The chain id, and
+//! whether transactions may be signed with or without EIP-155, are fixed by
+//! the chain.
+//!
+//! The access list is only hashed, as the tx table has no field of it, and
+//! neither are the fees of an EIP-1559 transaction looked up, as its gas price
+//! depends on the base fee of the block.
+//!
+//! The rows are enabled up to the capacity of the circuit, and the rows after
+//! the last transaction are padding, which are flagged by `is_padding` and
+//! left unconstrained.
+
+use super::Eip155Rule;
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    evm_circuit::{
+        table::{LookupTable, TxContextFieldTag},
+        util::{constraint_builder::BaseConstraintBuilder, not, select, sum},
+        witness::{
+            keccak_table_assignments, rlp_prefix, tx_sign_keccak_input, tx_sign_rlp_fields,
+            Transaction,
+        },
+    },
+    gadget::range_check::RangeTable,
+    util::Expr,
+};
+use eth_types::{Field, ToScalar, Word};
+use halo2_proofs::{
+    circuit::Layouter,
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+// Number of fields of the encodings, including the type and the prefix of the
+// list.
+const N_TAGS: usize = 14;
+
+// Number of kinds of transactions.
+const N_KINDS: usize = 4;
+
+const MAX_DEGREE: usize = 8;
+
+/// Bound on the number of bytes of the encoding of a transaction, besides the
+/// content of its call data and of its access list.  The longest encoding is
+/// the one of an EIP-1559 transaction, with 9 bytes for the prefixes of the
+/// list, the call data and the access list, 9 bytes for each of the nonce and
+/// the gas, 33 bytes for each of the chain id, the value and the fees, 21 for
+/// the callee and 1 for the type.
+pub(crate) const MAX_RLP_TX_BYTES: usize = 256;
+
+/// Field of the RLP encoding of a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RlpTxTag {
+    /// Prefix of the list of the fields
+    Prefix,
+    Nonce,
+    GasPrice,
+    Gas,
+    To,
+    Value,
+    Data,
+    ChainId,
+    /// 0 in place of the signature r
+    SignR,
+    /// 0 in place of the signature s
+    SignS,
+    /// Type of a typed transaction, which precedes the list
+    TxType,
+    MaxPriorityFeePerGas,
+    MaxFeePerGas,
+    AccessList,
+}
+
+impl RlpTxTag {
+    const ALL: [Self; N_TAGS] = [
+        Self::Prefix,
+        Self::Nonce,
+        Self::GasPrice,
+        Self::Gas,
+        Self::To,
+        Self::Value,
+        Self::Data,
+        Self::ChainId,
+        Self::SignR,
+        Self::SignS,
+        Self::TxType,
+        Self::MaxPriorityFeePerGas,
+        Self::MaxFeePerGas,
+        Self::AccessList,
+    ];
+
+    // Returns whether the field is a list, which is the list of the fields or
+    // the access list.
+    fn is_list(&self) -> bool {
+        matches!(self, Self::Prefix | Self::AccessList)
+    }
+
+    // Returns the tag of the value of the field in the tx table, if it's
+    // looked up there.
+    fn tx_field_tag(&self) -> Option<TxContextFieldTag> {
+        match self {
+            Self::Nonce => Some(TxContextFieldTag::Nonce),
+            Self::GasPrice => Some(TxContextFieldTag::GasPrice),
+            Self::Gas => Some(TxContextFieldTag::Gas),
+            Self::To => Some(TxContextFieldTag::CalleeAddress),
+            Self::Value => Some(TxContextFieldTag::Value),
+            _ => None,
+        }
+    }
+
+    // Returns whether the value of the field is the RLC of its bytes in the tx
+    // table, instead of their integer.
+    fn is_rlc(&self) -> bool {
+        matches!(self, Self::GasPrice | Self::Value)
+    }
+}
+
+/// Kind of a transaction, which decides the fields of its encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RlpTxKind {
+    /// Legacy transaction signed without EIP-155
+    Unprotected,
+    /// Legacy transaction signed with EIP-155
+    Eip155,
+    /// EIP-2930 transaction
+    AccessList,
+    /// EIP-1559 transaction
+    DynamicFee,
+}
+
+impl RlpTxKind {
+    const ALL: [Self; N_KINDS] = [
+        Self::Unprotected,
+        Self::Eip155,
+        Self::AccessList,
+        Self::DynamicFee,
+    ];
+
+    fn of(tx: &Transaction) -> Self {
+        match tx.tx_type {
+            0 if tx.is_eip155() => Self::Eip155,
+            0 => Self::Unprotected,
+            1 => Self::AccessList,
+            2 => Self::DynamicFee,
+            tx_type => panic!("unsupported transaction type {}", tx_type),
+        }
+    }
+
+    // Returns the fields of the encoding in order, as in `tx_sign_rlp_fields`.
+    fn tags(&self) -> &'static [RlpTxTag] {
+        use RlpTxTag::*;
+        match self {
+            Self::Unprotected => &[Prefix, Nonce, GasPrice, Gas, To, Value, Data],
+            Self::Eip155 => &[
+                Prefix, Nonce, GasPrice, Gas, To, Value, Data, ChainId, SignR, SignS,
+            ],
+            Self::AccessList => &[
+                TxType, Prefix, ChainId, Nonce, GasPrice, Gas, To, Value, Data, AccessList,
+            ],
+            Self::DynamicFee => &[
+                TxType,
+                Prefix,
+                ChainId,
+                Nonce,
+                MaxPriorityFeePerGas,
+                MaxFeePerGas,
+                Gas,
+                To,
+                Value,
+                Data,
+                AccessList,
+            ],
+        }
+    }
+
+    // Returns the type of the envelope of the transaction.
+    fn tx_type(&self) -> u64 {
+        match self {
+            Self::Unprotected | Self::Eip155 => 0,
+            Self::AccessList => 1,
+            Self::DynamicFee => 2,
+        }
+    }
+}
+
+/// Kind of the first byte of an RLP item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RlpPrefixKind {
+    /// The byte is the item itself
+    Single = 1,
+    /// The byte is the prefix of the item with its length
+    Short,
+    /// The byte is the prefix of the item with the number of bytes of its
+    /// length
+    Long,
+}
+
+impl RlpPrefixKind {
+    fn of(is_list: bool, byte: u8) -> Option<Self> {
+        match (is_list, byte) {
+            (false, 0x00..=0x7f) => Some(Self::Single),
+            (false, 0x80..=0xb7) | (true, 0xc0..=0xf7) => Some(Self::Short),
+            (false, 0xb8..=0xbf) | (true, 0xf8..=0xff) => Some(Self::Long),
+            _ => None,
+        }
+    }
+}
+
+// Witness of a byte of the encoding of a transaction.
+#[derive(Clone, Debug)]
+struct RlpRow<F> {
+    tx_id: usize,
+    tag: RlpTxTag,
+    byte: u8,
+    // Kind of the byte, if it's the first one of the field
+    kind: Option<RlpPrefixKind>,
+    is_length: bool,
+    // Number of bytes of the field after this one
+    remaining: usize,
+    // Length of the content of the field
+    length: usize,
+    length_acc: usize,
+    value_acc: F,
+    rlc_acc: F,
+    index: usize,
+    tx_length: usize,
+}
+
+// Returns the rows of the encoding of `tx`.
+fn rlp_rows<F: Field>(tx: &Transaction, chain_id: Word, randomness: F) -> Vec<RlpRow<F>> {
+    let tx_kind = RlpTxKind::of(tx);
+    let fields = tx_sign_rlp_fields(tx, chain_id);
+    let payload_length = fields.iter().map(Vec::len).sum::<usize>();
+    let tx_type = match tx_kind.tx_type() {
+        0 => Vec::new(),
+        tx_type => vec![vec![tx_type as u8]],
+    };
+    let prefix = rlp_prefix(0xc0, payload_length);
+    let tx_length = tx_type.len() + prefix.len() + payload_length;
+
+    let mut rows = Vec::with_capacity(tx_length);
+    let mut rlc_acc = F::zero();
+    for (tag, encoding) in tx_kind.tags().iter().zip(
+        tx_type
+            .iter()
+            .chain(std::iter::once(&prefix))
+            .chain(fields.iter()),
+    ) {
+        let is_list = tag.is_list();
+        let kind = RlpPrefixKind::of(is_list, encoding[0]).unwrap();
+        let offset = if is_list { 0x40 } else { 0 };
+        let n_length_bytes = match kind {
+            RlpPrefixKind::Long => (encoding[0] - 0xb7 - offset) as usize,
+            _ => 0,
+        };
+        let length = match kind {
+            _ if *tag == RlpTxTag::Prefix => payload_length,
+            RlpPrefixKind::Single => 1,
+            RlpPrefixKind::Short => (encoding[0] - 0x80 - offset) as usize,
+            RlpPrefixKind::Long => encoding.len() - 1 - n_length_bytes,
+        };
+        let multiplier = if tag.is_rlc() {
+            randomness
+        } else {
+            F::from(256)
+        };
+
+        let mut length_acc = 0;
+        let mut value_acc = F::zero();
+        for (idx, byte) in encoding.iter().enumerate() {
+            let is_length = (1..=n_length_bytes).contains(&idx);
+            if idx == 0 {
+                if kind == RlpPrefixKind::Single {
+                    value_acc = F::from(*byte as u64);
+                }
+            } else if is_length {
+                length_acc = length_acc * 256 + *byte as usize;
+            } else {
+                value_acc = value_acc * multiplier + F::from(*byte as u64);
+            }
+            rlc_acc = rlc_acc * randomness + F::from(*byte as u64);
+
+            rows.push(RlpRow {
+                tx_id: tx.id,
+                tag: *tag,
+                byte: *byte,
+                kind: if idx == 0 { Some(kind) } else { None },
+                is_length,
+                remaining: encoding.len() - 1 - idx,
+                length,
+                length_acc,
+                value_acc,
+                rlc_acc,
+                index: rows.len() + 1,
+                tx_length,
+            });
+        }
+    }
+    rows
+}
+
+/// Config of the RLP encoding of the transactions.
+#[derive(Clone, Debug)]
+pub(crate) struct RlpConfig<F> {
+    q_enable: Column<Fixed>,
+    // Enables the first row of the first transaction
+    q_first: Column<Fixed>,
+    // Whether the row is after the last transaction
+    is_padding: Column<Advice>,
+    tx_id: Column<Advice>,
+    // One-hot encoding of the field of the row
+    is_tag: [Column<Advice>; N_TAGS],
+    byte: Column<Advice>,
+    // Whether the byte is the first one of the field
+    is_first: Column<Advice>,
+    // Kind of the first byte of the field
+    is_single: Column<Advice>,
+    is_short: Column<Advice>,
+    is_long: Column<Advice>,
+    // Whether the byte is one of the length of a long item
+    is_length: Column<Advice>,
+    // Number of bytes of the field after this one, which is 0 at the end of
+    // the field
+    remaining: Column<Advice>,
+    remaining_inv: Column<Advice>,
+    is_end: Column<Advice>,
+    // Length of the content of the field
+    length: Column<Advice>,
+    // Length decoded from the length bytes so far
+    length_acc: Column<Advice>,
+    // Value decoded from the content bytes so far
+    value_acc: Column<Advice>,
+    // RLC of the bytes of the transaction so far
+    rlc_acc: Column<Advice>,
+    // Number of bytes of the transaction so far
+    index: Column<Advice>,
+    // Number of bytes of the transaction
+    tx_length: Column<Advice>,
+    // One-hot encoding of the kind of the transaction
+    is_kind: [Column<Advice>; N_KINDS],
+    chain_id: Column<Fixed>,
+    // Allowed values by the chain of whether the chain id is signed
+    eip155_allowed: [Column<Fixed>; 2],
+    // RLC of the keccak hash of the encoding
+    hash_rlc: Column<Advice>,
+    // Table of (is_list, byte, kind) of the first bytes of items
+    prefix_table: [Column<Fixed>; 3],
+    _marker: PhantomData<F>,
+}
+
+impl<F> Annotate for RlpConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate(owner, "q_enable", self.q_enable);
+        annotations.annotate(owner, "q_first", self.q_first);
+        annotations.annotate(owner, "is_padding", self.is_padding);
+        annotations.annotate(owner, "tx_id", self.tx_id);
+        annotations.annotate_all(owner, "is_tag", self.is_tag);
+        annotations.annotate(owner, "byte", self.byte);
+        annotations.annotate(owner, "is_first", self.is_first);
+        annotations.annotate(owner, "is_single", self.is_single);
+        annotations.annotate(owner, "is_short", self.is_short);
+        annotations.annotate(owner, "is_long", self.is_long);
+        annotations.annotate(owner, "is_length", self.is_length);
+        annotations.annotate(owner, "remaining", self.remaining);
+        annotations.annotate(owner, "remaining_inv", self.remaining_inv);
+        annotations.annotate(owner, "is_end", self.is_end);
+        annotations.annotate(owner, "length", self.length);
+        annotations.annotate(owner, "length_acc", self.length_acc);
+        annotations.annotate(owner, "value_acc", self.value_acc);
+        annotations.annotate(owner, "rlc_acc", self.rlc_acc);
+        annotations.annotate(owner, "index", self.index);
+        annotations.annotate(owner, "tx_length", self.tx_length);
+        annotations.annotate_all(owner, "is_kind", self.is_kind);
+        annotations.annotate(owner, "chain_id", self.chain_id);
+        annotations.annotate_all(owner, "eip155_allowed", self.eip155_allowed);
+        annotations.annotate(owner, "hash_rlc", self.hash_rlc);
+        annotations.annotate_all(owner, "prefix_table", self.prefix_table);
+    }
+}
+
+impl<F: Field> RlpConfig<F> {
+    /// Configure the RLP encoding of the transactions.  `randomness` is the
+    /// randomness of the RLC of the tables, and the bytes are looked up in
+    /// `u8_table`.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        randomness: Expression<F>,
+        u8_table: RangeTable<8>,
+        tx_table: impl LookupTable<F, 4>,
+        keccak_table: impl LookupTable<F, 3>,
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_first = meta.fixed_column();
+        let is_padding = meta.advice_column();
+        let [tx_id, byte, is_first, is_single, is_short, is_long, is_length] =
+            [(); 7].map(|_| meta.advice_column());
+        let is_tag = [(); N_TAGS].map(|_| meta.advice_column());
+        let [remaining, remaining_inv, is_end, length, length_acc, value_acc] =
+            [(); 6].map(|_| meta.advice_column());
+        let [rlc_acc, index, tx_length, hash_rlc] = [(); 4].map(|_| meta.advice_column());
+        let is_kind = [(); N_KINDS].map(|_| meta.advice_column());
+        let chain_id = meta.fixed_column();
+        let eip155_allowed = [(); 2].map(|_| meta.fixed_column());
+        let prefix_table = [(); 3].map(|_| meta.fixed_column());
+
+        let config = Self {
+            q_enable,
+            q_first,
+            is_padding,
+            tx_id,
+            is_tag,
+            byte,
+            is_first,
+            is_single,
+            is_short,
+            is_long,
+            is_length,
+            remaining,
+            remaining_inv,
+            is_end,
+            length,
+            length_acc,
+            value_acc,
+            rlc_acc,
+            index,
+            tx_length,
+            is_kind,
+            chain_id,
+            eip155_allowed,
+            hash_rlc,
+            prefix_table,
+            _marker: PhantomData,
+        };
+
+        meta.create_gate("RLP padding", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+
+            let q_next = meta.query_fixed(q_enable, Rotation::next());
+            let [is_padding, is_padding_next] = [Rotation::cur(), Rotation::next()]
+                .map(|rotation| meta.query_advice(is_padding, rotation));
+            cb.require_boolean("is_padding is boolean", is_padding.clone());
+            cb.condition(q_next * is_padding, |cb| {
+                cb.require_equal("padding is followed by padding", is_padding_next, 1.expr());
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("RLP encoding of transactions", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+
+            let q_next = config.is_tx_row(meta, Rotation::next());
+            let [cur, next] = [Rotation::cur(), Rotation::next()];
+            let tx_id = [cur, next].map(|rotation| meta.query_advice(tx_id, rotation));
+            let is_tag = [cur, next]
+                .map(|rotation| is_tag.map(|column| meta.query_advice(column, rotation)));
+            let byte = [cur, next].map(|rotation| meta.query_advice(byte, rotation));
+            let is_first = [cur, next].map(|rotation| meta.query_advice(is_first, rotation));
+            let is_length = [cur, next].map(|rotation| meta.query_advice(is_length, rotation));
+            let [is_single, is_short, is_long, remaining_inv, is_end] =
+                [is_single, is_short, is_long, remaining_inv, is_end]
+                    .map(|column| meta.query_advice(column, cur));
+            let [remaining, length, length_acc, value_acc, rlc_acc, index, tx_length] = [
+                remaining, length, length_acc, value_acc, rlc_acc, index, tx_length,
+            ]
+            .map(|column| [cur, next].map(|rotation| meta.query_advice(column, rotation)));
+            let is_kind = [cur, next]
+                .map(|rotation| is_kind.map(|column| meta.query_advice(column, rotation)));
+            let chain_id = meta.query_fixed(chain_id, cur);
+            let eip155_allowed = eip155_allowed.map(|column| meta.query_fixed(column, cur));
+
+            // The list of the fields and the access list have the prefixes of
+            // a list, but only the access list has its content in its field
+            let is_list = is_tag[0][RlpTxTag::Prefix as usize].clone()
+                + is_tag[0][RlpTxTag::AccessList as usize].clone();
+            let has_content = not::expr(is_tag[0][RlpTxTag::Prefix as usize].clone());
+            let is_typed = [0, 1].map(|idx| {
+                is_kind[idx][RlpTxKind::AccessList as usize].clone()
+                    + is_kind[idx][RlpTxKind::DynamicFee as usize].clone()
+            });
+            // The first field of a transaction is its type, or the list of its
+            // fields for a legacy transaction
+            let is_tx_start = [0, 1].map(|idx| {
+                is_tag[idx][RlpTxTag::TxType as usize].clone()
+                    + not::expr(is_typed[idx].clone())
+                        * is_tag[idx][RlpTxTag::Prefix as usize].clone()
+            });
+            let is_last = config.is_last(meta);
+            let multiplier =
+                RlpTxTag::ALL
+                    .iter()
+                    .zip(is_tag[0].iter())
+                    .fold(0.expr(), |acc, (tag, is_tag)| {
+                        acc + is_tag.clone()
+                            * if tag.is_rlc() {
+                                randomness.clone()
+                            } else {
+                                256.expr()
+                            }
+                    });
+
+            for value in [
+                &is_first[0],
+                &is_length[0],
+                &is_single,
+                &is_short,
+                &is_long,
+                &is_end,
+            ]
+            .iter()
+            .copied()
+            .chain(is_tag[0].iter())
+            .chain(is_kind[0].iter())
+            {
+                cb.require_boolean("flag is boolean", value.clone());
+            }
+            cb.require_equal("tag is one-hot", sum::expr(&is_tag[0]), 1.expr());
+            cb.require_equal("kind is one-hot", sum::expr(&is_kind[0]), 1.expr());
+            for kind in RlpTxKind::ALL {
+                for tag in RlpTxTag::ALL
+                    .iter()
+                    .filter(|tag| !kind.tags().contains(tag))
+                {
+                    cb.require_zero(
+                        "tag is a field of the kind of the transaction",
+                        is_kind[0][kind as usize].clone() * is_tag[0][*tag as usize].clone(),
+                    );
+                }
+            }
+            cb.require_in_set(
+                "chain id is signed or not as allowed by the chain",
+                not::expr(is_kind[0][RlpTxKind::Unprotected as usize].clone()),
+                eip155_allowed.to_vec(),
+            );
+            cb.require_zero(
+                "type of a typed transaction",
+                is_tag[0][RlpTxTag::TxType as usize].clone()
+                    * (byte[0].clone()
+                        - sum::expr(RlpTxKind::ALL.iter().map(|kind| {
+                            is_kind[0][*kind as usize].clone() * kind.tx_type().expr()
+                        }))),
+            );
+            cb.require_equal(
+                "first byte of a field has exactly one kind",
+                sum::expr([&is_single, &is_short, &is_long]),
+                is_first[0].clone(),
+            );
+            cb.require_zero(
+                "first byte of a field is not a length byte",
+                is_first[0].clone() * is_length[0].clone(),
+            );
+            cb.require_zero(
+                "is_end is 1 only if remaining is 0",
+                is_end.clone() * remaining[0].clone(),
+            );
+            cb.require_zero(
+                "is_end is 0 only if remaining is not 0",
+                not::expr(is_end.clone()) * (1.expr() - remaining[0].clone() * remaining_inv),
+            );
+
+            // First byte of a field
+            cb.condition(is_single.clone(), |cb| {
+                cb.require_zero("single byte is the end of the field", remaining[0].clone());
+                cb.require_equal(
+                    "single byte is the value",
+                    value_acc[0].clone(),
+                    byte[0].clone(),
+                );
+                cb.require_equal("single byte has length 1", length[0].clone(), 1.expr());
+            });
+            cb.condition(is_short.clone(), |cb| {
+                cb.require_equal(
+                    "short prefix is 0x80 + length",
+                    length[0].clone(),
+                    byte[0].clone() - 0x80.expr() - is_list.clone() * 0x40.expr(),
+                );
+                cb.require_equal(
+                    "short item is followed by its content",
+                    remaining[0].clone(),
+                    has_content.clone() * length[0].clone(),
+                );
+            });
+            cb.condition(is_long.clone(), |cb| {
+                cb.require_equal(
+                    "long prefix is 0xb7 + number of length bytes",
+                    remaining[0].clone(),
+                    byte[0].clone() - 0xb7.expr() - is_list.clone() * 0x40.expr()
+                        + has_content.clone() * length[0].clone(),
+                );
+            });
+            cb.require_zero(
+                "prefix has no value",
+                (is_short.clone() + is_long.clone()) * value_acc[0].clone(),
+            );
+            cb.require_zero(
+                "first byte has no length bytes before",
+                is_first[0].clone() * length_acc[0].clone(),
+            );
+            cb.require_zero(
+                "to is empty or an address",
+                is_first[0].clone()
+                    * is_tag[0][RlpTxTag::To as usize].clone()
+                    * length[0].clone()
+                    * (length[0].clone() - 20.expr()),
+            );
+            cb.require_zero(
+                "signature fields are 0",
+                is_first[0].clone()
+                    * (is_tag[0][RlpTxTag::SignR as usize].clone()
+                        + is_tag[0][RlpTxTag::SignS as usize].clone())
+                    * (byte[0].clone() - 0x80.expr()),
+            );
+            cb.condition(is_first[0].clone() * is_tx_start[0].clone(), |cb| {
+                cb.require_equal(
+                    "transaction starts with rlc_acc = byte",
+                    rlc_acc[0].clone(),
+                    byte[0].clone(),
+                );
+                cb.require_equal(
+                    "transaction starts with index = 1",
+                    index[0].clone(),
+                    1.expr(),
+                );
+            });
+
+            // End of a field
+            cb.require_zero(
+                "list prefix is followed by its content until the end of the transaction",
+                is_tag[0][RlpTxTag::Prefix as usize].clone()
+                    * is_end.clone()
+                    * (tx_length[0].clone() - index[0].clone() - length[0].clone()),
+            );
+            cb.condition(is_last.clone(), |cb| {
+                cb.require_equal(
+                    "transaction ends after tx_length bytes",
+                    index[0].clone(),
+                    tx_length[0].clone(),
+                );
+            });
+            cb.require_zero(
+                "chain_id is the chain id",
+                is_tag[0][RlpTxTag::ChainId as usize].clone()
+                    * is_end.clone()
+                    * (value_acc[0].clone() - chain_id),
+            );
+            let is_length_end = is_length[0].clone()
+                * (is_end.clone() + not::expr(is_end.clone()) * not::expr(is_length[1].clone()));
+            cb.require_zero(
+                "length bytes decode the length",
+                is_length_end.clone() * (length_acc[0].clone() - length[0].clone()),
+            );
+            cb.require_zero(
+                "length bytes are followed by the content",
+                is_length_end * has_content * (remaining[0].clone() - length[0].clone()),
+            );
+
+            // Transition to the next row
+            cb.condition(q_next.clone() * not::expr(is_end.clone()), |cb| {
+                cb.require_zero("next byte is in the same field", is_first[1].clone());
+                cb.require_equal("tx_id is the same", tx_id[1].clone(), tx_id[0].clone());
+                for (next, cur) in is_tag[1].iter().zip(is_tag[0].iter()) {
+                    cb.require_equal("tag is the same", next.clone(), cur.clone());
+                }
+                cb.require_equal("length is the same", length[1].clone(), length[0].clone());
+                cb.require_equal(
+                    "remaining decreases by 1",
+                    remaining[1].clone(),
+                    remaining[0].clone() - 1.expr(),
+                );
+                cb.require_zero(
+                    "length bytes follow only a long prefix",
+                    not::expr(is_length[0].clone()) * (is_length[1].clone() - is_long.clone()),
+                );
+                cb.require_equal(
+                    "length_acc accumulates the length bytes",
+                    length_acc[1].clone(),
+                    select::expr(
+                        is_length[1].clone(),
+                        length_acc[0].clone() * 256.expr() + byte[1].clone(),
+                        length_acc[0].clone(),
+                    ),
+                );
+                cb.require_equal(
+                    "value_acc accumulates the content bytes",
+                    value_acc[1].clone(),
+                    select::expr(
+                        is_length[1].clone(),
+                        value_acc[0].clone(),
+                        value_acc[0].clone() * multiplier + byte[1].clone(),
+                    ),
+                );
+            });
+            cb.condition(q_next.clone() * is_end.clone(), |cb| {
+                cb.require_equal("next byte starts a field", is_first[1].clone(), 1.expr());
+            });
+            let is_field_end = q_next.clone() * (is_end - is_last.clone());
+            cb.condition(is_field_end.clone(), |cb| {
+                cb.require_equal("tx_id is the same", tx_id[1].clone(), tx_id[0].clone());
+            });
+            for kind in RlpTxKind::ALL {
+                for tags in kind.tags().windows(2) {
+                    cb.condition(
+                        is_field_end.clone()
+                            * is_kind[0][kind as usize].clone()
+                            * is_tag[0][tags[0] as usize].clone(),
+                        |cb| {
+                            cb.require_equal(
+                                "tag is the next one of the kind of the transaction",
+                                is_tag[1][tags[1] as usize].clone(),
+                                1.expr(),
+                            );
+                        },
+                    );
+                }
+            }
+            cb.condition(q_next.clone() * is_last.clone(), |cb| {
+                cb.require_equal(
+                    "tx_id increases by 1",
+                    tx_id[1].clone(),
+                    tx_id[0].clone() + 1.expr(),
+                );
+                cb.require_equal(
+                    "next transaction starts with its first field",
+                    is_tx_start[1].clone(),
+                    1.expr(),
+                );
+            });
+            cb.condition(q_next.clone() * not::expr(is_last.clone()), |cb| {
+                cb.require_equal(
+                    "rlc_acc accumulates the bytes",
+                    rlc_acc[1].clone(),
+                    rlc_acc[0].clone() * randomness.clone() + byte[1].clone(),
+                );
+                cb.require_equal(
+                    "index increases by 1",
+                    index[1].clone(),
+                    index[0].clone() + 1.expr(),
+                );
+                cb.require_equal(
+                    "tx_length is the same",
+                    tx_length[1].clone(),
+                    tx_length[0].clone(),
+                );
+                for (next, cur) in is_kind[1].iter().zip(is_kind[0].iter()) {
+                    cb.require_equal("kind is the same", next.clone(), cur.clone());
+                }
+            });
+            cb.require_zero(
+                "last row is the end of a transaction",
+                not::expr(q_next) * not::expr(is_last),
+            );
+
+            cb.gate(config.is_tx_row(meta, Rotation::cur()))
+        });
+
+        meta.create_gate("RLP encoding starts with the first transaction", |meta| {
+            let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+            cb.require_equal(
+                "first row is the first byte of a field",
+                meta.query_advice(is_first, Rotation::cur()),
+                1.expr(),
+            );
+            let is_typed = [RlpTxKind::AccessList, RlpTxKind::DynamicFee]
+                .iter()
+                .fold(0.expr(), |acc, kind| {
+                    acc + meta.query_advice(is_kind[*kind as usize], Rotation::cur())
+                });
+            cb.require_equal(
+                "first row is the first field of the transaction",
+                meta.query_advice(is_tag[RlpTxTag::TxType as usize], Rotation::cur())
+                    + not::expr(is_typed)
+                        * meta.query_advice(is_tag[RlpTxTag::Prefix as usize], Rotation::cur()),
+                1.expr(),
+            );
+            cb.require_equal(
+                "first row is of the first transaction",
+                meta.query_advice(tx_id, Rotation::cur()),
+                1.expr(),
+            );
+            cb.gate(
+                meta.query_fixed(q_first, Rotation::cur())
+                    * not::expr(meta.query_advice(is_padding, Rotation::cur())),
+            )
+        });
+
+        meta.lookup_any("RLP byte is a byte", |meta| {
+            let is_tx_row = config.is_tx_row(meta, Rotation::cur());
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let [u8_table] = u8_table.table_exprs(meta);
+
+            vec![(is_tx_row * byte, u8_table)]
+        });
+
+        meta.lookup_any("RLP prefix kind", |meta| {
+            let is_tx_row = config.is_tx_row(meta, Rotation::cur());
+            let is_first = meta.query_advice(is_first, Rotation::cur());
+            let is_list = meta.query_advice(is_tag[RlpTxTag::Prefix as usize], Rotation::cur())
+                + meta.query_advice(is_tag[RlpTxTag::AccessList as usize], Rotation::cur());
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let kind = sum::expr([is_single, is_short, is_long].iter().enumerate().map(
+                |(idx, column)| meta.query_advice(*column, Rotation::cur()) * (idx + 1).expr(),
+            ));
+
+            vec![
+                is_tx_row.clone() * is_first.clone() * is_list,
+                is_tx_row.clone() * is_first * byte,
+                is_tx_row * kind,
+            ]
+            .into_iter()
+            .zip(prefix_table.map(|column| meta.query_fixed(column, Rotation::cur())))
+            .collect()
+        });
+
+        meta.lookup_any("RLP field value in tx table", |meta| {
+            let is_end = meta.query_advice(is_end, Rotation::cur());
+            let (is_field, field_tag) =
+                RlpTxTag::ALL
+                    .iter()
+                    .fold((0.expr(), 0.expr()), |(is_field, field_tag), tag| match tag
+                        .tx_field_tag()
+                    {
+                        Some(tx_field_tag) => {
+                            let is_tag = meta.query_advice(is_tag[*tag as usize], Rotation::cur());
+                            (
+                                is_field + is_tag.clone(),
+                                field_tag + is_tag * (tx_field_tag as u64).expr(),
+                            )
+                        }
+                        None => (is_field, field_tag),
+                    });
+            let condition = config.is_tx_row(meta, Rotation::cur()) * is_end * is_field;
+
+            vec![
+                meta.query_advice(tx_id, Rotation::cur()),
+                field_tag,
+                0.expr(),
+                meta.query_advice(value_acc, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(tx_table.table_exprs(meta))
+            .map(|(value, table)| (condition.clone() * value, table))
+            .collect()
+        });
+
+        meta.lookup_any("RLP to is empty only for contract creation", |meta| {
+            let condition = config.is_tx_row(meta, Rotation::cur())
+                * meta.query_advice(is_first, Rotation::cur())
+                * meta.query_advice(is_tag[RlpTxTag::To as usize], Rotation::cur());
+            let is_create = (20.expr() - meta.query_advice(length, Rotation::cur()))
+                * Expression::Constant(F::from(20).invert().unwrap());
+
+            vec![
+                meta.query_advice(tx_id, Rotation::cur()),
+                (TxContextFieldTag::IsCreate as u64).expr(),
+                0.expr(),
+                is_create,
+            ]
+            .into_iter()
+            .zip(tx_table.table_exprs(meta))
+            .map(|(value, table)| (condition.clone() * value, table))
+            .collect()
+        });
+
+        meta.lookup_any("RLP data length in tx table", |meta| {
+            let condition = config.is_tx_row(meta, Rotation::cur())
+                * meta.query_advice(is_first, Rotation::cur())
+                * meta.query_advice(is_tag[RlpTxTag::Data as usize], Rotation::cur());
+
+            vec![
+                meta.query_advice(tx_id, Rotation::cur()),
+                (TxContextFieldTag::CallDataLength as u64).expr(),
+                0.expr(),
+                meta.query_advice(length, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(tx_table.table_exprs(meta))
+            .map(|(value, table)| (condition.clone() * value, table))
+            .collect()
+        });
+
+        meta.lookup_any("RLP data byte in tx table", |meta| {
+            let is_first = meta.query_advice(is_first, Rotation::cur());
+            let is_length = meta.query_advice(is_length, Rotation::cur());
+            let is_content = meta.query_advice(is_single, Rotation::cur())
+                + not::expr(is_first) * not::expr(is_length);
+            let condition = config.is_tx_row(meta, Rotation::cur())
+                * meta.query_advice(is_tag[RlpTxTag::Data as usize], Rotation::cur())
+                * is_content;
+
+            vec![
+                meta.query_advice(tx_id, Rotation::cur()),
+                (TxContextFieldTag::CallData as u64).expr(),
+                meta.query_advice(length, Rotation::cur())
+                    - 1.expr()
+                    - meta.query_advice(remaining, Rotation::cur()),
+                meta.query_advice(byte, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(tx_table.table_exprs(meta))
+            .map(|(value, table)| (condition.clone() * value, table))
+            .collect()
+        });
+
+        meta.lookup_any("RLP encoding hash in keccak table", |meta| {
+            let condition = config.is_tx_row(meta, Rotation::cur()) * config.is_last(meta);
+
+            vec![
+                meta.query_advice(rlc_acc, Rotation::cur()),
+                meta.query_advice(index, Rotation::cur()),
+                meta.query_advice(hash_rlc, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(keccak_table.table_exprs(meta))
+            .map(|(value, table)| (condition.clone() * value, table))
+            .collect()
+        });
+
+        config
+    }
+
+    // Returns whether the row at `rotation` is a byte of a transaction, which
+    // is enabled and not padding.
+    fn is_tx_row(&self, meta: &mut VirtualCells<F>, rotation: Rotation) -> Expression<F> {
+        meta.query_fixed(self.q_enable, rotation)
+            * not::expr(meta.query_advice(self.is_padding, rotation))
+    }
+
+    // Returns whether the row is the last one of a transaction, which is the
+    // end of the last field of its kind.
+    fn is_last(&self, meta: &mut VirtualCells<F>) -> Expression<F> {
+        let is_last_tag = sum::expr(RlpTxKind::ALL.iter().map(|kind| {
+            let last_tag = kind.tags().last().unwrap();
+            meta.query_advice(self.is_kind[*kind as usize], Rotation::cur())
+                * meta.query_advice(self.is_tag[*last_tag as usize], Rotation::cur())
+        }));
+        is_last_tag * meta.query_advice(self.is_end, Rotation::cur())
+    }
+
+    /// Load the table of the kinds of the first bytes of items.
+    pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "RLP prefix table",
+            |mut region| {
+                let rows = [false, true].iter().flat_map(|is_list| {
+                    (0..=u8::MAX).filter_map(move |byte| {
+                        RlpPrefixKind::of(*is_list, byte)
+                            .map(|kind| [*is_list as u64, byte as u64, kind as u64])
+                    })
+                });
+                for (offset, row) in std::iter::once([0; 3]).chain(rows).enumerate() {
+                    for (column, value) in self.prefix_table.iter().zip(row) {
+                        region.assign_fixed(
+                            || "RLP prefix table",
+                            *column,
+                            offset,
+                            || Ok(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the RLP encodings of the transactions, in the first of the
+    /// `max_bytes` rows of the circuit, and padding in the rest of them.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        txs: &[Transaction],
+        chain_id: Word,
+        eip155_rule: Eip155Rule,
+        randomness: F,
+        max_bytes: usize,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "RLP encoding",
+            |mut region| {
+                let eip155_allowed = eip155_rule.allowed();
+                for offset in 0..max_bytes {
+                    for (column, value) in [
+                        (self.q_enable, F::one()),
+                        (self.q_first, F::from((offset == 0) as u64)),
+                        (self.chain_id, chain_id.to_scalar().unwrap()),
+                        (self.eip155_allowed[0], F::from(eip155_allowed[0] as u64)),
+                        (self.eip155_allowed[1], F::from(eip155_allowed[1] as u64)),
+                    ] {
+                        region.assign_fixed(|| "RLP fixed", column, offset, || Ok(value))?;
+                    }
+                }
+
+                let mut offset = 0;
+                for tx in txs {
+                    let tx_kind = RlpTxKind::of(tx);
+                    let hash_rlc =
+                        keccak_table_assignments(&tx_sign_keccak_input(tx, chain_id), randomness)
+                            [2];
+                    for row in rlp_rows(tx, chain_id, randomness) {
+                        for (column, tag) in self.is_tag.iter().zip(RlpTxTag::ALL.iter()) {
+                            region.assign_advice(
+                                || "RLP is_tag",
+                                *column,
+                                offset,
+                                || Ok(F::from((row.tag == *tag) as u64)),
+                            )?;
+                        }
+                        for (column, kind) in self.is_kind.iter().zip(RlpTxKind::ALL.iter()) {
+                            region.assign_advice(
+                                || "RLP is_kind",
+                                *column,
+                                offset,
+                                || Ok(F::from((tx_kind == *kind) as u64)),
+                            )?;
+                        }
+                        for (column, kind) in
+                            [self.is_single, self.is_short, self.is_long].iter().zip([
+                                RlpPrefixKind::Single,
+                                RlpPrefixKind::Short,
+                                RlpPrefixKind::Long,
+                            ])
+                        {
+                            region.assign_advice(
+                                || "RLP prefix kind",
+                                *column,
+                                offset,
+                                || Ok(F::from((row.kind == Some(kind)) as u64)),
+                            )?;
+                        }
+                        let remaining = F::from(row.remaining as u64);
+                        for (column, value) in [
+                            (self.tx_id, F::from(row.tx_id as u64)),
+                            (self.byte, F::from(row.byte as u64)),
+                            (self.is_first, F::from(row.kind.is_some() as u64)),
+                            (self.is_length, F::from(row.is_length as u64)),
+                            (self.remaining, remaining),
+                            (self.remaining_inv, remaining.invert().unwrap_or(F::zero())),
+                            (self.is_end, F::from((row.remaining == 0) as u64)),
+                            (self.length, F::from(row.length as u64)),
+                            (self.length_acc, F::from(row.length_acc as u64)),
+                            (self.value_acc, row.value_acc),
+                            (self.rlc_acc, row.rlc_acc),
+                            (self.index, F::from(row.index as u64)),
+                            (self.tx_length, F::from(row.tx_length as u64)),
+                            (self.hash_rlc, hash_rlc),
+                            (self.is_padding, F::zero()),
+                        ] {
+                            region.assign_advice(|| "RLP row", column, offset, || Ok(value))?;
+                        }
+
+                        offset += 1;
+                    }
+                }
+
+                for offset in offset..max_bytes {
+                    region.assign_advice(
+                        || "RLP is_padding",
+                        self.is_padding,
+                        offset,
+                        || Ok(F::one()),
+                    )?;
+                    for column in self.is_tag.iter().chain(self.is_kind.iter()).chain(
+                        [
+                            self.tx_id,
+                            self.byte,
+                            self.is_first,
+                            self.is_single,
+                            self.is_short,
+                            self.is_long,
+                            self.is_length,
+                            self.remaining,
+                            self.remaining_inv,
+                            self.is_end,
+                            self.length,
+                            self.length_acc,
+                            self.value_acc,
+                            self.rlc_acc,
+                            self.index,
+                            self.tx_length,
+                            self.hash_rlc,
+                        ]
+                        .iter(),
+                    ) {
+                        region.assign_advice(
+                            || "RLP padding",
+                            *column,
+                            offset,
+                            || Ok(F::zero()),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+impl<F: Field> LookupTable<F, 2> for RlpConfig<F> {
+    fn columns(&self) -> Vec<Column<Any>> {
+        vec![
+            self.tx_id.into(),
+            self.hash_rlc.into(),
+            self.q_enable.into(),
+            self.is_padding.into(),
+        ]
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        ["tx_id", "hash_rlc", "q_enable", "is_padding"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    /// Returns the table of `(tx_id, hash_rlc)` of the encodings of the
+    /// transactions.
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 2] {
+        let condition = self.is_tx_row(meta, Rotation::cur()) * self.is_last(meta);
+        [self.tx_id, self.hash_rlc]
+            .map(|column| condition.clone() * meta.query_advice(column, Rotation::cur()))
+    }
+}
@@ -0,0 +1,151 @@
+//! Binding of the signatures of the transactions to the addresses of their
+//! signers.
+//!
+//! The signatures are verified by the sig circuit, and every transaction has a
+//! row whose `(msg_hash_rlc, v, r_rlc, s_rlc, address)` is looked up in the
+//! sig table as a valid signature.  The address is looked up in the tx table as
+//! the caller address of the transaction, and the message hash as the hash of
+//! the RLP encoding of the transaction.
+
+use crate::{
+    annotation::{Annotate, ColumnAnnotations},
+    evm_circuit::table::{LookupTable, TxContextFieldTag},
+    sig_circuit::SignData,
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// Config that binds the signatures of the transactions to their signers.
+#[derive(Clone, Debug)]
+pub(crate) struct SignVerifyConfig<F> {
+    // Enables the row binding a signature to its transaction
+    q_sig: Selector,
+    tx_id: Column<Fixed>,
+    // The row of the signature in the sig table, without is_valid
+    msg_hash_rlc: Column<Advice>,
+    v: Column<Advice>,
+    r_rlc: Column<Advice>,
+    s_rlc: Column<Advice>,
+    address: Column<Advice>,
+    _marker: PhantomData<F>,
+}
+
+impl<F> Annotate for SignVerifyConfig<F> {
+    fn annotate(&self, owner: &str, annotations: &mut ColumnAnnotations) {
+        annotations.annotate(owner, "tx_id", self.tx_id);
+        annotations.annotate(owner, "msg_hash_rlc", self.msg_hash_rlc);
+        annotations.annotate(owner, "v", self.v);
+        annotations.annotate(owner, "r_rlc", self.r_rlc);
+        annotations.annotate(owner, "s_rlc", self.s_rlc);
+        annotations.annotate(owner, "address", self.address);
+    }
+}
+
+impl<F: Field> SignVerifyConfig<F> {
+    /// Configure the binding of the signatures, which are looked up in
+    /// `sig_table`.  `msg_hash_table` is the table of `(tx_id, msg_hash_rlc)`
+    /// of the transactions.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        tx_table: impl LookupTable<F, 4>,
+        sig_table: impl LookupTable<F, 6>,
+        msg_hash_table: impl LookupTable<F, 2>,
+    ) -> Self {
+        let q_sig = meta.complex_selector();
+        let tx_id = meta.fixed_column();
+        let [msg_hash_rlc, v, r_rlc, s_rlc, address] = [(); 5].map(|_| meta.advice_column());
+
+        meta.lookup_any("Signer address in tx table", |meta| {
+            let q_sig = meta.query_selector(q_sig);
+
+            vec![
+                meta.query_fixed(tx_id, Rotation::cur()),
+                (TxContextFieldTag::CallerAddress as u64).expr(),
+                0.expr(),
+                meta.query_advice(address, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(tx_table.table_exprs(meta))
+            .map(|(value, table)| (q_sig.clone() * value, table))
+            .collect()
+        });
+
+        meta.lookup_any("Message hash of transaction", |meta| {
+            let q_sig = meta.query_selector(q_sig);
+
+            vec![
+                meta.query_fixed(tx_id, Rotation::cur()),
+                meta.query_advice(msg_hash_rlc, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(msg_hash_table.table_exprs(meta))
+            .map(|(value, table)| (q_sig.clone() * value, table))
+            .collect()
+        });
+
+        meta.lookup_any("Valid signature in sig table", |meta| {
+            let q_sig = meta.query_selector(q_sig);
+
+            [msg_hash_rlc, v, r_rlc, s_rlc, address]
+                .iter()
+                .map(|column| meta.query_advice(*column, Rotation::cur()))
+                .chain(std::iter::once(1.expr()))
+                .zip(sig_table.table_exprs(meta))
+                .map(|(value, table)| (q_sig.clone() * value, table))
+                .collect()
+        });
+
+        Self {
+            q_sig,
+            tx_id,
+            msg_hash_rlc,
+            v,
+            r_rlc,
+            s_rlc,
+            address,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Binds the signatures of the transactions, in the order of their ids.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        sign_datas: &[SignData],
+        randomness: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "Signature of transaction",
+            |mut region| {
+                for (offset, sign_data) in sign_datas.iter().enumerate() {
+                    self.q_sig.enable(&mut region, offset)?;
+                    region.assign_fixed(
+                        || "tx_id",
+                        self.tx_id,
+                        offset,
+                        || Ok(F::from(offset as u64 + 1)),
+                    )?;
+                    let columns = [
+                        self.msg_hash_rlc,
+                        self.v,
+                        self.r_rlc,
+                        self.s_rlc,
+                        self.address,
+                    ];
+                    for (column, value) in
+                        columns.iter().zip(sign_data.table_assignment(randomness))
+                    {
+                        region.assign_advice(|| "signature", *column, offset, || Ok(value))?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
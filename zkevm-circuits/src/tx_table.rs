@@ -0,0 +1,72 @@
+#![allow(missing_docs)]
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Region,
+    plonk::{Advice, Any, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::evm_circuit::table::LookupTable;
+
+/// The tx table exported by the tx circuit, and looked up by the evm circuit
+/// and the pi circuit.  Each row is a `(tx_id, field_tag, index, value)`,
+/// where `index` is only used by the call data bytes.
+#[derive(Clone, Copy, Debug)]
+pub struct TxTable {
+    pub tx_id: Column<Advice>,
+    pub tag: Column<Advice>,
+    pub index: Column<Advice>,
+    pub value: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F, 4> for TxTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        [self.tx_id, self.tag, self.index, self.value]
+            .map(Into::into)
+            .to_vec()
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        ["tx_id", "tag", "index", "value"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> [Expression<F>; 4] {
+        [
+            meta.query_advice(self.tx_id, Rotation::cur()),
+            meta.query_advice(self.tag, Rotation::cur()),
+            meta.query_advice(self.index, Rotation::cur()),
+            meta.query_advice(self.value, Rotation::cur()),
+        ]
+    }
+}
+impl TxTable {
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            tx_id: meta.advice_column(),
+            tag: meta.advice_column(),
+            index: meta.advice_column(),
+            value: meta.advice_column(),
+        }
+    }
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 4],
+    ) -> Result<(), Error> {
+        for (column, value) in [self.tx_id, self.tag, self.index, self.value]
+            .iter()
+            .zip(row)
+        {
+            region.assign_advice(
+                || "assign tx row on tx table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+}
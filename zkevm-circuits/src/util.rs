@@ -1,7 +1,13 @@
 //! Common utility traits and functions.
 use bus_mapping::operation::Target;
 use eth_types::evm_types::{GasCost, OpcodeId};
-use halo2_proofs::{arithmetic::FieldExt, plonk::Expression};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::{ConstraintSystem, Expression},
+    poly::Rotation,
+};
+
+pub mod word;
 
 pub(crate) trait Expr<F: FieldExt> {
     fn expr(&self) -> Expression<F>;
@@ -63,3 +69,52 @@ impl<F: FieldExt> Expr<F> for i32 {
         )
     }
 }
+
+/// Returns the powers of the randomness of the random linear combinations,
+/// `r, r^2, ..., r^N`, queried from `N` instance columns.
+///
+/// The randomness must be unknown to the prover when the witness is
+/// committed, which the instance columns don't ensure on their own: the
+/// verifier has to derive it from the commitments of the witness.  Every
+/// circuit gets its randomness from here, so that it can be replaced in one
+/// place.
+///
+/// TODO: Use a challenge squeezed from the transcript after the first phase
+/// of advice columns instead, and assign the RLCs in the second phase.  The
+/// halo2 version in use has no phases nor challenges, so until it is upgraded
+/// the randomness is a synthesis-time value and the RLCs are unsound for
+/// production proofs.
+pub fn power_of_randomness_from_instance<F: FieldExt, const N: usize>(
+    meta: &mut ConstraintSystem<F>,
+) -> [Expression<F>; N] {
+    let columns = [(); N].map(|_| meta.instance_column());
+    let mut power_of_randomness = None;
+
+    meta.create_gate("power of randomness from instance", |meta| {
+        power_of_randomness =
+            Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+
+        [0.expr()]
+    });
+
+    power_of_randomness.unwrap()
+}
+
+/// Serialization of a field element as its little-endian representation, for
+/// the fields of type `F` annotated with
+/// `#[serde(with = "crate::util::serde_field")]`.
+pub(crate) mod serde_field {
+    use eth_types::Field;
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<F: Field, S: Serializer>(value: &F, serializer: S) -> Result<S::Ok, S::Error> {
+        value.to_repr().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, F: Field, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<F, D::Error> {
+        let repr = <[u8; 32]>::deserialize(deserializer)?;
+        Option::from(F::from_repr(repr)).ok_or_else(|| de::Error::custom("invalid field element"))
+    }
+}
@@ -0,0 +1,97 @@
+//! A 256-bit EVM word as its low and high 128-bit halves, which both fit in a
+//! field element.  The circuits encode the words they share in lookups the
+//! same way, with the same conversions, so that the host values, the
+//! witnesses and the expressions of a word can't disagree on its limbs.
+use crate::util::Expr;
+use eth_types::{Field, WordExt, U256};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Expression};
+
+/// A 256-bit word as its low and high 128-bit halves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Word<T> {
+    lo: T,
+    hi: T,
+}
+
+impl<T> Word<T> {
+    /// Construct the word of its `[lo, hi]` halves.
+    pub fn new([lo, hi]: [T; 2]) -> Self {
+        Self { lo, hi }
+    }
+
+    /// The low 128 bits.
+    pub fn lo(&self) -> &T {
+        &self.lo
+    }
+
+    /// The high 128 bits.
+    pub fn hi(&self) -> &T {
+        &self.hi
+    }
+
+    /// Returns the `(lo, hi)` halves.
+    pub fn to_lo_hi(self) -> (T, T) {
+        (self.lo, self.hi)
+    }
+
+    /// Maps both halves with `f`.
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Word<U> {
+        Word {
+            lo: f(self.lo),
+            hi: f(self.hi),
+        }
+    }
+}
+
+impl From<U256> for Word<U256> {
+    fn from(value: U256) -> Self {
+        Self::new(value.to_le_limbs())
+    }
+}
+
+impl Word<U256> {
+    /// Returns the halves as field elements.
+    pub fn into_scalars<F: Field>(self) -> Word<F> {
+        self.map(|half| F::from_u128(half.as_u128()))
+    }
+}
+
+impl<F: Field> Word<F> {
+    /// Returns the halves of `value` as field elements.
+    pub fn from_u256(value: U256) -> Self {
+        Word::<U256>::from(value).into_scalars()
+    }
+}
+
+impl<F: FieldExt> Word<Expression<F>> {
+    /// Returns the word composed of its 32 little-endian `bytes`.
+    pub fn from_bytes<E: Expr<F>>(bytes: &[E]) -> Self {
+        assert_eq!(bytes.len(), 32, "a word has 32 bytes");
+        let compose = |bytes: &[E]| {
+            bytes
+                .iter()
+                .rev()
+                .fold(0.expr(), |acc, byte| acc * 256.expr() + byte.expr())
+        };
+        Self::new([compose(&bytes[..16]), compose(&bytes[16..])])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Word;
+    use eth_types::U256;
+    use pairing::bn256::Fr as Fp;
+
+    #[test]
+    fn word_halves() {
+        let value = (U256::from(3) << 128) + U256::from(5);
+
+        let word = Word::from(value);
+        assert_eq!(word.to_lo_hi(), (U256::from(5), U256::from(3)));
+        assert_eq!(
+            Word::<Fp>::from_u256(value),
+            Word::new([5u64, 3].map(Fp::from))
+        );
+    }
+}